@@ -10,7 +10,10 @@ use log::error;
 use pinboard::NonEmptyPinboard;
 use rust_cast::CastDevice;
 
-use rustic_core::player::{queue::MemoryQueueBuilder, PlayerBuilder, PlayerBus, PlayerState};
+use rustic_core::player::{
+    queue::MemoryQueueBuilder, PlayerBuilder, PlayerBus, PlayerCapabilities, PlayerCapability,
+    PlayerError, PlayerState,
+};
 use rustic_core::Track;
 
 use crate::cast_state::CastState;
@@ -93,6 +96,13 @@ impl GoogleCastBackend {
 }
 
 impl rustic_core::PlayerBackend for GoogleCastBackend {
+    fn capabilities(&self) -> PlayerCapabilities {
+        PlayerCapabilities {
+            volume: true,
+            ..PlayerCapabilities::default()
+        }
+    }
+
     fn set_track(&self, track: &Track, stream_url: String) -> Result<(), Error> {
         self.internal_sender
             .send(InternalCommand::Play(track.clone(), stream_url))?;
@@ -120,16 +130,16 @@ impl rustic_core::PlayerBackend for GoogleCastBackend {
         state.volume
     }
 
-    fn set_blend_time(&self, duration: Duration) -> Result<(), Error> {
-        unimplemented!()
+    fn set_blend_time(&self, _duration: Duration) -> Result<(), Error> {
+        Err(PlayerError::Unsupported(PlayerCapability::Crossfade).into())
     }
 
     fn blend_time(&self) -> Duration {
         Duration::new(0, 0)
     }
 
-    fn seek(&self, duration: Duration) -> Result<(), Error> {
-        unimplemented!()
+    fn seek(&self, _duration: Duration) -> Result<(), Error> {
+        Err(PlayerError::Unsupported(PlayerCapability::Seek).into())
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+use std::thread;
+
+use failure::{bail, Error};
+use gstreamer::prelude::*;
+use pinboard::NonEmptyPinboard;
+
+use rustic_core::player::{OutputDevice, PlayerBus, PlayerEvent, PlayerState};
+
+/// Selects the audio sink of the player and follows added and removed devices
+///
+/// When the selected device disappears the player falls back to the default sink
+/// and moves back once the device is available again.
+#[derive(Clone)]
+pub(crate) struct GstDevices {
+    player: gstreamer_player::Player,
+    monitor: gstreamer::DeviceMonitor,
+    bus: PlayerBus,
+    state: Arc<NonEmptyPinboard<PlayerState>>,
+    /// The device selected by the user, `None` plays on the default sink
+    selected: Arc<NonEmptyPinboard<Option<String>>>,
+    /// The device the pipeline is currently playing on
+    active: Arc<NonEmptyPinboard<Option<String>>>,
+}
+
+impl GstDevices {
+    pub fn new(
+        player: gstreamer_player::Player,
+        bus: PlayerBus,
+        state: Arc<NonEmptyPinboard<PlayerState>>,
+        device: Option<String>,
+    ) -> Result<Self, Error> {
+        let monitor = gstreamer::DeviceMonitor::new();
+        monitor.add_filter(Some("Audio/Sink"), None);
+        monitor.start()?;
+        let devices = GstDevices {
+            player,
+            monitor,
+            bus,
+            state,
+            selected: Arc::new(NonEmptyPinboard::new(device)),
+            active: Arc::new(NonEmptyPinboard::new(None)),
+        };
+        devices.follow()?;
+
+        Ok(devices)
+    }
+
+    pub fn observe(&self) {
+        let devices = self.clone();
+        let bus = self.monitor.bus();
+        thread::spawn(move || {
+            for msg in bus.iter_timed(gstreamer::ClockTime::NONE) {
+                match msg.view() {
+                    gstreamer::MessageView::DeviceAdded(_)
+                    | gstreamer::MessageView::DeviceRemoved(_) => {}
+                    _ => continue,
+                }
+                if let Err(e) = devices.follow() {
+                    log::error!("Switching output device failed: {:?}", e);
+                }
+                devices.emit();
+            }
+        });
+    }
+
+    pub fn devices(&self) -> Vec<OutputDevice> {
+        let active = self.active.read();
+        self.monitor
+            .devices()
+            .into_iter()
+            .map(|device| {
+                let name = device.display_name().to_string();
+                let selected = match active {
+                    Some(ref active) => active == &name,
+                    None => is_default(&device),
+                };
+                OutputDevice {
+                    id: name.clone(),
+                    name,
+                    selected,
+                }
+            })
+            .collect()
+    }
+
+    pub fn set_device(&self, device_id: Option<&str>) -> Result<(), Error> {
+        if let Some(device_id) = device_id {
+            if self.find(device_id).is_none() {
+                bail!("Unknown output device {}", device_id);
+            }
+        }
+        self.selected.set(device_id.map(String::from));
+        self.follow()?;
+        self.emit();
+
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> Option<gstreamer::Device> {
+        self.monitor
+            .devices()
+            .into_iter()
+            .find(|device| device.display_name() == name)
+    }
+
+    /// Plays on the selected device if it's available or the default sink otherwise
+    fn follow(&self) -> Result<(), Error> {
+        let target = self
+            .selected
+            .read()
+            .and_then(|name| self.find(&name).map(|device| (name, device)));
+        let active = self.active.read();
+        if target.as_ref().map(|(name, _)| name) == active.as_ref() {
+            return Ok(());
+        }
+        let sink = match target {
+            Some((ref name, ref device)) => {
+                log::info!("Switching output device to {}", name);
+                Some(device.create_element(None)?)
+            }
+            None => {
+                log::info!("Switching to default output device");
+                None
+            }
+        };
+        self.set_sink(sink);
+        self.active.set(target.map(|(name, _)| name));
+
+        Ok(())
+    }
+
+    /// playbin only picks up a new audio sink when it's restarted
+    fn set_sink(&self, sink: Option<gstreamer::Element>) {
+        let position = self.player.position();
+        self.player.stop();
+        self.player.pipeline().set_property("audio-sink", sink);
+        match self.state.read() {
+            PlayerState::Play => self.player.play(),
+            PlayerState::Pause => self.player.pause(),
+            PlayerState::Stop => return,
+        }
+        if let Some(position) = position {
+            self.player.seek(position);
+        }
+    }
+
+    fn emit(&self) {
+        if let Err(e) = self
+            .bus
+            .emit_event(PlayerEvent::DevicesChanged(self.devices()))
+        {
+            log::error!("Emitting devices failed: {:?}", e);
+        }
+    }
+}
+
+fn is_default(device: &gstreamer::Device) -> bool {
+    device
+        .properties()
+        .and_then(|properties| properties.get::<bool>("is-default").ok())
+        .unwrap_or(false)
+}
@@ -6,16 +6,22 @@ use failure::Error;
 use pinboard::NonEmptyPinboard;
 
 use rustic_core::player::{
-    PlayerBackend, PlayerBuilder, PlayerBus, PlayerEvent, PlayerState, QueueCommand,
+    NextReason, OutputDevice, PlayerBackend, PlayerBuilder, PlayerBus, PlayerCapabilities,
+    PlayerCapability, PlayerError, PlayerEvent, PlayerState, QueueCommand,
 };
 use rustic_core::{Rustic, Track};
 
+use crate::devices::GstDevices;
+
+mod devices;
+
 pub struct GstBackend {
     core: Arc<Rustic>,
     current_volume: f32,
-    state: NonEmptyPinboard<PlayerState>,
+    state: Arc<NonEmptyPinboard<PlayerState>>,
     blend_time: Duration,
     player: gstreamer_player::Player,
+    devices: GstDevices,
     bus: PlayerBus,
 }
 
@@ -30,17 +36,25 @@ impl std::fmt::Debug for GstBackend {
 }
 
 impl GstBackend {
-    pub fn new(core: Arc<Rustic>, bus: PlayerBus) -> Result<Box<dyn PlayerBackend>, Error> {
+    pub fn new(
+        core: Arc<Rustic>,
+        bus: PlayerBus,
+        device: Option<String>,
+    ) -> Result<Box<dyn PlayerBackend>, Error> {
         // TODO: only run once
         std::thread::spawn(|| glib::MainLoop::new(None, false).run());
         gstreamer::init()?;
         let player = gstreamer_player::Player::new(None, None);
+        let state = Arc::new(NonEmptyPinboard::new(PlayerState::Stop));
+        let devices = GstDevices::new(player.clone(), bus.clone(), Arc::clone(&state), device)?;
+        devices.observe();
         let backend = GstBackend {
             core,
             blend_time: Duration::default(),
             current_volume: 1.0,
-            state: NonEmptyPinboard::new(PlayerState::Stop),
+            state,
             player,
+            devices,
             bus: bus.clone(),
         };
 
@@ -48,7 +62,7 @@ impl GstBackend {
         let error_bus = bus;
         backend.player.connect_end_of_stream(move |_| {
             log::debug!("reached end of stream");
-            if let Err(e) = eos_bus.send_queue_msg(QueueCommand::Next) {
+            if let Err(e) = eos_bus.send_queue_msg(QueueCommand::Next(NextReason::Finished)) {
                 log::error!("Failed loading next track: {:?}", e)
             }
         });
@@ -58,7 +72,7 @@ impl GstBackend {
         backend.player.connect_error(move |_, err| {
             log::debug!("skipping track because of playback error");
             log::error!("{:?}", err);
-            if let Err(e) = error_bus.send_queue_msg(QueueCommand::Next) {
+            if let Err(e) = error_bus.send_queue_msg(QueueCommand::Next(NextReason::Error)) {
                 log::error!("Failed loading next track: {:?}", e)
             }
         });
@@ -78,6 +92,15 @@ impl GstBackend {
 }
 
 impl PlayerBackend for GstBackend {
+    fn capabilities(&self) -> PlayerCapabilities {
+        PlayerCapabilities {
+            seek: true,
+            volume: true,
+            output_devices: true,
+            ..PlayerCapabilities::default()
+        }
+    }
+
     fn set_track(&self, track: &Track, stream_url: String) -> Result<(), Error> {
         log::debug!("Selecting {:?}", track);
 
@@ -131,15 +154,32 @@ impl PlayerBackend for GstBackend {
     }
 
     fn set_blend_time(&self, _duration: Duration) -> Result<(), Error> {
-        unimplemented!()
+        Err(PlayerError::Unsupported(PlayerCapability::Crossfade).into())
     }
 
     fn blend_time(&self) -> Duration {
         self.blend_time
     }
 
-    fn seek(&self, _duration: Duration) -> Result<(), Error> {
-        unimplemented!()
+    fn seek(&self, duration: Duration) -> Result<(), Error> {
+        self.player
+            .seek(gstreamer::ClockTime::from_nseconds(duration.as_nanos() as u64));
+        self.bus.emit_event(PlayerEvent::Seek(duration))?;
+        Ok(())
+    }
+
+    fn position(&self) -> Option<Duration> {
+        self.player
+            .position()
+            .map(|position| Duration::from_nanos(position.nseconds()))
+    }
+
+    fn devices(&self) -> Vec<OutputDevice> {
+        self.devices.devices()
+    }
+
+    fn set_device(&self, device_id: Option<&str>) -> Result<(), Error> {
+        self.devices.set_device(device_id)
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -148,11 +188,12 @@ impl PlayerBackend for GstBackend {
 }
 
 pub trait GstreamerPlayerBuilder {
-    fn with_gstreamer(&mut self) -> Result<&mut Self, Error>;
+    /// Plays on the audio device with the given name or on the default sink
+    fn with_gstreamer(&mut self, device: Option<String>) -> Result<&mut Self, Error>;
 }
 
 impl GstreamerPlayerBuilder for PlayerBuilder {
-    fn with_gstreamer(&mut self) -> Result<&mut Self, Error> {
-        self.with_player(GstBackend::new)
+    fn with_gstreamer(&mut self, device: Option<String>) -> Result<&mut Self, Error> {
+        self.with_player(|core, bus| GstBackend::new(core, bus, device))
     }
 }
@@ -0,0 +1,83 @@
+use std::thread;
+
+use crossbeam_channel::Sender;
+use failure::{format_err, Error};
+use log::{debug, error};
+use rodio::cpal::traits::HostTrait;
+use rodio::DeviceTrait;
+
+/// An output stream on an audio device
+///
+/// cpal streams can't be moved between threads so the stream lives on its own thread
+/// until this is dropped.
+pub(crate) struct DeviceOutput {
+    pub name: String,
+    pub handle: rodio::OutputStreamHandle,
+    _close: Sender<()>,
+}
+
+impl DeviceOutput {
+    /// Opens the device with the given name or the default device
+    pub fn open(device: Option<&str>) -> Result<DeviceOutput, Error> {
+        let device = find_device(device)?;
+        let name = device.name()?;
+        let (handle_tx, handle_rx) = crossbeam_channel::bounded(1);
+        let (close_tx, close_rx) = crossbeam_channel::bounded::<()>(0);
+        let thread_name = name.clone();
+        thread::spawn(move || match rodio::OutputStream::try_from_device(&device) {
+            Ok((stream, handle)) => {
+                let _ = handle_tx.send(Ok(handle));
+                // Blocks until the DeviceOutput is dropped
+                let _ = close_rx.recv();
+                debug!("Closing output stream on {}", thread_name);
+                drop(stream);
+            }
+            Err(e) => {
+                let _ = handle_tx.send(Err(Error::from(e)));
+            }
+        });
+        let handle = handle_rx.recv()??;
+
+        Ok(DeviceOutput {
+            name,
+            handle,
+            _close: close_tx,
+        })
+    }
+}
+
+/// Names of all available output devices
+pub(crate) fn list_devices() -> Result<Vec<String>, Error> {
+    let host = rodio::cpal::default_host();
+    let devices = host
+        .output_devices()?
+        .filter_map(|device| match device.name() {
+            Ok(name) => Some(name),
+            Err(e) => {
+                error!("Reading device name failed: {:?}", e);
+                None
+            }
+        })
+        .collect();
+
+    Ok(devices)
+}
+
+pub(crate) fn default_device() -> Option<String> {
+    let host = rodio::cpal::default_host();
+    host.default_output_device()
+        .and_then(|device| device.name().ok())
+}
+
+fn find_device(name: Option<&str>) -> Result<rodio::Device, Error> {
+    let host = rodio::cpal::default_host();
+    match name {
+        Some(name) => host
+            .output_devices()?
+            .find(|device| device.name().map(|n| n == name).unwrap_or(false))
+            .ok_or_else(|| format_err!("Unknown output device {}", name)),
+        None => host
+            .default_output_device()
+            .ok_or_else(|| format_err!("No default output device available")),
+    }
+}
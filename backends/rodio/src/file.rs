@@ -5,13 +5,21 @@ use std::path::Path;
 
 use crossbeam_channel::Sender;
 
-struct Emitter {
+/// Requests the next track once the end of a source has been reached
+pub(crate) struct Emitter {
     sender: Sender<()>,
     emitted: bool,
 }
 
 impl Emitter {
-    fn emit(&mut self) {
+    pub fn new(sender: Sender<()>) -> Emitter {
+        Emitter {
+            sender,
+            emitted: false,
+        }
+    }
+
+    pub fn emit(&mut self) {
         if self.emitted {
             return;
         }
@@ -25,13 +33,7 @@ pub(crate) struct RodioFile(File, Emitter);
 impl RodioFile {
     pub fn open<P: AsRef<Path>>(path: P, sender: Sender<()>) -> io::Result<RodioFile> {
         let file = File::open(path)?;
-        Ok(RodioFile(
-            file,
-            Emitter {
-                sender,
-                emitted: false,
-            },
-        ))
+        Ok(RodioFile(file, Emitter::new(sender)))
     }
 }
 
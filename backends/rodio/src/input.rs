@@ -0,0 +1,29 @@
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::file::RodioFile;
+use crate::stream::HttpStream;
+
+/// Everything the decoder can read from
+pub(crate) enum RodioInput {
+    File(RodioFile),
+    Http(HttpStream),
+}
+
+impl Read for RodioInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            RodioInput::File(file) => file.read(buf),
+            RodioInput::Http(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Seek for RodioInput {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            RodioInput::File(file) => file.seek(pos),
+            RodioInput::Http(stream) => stream.seek(pos),
+        }
+    }
+}
@@ -1,28 +1,53 @@
 use std::any::Any;
 use std::io::BufReader;
 use std::ops::DerefMut;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use std::thread;
 use std::time::Duration;
 
 use crossbeam_channel::Sender;
 use failure::{bail, Error};
-use log::{debug, trace};
+use log::{debug, error, info, trace, warn};
 use pinboard::NonEmptyPinboard;
 use url::Url;
 
-use rustic_core::player::{PlayerBackend, PlayerBuilder, PlayerBus, QueueCommand};
+use rustic_core::player::{
+    NextReason, OutputDevice, PlayerBackend, PlayerBuilder, PlayerBus, PlayerCapabilities,
+    PlayerCapability, PlayerError, QueueCommand,
+};
 use rustic_core::{PlayerEvent, PlayerState, Rustic, Track};
 
+use crate::device::DeviceOutput;
 use crate::file::RodioFile;
+use crate::input::RodioInput;
+use crate::position::PositionSource;
+use crate::stream::HttpStream;
 
+mod device;
 mod file;
+mod input;
+mod position;
+mod stream;
+
+/// cpal has no notifications for added or removed devices so we have to poll them
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 pub struct RodioBackend {
+    inner: Arc<RodioInner>,
+}
+
+struct RodioInner {
     core: Arc<Rustic>,
     state: NonEmptyPinboard<PlayerState>,
     blend_time: Duration,
-    current_sink: Arc<Mutex<Option<rodio::Sink>>>,
+    current_sink: Mutex<Option<rodio::Sink>>,
+    current_track: Mutex<Option<(Track, String)>>,
+    /// Position in the current track in nanoseconds
+    position: Arc<AtomicU64>,
+    /// The device selected by the user, `None` plays on the default device
+    device: NonEmptyPinboard<Option<String>>,
+    output: Mutex<Option<DeviceOutput>>,
     bus: PlayerBus,
     next_sender: Sender<()>,
 }
@@ -30,55 +55,87 @@ pub struct RodioBackend {
 impl std::fmt::Debug for RodioBackend {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         f.debug_struct("RodioBackend")
-            .field("state", &self.state)
-            .field("blend_time", &self.blend_time)
+            .field("state", &self.inner.state)
+            .field("blend_time", &self.inner.blend_time)
+            .field("device", &self.inner.device)
             .finish()
     }
 }
 
 impl RodioBackend {
-    pub fn new(core: Arc<Rustic>, bus: PlayerBus) -> Result<Box<dyn PlayerBackend>, Error> {
+    pub fn new(
+        core: Arc<Rustic>,
+        bus: PlayerBus,
+        device: Option<String>,
+    ) -> Result<Box<dyn PlayerBackend>, Error> {
         let (next_sender, next_receiver) = crossbeam_channel::unbounded();
-        let backend = RodioBackend {
+        let inner = RodioInner {
             core,
             state: NonEmptyPinboard::new(PlayerState::Stop),
             blend_time: Duration::default(),
-            current_sink: Arc::new(Mutex::new(None)),
+            current_sink: Mutex::new(None),
+            current_track: Mutex::new(None),
+            position: Arc::new(AtomicU64::new(0)),
+            device: NonEmptyPinboard::new(device),
+            output: Mutex::new(None),
             bus: bus.clone(),
             next_sender,
         };
+        let backend = RodioBackend {
+            inner: Arc::new(inner),
+        };
 
         thread::spawn(move || {
             for _ in next_receiver {
-                bus.send_queue_msg(QueueCommand::Next);
+                bus.send_queue_msg(QueueCommand::Next(NextReason::Finished));
             }
         });
+        RodioInner::watch_devices(Arc::downgrade(&backend.inner));
 
         Ok(Box::new(backend))
     }
+}
 
+impl RodioInner {
     fn decode_stream(
         &self,
         track: &Track,
         stream_url: String,
-    ) -> Result<rodio::Decoder<BufReader<RodioFile>>, Error> {
+    ) -> Result<rodio::Decoder<BufReader<RodioInput>>, Error> {
         trace!("Decoding stream {} for track {}", &stream_url, track);
         let url = Url::parse(&stream_url)?;
         match url.scheme() {
             "file" => self.decode_file(stream_url),
-            "http" | "https" => {
-                let path = self.core.cache.fetch_track(track, &stream_url)?;
-                self.decode_file(path)
-            }
+            "http" | "https" => match self.core.cache.cached_track(track) {
+                Some(path) => self.decode_file(path),
+                None => self.decode_http(track, stream_url),
+            },
             scheme => bail!("Invalid scheme: {}", scheme),
         }
     }
 
-    fn decode_file(&self, mut path: String) -> Result<rodio::Decoder<BufReader<RodioFile>>, Error> {
+    fn decode_file(&self, mut path: String) -> Result<rodio::Decoder<BufReader<RodioInput>>, Error> {
         path.replace_range(..7, "");
         trace!("Decoding file {}", &path);
         let file = RodioFile::open(path, self.next_sender.clone())?;
-        let decoder = rodio::Decoder::new(BufReader::new(file))?;
+        let decoder = rodio::Decoder::new(BufReader::new(RodioInput::File(file)))?;
+        Ok(decoder)
+    }
+
+    /// Starts decoding while the track is still downloading
+    fn decode_http(
+        &self,
+        track: &Track,
+        url: String,
+    ) -> Result<rodio::Decoder<BufReader<RodioInput>>, Error> {
+        trace!("Streaming {}", &url);
+        let stream = HttpStream::open(
+            track.clone(),
+            url,
+            Arc::clone(&self.core.cache),
+            self.next_sender.clone(),
+        );
+        let decoder = rodio::Decoder::new(BufReader::new(RodioInput::Http(stream)))?;
         Ok(decoder)
     }
 
@@ -103,6 +160,9 @@ impl RodioBackend {
     fn pause(&self) -> Result<(), Error> {
         if let Some(sink) = self.current_sink.lock().unwrap().deref_mut() {
             sink.pause();
+        }
+        // the sink is created once the track finished loading
+        if self.current_track.lock().unwrap().is_some() {
             self.write_state(PlayerState::Pause)?;
         }
         Ok(())
@@ -115,21 +175,52 @@ impl RodioBackend {
         }
         Ok(())
     }
-}
 
-impl PlayerBackend for RodioBackend {
-    fn set_track(&self, track: &Track, stream_url: String) -> Result<(), Error> {
-        debug!("Selecting {:?}", track);
+    fn volume(&self) -> f32 {
+        if let Some(sink) = self.current_sink.lock().unwrap().deref_mut() {
+            sink.volume()
+        } else {
+            1f32
+        }
+    }
+
+    /// Loads the track on a separate thread
+    ///
+    /// Opening a stream waits for the download so it would block the player otherwise.
+    fn spawn_load(self: &Arc<Self>, track: Track, stream_url: String, start: Duration) {
+        let inner = Arc::clone(self);
+        thread::spawn(move || {
+            if let Err(e) = inner.load_track(&track, stream_url, start) {
+                error!("Loading {} failed: {:?}", &track, e);
+            }
+        });
+    }
+
+    fn is_current_track(&self, track: &Track, stream_url: &str) -> bool {
+        match self.current_track.lock().unwrap().as_ref() {
+            Some((current, current_url)) => current == track && current_url == stream_url,
+            None => false,
+        }
+    }
+
+    fn load_track(&self, track: &Track, stream_url: String, start: Duration) -> Result<(), Error> {
         let volume = self.volume();
         {
-            self.bus
-                .emit_event(PlayerEvent::TrackChanged(track.clone()))?;
-            let source = self.decode_stream(track, stream_url)?;
-            let (_, handle) = rodio::OutputStream::try_default()?;
-            let sink = rodio::Sink::try_new(&handle)?;
+            let mut source = self.decode_stream(track, stream_url.clone())?;
+            position::skip(&mut source, start);
+            // another track was selected while this one was loading
+            if !self.is_current_track(track, &stream_url) {
+                return Ok(());
+            }
+            let mut output = self.output.lock().unwrap();
+            if output.is_none() {
+                *output = Some(self.open_output()?);
+            }
+            let sink = rodio::Sink::try_new(&output.as_ref().unwrap().handle)?;
             sink.set_volume(volume);
+            let source = PositionSource::new(source, Arc::clone(&self.position), start);
             sink.append(source);
-            if self.state() != PlayerState::Play {
+            if self.state.read() != PlayerState::Play {
                 sink.pause();
             }
             let mut current_sink = self.current_sink.lock().unwrap();
@@ -145,45 +236,218 @@ impl PlayerBackend for RodioBackend {
         Ok(())
     }
 
+    /// Opens the selected device and falls back to the default device when it's not available
+    fn open_output(&self) -> Result<DeviceOutput, Error> {
+        let device = self.device.read();
+        match DeviceOutput::open(device.as_deref()) {
+            Ok(output) => Ok(output),
+            Err(e) if device.is_some() => {
+                warn!(
+                    "Opening output device failed, falling back to default device: {:?}",
+                    e
+                );
+                DeviceOutput::open(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Moves playback to the selected device
+    ///
+    /// rodio can't seek so the current track is loaded again and resumes at the position
+    /// it had on the previous device.
+    fn switch_device(self: &Arc<Self>) -> Result<(), Error> {
+        let position;
+        {
+            let mut output = self.output.lock().unwrap();
+            let current = match output.as_ref() {
+                Some(output) => output.name.clone(),
+                // Nothing is playing yet, the device will be opened with the next track
+                None => return Ok(()),
+            };
+            let target = self.device.read().or_else(device::default_device);
+            if target.as_ref() == Some(&current) {
+                return Ok(());
+            }
+            let next = self.open_output()?;
+            info!("Switching output device to {}", &next.name);
+            if let Some(sink) = self.current_sink.lock().unwrap().take() {
+                sink.stop();
+            }
+            position = Duration::from_nanos(self.position.load(Ordering::Relaxed));
+            *output = Some(next);
+        }
+        let current_track = self.current_track.lock().unwrap().clone();
+        if let Some((track, stream_url)) = current_track {
+            self.spawn_load(track, stream_url, position);
+        }
+        Ok(())
+    }
+
+    fn devices(&self) -> Vec<OutputDevice> {
+        let devices = match device::list_devices() {
+            Ok(devices) => devices,
+            Err(e) => {
+                error!("Listing output devices failed: {:?}", e);
+                return Vec::new();
+            }
+        };
+        let selected = self
+            .output
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|output| output.name.clone())
+            .or_else(|| self.device.read())
+            .or_else(device::default_device);
+        devices
+            .into_iter()
+            .map(|name| OutputDevice {
+                selected: selected.as_ref() == Some(&name),
+                id: name.clone(),
+                name,
+            })
+            .collect()
+    }
+
+    fn emit_devices(&self) -> Result<(), Error> {
+        self.bus
+            .emit_event(PlayerEvent::DevicesChanged(self.devices()))
+    }
+
+    /// Follows added and removed devices until the backend is dropped
+    ///
+    /// Playback falls back to the default device when the selected device disappears
+    /// and moves back once it is available again.
+    fn watch_devices(inner: Weak<RodioInner>) {
+        thread::spawn(move || {
+            let mut known = device::list_devices().unwrap_or_default();
+            loop {
+                thread::sleep(DEVICE_POLL_INTERVAL);
+                let inner = match inner.upgrade() {
+                    Some(inner) => inner,
+                    None => break,
+                };
+                let devices = match device::list_devices() {
+                    Ok(devices) => devices,
+                    Err(e) => {
+                        error!("Listing output devices failed: {:?}", e);
+                        continue;
+                    }
+                };
+                if devices == known {
+                    continue;
+                }
+                debug!("Output devices changed: {:?}", &devices);
+                known = devices;
+                if let Err(e) = inner.follow_devices(&known) {
+                    error!("Switching output device failed: {:?}", e);
+                }
+                if let Err(e) = inner.emit_devices() {
+                    error!("{:?}", e);
+                }
+            }
+        });
+    }
+
+    fn follow_devices(self: &Arc<Self>, available: &[String]) -> Result<(), Error> {
+        let current = match self.output.lock().unwrap().as_ref() {
+            Some(output) => output.name.clone(),
+            None => return Ok(()),
+        };
+        let target = match self.device.read() {
+            Some(device) if available.contains(&device) => Some(device),
+            _ => device::default_device(),
+        };
+        if available.contains(&current) && target.as_ref() == Some(&current) {
+            return Ok(());
+        }
+        self.switch_device()
+    }
+}
+
+impl PlayerBackend for RodioBackend {
+    fn capabilities(&self) -> PlayerCapabilities {
+        PlayerCapabilities {
+            volume: true,
+            output_devices: true,
+            ..PlayerCapabilities::default()
+        }
+    }
+
+    fn set_track(&self, track: &Track, stream_url: String) -> Result<(), Error> {
+        debug!("Selecting {:?}", track);
+        self.inner
+            .bus
+            .emit_event(PlayerEvent::TrackChanged(track.clone()))?;
+        *self.inner.current_track.lock().unwrap() = Some((track.clone(), stream_url.clone()));
+        self.inner
+            .spawn_load(track.clone(), stream_url, Duration::default());
+
+        Ok(())
+    }
+
     fn set_state(&self, state: PlayerState) -> Result<(), Error> {
         match state {
-            PlayerState::Play => self.play()?,
-            PlayerState::Pause => self.pause()?,
-            PlayerState::Stop => self.stop()?,
+            PlayerState::Play => self.inner.play()?,
+            PlayerState::Pause => self.inner.pause()?,
+            PlayerState::Stop => self.inner.stop()?,
         }
         Ok(())
     }
 
     fn state(&self) -> PlayerState {
-        self.state.read()
+        self.inner.state.read()
     }
 
     fn set_volume(&self, volume: f32) -> Result<(), Error> {
-        if let Some(sink) = self.current_sink.lock().unwrap().deref_mut() {
+        if let Some(sink) = self.inner.current_sink.lock().unwrap().deref_mut() {
             sink.set_volume(volume);
-            self.bus.emit_event(PlayerEvent::VolumeChanged(volume))?;
+            self.inner
+                .bus
+                .emit_event(PlayerEvent::VolumeChanged(volume))?;
         }
         Ok(())
     }
 
     fn volume(&self) -> f32 {
-        if let Some(sink) = self.current_sink.lock().unwrap().deref_mut() {
-            sink.volume()
-        } else {
-            1f32
-        }
+        self.inner.volume()
     }
 
     fn set_blend_time(&self, _duration: Duration) -> Result<(), Error> {
-        unimplemented!()
+        Err(PlayerError::Unsupported(PlayerCapability::Crossfade).into())
     }
 
     fn blend_time(&self) -> Duration {
-        unimplemented!()
+        self.inner.blend_time
     }
 
     fn seek(&self, _duration: Duration) -> Result<(), Error> {
-        unimplemented!()
+        Err(PlayerError::Unsupported(PlayerCapability::Seek).into())
+    }
+
+    fn position(&self) -> Option<Duration> {
+        if self.inner.state.read() == PlayerState::Stop {
+            return None;
+        }
+        let position = self.inner.position.load(Ordering::Relaxed);
+
+        Some(Duration::from_nanos(position))
+    }
+
+    fn devices(&self) -> Vec<OutputDevice> {
+        self.inner.devices()
+    }
+
+    fn set_device(&self, device_id: Option<&str>) -> Result<(), Error> {
+        if let Some(device_id) = device_id {
+            if !device::list_devices()?.iter().any(|name| name == device_id) {
+                bail!("Unknown output device {}", device_id);
+            }
+        }
+        self.inner.device.set(device_id.map(String::from));
+        self.inner.switch_device()?;
+        self.inner.emit_devices()
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -192,11 +456,12 @@ impl PlayerBackend for RodioBackend {
 }
 
 pub trait RodioPlayerBuilder {
-    fn with_rodio(&mut self) -> Result<&mut Self, Error>;
+    /// Plays on the audio device with the given name or on the default device
+    fn with_rodio(&mut self, device: Option<String>) -> Result<&mut Self, Error>;
 }
 
 impl RodioPlayerBuilder for PlayerBuilder {
-    fn with_rodio(&mut self) -> Result<&mut Self, Error> {
-        self.with_player(RodioBackend::new)
+    fn with_rodio(&mut self, device: Option<String>) -> Result<&mut Self, Error> {
+        self.with_player(|core, bus| RodioBackend::new(core, bus, device))
     }
 }
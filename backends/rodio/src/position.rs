@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rodio::{Sample, Source};
+
+/// Keeps track of the position in the current track by counting the samples handed to the output
+///
+/// rodio has no notion of a playback position, the sink only pulls samples while it's playing.
+pub(crate) struct PositionSource<S> {
+    inner: S,
+    /// Played time in nanoseconds
+    position: Arc<AtomicU64>,
+}
+
+impl<S> PositionSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    /// The position starts at `start`, the samples before it have to be skipped already
+    pub fn new(inner: S, position: Arc<AtomicU64>, start: Duration) -> Self {
+        position.store(start.as_nanos() as u64, Ordering::Relaxed);
+        PositionSource { inner, position }
+    }
+}
+
+/// Drops the samples of the given duration from the start of the source
+///
+/// rodio sources can't seek, the skipped samples are decoded on the calling thread.
+pub(crate) fn skip<S>(source: &mut S, duration: Duration)
+where
+    S: Source,
+    S::Item: Sample,
+{
+    let frames = (duration.as_secs_f64() * f64::from(source.sample_rate())) as u64;
+    let samples = frames * u64::from(source.channels());
+    for _ in 0..samples {
+        if source.next().is_none() {
+            break;
+        }
+    }
+}
+
+impl<S> Iterator for PositionSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let sample = self.inner.next()?;
+        let samples_per_second =
+            u64::from(self.inner.sample_rate()) * u64::from(self.inner.channels());
+        if samples_per_second > 0 {
+            self.position
+                .fetch_add(1_000_000_000 / samples_per_second, Ordering::Relaxed);
+        }
+        Some(sample)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S> Source for PositionSource<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
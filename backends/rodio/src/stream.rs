@@ -0,0 +1,361 @@
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Receiver, Sender};
+use failure::bail;
+use log::{debug, error, trace};
+use reqwest::blocking::Client;
+use reqwest::header::{ACCEPT_RANGES, RANGE};
+use reqwest::StatusCode;
+
+use rustic_core::cache::SharedCache;
+use rustic_core::Track;
+
+use crate::file::Emitter;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Reads further ahead than this use a range request instead of waiting for the download
+const RANGE_THRESHOLD: u64 = 512 * 1024;
+/// Reads fail when no data arrived in this time
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default)]
+struct Download {
+    /// Shared with the cache once the download is complete
+    data: Arc<Vec<u8>>,
+    length: Option<u64>,
+    accepts_ranges: bool,
+    complete: bool,
+    error: Option<String>,
+    range: Option<RangeDownload>,
+    /// The stream was dropped, running range requests can be aborted
+    closed: bool,
+}
+
+/// Data of a range request, starting at offset
+#[derive(Debug)]
+struct RangeDownload {
+    offset: u64,
+    data: Vec<u8>,
+    complete: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct SharedDownload {
+    state: Mutex<Download>,
+    changed: Condvar,
+}
+
+/// A seekable reader for an http stream
+///
+/// The whole file is downloaded in the background and written to the cache once it's complete.
+/// Reads block until the requested data has been downloaded, at most for [READ_TIMEOUT].
+/// Reads far ahead of the download, e.g. decoders probing the end of the file,
+/// are served by http range requests which also run on a background thread.
+pub(crate) struct HttpStream {
+    download: Arc<SharedDownload>,
+    position: u64,
+    range_sender: Sender<u64>,
+    emitter: Emitter,
+}
+
+impl HttpStream {
+    pub fn open(
+        track: Track,
+        url: String,
+        cache: SharedCache,
+        next_sender: Sender<()>,
+    ) -> HttpStream {
+        let download = Arc::new(SharedDownload::default());
+        let (range_sender, range_receiver) = crossbeam_channel::unbounded();
+        let stream = HttpStream {
+            download: Arc::clone(&download),
+            position: 0,
+            range_sender,
+            emitter: Emitter::new(next_sender),
+        };
+        {
+            let download = Arc::clone(&download);
+            let url = url.clone();
+            thread::spawn(move || serve_ranges(&url, &download, range_receiver));
+        }
+        thread::spawn(move || {
+            let client = Client::new();
+            if let Err(e) = download_track(&client, &url, &download) {
+                error!("Downloading {} failed: {:?}", &url, e);
+                let mut state = download.state.lock().unwrap();
+                state.error = Some(e.to_string());
+                download.changed.notify_all();
+                return;
+            }
+            let data = Arc::clone(&download.state.lock().unwrap().data);
+            if let Err(e) = cache.store_track(&track, &data) {
+                error!("Caching {} failed: {:?}", &track, e);
+            }
+        });
+
+        stream
+    }
+
+    fn wait_for_length(&self) -> io::Result<u64> {
+        let deadline = Instant::now() + READ_TIMEOUT;
+        let mut state = self.download.state.lock().unwrap();
+        loop {
+            if let Some(length) = state.length {
+                return Ok(length);
+            }
+            if state.complete {
+                return Ok(state.data.len() as u64);
+            }
+            if let Some(ref e) = state.error {
+                return Err(io::Error::new(io::ErrorKind::Other, e.clone()));
+            }
+            state = wait(&self.download, state, deadline)?;
+        }
+    }
+}
+
+impl Download {
+    /// The downloaded data starting at the given position
+    fn available(&self, position: u64) -> Option<&[u8]> {
+        if position < self.data.len() as u64 {
+            return Some(&self.data[position as usize..]);
+        }
+        let range = self.range.as_ref()?;
+        if range.offset <= position && position < range.end() {
+            return Some(&range.data[(position - range.offset) as usize..]);
+        }
+        None
+    }
+
+    fn is_end(&self, position: u64) -> bool {
+        let past_length = self.length.map(|length| position >= length);
+        if self.complete || past_length.unwrap_or(false) {
+            return true;
+        }
+        match self.range {
+            Some(ref range) => range.complete && range.offset <= position,
+            None => false,
+        }
+    }
+
+    fn error(&self, position: u64) -> Option<&String> {
+        let range_error = self
+            .range
+            .as_ref()
+            .filter(|range| range.offset <= position)
+            .and_then(|range| range.error.as_ref());
+
+        range_error.or_else(|| self.error.as_ref())
+    }
+
+    /// Whether neither the download nor the running range request will reach the position soon
+    fn needs_range(&self, position: u64) -> bool {
+        if !self.accepts_ranges || position < self.data.len() as u64 + RANGE_THRESHOLD {
+            return false;
+        }
+        match self.range {
+            Some(ref range) => position < range.offset || position > range.end() + RANGE_THRESHOLD,
+            None => true,
+        }
+    }
+}
+
+impl RangeDownload {
+    fn new(offset: u64) -> RangeDownload {
+        RangeDownload {
+            offset,
+            data: Vec::new(),
+            complete: false,
+            error: None,
+        }
+    }
+
+    fn end(&self) -> u64 {
+        self.offset + self.data.len() as u64
+    }
+}
+
+impl Read for HttpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let deadline = Instant::now() + READ_TIMEOUT;
+        let mut state = self.download.state.lock().unwrap();
+        loop {
+            if let Some(data) = state.available(self.position) {
+                let count = buf.len().min(data.len());
+                buf[..count].copy_from_slice(&data[..count]);
+                self.position += count as u64;
+                return Ok(count);
+            }
+            if state.is_end(self.position) {
+                self.emitter.emit();
+                return Ok(0);
+            }
+            if let Some(e) = state.error(self.position) {
+                return Err(io::Error::new(io::ErrorKind::Other, e.clone()));
+            }
+            if state.needs_range(self.position) {
+                trace!("Requesting range from byte {}", self.position);
+                state.range = Some(RangeDownload::new(self.position));
+                self.range_sender
+                    .send(self.position)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            }
+            state = wait(&self.download, state, deadline)?;
+        }
+    }
+}
+
+impl Seek for HttpStream {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(position) => position as i64,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+            SeekFrom::End(offset) => self.wait_for_length()? as i64 + offset,
+        };
+        if position < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            ));
+        }
+        self.position = position as u64;
+        Ok(self.position)
+    }
+}
+
+impl Drop for HttpStream {
+    fn drop(&mut self) {
+        let mut state = self.download.state.lock().unwrap();
+        state.closed = true;
+        state.range = None;
+    }
+}
+
+/// Waits for more data until the deadline passed
+fn wait<'a>(
+    download: &'a SharedDownload,
+    state: MutexGuard<'a, Download>,
+    deadline: Instant,
+) -> io::Result<MutexGuard<'a, Download>> {
+    let timeout = deadline.saturating_duration_since(Instant::now());
+    if timeout == Duration::default() {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "timed out waiting for the download",
+        ));
+    }
+    let (state, _) = download.changed.wait_timeout(state, timeout).unwrap();
+
+    Ok(state)
+}
+
+/// Runs the range requests of a stream until the stream is dropped
+///
+/// Only the latest request is served, older ones are aborted.
+fn serve_ranges(url: &str, download: &SharedDownload, requests: Receiver<u64>) {
+    let client = Client::new();
+    for offset in requests {
+        if let Err(e) = download_range(&client, url, download, offset) {
+            error!("Range request for {} failed: {:?}", url, e);
+            let mut state = download.state.lock().unwrap();
+            if let Some(range) = state.range.as_mut().filter(|range| range.offset == offset) {
+                range.error = Some(e.to_string());
+            }
+            download.changed.notify_all();
+        }
+    }
+}
+
+fn download_range(
+    client: &Client,
+    url: &str,
+    download: &SharedDownload,
+    offset: u64,
+) -> Result<(), failure::Error> {
+    if !is_requested(download, offset) {
+        return Ok(());
+    }
+    trace!("Requesting {} from byte {}", url, offset);
+    let mut response = client
+        .get(url)
+        .header(RANGE, format!("bytes={}-", offset))
+        .send()?
+        .error_for_status()?;
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        bail!("server ignored range request");
+    }
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        let count = match response.read(&mut chunk) {
+            Ok(count) => count,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let mut state = download.state.lock().unwrap();
+        let downloaded = state.data.len() as u64;
+        let range = match state.range.as_mut() {
+            Some(range) if range.offset == offset => range,
+            // the stream was dropped or requested another range
+            _ => return Ok(()),
+        };
+        if count == 0 {
+            range.complete = true;
+        } else {
+            range.data.extend_from_slice(&chunk[..count]);
+        }
+        // the download serves the range from here on
+        let done = range.complete || downloaded >= range.end();
+        download.changed.notify_all();
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+fn is_requested(download: &SharedDownload, offset: u64) -> bool {
+    let state = download.state.lock().unwrap();
+    !state.closed && state.range.as_ref().map(|range| range.offset) == Some(offset)
+}
+
+fn download_track(client: &Client, url: &str, download: &SharedDownload) -> Result<(), failure::Error> {
+    debug!("Streaming {}", url);
+    let mut response = client.get(url).send()?.error_for_status()?;
+    {
+        let mut state = download.state.lock().unwrap();
+        state.length = response.content_length();
+        state.accepts_ranges = response
+            .headers()
+            .get(ACCEPT_RANGES)
+            .map(|value| value == "bytes")
+            .unwrap_or(false);
+        if let Some(length) = state.length {
+            Arc::make_mut(&mut state.data).reserve(length as usize);
+        }
+    }
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        let count = match response.read(&mut chunk) {
+            Ok(count) => count,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let mut state = download.state.lock().unwrap();
+        if count == 0 {
+            state.complete = true;
+        } else {
+            Arc::make_mut(&mut state.data).extend_from_slice(&chunk[..count]);
+        }
+        download.changed.notify_all();
+        if state.complete {
+            break;
+        }
+    }
+    debug!("Finished streaming {}", url);
+
+    Ok(())
+}
@@ -7,10 +7,17 @@ pub enum SnapcastError {
     HttpError(String),
     #[error("rpc error {0}")]
     RpcError(#[from] RpcError),
+    #[error("io error {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("json error {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("connection to snapserver closed")]
+    ConnectionClosed,
 }
 
 pub type Result<T> = std::result::Result<T, SnapcastError>;
 
+#[cfg(feature = "http")]
 impl From<surf::Error> for SnapcastError {
     fn from(err: surf::Error) -> Self {
         SnapcastError::HttpError(err.to_string())
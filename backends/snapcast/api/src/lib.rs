@@ -1,11 +1,17 @@
 use self::models::*;
 pub use crate::error::*;
-use crate::rpc::{HttpTransport, SnapcastTransport, TcpTransport};
+#[cfg(feature = "http")]
+use crate::rpc::HttpTransport;
+pub use crate::rpc::RpcError;
+use crate::rpc::SnapcastTransport;
+#[cfg(feature = "tcp")]
+use crate::rpc::TcpTransport;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 mod error;
+pub mod plugin;
 mod rpc;
 
 #[derive(Debug)]
@@ -31,12 +37,96 @@ impl SnapcastClient {
         }
     }
 
+    /// Notifications about changes on the snapserver
+    ///
+    /// Only available with the tcp transport, for http the channel will be closed right away
+    #[cfg(feature = "tcp")]
+    pub fn notifications(&self) -> smol::channel::Receiver<Notification> {
+        self.transport.notifications()
+    }
+
+    pub async fn server_status(&self) -> Result<Server> {
+        let response = self
+            .request::<_, ServerStatusResponse>("Server.GetStatus", &EmptyRequest {})
+            .await?;
+
+        Ok(response.server)
+    }
+
     pub async fn get_client_status(&self, id: String) -> Result<Client> {
         let response = self
-            .request("Client.GetStatus", &GetClientStatusRequest { id })
+            .request::<_, GetStatusResponse>("Client.GetStatus", &GetClientStatusRequest { id })
             .await?;
 
-        Ok(response)
+        Ok(response.client)
+    }
+
+    pub async fn set_client_volume(&self, id: String, volume: Volume) -> Result<Volume> {
+        let response = self
+            .request::<_, SetVolumeResponse>(
+                "Client.SetVolume",
+                &SetVolumeRequest { id, volume },
+            )
+            .await?;
+
+        Ok(response.volume)
+    }
+
+    /// Latency in milliseconds
+    pub async fn set_client_latency(&self, id: String, latency: i64) -> Result<i64> {
+        let response = self
+            .request::<_, SetLatencyResponse>(
+                "Client.SetLatency",
+                &SetLatencyRequest { id, latency },
+            )
+            .await?;
+
+        Ok(response.latency)
+    }
+
+    /// Returns Stream ID
+    pub async fn set_group_stream(&self, group_id: String, stream_id: String) -> Result<String> {
+        let response = self
+            .request::<_, SetStreamResponse>(
+                "Group.SetStream",
+                &SetStreamRequest {
+                    id: group_id,
+                    stream_id,
+                },
+            )
+            .await?;
+
+        Ok(response.stream_id)
+    }
+
+    /// Moves the given clients into the group, clients not listed are moved into new groups
+    pub async fn set_group_clients(
+        &self,
+        group_id: String,
+        clients: Vec<String>,
+    ) -> Result<Server> {
+        let response = self
+            .request::<_, ServerStatusResponse>(
+                "Group.SetClients",
+                &SetClientsRequest {
+                    id: group_id,
+                    clients,
+                },
+            )
+            .await?;
+
+        Ok(response.server)
+    }
+
+    pub async fn set_group_mute(&self, group_id: String, mute: bool) -> Result<bool> {
+        let response = self
+            .request::<_, SetMuteResponse>(
+                "Group.SetMute",
+                &SetMuteRequest { id: group_id, mute },
+            )
+            .await?;
+
+        Ok(response.mute)
     }
 
     /// Returns Stream ID
@@ -75,6 +165,9 @@ impl SnapcastClient {
 pub mod models {
     use serde::{Deserialize, Serialize};
 
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct EmptyRequest {}
+
     #[derive(Debug, Clone, Serialize, Deserialize)]
     #[serde(rename_all = "camelCase")]
     pub(crate) struct AddStreamRequest {
@@ -96,6 +189,216 @@ pub mod models {
         pub client: Client,
     }
 
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct ServerStatusResponse {
+        pub server: Server,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct SetVolumeRequest {
+        pub id: String,
+        pub volume: Volume,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct SetVolumeResponse {
+        pub volume: Volume,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct SetLatencyRequest {
+        pub id: String,
+        pub latency: i64,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct SetLatencyResponse {
+        pub latency: i64,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct SetStreamRequest {
+        pub id: String,
+        pub stream_id: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct SetStreamResponse {
+        pub stream_id: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct SetClientsRequest {
+        pub id: String,
+        pub clients: Vec<String>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct SetMuteRequest {
+        pub id: String,
+        pub mute: bool,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct SetMuteResponse {
+        pub mute: bool,
+    }
+
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct StreamProperties {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub playback_status: Option<PlaybackStatus>,
+        /// Position in the current track in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub position: Option<f64>,
+        pub can_go_next: bool,
+        pub can_go_previous: bool,
+        pub can_play: bool,
+        pub can_pause: bool,
+        pub can_seek: bool,
+        pub can_control: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub metadata: Option<StreamMetadata>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum PlaybackStatus {
+        Playing,
+        Paused,
+        Stopped,
+    }
+
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct StreamMetadata {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub title: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub artist: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub album: Option<String>,
+        /// Duration of the track in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub duration: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub art_url: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub art_data: Option<ArtData>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ArtData {
+        /// Base64 encoded image
+        pub data: String,
+        /// File extension of the image, e.g. png
+        pub extension: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Server {
+        pub groups: Vec<Group>,
+        pub streams: Vec<Stream>,
+    }
+
+    impl Server {
+        pub fn group(&self, group_id: &str) -> Option<&Group> {
+            self.groups.iter().find(|group| group.id == group_id)
+        }
+
+        /// Returns the client and the group it belongs to
+        pub fn client(&self, client_id: &str) -> Option<(&Group, &Client)> {
+            self.groups.iter().find_map(|group| {
+                group
+                    .clients
+                    .iter()
+                    .find(|client| client.id == client_id)
+                    .map(|client| (group, client))
+            })
+        }
+
+        fn client_mut(&mut self, client_id: &str) -> Option<&mut Client> {
+            self.groups
+                .iter_mut()
+                .flat_map(|group| group.clients.iter_mut())
+                .find(|client| client.id == client_id)
+        }
+
+        fn group_mut(&mut self, group_id: &str) -> Option<&mut Group> {
+            self.groups.iter_mut().find(|group| group.id == group_id)
+        }
+
+        /// Applies the change of the given notification
+        ///
+        /// Returns false when the notification can't be applied to this status,
+        /// e.g. for unknown clients. The status should be fetched again in that case.
+        pub fn apply(&mut self, notification: &Notification) -> bool {
+            match notification {
+                Notification::ServerUpdated { server } => {
+                    *self = server.clone();
+                    true
+                }
+                Notification::ClientConnected { client, .. }
+                | Notification::ClientDisconnected { client, .. } => {
+                    match self.client_mut(&client.id) {
+                        Some(current) => {
+                            *current = client.clone();
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                Notification::ClientVolumeChanged { id, volume } => self
+                    .client_mut(id)
+                    .map(|client| client.config.volume = *volume)
+                    .is_some(),
+                Notification::ClientLatencyChanged { id, latency } => self
+                    .client_mut(id)
+                    .map(|client| client.config.latency = *latency)
+                    .is_some(),
+                Notification::ClientNameChanged { id, name } => self
+                    .client_mut(id)
+                    .map(|client| client.config.name = name.clone())
+                    .is_some(),
+                Notification::GroupMuted { id, mute } => self
+                    .group_mut(id)
+                    .map(|group| group.muted = *mute)
+                    .is_some(),
+                Notification::GroupStreamChanged { id, stream_id } => self
+                    .group_mut(id)
+                    .map(|group| group.stream_id = stream_id.clone())
+                    .is_some(),
+                Notification::GroupNameChanged { id, name } => self
+                    .group_mut(id)
+                    .map(|group| group.name = name.clone())
+                    .is_some(),
+                Notification::StreamUpdated { id, stream } => {
+                    match self.streams.iter_mut().find(|s| &s.id == id) {
+                        Some(current) => *current = stream.clone(),
+                        None => self.streams.push(stream.clone()),
+                    }
+                    true
+                }
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Group {
+        pub id: String,
+        pub name: String,
+        pub muted: bool,
+        pub stream_id: String,
+        pub clients: Vec<Client>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Stream {
+        pub id: String,
+        pub status: String,
+    }
+
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct Client {
         pub config: Config,
@@ -107,6 +410,17 @@ pub mod models {
         pub snapclient: Snapclient,
     }
 
+    impl Client {
+        /// The configured name or the hostname if none is configured
+        pub fn display_name(&self) -> &str {
+            if self.config.name.is_empty() {
+                &self.host.name
+            } else {
+                &self.config.name
+            }
+        }
+    }
+
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct Config {
         pub instance: i64,
@@ -143,4 +457,162 @@ pub mod models {
         pub protocol_version: i64,
         pub version: String,
     }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(tag = "method", content = "params")]
+    pub enum Notification {
+        #[serde(rename = "Client.OnConnect")]
+        ClientConnected { id: String, client: Client },
+        #[serde(rename = "Client.OnDisconnect")]
+        ClientDisconnected { id: String, client: Client },
+        #[serde(rename = "Client.OnVolumeChanged")]
+        ClientVolumeChanged { id: String, volume: Volume },
+        #[serde(rename = "Client.OnLatencyChanged")]
+        ClientLatencyChanged { id: String, latency: i64 },
+        #[serde(rename = "Client.OnNameChanged")]
+        ClientNameChanged { id: String, name: String },
+        #[serde(rename = "Group.OnMute")]
+        GroupMuted { id: String, mute: bool },
+        #[serde(rename = "Group.OnStreamChanged")]
+        GroupStreamChanged { id: String, stream_id: String },
+        #[serde(rename = "Group.OnNameChanged")]
+        GroupNameChanged { id: String, name: String },
+        #[serde(rename = "Stream.OnUpdate")]
+        StreamUpdated { id: String, stream: Stream },
+        #[serde(rename = "Server.OnUpdate")]
+        ServerUpdated { server: Server },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::models::{Notification, Server};
+
+    const SERVER_STATUS: &str = r#"{
+        "groups": [{
+            "id": "4dcc4e3b-c699-a04b-7f0c-8260d23c43e1",
+            "name": "",
+            "muted": false,
+            "stream_id": "rustic",
+            "clients": [{
+                "config": {
+                    "instance": 1,
+                    "latency": 0,
+                    "name": "",
+                    "volume": { "muted": false, "percent": 74 }
+                },
+                "connected": true,
+                "host": {
+                    "arch": "x86_64",
+                    "ip": "127.0.0.1",
+                    "mac": "00:21:6a:7d:74:fc",
+                    "name": "T400",
+                    "os": "Linux Mint 17.3 Rosa"
+                },
+                "id": "00:21:6a:7d:74:fc",
+                "lastSeen": { "sec": 1488026416, "usec": 135973 },
+                "snapclient": { "name": "Snapclient", "protocolVersion": 2, "version": "0.10.0" }
+            }]
+        }],
+        "streams": [{ "id": "rustic", "status": "playing" }]
+    }"#;
+
+    const VOLUME_CHANGED: &str = r#"{"jsonrpc":"2.0","method":"Client.OnVolumeChanged","params":{"id":"00:21:6a:7d:74:fc","volume":{"muted":false,"percent":36}}}"#;
+
+    const GROUP_MUTED: &str = r#"{"jsonrpc":"2.0","method":"Group.OnMute","params":{"id":"4dcc4e3b-c699-a04b-7f0c-8260d23c43e1","mute":true}}"#;
+
+    /// Sends the notifications before answering the first request, like snapserver interleaves them
+    ///
+    /// The response gets the id of the request.
+    #[cfg(feature = "tcp")]
+    async fn serve(
+        listener: smol::net::TcpListener,
+        notifications: &[&str],
+        mut response: serde_json::Value,
+    ) {
+        use smol::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        use smol::stream::StreamExt;
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut lines = BufReader::new(stream.clone()).lines();
+        let request = lines.next().await.unwrap().unwrap();
+        let request: serde_json::Value = serde_json::from_str(&request).unwrap();
+        for notification in notifications {
+            stream.write_all(notification.as_bytes()).await.unwrap();
+            stream.write_all(b"\n").await.unwrap();
+        }
+        response["id"] = request["id"].clone();
+        stream
+            .write_all(format!("{}\n", response).as_bytes())
+            .await
+            .unwrap();
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn tcp_transport_should_reflect_changes_made_elsewhere() {
+        smol::block_on(async {
+            let listener = smol::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            let server = smol::spawn(async move {
+                let response = serde_json::json!({"jsonrpc": "2.0", "result": {"mute": false}});
+                serve(listener, &[VOLUME_CHANGED, GROUP_MUTED], response).await;
+            });
+            let client = super::SnapcastClient::tcp(&address);
+            let notifications = client.notifications();
+            let mut status: Server = serde_json::from_str(SERVER_STATUS).unwrap();
+
+            client
+                .set_group_mute("other".to_string(), false)
+                .await
+                .unwrap();
+            server.await;
+
+            let volume_changed = notifications.recv().await.unwrap();
+            let group_muted = notifications.recv().await.unwrap();
+            assert!(status.apply(&volume_changed));
+            assert!(status.apply(&group_muted));
+            let (group, client) = status.client("00:21:6a:7d:74:fc").unwrap();
+            assert_eq!(client.config.volume.percent, 36);
+            assert!(group.muted);
+        });
+    }
+
+    #[test]
+    fn notifications_of_unknown_clients_should_not_be_applied() {
+        let mut status: Server = serde_json::from_str(SERVER_STATUS).unwrap();
+        let notification: Notification = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","method":"Client.OnVolumeChanged","params":{"id":"unknown","volume":{"muted":true,"percent":0}}}"#,
+        )
+        .unwrap();
+
+        assert!(!status.apply(&notification));
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn tcp_transport_should_fail_with_the_error_of_snapserver() {
+        smol::block_on(async {
+            let listener = smol::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            // How snapserver answers methods it doesn't know, e.g. older versions
+            let response = serde_json::from_str(
+                r#"{"error":{"code":-32601,"data":"Group.SetMute","message":"Method not found"},"jsonrpc":"2.0"}"#,
+            )
+            .unwrap();
+            let server = smol::spawn(async move { serve(listener, &[], response).await });
+            let client = super::SnapcastClient::tcp(&address);
+
+            let result = client.set_group_mute("group".to_string(), true).await;
+            server.await;
+
+            match result {
+                Err(super::SnapcastError::RpcError(err)) => {
+                    assert_eq!(err.code, -32601);
+                    assert_eq!(err.message, "Method not found");
+                }
+                result => panic!("unexpected result {:?}", result),
+            }
+        });
+    }
 }
@@ -0,0 +1,246 @@
+//! Protocol between snapserver and the controlscript of a stream
+//!
+//! The control api has no way to set the metadata of a stream or to receive control commands.
+//! Snapserver starts the `controlscript` configured for a stream and talks json rpc with it
+//! over stdin and stdout instead. `Stream.Control` requests of snapcast clients are forwarded
+//! to it and the properties it sends are published to the clients.
+//!
+//! See https://github.com/badaix/snapcast/blob/master/doc/json_rpc_api/stream_plugin.md
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::models::StreamProperties;
+use crate::{Result, RpcError};
+
+/// Command a snapcast client sent to control the stream
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamControlCommand {
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    /// Seek relative to the current position, offset in seconds
+    Seek {
+        offset: f64,
+    },
+    /// Seek to an absolute position in seconds
+    SetPosition {
+        position: f64,
+    },
+}
+
+impl StreamControlCommand {
+    fn parse(params: &Value) -> Option<Self> {
+        let param = |key: &str| params["params"][key].as_f64();
+        let command = match params["command"].as_str()? {
+            "play" => StreamControlCommand::Play,
+            "pause" => StreamControlCommand::Pause,
+            "playPause" => StreamControlCommand::PlayPause,
+            "stop" => StreamControlCommand::Stop,
+            "next" => StreamControlCommand::Next,
+            "previous" => StreamControlCommand::Previous,
+            "seek" => StreamControlCommand::Seek {
+                offset: param("offset")?,
+            },
+            "setPosition" => StreamControlCommand::SetPosition {
+                position: param("position")?,
+            },
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+/// Request snapserver sent to the controlscript
+#[derive(Debug, Clone, PartialEq)]
+pub enum PluginRequest {
+    Control(StreamControlCommand),
+    /// Sets one of `loopStatus`, `shuffle`, `volume`, `mute` or `rate`
+    SetProperty {
+        property: String,
+        value: Value,
+    },
+    GetProperties,
+}
+
+impl PluginRequest {
+    fn parse(method: &str, params: Value) -> std::result::Result<Self, RpcError> {
+        match method {
+            "Plugin.Stream.Player.Control" => StreamControlCommand::parse(&params)
+                .map(PluginRequest::Control)
+                .ok_or_else(|| RpcError::invalid_params(params)),
+            "Plugin.Stream.Player.SetProperty" => params
+                .as_object()
+                .and_then(|properties| properties.iter().next())
+                .map(|(property, value)| PluginRequest::SetProperty {
+                    property: property.clone(),
+                    value: value.clone(),
+                })
+                .ok_or_else(|| RpcError::invalid_params(params)),
+            "Plugin.Stream.Player.GetProperties" => Ok(PluginRequest::GetProperties),
+            method => Err(RpcError::method_not_found(method)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PluginMessage {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A request and the id its response has to be sent with
+#[derive(Debug, Clone)]
+pub struct PluginCall {
+    pub id: Value,
+    /// Unknown methods and invalid params are answered with this error
+    pub request: std::result::Result<PluginRequest, RpcError>,
+}
+
+impl PluginCall {
+    /// Parses a line snapserver wrote to the controlscript
+    pub fn parse(line: &str) -> Result<Self> {
+        let message: PluginMessage = serde_json::from_str(line)?;
+
+        Ok(PluginCall {
+            id: message.id,
+            request: PluginRequest::parse(&message.method, message.params),
+        })
+    }
+
+    /// The line to answer the request with
+    pub fn response(&self, result: std::result::Result<Value, RpcError>) -> String {
+        let response = match result {
+            Ok(result) => json!({ "id": self.id, "jsonrpc": "2.0", "result": result }),
+            Err(error) => json!({ "id": self.id, "jsonrpc": "2.0", "error": error }),
+        };
+        response.to_string()
+    }
+}
+
+/// Tells snapserver the controlscript is ready, snapserver requests the properties afterwards
+pub fn ready() -> String {
+    json!({ "jsonrpc": "2.0", "method": "Plugin.Stream.Ready" }).to_string()
+}
+
+/// Publishes the properties of the stream to the snapcast clients
+pub fn properties(properties: &StreamProperties) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "Plugin.Stream.Player.Properties",
+        "params": properties
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::models::{PlaybackStatus, StreamMetadata, StreamProperties};
+
+    use super::{properties, PluginCall, PluginRequest, StreamControlCommand};
+
+    fn request(line: &str) -> PluginRequest {
+        PluginCall::parse(line).unwrap().request.unwrap()
+    }
+
+    #[test]
+    fn parse_should_read_control_commands_without_params() {
+        let line = r#"{"id": 1, "jsonrpc": "2.0", "method": "Plugin.Stream.Player.Control", "params": {"command": "next", "params": {}}}"#;
+
+        assert_eq!(
+            request(line),
+            PluginRequest::Control(StreamControlCommand::Next)
+        );
+    }
+
+    #[test]
+    fn parse_should_read_control_command_params() {
+        let line = r#"{"id": 2, "jsonrpc": "2.0", "method": "Plugin.Stream.Player.Control", "params": {"command": "setPosition", "params": {"position": 12.5}}}"#;
+
+        assert_eq!(
+            request(line),
+            PluginRequest::Control(StreamControlCommand::SetPosition { position: 12.5 })
+        );
+    }
+
+    #[test]
+    fn parse_should_read_property_changes() {
+        let line = r#"{"id": 3, "jsonrpc": "2.0", "method": "Plugin.Stream.Player.SetProperty", "params": {"shuffle": true}}"#;
+
+        assert_eq!(
+            request(line),
+            PluginRequest::SetProperty {
+                property: "shuffle".into(),
+                value: Value::Bool(true)
+            }
+        );
+    }
+
+    #[test]
+    fn parse_should_read_requests_without_params() {
+        let line = r#"{"id": 4, "jsonrpc": "2.0", "method": "Plugin.Stream.Player.GetProperties"}"#;
+
+        assert_eq!(request(line), PluginRequest::GetProperties);
+    }
+
+    #[test]
+    fn unknown_methods_should_be_answered_with_method_not_found() {
+        let call =
+            PluginCall::parse(r#"{"id": 5, "jsonrpc": "2.0", "method": "Plugin.Stream.Unknown"}"#)
+                .unwrap();
+
+        let result = call.request.clone().map(|_| json!("ok"));
+        let response: Value = serde_json::from_str(&call.response(result)).unwrap();
+
+        assert_eq!(response["id"], 5);
+        assert_eq!(response["error"]["code"], -32601);
+        assert_eq!(response["error"]["message"], "Method not found");
+    }
+
+    #[test]
+    fn response_should_answer_with_the_id_of_the_request() {
+        let call = PluginCall::parse(
+            r#"{"id": 6, "jsonrpc": "2.0", "method": "Plugin.Stream.Player.Control", "params": {"command": "play", "params": {}}}"#,
+        )
+        .unwrap();
+
+        let response: Value = serde_json::from_str(&call.response(Ok(json!("ok")))).unwrap();
+
+        assert_eq!(response, json!({"id": 6, "jsonrpc": "2.0", "result": "ok"}));
+    }
+
+    #[test]
+    fn properties_should_be_sent_as_player_properties_notification() {
+        let notification = properties(&StreamProperties {
+            playback_status: Some(PlaybackStatus::Playing),
+            can_control: true,
+            metadata: Some(StreamMetadata {
+                title: Some("Ukulele".into()),
+                artist: Some(vec!["Bensound".into()]),
+                duration: Some(146.0),
+                ..StreamMetadata::default()
+            }),
+            ..StreamProperties::default()
+        });
+
+        let notification: Value = serde_json::from_str(&notification).unwrap();
+
+        assert_eq!(notification["method"], "Plugin.Stream.Player.Properties");
+        assert_eq!(notification["params"]["playbackStatus"], "playing");
+        assert_eq!(notification["params"]["canControl"], true);
+        assert_eq!(notification["params"]["metadata"]["title"], "Ukulele");
+        assert_eq!(
+            notification["params"]["metadata"]["artist"],
+            json!(["Bensound"])
+        );
+        assert_eq!(notification["params"]["metadata"]["duration"], 146.0);
+    }
+}
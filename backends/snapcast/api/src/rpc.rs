@@ -1,8 +1,13 @@
+#[cfg(feature = "http")]
 pub(crate) use self::http::HttpTransport;
+#[cfg(feature = "tcp")]
 pub(crate) use self::tcp::TcpTransport;
+#[cfg(feature = "tcp")]
+use crate::models::Notification;
 use crate::Result;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug)]
 pub(crate) enum SnapcastTransport {
@@ -25,7 +30,9 @@ impl SnapcastTransport {
     {
         let request = RpcRequest::new(request_id, method.to_string(), params);
         let res = match self {
+            #[cfg(feature = "http")]
             SnapcastTransport::Http(http) => http.request(request).await?,
+            #[cfg(feature = "tcp")]
             SnapcastTransport::Tcp(tcp) => tcp.request(request).await?,
         };
         match (res.result, res.error) {
@@ -34,6 +41,19 @@ impl SnapcastTransport {
             (_, _) => unreachable!(),
         }
     }
+
+    /// The http transport can't receive notifications, the returned channel will be closed right away
+    #[cfg(feature = "tcp")]
+    pub fn notifications(&self) -> smol::channel::Receiver<Notification> {
+        match self {
+            #[cfg(feature = "http")]
+            SnapcastTransport::Http(_) => {
+                let (_, rx) = smol::channel::unbounded();
+                rx
+            }
+            SnapcastTransport::Tcp(tcp) => tcp.subscribe(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -64,13 +84,41 @@ struct RpcResponse<R> {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, thiserror::Error)]
-#[error("{data}")]
+#[error("{message} ({data:?})")]
 pub struct RpcError {
     pub code: i64,
-    pub data: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
     pub message: String,
 }
 
+impl RpcError {
+    pub fn method_not_found(method: &str) -> Self {
+        RpcError {
+            code: -32601,
+            data: Some(method.to_string()),
+            message: "Method not found".to_string(),
+        }
+    }
+
+    pub fn invalid_params(params: Value) -> Self {
+        RpcError {
+            code: -32602,
+            data: Some(params.to_string()),
+            message: "Invalid params".to_string(),
+        }
+    }
+
+    pub fn internal_error(data: String) -> Self {
+        RpcError {
+            code: -32603,
+            data: Some(data),
+            message: "Internal error".to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
 mod http {
     use super::{RpcRequest, RpcResponse};
     use crate::Result;
@@ -97,7 +145,7 @@ mod http {
             TRes: DeserializeOwned,
         {
             let url = format!("{}/jsonrpc", self.host);
-            println!("POST {} {:?}", url, &req);
+            log::trace!("POST {} {:?}", url, &req);
             let res = surf::post(url)
                 .body(Body::from_json(&req)?)
                 .recv_json()
@@ -108,18 +156,52 @@ mod http {
     }
 }
 
+#[cfg(feature = "tcp")]
 mod tcp {
-    use super::{RpcRequest, RpcResponse};
-    use crate::Result;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
     use serde::de::DeserializeOwned;
     use serde::Serialize;
+    use serde_json::Value;
+    use smol::channel::{Receiver, Sender};
+    use smol::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use smol::net::TcpStream;
+    use smol::stream::StreamExt;
+
+    use super::{RpcRequest, RpcResponse};
+    use crate::models::Notification;
+    use crate::{Result, SnapcastError};
+
+    type PendingRequests = Arc<Mutex<HashMap<u64, Sender<Value>>>>;
+    type Subscribers = Arc<Mutex<Vec<Sender<Notification>>>>;
 
+    /// Newline delimited json rpc over a raw tcp connection (usually port 1705)
+    ///
+    /// The connection is opened lazily on the first request and reopened after it was closed.
+    /// Responses are matched to their requests by id, everything else is a server notification.
     #[derive(Debug)]
-    pub(crate) struct TcpTransport {}
+    pub(crate) struct TcpTransport {
+        address: String,
+        connection: Arc<smol::lock::Mutex<Option<TcpStream>>>,
+        pending: PendingRequests,
+        subscribers: Subscribers,
+    }
 
     impl TcpTransport {
-        pub fn new(_: String) -> Self {
-            TcpTransport {}
+        pub fn new(address: String) -> Self {
+            TcpTransport {
+                address,
+                connection: Default::default(),
+                pending: Default::default(),
+                subscribers: Default::default(),
+            }
+        }
+
+        pub(super) fn subscribe(&self) -> Receiver<Notification> {
+            let (tx, rx) = smol::channel::unbounded();
+            self.subscribers.lock().unwrap().push(tx);
+            rx
         }
 
         pub(super) async fn request<TReq, TRes>(
@@ -127,10 +209,91 @@ mod tcp {
             req: RpcRequest<TReq>,
         ) -> Result<RpcResponse<TRes>>
         where
-            TReq: Serialize,
+            TReq: Serialize + std::fmt::Debug,
             TRes: DeserializeOwned,
         {
-            unimplemented!()
+            log::trace!("{} {:?}", self.address, &req);
+            let mut payload = serde_json::to_vec(&req)?;
+            payload.push(b'\n');
+
+            let (tx, rx) = smol::channel::bounded(1);
+            self.pending.lock().unwrap().insert(req.id, tx);
+
+            if let Err(err) = self.send(&payload).await {
+                self.pending.lock().unwrap().remove(&req.id);
+                return Err(err);
+            }
+
+            let response = rx
+                .recv()
+                .await
+                .map_err(|_| SnapcastError::ConnectionClosed)?;
+            let response = serde_json::from_value(response)?;
+
+            Ok(response)
+        }
+
+        async fn send(&self, payload: &[u8]) -> Result<()> {
+            let mut connection = self.connection.lock().await;
+            if connection.is_none() {
+                let stream = TcpStream::connect(&self.address).await?;
+                self.spawn_reader(stream.clone());
+                *connection = Some(stream);
+            }
+            let stream = connection.as_mut().unwrap();
+            if let Err(err) = stream.write_all(payload).await {
+                *connection = None;
+                return Err(err.into());
+            }
+            Ok(())
+        }
+
+        fn spawn_reader(&self, stream: TcpStream) {
+            let connection = Arc::clone(&self.connection);
+            let pending = Arc::clone(&self.pending);
+            let subscribers = Arc::clone(&self.subscribers);
+            smol::spawn(async move {
+                let mut lines = BufReader::new(stream).lines();
+                while let Some(line) = lines.next().await {
+                    match line {
+                        Ok(line) => dispatch(&line, &pending, &subscribers),
+                        Err(err) => {
+                            log::error!("snapserver connection failed: {}", err);
+                            break;
+                        }
+                    }
+                }
+                log::debug!("snapserver connection closed");
+                connection.lock().await.take();
+                // Dropping the senders fails all requests which are still waiting for a response
+                pending.lock().unwrap().clear();
+            })
+            .detach();
+        }
+    }
+
+    fn dispatch(line: &str, pending: &PendingRequests, subscribers: &Subscribers) {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(err) => {
+                log::warn!("invalid message from snapserver: {}", err);
+                return;
+            }
+        };
+        match message.get("id").and_then(Value::as_u64) {
+            Some(id) => {
+                if let Some(tx) = pending.lock().unwrap().remove(&id) {
+                    let _ = tx.try_send(message);
+                }
+            }
+            None => match serde_json::from_value::<Notification>(message) {
+                Ok(notification) => {
+                    log::trace!("{:?}", &notification);
+                    let mut subscribers = subscribers.lock().unwrap();
+                    subscribers.retain(|tx| tx.try_send(notification.clone()).is_ok());
+                }
+                Err(err) => log::debug!("ignoring notification from snapserver: {}", err),
+            },
         }
     }
 }
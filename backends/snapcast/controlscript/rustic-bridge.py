@@ -0,0 +1,37 @@
+#!/usr/bin/env python3
+"""Controlscript relaying the stream plugin protocol of snapserver to rustic
+
+Snapserver starts this script for the stream of a rustic snapcast player and talks json rpc
+with it over stdin and stdout. The messages are relayed line by line to the address rustic
+passes with `--rustic=<host>:<port>`.
+"""
+
+import argparse
+import socket
+import sys
+import threading
+
+
+def relay_stdin(connection):
+    for line in sys.stdin:
+        connection.sendall(line.encode("utf-8"))
+    connection.shutdown(socket.SHUT_WR)
+
+
+def main():
+    parser = argparse.ArgumentParser()
+    parser.add_argument("--rustic", required=True, help="address of the rustic player")
+    # --stream, --snapcast-host and --snapcast-port passed by snapserver are not needed
+    args, _ = parser.parse_known_args()
+
+    host, _, port = args.rustic.rpartition(":")
+    connection = socket.create_connection((host, int(port)))
+    threading.Thread(target=relay_stdin, args=(connection,), daemon=True).start()
+
+    for line in connection.makefile("r", encoding="utf-8"):
+        sys.stdout.write(line)
+        sys.stdout.flush()
+
+
+if __name__ == "__main__":
+    main()
@@ -1,8 +1,12 @@
+use std::io::Write;
+use std::net::TcpStream;
+
 use failure::Error;
-use smol::process::{Child, Command};
 
 use snapcast_api::SnapcastClient;
 
+use crate::symphonia_decoder::{BITS_PER_SAMPLE, CHANNELS, SAMPLE_RATE};
+
 #[derive(Debug, Clone)]
 pub enum SnapcastAudioTransport {
     Tcp { host: String, port: u16 },
@@ -10,17 +14,30 @@ pub enum SnapcastAudioTransport {
 }
 
 impl SnapcastAudioTransport {
-    pub async fn add_stream(&self, name: &str, client: &SnapcastClient) -> Result<String, Error> {
-        let url = match self {
+    /// Adds the stream to snapserver, `params` are appended to the stream uri
+    pub async fn add_stream(
+        &self,
+        name: &str,
+        params: Option<String>,
+        client: &SnapcastClient,
+    ) -> Result<String, Error> {
+        let sample_format = format!("{}:{}:{}", SAMPLE_RATE, BITS_PER_SAMPLE, CHANNELS);
+        let mut url = match self {
             SnapcastAudioTransport::Tcp { host, port } => format!(
-                "tcp://{}:{}?name={}&mode=server&codec=pcm",
-                &host, &port, name
+                "tcp://{}:{}?name={}&mode=server&codec=pcm&sampleformat={}",
+                &host, &port, name, sample_format
             ),
             SnapcastAudioTransport::Pipe(pipe) => {
                 unix_named_pipe::create(&pipe, Some(0o644))?;
-                format!("pipe://{}?name={}&mode=read&codec=pcm", &pipe, name)
+                format!(
+                    "pipe://{}?name={}&mode=read&codec=pcm&sampleformat={}",
+                    &pipe, name, sample_format
+                )
             }
         };
+        if let Some(params) = params {
+            url = format!("{}&{}", url, params);
+        }
         let stream_id = client.add_stream(url).await?;
         Ok(stream_id)
     }
@@ -32,28 +49,19 @@ impl SnapcastAudioTransport {
         Ok(())
     }
 
-    pub fn spawn(&self, path: &str) -> Result<Child, Error> {
-        let target = match self {
-            SnapcastAudioTransport::Pipe(pipe) => pipe.clone(),
-            SnapcastAudioTransport::Tcp { host, port } => format!("tcp://{}:{}", host, port),
-        };
-        let child = Command::new("ffmpeg")
-            .args(&[
-                "-y",
-                "-i",
-                &path,
-                "-f",
-                "u16le",
-                "-acodec",
-                "pcm_s16le",
-                "-ac",
-                "2",
-                "-ar",
-                "48000",
-                &target,
-            ])
-            .spawn()?;
-
-        Ok(child)
+    /// Opens the transport for writing raw pcm samples
+    pub fn open(&self) -> Result<Box<dyn Write + Send>, Error> {
+        match self {
+            SnapcastAudioTransport::Pipe(ref pipe) => {
+                log::trace!("opening pipe {}", pipe);
+                let pipe = unix_named_pipe::open_write(pipe)?;
+                Ok(Box::new(pipe))
+            }
+            SnapcastAudioTransport::Tcp { ref host, ref port } => {
+                log::trace!("connecting to {}:{}", host, port);
+                let socket = TcpStream::connect(&format!("{}:{}", host, port))?;
+                Ok(Box::new(socket))
+            }
+        }
     }
 }
@@ -1,71 +1,132 @@
-use crate::audio_transport::SnapcastAudioTransport;
-use crate::BackgroundCommand;
-use failure::Error;
-use rustic_core::{PlayerState, Rustic, Track};
-use smol::process::Child;
+use std::io::Write;
 use std::sync::Arc;
+use std::time::Duration;
+
+use failure::Error;
+use pinboard::NonEmptyPinboard;
+use smol::channel::{Receiver, TryRecvError};
 use url::Url;
 
+use rustic_core::player::{NextReason, PlayerBus, QueueCommand};
+use rustic_core::{PlayerState, Rustic, Track};
+
+use crate::audio_transport::SnapcastAudioTransport;
+use crate::symphonia_decoder::{to_pcm, SymphoniaDecoder, CHANNELS, SAMPLE_RATE};
+use crate::BackgroundCommand;
+
 pub struct BackgroundJob {
-    pub core: Arc<Rustic>,
-    pub transport: SnapcastAudioTransport,
-    pub child: Option<Child>,
+    core: Arc<Rustic>,
+    bus: PlayerBus,
+    transport: SnapcastAudioTransport,
+    cmd_rx: Receiver<BackgroundCommand>,
+    state: PlayerState,
+    volume: f32,
+    decoder: Option<SymphoniaDecoder>,
+    output: Option<Box<dyn Write + Send>>,
+    /// Number of frames written since the start of the track
+    frames: u64,
+    position: Arc<NonEmptyPinboard<Duration>>,
 }
 
 impl BackgroundJob {
-    pub fn new(core: Arc<Rustic>, transport: SnapcastAudioTransport) -> Self {
+    pub fn new(
+        core: Arc<Rustic>,
+        bus: PlayerBus,
+        transport: SnapcastAudioTransport,
+        cmd_rx: Receiver<BackgroundCommand>,
+        position: Arc<NonEmptyPinboard<Duration>>,
+    ) -> Self {
         BackgroundJob {
             core,
-            transport: transport.clone(),
-            child: None,
+            bus,
+            transport,
+            cmd_rx,
+            state: PlayerState::Stop,
+            volume: 1.0,
+            decoder: None,
+            output: None,
+            frames: 0,
+            position,
         }
     }
 
-    pub fn has_child_exited(&mut self) -> bool {
-        self.child
-            .as_mut()
-            .and_then(|child| child.try_status().ok())
-            .flatten()
-            .is_some()
-    }
-
-    async fn get_child(&mut self) -> Result<(), Error> {
-        match self.child {
-            Some(ref mut child) => {
-                child.status().await?;
+    /// Runs until the backend is dropped.
+    ///
+    /// While nothing is playing this blocks on the command channel, while playing
+    /// writing to the transport blocks until the snapserver consumes the written samples.
+    pub fn run(mut self) {
+        loop {
+            let cmd = if self.is_playing() {
+                match self.cmd_rx.try_recv() {
+                    Ok(cmd) => Some(cmd),
+                    Err(TryRecvError::Empty) => None,
+                    Err(TryRecvError::Closed) => break,
+                }
+            } else {
+                match smol::block_on(self.cmd_rx.recv()) {
+                    Ok(cmd) => Some(cmd),
+                    Err(_) => break,
+                }
+            };
 
-                Ok(())
+            if let Some(cmd) = cmd {
+                if let Err(e) = self.handle_cmd(cmd) {
+                    log::error!("{:?}", e);
+                }
+                continue;
             }
-            None => {
-                futures::future::pending::<()>().await;
 
-                Ok(())
+            if let Err(e) = self.write_next_packet() {
+                log::error!("Playback failed, skipping track: {:?}", e);
+                self.decoder = None;
+                self.next(NextReason::Error);
             }
         }
+        log::debug!("Stopping snapcast background job");
     }
 
-    pub fn handle_cmd(&mut self, cmd: BackgroundCommand) -> Result<(), Error> {
+    fn is_playing(&self) -> bool {
+        self.state == PlayerState::Play && self.decoder.is_some()
+    }
+
+    fn handle_cmd(&mut self, cmd: BackgroundCommand) -> Result<(), Error> {
         match cmd {
             BackgroundCommand::Play(track, url) => {
-                self.decode_stream(&track, url)?;
+                self.decoder = None;
+                self.set_position(Duration::default());
+                if let Err(e) = self.decode_stream(&track, url) {
+                    self.next(NextReason::Error);
+                    return Err(e);
+                }
             }
             BackgroundCommand::SetState(PlayerState::Stop) => {
-                if let Some(mut child) = self.child.take() {
-                    child.kill()?;
+                self.state = PlayerState::Stop;
+                self.decoder = None;
+                self.set_position(Duration::default());
+            }
+            BackgroundCommand::SetState(state) => {
+                self.state = state;
+            }
+            BackgroundCommand::SetVolume(volume) => {
+                self.volume = volume;
+            }
+            BackgroundCommand::Seek(position) => {
+                if let Some(decoder) = self.decoder.as_mut() {
+                    decoder.seek(position)?;
+                    self.set_position(position);
                 }
             }
-            _ => {}
         }
         Ok(())
     }
 
-    pub fn decode_stream(&mut self, track: &Track, stream_url: String) -> Result<(), Error> {
+    fn decode_stream(&mut self, track: &Track, stream_url: String) -> Result<(), Error> {
         log::trace!("Decoding stream {} for track {}", &stream_url, track);
         let url = Url::parse(&stream_url)?;
         match url.scheme() {
             "file" => self.play_file(stream_url),
             "http" | "https" => {
-                let path = self.core.cache.fetch_track(track, &stream_url)?;
+                let path = self.core.cache.fetch_track_blocking(track, &stream_url)?;
                 self.play_file(path)
             }
             scheme => failure::bail!("Invalid scheme: {}", scheme),
@@ -76,12 +137,56 @@ impl BackgroundJob {
         path.replace_range(..7, "");
         log::trace!("Playing file {}", &path);
 
-        if let Some(mut child) = self.child.take() {
-            child.kill()?
-        }
+        self.decoder = Some(SymphoniaDecoder::open(&path)?);
 
-        self.child = Some(self.transport.spawn(&path)?);
+        Ok(())
+    }
 
+    fn write_next_packet(&mut self) -> Result<(), Error> {
+        let samples = match self.decoder.as_mut() {
+            Some(decoder) => decoder.next_samples()?,
+            None => return Ok(()),
+        };
+        match samples {
+            Some(samples) => {
+                let pcm = to_pcm(&samples, self.volume);
+                self.write(&pcm)?;
+                self.frames += (samples.len() / CHANNELS) as u64;
+                self.position.set(Duration::from_secs_f64(
+                    self.frames as f64 / f64::from(SAMPLE_RATE),
+                ));
+                Ok(())
+            }
+            None => {
+                log::debug!("reached end of track");
+                self.decoder = None;
+                self.next(NextReason::Finished);
+                Ok(())
+            }
+        }
+    }
+
+    fn write(&mut self, pcm: &[u8]) -> Result<(), Error> {
+        if self.output.is_none() {
+            self.output = Some(self.transport.open()?);
+        }
+        let output = self.output.as_mut().unwrap();
+        if let Err(e) = output.write_all(pcm) {
+            // Reopen the transport on the next write, the snapserver may have been restarted
+            self.output = None;
+            return Err(e.into());
+        }
         Ok(())
     }
+
+    fn set_position(&mut self, position: Duration) {
+        self.frames = (position.as_secs_f64() * f64::from(SAMPLE_RATE)) as u64;
+        self.position.set(position);
+    }
+
+    fn next(&self, reason: NextReason) {
+        if let Err(e) = self.bus.send_queue_msg(QueueCommand::Next(reason)) {
+            log::error!("Failed loading next track: {:?}", e);
+        }
+    }
 }
@@ -0,0 +1,129 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use failure::Error;
+use smol::channel::Sender;
+use smol::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use smol::net::{TcpListener, TcpStream};
+use smol::stream::StreamExt;
+
+use snapcast_api::plugin::{self, PluginCall};
+
+use crate::stream::SnapcastStream;
+
+/// Controlscript snapserver starts for the stream of the player
+///
+/// Snapserver only exchanges stream properties and control commands with the controlscript of a
+/// stream. `controlscript/rustic-bridge.py` relays those messages to the address rustic listens on.
+#[derive(Debug, Clone)]
+pub struct SnapcastControlScript {
+    /// Path of the bridge on the snapserver host
+    pub path: String,
+    /// Address rustic listens on for the bridge, it has to be reachable from the snapserver host
+    pub address: String,
+}
+
+/// Socket the controlscript bridge connects to
+#[derive(Clone)]
+pub(crate) struct ControlScriptSocket {
+    path: String,
+    listener: TcpListener,
+    address: SocketAddr,
+    connections: Arc<Mutex<Vec<Sender<String>>>>,
+}
+
+impl ControlScriptSocket {
+    pub fn bind(config: SnapcastControlScript) -> Result<Self, Error> {
+        let listener = smol::block_on(TcpListener::bind(&config.address))?;
+        let address = listener.local_addr()?;
+        log::debug!("Listening for the snapcast controlscript on {}", address);
+
+        Ok(ControlScriptSocket {
+            path: config.path,
+            listener,
+            address,
+            connections: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    /// Stream uri params which make snapserver start the bridge
+    pub fn stream_params(&self) -> String {
+        format!(
+            "controlscript={}&controlscriptparams=--rustic={}",
+            self.path, self.address
+        )
+    }
+
+    /// Accepts connections of the bridge and answers the requests of snapserver
+    pub fn serve(&self, stream: SnapcastStream) {
+        let socket = self.clone();
+        smol::spawn(async move {
+            loop {
+                match socket.listener.accept().await {
+                    Ok((connection, address)) => {
+                        log::debug!("Snapcast controlscript connected from {}", address);
+                        let socket = socket.clone();
+                        let stream = stream.clone();
+                        smol::spawn(async move { socket.handle(connection, stream).await })
+                            .detach();
+                    }
+                    Err(e) => {
+                        log::error!("Accepting snapcast controlscript failed: {:?}", e);
+                        break;
+                    }
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Sends a notification to snapserver
+    pub fn notify(&self, message: String) {
+        let connections = self.connections.lock().unwrap();
+        for connection in connections.iter() {
+            let _ = connection.try_send(message.clone());
+        }
+    }
+
+    async fn handle(&self, connection: TcpStream, stream: SnapcastStream) {
+        let (tx, rx) = smol::channel::unbounded::<String>();
+        let _ = tx.try_send(plugin::ready());
+        self.connections.lock().unwrap().push(tx.clone());
+
+        let mut writer = connection.clone();
+        smol::spawn(async move {
+            while let Ok(message) = rx.recv().await {
+                let message = format!("{}\n", message);
+                if let Err(e) = writer.write_all(message.as_bytes()).await {
+                    log::error!("Writing to snapcast controlscript failed: {:?}", e);
+                    break;
+                }
+            }
+        })
+        .detach();
+
+        let mut lines = BufReader::new(connection).lines();
+        while let Some(Ok(line)) = lines.next().await {
+            let call = match PluginCall::parse(&line) {
+                Ok(call) => call,
+                Err(e) => {
+                    log::warn!("Ignoring message of snapserver {}: {:?}", line, e);
+                    continue;
+                }
+            };
+            let result = match call.request.clone() {
+                Ok(request) => stream.handle_request(request).await,
+                Err(e) => Err(e),
+            };
+            if tx.send(call.response(result)).await.is_err() {
+                break;
+            }
+        }
+        log::debug!("Snapcast controlscript disconnected");
+        tx.close();
+        self.connections
+            .lock()
+            .unwrap()
+            .retain(|connection| !connection.is_closed());
+    }
+}
@@ -1,5 +1,8 @@
 mod audio_transport;
 mod background_job;
+mod controlscript;
+mod outputs;
+mod stream;
 mod symphonia_decoder;
 
 use std::any::Any;
@@ -7,24 +10,34 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use failure::Error;
-use smol::channel::{Sender, TryRecvError};
+use smol::channel::Sender;
 
 pub use crate::audio_transport::SnapcastAudioTransport;
 use crate::background_job::BackgroundJob;
+use crate::controlscript::ControlScriptSocket;
+pub use crate::controlscript::SnapcastControlScript;
+use crate::outputs::SnapcastOutputs;
+use crate::stream::SnapcastStream;
 use pinboard::NonEmptyPinboard;
-use rustic_core::player::QueueCommand;
 use rustic_core::{
-    player::{PlayerBuilder, PlayerBus},
+    player::{
+        PlayerBuilder, PlayerBus, PlayerCapabilities, PlayerCapability, PlayerError, PlayerOutput,
+    },
     Rustic,
 };
-use rustic_core::{PlayerBackend, PlayerState, Track};
+use rustic_core::{PlayerBackend, PlayerEvent, PlayerState, Track};
 use snapcast_api::SnapcastClient;
 
 struct SnapcastBackend {
     stream_id: String,
-    client: SnapcastClient,
+    client: Arc<SnapcastClient>,
+    outputs: SnapcastOutputs,
+    stream: SnapcastStream,
+    bus: PlayerBus,
     cmd_tx: Sender<BackgroundCommand>,
-    current_state: NonEmptyPinboard<PlayerState>,
+    current_state: Arc<NonEmptyPinboard<PlayerState>>,
+    current_volume: NonEmptyPinboard<f32>,
+    position: Arc<NonEmptyPinboard<Duration>>,
     transport: SnapcastAudioTransport,
 }
 
@@ -32,6 +45,8 @@ struct SnapcastBackend {
 enum BackgroundCommand {
     SetState(PlayerState),
     Play(Track, String),
+    SetVolume(f32),
+    Seek(Duration),
 }
 
 impl std::fmt::Debug for SnapcastBackend {
@@ -48,56 +63,90 @@ impl SnapcastBackend {
         bus: PlayerBus,
         api_url: String,
         transport: SnapcastAudioTransport,
+        control: Option<SnapcastControlScript>,
         name: String,
     ) -> Result<Self, Error> {
-        let client = SnapcastClient::http(api_url);
-        let stream_id = smol::block_on(transport.add_stream(&name, &client))?;
-
-        let (cmd_tx, mut cmd_rx) = smol::channel::unbounded::<BackgroundCommand>();
-
-        let mut background = BackgroundJob::new(core, transport.clone());
-
-        smol::spawn(async move {
-            loop {
-                if background.has_child_exited() {
-                    bus.send_queue_msg(QueueCommand::Next).unwrap();
-                }
-
-                match cmd_rx.try_recv() {
-                    Ok(cmd) => background.handle_cmd(cmd).unwrap(),
-                    Err(TryRecvError::Empty) => {}
-                    Err(e) => {
-                        log::error!("{:?}", e);
-                        return;
-                    }
-                }
-            }
-        })
-        .detach();
+        let client = if api_url.starts_with("tcp://") {
+            SnapcastClient::tcp(&api_url["tcp://".len()..])
+        } else {
+            SnapcastClient::http(api_url)
+        };
+        let client = Arc::new(client);
+        let control = control.map(ControlScriptSocket::bind).transpose()?;
+        let control_params = control.as_ref().map(|control| control.stream_params());
+        let stream_id = smol::block_on(transport.add_stream(&name, control_params, &client))?;
+        let outputs = SnapcastOutputs::new(Arc::clone(&client), stream_id.clone(), bus.clone())?;
+        outputs.observe();
+        let current_state = Arc::new(NonEmptyPinboard::new(PlayerState::Stop));
+        let position = Arc::new(NonEmptyPinboard::new(Duration::default()));
+        let stream = SnapcastStream::new(
+            Arc::clone(&core),
+            bus.clone(),
+            control.clone(),
+            Arc::clone(&current_state),
+            Arc::clone(&position),
+        );
+        if let Some(ref control) = control {
+            control.serve(stream.clone());
+        }
+
+        let (cmd_tx, cmd_rx) = smol::channel::unbounded::<BackgroundCommand>();
+
+        let background = BackgroundJob::new(
+            core,
+            bus.clone(),
+            transport.clone(),
+            cmd_rx,
+            Arc::clone(&position),
+        );
+
+        std::thread::Builder::new()
+            .name(format!("snapcast-{}", name))
+            .spawn(move || background.run())?;
 
         Ok(SnapcastBackend {
             stream_id,
             client,
+            outputs,
+            stream,
+            bus,
             cmd_tx,
             transport,
-            current_state: NonEmptyPinboard::new(PlayerState::Stop),
+            current_state,
+            current_volume: NonEmptyPinboard::new(1.0),
+            position,
         })
     }
 }
 
 impl PlayerBackend for SnapcastBackend {
+    fn capabilities(&self) -> PlayerCapabilities {
+        PlayerCapabilities {
+            seek: true,
+            volume: true,
+            ..PlayerCapabilities::default()
+        }
+    }
+
     fn set_track(&self, track: &Track, stream_url: String) -> Result<(), Error> {
         smol::block_on(
             self.cmd_tx
                 .send(BackgroundCommand::Play(track.clone(), stream_url)),
         )?;
+        self.bus
+            .emit_event(PlayerEvent::TrackChanged(track.clone()))?;
+        self.stream.set_track(track);
 
         Ok(())
     }
 
     fn set_state(&self, state: PlayerState) -> Result<(), Error> {
         self.cmd_tx.try_send(BackgroundCommand::SetState(state))?;
-        self.current_state.set(state);
+        if self.current_state.read() != state {
+            self.current_state.set(state);
+            self.bus.emit_event(PlayerEvent::StateChanged(state))?;
+            self.stream.publish(None);
+        }
         Ok(())
     }
 
@@ -106,24 +155,49 @@ impl PlayerBackend for SnapcastBackend {
     }
 
     fn set_volume(&self, volume: f32) -> Result<(), Error> {
-        unimplemented!()
+        self.cmd_tx.try_send(BackgroundCommand::SetVolume(volume))?;
+        self.current_volume.set(volume);
+        self.bus.emit_event(PlayerEvent::VolumeChanged(volume))?;
+        Ok(())
     }
 
     fn volume(&self) -> f32 {
-        log::warn!("unimplemented");
-        1.0
+        self.current_volume.read()
     }
 
-    fn set_blend_time(&self, duration: Duration) -> Result<(), Error> {
-        unimplemented!()
+    fn set_blend_time(&self, _duration: Duration) -> Result<(), Error> {
+        Err(PlayerError::Unsupported(PlayerCapability::Crossfade).into())
     }
 
     fn blend_time(&self) -> Duration {
-        unimplemented!()
+        Duration::default()
     }
 
     fn seek(&self, duration: Duration) -> Result<(), Error> {
-        unimplemented!()
+        self.cmd_tx.try_send(BackgroundCommand::Seek(duration))?;
+        self.bus.emit_event(PlayerEvent::Seek(duration))?;
+        self.stream.publish(Some(duration));
+        Ok(())
+    }
+
+    fn position(&self) -> Option<Duration> {
+        Some(self.position.read())
+    }
+
+    fn outputs(&self) -> Vec<PlayerOutput> {
+        self.outputs.outputs()
+    }
+
+    fn set_output_enabled(&self, output_id: &str, enabled: bool) -> Result<(), Error> {
+        self.outputs.set_enabled(output_id, enabled)
+    }
+
+    fn set_output_volume(&self, output_id: &str, volume: f32) -> Result<(), Error> {
+        self.outputs.set_volume(output_id, volume)
+    }
+
+    fn set_output_muted(&self, output_id: &str, muted: bool) -> Result<(), Error> {
+        self.outputs.set_muted(output_id, muted)
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -148,6 +222,7 @@ pub trait SnapcastPlayerBuilder {
         &mut self,
         api_url: String,
         transport: SnapcastAudioTransport,
+        control: Option<SnapcastControlScript>,
     ) -> Result<&mut Self, Error>;
 }
 
@@ -156,10 +231,11 @@ impl SnapcastPlayerBuilder for PlayerBuilder {
         &mut self,
         api_url: String,
         transport: SnapcastAudioTransport,
+        control: Option<SnapcastControlScript>,
     ) -> Result<&mut Self, Error> {
         let name = self.name.clone().expect("name should already be set"); // TODO: generate default name?
         self.with_player(move |rustic, bus| {
-            let backend = SnapcastBackend::new(rustic, bus, api_url, transport, name)?;
+            let backend = SnapcastBackend::new(rustic, bus, api_url, transport, control, name)?;
 
             Ok(Box::new(backend))
         })
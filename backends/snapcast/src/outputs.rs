@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use failure::{format_err, Error};
+use pinboard::NonEmptyPinboard;
+
+use rustic_core::player::{PlayerBus, PlayerOutput};
+use rustic_core::PlayerEvent;
+use snapcast_api::models::{Group, Notification, Server, Volume};
+use snapcast_api::SnapcastClient;
+
+/// Exposes the snapcast clients as outputs of the player
+///
+/// An output is enabled when the group of the client plays the stream of this player.
+#[derive(Clone)]
+pub(crate) struct SnapcastOutputs {
+    client: Arc<SnapcastClient>,
+    stream_id: String,
+    bus: PlayerBus,
+    status: Arc<NonEmptyPinboard<Server>>,
+}
+
+impl SnapcastOutputs {
+    pub fn new(client: Arc<SnapcastClient>, stream_id: String, bus: PlayerBus) -> Result<Self, Error> {
+        let status = smol::block_on(client.server_status())?;
+
+        Ok(SnapcastOutputs {
+            client,
+            stream_id,
+            bus,
+            status: Arc::new(NonEmptyPinboard::new(status)),
+        })
+    }
+
+    /// Keeps the outputs in sync with changes made by other snapcast clients
+    pub fn observe(&self) {
+        let outputs = self.clone();
+        let notifications = self.client.notifications();
+        smol::spawn(async move {
+            while let Ok(notification) = notifications.recv().await {
+                let mut status = outputs.status.read();
+                if status.apply(&notification) {
+                    outputs.status.set(status);
+                    outputs.emit();
+                } else if let Err(e) = outputs.refresh().await {
+                    log::error!("Fetching snapserver status failed: {:?}", e);
+                }
+            }
+            log::debug!("Stopped observing snapserver notifications");
+        })
+        .detach();
+    }
+
+    pub fn outputs(&self) -> Vec<PlayerOutput> {
+        let status = self.status.read();
+        status
+            .groups
+            .iter()
+            .flat_map(|group| {
+                let enabled = group.stream_id == self.stream_id && !group.muted;
+                group.clients.iter().map(move |client| PlayerOutput {
+                    id: client.id.clone(),
+                    name: client.display_name().to_string(),
+                    enabled,
+                    volume: client.config.volume.percent as f32 / 100f32,
+                    muted: client.config.volume.muted,
+                })
+            })
+            .collect()
+    }
+
+    pub fn set_enabled(&self, output_id: &str, enabled: bool) -> Result<(), Error> {
+        smol::block_on(async {
+            if enabled {
+                self.enable(output_id).await?;
+            } else {
+                self.disable(output_id).await?;
+            }
+            self.refresh().await
+        })
+    }
+
+    pub fn set_volume(&self, output_id: &str, volume: f32) -> Result<(), Error> {
+        let current = self.volume(output_id)?;
+        let volume = Volume {
+            percent: (volume * 100f32).round() as i64,
+            ..current
+        };
+        self.update_volume(output_id, volume)
+    }
+
+    pub fn set_muted(&self, output_id: &str, muted: bool) -> Result<(), Error> {
+        let current = self.volume(output_id)?;
+        let volume = Volume { muted, ..current };
+        self.update_volume(output_id, volume)
+    }
+
+    fn volume(&self, output_id: &str) -> Result<Volume, Error> {
+        let status = self.status.read();
+        let (_, client) = status
+            .client(output_id)
+            .ok_or_else(|| format_err!("Unknown output {}", output_id))?;
+
+        Ok(client.config.volume)
+    }
+
+    fn update_volume(&self, output_id: &str, volume: Volume) -> Result<(), Error> {
+        let volume = smol::block_on(
+            self.client
+                .set_client_volume(output_id.to_string(), volume),
+        )?;
+        // The snapserver doesn't notify the connection which made the change
+        self.apply(&Notification::ClientVolumeChanged {
+            id: output_id.to_string(),
+            volume,
+        });
+
+        Ok(())
+    }
+
+    async fn enable(&self, output_id: &str) -> Result<(), Error> {
+        let status = self.status.read();
+        let group = find_group(&status, output_id)?;
+        if group.stream_id == self.stream_id {
+            if group.muted {
+                self.client.set_group_mute(group.id.clone(), false).await?;
+            }
+            return Ok(());
+        }
+        let target = status
+            .groups
+            .iter()
+            .find(|group| group.stream_id == self.stream_id && !group.muted);
+        if let Some(target) = target {
+            let mut clients: Vec<String> = target.clients.iter().map(|c| c.id.clone()).collect();
+            clients.push(output_id.to_string());
+            self.client
+                .set_group_clients(target.id.clone(), clients)
+                .await?;
+        } else {
+            let group_id = self.isolate(group, output_id).await?;
+            self.client
+                .set_group_stream(group_id, self.stream_id.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn disable(&self, output_id: &str) -> Result<(), Error> {
+        let status = self.status.read();
+        let group = find_group(&status, output_id)?;
+        if group.stream_id != self.stream_id || group.muted {
+            return Ok(());
+        }
+        let group_id = self.isolate(group, output_id).await?;
+        let fallback = status
+            .streams
+            .iter()
+            .find(|stream| stream.id != self.stream_id);
+        match fallback {
+            Some(stream) => {
+                self.client
+                    .set_group_stream(group_id, stream.id.clone())
+                    .await?;
+            }
+            None => {
+                self.client.set_group_mute(group_id, true).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the client into its own group so other clients in the group are not affected
+    ///
+    /// Returns the id of the group the client is in afterwards
+    async fn isolate(&self, group: &Group, output_id: &str) -> Result<String, Error> {
+        if group.clients.len() == 1 {
+            return Ok(group.id.clone());
+        }
+        let clients = group
+            .clients
+            .iter()
+            .filter(|client| client.id != output_id)
+            .map(|client| client.id.clone())
+            .collect();
+        let status = self
+            .client
+            .set_group_clients(group.id.clone(), clients)
+            .await?;
+        let group = find_group(&status, output_id)?;
+
+        Ok(group.id.clone())
+    }
+
+    async fn refresh(&self) -> Result<(), Error> {
+        let status = self.client.server_status().await?;
+        self.status.set(status);
+        self.emit();
+
+        Ok(())
+    }
+
+    fn apply(&self, notification: &Notification) {
+        let mut status = self.status.read();
+        status.apply(notification);
+        self.status.set(status);
+        self.emit();
+    }
+
+    fn emit(&self) {
+        if let Err(e) = self
+            .bus
+            .emit_event(PlayerEvent::OutputsChanged(self.outputs()))
+        {
+            log::error!("Emitting outputs failed: {:?}", e);
+        }
+    }
+}
+
+fn find_group<'a>(status: &'a Server, output_id: &str) -> Result<&'a Group, Error> {
+    status
+        .client(output_id)
+        .map(|(group, _)| group)
+        .ok_or_else(|| format_err!("Unknown output {}", output_id))
+}
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use failure::Error;
+use pinboard::NonEmptyPinboard;
+use serde_json::{json, Value};
+
+use rustic_core::player::{NextReason, PlayerBus, PlayerCommand, QueueCommand};
+use rustic_core::provider::{ProviderItemType, Thumbnail, ThumbnailState};
+use rustic_core::{PlayerState, Rustic, Track};
+use snapcast_api::models::{ArtData, PlaybackStatus, StreamMetadata, StreamProperties};
+use snapcast_api::plugin::{self, PluginRequest, StreamControlCommand};
+use snapcast_api::RpcError;
+
+use crate::controlscript::ControlScriptSocket;
+
+/// Publishes the playback status of the player to the snapcast clients
+/// and routes their control commands to the player
+///
+/// Both go through the controlscript bridge, without one the stream has no metadata
+/// and can't be controlled by snapcast clients.
+#[derive(Clone)]
+pub(crate) struct SnapcastStream {
+    core: Arc<Rustic>,
+    bus: PlayerBus,
+    control: Option<ControlScriptSocket>,
+    state: Arc<NonEmptyPinboard<PlayerState>>,
+    position: Arc<NonEmptyPinboard<Duration>>,
+    track: Arc<NonEmptyPinboard<Option<Track>>>,
+}
+
+impl SnapcastStream {
+    pub fn new(
+        core: Arc<Rustic>,
+        bus: PlayerBus,
+        control: Option<ControlScriptSocket>,
+        state: Arc<NonEmptyPinboard<PlayerState>>,
+        position: Arc<NonEmptyPinboard<Duration>>,
+    ) -> Self {
+        SnapcastStream {
+            core,
+            bus,
+            control,
+            state,
+            position,
+            track: Arc::new(NonEmptyPinboard::new(None)),
+        }
+    }
+
+    pub fn set_track(&self, track: &Track) {
+        self.track.set(Some(track.clone()));
+        self.publish(None);
+    }
+
+    /// Sends the current properties of the stream in the background
+    ///
+    /// The position defaults to the one of the player
+    pub fn publish(&self, position: Option<Duration>) {
+        let control = match self.control {
+            Some(ref control) => control.clone(),
+            None => return,
+        };
+        let stream = self.clone();
+        smol::spawn(async move {
+            let properties = stream.properties(position).await;
+            control.notify(plugin::properties(&properties));
+        })
+        .detach();
+    }
+
+    /// Answers a request snapserver sent to the controlscript
+    pub async fn handle_request(&self, request: PluginRequest) -> Result<Value, RpcError> {
+        match request {
+            PluginRequest::GetProperties => {
+                let properties = self.properties(None).await;
+                serde_json::to_value(properties)
+                    .map_err(|e| RpcError::internal_error(e.to_string()))
+            }
+            PluginRequest::Control(command) => self
+                .handle_command(command)
+                .map(|_| json!("ok"))
+                .map_err(|e| RpcError::internal_error(e.to_string())),
+            PluginRequest::SetProperty { property, value } => {
+                Err(RpcError::invalid_params(json!({ property: value })))
+            }
+        }
+    }
+
+    fn handle_command(&self, command: StreamControlCommand) -> Result<(), Error> {
+        log::debug!("Received stream control command {:?}", &command);
+        match command {
+            StreamControlCommand::Play => self.set_state(PlayerState::Play),
+            StreamControlCommand::Pause => self.set_state(PlayerState::Pause),
+            StreamControlCommand::PlayPause => match self.state.read() {
+                PlayerState::Play => self.set_state(PlayerState::Pause),
+                _ => self.set_state(PlayerState::Play),
+            },
+            StreamControlCommand::Stop => self.bus.send_player_msg(PlayerCommand::Stop),
+            StreamControlCommand::Next => self
+                .bus
+                .send_queue_msg(QueueCommand::Next(NextReason::Skipped)),
+            StreamControlCommand::Previous => self.bus.send_queue_msg(QueueCommand::Prev),
+            StreamControlCommand::Seek { offset } => {
+                let position = self.position.read().as_secs_f64() + offset;
+                self.seek(position.max(0f64))
+            }
+            StreamControlCommand::SetPosition { position } if position >= 0f64 => {
+                self.seek(position)
+            }
+            command => failure::bail!("Unsupported stream control command {:?}", command),
+        }
+    }
+
+    fn set_state(&self, state: PlayerState) -> Result<(), Error> {
+        self.bus.send_player_msg(PlayerCommand::SetState(state))
+    }
+
+    fn seek(&self, position: f64) -> Result<(), Error> {
+        self.bus
+            .send_player_msg(PlayerCommand::Seek(Duration::from_secs_f64(position)))
+    }
+
+    async fn properties(&self, position: Option<Duration>) -> StreamProperties {
+        let track = self.track.read();
+        let playback_status = match self.state.read() {
+            PlayerState::Play => PlaybackStatus::Playing,
+            PlayerState::Pause => PlaybackStatus::Paused,
+            PlayerState::Stop => PlaybackStatus::Stopped,
+        };
+        let metadata = match track {
+            Some(track) => Some(self.metadata(track).await),
+            None => None,
+        };
+        let position = position.unwrap_or_else(|| self.position.read());
+
+        StreamProperties {
+            playback_status: Some(playback_status),
+            position: Some(position.as_secs_f64()),
+            can_go_next: true,
+            can_go_previous: true,
+            can_play: metadata.is_some(),
+            can_pause: true,
+            can_seek: true,
+            can_control: true,
+            metadata,
+        }
+    }
+
+    async fn metadata(&self, track: Track) -> StreamMetadata {
+        let (art_url, art_data) = match track.thumbnail {
+            ThumbnailState::Url(ref url) => (Some(url.clone()), None),
+            ThumbnailState::Data => (None, self.art_data(&track).await),
+            ThumbnailState::None => (None, None),
+        };
+
+        StreamMetadata {
+            title: Some(track.title),
+            artist: track.artist.map(|artist| vec![artist.name]),
+            album: track.album.map(|album| album.title),
+            duration: track.duration.map(|duration| duration as f64),
+            art_url,
+            art_data,
+        }
+    }
+
+    async fn art_data(&self, track: &Track) -> Option<ArtData> {
+        let item = ProviderItemType::Track(track.clone());
+        match self.core.thumbnail(&item).await {
+            Ok(Some(Thumbnail::Data { data, mime_type })) => {
+                let extension = mime_type
+                    .split('/')
+                    .last()
+                    .unwrap_or("jpeg")
+                    .to_string();
+                Some(ArtData {
+                    data: base64::encode(&data),
+                    extension,
+                })
+            }
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!("Loading cover art failed: {:?}", e);
+                None
+            }
+        }
+    }
+}
@@ -1,33 +1,36 @@
-use crate::audio_transport::SnapcastAudioTransport;
-use crate::background_job::BackgroundJob;
-use failure::Error;
 use std::fs::File;
-use std::io::Write;
-use std::net::TcpStream;
+use std::io::ErrorKind;
 use std::path::Path;
-use symphonia::core::audio::RawSampleBuffer;
-use symphonia::core::formats::FormatOptions;
+use std::time::Duration;
+
+use failure::Error;
+use symphonia::core::audio::{RawSampleBuffer, SignalSpec};
+use symphonia::core::codecs::Decoder;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekTo};
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
+use symphonia::core::units::Time;
 
-impl BackgroundJob {
-    pub fn _decode_file(&self, path: &str) -> Result<(), Error> {
-        log::trace!("Decoding file {}", &path);
-        let mut hint = Hint::new();
+/// Sample rate of the pcm stream registered at the snapserver
+pub const SAMPLE_RATE: u32 = 48000;
+/// Channel count of the pcm stream registered at the snapserver
+pub const CHANNELS: usize = 2;
+/// Bits per sample of the pcm stream registered at the snapserver
+pub const BITS_PER_SAMPLE: u32 = 16;
 
-        let mut target: Box<dyn Write> = match self.transport {
-            SnapcastAudioTransport::Pipe(ref pipe) => {
-                log::trace!("opening pipe {}", pipe);
-                let pipe = unix_named_pipe::open_write(pipe)?;
-                Box::new(pipe)
-            }
-            SnapcastAudioTransport::Tcp { ref host, ref port } => {
-                let socket = TcpStream::connect(&format!("{}:{}", host, port))?;
-                Box::new(socket)
-            }
-        };
+pub struct SymphoniaDecoder {
+    reader: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    buffer: Option<(RawSampleBuffer<f32>, u64, SignalSpec)>,
+    resampler: Option<Resampler>,
+}
 
+impl SymphoniaDecoder {
+    pub fn open(path: &str) -> Result<Self, Error> {
+        log::trace!("Decoding file {}", &path);
+        let mut hint = Hint::new();
         let path = Path::new(&path);
 
         if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
@@ -45,58 +48,278 @@ impl BackgroundJob {
 
         let probe = symphonia::default::get_probe().format(&hint, stream, &format, &metadata)?;
 
-        let mut reader = probe.format;
-        let stream = reader.default_stream().unwrap();
-        let mut decoder =
+        let reader = probe.format;
+        let stream = reader
+            .default_stream()
+            .ok_or_else(|| failure::format_err!("file has no audio stream"))?;
+        let decoder =
             symphonia::default::get_codecs().make(&stream.codec_params, &Default::default())?;
 
         log::trace!("got decoder");
-        let mut buffer = loop {
-            match decoder.decode(&reader.next_packet()?) {
-                Err(symphonia::core::errors::Error::DecodeError(err)) => {
+
+        Ok(SymphoniaDecoder {
+            reader,
+            decoder,
+            buffer: None,
+            resampler: None,
+        })
+    }
+
+    /// Decodes the next packet and returns interleaved samples in the output format.
+    ///
+    /// Returns None when the end of the track was reached.
+    pub fn next_samples(&mut self) -> Result<Option<Vec<f32>>, Error> {
+        loop {
+            let packet = match self.reader.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => {
+                    return Ok(None);
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let decoded = match self.decoder.decode(&packet) {
+                Err(SymphoniaError::DecodeError(err)) => {
                     log::warn!("decode error: {}", err);
                     continue;
                 }
-                Err(err) => {
-                    log::error!("{:?}", err);
-                    decoder.close();
-                    return Err(err.into());
-                }
-                Ok(decoded) => {
-                    log::trace!("writing packet");
-                    let spec = *decoded.spec();
-                    let duration = decoded.capacity() as symphonia::core::units::Duration;
-
-                    log::debug!(
-                        "channels: {:?}, bit rate: {}, capacity: {}",
-                        spec.channels,
-                        spec.rate,
-                        duration
-                    );
-                    let mut buffer = RawSampleBuffer::<f32>::new(duration, spec);
-                    buffer.copy_interleaved_ref(decoded);
-                    target.write_all(buffer.as_bytes())?;
-                    break buffer;
+                Err(err) => return Err(err.into()),
+                Ok(decoded) => decoded,
+            };
+            let spec = *decoded.spec();
+            let capacity = decoded.capacity() as u64;
+            let requires_new_buffer = match self.buffer {
+                Some((_, buffer_capacity, buffer_spec)) => {
+                    buffer_capacity < capacity || buffer_spec != spec
                 }
+                None => true,
+            };
+            if requires_new_buffer {
+                log::debug!(
+                    "channels: {:?}, sample rate: {}, capacity: {}",
+                    spec.channels,
+                    spec.rate,
+                    capacity
+                );
+                self.buffer = Some((RawSampleBuffer::<f32>::new(capacity, spec), capacity, spec));
             }
-        };
-        loop {
-            match decoder.decode(&reader.next_packet()?) {
-                Err(symphonia::core::errors::Error::DecodeError(err)) => {
-                    log::warn!("decode error: {}", err);
-                }
-                Err(err) => {
-                    decoder.close();
-                    return Err(err.into());
-                }
-                Ok(decoded) => {
-                    log::trace!("writing packet");
-                    buffer.copy_interleaved_ref(decoded);
-                    target.write_all(buffer.as_bytes())?;
-                }
+            let (buffer, _, _) = self.buffer.as_mut().unwrap();
+            buffer.copy_interleaved_ref(decoded);
+
+            let samples = read_samples(buffer.as_bytes());
+            let samples = map_channels(&samples, spec.channels.count());
+            let resampler = self
+                .resampler
+                .get_or_insert_with(|| Resampler::new(spec.rate, SAMPLE_RATE));
+            if resampler.from != spec.rate {
+                *resampler = Resampler::new(spec.rate, SAMPLE_RATE);
             }
+
+            return Ok(Some(resampler.process(&samples)));
         }
+    }
+
+    pub fn seek(&mut self, position: Duration) -> Result<(), Error> {
+        let time = Time {
+            seconds: position.as_secs(),
+            frac: f64::from(position.subsec_nanos()) / 1_000_000_000f64,
+        };
+        self.reader.seek(SeekTo::Time { time })?;
+        // the codec state of the previous position would corrupt the first packets after the seek
+        self.decoder.reset();
+        self.resampler = None;
 
         Ok(())
     }
 }
+
+impl Drop for SymphoniaDecoder {
+    fn drop(&mut self) {
+        self.decoder.close();
+    }
+}
+
+/// Converts interleaved f32 samples to signed 16 bit little endian pcm, applying the given volume
+pub fn to_pcm(samples: &[f32], volume: f32) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(samples.len() * 2);
+    for sample in samples {
+        let sample = (sample * volume).max(-1f32).min(1f32);
+        let sample = (sample * f32::from(i16::MAX)) as i16;
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+    bytes
+}
+
+fn read_samples(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+/// Maps the interleaved input samples to the channel layout of the output stream
+fn map_channels(samples: &[f32], channels: usize) -> Vec<f32> {
+    match channels {
+        CHANNELS => samples.to_vec(),
+        1 => samples
+            .iter()
+            .flat_map(|sample| vec![*sample; CHANNELS])
+            .collect(),
+        channels => samples
+            .chunks_exact(channels)
+            .flat_map(|frame| frame[..CHANNELS].to_vec())
+            .collect(),
+    }
+}
+
+/// Linear resampler which keeps the last frame of the previous packet to avoid gaps between packets
+struct Resampler {
+    from: u32,
+    to: u32,
+    position: f64,
+    previous: Option<[f32; CHANNELS]>,
+}
+
+impl Resampler {
+    fn new(from: u32, to: u32) -> Self {
+        Resampler {
+            from,
+            to,
+            position: 0f64,
+            previous: None,
+        }
+    }
+
+    fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        if self.from == self.to {
+            return samples.to_vec();
+        }
+        let mut frames: Vec<[f32; CHANNELS]> = Vec::with_capacity(samples.len() / CHANNELS + 1);
+        if let Some(previous) = self.previous {
+            frames.push(previous);
+        }
+        frames.extend(
+            samples
+                .chunks_exact(CHANNELS)
+                .map(|frame| [frame[0], frame[1]]),
+        );
+        if frames.len() < 2 {
+            self.previous = frames.last().cloned();
+            return Vec::new();
+        }
+
+        let step = f64::from(self.from) / f64::from(self.to);
+        let last = (frames.len() - 1) as f64;
+        let mut output = Vec::with_capacity((last / step) as usize * CHANNELS + CHANNELS);
+        while self.position < last {
+            let index = self.position.floor() as usize;
+            let fraction = (self.position - index as f64) as f32;
+            let current = frames[index];
+            let next = frames[index + 1];
+            for channel in 0..CHANNELS {
+                output.push(current[channel] + (next[channel] - current[channel]) * fraction);
+            }
+            self.position += step;
+        }
+        self.position -= last;
+        self.previous = frames.last().cloned();
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    const TEST_FILE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../provider/local/assets/bensound-ukulele.mp3"
+    );
+
+    #[test]
+    fn resampler_should_pass_through_matching_sample_rates() {
+        let mut resampler = Resampler::new(SAMPLE_RATE, SAMPLE_RATE);
+        let samples = vec![0.1, 0.2, 0.3, 0.4];
+
+        assert_eq!(resampler.process(&samples), samples);
+    }
+
+    #[test]
+    fn resampler_should_interpolate_between_frames() {
+        let mut resampler = Resampler::new(24000, 48000);
+
+        let output = resampler.process(&[0.0, 0.0, 1.0, -1.0]);
+
+        assert_eq!(output, vec![0.0, 0.0, 0.5, -0.5]);
+    }
+
+    #[test]
+    fn resampler_should_continue_with_the_last_frame_of_the_previous_packet() {
+        let mut resampler = Resampler::new(24000, 48000);
+
+        resampler.process(&[0.0, 0.0, 1.0, 1.0]);
+        let output = resampler.process(&[0.0, 0.0]);
+
+        assert_eq!(output, vec![1.0, 1.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn resampler_should_keep_the_output_length_proportional() {
+        let mut resampler = Resampler::new(44100, 48000);
+        let packet = vec![0.0; 1152 * CHANNELS];
+
+        let frames: usize = (0..100)
+            .map(|_| resampler.process(&packet).len() / CHANNELS)
+            .sum();
+
+        let expected = 1152 * 100 * 48000 / 44100;
+        assert!((frames as i64 - expected as i64).abs() <= 1);
+    }
+
+    #[test]
+    fn map_channels_should_duplicate_mono_samples() {
+        assert_eq!(map_channels(&[0.1, 0.2], 1), vec![0.1, 0.1, 0.2, 0.2]);
+    }
+
+    #[test]
+    fn map_channels_should_drop_additional_channels() {
+        let samples = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+
+        assert_eq!(map_channels(&samples, 3), vec![0.1, 0.2, 0.4, 0.5]);
+    }
+
+    #[test]
+    fn to_pcm_should_clip_samples() {
+        let pcm = to_pcm(&[2.0, -2.0], 1.0);
+
+        assert_eq!(
+            pcm,
+            [i16::MAX.to_le_bytes(), (-i16::MAX).to_le_bytes()].concat()
+        );
+    }
+
+    #[test]
+    fn decoder_should_emit_stereo_samples() {
+        let mut decoder = SymphoniaDecoder::open(TEST_FILE).unwrap();
+
+        let samples = decoder.next_samples().unwrap().unwrap();
+
+        assert!(!samples.is_empty());
+        assert_eq!(samples.len() % CHANNELS, 0);
+    }
+
+    #[test]
+    fn decoder_should_keep_decoding_after_seeking() {
+        let mut decoder = SymphoniaDecoder::open(TEST_FILE).unwrap();
+        decoder.next_samples().unwrap();
+
+        decoder.seek(Duration::from_secs(10)).unwrap();
+
+        for _ in 0..10 {
+            let samples = decoder.next_samples().unwrap().unwrap();
+            assert!(samples.iter().all(|sample| sample.is_finite()));
+        }
+    }
+}
@@ -44,7 +44,9 @@ pub fn gen_ffi_model(struct_decl: StructSignature) -> TokenStream {
 
 fn to_type(ty: &StructFieldType) -> TokenStream {
     match &ty {
-        StructFieldType::Type(ty) if ty == "String" => quote! { *const libc::c_char },
+        StructFieldType::Type(ty) if ty == "String" || ty == "ProviderTypeModel" => {
+            quote! { *const libc::c_char }
+        }
         StructFieldType::Type(ty) if ty == "bool" => quote! { bool },
         StructFieldType::Type(ty) if ty == "u64" => quote! { libc::c_ulong },
         StructFieldType::Type(ty) if ty == "f32" => quote! { libc::c_float },
@@ -65,6 +67,9 @@ fn field_conversion(name: &str, decl: &StructField) -> TokenStream {
     let name = format_ident!("{}", name);
     match &decl.ty {
         StructFieldType::Type(ty) if ty == "String" => quote! { #name: cstr!(model.#name), },
+        StructFieldType::Type(ty) if ty == "ProviderTypeModel" => {
+            quote! { #name: cstr!(model.#name.0), }
+        }
         StructFieldType::Type(ty) if ty == "bool" || ty == "u64" || ty == "f32" || ty == "f64" => {
             quote! { #name: model.#name, }
         }
@@ -19,13 +19,6 @@ pub enum FFISyncStateModel {
     Idle,
 }
 
-#[derive(Debug)]
-#[repr(C)]
-// TODO: add fields
-pub enum FFIProviderTypeModel {
-    Idle,
-}
-
 #[derive(Debug)]
 #[repr(C)]
 pub enum FFIRepeatModeModel {
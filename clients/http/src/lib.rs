@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::marker::PhantomData;
 
 use async_trait::async_trait;
@@ -160,6 +161,16 @@ where
         self.post(&url, ()).await?.no_content()
     }
 
+    async fn invoke_extension_action(&self, id: &str, action: &str) -> Result<()> {
+        let url = format!("/api/extensions/{}/actions/{}", id, action);
+        self.post(&url, ()).await?.no_content()
+    }
+
+    async fn update_extension_config(&self, id: &str, config: HashMap<String, Option<ExtensionConfigValueModel>>) -> Result<()> {
+        let url = format!("/api/extensions/{}/config", id);
+        self.put(&url, config).await?.no_content()
+    }
+
     async fn get_extensions(&self) -> Result<Vec<ExtensionModel>> {
         let res = self.get("/api/extensions").await?;
 
@@ -173,7 +184,11 @@ where
         Ok(res)
     }
 
-    async fn get_thumbnail(&self, _cursor: Cursor) -> Result<Option<CoverArtModel>> {
+    async fn get_thumbnail(
+        &self,
+        _cursor: Cursor,
+        _size: Option<u32>,
+    ) -> Result<Option<CoverArtModel>> {
         unimplemented!()
     }
 }
@@ -223,6 +238,24 @@ where
             }
         }
     }
+
+    async fn logout_provider(
+        &self,
+        provider: ProviderTypeModel,
+        purge_library: bool,
+    ) -> Result<()> {
+        let url = format!(
+            "/api/providers/{}/auth?purge_library={}",
+            provider, purge_library
+        );
+        self.delete(&url).await?;
+
+        Ok(())
+    }
+
+    fn observe_providers(&self) -> BoxStream<'static, ProviderStateEventModel> {
+        unimplemented!("requires socket api")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -230,6 +263,13 @@ struct ProviderFilterQuery {
     providers: Option<Vec<ProviderTypeModel>>,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct HistoryFilterQuery {
+    since: Option<u64>,
+    until: Option<u64>,
+    limit: Option<usize>,
+}
+
 impl From<Option<Vec<ProviderTypeModel>>> for ProviderFilterQuery {
     fn from(providers: Option<Vec<ProviderTypeModel>>) -> Self {
         ProviderFilterQuery { providers }
@@ -338,11 +378,99 @@ where
         Ok(res)
     }
 
+    async fn pin_offline(&self, cursor: Cursor) -> Result<()> {
+        let url = <RusticHttpClient<T, TRes>>::url_for_item(cursor);
+        self.post(&format!("{}/offline", url), ()).await?.no_content()?;
+
+        Ok(())
+    }
+
+    async fn unpin_offline(&self, cursor: Cursor) -> Result<()> {
+        let url = <RusticHttpClient<T, TRes>>::url_for_item(cursor);
+        self.delete(&format!("{}/offline", url)).await?;
+
+        Ok(())
+    }
+
+    async fn get_offline_mode(&self) -> Result<bool> {
+        let res = self.get("/api/library/offline").await?;
+
+        Ok(res)
+    }
+
+    async fn set_offline_mode(&self, enabled: bool) -> Result<()> {
+        self.put("/api/library/offline", enabled).await?.no_content()?;
+
+        Ok(())
+    }
+
+    fn observe_offline(&self) -> BoxStream<'static, OfflineEventModel> {
+        unimplemented!("requires socket api")
+    }
+
+    async fn update_metadata(&self, cursor: Cursor, update: MetadataUpdateModel) -> Result<()> {
+        let url = <RusticHttpClient<T, TRes>>::url_for_item(cursor);
+        self.put(&format!("{}/metadata", url), update).await?.no_content()?;
+
+        Ok(())
+    }
+
+    async fn set_rating(&self, cursor: &str, rating: RatingModel) -> Result<()> {
+        let url = format!("/api/library/tracks/{}/rating", cursor);
+        self.put(&url, rating).await?.no_content()?;
+
+        Ok(())
+    }
+
     fn sync_state(&self) -> BoxStream<'static, SyncStateModel> {
         unimplemented!("requires socket api")
     }
 
-    fn observe_library(&self) -> BoxStream<'static, LibraryEventModel> {
+    async fn get_library_changes(&self, since: u64) -> Result<LibraryChangesModel> {
+        let url = format!("/api/library/changes?since={}", since);
+        let res = self.get(&url).await?;
+
+        Ok(res)
+    }
+
+    async fn get_history(
+        &self,
+        since: Option<u64>,
+        limit: Option<usize>,
+    ) -> Result<Vec<PlayModel>> {
+        let query = HistoryFilterQuery {
+            since,
+            limit,
+            ..HistoryFilterQuery::default()
+        };
+        let query = serde_qs::to_string(&query)
+            .map_err(|e| format_err!("Query String serialization failed: {:?}", e))?;
+        let url = format!("/api/library/history?{}", &query);
+        let res = self.get(&url).await?;
+
+        Ok(res)
+    }
+
+    async fn get_play_stats(
+        &self,
+        since: Option<u64>,
+        until: Option<u64>,
+        limit: Option<usize>,
+    ) -> Result<PlayStatsModel> {
+        let query = HistoryFilterQuery {
+            since,
+            until,
+            limit,
+        };
+        let query = serde_qs::to_string(&query)
+            .map_err(|e| format_err!("Query String serialization failed: {:?}", e))?;
+        let url = format!("/api/library/stats?{}", &query);
+        let res = self.get(&url).await?;
+
+        Ok(res)
+    }
+
+    fn observe_library(&self) -> BoxStream<'static, LibraryChangeModel> {
         unimplemented!("requires socket api")
     }
 }
@@ -574,6 +702,80 @@ where
         Ok(())
     }
 
+    async fn player_set_output_enabled(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        enabled: bool,
+    ) -> Result<()> {
+        let url = match player_id {
+            Some(id) => format!("/api/players/{}/outputs/{}/enabled", id, output_id),
+            None => format!("/api/player/outputs/{}/enabled", output_id),
+        };
+        self.post(&url, enabled).await?;
+
+        Ok(())
+    }
+
+    async fn player_set_output_volume(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        volume: f32,
+    ) -> Result<()> {
+        let url = match player_id {
+            Some(id) => format!("/api/players/{}/outputs/{}/volume", id, output_id),
+            None => format!("/api/player/outputs/{}/volume", output_id),
+        };
+        self.post(&url, volume).await?;
+
+        Ok(())
+    }
+
+    async fn player_set_output_muted(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        muted: bool,
+    ) -> Result<()> {
+        let url = match player_id {
+            Some(id) => format!("/api/players/{}/outputs/{}/muted", id, output_id),
+            None => format!("/api/player/outputs/{}/muted", output_id),
+        };
+        self.post(&url, muted).await?;
+
+        Ok(())
+    }
+
+    async fn transfer_playback(
+        &self,
+        from_player_id: &str,
+        to_player_id: &str,
+        keep_playing: bool,
+    ) -> Result<()> {
+        let url = format!(
+            "/api/players/{}/transfer/{}?keep_playing={}",
+            from_player_id, to_player_id, keep_playing
+        );
+        self.post(&url, ()).await?;
+
+        Ok(())
+    }
+
+    async fn player_set_device(
+        &self,
+        player_id: Option<&str>,
+        device_id: Option<&str>,
+    ) -> Result<()> {
+        let url = match player_id {
+            Some(id) => format!("/api/players/{}/device", id),
+            None => "/api/player/device".to_string(),
+        };
+        self.post(&url, device_id).await?;
+
+        Ok(())
+    }
+
     fn observe_player(&self, _player_id: Option<&str>) -> BoxStream<'static, PlayerEventModel> {
         unimplemented!("requires socket api")
     }
@@ -590,7 +792,7 @@ where
         username: String,
         password: String,
     ) -> Result<()> {
-        let url = format!("/api/providers/{}/auth", serde_json::to_string(&provider)?);
+        let url = format!("/api/providers/{}/auth", provider);
         let model = ProviderAuthModel::UserPass { username, password };
 
         self.post(&url, model).await?;
@@ -607,7 +809,7 @@ where
             .map_err(|e| format_err!("Query String serialization failed: {:?}", e))?;
         let url = format!(
             "/api/providers/{}/auth/redirect?{}",
-            serde_json::to_string(&provider)?,
+            provider,
             query
         );
 
@@ -13,6 +13,8 @@ export interface RusticApiClient {
     getExtensions(): Promise<ExtensionModel[]>;
     enableExtension(id: string): Promise<void>;
     disableExtension(id: string): Promise<void>;
+    invokeExtensionAction(id: string, action: string): Promise<void>;
+    updateExtensionConfig(id: string, config: { [key: string]: ExtensionConfigValueModel | null }): Promise<void>;
     openShareUrl(url: string): Promise<OpenResultModel>;
     getProviders(): Promise<ProviderModel[]>;
     getAvailableProviders(): Promise<AvailableProviderModel[]>;
@@ -32,6 +34,20 @@ export interface RusticApiClient {
     removeAlbumFromLibrary(cursor: string): Promise<void>;
     removeArtistFromLibrary(cursor: string): Promise<void>;
     removePlaylistFromLibrary(cursor: string): Promise<void>;
+    pinTrackOffline(cursor: string): Promise<void>;
+    pinAlbumOffline(cursor: string): Promise<void>;
+    pinPlaylistOffline(cursor: string): Promise<void>;
+    unpinTrackOffline(cursor: string): Promise<void>;
+    unpinAlbumOffline(cursor: string): Promise<void>;
+    unpinPlaylistOffline(cursor: string): Promise<void>;
+    getOfflineMode(): Promise<boolean>;
+    setOfflineMode(enabled: boolean): Promise<void>;
+    getLibraryChanges(since: number): Promise<LibraryChangesModel>;
+    getHistory(since?: number, limit?: number): Promise<PlayModel[]>;
+    getPlayStats(since?: number, until?: number, limit?: number): Promise<PlayStatsModel>;
+    updateTrackMetadata(cursor: string, update: MetadataUpdateModel): Promise<void>;
+    updateAlbumMetadata(cursor: string, update: MetadataUpdateModel): Promise<void>;
+    setRating(cursor: string, rating: RatingModel): Promise<void>;
     addPlaylist(name: String): Promise<PlaylistModel>;
     removePlaylist(cursor: String): Promise<void>;
     addTrackToPlaylist(playlist: String, track: String): Promise<void>;
@@ -51,4 +67,9 @@ export interface RusticApiClient {
     playerControlPlay(player_id?: string): Promise<void>;
     playerControlPause(player_id?: string): Promise<void>;
     playerSetVolume(player_id: string | undefined, volume: number): Promise<void>;
+    playerSetOutputEnabled(player_id: string | undefined, output_id: string, enabled: boolean): Promise<void>;
+    playerSetOutputVolume(player_id: string | undefined, output_id: string, volume: number): Promise<void>;
+    playerSetOutputMuted(player_id: string | undefined, output_id: string, muted: boolean): Promise<void>;
+    transferPlayback(from_player_id: string, to_player_id: string, keep_playing: boolean): Promise<void>;
+    playerSetDevice(player_id?: string, device_id?: string): Promise<void>;
 }"#;
@@ -42,6 +42,17 @@ pub async fn disable_extension(id: String) -> ApiResult {
     execute(CLIENT.disable_extension(&id)).await
 }
 
+#[wasm_bindgen(js_name = "invokeExtensionAction")]
+pub async fn invoke_extension_action(id: String, action: String) -> ApiResult {
+    execute(CLIENT.invoke_extension_action(&id, &action)).await
+}
+
+#[wasm_bindgen(js_name = "updateExtensionConfig")]
+pub async fn update_extension_config(id: String, config: JsValue) -> ApiResult {
+    let config = config.into_serde().map_err(|e| format!("{:?}", e))?;
+    execute(CLIENT.update_extension_config(&id, config)).await
+}
+
 #[wasm_bindgen(js_name = "openShareUrl")]
 pub async fn open_share_url(url: String) -> ApiResult {
     execute(CLIENT.open_share_url(&url)).await
@@ -141,6 +152,92 @@ pub async fn remove_playlist_from_library(cursor: String) -> ApiResult {
     execute(CLIENT.remove_from_library(Cursor::Playlist(cursor))).await
 }
 
+#[wasm_bindgen(js_name = "pinTrackOffline")]
+pub async fn pin_track_offline(cursor: String) -> ApiResult {
+    execute(CLIENT.pin_offline(Cursor::Track(cursor))).await
+}
+
+#[wasm_bindgen(js_name = "pinAlbumOffline")]
+pub async fn pin_album_offline(cursor: String) -> ApiResult {
+    execute(CLIENT.pin_offline(Cursor::Album(cursor))).await
+}
+
+#[wasm_bindgen(js_name = "pinPlaylistOffline")]
+pub async fn pin_playlist_offline(cursor: String) -> ApiResult {
+    execute(CLIENT.pin_offline(Cursor::Playlist(cursor))).await
+}
+
+#[wasm_bindgen(js_name = "unpinTrackOffline")]
+pub async fn unpin_track_offline(cursor: String) -> ApiResult {
+    execute(CLIENT.unpin_offline(Cursor::Track(cursor))).await
+}
+
+#[wasm_bindgen(js_name = "unpinAlbumOffline")]
+pub async fn unpin_album_offline(cursor: String) -> ApiResult {
+    execute(CLIENT.unpin_offline(Cursor::Album(cursor))).await
+}
+
+#[wasm_bindgen(js_name = "unpinPlaylistOffline")]
+pub async fn unpin_playlist_offline(cursor: String) -> ApiResult {
+    execute(CLIENT.unpin_offline(Cursor::Playlist(cursor))).await
+}
+
+#[wasm_bindgen(js_name = "getOfflineMode")]
+pub async fn get_offline_mode() -> ApiResult {
+    execute(CLIENT.get_offline_mode()).await
+}
+
+#[wasm_bindgen(js_name = "setOfflineMode")]
+pub async fn set_offline_mode(enabled: bool) -> ApiResult {
+    execute(CLIENT.set_offline_mode(enabled)).await
+}
+
+#[wasm_bindgen(js_name = "getLibraryChanges")]
+pub async fn get_library_changes(since: u32) -> ApiResult {
+    execute(CLIENT.get_library_changes(since as u64)).await
+}
+
+#[wasm_bindgen(js_name = "getHistory")]
+pub async fn get_history(since: Option<u32>, limit: Option<u32>) -> ApiResult {
+    execute(CLIENT.get_history(
+        since.map(|since| since as u64),
+        limit.map(|limit| limit as usize),
+    ))
+    .await
+}
+
+#[wasm_bindgen(js_name = "getPlayStats")]
+pub async fn get_play_stats(
+    since: Option<u32>,
+    until: Option<u32>,
+    limit: Option<u32>,
+) -> ApiResult {
+    execute(CLIENT.get_play_stats(
+        since.map(|since| since as u64),
+        until.map(|until| until as u64),
+        limit.map(|limit| limit as usize),
+    ))
+    .await
+}
+
+#[wasm_bindgen(js_name = "updateTrackMetadata")]
+pub async fn update_track_metadata(cursor: String, update: JsValue) -> ApiResult {
+    let update = update.into_serde().map_err(|e| format!("{:?}", e))?;
+    execute(CLIENT.update_metadata(Cursor::Track(cursor), update)).await
+}
+
+#[wasm_bindgen(js_name = "updateAlbumMetadata")]
+pub async fn update_album_metadata(cursor: String, update: JsValue) -> ApiResult {
+    let update = update.into_serde().map_err(|e| format!("{:?}", e))?;
+    execute(CLIENT.update_metadata(Cursor::Album(cursor), update)).await
+}
+
+#[wasm_bindgen(js_name = "setRating")]
+pub async fn set_rating(cursor: String, rating: JsValue) -> ApiResult {
+    let rating = rating.into_serde().map_err(|e| format!("{:?}", e))?;
+    execute(CLIENT.set_rating(&cursor, rating)).await
+}
+
 #[wasm_bindgen(js_name = "searchLibrary")]
 pub async fn search_library(query: String) -> ApiResult {
     execute(CLIENT.search_library(&query)).await
@@ -250,3 +347,44 @@ pub async fn player_control_pause(player_id: Option<String>) -> ApiResult {
 pub async fn player_set_volume(player_id: Option<String>, volume: f32) -> ApiResult {
     execute(CLIENT.player_set_volume(player_id.as_deref(), volume)).await
 }
+
+#[wasm_bindgen(js_name = "transferPlayback")]
+pub async fn transfer_playback(
+    from_player_id: String,
+    to_player_id: String,
+    keep_playing: bool,
+) -> ApiResult {
+    execute(CLIENT.transfer_playback(&from_player_id, &to_player_id, keep_playing)).await
+}
+
+#[wasm_bindgen(js_name = "playerSetOutputEnabled")]
+pub async fn player_set_output_enabled(
+    player_id: Option<String>,
+    output_id: String,
+    enabled: bool,
+) -> ApiResult {
+    execute(CLIENT.player_set_output_enabled(player_id.as_deref(), &output_id, enabled)).await
+}
+
+#[wasm_bindgen(js_name = "playerSetOutputVolume")]
+pub async fn player_set_output_volume(
+    player_id: Option<String>,
+    output_id: String,
+    volume: f32,
+) -> ApiResult {
+    execute(CLIENT.player_set_output_volume(player_id.as_deref(), &output_id, volume)).await
+}
+
+#[wasm_bindgen(js_name = "playerSetOutputMuted")]
+pub async fn player_set_output_muted(
+    player_id: Option<String>,
+    output_id: String,
+    muted: bool,
+) -> ApiResult {
+    execute(CLIENT.player_set_output_muted(player_id.as_deref(), &output_id, muted)).await
+}
+
+#[wasm_bindgen(js_name = "playerSetDevice")]
+pub async fn player_set_device(player_id: Option<String>, device_id: Option<String>) -> ApiResult {
+    execute(CLIENT.player_set_device(player_id.as_deref(), device_id.as_deref())).await
+}
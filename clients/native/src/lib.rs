@@ -1,6 +1,6 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use futures::future;
 use log::{debug, error, trace};
 
 use async_trait::async_trait;
@@ -8,8 +8,10 @@ use rustic_api::client::*;
 use rustic_api::cursor::Cursor;
 use rustic_api::models::*;
 use rustic_core::provider::{InternalUri, ProviderItem, ProviderItemType};
-use rustic_core::{Album, Artist, CredentialStore, Playlist, Provider, Rustic, SingleQuery, Track};
-use rustic_extension_api::{ExtensionApi, ExtensionManager};
+use rustic_core::{
+    Album, Artist, CredentialStore, Playlist, Provider, ProviderId, Rustic, SingleQuery, Track,
+};
+use rustic_extension_api::{ExtensionApi, ExtensionConfigValue, ExtensionManager};
 use std::convert::TryInto;
 
 mod library;
@@ -29,12 +31,12 @@ impl RusticNativeClient {
     pub fn new(
         app: Arc<Rustic>,
         extensions: ExtensionManager,
-        cred_store: Box<dyn CredentialStore>,
+        cred_store: Arc<Box<dyn CredentialStore>>,
     ) -> RusticNativeClient {
         RusticNativeClient {
             app,
             extensions,
-            credential_store: Arc::new(cred_store),
+            credential_store: cred_store,
         }
     }
 
@@ -90,16 +92,19 @@ impl RusticApiClient for RusticNativeClient {
         query: &str,
         provider_filter: Option<Vec<ProviderTypeModel>>,
     ) -> Result<SearchResults> {
-        let providers = &self.app.providers;
+        let providers = self.app.providers();
         trace!("search {}", query);
 
         let sw = stopwatch::Stopwatch::start_new();
-        let providers: Vec<&Provider> = providers
-            .iter()
+        let providers: Vec<Provider> = providers
+            .into_iter()
             .filter(|provider| {
                 if let Some(ref provider_filter) = provider_filter {
-                    let p = provider.provider_type.into();
-                    provider_filter.contains(&p)
+                    provider_filter
+                        .iter()
+                        .cloned()
+                        .map(ProviderId::from)
+                        .any(|filter| filter.includes(&provider.id))
                 } else {
                     true
                 }
@@ -108,65 +113,52 @@ impl RusticApiClient for RusticNativeClient {
         let mut results: Vec<ProviderItem> = Vec::new();
         // TODO: run in parallel
         for provider in providers {
-            let provider = provider.get().await;
-            if !provider.state().is_authenticated() {
+            if !provider.get().await.state().is_authenticated() {
                 continue;
             }
             match provider.search(query.to_string()).await {
                 Ok(mut result) => {
                     results.append(&mut result);
                 }
-                Err(e) => error!(
-                    "Searching failed for provider {:?}: {:?}",
-                    provider.provider(),
-                    e
-                ),
+                Err(e) => error!("Searching failed for provider {}: {:?}", provider.id, e),
             }
         }
         debug!("Searching took {}ms", sw.elapsed_ms());
 
-        let tracks = future::try_join_all(
-            results
-                .iter()
-                .cloned()
-                .filter(|result| result.is_track())
-                .map(Track::from)
-                .map(|track| self.extensions.resolve_track(track)),
-        )
-        .await?;
+        let tracks = results
+            .iter()
+            .cloned()
+            .filter(|result| result.is_track())
+            .map(Track::from)
+            .collect();
+        let tracks = self.extensions.resolve_tracks(tracks).await?;
         let tracks: Vec<_> = tracks.into_iter().map(TrackModel::from).collect();
 
-        let albums = future::try_join_all(
-            results
-                .iter()
-                .cloned()
-                .filter(|result| result.is_album())
-                .map(Album::from)
-                .map(|album| self.extensions.resolve_album(album)),
-        )
-        .await?;
+        let albums = results
+            .iter()
+            .cloned()
+            .filter(|result| result.is_album())
+            .map(Album::from)
+            .collect();
+        let albums = self.extensions.resolve_albums(albums).await?;
         let albums: Vec<_> = albums.into_iter().map(AlbumModel::from).collect();
 
-        let artists = future::try_join_all(
-            results
-                .iter()
-                .cloned()
-                .filter(|result| result.is_artist())
-                .map(Artist::from)
-                .map(|artist| self.extensions.resolve_artist(artist)),
-        )
-        .await?;
+        let artists = results
+            .iter()
+            .cloned()
+            .filter(|result| result.is_artist())
+            .map(Artist::from)
+            .collect();
+        let artists = self.extensions.resolve_artists(artists).await?;
         let artists: Vec<_> = artists.into_iter().map(ArtistModel::from).collect();
 
-        let playlists = future::try_join_all(
-            results
-                .iter()
-                .cloned()
-                .filter(|result| result.is_playlist())
-                .map(Playlist::from)
-                .map(|playlist| self.extensions.resolve_playlist(playlist)),
-        )
-        .await?;
+        let playlists = results
+            .iter()
+            .cloned()
+            .filter(|result| result.is_playlist())
+            .map(Playlist::from)
+            .collect();
+        let playlists = self.extensions.resolve_playlists(playlists).await?;
         let playlists: Vec<_> = playlists.into_iter().map(PlaylistModel::from).collect();
 
         Ok(SearchResults {
@@ -195,10 +187,26 @@ impl RusticApiClient for RusticNativeClient {
         self.extensions.disable_extension(id).await
     }
 
+    async fn invoke_extension_action(&self, id: &str, action: &str) -> Result<()> {
+        self.extensions.invoke_extension_action(id, action).await
+    }
+
+    async fn update_extension_config(
+        &self,
+        id: &str,
+        config: HashMap<String, Option<ExtensionConfigValueModel>>,
+    ) -> Result<()> {
+        let config = config
+            .into_iter()
+            .map(|(key, value)| (key, value.map(ExtensionConfigValue::from)))
+            .collect();
+        self.extensions.update_extension_config(id, config).await
+    }
+
     async fn get_extensions(&self) -> Result<Vec<ExtensionModel>> {
         let extensions = self
             .extensions
-            .get_extensions_with_controls()
+            .get_extension_details()
             .await?
             .into_iter()
             .map(ExtensionModel::from)
@@ -214,7 +222,11 @@ impl RusticApiClient for RusticNativeClient {
         Ok(result)
     }
 
-    async fn get_thumbnail(&self, cursor: Cursor) -> Result<Option<CoverArtModel>> {
+    async fn get_thumbnail(
+        &self,
+        cursor: Cursor,
+        size: Option<u32>,
+    ) -> Result<Option<CoverArtModel>> {
         let uri = cursor.try_into()?;
         let provider_item = match uri {
             InternalUri::Track(uri) => {
@@ -233,7 +245,7 @@ impl RusticApiClient for RusticNativeClient {
         };
 
         if let Some(item) = provider_item {
-            let cover_art = self.app.thumbnail(&item).await?;
+            let cover_art = self.app.cover_art(&item, size).await?;
             let cover_art = cover_art.map(CoverArtModel::from);
 
             Ok(cover_art)
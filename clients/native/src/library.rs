@@ -1,7 +1,6 @@
 use std::convert::TryInto;
 
 use async_trait::async_trait;
-use futures::future;
 use futures::stream::{BoxStream, StreamExt};
 use itertools::Itertools;
 use log::debug;
@@ -9,12 +8,16 @@ use log::debug;
 use rustic_api::client::{LibraryApiClient, Result};
 use rustic_api::cursor::{from_cursor, Cursor};
 use rustic_api::models::*;
+use rustic_core::library::HistoryQuery;
+use rustic_core::offline;
 use rustic_core::provider::InternalUri;
-use rustic_core::{MultiQuery, ProviderType, QueryJoins, SingleQuery};
+use rustic_core::{MultiQuery, ProviderId, QueryJoins, SingleQuery};
 use rustic_extension_api::ExtensionApi;
 
 use crate::RusticNativeClient;
 
+const DEFAULT_STATS_LIMIT: usize = 10;
+
 #[async_trait]
 impl LibraryApiClient for RusticNativeClient {
     async fn get_albums(
@@ -27,18 +30,16 @@ impl LibraryApiClient for RusticNativeClient {
         let providers = providers
             .unwrap_or_default()
             .into_iter()
-            .map(ProviderType::from)
+            .map(ProviderId::from)
             .collect();
         query.with_providers(providers);
-        let albums = self.app.library.query_albums(query)?;
+        let mut albums = self.app.library.query_albums(query)?;
+        if self.app.offline.is_enabled() {
+            albums.retain(|album| offline::is_album_available(&self.app, album));
+        }
         debug!("Fetching albums took {}ms", sw.elapsed_ms());
 
-        let albums = future::try_join_all(
-            albums
-                .into_iter()
-                .map(|album| self.extensions.resolve_album(album)),
-        )
-        .await?;
+        let albums = self.extensions.resolve_albums(albums).await?;
 
         let albums = albums.into_iter().map(AlbumModel::from).collect();
 
@@ -71,12 +72,7 @@ impl LibraryApiClient for RusticNativeClient {
         let artists = self.app.library.query_artists(MultiQuery::new())?;
         debug!("Fetching artists took {}ms", sw.elapsed_ms());
 
-        let artists = future::try_join_all(
-            artists
-                .into_iter()
-                .map(|artist| self.extensions.resolve_artist(artist)),
-        )
-        .await?;
+        let artists = self.extensions.resolve_artists(artists).await?;
 
         let artists = artists.into_iter().map(ArtistModel::from).collect();
         Ok(artists)
@@ -113,17 +109,15 @@ impl LibraryApiClient for RusticNativeClient {
         let providers = providers
             .unwrap_or_default()
             .into_iter()
-            .map(ProviderType::from)
+            .map(ProviderId::from)
             .collect();
         query.with_providers(providers);
-        let playlists = self.app.library.query_playlists(query)?;
+        let mut playlists = self.app.library.query_playlists(query)?;
+        if self.app.offline.is_enabled() {
+            playlists.retain(|playlist| offline::is_playlist_available(&self.app, playlist));
+        }
         debug!("Fetching playlists took {}ms", sw.elapsed_ms());
-        let playlists = future::try_join_all(
-            playlists
-                .into_iter()
-                .map(|playlist| self.extensions.resolve_playlist(playlist)),
-        )
-        .await?;
+        let playlists = self.extensions.resolve_playlists(playlists).await?;
         let playlists = playlists
             .into_iter()
             .map(PlaylistModel::from)
@@ -159,18 +153,24 @@ impl LibraryApiClient for RusticNativeClient {
         let providers = providers
             .unwrap_or_default()
             .into_iter()
-            .map(ProviderType::from)
+            .map(ProviderId::from)
             .collect();
         query.with_providers(providers);
-        let tracks = self.app.library.query_tracks(query)?;
+        let mut tracks = self.app.library.query_tracks(query)?;
+        if self.app.offline.is_enabled() {
+            tracks.retain(|track| offline::is_track_available(&self.app, track));
+        }
         debug!("Fetching tracks took {}ms", sw.elapsed_ms());
-        let tracks = future::try_join_all(
-            tracks
-                .into_iter()
-                .map(|track| self.extensions.resolve_track(track)),
-        )
-        .await?;
-        let tracks = tracks.into_iter().map(TrackModel::from).collect();
+        let tracks = self.extensions.resolve_tracks(tracks).await?;
+        let uris: Vec<String> = tracks.iter().map(|track| track.uri.clone()).collect();
+        let plays = self.app.history.track_plays(&uris)?;
+        let tracks = tracks
+            .into_iter()
+            .map(|track| {
+                let track_plays = plays.get(&track.uri);
+                TrackModel::from(track).with_plays(track_plays)
+            })
+            .collect();
         Ok(tracks)
     }
 
@@ -183,10 +183,18 @@ impl LibraryApiClient for RusticNativeClient {
         for cursor in cursors {
             let uri = from_cursor(cursor)?;
             if let Some(track) = self.query_track(uri.into()).await? {
-                let track = self.extensions.resolve_track(track).await?;
-                tracks.push(track.into());
+                tracks.push(self.extensions.resolve_track(track).await?);
             }
         }
+        let uris: Vec<String> = tracks.iter().map(|track| track.uri.clone()).collect();
+        let plays = self.app.history.track_plays(&uris)?;
+        let tracks: Vec<TrackModel> = tracks
+            .into_iter()
+            .map(|track| {
+                let track_plays = plays.get(&track.uri);
+                TrackModel::from(track).with_plays(track_plays)
+            })
+            .collect();
         let track: Option<TrackCollection> = Aggregate::aggregate_single(tracks);
         debug!("Fetching track took {}ms", sw.elapsed_ms());
 
@@ -218,34 +226,10 @@ impl LibraryApiClient for RusticNativeClient {
     async fn search_library(&self, query: &str) -> Result<SearchResults> {
         let results = self.app.library.search(query.into())?;
 
-        let tracks = future::try_join_all(
-            results
-                .tracks
-                .into_iter()
-                .map(|track| self.extensions.resolve_track(track)),
-        )
-        .await?;
-        let albums = future::try_join_all(
-            results
-                .albums
-                .into_iter()
-                .map(|album| self.extensions.resolve_album(album)),
-        )
-        .await?;
-        let artists = future::try_join_all(
-            results
-                .artists
-                .into_iter()
-                .map(|artist| self.extensions.resolve_artist(artist)),
-        )
-        .await?;
-        let playlists = future::try_join_all(
-            results
-                .playlists
-                .into_iter()
-                .map(|playlist| self.extensions.resolve_playlist(playlist)),
-        )
-        .await?;
+        let tracks = self.extensions.resolve_tracks(results.tracks).await?;
+        let albums = self.extensions.resolve_albums(results.albums).await?;
+        let artists = self.extensions.resolve_artists(results.artists).await?;
+        let playlists = self.extensions.resolve_playlists(results.playlists).await?;
 
         Ok(SearchResults {
             tracks: tracks.into_iter().map(TrackModel::from).collect(),
@@ -255,6 +239,52 @@ impl LibraryApiClient for RusticNativeClient {
         })
     }
 
+    async fn pin_offline(&self, cursor: Cursor) -> Result<()> {
+        offline::pin(&self.app, cursor.try_into()?).await?;
+
+        Ok(())
+    }
+
+    async fn unpin_offline(&self, cursor: Cursor) -> Result<()> {
+        offline::unpin(&self.app, cursor.try_into()?)?;
+
+        Ok(())
+    }
+
+    async fn get_offline_mode(&self) -> Result<bool> {
+        Ok(self.app.offline.is_enabled())
+    }
+
+    async fn set_offline_mode(&self, enabled: bool) -> Result<()> {
+        self.app.offline.set_enabled(enabled);
+
+        Ok(())
+    }
+
+    fn observe_offline(&self) -> BoxStream<'static, OfflineEventModel> {
+        self.app
+            .offline
+            .events
+            .stream()
+            .map(OfflineEventModel::from)
+            .boxed()
+    }
+
+    async fn update_metadata(&self, cursor: Cursor, update: MetadataUpdateModel) -> Result<()> {
+        self.app
+            .update_metadata(cursor.try_into()?, update.try_into()?)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_rating(&self, cursor: &str, rating: RatingModel) -> Result<()> {
+        let uri = from_cursor(cursor)?;
+        self.app.set_rating(uri, rating.into()).await?;
+
+        Ok(())
+    }
+
     fn sync_state(&self) -> BoxStream<'static, SyncStateModel> {
         self.app
             .sync
@@ -264,11 +294,55 @@ impl LibraryApiClient for RusticNativeClient {
             .boxed()
     }
 
-    fn observe_library(&self) -> BoxStream<'static, LibraryEventModel> {
+    async fn get_library_changes(&self, since: u64) -> Result<LibraryChangesModel> {
+        let revision = self.app.library.revision()?;
+        let changes = self
+            .app
+            .library
+            .changes_since(since)?
+            .into_iter()
+            .map(LibraryChangeModel::from)
+            .collect();
+
+        Ok(LibraryChangesModel { revision, changes })
+    }
+
+    async fn get_history(
+        &self,
+        since: Option<u64>,
+        limit: Option<usize>,
+    ) -> Result<Vec<PlayModel>> {
+        let query = HistoryQuery::new().since(since).limit(limit);
+        let plays = self
+            .app
+            .history
+            .query(query)?
+            .into_iter()
+            .map(PlayModel::from)
+            .collect();
+
+        Ok(plays)
+    }
+
+    async fn get_play_stats(
+        &self,
+        since: Option<u64>,
+        until: Option<u64>,
+        limit: Option<usize>,
+    ) -> Result<PlayStatsModel> {
+        let stats = self
+            .app
+            .history
+            .stats(since, until, limit.unwrap_or(DEFAULT_STATS_LIMIT))?;
+
+        Ok(stats.into())
+    }
+
+    fn observe_library(&self) -> BoxStream<'static, LibraryChangeModel> {
         self.app
             .library
             .observe()
-            .map(LibraryEventModel::from)
+            .map(LibraryChangeModel::from)
             .boxed()
     }
 }
@@ -91,6 +91,66 @@ impl PlayerApiClient for RusticNativeClient {
         Ok(())
     }
 
+    async fn player_set_output_enabled(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        enabled: bool,
+    ) -> Result<()> {
+        let player = self.get_player_or_default(player_id)?;
+        player.backend.set_output_enabled(output_id, enabled)?;
+
+        Ok(())
+    }
+
+    async fn player_set_output_volume(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        volume: f32,
+    ) -> Result<()> {
+        let player = self.get_player_or_default(player_id)?;
+        player
+            .backend
+            .set_output_volume(output_id, volume.max(0f32).min(1f32))?;
+
+        Ok(())
+    }
+
+    async fn player_set_output_muted(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        muted: bool,
+    ) -> Result<()> {
+        let player = self.get_player_or_default(player_id)?;
+        player.backend.set_output_muted(output_id, muted)?;
+
+        Ok(())
+    }
+
+    async fn transfer_playback(
+        &self,
+        from_player_id: &str,
+        to_player_id: &str,
+        keep_playing: bool,
+    ) -> Result<()> {
+        self.app
+            .transfer_playback(from_player_id, to_player_id, keep_playing)
+            .await
+    }
+
+    async fn player_set_device(
+        &self,
+        player_id: Option<&str>,
+        device_id: Option<&str>,
+    ) -> Result<()> {
+        let player = self.get_player_or_default(player_id)?;
+        player.backend.set_device(device_id)?;
+
+        Ok(())
+    }
+
     fn observe_player(&self, player_id: Option<&str>) -> BoxStream<'static, PlayerEventModel> {
         let player = self.get_player_or_default(player_id).unwrap();
 
@@ -115,6 +175,18 @@ async fn player_to_model(player_id: String, player: Arc<Player>) -> Result<Playe
     };
     let volume = player.backend.volume();
     let repeat_mode = player.queue.repeat().await?;
+    let outputs = player
+        .backend
+        .outputs()
+        .into_iter()
+        .map(PlayerOutputModel::from)
+        .collect();
+    let devices = player
+        .backend
+        .devices()
+        .into_iter()
+        .map(OutputDeviceModel::from)
+        .collect();
 
     Ok(PlayerModel {
         cursor: to_cursor(&player_id),
@@ -123,5 +195,8 @@ async fn player_to_model(player_id: String, player: Arc<Player>) -> Result<Playe
         volume,
         current,
         repeat: repeat_mode.into(),
+        outputs,
+        devices,
+        capabilities: player.backend.capabilities().into(),
     })
 }
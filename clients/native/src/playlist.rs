@@ -4,7 +4,7 @@ use failure::format_err;
 use rustic_api::client::*;
 use rustic_api::cursor::from_cursor;
 use rustic_api::models::PlaylistModel;
-use rustic_core::{Playlist, ProviderType};
+use rustic_core::{Playlist, ProviderId};
 
 use crate::RusticNativeClient;
 
@@ -15,7 +15,7 @@ impl PlaylistApiClient for RusticNativeClient {
             id: None,
             title: name.into(),
             tracks: vec![],
-            provider: ProviderType::Internal,
+            provider: ProviderId::INTERNAL,
             uri: format!("internal://playlist/{}", name),
         };
         self.app.library.add_playlist(&mut playlist)?;
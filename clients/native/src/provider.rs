@@ -1,9 +1,11 @@
 use failure::err_msg;
+use futures::stream::{BoxStream, StreamExt};
 
 use async_trait::async_trait;
 use rustic_api::client::*;
 use rustic_api::models::*;
-use rustic_core::provider::{Authentication, Provider};
+use rustic_core::auth;
+use rustic_core::provider::{Authentication, Provider, ProviderId};
 
 use crate::RusticNativeClient;
 
@@ -11,18 +13,16 @@ use crate::RusticNativeClient;
 impl ProviderApiClient for RusticNativeClient {
     async fn get_providers(&self) -> Result<Vec<ProviderModel>> {
         let mut provider_models = vec![ProviderModel::internal()];
-        for provider in self.app.providers.iter() {
-            let provider = provider.get().await;
-            if !provider.state().is_authenticated() {
+        for provider in self.app.providers() {
+            let instance = provider.get().await;
+            if !instance.state().is_authenticated() {
                 continue;
             }
-            let title = provider.title().to_owned();
-            let provider_type = provider.provider();
-            let explore = provider.root();
+            let explore = instance.root();
 
             let model = ProviderModel {
-                title,
-                provider: provider_type.into(),
+                title: provider.title(),
+                provider: provider.id.clone().into(),
                 explore: explore.into(),
             };
             provider_models.push(model);
@@ -33,14 +33,12 @@ impl ProviderApiClient for RusticNativeClient {
 
     async fn get_available_providers(&self) -> Result<Vec<AvailableProviderModel>> {
         let mut provider_models = Vec::new();
-        for provider in self.app.providers.iter() {
-            let provider = provider.get().await;
-            let provider_type = provider.provider();
-            let auth_state = provider.state();
+        for provider in self.app.providers() {
+            let auth_state = provider.get().await.state();
 
             let model = AvailableProviderModel {
-                provider: provider_type.into(),
-                title: provider.title().to_owned(),
+                provider: provider.id.clone().into(),
+                title: provider.title(),
                 enabled: true,
                 auth_state: auth_state.into(),
             };
@@ -59,7 +57,6 @@ impl ProviderApiClient for RusticNativeClient {
             .get_provider(provider_type)
             .ok_or_else(|| err_msg("Invalid provider"))?;
 
-        let provider = provider.get().await;
         let path = path.split('/').map(String::from).collect();
         let folder = provider.navigate(path).await?;
         let folder = ProviderFolderModel::from(folder);
@@ -75,21 +72,41 @@ impl ProviderApiClient for RusticNativeClient {
         let provider = self.get_provider(provider_type);
 
         if let Some(provider) = provider {
-            let mut provider = provider.get_mut().await;
             let auth = Authentication::from(auth);
             let cred_store = self.credential_store.as_ref().as_ref();
-            provider.authenticate(auth, cred_store).await?;
+            auth::authenticate(&self.app, &provider, auth, cred_store).await?;
         }
 
         Ok(())
     }
+
+    async fn logout_provider(
+        &self,
+        provider_type: ProviderTypeModel,
+        purge_library: bool,
+    ) -> Result<()> {
+        let provider = self
+            .get_provider(provider_type)
+            .ok_or_else(|| err_msg("Invalid provider"))?;
+        let cred_store = self.credential_store.as_ref().as_ref();
+        auth::logout(&self.app, &provider, cred_store, purge_library).await?;
+
+        Ok(())
+    }
+
+    fn observe_providers(&self) -> BoxStream<'static, ProviderStateEventModel> {
+        self.app
+            .auth
+            .events
+            .stream()
+            .map(ProviderStateEventModel::from)
+            .boxed()
+    }
 }
 
 impl RusticNativeClient {
-    fn get_provider(&self, provider_type: ProviderTypeModel) -> Option<&Provider> {
-        self.app
-            .providers
-            .iter()
-            .find(|p| p.provider_type == provider_type.into())
+    fn get_provider(&self, provider_type: ProviderTypeModel) -> Option<Provider> {
+        let id = ProviderId::from(provider_type);
+        self.app.providers().into_iter().find(|p| p.id == id)
     }
 }
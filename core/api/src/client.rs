@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use futures::stream::BoxStream;
 
@@ -34,12 +36,26 @@ pub trait RusticApiClient:
 
     async fn enable_extension(&self, id: &str) -> Result<()>;
     async fn disable_extension(&self, id: &str) -> Result<()>;
+    async fn invoke_extension_action(&self, id: &str, action: &str) -> Result<()>;
+    /// Validates and applies the given config values, values not included stay unchanged
+    ///
+    /// Values set to None are reset to their default.
+    async fn update_extension_config(
+        &self,
+        id: &str,
+        config: HashMap<String, Option<ExtensionConfigValueModel>>,
+    ) -> Result<()>;
 
     async fn get_extensions(&self) -> Result<Vec<ExtensionModel>>;
 
     async fn open_share_url(&self, url: &str) -> Result<Option<OpenResultModel>>;
 
-    async fn get_thumbnail(&self, cursor: Cursor) -> Result<Option<CoverArtModel>>;
+    /// Returns the cover art scaled to fit into `size` pixels, `None` returns the original image
+    async fn get_thumbnail(
+        &self,
+        cursor: Cursor,
+        size: Option<u32>,
+    ) -> Result<Option<CoverArtModel>>;
 }
 
 #[reflect_trait]
@@ -61,6 +77,12 @@ pub trait ProviderApiClient: Sync + Send {
         provider: ProviderTypeModel,
         auth: ProviderAuthModel,
     ) -> Result<()>;
+
+    /// Removes the stored credentials, `purge_library` also removes the library items of the provider
+    async fn logout_provider(&self, provider: ProviderTypeModel, purge_library: bool)
+        -> Result<()>;
+
+    fn observe_providers(&self) -> BoxStream<'static, ProviderStateEventModel>;
 }
 
 #[reflect_trait]
@@ -98,9 +120,42 @@ pub trait LibraryApiClient: Sync + Send {
 
     async fn search_library(&self, query: &str) -> Result<SearchResults>;
 
+    /// Downloads the track, album or playlist so it can be played without network access
+    async fn pin_offline(&self, cursor: Cursor) -> Result<()>;
+
+    async fn unpin_offline(&self, cursor: Cursor) -> Result<()>;
+
+    async fn get_offline_mode(&self) -> Result<bool>;
+
+    /// In offline mode only items which are available offline are listed
+    async fn set_offline_mode(&self, enabled: bool) -> Result<()>;
+
+    fn observe_offline(&self) -> BoxStream<'static, OfflineEventModel>;
+
+    /// Writes the changed tags of a track or album, fails for read-only providers
+    async fn update_metadata(&self, cursor: Cursor, update: MetadataUpdateModel) -> Result<()>;
+
+    /// Rates the track in the library and mirrors the rating to providers which support it
+    async fn set_rating(&self, cursor: &str, rating: RatingModel) -> Result<()>;
+
     fn sync_state(&self) -> BoxStream<'static, SyncStateModel>;
 
-    fn observe_library(&self) -> BoxStream<'static, LibraryEventModel>;
+    /// Lists all library changes after the given revision, use revision 0 to get everything
+    async fn get_library_changes(&self, since: u64) -> Result<LibraryChangesModel>;
+
+    /// Lists the plays since the given unix timestamp, newest first
+    async fn get_history(&self, since: Option<u64>, limit: Option<usize>)
+        -> Result<Vec<PlayModel>>;
+
+    /// Most played tracks, artists and albums between the given unix timestamps
+    async fn get_play_stats(
+        &self,
+        since: Option<u64>,
+        until: Option<u64>,
+        limit: Option<usize>,
+    ) -> Result<PlayStatsModel>;
+
+    fn observe_library(&self) -> BoxStream<'static, LibraryChangeModel>;
 }
 
 #[reflect_trait]
@@ -168,5 +223,41 @@ pub trait PlayerApiClient: Sync + Send {
         repeat: RepeatModeModel,
     ) -> Result<()>;
 
+    async fn player_set_output_enabled(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        enabled: bool,
+    ) -> Result<()>;
+
+    async fn player_set_output_volume(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        volume: f32,
+    ) -> Result<()>;
+
+    async fn player_set_output_muted(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        muted: bool,
+    ) -> Result<()>;
+
+    /// Move the queue and playback position from one player to another
+    async fn transfer_playback(
+        &self,
+        from_player_id: &str,
+        to_player_id: &str,
+        keep_playing: bool,
+    ) -> Result<()>;
+
+    /// Select the audio device of the player, `None` selects the default device
+    async fn player_set_device(
+        &self,
+        player_id: Option<&str>,
+        device_id: Option<&str>,
+    ) -> Result<()>;
+
     fn observe_player(&self, player_id: Option<&str>) -> BoxStream<'static, PlayerEventModel>;
 }
@@ -6,16 +6,26 @@ use rustic_core::provider::{
     Authentication, InternalUri, ProviderFolder, ProviderItem, ProviderItemType, ProviderState,
     Thumbnail,
 };
+use rustic_core::player::{OutputDevice, PlayerCapabilities, PlayerOutput};
+use rustic_core::auth::ProviderStateEvent;
+use rustic_core::cache::CoverArt;
+use rustic_core::offline::OfflineEvent;
 use rustic_core::sync::{SyncEvent, SyncItem, SyncItemState};
 use rustic_core::{
-    Album, Artist, LibraryEvent, PlayerEvent, PlayerState, Playlist, ProviderType, QueuedTrack,
-    Rating, RepeatMode, Track, TrackPosition,
+    Album, Artist, LibraryChange, LibraryEvent, PlayerEvent, PlayerState, Playlist, ProviderId,
+    QueuedTrack, Rating, RepeatMode, Track, TrackPosition,
+};
+use rustic_extension_api::{
+    ExtensionAction, ExtensionCallMetrics, ExtensionConfigField, ExtensionConfigType,
+    ExtensionConfigValue, ExtensionControls, ExtensionDetails, ExtensionInfo,
 };
-use rustic_extension_api::{ExtensionMetadata, ExtensionControls, ExtensionInfo, ExtensionAction};
 
 use crate::cursor::{from_cursor, to_cursor, Cursor};
 use crate::models::*;
-use rustic_core::library::{Chapter, Lyrics, MetaValue, TimestampedLyric};
+use rustic_core::library::{
+    ChangedField, Chapter, Lyrics, MetaValue, MetadataUpdate, PlayRecord, PlayStats,
+    TimestampedLyric, TrackPlays,
+};
 
 impl From<Album> for AlbumModel {
     fn from(album: Album) -> Self {
@@ -88,14 +98,103 @@ impl From<ProviderState> for ProviderStateModel {
     }
 }
 
-impl From<(ExtensionMetadata, bool, ExtensionControls)> for ExtensionModel {
-    fn from((metadata, enabled, controls): (ExtensionMetadata, bool, ExtensionControls)) -> Self {
+impl From<ProviderStateEvent> for ProviderStateEventModel {
+    fn from(event: ProviderStateEvent) -> Self {
+        ProviderStateEventModel {
+            provider: event.provider.into(),
+            auth_state: event.state.into(),
+        }
+    }
+}
+
+impl From<ExtensionDetails> for ExtensionModel {
+    fn from(extension: ExtensionDetails) -> Self {
+        let metadata = extension.metadata;
         ExtensionModel {
             name: metadata.name,
             id: metadata.id,
             version: metadata.version,
-            enabled,
-            controls: controls.into(),
+            enabled: extension.enabled,
+            controls: extension.controls.into(),
+            config_schema: metadata
+                .config
+                .fields
+                .into_iter()
+                .map(ExtensionConfigFieldModel::from)
+                .collect(),
+            config: extension
+                .config
+                .into_iter()
+                .map(|(key, value)| (key, value.into()))
+                .collect(),
+            disabled_reason: extension.health.disabled_reason,
+            metrics: extension
+                .health
+                .metrics
+                .into_iter()
+                .map(ExtensionCallMetricsModel::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<ExtensionCallMetrics> for ExtensionCallMetricsModel {
+    fn from(metrics: ExtensionCallMetrics) -> Self {
+        ExtensionCallMetricsModel {
+            average_ms: metrics.average_time().as_millis() as u64,
+            max_ms: metrics.max_time.as_millis() as u64,
+            call: metrics.call,
+            calls: metrics.calls,
+            failures: metrics.failures,
+        }
+    }
+}
+
+impl From<ExtensionConfigField> for ExtensionConfigFieldModel {
+    fn from(field: ExtensionConfigField) -> Self {
+        ExtensionConfigFieldModel {
+            key: field.key,
+            label: field.label,
+            field_type: field.field_type.into(),
+            default: field.default.map(ExtensionConfigValueModel::from),
+            allowed_values: field
+                .allowed_values
+                .into_iter()
+                .map(ExtensionConfigValueModel::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<ExtensionConfigType> for ExtensionConfigTypeModel {
+    fn from(field_type: ExtensionConfigType) -> Self {
+        match field_type {
+            ExtensionConfigType::Bool => ExtensionConfigTypeModel::Bool,
+            ExtensionConfigType::String => ExtensionConfigTypeModel::String,
+            ExtensionConfigType::Float => ExtensionConfigTypeModel::Float,
+            ExtensionConfigType::Int => ExtensionConfigTypeModel::Int,
+        }
+    }
+}
+
+impl From<ExtensionConfigValue> for ExtensionConfigValueModel {
+    fn from(value: ExtensionConfigValue) -> Self {
+        match value {
+            ExtensionConfigValue::Bool(value) => ExtensionConfigValueModel::Bool(value),
+            ExtensionConfigValue::String(value) => ExtensionConfigValueModel::String(value),
+            ExtensionConfigValue::Float(value) => ExtensionConfigValueModel::Float(value),
+            ExtensionConfigValue::Int(value) => ExtensionConfigValueModel::Int(value),
+        }
+    }
+}
+
+impl From<ExtensionConfigValueModel> for ExtensionConfigValue {
+    fn from(value: ExtensionConfigValueModel) -> Self {
+        match value {
+            ExtensionConfigValueModel::Bool(value) => ExtensionConfigValue::Bool(value),
+            ExtensionConfigValueModel::String(value) => ExtensionConfigValue::String(value),
+            ExtensionConfigValueModel::Float(value) => ExtensionConfigValue::Float(value),
+            ExtensionConfigValueModel::Int(value) => ExtensionConfigValue::Int(value),
         }
     }
 }
@@ -173,6 +272,61 @@ impl From<Track> for TrackModel {
             comments: track.comments,
             lyrics: track.lyrics.into(),
             chapters: track.chapters.into_iter().map(ChapterModel::from).collect(),
+            play_count: 0,
+            last_played: None,
+        }
+    }
+}
+
+impl TrackModel {
+    pub fn with_plays(mut self, plays: Option<&TrackPlays>) -> Self {
+        if let Some(plays) = plays {
+            self.play_count = plays.count;
+            self.last_played = plays.last_played;
+        }
+        self
+    }
+}
+
+impl From<PlayRecord> for PlayModel {
+    fn from(play: PlayRecord) -> Self {
+        PlayModel {
+            track: play.track.into(),
+            player_cursor: to_cursor(&play.player),
+            started_at: play.started_at,
+            listened: play.listened,
+            completed: play.completed,
+        }
+    }
+}
+
+impl From<PlayStats> for PlayStatsModel {
+    fn from(stats: PlayStats) -> Self {
+        PlayStatsModel {
+            tracks: stats
+                .tracks
+                .into_iter()
+                .map(|count| TrackPlayCountModel {
+                    track: count.item.into(),
+                    plays: count.plays,
+                })
+                .collect(),
+            artists: stats
+                .artists
+                .into_iter()
+                .map(|count| ArtistPlayCountModel {
+                    artist: count.item.into(),
+                    plays: count.plays,
+                })
+                .collect(),
+            albums: stats
+                .albums
+                .into_iter()
+                .map(|count| AlbumPlayCountModel {
+                    album: count.item.into(),
+                    plays: count.plays,
+                })
+                .collect(),
         }
     }
 }
@@ -266,33 +420,15 @@ impl From<&ProviderItemType> for ProviderItemTypeModel {
     }
 }
 
-impl From<ProviderType> for ProviderTypeModel {
-    fn from(provider: ProviderType) -> Self {
-        match provider {
-            ProviderType::Internal => ProviderTypeModel::Internal,
-            ProviderType::Pocketcasts => ProviderTypeModel::Pocketcasts,
-            ProviderType::GooglePlayMusic => ProviderTypeModel::GooglePlayMusic,
-            ProviderType::LocalMedia => ProviderTypeModel::LocalMedia,
-            ProviderType::Soundcloud => ProviderTypeModel::Soundcloud,
-            ProviderType::Spotify => ProviderTypeModel::Spotify,
-            ProviderType::Youtube => ProviderTypeModel::Youtube,
-            ProviderType::YouTubeMusic => ProviderTypeModel::YouTubeMusic,
-        }
+impl From<ProviderId> for ProviderTypeModel {
+    fn from(provider: ProviderId) -> Self {
+        ProviderTypeModel(provider.to_string())
     }
 }
 
-impl From<ProviderTypeModel> for ProviderType {
+impl From<ProviderTypeModel> for ProviderId {
     fn from(provider: ProviderTypeModel) -> Self {
-        match provider {
-            ProviderTypeModel::Internal => ProviderType::Internal,
-            ProviderTypeModel::Pocketcasts => ProviderType::Pocketcasts,
-            ProviderTypeModel::GooglePlayMusic => ProviderType::GooglePlayMusic,
-            ProviderTypeModel::LocalMedia => ProviderType::LocalMedia,
-            ProviderTypeModel::Soundcloud => ProviderType::Soundcloud,
-            ProviderTypeModel::Spotify => ProviderType::Spotify,
-            ProviderTypeModel::Youtube => ProviderType::Youtube,
-            ProviderTypeModel::YouTubeMusic => ProviderType::YouTubeMusic,
-        }
+        ProviderId::from(provider.0)
     }
 }
 
@@ -327,6 +463,25 @@ impl From<SyncEvent> for SyncStateModel {
     }
 }
 
+impl From<OfflineEvent> for OfflineEventModel {
+    fn from(event: OfflineEvent) -> Self {
+        match event {
+            OfflineEvent::Progress {
+                uri,
+                downloaded,
+                total,
+            } => OfflineEventModel::Progress(OfflineProgressModel {
+                cursor: to_cursor(&uri),
+                downloaded,
+                total,
+            }),
+            OfflineEvent::Completed(uri) => OfflineEventModel::Completed(to_cursor(&uri)),
+            OfflineEvent::Failed(uri) => OfflineEventModel::Failed(to_cursor(&uri)),
+            OfflineEvent::Removed(uri) => OfflineEventModel::Removed(to_cursor(&uri)),
+        }
+    }
+}
+
 impl From<SyncItem> for SyncItemModel {
     fn from(item: SyncItem) -> Self {
         SyncItemModel {
@@ -359,11 +514,52 @@ impl From<PlayerEvent> for PlayerEventModel {
                 PlayerEventModel::StateChanged(state == PlayerState::Play)
             }
             PlayerEvent::VolumeChanged(volume) => PlayerEventModel::VolumeChanged(volume),
+            PlayerEvent::OutputsChanged(outputs) => PlayerEventModel::OutputsChanged(
+                outputs.into_iter().map(PlayerOutputModel::from).collect(),
+            ),
+            PlayerEvent::DevicesChanged(devices) => PlayerEventModel::DevicesChanged(
+                devices.into_iter().map(OutputDeviceModel::from).collect(),
+            ),
             _ => unreachable!("this should be filtered before"),
         }
     }
 }
 
+impl From<PlayerOutput> for PlayerOutputModel {
+    fn from(output: PlayerOutput) -> Self {
+        PlayerOutputModel {
+            cursor: to_cursor(&output.id),
+            name: output.name,
+            enabled: output.enabled,
+            volume: output.volume,
+            muted: output.muted,
+        }
+    }
+}
+
+impl From<OutputDevice> for OutputDeviceModel {
+    fn from(device: OutputDevice) -> Self {
+        OutputDeviceModel {
+            cursor: to_cursor(&device.id),
+            name: device.name,
+            selected: device.selected,
+        }
+    }
+}
+
+impl From<PlayerCapabilities> for PlayerCapabilitiesModel {
+    fn from(capabilities: PlayerCapabilities) -> Self {
+        PlayerCapabilitiesModel {
+            seek: capabilities.seek,
+            volume: capabilities.volume,
+            crossfade: capabilities.crossfade,
+            gapless: capabilities.gapless,
+            playback_rate: capabilities.playback_rate,
+            output_devices: capabilities.output_devices,
+        }
+    }
+}
+
 impl From<PlayerEvent> for QueueEventModel {
     fn from(event: PlayerEvent) -> Self {
         match event {
@@ -385,12 +581,54 @@ impl From<Thumbnail> for CoverArtModel {
                 CoverArtModel::Data {
                     data: stream.boxed(),
                     mime_type,
+                    etag: None,
+                    last_modified: None,
                 }
             }
         }
     }
 }
 
+impl From<CoverArt> for CoverArtModel {
+    fn from(cover_art: CoverArt) -> Self {
+        let data = cover_art.data;
+        let stream = futures::stream::once(async { data });
+
+        CoverArtModel::Data {
+            data: stream.boxed(),
+            mime_type: cover_art.mime_type,
+            etag: Some(cover_art.etag),
+            last_modified: Some(cover_art.last_modified),
+        }
+    }
+}
+
+impl TryFrom<MetadataUpdateModel> for MetadataUpdate {
+    type Error = failure::Error;
+
+    fn try_from(update: MetadataUpdateModel) -> Result<Self, Self::Error> {
+        let cover_art = match update.cover_art {
+            Some(cover_art) => Some(Thumbnail::Data {
+                data: base64::decode(&cover_art.data)?,
+                mime_type: cover_art.mime_type,
+            }),
+            None => None,
+        };
+
+        Ok(MetadataUpdate {
+            title: update.title,
+            artists: update.artists,
+            album: update.album,
+            album_artist: update.album_artist,
+            track_number: update.track_number,
+            disc_number: update.disc_number,
+            genre: update.genre,
+            year: update.year,
+            cover_art,
+        })
+    }
+}
+
 impl TryFrom<Cursor> for InternalUri {
     type Error = failure::Error;
 
@@ -462,21 +700,66 @@ impl From<Rating> for RatingModel {
     }
 }
 
+impl From<RatingModel> for Rating {
+    fn from(rating: RatingModel) -> Self {
+        match rating {
+            RatingModel::None => Rating::None,
+            RatingModel::Like => Rating::Like,
+            RatingModel::Dislike => Rating::Dislike,
+            RatingModel::Stars(stars) => Rating::Stars(stars),
+        }
+    }
+}
+
 impl From<LibraryEvent> for LibraryEventModel {
     fn from(library_event: LibraryEvent) -> Self {
         match library_event {
             LibraryEvent::TrackAdded(track) => LibraryEventModel::TrackAdded(track.into()),
             LibraryEvent::TrackRemoved(uri) => LibraryEventModel::TrackRemoved(to_cursor(&uri)),
+            LibraryEvent::TrackUpdated(track, changed) => LibraryEventModel::TrackUpdated {
+                track: track.into(),
+                changed: changed_fields(changed),
+            },
             LibraryEvent::AlbumAdded(album) => LibraryEventModel::AlbumAdded(album.into()),
             LibraryEvent::AlbumRemoved(uri) => LibraryEventModel::AlbumRemoved(to_cursor(&uri)),
+            LibraryEvent::AlbumUpdated(album, changed) => LibraryEventModel::AlbumUpdated {
+                album: album.into(),
+                changed: changed_fields(changed),
+            },
             LibraryEvent::ArtistAdded(artist) => LibraryEventModel::ArtistAdded(artist.into()),
             LibraryEvent::ArtistRemoved(uri) => LibraryEventModel::ArtistRemoved(to_cursor(&uri)),
+            LibraryEvent::ArtistUpdated(artist, changed) => LibraryEventModel::ArtistUpdated {
+                artist: artist.into(),
+                changed: changed_fields(changed),
+            },
             LibraryEvent::PlaylistAdded(playlist) => {
                 LibraryEventModel::PlaylistAdded(playlist.into())
             }
             LibraryEvent::PlaylistRemoved(uri) => {
                 LibraryEventModel::PlaylistRemoved(to_cursor(&uri))
             }
+            LibraryEvent::PlaylistUpdated(playlist, changed) => {
+                LibraryEventModel::PlaylistUpdated {
+                    playlist: playlist.into(),
+                    changed: changed_fields(changed),
+                }
+            }
+        }
+    }
+}
+
+fn changed_fields(fields: Vec<ChangedField>) -> Vec<String> {
+    fields
+        .into_iter()
+        .map(|field| field.name().to_string())
+        .collect()
+}
+
+impl From<LibraryChange> for LibraryChangeModel {
+    fn from(change: LibraryChange) -> Self {
+        LibraryChangeModel {
+            revision: change.revision,
+            event: change.event.into(),
         }
     }
 }
@@ -31,3 +31,16 @@ pub enum ProviderStateModel {
     PasswordAuthentication,
     Authenticated,
 }
+
+/// Emitted when a provider gains or loses its authentication, e.g. when a token can't be refreshed anymore
+#[reflect_struct]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderStateEventModel {
+    pub provider: ProviderTypeModel,
+    pub auth_state: ProviderStateModel,
+}
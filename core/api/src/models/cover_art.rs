@@ -1,9 +1,13 @@
+use std::time::SystemTime;
+
 use futures::stream::BoxStream;
 
 pub enum CoverArtModel {
     Data {
         data: BoxStream<'static, Vec<u8>>,
         mime_type: String,
+        etag: Option<String>,
+        last_modified: Option<SystemTime>,
     },
     Url(String),
 }
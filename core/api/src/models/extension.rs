@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use rustic_reflect_macros::reflect_struct;
 use serde::{Deserialize, Serialize};
 #[cfg(target_arch = "wasm32")]
@@ -15,6 +17,30 @@ pub struct ExtensionModel {
     pub version: String,
     pub enabled: bool,
     pub controls: ExtensionControlsModel,
+    #[serde(default)]
+    pub config_schema: Vec<ExtensionConfigFieldModel>,
+    /// The current config including defaults
+    #[serde(default)]
+    pub config: HashMap<String, ExtensionConfigValueModel>,
+    /// Set when the extension was disabled automatically because it crashed or timed out repeatedly
+    #[serde(default)]
+    pub disabled_reason: Option<String>,
+    #[serde(default)]
+    pub metrics: Vec<ExtensionCallMetricsModel>,
+}
+
+#[reflect_struct]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct ExtensionCallMetricsModel {
+    pub call: String,
+    pub calls: u64,
+    pub failures: u64,
+    pub average_ms: u64,
+    pub max_ms: u64,
 }
 
 #[reflect_struct]
@@ -48,3 +74,42 @@ pub struct ExtensionActionModel {
 pub enum ExtensionInfoModel {
     Link(String),
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+#[serde(untagged)]
+pub enum ExtensionConfigValueModel {
+    Bool(bool),
+    String(String),
+    Int(i64),
+    Float(f64),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub enum ExtensionConfigTypeModel {
+    Bool,
+    String,
+    Float,
+    Int,
+}
+
+#[reflect_struct]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct ExtensionConfigFieldModel {
+    pub key: String,
+    pub label: String,
+    pub field_type: ExtensionConfigTypeModel,
+    pub default: Option<ExtensionConfigValueModel>,
+    pub allowed_values: Vec<ExtensionConfigValueModel>,
+}
@@ -0,0 +1,70 @@
+use rustic_reflect_macros::reflect_struct;
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::models::{AlbumModel, ArtistModel, TrackModel};
+
+#[reflect_struct]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayModel {
+    pub track: TrackModel,
+    pub player_cursor: String,
+    /// Unix timestamp in seconds
+    pub started_at: u64,
+    /// Seconds the track was actually playing
+    pub listened: u64,
+    /// false when the track was skipped
+    pub completed: bool,
+}
+
+#[reflect_struct]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct TrackPlayCountModel {
+    pub track: TrackModel,
+    pub plays: usize,
+}
+
+#[reflect_struct]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct ArtistPlayCountModel {
+    pub artist: ArtistModel,
+    pub plays: usize,
+}
+
+#[reflect_struct]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct AlbumPlayCountModel {
+    pub album: AlbumModel,
+    pub plays: usize,
+}
+
+/// Most played items in a time window, ordered by play count
+#[reflect_struct]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct PlayStatsModel {
+    pub tracks: Vec<TrackPlayCountModel>,
+    pub artists: Vec<ArtistPlayCountModel>,
+    pub albums: Vec<AlbumPlayCountModel>,
+}
@@ -1,5 +1,6 @@
 use std::time::Duration;
 
+use rustic_reflect_macros::reflect_struct;
 use serde::{Deserialize, Serialize};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
@@ -16,16 +17,65 @@ pub enum LibraryEventModel {
     TrackAdded(TrackModel),
     /// Emitted when the track with the given cursor was removed
     TrackRemoved(String),
+    /// Emitted when an existing track was synced with new metadata
+    TrackUpdated {
+        track: TrackModel,
+        /// Names of the changed fields
+        changed: Vec<String>,
+    },
     /// Emitted when a new album was added
     AlbumAdded(AlbumModel),
     /// Emitted when the album with the given cursor was removed
     AlbumRemoved(String),
+    /// Emitted when an existing album was synced with new metadata
+    AlbumUpdated {
+        album: AlbumModel,
+        /// Names of the changed fields
+        changed: Vec<String>,
+    },
     /// Emitted when a new artist was added
     ArtistAdded(ArtistModel),
     /// Emitted when the artist with the given cursor was removed
     ArtistRemoved(String),
+    /// Emitted when an existing artist was synced with new metadata
+    ArtistUpdated {
+        artist: ArtistModel,
+        /// Names of the changed fields
+        changed: Vec<String>,
+    },
     /// Emitted when a new playlist was added
     PlaylistAdded(PlaylistModel),
     /// Emitted when the playlist with the given cursor was removed
     PlaylistRemoved(String),
+    /// Emitted when an existing playlist was synced with new tracks or a new title
+    PlaylistUpdated {
+        playlist: PlaylistModel,
+        /// Names of the changed fields
+        changed: Vec<String>,
+    },
+}
+
+/// A library event with the revision it created
+#[reflect_struct]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct LibraryChangeModel {
+    pub revision: u64,
+    pub event: LibraryEventModel,
+}
+
+/// Everything which changed since a given revision
+#[reflect_struct]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct LibraryChangesModel {
+    /// The current revision, use it to ask for the next changes
+    pub revision: u64,
+    pub changes: Vec<LibraryChangeModel>,
 }
@@ -0,0 +1,35 @@
+use rustic_reflect_macros::reflect_struct;
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Changes to the tags of a track or album, missing fields are left untouched
+#[reflect_struct]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct MetadataUpdateModel {
+    pub title: Option<String>,
+    pub artists: Option<Vec<String>>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub cover_art: Option<MetadataCoverArtModel>,
+}
+
+#[reflect_struct]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct MetadataCoverArtModel {
+    pub mime_type: String,
+    /// Base64 encoded image
+    pub data: String,
+}
@@ -4,8 +4,11 @@ pub use self::artist::*;
 pub use self::available_provider::*;
 pub use self::cover_art::*;
 pub use self::extension::*;
+pub use self::history::*;
 pub use self::library_event::*;
 pub use self::meta::*;
+pub use self::metadata::*;
+pub use self::offline::*;
 pub use self::open_result::*;
 pub use self::player::*;
 pub use self::player_event::*;
@@ -23,8 +26,11 @@ mod artist;
 mod available_provider;
 mod cover_art;
 mod extension;
+mod history;
 mod library_event;
 mod meta;
+mod metadata;
+mod offline;
 mod open_result;
 mod player;
 mod player_event;
@@ -0,0 +1,31 @@
+use rustic_reflect_macros::reflect_struct;
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub enum OfflineEventModel {
+    Progress(OfflineProgressModel),
+    /// Cursor of the item which is now available offline
+    Completed(String),
+    /// Cursor of the item which failed to download
+    Failed(String),
+    /// Cursor of the item which is no longer available offline
+    Removed(String),
+}
+
+#[reflect_struct]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct OfflineProgressModel {
+    pub cursor: String,
+    pub downloaded: usize,
+    pub total: usize,
+}
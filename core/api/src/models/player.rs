@@ -17,4 +17,48 @@ pub struct PlayerModel {
     pub volume: f32,
     pub current: Option<TrackModel>,
     pub repeat: RepeatModeModel,
+    pub outputs: Vec<PlayerOutputModel>,
+    pub devices: Vec<OutputDeviceModel>,
+    pub capabilities: PlayerCapabilitiesModel,
+}
+
+#[reflect_struct]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct OutputDeviceModel {
+    pub cursor: String,
+    pub name: String,
+    pub selected: bool,
+}
+
+#[reflect_struct]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct PlayerCapabilitiesModel {
+    pub seek: bool,
+    pub volume: bool,
+    pub crossfade: bool,
+    pub gapless: bool,
+    pub playback_rate: bool,
+    pub output_devices: bool,
+}
+
+#[reflect_struct]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(typescript_definitions::TypescriptDefinition)
+)]
+pub struct PlayerOutputModel {
+    pub cursor: String,
+    pub name: String,
+    pub enabled: bool,
+    pub volume: f32,
+    pub muted: bool,
 }
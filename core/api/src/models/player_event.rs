@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
-use crate::models::TrackModel;
+use crate::models::{OutputDeviceModel, PlayerOutputModel, TrackModel};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[cfg_attr(
@@ -22,4 +22,8 @@ pub enum PlayerEventModel {
     Buffering,
     /// The current volume has changed
     VolumeChanged(f32),
+    /// The outputs or their settings have changed
+    OutputsChanged(Vec<PlayerOutputModel>),
+    /// Audio devices were added or removed or another device was selected
+    DevicesChanged(Vec<OutputDeviceModel>),
 }
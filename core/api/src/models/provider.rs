@@ -20,7 +20,7 @@ impl ProviderModel {
     pub fn internal() -> Self {
         ProviderModel {
             title: "Internal".into(),
-            provider: ProviderTypeModel::Internal,
+            provider: ProviderTypeModel::from("internal"),
             explore: ProviderFolderModel::default(),
         }
     }
@@ -61,25 +61,25 @@ pub enum ProviderItemTypeModel {
     Playlist(PlaylistModel),
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+/// Id of a provider instance, e.g. `spotify` or `spotify:work`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[cfg_attr(
     target_arch = "wasm32",
     derive(typescript_definitions::TypescriptDefinition)
 )]
-#[serde(rename_all = "lowercase")]
-pub enum ProviderTypeModel {
-    Internal,
-    Pocketcasts,
-    Soundcloud,
-    #[serde(rename = "gmusic")]
-    #[deprecated]
-    GooglePlayMusic,
-    Spotify,
-    #[serde(rename = "local")]
-    LocalMedia,
-    Youtube,
-    #[serde(rename = "ytmusic")]
-    YouTubeMusic,
+#[serde(transparent)]
+pub struct ProviderTypeModel(pub String);
+
+impl From<&str> for ProviderTypeModel {
+    fn from(id: &str) -> Self {
+        ProviderTypeModel(id.to_string())
+    }
+}
+
+impl std::fmt::Display for ProviderTypeModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
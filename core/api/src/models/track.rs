@@ -32,6 +32,12 @@ pub struct TrackModel {
     pub lyrics: LyricsModel,
     pub comments: Option<String>,
     pub chapters: Vec<ChapterModel>,
+    /// Number of plays which weren't skipped
+    #[serde(default)]
+    pub play_count: u64,
+    /// Unix timestamp in seconds
+    #[serde(default)]
+    pub last_played: Option<u64>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -58,6 +58,18 @@ impl RusticApiClient for TestApiClient {
         unimplemented!()
     }
 
+    async fn invoke_extension_action(&self, id: &str, action: &str) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn update_extension_config(
+        &self,
+        id: &str,
+        config: HashMap<String, Option<ExtensionConfigValueModel>>,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
     async fn get_extensions(&self) -> Result<Vec<ExtensionModel>> {
         Ok(self.extensions.clone())
     }
@@ -66,7 +78,11 @@ impl RusticApiClient for TestApiClient {
         unimplemented!()
     }
 
-    async fn get_thumbnail(&self, cursor: Cursor) -> Result<Option<CoverArtModel>> {
+    async fn get_thumbnail(
+        &self,
+        cursor: Cursor,
+        size: Option<u32>,
+    ) -> Result<Option<CoverArtModel>> {
         unimplemented!()
     }
 }
@@ -112,6 +128,50 @@ impl PlayerApiClient for TestApiClient {
         unimplemented!()
     }
 
+    async fn player_set_output_enabled(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        enabled: bool,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn player_set_output_volume(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        volume: f32,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn player_set_output_muted(
+        &self,
+        player_id: Option<&str>,
+        output_id: &str,
+        muted: bool,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn transfer_playback(
+        &self,
+        from_player_id: &str,
+        to_player_id: &str,
+        keep_playing: bool,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn player_set_device(
+        &self,
+        player_id: Option<&str>,
+        device_id: Option<&str>,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
     fn observe_player(&self, player_id: Option<&str>) -> BoxStream<'static, PlayerEventModel> {
         unimplemented!()
     }
@@ -238,11 +298,60 @@ impl LibraryApiClient for TestApiClient {
         unimplemented!()
     }
 
+    async fn pin_offline(&self, cursor: Cursor) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn unpin_offline(&self, cursor: Cursor) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn get_offline_mode(&self) -> Result<bool> {
+        unimplemented!()
+    }
+
+    async fn set_offline_mode(&self, enabled: bool) -> Result<()> {
+        unimplemented!()
+    }
+
+    fn observe_offline(&self) -> BoxStream<'static, OfflineEventModel> {
+        unimplemented!()
+    }
+
+    async fn update_metadata(&self, cursor: Cursor, update: MetadataUpdateModel) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn set_rating(&self, cursor: &str, rating: RatingModel) -> Result<()> {
+        unimplemented!()
+    }
+
     fn sync_state(&self) -> BoxStream<'static, SyncStateModel> {
         unimplemented!()
     }
 
-    fn observe_library(&self) -> BoxStream<'static, LibraryEventModel> {
+    async fn get_library_changes(&self, since: u64) -> Result<LibraryChangesModel> {
+        unimplemented!()
+    }
+
+    async fn get_history(
+        &self,
+        since: Option<u64>,
+        limit: Option<usize>,
+    ) -> Result<Vec<PlayModel>> {
+        unimplemented!()
+    }
+
+    async fn get_play_stats(
+        &self,
+        since: Option<u64>,
+        until: Option<u64>,
+        limit: Option<usize>,
+    ) -> Result<PlayStatsModel> {
+        unimplemented!()
+    }
+
+    fn observe_library(&self) -> BoxStream<'static, LibraryChangeModel> {
         unimplemented!()
     }
 }
@@ -273,4 +382,16 @@ impl ProviderApiClient for TestApiClient {
     ) -> Result<()> {
         unimplemented!()
     }
+
+    async fn logout_provider(
+        &self,
+        provider: ProviderTypeModel,
+        purge_library: bool,
+    ) -> Result<()> {
+        unimplemented!()
+    }
+
+    fn observe_providers(&self) -> BoxStream<'static, ProviderStateEventModel> {
+        unimplemented!()
+    }
 }
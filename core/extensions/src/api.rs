@@ -3,9 +3,11 @@ use std::collections::HashMap;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
-use rustic_core::{Album, Artist, Playlist, Track};
+use rustic_core::library::PlayRecord;
+use rustic_core::{Album, Artist, LibraryChange, PlayerState, Playlist, QueuedTrack, Track};
 
 pub use crate::ExtensionRuntime;
+pub use crate::config::*;
 pub use crate::controls::*;
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -13,8 +15,9 @@ pub use crate::controls::*;
 pub enum ExtensionConfigValue {
     Bool(bool),
     String(String),
-    Float(f64),
+    // Int has to be listed before Float, otherwise untagged deserialization reads every number as float
     Int(i64),
+    Float(f64),
 }
 
 impl ExtensionConfigValue {
@@ -58,11 +61,43 @@ impl ExtensionConfigValue {
     }
 }
 
+impl From<bool> for ExtensionConfigValue {
+    fn from(value: bool) -> Self {
+        ExtensionConfigValue::Bool(value)
+    }
+}
+
+impl From<String> for ExtensionConfigValue {
+    fn from(value: String) -> Self {
+        ExtensionConfigValue::String(value)
+    }
+}
+
+impl From<&str> for ExtensionConfigValue {
+    fn from(value: &str) -> Self {
+        ExtensionConfigValue::String(value.to_string())
+    }
+}
+
+impl From<f64> for ExtensionConfigValue {
+    fn from(value: f64) -> Self {
+        ExtensionConfigValue::Float(value)
+    }
+}
+
+impl From<i64> for ExtensionConfigValue {
+    fn from(value: i64) -> Self {
+        ExtensionConfigValue::Int(value)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExtensionMetadata {
     pub id: String,
     pub name: String,
     pub version: String,
+    #[serde(default)]
+    pub config: ExtensionConfigSchema,
 }
 
 pub trait ExtensionLibrary: Extension {
@@ -75,6 +110,13 @@ pub trait Extension: std::fmt::Debug + Send + Sync + ExtensionApi {
     fn setup(&mut self, runtime: &ExtensionRuntime) -> Result<(), failure::Error> {
         Ok(())
     }
+
+    /// Called with the complete config when it was changed through the api
+    ///
+    /// The config has already been validated against the schema in the metadata.
+    fn update_config(&mut self, config: ExtensionConfig) -> Result<(), failure::Error> {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -90,10 +132,43 @@ pub trait ExtensionApi {
         Ok(Default::default())
     }
 
+    /// Called when one of the actions from the controls is invoked
+    async fn invoke_action(&self, key: String) -> Result<(), failure::Error> {
+        Err(failure::format_err!("Unknown action {}", key))
+    }
+
     async fn on_add_to_queue(&self, player_id: String, tracks: Vec<Track>) -> Result<Vec<Track>, failure::Error> {
         Ok(tracks)
     }
 
+    /// Called when a player starts playing a new track
+    async fn on_play_started(&self, player_id: String, track: Track) -> Result<(), failure::Error> {
+        Ok(())
+    }
+
+    /// Called when a track finished playing or was skipped
+    ///
+    /// The record contains how long the track was actually listened to and whether it was skipped.
+    async fn on_play_finished(&self, play: PlayRecord) -> Result<(), failure::Error> {
+        Ok(())
+    }
+
+    async fn on_state_changed(&self, player_id: String, state: PlayerState) -> Result<(), failure::Error> {
+        Ok(())
+    }
+
+    async fn on_volume_changed(&self, player_id: String, volume: f32) -> Result<(), failure::Error> {
+        Ok(())
+    }
+
+    async fn on_queue_updated(&self, player_id: String, queue: Vec<QueuedTrack>) -> Result<(), failure::Error> {
+        Ok(())
+    }
+
+    async fn on_library_event(&self, change: LibraryChange) -> Result<(), failure::Error> {
+        Ok(())
+    }
+
     async fn resolve_track(&self, track: Track) -> Result<Track, failure::Error> {
         Ok(track)
     }
@@ -110,6 +185,42 @@ pub trait ExtensionApi {
         Ok(playlist)
     }
 
+    /// Resolves multiple tracks with a single call, the default calls `resolve_track` for every track
+    async fn resolve_tracks(&self, tracks: Vec<Track>) -> Result<Vec<Track>, failure::Error> {
+        let mut resolved = Vec::with_capacity(tracks.len());
+        for track in tracks {
+            resolved.push(self.resolve_track(track).await?);
+        }
+        Ok(resolved)
+    }
+
+    async fn resolve_albums(&self, albums: Vec<Album>) -> Result<Vec<Album>, failure::Error> {
+        let mut resolved = Vec::with_capacity(albums.len());
+        for album in albums {
+            resolved.push(self.resolve_album(album).await?);
+        }
+        Ok(resolved)
+    }
+
+    async fn resolve_artists(&self, artists: Vec<Artist>) -> Result<Vec<Artist>, failure::Error> {
+        let mut resolved = Vec::with_capacity(artists.len());
+        for artist in artists {
+            resolved.push(self.resolve_artist(artist).await?);
+        }
+        Ok(resolved)
+    }
+
+    async fn resolve_playlists(
+        &self,
+        playlists: Vec<Playlist>,
+    ) -> Result<Vec<Playlist>, failure::Error> {
+        let mut resolved = Vec::with_capacity(playlists.len());
+        for playlist in playlists {
+            resolved.push(self.resolve_playlist(playlist).await?);
+        }
+        Ok(resolved)
+    }
+
     async fn player_control_next(&self, player_id: Option<&str>) -> Result<bool, failure::Error> {
         Ok(true)
     }
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use failure::{bail, Error};
+use serde::{Deserialize, Serialize};
+
+use crate::api::ExtensionConfigValue;
+
+pub type ExtensionConfig = HashMap<String, ExtensionConfigValue>;
+/// Changed config values, `None` resets the value to the default of the schema
+pub type ExtensionConfigUpdate = HashMap<String, Option<ExtensionConfigValue>>;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ExtensionConfigType {
+    Bool,
+    String,
+    Float,
+    Int,
+}
+
+impl ExtensionConfigType {
+    fn matches(self, value: &ExtensionConfigValue) -> bool {
+        matches!(
+            (self, value),
+            (ExtensionConfigType::Bool, ExtensionConfigValue::Bool(_))
+                | (ExtensionConfigType::String, ExtensionConfigValue::String(_))
+                | (ExtensionConfigType::Float, ExtensionConfigValue::Float(_))
+                | (ExtensionConfigType::Int, ExtensionConfigValue::Int(_))
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ExtensionConfigField {
+    pub key: String,
+    pub label: String,
+    pub field_type: ExtensionConfigType,
+    pub default: Option<ExtensionConfigValue>,
+    /// Restricts the value to the given options, empty allows every value of the field type
+    pub allowed_values: Vec<ExtensionConfigValue>,
+}
+
+impl ExtensionConfigField {
+    pub fn new(key: &str, label: &str, field_type: ExtensionConfigType) -> Self {
+        ExtensionConfigField {
+            key: key.to_string(),
+            label: label.to_string(),
+            field_type,
+            default: None,
+            allowed_values: Vec::new(),
+        }
+    }
+
+    pub fn bool(key: &str, label: &str) -> Self {
+        ExtensionConfigField::new(key, label, ExtensionConfigType::Bool)
+    }
+
+    pub fn string(key: &str, label: &str) -> Self {
+        ExtensionConfigField::new(key, label, ExtensionConfigType::String)
+    }
+
+    pub fn float(key: &str, label: &str) -> Self {
+        ExtensionConfigField::new(key, label, ExtensionConfigType::Float)
+    }
+
+    pub fn int(key: &str, label: &str) -> Self {
+        ExtensionConfigField::new(key, label, ExtensionConfigType::Int)
+    }
+
+    pub fn default_value<V: Into<ExtensionConfigValue>>(mut self, value: V) -> Self {
+        self.default = Some(value.into());
+        self
+    }
+
+    pub fn allowed_values<V: Into<ExtensionConfigValue>>(mut self, values: Vec<V>) -> Self {
+        self.allowed_values = values.into_iter().map(V::into).collect();
+        self
+    }
+
+    fn validate(&self, value: &ExtensionConfigValue) -> Result<(), Error> {
+        if !self.field_type.matches(value) {
+            bail!(
+                "Invalid value {:?} for {}, expected {:?}",
+                value,
+                self.key,
+                self.field_type
+            );
+        }
+        if !self.allowed_values.is_empty() && !self.allowed_values.contains(value) {
+            bail!(
+                "Invalid value {:?} for {}, allowed values are {:?}",
+                value,
+                self.key,
+                self.allowed_values
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Describes the config values an extension accepts
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct ExtensionConfigSchema {
+    pub fields: Vec<ExtensionConfigField>,
+}
+
+impl ExtensionConfigSchema {
+    pub fn new() -> Self {
+        ExtensionConfigSchema::default()
+    }
+
+    pub fn field(mut self, field: ExtensionConfigField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Checks that all values belong to a field and match its type and allowed values
+    pub fn validate(&self, config: &ExtensionConfig) -> Result<(), Error> {
+        for (key, value) in config {
+            match self.fields.iter().find(|field| &field.key == key) {
+                Some(field) => field.validate(value)?,
+                None => bail!("Unknown config key {}", key),
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the changes to the config and validates the result
+    ///
+    /// Values which are not part of the update stay unchanged.
+    pub fn apply(
+        &self,
+        mut config: ExtensionConfig,
+        update: ExtensionConfigUpdate,
+    ) -> Result<ExtensionConfig, Error> {
+        for (key, value) in update {
+            match value {
+                Some(value) => {
+                    config.insert(key, value);
+                }
+                None if self.fields.iter().any(|field| field.key == key) => {
+                    config.remove(&key);
+                }
+                None => bail!("Unknown config key {}", key),
+            }
+        }
+        self.validate(&config)?;
+
+        Ok(config)
+    }
+
+    /// Fills in the defaults of all fields missing in the given config
+    pub fn with_defaults(&self, mut config: ExtensionConfig) -> ExtensionConfig {
+        for field in &self.fields {
+            if let Some(ref default) = field.default {
+                config
+                    .entry(field.key.clone())
+                    .or_insert_with(|| default.clone());
+            }
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::api::ExtensionConfigValue;
+
+    use super::{ExtensionConfigField, ExtensionConfigSchema};
+
+    fn schema() -> ExtensionConfigSchema {
+        ExtensionConfigSchema::new().field(
+            ExtensionConfigField::string("level", "Level")
+                .default_value("uwu")
+                .allowed_values(vec!["owo", "uwu", "uvu"]),
+        )
+    }
+
+    #[test]
+    fn validate_should_reject_values_which_are_not_allowed() {
+        let mut config = HashMap::new();
+        config.insert("level".to_string(), ExtensionConfigValue::from("uwo"));
+
+        assert!(schema().validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_should_reject_unknown_keys() {
+        let mut config = HashMap::new();
+        config.insert("volume".to_string(), ExtensionConfigValue::Int(1));
+
+        assert!(schema().validate(&config).is_err());
+    }
+
+    #[test]
+    fn apply_should_keep_values_missing_in_the_update() {
+        let schema = schema().field(ExtensionConfigField::int("volume", "Volume"));
+        let mut config = HashMap::new();
+        config.insert("volume".to_string(), ExtensionConfigValue::Int(1));
+        let mut update = HashMap::new();
+        update.insert("level".to_string(), Some(ExtensionConfigValue::from("owo")));
+
+        let config = schema.apply(config, update).unwrap();
+
+        assert_eq!(config["volume"], ExtensionConfigValue::Int(1));
+        assert_eq!(config["level"], ExtensionConfigValue::from("owo"));
+    }
+
+    #[test]
+    fn apply_should_reset_values_to_the_default() {
+        let mut config = HashMap::new();
+        config.insert("level".to_string(), ExtensionConfigValue::from("owo"));
+        let mut update = HashMap::new();
+        update.insert("level".to_string(), None);
+
+        let config = schema().apply(config, update).unwrap();
+
+        assert!(!config.contains_key("level"));
+        assert_eq!(
+            schema().with_defaults(config)["level"],
+            ExtensionConfigValue::from("uwu")
+        );
+    }
+
+    #[test]
+    fn apply_should_reject_resetting_unknown_keys() {
+        let mut update = HashMap::new();
+        update.insert("volume".to_string(), None);
+
+        assert!(schema().apply(HashMap::new(), update).is_err());
+    }
+
+    #[test]
+    fn with_defaults_should_fill_missing_values() {
+        let config = schema().with_defaults(HashMap::new());
+
+        assert_eq!(config["level"], ExtensionConfigValue::from("uwu"));
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use failure::Fail;
+
+/// Number of consecutive crashes or timeouts after which an extension gets disabled
+const MAX_CONSECUTIVE_FAILURES: usize = 3;
+
+pub const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Fail)]
+pub enum ExtensionCallError {
+    #[fail(display = "Extension did not respond within {}ms", _0)]
+    Timeout(u128),
+    #[fail(display = "Extension crashed while handling the call")]
+    Crashed,
+}
+
+/// Latency of all calls of one kind to an extension
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtensionCallMetrics {
+    pub call: String,
+    pub calls: u64,
+    pub failures: u64,
+    pub total_time: Duration,
+    pub max_time: Duration,
+}
+
+impl ExtensionCallMetrics {
+    pub fn average_time(&self) -> Duration {
+        if self.calls == 0 {
+            Duration::default()
+        } else {
+            self.total_time / self.calls as u32
+        }
+    }
+
+    fn record(&mut self, duration: Duration) {
+        self.calls += 1;
+        self.total_time += duration;
+        self.max_time = self.max_time.max(duration);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionHealthReport {
+    /// Set when the extension was disabled because it misbehaved
+    pub disabled_reason: Option<String>,
+    pub metrics: Vec<ExtensionCallMetrics>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ExtensionHealth {
+    pub(crate) timeout: Duration,
+    consecutive_failures: AtomicUsize,
+    disabled_reason: Mutex<Option<String>>,
+    metrics: Mutex<HashMap<&'static str, ExtensionCallMetrics>>,
+}
+
+impl ExtensionHealth {
+    pub fn new(timeout: Duration) -> Self {
+        ExtensionHealth {
+            timeout,
+            consecutive_failures: AtomicUsize::default(),
+            disabled_reason: Mutex::default(),
+            metrics: Mutex::default(),
+        }
+    }
+
+    pub fn record_success(&self, call: &'static str, duration: Duration) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        self.metrics(call, |metrics| metrics.record(duration));
+    }
+
+    /// Returns true when the extension failed too often and should be disabled
+    pub fn record_failure(
+        &self,
+        call: &'static str,
+        duration: Duration,
+        error: &ExtensionCallError,
+    ) -> bool {
+        self.metrics(call, |metrics| {
+            metrics.record(duration);
+            metrics.failures += 1;
+        });
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < MAX_CONSECUTIVE_FAILURES {
+            return false;
+        }
+        let mut reason = self.disabled_reason.lock().unwrap();
+        if reason.is_some() {
+            return false;
+        }
+        *reason = Some(format!(
+            "Disabled after {} consecutive failures, last error in {}: {}",
+            failures, call, error
+        ));
+        true
+    }
+
+    /// Forgets previous failures, called when the extension is enabled again
+    pub fn reset(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        self.disabled_reason.lock().unwrap().take();
+    }
+
+    pub fn report(&self) -> ExtensionHealthReport {
+        let mut metrics: Vec<_> = self.metrics.lock().unwrap().values().cloned().collect();
+        metrics.sort_by(|lhs, rhs| lhs.call.cmp(&rhs.call));
+
+        ExtensionHealthReport {
+            disabled_reason: self.disabled_reason.lock().unwrap().clone(),
+            metrics,
+        }
+    }
+
+    fn metrics<F: FnOnce(&mut ExtensionCallMetrics)>(&self, call: &'static str, update: F) {
+        let mut metrics = self.metrics.lock().unwrap();
+        let metrics = metrics.entry(call).or_insert_with(|| ExtensionCallMetrics {
+            call: call.to_string(),
+            ..ExtensionCallMetrics::default()
+        });
+        update(metrics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{ExtensionCallError, ExtensionHealth, DEFAULT_CALL_TIMEOUT};
+
+    #[test]
+    fn record_failure_should_disable_after_consecutive_failures() {
+        let health = ExtensionHealth::new(DEFAULT_CALL_TIMEOUT);
+        let error = ExtensionCallError::Crashed;
+
+        assert!(!health.record_failure("resolve_track", Duration::default(), &error));
+        assert!(!health.record_failure("resolve_track", Duration::default(), &error));
+        assert!(health.record_failure("resolve_track", Duration::default(), &error));
+        assert!(health.report().disabled_reason.is_some());
+    }
+
+    #[test]
+    fn record_success_should_reset_consecutive_failures() {
+        let health = ExtensionHealth::new(DEFAULT_CALL_TIMEOUT);
+        let error = ExtensionCallError::Timeout(5000);
+
+        health.record_failure("resolve_track", Duration::default(), &error);
+        health.record_failure("resolve_track", Duration::default(), &error);
+        health.record_success("resolve_track", Duration::default());
+
+        assert!(!health.record_failure("resolve_track", Duration::default(), &error));
+    }
+
+    #[test]
+    fn report_should_contain_call_metrics() {
+        let health = ExtensionHealth::new(DEFAULT_CALL_TIMEOUT);
+
+        health.record_success("resolve_track", Duration::from_millis(10));
+        health.record_success("resolve_track", Duration::from_millis(30));
+
+        let report = health.report();
+        let metrics = &report.metrics[0];
+        assert_eq!(metrics.calls, 2);
+        assert_eq!(metrics.max_time, Duration::from_millis(30));
+        assert_eq!(metrics.average_time(), Duration::from_millis(20));
+    }
+}
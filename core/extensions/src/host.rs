@@ -1,27 +1,67 @@
 use crate::plugin::ExtensionCommand;
 use crate::ExtensionConfigValue;
 use failure::format_err;
+use futures::future::{AbortHandle, AbortRegistration, Abortable};
+use futures::{select_biased, FutureExt};
 use rustic_queue::{broadcast, Sender};
 use std::collections::HashMap;
 use std::ffi::OsStr;
+use std::panic::AssertUnwindSafe;
+
+type HostMessage = (ExtensionCommand, AbortRegistration);
 
 pub struct ExtensionHost {
-    extension: Sender<ExtensionCommand>,
+    extension: Sender<HostMessage>,
+    /// Notifications have their own queue so a backlog of them doesn't hold back calls
+    notifications: Sender<HostMessage>,
     task: tokio::task::JoinHandle<Option<u8>>,
-    library: libloading::Library,
+    /// Native extensions have to keep their library loaded, wasm extensions don't have one
+    library: Option<libloading::Library>,
 }
 
 impl ExtensionHost {
-    pub fn new((library, mut plugin): (libloading::Library, Box<dyn ExtensionPlugin>)) -> Self {
+    pub fn new((library, plugin): (libloading::Library, Box<dyn ExtensionPlugin>)) -> Self {
+        ExtensionHost::spawn(Some(library), plugin)
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn wasm(plugin: crate::wasm::WasmPlugin) -> Self {
+        ExtensionHost::spawn(None, Box::new(plugin))
+    }
+
+    /// Hosts a plugin which is compiled into the host, used for testing
+    #[cfg(test)]
+    pub(crate) fn plugin(plugin: Box<dyn ExtensionPlugin>) -> Self {
+        ExtensionHost::spawn(None, plugin)
+    }
+
+    fn spawn(library: Option<libloading::Library>, mut plugin: Box<dyn ExtensionPlugin>) -> Self {
         let (tx, rx) = broadcast();
+        let (notifications_tx, notifications_rx) = broadcast();
         ExtensionHost {
             library,
             extension: tx,
+            notifications: notifications_tx,
             task: tokio::spawn(async move {
-                while let Ok(message) = rx.recv_async().await {
+                loop {
+                    // calls are handled first, notifications only in between
+                    let message = select_biased! {
+                        message = rx.recv_async().fuse() => message,
+                        message = notifications_rx.recv_async().fuse() => message,
+                    };
+                    let (message, abort) = match message {
+                        Ok(message) => message,
+                        Err(_) => break,
+                    };
                     log::trace!("delegating message to plugin handler {:?}", message);
-                    if let Some(status) = plugin.handle_message(message).await {
-                        return Some(status);
+                    let command = message.name();
+                    let handler = Abortable::new(plugin.handle_message(message), abort);
+                    // a panic drops the response sender, so the caller gets an error instead of waiting forever
+                    match AssertUnwindSafe(handler).catch_unwind().await {
+                        Ok(Ok(Some(status))) => return Some(status),
+                        Ok(Ok(None)) => {}
+                        Ok(Err(_)) => log::warn!("Cancelled {} after it timed out", command),
+                        Err(_) => log::error!("Extension panicked while handling {}", command),
                     }
                 }
                 None
@@ -29,8 +69,15 @@ impl ExtensionHost {
         }
     }
 
-    pub async fn send(&mut self, message: ExtensionCommand) {
-        self.extension.send_async(message).await;
+    /// Queues the message, the returned handle cancels handling it
+    pub async fn send(&mut self, message: ExtensionCommand) -> AbortHandle {
+        let (handle, registration) = AbortHandle::new_pair();
+        if message.is_notification() {
+            self.notifications.send_async((message, registration)).await;
+        } else {
+            self.extension.send_async((message, registration)).await;
+        }
+        handle
     }
 }
 
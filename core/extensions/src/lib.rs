@@ -1,9 +1,14 @@
 mod api;
+mod config;
+mod health;
 pub mod host;
 mod macros;
 mod manager;
 mod plugin;
+mod provider;
 mod runtime;
+#[cfg(feature = "wasm")]
+mod wasm;
 mod controls;
 
 pub use self::api::*;
@@ -13,3 +18,8 @@ pub use self::manager::*;
 pub use self::plugin::*;
 pub use self::runtime::ExtensionRuntime;
 pub use self::controls::*;
+pub use self::health::{
+    ExtensionCallError, ExtensionCallMetrics, ExtensionHealthReport, DEFAULT_CALL_TIMEOUT,
+};
+#[cfg(feature = "wasm")]
+pub use self::wasm::{WasmLimits, ABI_VERSION};
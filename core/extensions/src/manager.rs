@@ -5,59 +5,175 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use failure::{bail, Error};
+use futures::future::{AbortHandle, Future, FutureExt};
+use futures::stream::{self, StreamExt};
 use log::{info, trace};
-use rustic_queue::{one_shot, Receiver};
+use rustic_queue::{broadcast, one_shot, Receiver, Sender};
 use tokio::sync::Mutex;
 
-use rustic_core::{Album, Artist, Playlist, StorageCollection, Track};
+use rustic_core::history::PlayEvent;
+use rustic_core::library::{MetaValue, PlayRecord};
+use rustic_core::{
+    Album, Artist, Library, LibraryChange, PlayerEvent, PlayerState, Playlist, QueuedTrack,
+    StorageCollection, Track,
+};
 
 use crate::api::*;
 use crate::host::{construct_plugin, ExtensionHost};
 use crate::plugin::*;
 use crate::runtime::ExtensionRuntime;
 use crate::controls::*;
-use std::time::Instant;
+use crate::health::*;
+#[cfg(feature = "wasm")]
+use crate::wasm::{WasmLimits, WasmPlugin};
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
 
 const EXTENSION_COLLECTION_KEY: &str = "extensions";
+const EXTENSION_CONFIG_COLLECTION_KEY: &str = "extension-config";
+
+enum Notification {
+    Play(PlayEvent),
+    Player(String, PlayerEvent),
+    Library(LibraryChange),
+}
 
 #[derive(Clone)]
-pub struct HostedExtension(
-    ExtensionMetadata,
-    Arc<AtomicBool>,
-    Arc<Mutex<ExtensionHost>>,
-);
+pub struct HostedExtension {
+    pub(crate) metadata: ExtensionMetadata,
+    enabled: Arc<AtomicBool>,
+    host: Arc<Mutex<ExtensionHost>>,
+    /// Notifies the manager about extensions which have to be disabled because they misbehave
+    disable_sender: Sender<String>,
+    /// The configured values without the defaults of the schema
+    config: Arc<std::sync::Mutex<ExtensionConfig>>,
+    health: Arc<ExtensionHealth>,
+}
+
+/// Everything the api exposes about an extension
+#[derive(Debug, Clone)]
+pub struct ExtensionDetails {
+    pub metadata: ExtensionMetadata,
+    pub enabled: bool,
+    pub controls: ExtensionControls,
+    pub config: ExtensionConfig,
+    pub health: ExtensionHealthReport,
+}
 
 impl HostedExtension {
+    fn new(
+        metadata: ExtensionMetadata,
+        host: ExtensionHost,
+        config: ExtensionConfig,
+        call_timeout: Duration,
+        disable_sender: Sender<String>,
+    ) -> Self {
+        HostedExtension {
+            metadata,
+            enabled: Arc::new(AtomicBool::new(false)),
+            host: Arc::new(Mutex::new(host)),
+            disable_sender,
+            config: Arc::new(std::sync::Mutex::new(config)),
+            health: Arc::new(ExtensionHealth::new(call_timeout)),
+        }
+    }
+
     pub fn get_metadata(&self) -> (ExtensionMetadata, bool) {
-        (self.0.clone(), self.1.load(Ordering::Relaxed))
+        (self.metadata.clone(), self.enabled.load(Ordering::Relaxed))
+    }
+
+    /// The current config including the defaults of the schema
+    pub fn get_config(&self) -> ExtensionConfig {
+        let config = self.config.lock().unwrap().clone();
+        self.metadata.config.with_defaults(config)
+    }
+
+    /// Validates the given changes and pushes the merged config to the extension
+    ///
+    /// Returns the merged config without defaults so it can be persisted.
+    async fn update_config(&self, update: ExtensionConfigUpdate) -> Result<ExtensionConfig, Error> {
+        let config = self.config.lock().unwrap().clone();
+        let merged = self.metadata.config.apply(config, update)?;
+        let (tx, rx) = one_shot();
+        self.rpc(
+            ExtensionCommand::UpdateConfig(self.metadata.config.with_defaults(merged.clone()), tx),
+            rx,
+        )
+        .await?;
+        *self.config.lock().unwrap() = merged.clone();
+
+        Ok(merged)
     }
 
     pub fn is_enabled(&self) -> bool {
-        self.1.load(Ordering::Relaxed)
+        self.enabled.load(Ordering::Relaxed)
     }
 
-    pub async fn send(&self, message: ExtensionCommand) {
-        let mut host = self.2.lock().await;
-        host.send(message).await;
+    /// Queues the message, the returned handle cancels handling it
+    pub async fn send(&self, message: ExtensionCommand) -> AbortHandle {
+        let mut host = self.host.lock().await;
+        host.send(message).await
     }
 
+    pub fn get_health(&self) -> ExtensionHealthReport {
+        self.health.report()
+    }
+
+    pub(crate) fn call_timeout(&self) -> Duration {
+        self.health.timeout
+    }
+
+    /// Sends the message and waits for the response
+    ///
+    /// Calls which time out are cancelled so they don't hold back the following ones.
     async fn rpc<T: 'static>(
         &self,
         message: ExtensionCommand,
         rx: Receiver<Result<T, Error>>,
     ) -> Result<T, Error> {
-        self.send(message).await;
-        rx.recv_async().await?
+        let call = message.name();
+        let handle = self.send(message).await;
+        let response = rx.recv_async().map(Result::ok);
+        let result = self.supervise(call, self.health.timeout, response).await;
+        // finished calls are not affected, only a call which timed out is cancelled
+        handle.abort();
+        result
     }
-}
 
-impl From<(ExtensionMetadata, ExtensionHost)> for HostedExtension {
-    fn from((metadata, host): (ExtensionMetadata, ExtensionHost)) -> Self {
-        HostedExtension(
-            metadata,
-            Arc::new(AtomicBool::new(false)),
-            Arc::new(Mutex::new(host)),
-        )
+    /// Waits for a call into the extension and tracks its health
+    ///
+    /// The call resolves to `None` when the extension crashed while handling it.
+    /// Extensions which crash or time out repeatedly get disabled.
+    pub(crate) async fn supervise<T, F>(
+        &self,
+        call: &'static str,
+        call_timeout: Duration,
+        response: F,
+    ) -> Result<T, Error>
+    where
+        F: Future<Output = Option<Result<T, Error>>>,
+    {
+        let stopwatch = Instant::now();
+        let error = match timeout(call_timeout, response).await {
+            Ok(Some(result)) => {
+                self.health.record_success(call, stopwatch.elapsed());
+                return result;
+            }
+            Ok(None) => ExtensionCallError::Crashed,
+            Err(_) => ExtensionCallError::Timeout(call_timeout.as_millis()),
+        };
+        if self
+            .health
+            .record_failure(call, stopwatch.elapsed(), &error)
+        {
+            log::error!(
+                "Disabling extension {} because it failed repeatedly: {}",
+                &self.metadata.name,
+                error
+            );
+            let _ = self.disable_sender.send(self.metadata.id.clone());
+        }
+        Err(error.into())
     }
 }
 
@@ -66,17 +182,18 @@ impl ExtensionApi for HostedExtension {
     async fn on_enable(&self) -> Result<(), Error> {
         let (tx, rx) = one_shot();
         self.rpc(ExtensionCommand::Enable(tx), rx).await?;
-        self.1.store(true, Ordering::Relaxed);
-        info!("Enabled {} extension", &self.0.name);
+        self.health.reset();
+        self.enabled.store(true, Ordering::Relaxed);
+        info!("Enabled {} extension", &self.metadata.name);
         Ok(())
     }
 
+    /// The extension is disabled even when it fails to handle it
     async fn on_disable(&self) -> Result<(), Error> {
+        self.enabled.store(false, Ordering::Relaxed);
+        info!("Disabled {} extension", &self.metadata.name);
         let (tx, rx) = one_shot();
-        self.rpc(ExtensionCommand::Disable(tx), rx).await?;
-        self.1.store(false, Ordering::Relaxed);
-        info!("Disabled {} extension", &self.0.name);
-        Ok(())
+        self.rpc(ExtensionCommand::Disable(tx), rx).await
     }
 
     async fn get_controls(&self) -> Result<ExtensionControls, Error> {
@@ -84,12 +201,55 @@ impl ExtensionApi for HostedExtension {
         self.rpc(ExtensionCommand::GetControls(tx), rx).await
     }
 
+    async fn invoke_action(&self, key: String) -> Result<(), Error> {
+        let (tx, rx) = one_shot();
+        self.rpc(ExtensionCommand::InvokeAction(key, tx), rx).await
+    }
+
 
     async fn on_add_to_queue(&self, player_id: String, tracks: Vec<Track>) -> Result<Vec<Track>, Error> {
         let (tx, rx) = one_shot();
         self.rpc(ExtensionCommand::AddToQueue(player_id, tracks, tx), rx).await
     }
 
+    async fn on_play_started(&self, player_id: String, track: Track) -> Result<(), Error> {
+        self.send(ExtensionCommand::PlayStarted(player_id, track))
+            .await;
+        Ok(())
+    }
+
+    async fn on_play_finished(&self, play: PlayRecord) -> Result<(), Error> {
+        self.send(ExtensionCommand::PlayFinished(play)).await;
+        Ok(())
+    }
+
+    async fn on_state_changed(&self, player_id: String, state: PlayerState) -> Result<(), Error> {
+        self.send(ExtensionCommand::StateChanged(player_id, state))
+            .await;
+        Ok(())
+    }
+
+    async fn on_volume_changed(&self, player_id: String, volume: f32) -> Result<(), Error> {
+        self.send(ExtensionCommand::VolumeChanged(player_id, volume))
+            .await;
+        Ok(())
+    }
+
+    async fn on_queue_updated(
+        &self,
+        player_id: String,
+        queue: Vec<QueuedTrack>,
+    ) -> Result<(), Error> {
+        self.send(ExtensionCommand::QueueUpdated(player_id, queue))
+            .await;
+        Ok(())
+    }
+
+    async fn on_library_event(&self, change: LibraryChange) -> Result<(), Error> {
+        self.send(ExtensionCommand::LibraryChanged(change)).await;
+        Ok(())
+    }
+
     async fn resolve_track(&self, track: Track) -> Result<Track, Error> {
         let (tx, rx) = one_shot();
         self.rpc(ExtensionCommand::ResolveTrack(track, tx), rx)
@@ -113,14 +273,50 @@ impl ExtensionApi for HostedExtension {
         self.rpc(ExtensionCommand::ResolvePlaylist(playlist, tx), rx)
             .await
     }
+
+    async fn resolve_tracks(&self, tracks: Vec<Track>) -> Result<Vec<Track>, Error> {
+        let (tx, rx) = one_shot();
+        self.rpc(ExtensionCommand::ResolveTracks(tracks, tx), rx).await
+    }
+
+    async fn resolve_albums(&self, albums: Vec<Album>) -> Result<Vec<Album>, Error> {
+        let (tx, rx) = one_shot();
+        self.rpc(ExtensionCommand::ResolveAlbums(albums, tx), rx).await
+    }
+
+    async fn resolve_artists(&self, artists: Vec<Artist>) -> Result<Vec<Artist>, Error> {
+        let (tx, rx) = one_shot();
+        self.rpc(ExtensionCommand::ResolveArtists(artists, tx), rx).await
+    }
+
+    async fn resolve_playlists(&self, playlists: Vec<Playlist>) -> Result<Vec<Playlist>, Error> {
+        let (tx, rx) = one_shot();
+        self.rpc(ExtensionCommand::ResolvePlaylists(playlists, tx), rx)
+            .await
+    }
 }
 
 #[derive(Default)]
 pub struct ExtensionManagerBuilder {
-    extensions: Vec<(ExtensionMetadata, ExtensionHost)>,
+    extensions: Vec<(ExtensionMetadata, ExtensionHost, ExtensionConfig)>,
+    call_timeout: Option<Duration>,
+    #[cfg(feature = "wasm")]
+    wasm_limits: WasmLimits,
 }
 
 impl ExtensionManagerBuilder {
+    /// Overrides how long to wait for an extension to answer, defaults to [DEFAULT_CALL_TIMEOUT]
+    pub fn with_call_timeout(mut self, timeout: Duration) -> Self {
+        self.call_timeout = Some(timeout);
+        self
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn with_wasm_limits(mut self, limits: WasmLimits) -> Self {
+        self.wasm_limits = limits;
+        self
+    }
+
     pub async fn load_dir(
         &mut self,
         dir: &Path,
@@ -140,6 +336,7 @@ impl ExtensionManagerBuilder {
                 file_name.ends_with("extension.so")
                     || file_name.ends_with("extension.dylib")
                     || file_name.ends_with("extension.dll")
+                    || (cfg!(feature = "wasm") && file_name.ends_with("extension.wasm"))
             })
             .collect();
         for entry in extensions {
@@ -154,6 +351,10 @@ impl ExtensionManagerBuilder {
         path: &Path,
         config: &HashMap<String, HashMap<String, ExtensionConfigValue>>,
     ) -> Result<(), Error> {
+        #[cfg(feature = "wasm")]
+        if path.extension().map(|extension| extension == "wasm").unwrap_or_default() {
+            return self.load_wasm(path, config);
+        }
         let plugin = construct_plugin(&path, config)?;
         let mut host = ExtensionHost::new(plugin);
         let (tx, rx) = one_shot();
@@ -161,18 +362,45 @@ impl ExtensionManagerBuilder {
 
         let metadata = rx.recv_async().await?;
         info!("Loaded Extension: {} v{}", metadata.name, metadata.version);
-        self.extensions.push((metadata, host));
+        let extension_config = config.get(&metadata.id).cloned().unwrap_or_default();
+        self.extensions.push((metadata, host, extension_config));
+        Ok(())
+    }
+
+    #[cfg(feature = "wasm")]
+    fn load_wasm(
+        &mut self,
+        path: &Path,
+        config: &HashMap<String, HashMap<String, ExtensionConfigValue>>,
+    ) -> Result<(), Error> {
+        let plugin = WasmPlugin::load(path, config, self.wasm_limits)?;
+        let metadata = plugin.metadata().clone();
+        info!("Loaded Wasm Extension: {} v{}", metadata.name, metadata.version);
+        let extension_config = config.get(&metadata.id).cloned().unwrap_or_default();
+        let host = ExtensionHost::wasm(plugin);
+        self.extensions.push((metadata, host, extension_config));
         Ok(())
     }
 
     pub fn build(self) -> ExtensionManager {
+        let call_timeout = self.call_timeout.unwrap_or(DEFAULT_CALL_TIMEOUT);
+        let (disable_sender, disable_receiver) = broadcast();
         ExtensionManager {
             extensions: self
                 .extensions
                 .into_iter()
-                .map(HostedExtension::from)
+                .map(|(metadata, host, config)| {
+                    HostedExtension::new(
+                        metadata,
+                        host,
+                        config,
+                        call_timeout,
+                        disable_sender.clone(),
+                    )
+                })
                 .collect(),
             runtime: None,
+            disable_receiver,
         }
     }
 }
@@ -181,6 +409,8 @@ impl ExtensionManagerBuilder {
 pub struct ExtensionManager {
     extensions: Vec<HostedExtension>,
     runtime: Option<ExtensionRuntime>,
+    /// Ids of extensions which failed repeatedly
+    disable_receiver: Receiver<String>,
 }
 
 impl std::fmt::Debug for ExtensionManager {
@@ -199,12 +429,25 @@ impl ExtensionManager {
             .collect()
     }
 
-    pub async fn get_extensions_with_controls(&self) -> Result<Vec<(ExtensionMetadata, bool, ExtensionControls)>, Error> {
+    /// A broken extension doesn't fail the whole list, it's just listed without controls
+    pub async fn get_extension_details(&self) -> Result<Vec<ExtensionDetails>, Error> {
         let mut extensions = Vec::with_capacity(self.extensions.len());
         for extension in self.extensions.iter() {
             let (metadata, enabled) = extension.get_metadata();
-            let controls = extension.get_controls().await?;
-            extensions.push((metadata, enabled, controls));
+            let controls = match extension.get_controls().await {
+                Ok(controls) => controls,
+                Err(e) => {
+                    log::warn!("Fetching controls of extension {} failed: {:?}", &metadata.name, e);
+                    ExtensionControls::default()
+                }
+            };
+            extensions.push(ExtensionDetails {
+                metadata,
+                enabled,
+                controls,
+                config: extension.get_config(),
+                health: extension.get_health(),
+            });
         }
 
         Ok(extensions)
@@ -215,30 +458,152 @@ impl ExtensionManager {
             .storage
             .open_collection(EXTENSION_COLLECTION_KEY)
             .await?;
+        let config_collection = runtime
+            .storage
+            .open_collection(EXTENSION_CONFIG_COLLECTION_KEY)
+            .await?;
         for extension in self.extensions.iter() {
             let (tx, rx) = one_shot();
             extension
                 .rpc(
-                    ExtensionCommand::Setup(runtime.for_extension(extension.0.clone()), tx),
+                    ExtensionCommand::Setup(runtime.for_extension(extension), tx),
                     rx,
                 )
                 .await?;
-            match collection.read(&extension.0.id).await? {
+            if let Some(config) = config_collection.read(&extension.metadata.id).await? {
+                let config = config.string().unwrap_or_default();
+                let config: ExtensionConfig = serde_json::from_str(&config)?;
+                // the stored config is complete, values missing in it have been reset
+                let mut update: ExtensionConfigUpdate = extension
+                    .metadata
+                    .config
+                    .fields
+                    .iter()
+                    .map(|field| (field.key.clone(), None))
+                    .collect();
+                update.extend(config.into_iter().map(|(key, value)| (key, Some(value))));
+                if let Err(e) = extension.update_config(update).await {
+                    log::warn!(
+                        "Applying stored config of extension {} failed: {:?}",
+                        &extension.metadata.name,
+                        e
+                    );
+                }
+            }
+            match collection.read(&extension.metadata.id).await? {
                 Some(value) if value.bool().unwrap_or_default() => {
                     extension.on_enable().await?;
+                    runtime.enable_providers(&extension.metadata.id).await;
                 }
                 _ => {}
             }
         }
+        self.observe_events(&runtime);
         self.runtime = Some(runtime);
+        self.disable_failing_extensions();
+        Ok(())
+    }
+
+    /// Extensions which failed repeatedly are disabled like through the api,
+    /// so their providers are removed and they stay disabled after a restart
+    fn disable_failing_extensions(&self) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            while let Ok(id) = manager.disable_receiver.recv_async().await {
+                if let Err(e) = manager.disable_extension(&id).await {
+                    log::error!("Disabling extension {} failed: {:?}", id, e);
+                }
+            }
+        });
+    }
+
+    /// Forwards player, play and library events to the enabled extensions
+    ///
+    /// Notifications are queued per extension without waiting for them to be handled,
+    /// so a slow extension can't hold back the others.
+    fn observe_events(&self, runtime: &ExtensionRuntime) {
+        let plays = runtime.observe_plays().map(Notification::Play);
+        let players = runtime
+            .observe_players()
+            .map(|(player_id, event)| Notification::Player(player_id, event));
+        let library = runtime.observe().map(Notification::Library);
+        let mut notifications = stream::select(plays, stream::select(players, library));
+        let manager = self.clone();
+        tokio::spawn(async move {
+            while let Some(notification) = notifications.next().await {
+                if let Err(e) = manager.notify(notification).await {
+                    log::error!("Notifying extensions failed: {:?}", e);
+                }
+            }
+        });
+    }
+
+    async fn notify(&self, notification: Notification) -> Result<(), Error> {
+        match notification {
+            Notification::Play(PlayEvent::Started { player, track }) => {
+                self.on_play_started(player, track).await
+            }
+            Notification::Play(PlayEvent::Finished(play)) => self.on_play_finished(play).await,
+            Notification::Player(player_id, PlayerEvent::StateChanged(state)) => {
+                self.on_state_changed(player_id, state).await
+            }
+            Notification::Player(player_id, PlayerEvent::VolumeChanged(volume)) => {
+                self.on_volume_changed(player_id, volume).await
+            }
+            Notification::Player(player_id, PlayerEvent::QueueUpdated(queue)) => {
+                self.on_queue_updated(player_id, queue).await
+            }
+            // track changes are covered by the play events
+            Notification::Player(_, _) => Ok(()),
+            Notification::Library(change) => self.on_library_event(change).await,
+        }
+    }
+
+    pub async fn invoke_extension_action(&self, id: &str, key: &str) -> Result<(), Error> {
+        match self.get_enabled_extensions().find(|e| e.metadata.id == id) {
+            Some(extension) => {
+                trace!(
+                    "Invoking action {} of extension {}",
+                    key,
+                    &extension.metadata.name
+                );
+                extension.invoke_action(key.to_string()).await
+            }
+            None => bail!("Extension {} is not enabled", id),
+        }
+    }
+
+    /// Validates, applies and persists the given config values
+    ///
+    /// Values which are not part of the given config stay unchanged, `None` resets a value to its default.
+    pub async fn update_extension_config(
+        &self,
+        id: &str,
+        config: ExtensionConfigUpdate,
+    ) -> Result<(), Error> {
+        let extension = match self.extensions.iter().find(|e| e.metadata.id == id) {
+            Some(extension) => extension,
+            None => bail!("Unknown extension {}", id),
+        };
+        let config = extension.update_config(config).await?;
+        let collection = self
+            .get_collection(EXTENSION_CONFIG_COLLECTION_KEY)
+            .await?;
+        collection
+            .write(id, MetaValue::String(serde_json::to_string(&config)?))
+            .await?;
+
         Ok(())
     }
 
     pub async fn enable_extension(&self, id: &str) -> Result<(), Error> {
         let collection = self.get_extensions_collection().await?;
-        if let Some(extension) = self.extensions.iter().find(|e| e.0.id == id) {
-            trace!("Enabling extension {}...", &extension.0.name);
+        if let Some(extension) = self.extensions.iter().find(|e| e.metadata.id == id) {
+            trace!("Enabling extension {}...", &extension.metadata.name);
             extension.on_enable().await?;
+            if let Some(runtime) = self.runtime.as_ref() {
+                runtime.enable_providers(id).await;
+            }
             collection.write(id, true.into()).await?;
         }
         Ok(())
@@ -246,20 +611,68 @@ impl ExtensionManager {
 
     pub async fn disable_extension(&self, id: &str) -> Result<(), Error> {
         let collection = self.get_extensions_collection().await?;
-        if let Some(extension) = self.extensions.iter().find(|e| e.0.id == id) {
-            trace!("Disabling extension {}...", &extension.0.name);
-            extension.on_disable().await?;
+        if let Some(extension) = self.extensions.iter().find(|e| e.metadata.id == id) {
+            trace!("Disabling extension {}...", &extension.metadata.name);
+            if let Err(e) = extension.on_disable().await {
+                log::warn!(
+                    "Extension {} failed to handle being disabled: {:?}",
+                    &extension.metadata.name,
+                    e
+                );
+            }
+            if let Some(runtime) = self.runtime.as_ref() {
+                runtime.disable_providers(id);
+            }
             collection.write(id, false.into()).await?;
         }
         Ok(())
     }
 
     async fn get_extensions_collection(&self) -> Result<Box<dyn StorageCollection>, Error> {
+        self.get_collection(EXTENSION_COLLECTION_KEY).await
+    }
+
+    async fn get_collection(&self, name: &str) -> Result<Box<dyn StorageCollection>, Error> {
         if self.runtime.is_none() {
             bail!("Tried to store extension state before setup");
         }
         let runtime = self.runtime.as_ref().unwrap();
-        runtime.storage.open_collection("extensions").await
+        runtime.storage.open_collection(name).await
+    }
+
+    /// Passes the item through all enabled extensions
+    ///
+    /// When an extension fails the item is passed on unchanged, so one broken extension can't break browsing.
+    async fn resolve<'a, T, F, Fut>(
+        &'a self,
+        kind: &str,
+        mut item: T,
+        resolve: F,
+    ) -> Result<T, Error>
+    where
+        T: Clone,
+        F: Fn(&'a HostedExtension, T) -> Fut,
+        Fut: std::future::Future<Output = Result<T, Error>>,
+    {
+        for extension in self.get_enabled_extensions() {
+            let stopwatch = Instant::now();
+            match resolve(extension, item.clone()).await {
+                Ok(resolved) => item = resolved,
+                Err(e) => log::warn!(
+                    "Resolving {} with extension {} failed: {:?}",
+                    kind,
+                    extension.metadata.name,
+                    e
+                ),
+            }
+            trace!(
+                "Resolved {} from extension {} in {}ms",
+                kind,
+                extension.metadata.name,
+                stopwatch.elapsed().as_millis()
+            );
+        }
+        Ok(item)
     }
 
     pub fn get_enabled_extensions(&self) -> impl Iterator<Item = &HostedExtension> {
@@ -275,61 +688,597 @@ impl ExtensionApi for ExtensionManager {
         let mut tracks = tracks;
 
         for extension in self.get_enabled_extensions() {
-            tracks = extension.on_add_to_queue(player_id.clone(), tracks).await?;
+            match extension
+                .on_add_to_queue(player_id.clone(), tracks.clone())
+                .await
+            {
+                Ok(result) => tracks = result,
+                Err(e) => log::warn!(
+                    "Extension {} failed to handle added tracks: {:?}",
+                    extension.metadata.name,
+                    e
+                ),
+            }
         }
 
         Ok(tracks)
     }
 
-    async fn resolve_track(&self, mut track: Track) -> Result<Track, Error> {
+    async fn on_play_started(&self, player_id: String, track: Track) -> Result<(), Error> {
         for extension in self.get_enabled_extensions() {
-            let stopwatch = Instant::now();
-            track = extension.resolve_track(track).await?;
-            trace!(
-                "Resolved track from extension {} in {}ms",
-                extension.0.name,
-                stopwatch.elapsed().as_millis()
-            );
+            extension
+                .on_play_started(player_id.clone(), track.clone())
+                .await?;
         }
-        Ok(track)
+
+        Ok(())
     }
 
-    async fn resolve_album(&self, mut album: Album) -> Result<Album, Error> {
+    async fn on_play_finished(&self, play: PlayRecord) -> Result<(), Error> {
         for extension in self.get_enabled_extensions() {
-            let stopwatch = Instant::now();
-            album = extension.resolve_album(album).await?;
-            trace!(
-                "Resolved album from extension {} in {}ms",
-                extension.0.name,
-                stopwatch.elapsed().as_millis()
-            );
+            extension.on_play_finished(play.clone()).await?;
         }
-        Ok(album)
+
+        Ok(())
     }
 
-    async fn resolve_artist(&self, mut artist: Artist) -> Result<Artist, Error> {
+    async fn on_state_changed(&self, player_id: String, state: PlayerState) -> Result<(), Error> {
         for extension in self.get_enabled_extensions() {
-            let stopwatch = Instant::now();
-            artist = extension.resolve_artist(artist).await?;
-            trace!(
-                "Resolved artist from extension {} in {}ms",
-                extension.0.name,
-                stopwatch.elapsed().as_millis()
-            );
+            extension.on_state_changed(player_id.clone(), state).await?;
         }
-        Ok(artist)
+
+        Ok(())
     }
 
-    async fn resolve_playlist(&self, mut playlist: Playlist) -> Result<Playlist, Error> {
+    async fn on_volume_changed(&self, player_id: String, volume: f32) -> Result<(), Error> {
         for extension in self.get_enabled_extensions() {
-            let stopwatch = Instant::now();
-            playlist = extension.resolve_playlist(playlist).await?;
-            trace!(
-                "Resolved playlist from extension {} in {}ms",
-                extension.0.name,
-                stopwatch.elapsed().as_millis()
-            );
+            extension
+                .on_volume_changed(player_id.clone(), volume)
+                .await?;
         }
-        Ok(playlist)
+
+        Ok(())
+    }
+
+    async fn on_queue_updated(
+        &self,
+        player_id: String,
+        queue: Vec<QueuedTrack>,
+    ) -> Result<(), Error> {
+        for extension in self.get_enabled_extensions() {
+            extension
+                .on_queue_updated(player_id.clone(), queue.clone())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn on_library_event(&self, change: LibraryChange) -> Result<(), Error> {
+        for extension in self.get_enabled_extensions() {
+            extension.on_library_event(change.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn resolve_track(&self, track: Track) -> Result<Track, Error> {
+        self.resolve("track", track, |extension, track| extension.resolve_track(track))
+            .await
+    }
+
+    async fn resolve_album(&self, album: Album) -> Result<Album, Error> {
+        self.resolve("album", album, |extension, album| extension.resolve_album(album))
+            .await
+    }
+
+    async fn resolve_artist(&self, artist: Artist) -> Result<Artist, Error> {
+        self.resolve("artist", artist, |extension, artist| {
+            extension.resolve_artist(artist)
+        })
+        .await
+    }
+
+    async fn resolve_playlist(&self, playlist: Playlist) -> Result<Playlist, Error> {
+        self.resolve("playlist", playlist, |extension, playlist| {
+            extension.resolve_playlist(playlist)
+        })
+        .await
+    }
+
+    async fn resolve_tracks(&self, tracks: Vec<Track>) -> Result<Vec<Track>, Error> {
+        self.resolve("tracks", tracks, |extension, tracks| {
+            extension.resolve_tracks(tracks)
+        })
+        .await
+    }
+
+    async fn resolve_albums(&self, albums: Vec<Album>) -> Result<Vec<Album>, Error> {
+        self.resolve("albums", albums, |extension, albums| {
+            extension.resolve_albums(albums)
+        })
+        .await
+    }
+
+    async fn resolve_artists(&self, artists: Vec<Artist>) -> Result<Vec<Artist>, Error> {
+        self.resolve("artists", artists, |extension, artists| {
+            extension.resolve_artists(artists)
+        })
+        .await
+    }
+
+    async fn resolve_playlists(&self, playlists: Vec<Playlist>) -> Result<Vec<Playlist>, Error> {
+        self.resolve("playlists", playlists, |extension, playlists| {
+            extension.resolve_playlists(playlists)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use async_trait::async_trait;
+    use failure::Error;
+
+    use rustic_core::cache::CacheOptions;
+    use rustic_core::library::MetaValue;
+    use rustic_core::provider::{
+        Authentication, ProviderFolder, ProviderInstance, ProviderItem, ProviderState, SyncResult,
+    };
+    use rustic_core::{
+        Album, Artist, CredentialStore, Credentials, InternalUri, Playlist, Provider, ProviderId,
+        Rustic, SharedLibrary, SharedStorageBackend, StorageBackend, StorageCollection, Track,
+    };
+    use rustic_memory_store::MemoryLibrary;
+    use url::Url;
+
+    use crate::api::*;
+    use crate::host::ExtensionHost;
+    use crate::runtime::ExtensionRuntime;
+
+    use super::{
+        ExtensionManager, ExtensionManagerBuilder, EXTENSION_COLLECTION_KEY,
+        EXTENSION_CONFIG_COLLECTION_KEY,
+    };
+
+    type Collections = Arc<Mutex<HashMap<String, HashMap<String, MetaValue>>>>;
+
+    #[derive(Debug, Default, Clone)]
+    struct MemoryStorage {
+        collections: Collections,
+    }
+
+    impl MemoryStorage {
+        fn get(&self, collection: &str, key: &str) -> Option<MetaValue> {
+            let collections = self.collections.lock().unwrap();
+            collections.get(collection)?.get(key).cloned()
+        }
+
+        fn set(&self, collection: &str, key: &str, value: MetaValue) {
+            let mut collections = self.collections.lock().unwrap();
+            collections
+                .entry(collection.to_string())
+                .or_default()
+                .insert(key.to_string(), value);
+        }
+    }
+
+    #[derive(Debug)]
+    struct MemoryCollection {
+        name: String,
+        storage: MemoryStorage,
+    }
+
+    #[async_trait]
+    impl StorageBackend for MemoryStorage {
+        async fn open_collection(&self, name: &str) -> Result<Box<dyn StorageCollection>, Error> {
+            Ok(Box::new(MemoryCollection {
+                name: name.to_string(),
+                storage: self.clone(),
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl StorageCollection for MemoryCollection {
+        async fn read(&self, name: &str) -> Result<Option<MetaValue>, Error> {
+            Ok(self.storage.get(&self.name, name))
+        }
+
+        async fn write(&self, name: &str, value: MetaValue) -> Result<(), Error> {
+            self.storage.set(&self.name, name, value);
+            Ok(())
+        }
+    }
+
+    struct NoCredentials;
+
+    #[async_trait]
+    impl CredentialStore for NoCredentials {
+        async fn get_credentials(&self, _: ProviderId) -> Result<Option<Credentials>, Error> {
+            Ok(None)
+        }
+
+        async fn store_credentials(&self, _: ProviderId, _: Credentials) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn delete_credentials(&self, _: ProviderId) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    /// Records the config pushed by the manager
+    #[derive(Debug, Default, Clone)]
+    struct TestExtension {
+        config: Arc<Mutex<Option<ExtensionConfig>>>,
+    }
+
+    impl TestExtension {
+        fn config(&self) -> Option<ExtensionConfig> {
+            self.config.lock().unwrap().clone()
+        }
+    }
+
+    impl ExtensionLibrary for TestExtension {
+        fn new(_: HashMap<String, ExtensionConfigValue>) -> Self {
+            TestExtension::default()
+        }
+
+        fn metadata() -> ExtensionMetadata {
+            ExtensionMetadata {
+                id: "test".into(),
+                name: "Test".into(),
+                version: "1.0.0".into(),
+                config: ExtensionConfigSchema::new()
+                    .field(
+                        ExtensionConfigField::string("level", "Level")
+                            .default_value("uwu")
+                            .allowed_values(vec!["owo", "uwu"]),
+                    )
+                    .field(ExtensionConfigField::int("volume", "Volume")),
+            }
+        }
+    }
+
+    impl Extension for TestExtension {
+        fn setup(&mut self, runtime: &ExtensionRuntime) -> Result<(), Error> {
+            runtime.register_provider(Box::new(TestProvider))
+        }
+
+        fn update_config(&mut self, config: ExtensionConfig) -> Result<(), Error> {
+            self.config.lock().unwrap().replace(config);
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ExtensionApi for TestExtension {
+        /// The action `hang` never finishes
+        async fn invoke_action(&self, key: String) -> Result<(), Error> {
+            if key == "hang" {
+                futures::future::pending::<()>().await;
+            }
+            Ok(())
+        }
+    }
+
+    /// Searching for `hang` never finishes, searching for `panic` panics
+    #[derive(Debug)]
+    struct TestProvider;
+
+    #[async_trait]
+    impl ProviderInstance for TestProvider {
+        async fn setup(&mut self, _: &dyn CredentialStore) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn title(&self) -> &'static str {
+            "Test"
+        }
+
+        fn uri_scheme(&self) -> &'static str {
+            "test"
+        }
+
+        fn provider(&self) -> ProviderId {
+            ProviderId::new("test")
+        }
+
+        fn state(&self) -> ProviderState {
+            ProviderState::NoAuthentication
+        }
+
+        async fn authenticate(
+            &mut self,
+            _: Authentication,
+            _: &dyn CredentialStore,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn sync(&self, _: SharedLibrary) -> Result<SyncResult, Error> {
+            Ok(SyncResult::empty())
+        }
+
+        fn root(&self) -> ProviderFolder {
+            ProviderFolder::empty()
+        }
+
+        async fn navigate(&self, _: Vec<String>) -> Result<ProviderFolder, Error> {
+            Ok(ProviderFolder::empty())
+        }
+
+        async fn search(&self, query: String) -> Result<Vec<ProviderItem>, Error> {
+            match query.as_str() {
+                "hang" => futures::future::pending().await,
+                "panic" => panic!("search failed"),
+                _ => Ok(Vec::new()),
+            }
+        }
+
+        async fn resolve_track(&self, _: &str) -> Result<Option<Track>, Error> {
+            Ok(None)
+        }
+
+        async fn resolve_album(&self, _: &str) -> Result<Option<Album>, Error> {
+            Ok(None)
+        }
+
+        async fn resolve_artist(&self, _: &str) -> Result<Option<Artist>, Error> {
+            Ok(None)
+        }
+
+        async fn resolve_playlist(&self, _: &str) -> Result<Option<Playlist>, Error> {
+            Ok(None)
+        }
+
+        async fn stream_url(&self, _: &Track) -> Result<String, Error> {
+            Ok(String::new())
+        }
+
+        async fn resolve_share_url(&self, _: Url) -> Result<Option<InternalUri>, Error> {
+            Ok(None)
+        }
+    }
+
+    fn cache_options() -> CacheOptions {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("rustic-extensions-test-{}", nanos));
+        CacheOptions {
+            path: path.join("tracks"),
+            max_size: 0,
+            prefetch: 0,
+            coverart_path: path.join("coverart"),
+            coverart_max_size: 0,
+        }
+    }
+
+    fn app(storage: &MemoryStorage) -> Arc<Rustic> {
+        let storage: SharedStorageBackend = Arc::new(Box::new(storage.clone()));
+        Rustic::new(
+            Box::new(MemoryLibrary::default()),
+            storage,
+            Vec::new(),
+            cache_options(),
+        )
+        .unwrap()
+    }
+
+    async fn manager(extension: &TestExtension, storage: &MemoryStorage) -> ExtensionManager {
+        manager_with_app(app(storage), extension, storage).await
+    }
+
+    async fn manager_with_app(
+        app: Arc<Rustic>,
+        extension: &TestExtension,
+        storage: &MemoryStorage,
+    ) -> ExtensionManager {
+        let mut builder =
+            ExtensionManagerBuilder::default().with_call_timeout(Duration::from_millis(100));
+        builder.extensions.push((
+            TestExtension::metadata(),
+            ExtensionHost::plugin(Box::new(extension.clone())),
+            HashMap::new(),
+        ));
+        let mut manager = builder.build();
+        let storage: SharedStorageBackend = Arc::new(Box::new(storage.clone()));
+        let runtime = ExtensionRuntime::new(app, storage, Arc::new(Box::new(NoCredentials)));
+        manager.setup(runtime).await.unwrap();
+        manager
+    }
+
+    fn test_provider(app: &Rustic) -> Option<Provider> {
+        app.providers()
+            .into_iter()
+            .find(|provider| provider.uri_scheme == "test")
+    }
+
+    fn update(key: &str, value: Option<ExtensionConfigValue>) -> ExtensionConfigUpdate {
+        let mut update = HashMap::new();
+        update.insert(key.to_string(), value);
+        update
+    }
+
+    fn stored_config(storage: &MemoryStorage) -> Option<ExtensionConfig> {
+        let config = storage.get(EXTENSION_CONFIG_COLLECTION_KEY, "test")?;
+        let config = config.string().unwrap();
+
+        Some(serde_json::from_str(&config).unwrap())
+    }
+
+    #[tokio::test]
+    async fn update_extension_config_should_apply_and_persist_the_config() {
+        let extension = TestExtension::default();
+        let storage = MemoryStorage::default();
+        let manager = manager(&extension, &storage).await;
+
+        manager
+            .update_extension_config("test", update("volume", Some(ExtensionConfigValue::Int(5))))
+            .await
+            .unwrap();
+
+        let applied = extension.config().unwrap();
+        assert_eq!(applied["volume"], ExtensionConfigValue::Int(5));
+        assert_eq!(applied["level"], ExtensionConfigValue::from("uwu"));
+        let stored = stored_config(&storage).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored["volume"], ExtensionConfigValue::Int(5));
+    }
+
+    #[tokio::test]
+    async fn update_extension_config_should_reject_invalid_values() {
+        let extension = TestExtension::default();
+        let storage = MemoryStorage::default();
+        let manager = manager(&extension, &storage).await;
+
+        let result = manager
+            .update_extension_config("test", update("level", Some("uwo".into())))
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(extension.config(), None);
+        assert_eq!(stored_config(&storage), None);
+    }
+
+    #[tokio::test]
+    async fn update_extension_config_should_reset_values_to_the_default() {
+        let extension = TestExtension::default();
+        let storage = MemoryStorage::default();
+        let manager = manager(&extension, &storage).await;
+        manager
+            .update_extension_config("test", update("level", Some("owo".into())))
+            .await
+            .unwrap();
+
+        manager
+            .update_extension_config("test", update("level", None))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            extension.config().unwrap()["level"],
+            ExtensionConfigValue::from("uwu")
+        );
+        assert_eq!(stored_config(&storage), Some(HashMap::new()));
+    }
+
+    #[tokio::test]
+    async fn setup_should_apply_the_stored_config() {
+        let extension = TestExtension::default();
+        let storage = MemoryStorage::default();
+        storage.set(
+            EXTENSION_CONFIG_COLLECTION_KEY,
+            "test",
+            MetaValue::String(r#"{"level":"owo"}"#.into()),
+        );
+
+        let manager = manager(&extension, &storage).await;
+
+        assert_eq!(
+            manager.extensions[0].get_config()["level"],
+            ExtensionConfigValue::from("owo")
+        );
+        assert_eq!(
+            extension.config().unwrap()["level"],
+            ExtensionConfigValue::from("owo")
+        );
+    }
+
+    #[tokio::test]
+    async fn timed_out_calls_should_not_hold_back_following_calls() {
+        let extension = TestExtension::default();
+        let storage = MemoryStorage::default();
+        storage.set(EXTENSION_COLLECTION_KEY, "test", true.into());
+        let manager = manager(&extension, &storage).await;
+
+        let hanging = manager.invoke_extension_action("test", "hang").await;
+        let following = manager.invoke_extension_action("test", "ok").await;
+
+        assert!(hanging.is_err());
+        assert!(following.is_ok());
+    }
+
+    #[tokio::test]
+    async fn repeatedly_failing_extensions_should_be_disabled() {
+        let extension = TestExtension::default();
+        let storage = MemoryStorage::default();
+        storage.set(EXTENSION_COLLECTION_KEY, "test", true.into());
+        let manager = manager(&extension, &storage).await;
+
+        for _ in 0..3 {
+            let result = manager.invoke_extension_action("test", "hang").await;
+            assert!(result.is_err());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(!manager.extensions[0].is_enabled());
+        assert_eq!(
+            storage.get(EXTENSION_COLLECTION_KEY, "test"),
+            Some(false.into())
+        );
+        assert!(manager.extensions[0].get_health().disabled_reason.is_some());
+    }
+
+    #[tokio::test]
+    async fn providers_should_only_be_available_while_the_extension_is_enabled() {
+        let extension = TestExtension::default();
+        let storage = MemoryStorage::default();
+        let app = app(&storage);
+        let manager = manager_with_app(Arc::clone(&app), &extension, &storage).await;
+        assert!(test_provider(&app).is_none());
+
+        manager.enable_extension("test").await.unwrap();
+        assert!(test_provider(&app).is_some());
+
+        manager.disable_extension("test").await.unwrap();
+        assert!(test_provider(&app).is_none());
+    }
+
+    #[tokio::test]
+    async fn panicking_provider_calls_should_be_tracked_as_failure() {
+        let extension = TestExtension::default();
+        let storage = MemoryStorage::default();
+        storage.set(EXTENSION_COLLECTION_KEY, "test", true.into());
+        let app = app(&storage);
+        let manager = manager_with_app(Arc::clone(&app), &extension, &storage).await;
+        let provider = test_provider(&app).unwrap();
+
+        let result = provider.search("panic".into()).await;
+
+        assert!(result.is_err());
+        let health = manager.extensions[0].get_health();
+        let search = health
+            .metrics
+            .iter()
+            .find(|metrics| metrics.call == "provider_search")
+            .unwrap();
+        assert_eq!(search.failures, 1);
+    }
+
+    #[tokio::test]
+    async fn providers_of_repeatedly_failing_extensions_should_be_removed() {
+        let extension = TestExtension::default();
+        let storage = MemoryStorage::default();
+        storage.set(EXTENSION_COLLECTION_KEY, "test", true.into());
+        let app = app(&storage);
+        let manager = manager_with_app(Arc::clone(&app), &extension, &storage).await;
+        let provider = test_provider(&app).unwrap();
+
+        for _ in 0..3 {
+            let result = provider.search("hang".into()).await;
+            assert!(result.is_err());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(!manager.extensions[0].is_enabled());
+        assert!(test_provider(&app).is_none());
     }
 }
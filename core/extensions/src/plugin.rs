@@ -1,7 +1,8 @@
 use async_trait::async_trait;
 use rustic_queue::Sender;
 
-use rustic_core::{Album, Artist, Playlist, Track};
+use rustic_core::library::PlayRecord;
+use rustic_core::{Album, Artist, LibraryChange, PlayerState, Playlist, QueuedTrack, Track};
 
 use crate::api::*;
 use crate::host::ExtensionPlugin;
@@ -32,6 +33,30 @@ impl<T: ExtensionLibrary + 'static> ExtensionPlugin for T {
                 let result = self.on_add_to_queue(player_id, tracks).await;
                 response.send_async(result).await;
             }
+            ExtensionCommand::PlayStarted(player_id, track) => {
+                let result = self.on_play_started(player_id, track).await;
+                log_hook_error("on_play_started", result);
+            }
+            ExtensionCommand::PlayFinished(play) => {
+                let result = self.on_play_finished(play).await;
+                log_hook_error("on_play_finished", result);
+            }
+            ExtensionCommand::StateChanged(player_id, state) => {
+                let result = self.on_state_changed(player_id, state).await;
+                log_hook_error("on_state_changed", result);
+            }
+            ExtensionCommand::VolumeChanged(player_id, volume) => {
+                let result = self.on_volume_changed(player_id, volume).await;
+                log_hook_error("on_volume_changed", result);
+            }
+            ExtensionCommand::QueueUpdated(player_id, queue) => {
+                let result = self.on_queue_updated(player_id, queue).await;
+                log_hook_error("on_queue_updated", result);
+            }
+            ExtensionCommand::LibraryChanged(change) => {
+                let result = self.on_library_event(change).await;
+                log_hook_error("on_library_event", result);
+            }
             ExtensionCommand::ResolveTrack(track, response) => {
                 let result = self.resolve_track(track).await;
                 response.send_async(result).await;
@@ -48,10 +73,34 @@ impl<T: ExtensionLibrary + 'static> ExtensionPlugin for T {
                 let result = self.resolve_playlist(playlist).await;
                 response.send_async(result).await;
             }
+            ExtensionCommand::ResolveTracks(tracks, response) => {
+                let result = self.resolve_tracks(tracks).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::ResolveAlbums(albums, response) => {
+                let result = self.resolve_albums(albums).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::ResolveArtists(artists, response) => {
+                let result = self.resolve_artists(artists).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::ResolvePlaylists(playlists, response) => {
+                let result = self.resolve_playlists(playlists).await;
+                response.send_async(result).await;
+            }
             ExtensionCommand::GetControls(response) => {
                 let result = self.get_controls().await;
                 response.send_async(result).await;
             }
+            ExtensionCommand::InvokeAction(key, response) => {
+                let result = self.invoke_action(key).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::UpdateConfig(config, response) => {
+                let result = self.update_config(config);
+                response.send_async(result).await;
+            }
         }
         None
     }
@@ -62,11 +111,73 @@ pub enum ExtensionCommand {
     Setup(ExtensionRuntime, Sender<Result<(), failure::Error>>),
     GetMetadata(Sender<ExtensionMetadata>),
     GetControls(Sender<Result<ExtensionControls, failure::Error>>),
+    InvokeAction(String, Sender<Result<(), failure::Error>>),
+    UpdateConfig(ExtensionConfig, Sender<Result<(), failure::Error>>),
     Enable(Sender<Result<(), failure::Error>>),
     Disable(Sender<Result<(), failure::Error>>),
     AddToQueue(String, Vec<Track>, Sender<Result<Vec<Track>, failure::Error>>),
+    // Notifications don't have a response so they can't block the sender
+    PlayStarted(String, Track),
+    PlayFinished(PlayRecord),
+    StateChanged(String, PlayerState),
+    VolumeChanged(String, f32),
+    QueueUpdated(String, Vec<QueuedTrack>),
+    LibraryChanged(LibraryChange),
     ResolveTrack(Track, Sender<Result<Track, failure::Error>>),
     ResolveAlbum(Album, Sender<Result<Album, failure::Error>>),
     ResolveArtist(Artist, Sender<Result<Artist, failure::Error>>),
     ResolvePlaylist(Playlist, Sender<Result<Playlist, failure::Error>>),
+    ResolveTracks(Vec<Track>, Sender<Result<Vec<Track>, failure::Error>>),
+    ResolveAlbums(Vec<Album>, Sender<Result<Vec<Album>, failure::Error>>),
+    ResolveArtists(Vec<Artist>, Sender<Result<Vec<Artist>, failure::Error>>),
+    ResolvePlaylists(Vec<Playlist>, Sender<Result<Vec<Playlist>, failure::Error>>),
+}
+
+impl ExtensionCommand {
+    /// Name of the hook handling this command, used for metrics
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExtensionCommand::Setup(_, _) => "setup",
+            ExtensionCommand::GetMetadata(_) => "metadata",
+            ExtensionCommand::GetControls(_) => "get_controls",
+            ExtensionCommand::InvokeAction(_, _) => "invoke_action",
+            ExtensionCommand::UpdateConfig(_, _) => "update_config",
+            ExtensionCommand::Enable(_) => "on_enable",
+            ExtensionCommand::Disable(_) => "on_disable",
+            ExtensionCommand::AddToQueue(_, _, _) => "on_add_to_queue",
+            ExtensionCommand::PlayStarted(_, _) => "on_play_started",
+            ExtensionCommand::PlayFinished(_) => "on_play_finished",
+            ExtensionCommand::StateChanged(_, _) => "on_state_changed",
+            ExtensionCommand::VolumeChanged(_, _) => "on_volume_changed",
+            ExtensionCommand::QueueUpdated(_, _) => "on_queue_updated",
+            ExtensionCommand::LibraryChanged(_) => "on_library_event",
+            ExtensionCommand::ResolveTrack(_, _) => "resolve_track",
+            ExtensionCommand::ResolveAlbum(_, _) => "resolve_album",
+            ExtensionCommand::ResolveArtist(_, _) => "resolve_artist",
+            ExtensionCommand::ResolvePlaylist(_, _) => "resolve_playlist",
+            ExtensionCommand::ResolveTracks(_, _) => "resolve_tracks",
+            ExtensionCommand::ResolveAlbums(_, _) => "resolve_albums",
+            ExtensionCommand::ResolveArtists(_, _) => "resolve_artists",
+            ExtensionCommand::ResolvePlaylists(_, _) => "resolve_playlists",
+        }
+    }
+
+    /// Notifications are fire and forget, nobody waits for them to be handled
+    pub fn is_notification(&self) -> bool {
+        matches!(
+            self,
+            ExtensionCommand::PlayStarted(_, _)
+                | ExtensionCommand::PlayFinished(_)
+                | ExtensionCommand::StateChanged(_, _)
+                | ExtensionCommand::VolumeChanged(_, _)
+                | ExtensionCommand::QueueUpdated(_, _)
+                | ExtensionCommand::LibraryChanged(_)
+        )
+    }
+}
+
+fn log_hook_error(hook: &str, result: Result<(), failure::Error>) {
+    if let Err(e) = result {
+        log::error!("Extension hook {} failed: {:?}", hook, e);
+    }
 }
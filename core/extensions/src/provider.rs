@@ -0,0 +1,263 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use failure::{bail, Error};
+use futures::future::{Future, FutureExt};
+use url::Url;
+
+use rustic_core::library::MetadataUpdate;
+use rustic_core::provider::{
+    Authentication, ProviderFolder, ProviderInstance, ProviderItem, ProviderItemType,
+    ProviderState, SyncResult, Thumbnail,
+};
+use rustic_core::{
+    Album, Artist, CredentialStore, InternalUri, Playlist, ProviderId, Rating, SharedLibrary, Track,
+};
+
+use crate::manager::HostedExtension;
+
+/// Syncing a large library takes a while, only a hanging sync should count as failure
+const SYNC_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Supervises the calls to a provider registered by an extension
+///
+/// Calls are tracked like every other call to the extension, so a provider which panics
+/// or doesn't respond counts towards disabling the extension instead of breaking the app.
+pub(crate) struct ExtensionProvider {
+    extension: HostedExtension,
+    provider: Box<dyn ProviderInstance + Send + Sync>,
+}
+
+impl ExtensionProvider {
+    pub fn new(
+        extension: HostedExtension,
+        provider: Box<dyn ProviderInstance + Send + Sync>,
+    ) -> Self {
+        ExtensionProvider {
+            extension,
+            provider,
+        }
+    }
+}
+
+impl std::fmt::Debug for ExtensionProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtensionProvider")
+            .field("extension", &self.extension.metadata.id)
+            .field("provider", &self.provider)
+            .finish()
+    }
+}
+
+/// Supervises the call with the regular call timeout of the extension
+async fn call<T, F>(extension: &HostedExtension, name: &'static str, future: F) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    supervise(extension, name, extension.call_timeout(), future).await
+}
+
+/// Panics are caught so they are tracked as crash of the extension
+async fn supervise<T, F>(
+    extension: &HostedExtension,
+    name: &'static str,
+    timeout: Duration,
+    future: F,
+) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    if !extension.is_enabled() {
+        bail!("Extension {} is disabled", &extension.metadata.name);
+    }
+    let response = AssertUnwindSafe(future).catch_unwind().map(Result::ok);
+    extension.supervise(name, timeout, response).await
+}
+
+#[async_trait]
+impl ProviderInstance for ExtensionProvider {
+    async fn setup(&mut self, cred_store: &dyn CredentialStore) -> Result<(), Error> {
+        call(
+            &self.extension,
+            "provider_setup",
+            self.provider.setup(cred_store),
+        )
+        .await
+    }
+
+    fn title(&self) -> &'static str {
+        self.provider.title()
+    }
+
+    fn uri_scheme(&self) -> &'static str {
+        self.provider.uri_scheme()
+    }
+
+    fn provider(&self) -> ProviderId {
+        self.provider.provider()
+    }
+
+    fn state(&self) -> ProviderState {
+        catch_unwind(AssertUnwindSafe(|| self.provider.state())).unwrap_or_else(|_| {
+            ProviderState::InvalidConfiguration(Some("Extension crashed".to_string()))
+        })
+    }
+
+    async fn authenticate(
+        &mut self,
+        auth: Authentication,
+        cred_store: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        call(
+            &self.extension,
+            "provider_authenticate",
+            self.provider.authenticate(auth, cred_store),
+        )
+        .await
+    }
+
+    async fn logout(&mut self) -> Result<(), Error> {
+        call(&self.extension, "provider_logout", self.provider.logout()).await
+    }
+
+    async fn refresh_authentication(
+        &mut self,
+        cred_store: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        call(
+            &self.extension,
+            "provider_refresh_authentication",
+            self.provider.refresh_authentication(cred_store),
+        )
+        .await
+    }
+
+    async fn sync(&self, library: SharedLibrary) -> Result<SyncResult, Error> {
+        let sync = self.provider.sync(library);
+        supervise(&self.extension, "provider_sync", SYNC_TIMEOUT, sync).await
+    }
+
+    fn root(&self) -> ProviderFolder {
+        catch_unwind(AssertUnwindSafe(|| self.provider.root()))
+            .unwrap_or_else(|_| ProviderFolder::empty())
+    }
+
+    async fn navigate(&self, path: Vec<String>) -> Result<ProviderFolder, Error> {
+        call(
+            &self.extension,
+            "provider_navigate",
+            self.provider.navigate(path),
+        )
+        .await
+    }
+
+    async fn search(&self, query: String) -> Result<Vec<ProviderItem>, Error> {
+        call(
+            &self.extension,
+            "provider_search",
+            self.provider.search(query),
+        )
+        .await
+    }
+
+    async fn resolve_track(&self, uri: &str) -> Result<Option<Track>, Error> {
+        call(
+            &self.extension,
+            "provider_resolve_track",
+            self.provider.resolve_track(uri),
+        )
+        .await
+    }
+
+    async fn resolve_album(&self, uri: &str) -> Result<Option<Album>, Error> {
+        call(
+            &self.extension,
+            "provider_resolve_album",
+            self.provider.resolve_album(uri),
+        )
+        .await
+    }
+
+    async fn resolve_artist(&self, uri: &str) -> Result<Option<Artist>, Error> {
+        call(
+            &self.extension,
+            "provider_resolve_artist",
+            self.provider.resolve_artist(uri),
+        )
+        .await
+    }
+
+    async fn resolve_playlist(&self, uri: &str) -> Result<Option<Playlist>, Error> {
+        call(
+            &self.extension,
+            "provider_resolve_playlist",
+            self.provider.resolve_playlist(uri),
+        )
+        .await
+    }
+
+    async fn stream_url(&self, track: &Track) -> Result<String, Error> {
+        call(
+            &self.extension,
+            "provider_stream_url",
+            self.provider.stream_url(track),
+        )
+        .await
+    }
+
+    async fn thumbnail(
+        &self,
+        provider_item: &ProviderItemType,
+    ) -> Result<Option<Thumbnail>, Error> {
+        call(
+            &self.extension,
+            "provider_thumbnail",
+            self.provider.thumbnail(provider_item),
+        )
+        .await
+    }
+
+    async fn thumbnail_modified(
+        &self,
+        provider_item: &ProviderItemType,
+    ) -> Result<Option<SystemTime>, Error> {
+        call(
+            &self.extension,
+            "provider_thumbnail_modified",
+            self.provider.thumbnail_modified(provider_item),
+        )
+        .await
+    }
+
+    async fn resolve_share_url(&self, url: Url) -> Result<Option<InternalUri>, Error> {
+        call(
+            &self.extension,
+            "provider_resolve_share_url",
+            self.provider.resolve_share_url(url),
+        )
+        .await
+    }
+
+    async fn update_metadata(
+        &self,
+        provider_item: &ProviderItemType,
+        update: &MetadataUpdate,
+    ) -> Result<Vec<ProviderItemType>, Error> {
+        call(
+            &self.extension,
+            "provider_update_metadata",
+            self.provider.update_metadata(provider_item, update),
+        )
+        .await
+    }
+
+    async fn set_rating(&self, track: &Track, rating: Rating) -> Result<(), Error> {
+        call(
+            &self.extension,
+            "provider_set_rating",
+            self.provider.set_rating(track, rating),
+        )
+        .await
+    }
+}
@@ -1,53 +1,126 @@
+use std::collections::HashMap;
 use std::fmt::Formatter;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use failure::{Error, format_err};
 use futures::stream::BoxStream;
 
 use rustic_core::{
-    Album, Artist, InternalUri, Library, LibraryEvent, MultiQuery, Player, Playlist,
-    provider::{ProviderItemType, Thumbnail}, Rustic, SearchResults, SharedLibrary, SharedStorageBackend, SingleQuery, Track,
+    Album, Artist, CredentialStore, InternalUri, Library, LibraryChange, MultiQuery, Player, PlayerEvent, Playlist,
+    provider::{Provider, ProviderInstance, ProviderItemType, Thumbnail}, Rating, Rustic, SearchResults, SharedLibrary, SharedStorageBackend, SingleQuery, Track,
 };
-use rustic_core::library::MetaValue;
+use rustic_core::history::PlayEvent;
+use rustic_core::library::{HistoryQuery, MetaValue, PlayRecord, TrackPlays};
 
-use crate::ExtensionMetadata;
+use crate::manager::HostedExtension;
+use crate::provider::ExtensionProvider;
+
+type ExtensionProviders = Arc<Mutex<HashMap<String, Vec<Provider>>>>;
 
 #[derive(Clone)]
 pub struct ExtensionRuntime {
     app: Arc<Rustic>,
     pub(crate) storage: SharedStorageBackend,
-    extension: Option<ExtensionMetadata>,
+    credential_store: Arc<Box<dyn CredentialStore>>,
+    /// Providers registered by each extension, keyed by the extension id
+    providers: ExtensionProviders,
+    extension: Option<HostedExtension>,
 }
 
 impl std::fmt::Debug for ExtensionRuntime {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("ExtensionRuntime")
             .field("storage", &self.storage)
-            .field("extension", &self.extension)
+            .field("extension", &self.extension.as_ref().map(|e| &e.metadata))
             .finish()
     }
 }
 
 impl ExtensionRuntime {
-    pub fn new(app: Arc<Rustic>, storage: SharedStorageBackend) -> Self {
+    pub fn new(
+        app: Arc<Rustic>,
+        storage: SharedStorageBackend,
+        credential_store: Arc<Box<dyn CredentialStore>>,
+    ) -> Self {
         ExtensionRuntime {
             app,
             storage,
+            credential_store,
+            providers: ExtensionProviders::default(),
             extension: None,
         }
     }
 
-    pub fn for_extension(&self, metadata: ExtensionMetadata) -> Self {
+    pub(crate) fn for_extension(&self, extension: &HostedExtension) -> Self {
         ExtensionRuntime {
             app: self.app.clone(),
             storage: self.storage.clone(),
-            extension: Some(metadata),
+            credential_store: self.credential_store.clone(),
+            providers: self.providers.clone(),
+            extension: Some(extension.clone()),
+        }
+    }
+
+    /// Registers a provider which is available while the extension is enabled
+    ///
+    /// Should be called during setup. The uri scheme of the provider has to be unique.
+    /// Calls to the provider are supervised like every other call to the extension.
+    pub fn register_provider(
+        &self,
+        provider: Box<dyn ProviderInstance + Send + Sync>,
+    ) -> Result<(), Error> {
+        if let Some(ref extension) = self.extension {
+            let provider: Box<dyn ProviderInstance + Send + Sync> =
+                Box::new(ExtensionProvider::new(extension.clone(), provider));
+            let provider = Provider::from(provider);
+            let mut providers = self.providers.lock().unwrap();
+            let scheme_taken = providers
+                .values()
+                .flatten()
+                .any(|p| p.uri_scheme == provider.uri_scheme);
+            if scheme_taken {
+                return Err(format_err!(
+                    "Uri scheme {} is already registered",
+                    provider.uri_scheme
+                ));
+            }
+            providers
+                .entry(extension.metadata.id.clone())
+                .or_default()
+                .push(provider);
+
+            Ok(())
+        } else {
+            Err(format_err!("ExtensionRuntime is not setup properly"))
+        }
+    }
+
+    /// Sets up the providers of the given extension and makes them available in the app
+    pub(crate) async fn enable_providers(&self, extension_id: &str) {
+        let providers = self.get_providers(extension_id);
+        for provider in providers {
+            let cred_store = self.credential_store.as_ref().as_ref();
+            if let Err(e) = provider.setup(cred_store).await {
+                log::error!("Can't setup {} provider: {:?}", provider.title(), e);
+            }
+            self.app.add_provider(provider);
+        }
+    }
+
+    pub(crate) fn disable_providers(&self, extension_id: &str) {
+        for provider in self.get_providers(extension_id) {
+            self.app.remove_provider(&provider.id);
         }
     }
 
+    fn get_providers(&self, extension_id: &str) -> Vec<Provider> {
+        let providers = self.providers.lock().unwrap();
+        providers.get(extension_id).cloned().unwrap_or_default()
+    }
+
     pub async fn read_metadata(&self, key: &str) -> Result<Option<MetaValue>, Error> {
         if let Some(ref extension) = self.extension {
-            let collection = self.storage.open_collection(&extension.id).await?;
+            let collection = self.storage.open_collection(&extension.metadata.id).await?;
             let value = collection.read(key).await?;
 
             Ok(value)
@@ -58,7 +131,7 @@ impl ExtensionRuntime {
 
     pub async fn write_metadata(&self, key: &str, value: MetaValue) -> Result<(), Error> {
         if let Some(ref extension) = self.extension {
-            let collection = self.storage.open_collection(&extension.id).await?;
+            let collection = self.storage.open_collection(&extension.metadata.id).await?;
             let value = collection.write(key, value).await?;
 
             Ok(value)
@@ -67,6 +140,14 @@ impl ExtensionRuntime {
         }
     }
 
+    pub(crate) fn observe_plays(&self) -> BoxStream<'static, PlayEvent> {
+        self.app.history.observe_plays()
+    }
+
+    pub(crate) fn observe_players(&self) -> BoxStream<'static, (String, PlayerEvent)> {
+        self.app.observe_players()
+    }
+
     pub async fn query_track(&self, query: SingleQuery) -> Result<Option<Track>, failure::Error> {
         self.app.query_track(query).await
     }
@@ -169,6 +250,10 @@ impl Library for ExtensionRuntime {
         self.app.library.sync_track(track)
     }
 
+    fn set_rating(&self, uri: &str, rating: Rating) -> Result<(), Error> {
+        self.app.library.set_rating(uri, rating)
+    }
+
     fn sync_album(&self, album: &mut Album) -> Result<(), Error> {
         self.app.library.sync_album(album)
     }
@@ -205,7 +290,27 @@ impl Library for ExtensionRuntime {
         self.app.library.flush()
     }
 
-    fn observe(&self) -> BoxStream<'static, LibraryEvent> {
+    fn revision(&self) -> Result<u64, Error> {
+        self.app.library.revision()
+    }
+
+    fn changes_since(&self, revision: u64) -> Result<Vec<LibraryChange>, Error> {
+        self.app.library.changes_since(revision)
+    }
+
+    fn add_play(&self, play: PlayRecord) -> Result<(), Error> {
+        self.app.library.add_play(play)
+    }
+
+    fn query_plays(&self, query: HistoryQuery) -> Result<Vec<PlayRecord>, Error> {
+        self.app.library.query_plays(query)
+    }
+
+    fn query_track_plays(&self, uris: &[String]) -> Result<HashMap<String, TrackPlays>, Error> {
+        self.app.library.query_track_plays(uris)
+    }
+
+    fn observe(&self) -> BoxStream<'static, LibraryChange> {
         self.app.library.observe()
     }
 }
@@ -0,0 +1,797 @@
+//! Host for extensions compiled to WebAssembly
+//!
+//! Unlike native extensions, wasm extensions don't share any rust types with the host.
+//! All communication happens through json encoded messages in the linear memory of the module.
+//!
+//! A module has to export:
+//! * `memory`
+//! * `rustic_abi_version() -> i32` returning [ABI_VERSION]
+//! * `rustic_alloc(len: i32) -> i32` allocating `len` bytes for the host to write a request into
+//! * `rustic_dealloc(ptr: i32, len: i32)` freeing requests and responses
+//! * `rustic_handle(ptr: i32, len: i32) -> i64` handling a [WasmRequest],
+//!   returning the pointer of the response in the upper and the length in the lower 32 bits
+//!
+//! Responses are encoded as `{"Ok": value}` or `{"Err": "message"}`.
+//!
+//! The response to [WasmRequest::Setup] lists the providers the extension offers.
+//! Requests to them name the provider by its uri scheme. Wasm providers can't access the library,
+//! so they don't sync and don't support authentication.
+//!
+//! The module may import `rustic.log(level: i32, ptr: i32, len: i32)` to write utf-8 messages to the host log.
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use failure::{bail, format_err, Error};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use wasmtime::{
+    AsContext, Caller, Config, Engine, Linker, Memory, Module, Store, StoreLimits,
+    StoreLimitsBuilder, TypedFunc,
+};
+
+use rustic_core::library::PlayRecord;
+use rustic_core::provider::{
+    Authentication, ProviderFolder, ProviderInstance, ProviderItem, ProviderState, SyncResult,
+};
+use rustic_core::{
+    Album, Artist, CredentialStore, InternalUri, LibraryChange, PlayerState, Playlist, ProviderId,
+    QueuedTrack, SharedLibrary, Track,
+};
+use url::Url;
+
+use crate::api::{ExtensionConfig, ExtensionMetadata};
+use crate::controls::ExtensionControls;
+use crate::host::ExtensionPlugin;
+use crate::plugin::ExtensionCommand;
+use crate::runtime::ExtensionRuntime;
+
+/// Has to be increased with every incompatible change to [WasmRequest] or the exported functions
+pub const ABI_VERSION: i32 = 2;
+
+/// Resource limits applied to every wasm extension
+#[derive(Debug, Clone, Copy)]
+pub struct WasmLimits {
+    /// Amount of fuel a single call may consume, roughly one unit per executed instruction
+    pub fuel_per_call: u64,
+    /// Maximum size of the linear memory in bytes
+    pub max_memory: usize,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        WasmLimits {
+            fuel_per_call: 1_000_000_000,
+            max_memory: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Serialized form of [ExtensionCommand]
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum WasmRequest {
+    /// Always the first request, the metadata may not depend on the config
+    Metadata,
+    /// Contains the config of the extension, sent once after [WasmRequest::Metadata]
+    New(ExtensionConfig),
+    /// Responds with the list of [WasmProviderInfo]s
+    Setup,
+    Enable,
+    Disable,
+    GetControls,
+    InvokeAction(String),
+    UpdateConfig(ExtensionConfig),
+    AddToQueue {
+        player_id: String,
+        tracks: Vec<Track>,
+    },
+    PlayStarted {
+        player_id: String,
+        track: Track,
+    },
+    PlayFinished(PlayRecord),
+    StateChanged {
+        player_id: String,
+        state: PlayerState,
+    },
+    VolumeChanged {
+        player_id: String,
+        volume: f32,
+    },
+    QueueUpdated {
+        player_id: String,
+        queue: Vec<QueuedTrack>,
+    },
+    LibraryChanged(LibraryChange),
+    ResolveTrack(Track),
+    ResolveAlbum(Album),
+    ResolveArtist(Artist),
+    ResolvePlaylist(Playlist),
+    ProviderNavigate {
+        uri_scheme: String,
+        path: Vec<String>,
+    },
+    ProviderSearch {
+        uri_scheme: String,
+        query: String,
+    },
+    ProviderResolveTrack {
+        uri_scheme: String,
+        uri: String,
+    },
+    ProviderResolveAlbum {
+        uri_scheme: String,
+        uri: String,
+    },
+    ProviderResolveArtist {
+        uri_scheme: String,
+        uri: String,
+    },
+    ProviderResolvePlaylist {
+        uri_scheme: String,
+        uri: String,
+    },
+    ProviderStreamUrl {
+        uri_scheme: String,
+        track: Track,
+    },
+}
+
+/// A provider offered by a wasm extension
+#[derive(Debug, Clone, Deserialize)]
+struct WasmProviderInfo {
+    id: ProviderId,
+    title: String,
+    uri_scheme: String,
+    root: ProviderFolder,
+}
+
+#[derive(Debug, Deserialize)]
+enum WasmResponse<T> {
+    Ok(T),
+    Err(String),
+}
+
+struct WasmState {
+    limits: StoreLimits,
+}
+
+struct WasmInstance {
+    store: Store<WasmState>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    dealloc: TypedFunc<(i32, i32), ()>,
+    handle: TypedFunc<(i32, i32), i64>,
+    fuel_per_call: u64,
+    fuel_added: u64,
+}
+
+impl WasmInstance {
+    fn load(path: &Path, limits: WasmLimits) -> Result<Self, Error> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(wasm_error)?;
+        let module = Module::from_file(&engine, path).map_err(wasm_error)?;
+        let mut store = Store::new(
+            &engine,
+            WasmState {
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(limits.max_memory)
+                    .instances(1)
+                    .build(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        let mut linker = Linker::new(&engine);
+        linker
+            .func_wrap("rustic", "log", WasmInstance::log)
+            .map_err(wasm_error)?;
+        // the fuel for instantiation
+        store.add_fuel(limits.fuel_per_call).map_err(wasm_error)?;
+        let module = linker
+            .instantiate(&mut store, &module)
+            .map_err(wasm_error)?;
+        let version = module
+            .get_typed_func::<(), i32, _>(&mut store, "rustic_abi_version")
+            .map_err(wasm_error)?
+            .call(&mut store, ())?;
+        if version != ABI_VERSION {
+            bail!(
+                "Unsupported abi version {}, expected {}",
+                version,
+                ABI_VERSION
+            );
+        }
+        let memory = module
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| format_err!("Extension does not export memory"))?;
+        let alloc = module
+            .get_typed_func(&mut store, "rustic_alloc")
+            .map_err(wasm_error)?;
+        let dealloc = module
+            .get_typed_func(&mut store, "rustic_dealloc")
+            .map_err(wasm_error)?;
+        let handle = module
+            .get_typed_func(&mut store, "rustic_handle")
+            .map_err(wasm_error)?;
+        let instance = WasmInstance {
+            store,
+            memory,
+            alloc,
+            dealloc,
+            handle,
+            fuel_per_call: limits.fuel_per_call,
+            fuel_added: limits.fuel_per_call,
+        };
+
+        Ok(instance)
+    }
+
+    fn log(caller: Caller<'_, WasmState>, level: i32, ptr: i32, len: i32) {
+        let memory = match caller.get_export("memory").and_then(|e| e.into_memory()) {
+            Some(memory) => memory,
+            None => return,
+        };
+        let buffer = match read_memory(&memory, &caller, ptr, len) {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                log::warn!("Extension tried to log {:?}", e);
+                return;
+            }
+        };
+        let message = String::from_utf8_lossy(&buffer);
+        let level = match level {
+            0 => log::Level::Error,
+            1 => log::Level::Warn,
+            2 => log::Level::Info,
+            3 => log::Level::Debug,
+            _ => log::Level::Trace,
+        };
+        log::log!(level, "{}", message);
+    }
+
+    /// Tops the fuel up so every call has the same budget, regardless of what previous calls consumed
+    fn refuel(&mut self) -> Result<(), Error> {
+        let consumed = self.store.fuel_consumed().unwrap_or_default();
+        let target = consumed + self.fuel_per_call;
+        self.store
+            .add_fuel(target - self.fuel_added)
+            .map_err(wasm_error)?;
+        self.fuel_added = target;
+        Ok(())
+    }
+
+    fn call(&mut self, request: &[u8]) -> Result<Vec<u8>, Error> {
+        self.refuel()?;
+        let len = request.len() as i32;
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        self.memory
+            .write(&mut self.store, ptr as usize, request)
+            .map_err(wasm_error)?;
+        let result = self.handle.call(&mut self.store, (ptr, len));
+        self.dealloc.call(&mut self.store, (ptr, len))?;
+        let result = result?;
+
+        let response_ptr = (result >> 32) as i32;
+        let response_len = (result & 0xFFFF_FFFF) as i32;
+        let response = read_memory(&self.memory, &self.store, response_ptr, response_len)?;
+        self.dealloc
+            .call(&mut self.store, (response_ptr, response_len))?;
+
+        Ok(response)
+    }
+
+    fn request<T: DeserializeOwned>(&mut self, request: &WasmRequest) -> Result<T, Error> {
+        let request = serde_json::to_vec(request)?;
+        let response = self.call(&request)?;
+        match serde_json::from_slice(&response)? {
+            WasmResponse::Ok(value) => Ok(value),
+            WasmResponse::Err(message) => Err(format_err!("{}", message)),
+        }
+    }
+}
+
+/// Copies `len` bytes starting at `ptr` out of the linear memory
+///
+/// The guest passes both as i32, they are read as u32 and checked against the size of the memory
+/// before anything is allocated, so a broken module can't make the host allocate arbitrary amounts.
+fn read_memory(
+    memory: &Memory,
+    store: impl AsContext,
+    ptr: i32,
+    len: i32,
+) -> Result<Vec<u8>, Error> {
+    let ptr = ptr as u32 as usize;
+    let len = len as u32 as usize;
+    let size = memory.data_size(&store);
+    match ptr.checked_add(len) {
+        Some(end) if end <= size => {}
+        _ => bail!(
+            "Out of bounds memory access at {} with length {}, memory size is {}",
+            ptr,
+            len,
+            size
+        ),
+    }
+    let mut buffer = vec![0; len];
+    memory.read(&store, ptr, &mut buffer).map_err(wasm_error)?;
+
+    Ok(buffer)
+}
+
+/// Forwards [ExtensionCommand]s to a wasm module
+pub struct WasmPlugin {
+    metadata: ExtensionMetadata,
+    instance: Arc<Mutex<WasmInstance>>,
+}
+
+impl WasmPlugin {
+    pub fn load(
+        path: &Path,
+        config: &HashMap<String, ExtensionConfig>,
+        limits: WasmLimits,
+    ) -> Result<Self, Error> {
+        let mut instance = WasmInstance::load(path, limits)?;
+        let metadata: ExtensionMetadata = instance.request(&WasmRequest::Metadata)?;
+        let config = config.get(&metadata.id).cloned().unwrap_or_default();
+        instance.request::<()>(&WasmRequest::New(config))?;
+
+        Ok(WasmPlugin {
+            metadata,
+            instance: Arc::new(Mutex::new(instance)),
+        })
+    }
+
+    pub fn metadata(&self) -> &ExtensionMetadata {
+        &self.metadata
+    }
+
+    async fn request<T: DeserializeOwned + Send + 'static>(
+        &self,
+        request: WasmRequest,
+    ) -> Result<T, Error> {
+        blocking_request(&self.instance, request).await
+    }
+
+    async fn setup(&self, runtime: &ExtensionRuntime) -> Result<(), Error> {
+        let providers: Vec<WasmProviderInfo> = self.request(WasmRequest::Setup).await?;
+        for info in providers {
+            let provider = WasmProvider::new(info, Arc::clone(&self.instance));
+            runtime.register_provider(Box::new(provider))?;
+        }
+        Ok(())
+    }
+
+    /// The abi has no batched requests, but sending all items in one blocking task still saves the context switches
+    async fn request_each<T, F>(&self, items: Vec<T>, request: F) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned + Send + 'static,
+        F: Fn(T) -> WasmRequest + Send + 'static,
+    {
+        let instance = Arc::clone(&self.instance);
+        tokio::task::spawn_blocking(move || {
+            let mut instance = instance
+                .lock()
+                .map_err(|_| format_err!("Extension crashed in a previous call"))?;
+            items
+                .into_iter()
+                .map(|item| instance.request(&request(item)))
+                .collect()
+        })
+        .await?
+    }
+
+    async fn notify(&self, hook: &str, request: WasmRequest) {
+        if let Err(e) = self.request::<()>(request).await {
+            log::error!("Extension hook {} failed: {:?}", hook, e);
+        }
+    }
+}
+
+#[async_trait]
+impl ExtensionPlugin for WasmPlugin {
+    async fn handle_message(&mut self, message: ExtensionCommand) -> Option<u8> {
+        match message {
+            ExtensionCommand::Setup(runtime, tx) => {
+                let result = self.setup(&runtime).await;
+                tx.send_async(result).await;
+            }
+            ExtensionCommand::GetMetadata(tx) => {
+                tx.send_async(self.metadata.clone()).await;
+            }
+            ExtensionCommand::GetControls(response) => {
+                let result = self
+                    .request::<ExtensionControls>(WasmRequest::GetControls)
+                    .await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::InvokeAction(key, response) => {
+                let result = self.request(WasmRequest::InvokeAction(key)).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::UpdateConfig(config, response) => {
+                let result = self.request(WasmRequest::UpdateConfig(config)).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::Enable(response) => {
+                let result = self.request(WasmRequest::Enable).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::Disable(response) => {
+                let result = self.request(WasmRequest::Disable).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::AddToQueue(player_id, tracks, response) => {
+                let result = self
+                    .request(WasmRequest::AddToQueue { player_id, tracks })
+                    .await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::PlayStarted(player_id, track) => {
+                self.notify(
+                    "on_play_started",
+                    WasmRequest::PlayStarted { player_id, track },
+                )
+                .await;
+            }
+            ExtensionCommand::PlayFinished(play) => {
+                self.notify("on_play_finished", WasmRequest::PlayFinished(play))
+                    .await;
+            }
+            ExtensionCommand::StateChanged(player_id, state) => {
+                self.notify(
+                    "on_state_changed",
+                    WasmRequest::StateChanged { player_id, state },
+                )
+                .await;
+            }
+            ExtensionCommand::VolumeChanged(player_id, volume) => {
+                self.notify(
+                    "on_volume_changed",
+                    WasmRequest::VolumeChanged { player_id, volume },
+                )
+                .await;
+            }
+            ExtensionCommand::QueueUpdated(player_id, queue) => {
+                self.notify(
+                    "on_queue_updated",
+                    WasmRequest::QueueUpdated { player_id, queue },
+                )
+                .await;
+            }
+            ExtensionCommand::LibraryChanged(change) => {
+                self.notify("on_library_event", WasmRequest::LibraryChanged(change))
+                    .await;
+            }
+            ExtensionCommand::ResolveTrack(track, response) => {
+                let result = self.request(WasmRequest::ResolveTrack(track)).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::ResolveAlbum(album, response) => {
+                let result = self.request(WasmRequest::ResolveAlbum(album)).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::ResolveArtist(artist, response) => {
+                let result = self.request(WasmRequest::ResolveArtist(artist)).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::ResolvePlaylist(playlist, response) => {
+                let result = self.request(WasmRequest::ResolvePlaylist(playlist)).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::ResolveTracks(tracks, response) => {
+                let result = self.request_each(tracks, WasmRequest::ResolveTrack).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::ResolveAlbums(albums, response) => {
+                let result = self.request_each(albums, WasmRequest::ResolveAlbum).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::ResolveArtists(artists, response) => {
+                let result = self.request_each(artists, WasmRequest::ResolveArtist).await;
+                response.send_async(result).await;
+            }
+            ExtensionCommand::ResolvePlaylists(playlists, response) => {
+                let result = self
+                    .request_each(playlists, WasmRequest::ResolvePlaylist)
+                    .await;
+                response.send_async(result).await;
+            }
+        }
+        None
+    }
+}
+
+/// Wasm calls are blocking so they are moved off the async executor
+async fn blocking_request<T: DeserializeOwned + Send + 'static>(
+    instance: &Arc<Mutex<WasmInstance>>,
+    request: WasmRequest,
+) -> Result<T, Error> {
+    let instance = Arc::clone(instance);
+    tokio::task::spawn_blocking(move || {
+        let mut instance = instance
+            .lock()
+            .map_err(|_| format_err!("Extension crashed in a previous call"))?;
+        instance.request(&request)
+    })
+    .await?
+}
+
+/// Forwards provider calls to a wasm module
+///
+/// The title and uri scheme are leaked once per provider, the trait requires them to be static.
+struct WasmProvider {
+    id: ProviderId,
+    title: &'static str,
+    uri_scheme: &'static str,
+    root: ProviderFolder,
+    instance: Arc<Mutex<WasmInstance>>,
+}
+
+impl WasmProvider {
+    fn new(info: WasmProviderInfo, instance: Arc<Mutex<WasmInstance>>) -> Self {
+        WasmProvider {
+            id: info.id,
+            title: Box::leak(info.title.into_boxed_str()),
+            uri_scheme: Box::leak(info.uri_scheme.into_boxed_str()),
+            root: info.root,
+            instance,
+        }
+    }
+
+    async fn request<T: DeserializeOwned + Send + 'static>(
+        &self,
+        request: WasmRequest,
+    ) -> Result<T, Error> {
+        blocking_request(&self.instance, request).await
+    }
+}
+
+impl std::fmt::Debug for WasmProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmProvider")
+            .field("id", &self.id)
+            .field("uri_scheme", &self.uri_scheme)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl ProviderInstance for WasmProvider {
+    async fn setup(&mut self, _: &dyn CredentialStore) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn title(&self) -> &'static str {
+        self.title
+    }
+
+    fn uri_scheme(&self) -> &'static str {
+        self.uri_scheme
+    }
+
+    fn provider(&self) -> ProviderId {
+        self.id.clone()
+    }
+
+    fn state(&self) -> ProviderState {
+        ProviderState::NoAuthentication
+    }
+
+    async fn authenticate(
+        &mut self,
+        _: Authentication,
+        _: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        bail!("Wasm providers don't support authentication")
+    }
+
+    async fn sync(&self, _: SharedLibrary) -> Result<SyncResult, Error> {
+        Ok(SyncResult::empty())
+    }
+
+    fn root(&self) -> ProviderFolder {
+        self.root.clone()
+    }
+
+    async fn navigate(&self, path: Vec<String>) -> Result<ProviderFolder, Error> {
+        let uri_scheme = self.uri_scheme.to_string();
+        self.request(WasmRequest::ProviderNavigate { uri_scheme, path })
+            .await
+    }
+
+    async fn search(&self, query: String) -> Result<Vec<ProviderItem>, Error> {
+        let uri_scheme = self.uri_scheme.to_string();
+        self.request(WasmRequest::ProviderSearch { uri_scheme, query })
+            .await
+    }
+
+    async fn resolve_track(&self, uri: &str) -> Result<Option<Track>, Error> {
+        let uri_scheme = self.uri_scheme.to_string();
+        let uri = uri.to_string();
+        self.request(WasmRequest::ProviderResolveTrack { uri_scheme, uri })
+            .await
+    }
+
+    async fn resolve_album(&self, uri: &str) -> Result<Option<Album>, Error> {
+        let uri_scheme = self.uri_scheme.to_string();
+        let uri = uri.to_string();
+        self.request(WasmRequest::ProviderResolveAlbum { uri_scheme, uri })
+            .await
+    }
+
+    async fn resolve_artist(&self, uri: &str) -> Result<Option<Artist>, Error> {
+        let uri_scheme = self.uri_scheme.to_string();
+        let uri = uri.to_string();
+        self.request(WasmRequest::ProviderResolveArtist { uri_scheme, uri })
+            .await
+    }
+
+    async fn resolve_playlist(&self, uri: &str) -> Result<Option<Playlist>, Error> {
+        let uri_scheme = self.uri_scheme.to_string();
+        let uri = uri.to_string();
+        self.request(WasmRequest::ProviderResolvePlaylist { uri_scheme, uri })
+            .await
+    }
+
+    async fn stream_url(&self, track: &Track) -> Result<String, Error> {
+        let uri_scheme = self.uri_scheme.to_string();
+        let track = track.clone();
+        self.request(WasmRequest::ProviderStreamUrl { uri_scheme, track })
+            .await
+    }
+
+    async fn resolve_share_url(&self, _: Url) -> Result<Option<InternalUri>, Error> {
+        Ok(None)
+    }
+}
+
+/// wasmtime uses anyhow errors which don't implement std::error::Error
+fn wasm_error<E: std::fmt::Display>(error: E) -> Error {
+    format_err!("{}", error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rustic_core::ProviderId;
+
+    use super::{WasmInstance, WasmLimits, WasmProviderInfo, WasmRequest};
+
+    /// Answers every request with the request itself, logging with an invalid pointer before
+    const ECHO_MODULE: &str = r#"
+        (module
+            (import "rustic" "log" (func $log (param i32 i32 i32)))
+            (memory (export "memory") 1)
+            (func (export "rustic_abi_version") (result i32) i32.const 2)
+            (func (export "rustic_alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "rustic_dealloc") (param i32 i32))
+            (func (export "rustic_handle") (param $ptr i32) (param $len i32) (result i64)
+                (call $log (i32.const 0) (i32.const -1) (i32.const -1))
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                    (i64.extend_i32_u (local.get $len)))))
+    "#;
+
+    const LOOPING_MODULE: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "rustic_abi_version") (result i32) i32.const 2)
+            (func (export "rustic_alloc") (param i32) (result i32) i32.const 0)
+            (func (export "rustic_dealloc") (param i32 i32))
+            (func (export "rustic_handle") (param i32 i32) (result i64)
+                (loop br 0)
+                i64.const 0))
+    "#;
+
+    /// Answers every request with the given response pointer and length
+    fn responding_module(response: &str) -> String {
+        format!(
+            r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "rustic_abi_version") (result i32) i32.const 2)
+                (func (export "rustic_alloc") (param i32) (result i32) i32.const 0)
+                (func (export "rustic_dealloc") (param i32 i32))
+                (func (export "rustic_handle") (param i32 i32) (result i64)
+                    i64.const {}))
+            "#,
+            response
+        )
+    }
+
+    /// Answers every request with the given json, which is stored behind the request buffer
+    fn serving_module(response: &str) -> String {
+        let response_ptr: i64 = 1024 << 32 | response.len() as i64;
+        format!(
+            r#"
+            (module
+                (memory (export "memory") 1)
+                (data (i32.const 1024) "{}")
+                (func (export "rustic_abi_version") (result i32) i32.const 2)
+                (func (export "rustic_alloc") (param i32) (result i32) i32.const 0)
+                (func (export "rustic_dealloc") (param i32 i32))
+                (func (export "rustic_handle") (param i32 i32) (result i64)
+                    i64.const {}))
+            "#,
+            response.replace('"', "\\\""),
+            response_ptr
+        )
+    }
+
+    fn instance(name: &str, module: &str, limits: WasmLimits) -> WasmInstance {
+        let path: PathBuf = std::env::temp_dir().join(format!("rustic-{}-extension.wat", name));
+        std::fs::write(&path, module).unwrap();
+
+        WasmInstance::load(&path, limits).unwrap()
+    }
+
+    #[test]
+    fn call_should_return_the_response_of_the_module() {
+        let mut instance = instance("echo", ECHO_MODULE, WasmLimits::default());
+
+        let response = instance.call(b"{\"Ok\":null}").unwrap();
+
+        assert_eq!(response, b"{\"Ok\":null}");
+    }
+
+    #[test]
+    fn request_should_deserialize_the_response() {
+        let mut instance = instance("echo-request", ECHO_MODULE, WasmLimits::default());
+
+        // the echoed request is no valid response, but it has to reach the deserialization
+        let result = instance.request::<()>(&WasmRequest::Setup);
+
+        assert!(result.unwrap_err().downcast::<serde_json::Error>().is_ok());
+    }
+
+    #[test]
+    fn calls_should_be_aborted_when_running_out_of_fuel() {
+        let limits = WasmLimits {
+            fuel_per_call: 10_000,
+            ..WasmLimits::default()
+        };
+        let mut instance = instance("looping", LOOPING_MODULE, limits);
+
+        let result = instance.request::<()>(&WasmRequest::Setup);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn call_should_reject_responses_outside_of_the_memory() {
+        // pointer 0xFFFFFFFF would be negative as i32
+        let module = responding_module("0xFFFFFFFF00000010");
+        let mut instance = instance("bad-pointer", &module, WasmLimits::default());
+
+        assert!(instance.call(b"{}").is_err());
+    }
+
+    #[test]
+    fn call_should_reject_responses_larger_than_the_memory() {
+        // length 0xFFFFFFFF would be negative as i32
+        let module = responding_module("0x00000000FFFFFFFF");
+        let mut instance = instance("bad-length", &module, WasmLimits::default());
+
+        assert!(instance.call(b"{}").is_err());
+    }
+
+    #[test]
+    fn setup_should_return_the_offered_providers() {
+        let module = serving_module(
+            r#"{"Ok":[{"id":"wasm","title":"Wasm","uri_scheme":"wasm","root":{"folders":[],"items":[]}}]}"#,
+        );
+        let mut instance = instance("providers", &module, WasmLimits::default());
+
+        let providers = instance
+            .request::<Vec<WasmProviderInfo>>(&WasmRequest::Setup)
+            .unwrap();
+
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].id, ProviderId::from("wasm"));
+        assert_eq!(providers[0].uri_scheme, "wasm");
+    }
+}
@@ -1,6 +1,7 @@
 use futures::stream::Stream;
 use futures::stream::StreamExt;
 use futures_signals::signal::{Mutable, MutableSignalCloned, SignalExt};
+use tokio::sync::broadcast as multicast_channel;
 
 type SenderImplementation<T> = flume::Sender<T>;
 type ReceiverImplementation<T> = flume::Receiver<T>;
@@ -14,6 +15,18 @@ pub fn broadcast<T>() -> (Sender<T>, Receiver<T>) {
     (tx.into(), rx.into())
 }
 
+/// Messages a multicast subscriber can fall behind before it misses the oldest ones
+const MULTICAST_CAPACITY: usize = 1024;
+
+/// Every subscriber receives every message which was sent after it subscribed
+///
+/// In contrast to [broadcast] the receivers don't compete for messages.
+pub fn multicast<T>() -> (MulticastSender<T>, MulticastReceiver<T>) {
+    let (tx, _) = multicast_channel::channel(MULTICAST_CAPACITY);
+
+    (MulticastSender { tx: tx.clone() }, MulticastReceiver { tx })
+}
+
 pub fn one_shot<T>() -> (Sender<T>, Receiver<T>) {
     let (tx, rx) = flume::bounded(1);
 
@@ -106,3 +119,71 @@ impl<T> From<Mutable<Option<T>>> for BusReceiver<T> {
         Self { mutable }
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct MulticastSender<T> {
+    tx: multicast_channel::Sender<T>,
+}
+
+impl<T> MulticastSender<T> {
+    /// Sends the message to all current subscribers, without subscribers it is dropped
+    pub fn send(&self, msg: T) {
+        let _ = self.tx.send(msg);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MulticastReceiver<T> {
+    tx: multicast_channel::Sender<T>,
+}
+
+impl<T: 'static + Clone + Send> MulticastReceiver<T> {
+    /// Subscribes to all messages sent from now on
+    ///
+    /// Subscribers which fall too far behind skip the messages they missed.
+    pub fn stream(&self) -> impl Stream<Item = T> {
+        let rx = self.tx.subscribe();
+        futures::stream::unfold(rx, |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(msg) => return Some((msg, rx)),
+                    Err(multicast_channel::error::RecvError::Lagged(_)) => continue,
+                    Err(multicast_channel::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::multicast;
+
+    #[tokio::test]
+    async fn multicast_should_deliver_every_message_to_every_subscriber() {
+        let (tx, rx) = multicast();
+        let first = rx.stream();
+        let second = rx.stream();
+
+        tx.send(1);
+        tx.send(2);
+        drop((tx, rx));
+
+        assert_eq!(first.collect::<Vec<_>>().await, vec![1, 2]);
+        assert_eq!(second.collect::<Vec<_>>().await, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn multicast_should_only_deliver_messages_sent_after_subscribing() {
+        let (tx, rx) = multicast();
+        tx.send(1);
+        let stream = rx.stream();
+
+        tx.send(2);
+        drop((tx, rx));
+
+        assert_eq!(stream.collect::<Vec<_>>().await, vec![2]);
+    }
+}
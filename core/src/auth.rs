@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::mem::{discriminant, Discriminant};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use failure::Error;
+use rustic_queue::{multicast, MulticastReceiver, MulticastSender};
+
+use crate::provider::{Authentication, ProviderState};
+use crate::{CredentialStore, MultiQuery, Provider, ProviderId, Rustic, SharedLibrary};
+
+#[derive(Debug, Clone)]
+pub struct ProviderStateEvent {
+    pub provider: ProviderId,
+    pub state: ProviderState,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthState {
+    pub events: MulticastReceiver<ProviderStateEvent>,
+    tx: MulticastSender<ProviderStateEvent>,
+    states: Arc<Mutex<HashMap<ProviderId, Discriminant<ProviderState>>>>,
+}
+
+impl AuthState {
+    pub(crate) fn new() -> AuthState {
+        let (tx, rx) = multicast();
+
+        AuthState {
+            events: rx,
+            tx,
+            states: Arc::default(),
+        }
+    }
+
+    /// Broadcasts the state of the provider when it changed since the last update
+    ///
+    /// The first update of a provider only records its state.
+    pub async fn update(&self, provider: &Provider) {
+        let state = provider.get().await.state();
+        let previous = self
+            .states
+            .lock()
+            .unwrap()
+            .insert(provider.id.clone(), discriminant(&state));
+        match previous {
+            Some(previous) if previous != discriminant(&state) => {
+                self.next(ProviderStateEvent {
+                    provider: provider.id.clone(),
+                    state,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn next(&self, event: ProviderStateEvent) {
+        log::trace!("{:?}", event);
+        self.tx.send(event);
+    }
+}
+
+/// Providers should refresh tokens which expire before the next refresh
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Periodically refreshes the authentication of all providers
+///
+/// Providers which lost their authentication, e.g. because the refresh token got revoked, emit a state event.
+pub async fn start(
+    app: Arc<Rustic>,
+    cred_store: Arc<Box<dyn CredentialStore>>,
+) -> Result<(), Error> {
+    log::info!("Starting Authentication Refresh");
+    for provider in app.providers() {
+        app.auth.update(&provider).await;
+    }
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        refresh(&app, cred_store.as_ref().as_ref()).await;
+    }
+}
+
+async fn refresh(app: &Arc<Rustic>, cred_store: &dyn CredentialStore) {
+    for provider in app.providers() {
+        if let Err(err) = provider.refresh_authentication(cred_store).await {
+            log::error!(
+                "Refreshing authentication of {} failed: {:?}",
+                provider.title(),
+                err
+            );
+        }
+        app.auth.update(&provider).await;
+    }
+}
+
+pub async fn authenticate(
+    app: &Rustic,
+    provider: &Provider,
+    auth: Authentication,
+    cred_store: &dyn CredentialStore,
+) -> Result<(), Error> {
+    let result = provider.authenticate(auth, cred_store).await;
+    app.auth.update(provider).await;
+
+    result
+}
+
+/// Ends the session of the provider and removes its stored credentials
+///
+/// With `purge_library` all library items of the provider account are removed as well.
+pub async fn logout(
+    app: &Rustic,
+    provider: &Provider,
+    cred_store: &dyn CredentialStore,
+    purge_library: bool,
+) -> Result<(), Error> {
+    log::info!("Logging out of {}", provider.title());
+    provider.logout(cred_store).await?;
+    app.auth.update(provider).await;
+    if purge_library {
+        remove_items(&app.library, &provider.id)?;
+    }
+
+    Ok(())
+}
+
+/// Not every store applies the provider filter and the filter of the default account includes the
+/// other accounts as well, so every item is checked for the exact account.
+fn remove_items(library: &SharedLibrary, provider: &ProviderId) -> Result<(), Error> {
+    let mut query = MultiQuery::new();
+    query.with_providers(vec![provider.clone()]);
+    for track in library.query_tracks(query.clone())? {
+        if &track.provider == provider {
+            library.remove_track(&track)?;
+        }
+    }
+    for album in library.query_albums(query.clone())? {
+        if &album.provider == provider {
+            library.remove_album(&album)?;
+        }
+    }
+    for artist in library.query_artists(query.clone())? {
+        if &artist.provider == provider {
+            library.remove_artist(&artist)?;
+        }
+    }
+    for playlist in library.query_playlists(query)? {
+        if &playlist.provider == provider {
+            library.remove_playlist(&playlist)?;
+        }
+    }
+    library.flush()
+}
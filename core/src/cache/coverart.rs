@@ -0,0 +1,478 @@
+use std::collections::HashMap;
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use failure::{bail, format_err, Error};
+use image::{GenericImageView, ImageFormat, ImageOutputFormat};
+use log::{debug, error, trace};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::provider::Thumbnail;
+
+const INDEX_FILE: &str = "index.json";
+/// Requested sizes are rounded up to the next variant so we don't store an image for every pixel size
+const VARIANT_SIZES: [u32; 5] = [64, 128, 256, 512, 1024];
+/// Access times are only kept in memory for this long before the index is written
+const ACCESS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A cached image with the metadata required for http caching
+#[derive(Debug, Clone)]
+pub struct CoverArt {
+    pub data: Vec<u8>,
+    pub mime_type: String,
+    pub etag: String,
+    pub last_modified: SystemTime,
+}
+
+impl From<CoverArt> for Thumbnail {
+    fn from(cover_art: CoverArt) -> Self {
+        Thumbnail::Data {
+            data: cover_art.data,
+            mime_type: cover_art.mime_type,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CoverArtIndex {
+    /// Entries by filename
+    entries: HashMap<String, CoverArtEntry>,
+    /// Time of the oldest access which has not been written yet
+    #[serde(skip)]
+    unsaved_since: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CoverArtEntry {
+    mime_type: String,
+    etag: String,
+    size: u64,
+    /// Unix timestamp of when the file was written
+    created: u64,
+    /// Unix timestamp of the last time the file was read
+    last_access: u64,
+}
+
+/// Downloaded cover art and scaled down variants of it
+///
+/// Variants are generated on demand from the original image.
+/// When the cache grows above its maximum size the least recently used files are removed.
+#[derive(Debug)]
+pub struct CoverArtCache {
+    path: PathBuf,
+    max_size: u64,
+    index: Mutex<CoverArtIndex>,
+}
+
+impl CoverArtCache {
+    pub fn new(path: PathBuf, max_size: u64) -> Result<CoverArtCache, Error> {
+        create_dir_all(&path)?;
+        let index = load_index(&path);
+        let cache = CoverArtCache {
+            path,
+            max_size,
+            index: Mutex::new(index),
+        };
+        cache.cleanup();
+
+        Ok(cache)
+    }
+
+    /// Returns the image for the key scaled to fit into the requested size
+    ///
+    /// Returns `None` when the original image has not been cached yet.
+    /// Images are never scaled up, `None` as size returns the original image.
+    pub async fn get(&self, key: &str, size: Option<u32>) -> Result<Option<CoverArt>, Error> {
+        let original = original_filename(key);
+        let variant = match variant_size(size) {
+            Some(variant) => variant,
+            None => return self.read(&original).await,
+        };
+        let filename = variant_filename(key, variant);
+        if let Some(cover_art) = self.read(&filename).await? {
+            return Ok(Some(cover_art));
+        }
+        let cover_art = match self.read(&original).await? {
+            Some(cover_art) => cover_art,
+            None => return Ok(None),
+        };
+        trace!("Scaling {} to {}px", key, variant);
+        let data = cover_art.data.clone();
+        let resized = tokio::task::spawn_blocking(move || resize(&data, variant)).await??;
+        match resized {
+            Some(data) => {
+                self.write(filename.clone(), &data).await?;
+                self.read(&filename).await
+            }
+            // The original is smaller than the requested size, it's stored as the variant
+            // so it doesn't have to be decoded again
+            None => {
+                self.write(filename, &cover_art.data).await?;
+                Ok(Some(cover_art))
+            }
+        }
+    }
+
+    /// Stores the original image for the key, replacing all previously generated variants
+    pub async fn store(&self, key: &str, data: &[u8]) -> Result<(), Error> {
+        debug!("Caching cover art {}", key);
+        let original = original_filename(key);
+        let variants: Vec<String> = VARIANT_SIZES
+            .iter()
+            .map(|size| variant_filename(key, *size))
+            .collect();
+        self.remove(&variants);
+        self.write(original, data).await
+    }
+
+    /// Removes the original image and all variants of the key
+    pub fn invalidate(&self, key: &str) {
+        debug!("Invalidating cover art {}", key);
+        let mut filenames: Vec<String> = VARIANT_SIZES
+            .iter()
+            .map(|size| variant_filename(key, *size))
+            .collect();
+        filenames.push(original_filename(key));
+        self.remove(&filenames);
+    }
+
+    /// Downloads the image at the url and stores it for the key
+    ///
+    /// Only http urls are supported, local files have to be read by their provider.
+    pub async fn download(&self, key: &str, url: &str) -> Result<(), Error> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            bail!("Unsupported cover art url {}", url);
+        }
+        debug!("Downloading cover art {}", url);
+        let res = reqwest::get(url).await?.error_for_status()?;
+        let data = res.bytes().await?;
+        self.store(key, &data).await
+    }
+
+    async fn read(&self, filename: &str) -> Result<Option<CoverArt>, Error> {
+        let entry = {
+            let mut index = self.index.lock().unwrap();
+            match index.entries.get_mut(filename) {
+                Some(entry) => {
+                    entry.last_access = now();
+                    entry.clone()
+                }
+                None => return Ok(None),
+            }
+        };
+        let data = match tokio::fs::read(self.path.join(filename)).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut index = self.index.lock().unwrap();
+                index.entries.remove(filename);
+                self.save(&mut index);
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        {
+            let mut index = self.index.lock().unwrap();
+            let unsaved_since = *index.unsaved_since.get_or_insert_with(Instant::now);
+            if unsaved_since.elapsed() >= ACCESS_SAVE_INTERVAL {
+                self.save(&mut index);
+            }
+        }
+
+        Ok(Some(CoverArt {
+            data,
+            mime_type: entry.mime_type,
+            etag: entry.etag,
+            last_modified: UNIX_EPOCH + Duration::from_secs(entry.created),
+        }))
+    }
+
+    async fn write(&self, filename: String, data: &[u8]) -> Result<(), Error> {
+        tokio::fs::write(self.path.join(&filename), data).await?;
+        let timestamp = now();
+        let entry = CoverArtEntry {
+            mime_type: mime_type(data).to_string(),
+            etag: format!("{:x}", md5::compute(data)),
+            size: data.len() as u64,
+            created: timestamp,
+            last_access: timestamp,
+        };
+        let mut index = self.index.lock().unwrap();
+        index.entries.insert(filename.clone(), entry);
+        self.evict(&mut index, &filename);
+        self.save(&mut index);
+
+        Ok(())
+    }
+
+    fn remove(&self, filenames: &[String]) {
+        let mut index = self.index.lock().unwrap();
+        for filename in filenames {
+            if index.entries.remove(filename).is_some() {
+                let _ = std::fs::remove_file(self.path.join(filename));
+            }
+        }
+        self.save(&mut index);
+    }
+
+    /// Removes the least recently used images until the cache fits its maximum size
+    fn evict(&self, index: &mut CoverArtIndex, keep: &str) {
+        let mut size: u64 = index.entries.values().map(|entry| entry.size).sum();
+        while size > self.max_size {
+            let oldest = index
+                .entries
+                .iter()
+                .filter(|(filename, _)| filename.as_str() != keep)
+                .min_by_key(|(_, entry)| entry.last_access)
+                .map(|(filename, _)| filename.clone());
+            let filename = match oldest {
+                Some(filename) => filename,
+                None => break,
+            };
+            let entry = index.entries.remove(&filename).unwrap();
+            debug!("Evicting cover art {} from cache", &filename);
+            if let Err(e) = std::fs::remove_file(self.path.join(&filename)) {
+                error!("Removing cached cover art {} failed: {:?}", &filename, e);
+            }
+            size -= entry.size;
+        }
+    }
+
+    /// Drops index entries whose file is gone
+    fn cleanup(&self) {
+        let mut index = self.index.lock().unwrap();
+        let path = &self.path;
+        index
+            .entries
+            .retain(|filename, _| path.join(filename).exists());
+        self.evict(&mut index, "");
+        self.save(&mut index);
+    }
+
+    fn save(&self, index: &mut CoverArtIndex) {
+        index.unsaved_since = None;
+        let path = self.path.join(INDEX_FILE);
+        let result = serde_json::to_vec(index)
+            .map_err(Error::from)
+            .and_then(|data| std::fs::write(&path, data).map_err(Error::from));
+        if let Err(e) = result {
+            error!("Saving cover art index failed: {:?}", e);
+        }
+    }
+}
+
+impl Drop for CoverArtCache {
+    fn drop(&mut self) {
+        let mut index = self.index.lock().unwrap();
+        if index.unsaved_since.is_some() {
+            self.save(&mut index);
+        }
+    }
+}
+
+fn load_index(path: &Path) -> CoverArtIndex {
+    let path = path.join(INDEX_FILE);
+    if !path.exists() {
+        return CoverArtIndex::default();
+    }
+    match std::fs::read(&path)
+        .map_err(Error::from)
+        .and_then(|data| serde_json::from_slice(&data).map_err(Error::from))
+    {
+        Ok(index) => index,
+        Err(e) => {
+            error!("Loading cover art index failed, starting with an empty cache: {:?}", e);
+            CoverArtIndex::default()
+        }
+    }
+}
+
+/// The smallest variant the requested size fits into, `None` for the original image
+fn variant_size(size: Option<u32>) -> Option<u32> {
+    let size = size?;
+    VARIANT_SIZES.iter().copied().find(|variant| *variant >= size)
+}
+
+fn original_filename(key: &str) -> String {
+    format!("{:x}", md5::compute(key))
+}
+
+fn variant_filename(key: &str, size: u32) -> String {
+    format!("{}-{}", original_filename(key), size)
+}
+
+/// Scales the image down to fit into a square of the given size
+///
+/// Returns `None` when the image is small enough already.
+/// PNGs stay PNGs to keep transparency, everything else is encoded as jpeg.
+fn resize(data: &[u8], size: u32) -> Result<Option<Vec<u8>>, Error> {
+    let format = image::guess_format(data)?;
+    let image = image::load_from_memory_with_format(data, format)?;
+    let (width, height) = image.dimensions();
+    if width <= size && height <= size {
+        return Ok(None);
+    }
+    let output_format = match format {
+        ImageFormat::Png => ImageOutputFormat::Png,
+        _ => ImageOutputFormat::Jpeg(90),
+    };
+    let mut buffer = Vec::new();
+    image
+        .thumbnail(size, size)
+        .write_to(&mut buffer, output_format)
+        .map_err(|e| format_err!("Encoding cover art failed: {:?}", e))?;
+
+    Ok(Some(buffer))
+}
+
+/// Sniffs the mime type from the image data, falling back to jpeg
+fn mime_type(data: &[u8]) -> &'static str {
+    match image::guess_format(data) {
+        Ok(ImageFormat::Png) => "image/png",
+        Ok(ImageFormat::Gif) => "image/gif",
+        Ok(ImageFormat::WebP) => "image/webp",
+        Ok(ImageFormat::Bmp) => "image/bmp",
+        _ => "image/jpeg",
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{mime_type, variant_size, CoverArtCache, INDEX_FILE};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const GIF: &[u8] = b"GIF89a\x01\0\x01\0";
+    const WEBP: &[u8] = b"RIFF\0\0\0\0WEBPVP8 ";
+    const BMP: &[u8] = b"BM\0\0\0\0\0\0\0\0";
+    const JPEG: &[u8] = b"\xff\xd8\xff\xe0\0\x10JFIF";
+    /// A complete png of a single pixel
+    const PIXEL: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00, 0x3a,
+        0x7e, 0x9b, 0x55, 0x00, 0x00, 0x00, 0x0a, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x60,
+        0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x48, 0xaf, 0xa4, 0x71, 0x00, 0x00, 0x00, 0x00, 0x49,
+        0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    fn cache(max_size: u64) -> (CoverArtCache, PathBuf) {
+        let path = std::env::temp_dir().join(format!("rustic-coverart-{}", uuid::Uuid::new_v4()));
+
+        (CoverArtCache::new(path.clone(), max_size).unwrap(), path)
+    }
+
+    #[test]
+    fn variant_size_should_round_up_to_next_variant() {
+        assert_eq!(variant_size(Some(100)), Some(128));
+        assert_eq!(variant_size(Some(64)), Some(64));
+    }
+
+    #[test]
+    fn variant_size_should_use_original_for_large_sizes() {
+        assert_eq!(variant_size(Some(2048)), None);
+        assert_eq!(variant_size(None), None);
+    }
+
+    #[test]
+    fn mime_type_should_sniff_the_image_format() {
+        assert_eq!(mime_type(PNG), "image/png");
+        assert_eq!(mime_type(GIF), "image/gif");
+        assert_eq!(mime_type(WEBP), "image/webp");
+        assert_eq!(mime_type(BMP), "image/bmp");
+        assert_eq!(mime_type(JPEG), "image/jpeg");
+    }
+
+    #[test]
+    fn mime_type_should_fall_back_to_jpeg() {
+        assert_eq!(mime_type(b"not an image"), "image/jpeg");
+    }
+
+    #[tokio::test]
+    async fn get_should_return_stored_images() {
+        let (cache, path) = cache(1024);
+        cache.store("test:a", PNG).await.unwrap();
+
+        let cover_art = cache.get("test:a", None).await.unwrap().unwrap();
+
+        assert_eq!(cover_art.data, PNG);
+        assert_eq!(cover_art.mime_type, "image/png");
+        assert_eq!(cover_art.etag, format!("{:x}", md5::compute(PNG)));
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn get_should_not_write_the_index_on_every_hit() {
+        let (cache, path) = cache(1024);
+        cache.store("test:a", PNG).await.unwrap();
+        std::fs::remove_file(path.join(INDEX_FILE)).unwrap();
+
+        cache.get("test:a", None).await.unwrap();
+
+        assert!(!path.join(INDEX_FILE).exists());
+        drop(cache);
+        assert!(path.join(INDEX_FILE).exists());
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn get_should_store_small_originals_as_variant() {
+        let (cache, path) = cache(1024);
+        cache.store("test:a", PIXEL).await.unwrap();
+
+        let cover_art = cache.get("test:a", Some(64)).await.unwrap().unwrap();
+
+        assert_eq!(cover_art.data, PIXEL);
+        let variant = std::fs::read(path.join(super::variant_filename("test:a", 64))).unwrap();
+        assert_eq!(variant, PIXEL);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalidate_should_remove_the_image_and_its_variants() {
+        let (cache, path) = cache(1024);
+        cache.store("test:a", PNG).await.unwrap();
+        cache.store("test:b", PNG).await.unwrap();
+
+        cache.invalidate("test:a");
+
+        assert!(cache.get("test:a", None).await.unwrap().is_none());
+        assert!(cache.get("test:b", None).await.unwrap().is_some());
+        assert!(!path.join(super::original_filename("test:a")).exists());
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn store_should_evict_the_least_recently_used_images() {
+        let size = PNG.len() as u64;
+        let (cache, path) = cache(size * 2);
+        cache.store("test:a", PNG).await.unwrap();
+        cache.store("test:b", PNG).await.unwrap();
+        {
+            let mut index = cache.index.lock().unwrap();
+            for (filename, entry) in index.entries.iter_mut() {
+                entry.last_access = if filename == &super::original_filename("test:a") {
+                    2
+                } else {
+                    1
+                };
+            }
+        }
+
+        cache.store("test:c", PNG).await.unwrap();
+
+        assert!(cache.get("test:a", None).await.unwrap().is_some());
+        assert!(cache.get("test:b", None).await.unwrap().is_none());
+        assert!(cache.get("test:c", None).await.unwrap().is_some());
+        assert!(!path.join(super::original_filename("test:b")).exists());
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -1,150 +1,74 @@
-use std::collections::HashMap;
-use std::fs::{create_dir_all, OpenOptions};
-use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use failure::{format_err, Error};
-use log::{debug, trace};
-use pinboard::NonEmptyPinboard;
-use reqwest::get;
+use failure::Error;
 
-use crate::provider::Thumbnail;
 use crate::Track;
 
-#[derive(Debug)]
-struct CachedEntry {
-    uri: String,
-    filename: String,
-}
+pub use self::coverart::{CoverArt, CoverArtCache};
+pub use self::pinned::PinnedTracks;
+pub use self::tracks::{default_cache_dir, default_coverart_dir, CacheOptions, TrackCache};
+
+mod coverart;
+mod pinned;
+mod tracks;
 
 #[derive(Debug)]
 pub struct Cache {
-    pub tracks: Arc<NonEmptyPinboard<HashMap<String, String>>>,
+    pub tracks: TrackCache,
+    pub pinned: PinnedTracks,
+    pub coverart: CoverArtCache,
+    runtime: tokio::runtime::Handle,
 }
 
 pub type SharedCache = Arc<Cache>;
 
-pub fn setup() -> Result<(), Error> {
-    create_dir_all(".cache/music")?;
-    create_dir_all(".cache/coverart")?;
-
-    Ok(())
-}
-
 impl Cache {
-    pub fn new() -> Cache {
-        Cache {
-            tracks: Arc::new(NonEmptyPinboard::new(HashMap::new())),
-        }
-    }
-
-    pub fn fetch_track(&self, track: &Track, stream_url: &str) -> Result<String, Error> {
-        trace!("fetch_track (track: {}, stream_url: {})", track, stream_url);
-        {
-            let map = self.tracks.read();
-            if let Some(file) = map.get(&track.uri) {
-                return Ok(format!("file://.cache/music/{}", file));
-            }
-        }
-        trace!("track not cached yet");
-        let entry = cache_track(track, stream_url)?;
-        {
-            let mut map = self.tracks.read();
-            map.insert(entry.uri.clone(), entry.filename.clone());
-            self.tracks.set(map);
-        }
-        Ok(format!("file://.cache/music/{}", entry.filename))
-    }
-
-    pub fn prepare_track(&self, track: &Track, stream_url: &str) -> Result<(), Error> {
-        self.fetch_track(track, stream_url)?;
-        Ok(())
+    /// Blocking fetches of threads outside of the runtime are run on the given runtime
+    pub fn new(options: CacheOptions, runtime: tokio::runtime::Handle) -> Result<Cache, Error> {
+        let pinned = PinnedTracks::new(options.path.join("pinned"))?;
+        let coverart =
+            CoverArtCache::new(options.coverart_path.clone(), options.coverart_max_size)?;
+
+        Ok(Cache {
+            tracks: TrackCache::new(options)?,
+            pinned,
+            coverart,
+            runtime,
+        })
     }
 
-    pub async fn fetch_thumbnail(&self, cover: &Thumbnail) -> Result<Option<Thumbnail>, Error> {
-        match cover {
-            Thumbnail::Url(ref url) => {
-                let path = self.get_coverart_path(url);
-                if path.exists() {
-                    let file = self.read_cache_file(&path).await?;
-                    Ok(Some(file))
-                } else {
-                    Ok(None)
-                }
-            }
-            _ => Ok(None),
-        }
+    /// Downloads the track unless it's cached already and returns its file url
+    pub async fn fetch_track(&self, track: &Track, stream_url: &str) -> Result<String, Error> {
+        self.tracks.fetch(track, stream_url).await
     }
 
-    pub async fn cache_thumbnail(&self, cover: &Thumbnail) -> Result<Thumbnail, Error> {
-        match cover {
-            Thumbnail::Url(ref url) => self.download_coverart(url).await,
-            _ => Err(format_err!("only caching thumbnail urls")),
-        }
+    /// Like [Cache::fetch_track] for threads outside of the tokio runtime
+    pub fn fetch_track_blocking(&self, track: &Track, stream_url: &str) -> Result<String, Error> {
+        self.runtime.block_on(self.tracks.fetch(track, stream_url))
     }
 
-    fn get_coverart_path(&self, url: &str) -> PathBuf {
-        let hash = md5::compute(url);
-        let path = format!(".cache/coverart/{:x}", hash);
-
-        let mut result = PathBuf::new();
-        result.push(&path);
-
-        result
+    /// Returns the file url of the track if it has been cached before
+    pub fn cached_track(&self, track: &Track) -> Option<String> {
+        self.tracks.get(track)
     }
 
-    async fn download_coverart(&self, url: &str) -> Result<Thumbnail, Error> {
-        use futures::prelude::*;
-        use tokio::fs::File;
-        use tokio_util::compat::TokioAsyncWriteCompatExt;
-
-        debug!("downloading thumbnail {}", url);
-
-        let file_path = self.get_coverart_path(url);
-        let mut file = File::create(&file_path).await?.compat_write();
-        let res = get(url).await?;
-        let stream = res
-            .bytes_stream()
-            .map_err(|e| futures::io::Error::new(futures::io::ErrorKind::Other, e))
-            .into_async_read();
-
-        futures::io::copy(stream, &mut file).await?;
-
-        self.read_cache_file(&file_path).await
+    /// Stores a track which was downloaded elsewhere, e.g. while streaming it
+    pub fn store_track(&self, track: &Track, data: &[u8]) -> Result<(), Error> {
+        self.tracks.store(track, data)
     }
 
-    async fn read_cache_file(&self, path: &Path) -> Result<Thumbnail, Error> {
-        let data = tokio::fs::read(path).await?;
-
-        Ok(Thumbnail::Data {
-            mime_type: String::from("image/jpeg"),
-            data,
-        })
+    /// Makes the track available offline, reusing the cached file when possible
+    pub async fn pin_track(&self, track: &Track, stream_url: &str) -> Result<(), Error> {
+        if self.pinned.get(track).is_some() {
+            return Ok(());
+        }
+        match self.tracks.file_path(track) {
+            Some(path) => self.pinned.copy(track, &path),
+            None => self.pinned.download(track, stream_url).await,
+        }
     }
-}
 
-fn cache_track(track: &Track, stream_url: &str) -> Result<CachedEntry, Error> {
-    trace!("cache_track (track: {}, stream_url: {})", track, stream_url);
-    let base = ".cache/music";
-    let hash = md5::compute(&track.uri);
-    let filename = format!("{:x}", hash);
-    let path = format!("{}/{}", base, filename);
-    if Path::new(&path).exists() {
-        trace!("file already exists");
-        return Ok(CachedEntry {
-            filename,
-            uri: track.uri.clone(),
-        });
+    pub async fn prepare_track(&self, track: &Track, stream_url: &str) -> Result<(), Error> {
+        self.tracks.prefetch(track, stream_url).await
     }
-
-    debug!("{} -> {}", &track.uri, &filename);
-
-    let mut file = OpenOptions::new().create(true).write(true).open(&path)?;
-    let mut res = reqwest::blocking::get(stream_url)?;
-    res.copy_to(&mut file)?;
-
-    Ok(CachedEntry {
-        filename,
-        uri: track.uri.clone(),
-    })
 }
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use failure::Error;
+use log::{debug, error};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::Track;
+
+use super::tracks::{download_file, file_url, track_filename};
+
+const INDEX_FILE: &str = "pinned.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PinnedIndex {
+    /// Pinned tracks, albums and playlists with the uris of their tracks
+    items: HashMap<String, Vec<String>>,
+    /// Downloaded files by track uri
+    files: HashMap<String, String>,
+}
+
+impl PinnedIndex {
+    fn is_referenced(&self, track: &str) -> bool {
+        self.items
+            .values()
+            .any(|tracks| tracks.iter().any(|uri| uri == track))
+    }
+}
+
+/// Tracks which were made available offline
+///
+/// Pinned tracks live in their own directory and are never evicted,
+/// they are only removed once no pinned item references them anymore.
+#[derive(Debug)]
+pub struct PinnedTracks {
+    path: PathBuf,
+    index: Mutex<PinnedIndex>,
+}
+
+impl PinnedTracks {
+    pub fn new(path: PathBuf) -> Result<PinnedTracks, Error> {
+        create_dir_all(&path)?;
+        let index = load_index(&path);
+
+        Ok(PinnedTracks {
+            path,
+            index: Mutex::new(index),
+        })
+    }
+
+    /// Returns the file url of the track if it has been downloaded for offline use
+    pub fn get(&self, track: &Track) -> Option<String> {
+        let index = self.index.lock().unwrap();
+        let filename = index.files.get(&track.uri)?;
+        let path = self.path.join(filename);
+        if !path.exists() {
+            return None;
+        }
+
+        Some(file_url(&path))
+    }
+
+    pub fn contains(&self, track: &Track) -> bool {
+        self.index.lock().unwrap().files.contains_key(&track.uri)
+    }
+
+    /// Whether the track, album or playlist with the given uri is pinned
+    pub fn is_pinned(&self, uri: &str) -> bool {
+        self.index.lock().unwrap().items.contains_key(uri)
+    }
+
+    /// Uris of all pinned tracks, albums and playlists
+    pub fn items(&self) -> Vec<String> {
+        self.index.lock().unwrap().items.keys().cloned().collect()
+    }
+
+    /// Remembers the tracks of a pinned item
+    ///
+    /// The tracks still have to be downloaded with [PinnedTracks::download].
+    pub fn pin(&self, uri: &str, tracks: &[Track]) {
+        let mut index = self.index.lock().unwrap();
+        let tracks = tracks.iter().map(|track| track.uri.clone()).collect();
+        index.items.insert(uri.to_string(), tracks);
+        self.save(&index);
+    }
+
+    /// Downloads the track unless it's available offline already
+    pub async fn download(&self, track: &Track, stream_url: &str) -> Result<(), Error> {
+        if self.get(track).is_some() || !self.is_referenced(track) {
+            return Ok(());
+        }
+        let filename = track_filename(track);
+        let path = self.path.join(&filename);
+        debug!("Pinning {} -> {}", &track.uri, &filename);
+        download_file(stream_url, &path).await?;
+        self.insert(track, filename);
+
+        Ok(())
+    }
+
+    /// Copies a track from the track cache instead of downloading it again
+    pub fn copy(&self, track: &Track, source: &Path) -> Result<(), Error> {
+        let filename = track_filename(track);
+        std::fs::copy(source, self.path.join(&filename))?;
+        self.insert(track, filename);
+
+        Ok(())
+    }
+
+    /// Removes the item and deletes all files which are not used by other pinned items
+    pub fn unpin(&self, uri: &str) -> Result<(), Error> {
+        let mut index = self.index.lock().unwrap();
+        if index.items.remove(uri).is_none() {
+            return Ok(());
+        }
+        let referenced: Vec<String> = index.items.values().flatten().cloned().collect();
+        let unused: Vec<String> = index
+            .files
+            .keys()
+            .filter(|track| !referenced.contains(track))
+            .cloned()
+            .collect();
+        for track in unused {
+            let filename = index.files.remove(&track).unwrap();
+            debug!("Removing pinned track {}", &track);
+            if let Err(e) = std::fs::remove_file(self.path.join(&filename)) {
+                error!("Removing pinned file {} failed: {:?}", &filename, e);
+            }
+        }
+        self.save(&index);
+
+        Ok(())
+    }
+
+    fn is_referenced(&self, track: &Track) -> bool {
+        self.index.lock().unwrap().is_referenced(&track.uri)
+    }
+
+    /// Adds the file to the index unless the track was unpinned in the meantime
+    fn insert(&self, track: &Track, filename: String) {
+        let mut index = self.index.lock().unwrap();
+        if !index.is_referenced(&track.uri) {
+            debug!("{} was unpinned while downloading", &track.uri);
+            if let Err(e) = std::fs::remove_file(self.path.join(&filename)) {
+                error!("Removing pinned file {} failed: {:?}", &filename, e);
+            }
+            return;
+        }
+        index.files.insert(track.uri.clone(), filename);
+        self.save(&index);
+    }
+
+    fn save(&self, index: &PinnedIndex) {
+        let path = self.path.join(INDEX_FILE);
+        let result = serde_json::to_vec(index)
+            .map_err(Error::from)
+            .and_then(|data| std::fs::write(&path, data).map_err(Error::from));
+        if let Err(e) = result {
+            error!("Saving pinned index failed: {:?}", e);
+        }
+    }
+}
+
+fn load_index(path: &Path) -> PinnedIndex {
+    let path = path.join(INDEX_FILE);
+    if !path.exists() {
+        return PinnedIndex::default();
+    }
+    match std::fs::read(&path)
+        .map_err(Error::from)
+        .and_then(|data| serde_json::from_slice(&data).map_err(Error::from))
+    {
+        Ok(index) => index,
+        Err(e) => {
+            error!("Loading pinned index failed: {:?}", e);
+            PinnedIndex::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::testing::track;
+
+    use super::PinnedTracks;
+
+    fn pinned() -> (PinnedTracks, PathBuf) {
+        let path = std::env::temp_dir().join(format!("rustic-pinned-{}", uuid::Uuid::new_v4()));
+        let pinned = PinnedTracks::new(path.join("pinned")).unwrap();
+        std::fs::write(path.join("source"), b"data").unwrap();
+
+        (pinned, path)
+    }
+
+    #[test]
+    fn copy_should_make_the_track_available() {
+        let (pinned, path) = pinned();
+        let track = track("test:track");
+        pinned.pin("test:album", &[track.clone()]);
+
+        pinned.copy(&track, &path.join("source")).unwrap();
+
+        assert!(pinned.contains(&track));
+        assert!(pinned.get(&track).is_some());
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn unpin_should_remove_unreferenced_files() {
+        let (pinned, path) = pinned();
+        let track = track("test:track");
+        pinned.pin("test:album", &[track.clone()]);
+        pinned.copy(&track, &path.join("source")).unwrap();
+
+        pinned.unpin("test:album").unwrap();
+
+        assert!(!pinned.contains(&track));
+        assert!(!pinned.is_pinned("test:album"));
+        assert_eq!(std::fs::read_dir(path.join("pinned")).unwrap().count(), 1);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn unpin_should_keep_files_of_other_items() {
+        let (pinned, path) = pinned();
+        let track = track("test:track");
+        pinned.pin("test:album", &[track.clone()]);
+        pinned.pin("test:playlist", &[track.clone()]);
+        pinned.copy(&track, &path.join("source")).unwrap();
+
+        pinned.unpin("test:album").unwrap();
+
+        assert!(pinned.get(&track).is_some());
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn copy_should_discard_tracks_unpinned_while_downloading() {
+        let (pinned, path) = pinned();
+        let track = track("test:track");
+        pinned.pin("test:album", &[track.clone()]);
+        pinned.unpin("test:album").unwrap();
+
+        pinned.copy(&track, &path.join("source")).unwrap();
+
+        assert!(!pinned.contains(&track));
+        // only the index is left
+        assert_eq!(std::fs::read_dir(path.join("pinned")).unwrap().count(), 1);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use failure::Error;
+use futures::prelude::*;
+use log::{debug, error, trace};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::Track;
+
+const INDEX_FILE: &str = "index.json";
+/// Access times are only kept in memory for this long before the index is written
+const ACCESS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Settings for the audio cache
+#[derive(Debug, Clone)]
+pub struct CacheOptions {
+    /// Directory the cached tracks are stored in
+    pub path: PathBuf,
+    /// Maximum size of all cached tracks in bytes
+    pub max_size: u64,
+    /// Number of upcoming queue entries which are downloaded ahead of time
+    pub prefetch: usize,
+    /// Directory the cover art is stored in
+    pub coverart_path: PathBuf,
+    /// Maximum size of all cached cover art in bytes
+    pub coverart_max_size: u64,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        CacheOptions {
+            path: default_cache_dir(),
+            max_size: 2 * 1024 * 1024 * 1024,
+            prefetch: 2,
+            coverart_path: default_coverart_dir(),
+            coverart_max_size: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// `$XDG_CACHE_HOME/rustic/music` or `.cache/music` when there is no cache dir
+pub fn default_cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .map(|dir| dir.join("rustic"))
+        .unwrap_or_else(|| PathBuf::from(".cache"))
+        .join("music")
+}
+
+/// `$XDG_CACHE_HOME/rustic/coverart` or `.cache/coverart` when there is no cache dir
+pub fn default_coverart_dir() -> PathBuf {
+    dirs::cache_dir()
+        .map(|dir| dir.join("rustic"))
+        .unwrap_or_else(|| PathBuf::from(".cache"))
+        .join("coverart")
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrackIndex {
+    entries: HashMap<String, IndexEntry>,
+    /// Time of the oldest access which has not been written yet
+    #[serde(skip)]
+    unsaved_since: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    filename: String,
+    size: u64,
+    /// Unix timestamp of the last time the track was played
+    last_access: u64,
+}
+
+/// Audio files of streamed tracks
+///
+/// The index is persisted next to the files so the cache survives restarts.
+/// When the cache grows above its maximum size the least recently used tracks are removed.
+#[derive(Debug)]
+pub struct TrackCache {
+    options: CacheOptions,
+    index: Mutex<TrackIndex>,
+    /// Uris of tracks which are currently downloading
+    pending: Mutex<HashSet<String>>,
+}
+
+impl TrackCache {
+    pub fn new(options: CacheOptions) -> Result<TrackCache, Error> {
+        create_dir_all(&options.path)?;
+        let index = load_index(&options.path);
+        let cache = TrackCache {
+            options,
+            index: Mutex::new(index),
+            pending: Mutex::new(HashSet::new()),
+        };
+        cache.cleanup();
+
+        Ok(cache)
+    }
+
+    pub fn prefetch_count(&self) -> usize {
+        self.options.prefetch
+    }
+
+    /// Returns the file url of the track if it's cached and marks it as recently used
+    pub fn get(&self, track: &Track) -> Option<String> {
+        let mut index = self.index.lock().unwrap();
+        let entry = index.entries.get_mut(&track.uri)?;
+        let path = self.options.path.join(&entry.filename);
+        if !path.exists() {
+            index.entries.remove(&track.uri);
+            self.save(&mut index);
+            return None;
+        }
+        entry.last_access = now();
+        let unsaved_since = *index.unsaved_since.get_or_insert_with(Instant::now);
+        if unsaved_since.elapsed() >= ACCESS_SAVE_INTERVAL {
+            self.save(&mut index);
+        }
+
+        Some(file_url(&path))
+    }
+
+    /// Returns the path of the cached file without touching the entry
+    pub fn file_path(&self, track: &Track) -> Option<PathBuf> {
+        let index = self.index.lock().unwrap();
+        let entry = index.entries.get(&track.uri)?;
+        let path = self.options.path.join(&entry.filename);
+
+        Some(path).filter(|path| path.exists())
+    }
+
+    pub fn contains(&self, track: &Track) -> bool {
+        self.index.lock().unwrap().entries.contains_key(&track.uri)
+    }
+
+    /// Downloads the track unless it's cached already and returns its file url
+    pub async fn fetch(&self, track: &Track, stream_url: &str) -> Result<String, Error> {
+        trace!("fetch_track (track: {}, stream_url: {})", track, stream_url);
+        if let Some(path) = self.get(track) {
+            return Ok(path);
+        }
+        trace!("track not cached yet");
+        self.pending.lock().unwrap().insert(track.uri.clone());
+        let result = self.download(track, stream_url).await;
+        self.pending.lock().unwrap().remove(&track.uri);
+
+        result
+    }
+
+    /// Downloads the track in the background unless it's cached or downloading already
+    pub async fn prefetch(&self, track: &Track, stream_url: &str) -> Result<(), Error> {
+        if self.contains(track) || self.pending.lock().unwrap().contains(&track.uri) {
+            return Ok(());
+        }
+        debug!("Prefetching {}", track);
+        self.fetch(track, stream_url).await?;
+
+        Ok(())
+    }
+
+    /// Stores a track which was downloaded elsewhere, e.g. while streaming it
+    pub fn store(&self, track: &Track, data: &[u8]) -> Result<(), Error> {
+        let filename = track_filename(track);
+        let path = self.options.path.join(&filename);
+        debug!("{} -> {}", &track.uri, &filename);
+        std::fs::write(&path, data)?;
+        self.insert(track, filename, data.len() as u64);
+
+        Ok(())
+    }
+
+    async fn download(&self, track: &Track, stream_url: &str) -> Result<String, Error> {
+        let filename = track_filename(track);
+        let path = self.options.path.join(&filename);
+        debug!("{} -> {}", &track.uri, &filename);
+        let size = download_file(stream_url, &path).await?;
+        self.insert(track, filename, size);
+
+        Ok(file_url(&path))
+    }
+
+    fn insert(&self, track: &Track, filename: String, size: u64) {
+        let mut index = self.index.lock().unwrap();
+        index.entries.insert(
+            track.uri.clone(),
+            IndexEntry {
+                filename,
+                size,
+                last_access: now(),
+            },
+        );
+        self.evict(&mut index, &track.uri);
+        self.save(&mut index);
+    }
+
+    /// Removes the least recently used tracks until the cache fits its maximum size
+    ///
+    /// The track with the given uri is kept even if it's larger than the cache.
+    fn evict(&self, index: &mut TrackIndex, keep: &str) {
+        let mut size: u64 = index.entries.values().map(|entry| entry.size).sum();
+        while size > self.options.max_size {
+            let oldest = index
+                .entries
+                .iter()
+                .filter(|(uri, _)| uri.as_str() != keep)
+                .min_by_key(|(_, entry)| entry.last_access)
+                .map(|(uri, _)| uri.clone());
+            let uri = match oldest {
+                Some(uri) => uri,
+                None => break,
+            };
+            let entry = index.entries.remove(&uri).unwrap();
+            debug!("Evicting {} from cache", &uri);
+            if let Err(e) = std::fs::remove_file(self.options.path.join(&entry.filename)) {
+                error!("Removing cached file {} failed: {:?}", &entry.filename, e);
+            }
+            size -= entry.size;
+        }
+    }
+
+    /// Drops index entries whose file is gone and leftovers of interrupted downloads
+    fn cleanup(&self) {
+        let mut index = self.index.lock().unwrap();
+        let path = &self.options.path;
+        index
+            .entries
+            .retain(|_, entry| path.join(&entry.filename).exists());
+        if let Ok(files) = std::fs::read_dir(path) {
+            for file in files.filter_map(|file| file.ok()) {
+                if file.path().extension().map(|ext| ext == "part").unwrap_or(false) {
+                    let _ = std::fs::remove_file(file.path());
+                }
+            }
+        }
+        self.evict(&mut index, "");
+        self.save(&mut index);
+    }
+
+    fn save(&self, index: &mut TrackIndex) {
+        index.unsaved_since = None;
+        let path = self.options.path.join(INDEX_FILE);
+        let result = serde_json::to_vec(index)
+            .map_err(Error::from)
+            .and_then(|data| std::fs::write(&path, data).map_err(Error::from));
+        if let Err(e) = result {
+            error!("Saving cache index failed: {:?}", e);
+        }
+    }
+}
+
+impl Drop for TrackCache {
+    fn drop(&mut self) {
+        let mut index = self.index.lock().unwrap();
+        if index.unsaved_since.is_some() {
+            self.save(&mut index);
+        }
+    }
+}
+
+fn load_index(path: &Path) -> TrackIndex {
+    let path = path.join(INDEX_FILE);
+    if !path.exists() {
+        return TrackIndex::default();
+    }
+    match std::fs::read(&path)
+        .map_err(Error::from)
+        .and_then(|data| serde_json::from_slice(&data).map_err(Error::from))
+    {
+        Ok(index) => index,
+        Err(e) => {
+            error!("Loading cache index failed, starting with an empty cache: {:?}", e);
+            TrackIndex::default()
+        }
+    }
+}
+
+/// Downloads the url to the given path and returns the size of the file
+///
+/// The data is written to a temporary file first so interrupted downloads never show up as complete files.
+pub(super) async fn download_file(url: &str, path: &Path) -> Result<u64, Error> {
+    use tokio::fs::File;
+    use tokio_util::compat::TokioAsyncWriteCompatExt;
+
+    // Concurrent downloads of the same track must not write to the same file
+    let partial_path = path.with_extension(format!("{}.part", uuid::Uuid::new_v4()));
+    let result: Result<u64, Error> = async {
+        let mut file = File::create(&partial_path).await?.compat_write();
+        let res = reqwest::get(url).await?.error_for_status()?;
+        let stream = res
+            .bytes_stream()
+            .map_err(|e| futures::io::Error::new(futures::io::ErrorKind::Other, e))
+            .into_async_read();
+        let size = futures::io::copy(stream, &mut file).await?;
+        file.close().await?;
+        tokio::fs::rename(&partial_path, path).await?;
+
+        Ok(size)
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&partial_path).await;
+    }
+
+    result
+}
+
+pub(super) fn track_filename(track: &Track) -> String {
+    let hash = md5::compute(&track.uri);
+    format!("{:x}", hash)
+}
+
+pub(super) fn file_url(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::testing::track;
+
+    use super::{CacheOptions, TrackCache, INDEX_FILE};
+
+    fn cache(max_size: u64) -> (TrackCache, PathBuf) {
+        let path = std::env::temp_dir().join(format!("rustic-tracks-{}", uuid::Uuid::new_v4()));
+        let options = CacheOptions {
+            path: path.clone(),
+            max_size,
+            ..CacheOptions::default()
+        };
+
+        (TrackCache::new(options).unwrap(), path)
+    }
+
+    #[test]
+    fn get_should_return_stored_tracks() {
+        let (cache, path) = cache(1024);
+        let track = track("test:a");
+        cache.store(&track, b"data").unwrap();
+
+        let url = cache.get(&track);
+
+        let file = cache.file_path(&track).unwrap();
+        assert_eq!(url, Some(format!("file://{}", file.display())));
+        assert_eq!(std::fs::read(file).unwrap(), b"data");
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn get_should_return_none_for_unknown_tracks() {
+        let (cache, path) = cache(1024);
+
+        assert_eq!(cache.get(&track("test:a")), None);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn get_should_not_write_the_index_on_every_hit() {
+        let (cache, path) = cache(1024);
+        let track = track("test:a");
+        cache.store(&track, b"data").unwrap();
+        std::fs::remove_file(path.join(INDEX_FILE)).unwrap();
+
+        cache.get(&track);
+
+        assert!(!path.join(INDEX_FILE).exists());
+        drop(cache);
+        assert!(path.join(INDEX_FILE).exists());
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn store_should_evict_the_least_recently_used_tracks() {
+        let (cache, path) = cache(10);
+        cache.store(&track("test:a"), b"aaaa").unwrap();
+        cache.store(&track("test:b"), b"bbbb").unwrap();
+        {
+            let mut index = cache.index.lock().unwrap();
+            index.entries.get_mut("test:a").unwrap().last_access = 2;
+            index.entries.get_mut("test:b").unwrap().last_access = 1;
+        }
+        let evicted = cache.file_path(&track("test:b")).unwrap();
+
+        cache.store(&track("test:c"), b"cccc").unwrap();
+
+        assert!(cache.contains(&track("test:a")));
+        assert!(!cache.contains(&track("test:b")));
+        assert!(cache.contains(&track("test:c")));
+        assert!(!evicted.exists());
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn store_should_keep_tracks_larger_than_the_cache() {
+        let (cache, path) = cache(2);
+        cache.store(&track("test:a"), b"a").unwrap();
+
+        cache.store(&track("test:b"), b"bbbb").unwrap();
+
+        assert!(!cache.contains(&track("test:a")));
+        assert!(cache.contains(&track("test:b")));
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use crate::ProviderType;
+use crate::ProviderId;
 use async_trait::async_trait;
 use failure::{format_err, Error};
 use serde::de::DeserializeOwned;
@@ -51,11 +51,21 @@ impl fmt::Debug for Credentials {
 
 #[async_trait]
 pub trait CredentialStore: Send + Sync {
-    async fn get_credentials(&self, provider: ProviderType) -> Result<Option<Credentials>, Error>;
+    async fn get_credentials(&self, provider: ProviderId) -> Result<Option<Credentials>, Error>;
 
     async fn store_credentials(
         &self,
-        provider: ProviderType,
+        provider: ProviderId,
         credentials: Credentials,
     ) -> Result<(), Error>;
+
+    /// Removes the stored credentials, succeeds when there are none
+    async fn delete_credentials(&self, provider: ProviderId) -> Result<(), Error>;
+
+    /// The id the credentials of the provider are stored under
+    ///
+    /// Tells provider implementations which account they are serving.
+    fn account(&self, provider: ProviderId) -> ProviderId {
+        provider
+    }
 }
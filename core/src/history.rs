@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use failure::Error;
+use futures::stream::{BoxStream, StreamExt};
+use rustic_queue::{multicast, MulticastReceiver, MulticastSender};
+
+use crate::library::{HistoryQuery, PlayRecord, PlayStats, SharedLibrary, Track, TrackPlays};
+use crate::{PlayerEvent, PlayerState};
+
+/// Records the tracks played by all players into the library
+#[derive(Debug, Clone)]
+pub struct PlayHistory {
+    library: SharedLibrary,
+    sessions: Arc<Mutex<HashMap<String, PlaySession>>>,
+    event_sender: MulticastSender<PlayEvent>,
+    event_receiver: MulticastReceiver<PlayEvent>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PlayEvent {
+    /// The player started playing the given track
+    Started { player: String, track: Track },
+    /// The play has been recorded, either because the track ended or another track started playing
+    Finished(PlayRecord),
+}
+
+#[derive(Debug, Default)]
+struct PlaySession {
+    current: Option<CurrentPlay>,
+    playing: bool,
+}
+
+#[derive(Debug)]
+struct CurrentPlay {
+    track: Track,
+    started_at: u64,
+    listened: Duration,
+    resumed: Option<Instant>,
+    ended: bool,
+}
+
+impl CurrentPlay {
+    fn new(track: Track, playing: bool) -> Self {
+        CurrentPlay {
+            track,
+            started_at: now(),
+            listened: Duration::default(),
+            resumed: if playing { Some(Instant::now()) } else { None },
+            ended: false,
+        }
+    }
+
+    fn resume(&mut self) {
+        if self.resumed.is_none() {
+            self.resumed = Some(Instant::now());
+        }
+    }
+
+    fn pause(&mut self) {
+        if let Some(resumed) = self.resumed.take() {
+            self.listened += resumed.elapsed();
+        }
+    }
+
+    fn into_record(mut self, player: &str) -> PlayRecord {
+        self.pause();
+        PlayRecord {
+            track: self.track,
+            player: player.to_string(),
+            started_at: self.started_at,
+            listened: self.listened.as_secs(),
+            completed: self.ended,
+        }
+    }
+}
+
+impl PlayHistory {
+    pub(crate) fn new(library: SharedLibrary) -> Self {
+        let (event_sender, event_receiver) = multicast();
+        PlayHistory {
+            library,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            event_sender,
+            event_receiver,
+        }
+    }
+
+    /// Records the plays of the player with the given id
+    pub(crate) fn observe(&self, player_id: String, events: MulticastReceiver<PlayerEvent>) {
+        let history = self.clone();
+        let mut events = Box::pin(events.stream());
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                if let Err(e) = history.handle_event(&player_id, event) {
+                    log::error!("Recording play failed: {:?}", e);
+                }
+            }
+        });
+    }
+
+    /// The current track of the player played until the end
+    ///
+    /// Called when a backend reports the track as finished,
+    /// tracks which change for any other reason count as skipped.
+    pub(crate) fn track_ended(&self, player_id: &str) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(play) = sessions
+            .get_mut(player_id)
+            .and_then(|session| session.current.as_mut())
+        {
+            play.ended = true;
+        }
+    }
+
+    fn handle_event(&self, player_id: &str, event: PlayerEvent) -> Result<(), Error> {
+        let finished = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let session = sessions.entry(player_id.to_string()).or_default();
+            match event {
+                PlayerEvent::TrackChanged(track) => {
+                    self.emit(PlayEvent::Started {
+                        player: player_id.to_string(),
+                        track: track.clone(),
+                    });
+                    let play = CurrentPlay::new(track, session.playing);
+                    session.current.replace(play)
+                }
+                PlayerEvent::StateChanged(PlayerState::Play) => {
+                    session.playing = true;
+                    if let Some(play) = session.current.as_mut() {
+                        play.resume();
+                    }
+                    None
+                }
+                PlayerEvent::StateChanged(PlayerState::Pause) => {
+                    session.playing = false;
+                    if let Some(play) = session.current.as_mut() {
+                        play.pause();
+                    }
+                    None
+                }
+                PlayerEvent::StateChanged(PlayerState::Stop) => {
+                    session.playing = false;
+                    session.current.take()
+                }
+                _ => None,
+            }
+        };
+        if let Some(play) = finished {
+            let record = play.into_record(player_id);
+            log::debug!("Recording play of {}", &record.track);
+            self.library.add_play(record.clone())?;
+            self.emit(PlayEvent::Finished(record));
+        }
+
+        Ok(())
+    }
+
+    fn emit(&self, event: PlayEvent) {
+        self.event_sender.send(event);
+    }
+
+    /// Notifies about started and finished plays of all players
+    pub fn observe_plays(&self) -> BoxStream<'static, PlayEvent> {
+        self.event_receiver.stream().boxed()
+    }
+
+    pub fn query(&self, query: HistoryQuery) -> Result<Vec<PlayRecord>, Error> {
+        self.library.query_plays(query)
+    }
+
+    /// Most played tracks, artists and albums in the given time window
+    pub fn stats(
+        &self,
+        since: Option<u64>,
+        until: Option<u64>,
+        limit: usize,
+    ) -> Result<PlayStats, Error> {
+        let plays = self.query(HistoryQuery::new().since(since).until(until))?;
+
+        Ok(PlayStats::from_plays(&plays, limit))
+    }
+
+    /// Play count and last play of the given track uris
+    pub fn track_plays(&self, uris: &[String]) -> Result<HashMap<String, TrackPlays>, Error> {
+        self.library.query_track_plays(uris)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
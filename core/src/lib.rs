@@ -1,70 +1,143 @@
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::UNIX_EPOCH;
 
 use failure::format_err;
+use futures::stream::{BoxStream, StreamExt};
 use log::{debug, trace};
+use rustic_queue::{multicast, MulticastReceiver, MulticastSender};
 use url::Url;
 
 pub use library::LibraryItemIdentifier;
 
 pub use crate::cred_store::{CredentialStore, Credentials};
 pub use crate::library::{
-    Album, Artist, Library, LibraryEvent, LibraryQueryJoins, MultiQuery, Playlist, QueryJoins,
-    Rating, SearchResults, SharedLibrary, SingleQuery, Track, TrackPosition,
+    Album, Artist, Library, LibraryChange, LibraryEvent, LibraryQueryJoins, MetadataUpdate,
+    MultiQuery, Playlist, QueryJoins, Rating, SearchResults, SharedLibrary, SingleQuery, Track,
+    TrackPosition,
 };
 pub use crate::player::{PlayerBackend, PlayerEvent, PlayerState, QueuedTrack, RepeatMode, Player};
-pub use crate::provider::{Explorer, Provider, ProviderType, InternalUri};
+pub use crate::provider::{Explorer, Provider, ProviderId, InternalUri};
 use crate::provider::{ProviderItemType, Thumbnail, ThumbnailState};
 pub use crate::storage_backend::{SharedStorageBackend, StorageBackend, StorageCollection};
 
 mod storage_backend;
 
+pub mod auth;
 pub mod cache;
 mod cred_store;
+pub mod history;
 pub mod library;
+pub mod offline;
 pub mod player;
 pub mod provider;
 pub mod sync;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 pub struct Rustic {
     player: Arc<Mutex<HashMap<String, Arc<Player>>>>,
     pub library: library::SharedLibrary,
     pub storage: SharedStorageBackend,
-    pub providers: Vec<Provider>,
+    providers: RwLock<Vec<Provider>>,
     pub cache: cache::SharedCache,
     default_player: Arc<Mutex<Option<String>>>,
     pub sync: sync::SyncState,
+    pub offline: offline::OfflineState,
+    pub auth: auth::AuthState,
+    pub history: history::PlayHistory,
+    player_event_sender: MulticastSender<(String, PlayerEvent)>,
+    player_event_receiver: MulticastReceiver<(String, PlayerEvent)>,
 }
 
 impl Rustic {
+    /// Fails when called outside of the tokio runtime
     pub fn new(
         library: Box<dyn Library>,
         storage: SharedStorageBackend,
         providers: Vec<Provider>,
+        cache: cache::CacheOptions,
     ) -> Result<Arc<Rustic>, failure::Error> {
-        let library = Arc::new(library);
+        let library: SharedLibrary = Arc::new(library);
+        let runtime = tokio::runtime::Handle::try_current()?;
+        let cache = cache::Cache::new(cache, runtime)?;
+        let history = history::PlayHistory::new(Arc::clone(&library));
+        let (player_event_sender, player_event_receiver) = multicast();
         Ok(Arc::new(Rustic {
             player: Arc::new(Mutex::new(HashMap::new())),
             library,
             storage,
-            providers,
-            cache: Arc::new(cache::Cache::new()),
+            providers: RwLock::new(providers),
+            cache: Arc::new(cache),
             default_player: Arc::new(Mutex::new(None)),
             sync: sync::SyncState::new(),
+            offline: offline::OfflineState::new(),
+            auth: auth::AuthState::new(),
+            history,
+            player_event_sender,
+            player_event_receiver,
         }))
     }
 
     pub fn add_player(&self, id: String, player: Arc<Player>) {
         debug!("Adding player {}: {:?}", id, player);
+        self.history.observe(id.clone(), player.events());
+        self.forward_player_events(id.clone(), &player);
         let mut players = self.player.lock().unwrap();
         players.insert(id, player);
     }
 
+    fn forward_player_events(&self, id: String, player: &Player) {
+        let sender = self.player_event_sender.clone();
+        let mut events = Box::pin(player.events().stream());
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                sender.send((id.clone(), event));
+            }
+        });
+    }
+
+    /// Events of all players together with the id of the emitting player
+    ///
+    /// Every subscriber receives every event.
+    pub fn observe_players(&self) -> BoxStream<'static, (String, PlayerEvent)> {
+        self.player_event_receiver.stream().boxed()
+    }
+
+    /// All currently available providers, including the ones registered by extensions
+    pub fn providers(&self) -> Vec<Provider> {
+        self.providers.read().unwrap().clone()
+    }
+
+    /// Makes the provider available for searching, syncing and playback
+    ///
+    /// Replaces an existing provider with the same id.
+    pub fn add_provider(&self, provider: Provider) {
+        debug!("Adding provider {}", provider.title());
+        let mut providers = self.providers.write().unwrap();
+        providers.retain(|p| p.id != provider.id);
+        providers.push(provider);
+    }
+
+    pub fn remove_provider(&self, id: &ProviderId) {
+        debug!("Removing provider {}", id);
+        let mut providers = self.providers.write().unwrap();
+        providers.retain(|p| &p.id != id);
+    }
+
     pub fn get_player(&self, id: String) -> Option<Arc<Player>> {
         let player = self.player.lock().unwrap();
         player.get(&id).map(Arc::clone)
     }
 
+    pub(crate) fn get_player_id(&self, player: &Player) -> Option<String> {
+        let players = self.player.lock().unwrap();
+        players
+            .iter()
+            .find(|(_, p)| std::ptr::eq(p.as_ref(), player))
+            .map(|(id, _)| id.clone())
+    }
+
     pub fn get_default_player(&self) -> Option<Arc<Player>> {
         let default_player = self.default_player.lock().unwrap();
         default_player.as_ref().and_then(|id| {
@@ -83,6 +156,26 @@ impl Rustic {
         *default_player = Some(id);
     }
 
+    /// Moves playback from one player to another, see [Player::transfer_to]
+    pub async fn transfer_playback(
+        &self,
+        from: &str,
+        to: &str,
+        keep_playing: bool,
+    ) -> Result<(), failure::Error> {
+        if from == to {
+            failure::bail!("Can't transfer playback to the same player");
+        }
+        let source = self
+            .get_player(from.to_string())
+            .ok_or_else(|| failure::format_err!("Unknown player {}", from))?;
+        let target = self
+            .get_player(to.to_string())
+            .ok_or_else(|| failure::format_err!("Unknown player {}", to))?;
+
+        source.transfer_to(&target, keep_playing).await
+    }
+
     pub fn get_players(&self) -> Vec<(String, Arc<Player>)> {
         let players = self.player.lock().unwrap();
         players
@@ -100,7 +193,7 @@ impl Rustic {
             trace!("Track is not in library, asking provider");
             let provider = self.get_provider_for_url(uri)?;
             let track = match provider {
-                Some(provider) => provider.get().await.resolve_track(uri).await?,
+                Some(provider) => provider.resolve_track(uri).await?,
                 _ => None,
             };
             Ok(track)
@@ -119,7 +212,7 @@ impl Rustic {
             trace!("Album is not in library, asking provider");
             let provider = self.get_provider_for_url(uri)?;
             let album = match provider {
-                Some(provider) => provider.get().await.resolve_album(uri).await?,
+                Some(provider) => provider.resolve_album(uri).await?,
                 _ => None,
             };
             Ok(album)
@@ -136,7 +229,7 @@ impl Rustic {
             trace!("Artist is not in library, asking provider");
             let provider = self.get_provider_for_url(uri)?;
             let artist = match provider {
-                Some(provider) => provider.get().await.resolve_artist(uri).await?,
+                Some(provider) => provider.resolve_artist(uri).await?,
                 _ => None, // TODO: we could fallback to the library here
             };
             Ok(artist)
@@ -158,7 +251,7 @@ impl Rustic {
             trace!("Playlist is not in library, asking provider");
             let provider = self.get_provider_for_url(uri)?;
             let playlist = match provider {
-                Some(provider) => provider.get().await.resolve_playlist(uri).await?,
+                Some(provider) => provider.resolve_playlist(uri).await?,
                 _ => None,
             };
             Ok(playlist)
@@ -168,19 +261,52 @@ impl Rustic {
         }
     }
 
-    fn get_provider_for_url(&self, uri: &str) -> Result<Option<&Provider>, failure::Error> {
+    /// Picks the account the uri belongs to, falling back to another account of the same provider
+    fn get_provider_for_url(&self, uri: &str) -> Result<Option<Provider>, failure::Error> {
         trace!("get_provider for {}", uri);
         let url = Url::parse(uri)?;
-        let provider = self
-            .providers
-            .iter()
-            .find(|provider| provider.uri_scheme == url.scheme());
+        let account = provider::uri_account(uri);
+        let providers = self.providers.read().unwrap();
+        let matching = || {
+            providers
+                .iter()
+                .filter(|provider| provider.uri_scheme == url.scheme())
+        };
+        // Uris of other accounts are only resolved by their own account
+        let provider = matching()
+            .find(|provider| provider.account() == account)
+            .or_else(|| matching().next().filter(|_| account.is_none()))
+            .cloned();
         Ok(provider)
     }
 
+    /// Falls back to another account of the same provider when the default account is gone
+    ///
+    /// Items whose uri belongs to another account are only handled by that account.
+    fn find_provider(&self, id: &ProviderId, uri: &str) -> Option<Provider> {
+        let providers = self.providers.read().unwrap();
+        let fallback = || match provider::uri_account(uri) {
+            Some(_) => None,
+            None => providers.iter().find(|p| p.id.key() == id.key()),
+        };
+        providers
+            .iter()
+            .find(|p| &p.id == id)
+            .or_else(fallback)
+            .cloned()
+    }
+
     pub async fn stream_url(&self, track: &Track) -> Result<String, failure::Error> {
+        if let Some(file) = self.cache.pinned.get(track) {
+            debug!("serving pinned track {} from {}", track.uri, &file);
+            return Ok(file);
+        }
+        if let Some(file) = self.cache.cached_track(track) {
+            debug!("serving cached track {} from {}", track.uri, &file);
+            return Ok(file);
+        }
         let provider = self.get_provider(track)?;
-        let stream_url = provider.get().await.stream_url(track).await?;
+        let stream_url = provider.stream_url(track).await?;
         debug!(
             "getting stream url for track {} => {}",
             track.uri, &stream_url
@@ -189,64 +315,193 @@ impl Rustic {
         Ok(stream_url)
     }
 
+    /// The original cover art of the item, see [Rustic::cover_art]
     pub async fn thumbnail(
         &self,
         provider_item: &ProviderItemType,
     ) -> Result<Option<Thumbnail>, failure::Error> {
+        let cover_art = self.cover_art(provider_item, None).await?;
+
+        Ok(cover_art.map(Thumbnail::from))
+    }
+
+    /// Returns the cover art of the item scaled to fit into the given size
+    ///
+    /// Cover art is loaded through the cache, remote images are only downloaded once.
+    pub async fn cover_art(
+        &self,
+        provider_item: &ProviderItemType,
+        size: Option<u32>,
+    ) -> Result<Option<cache::CoverArt>, failure::Error> {
         let thumbnail = provider_item.thumbnail();
-        let thumbnail = match thumbnail {
-            ThumbnailState::Url(url) => {
-                let thumbnail = Thumbnail::Url(url);
-                let cached_cover = self.cache.fetch_thumbnail(&thumbnail).await?;
-
-                if cached_cover.is_some() {
-                    cached_cover
-                } else {
-                    let cover = self.cache.cache_thumbnail(&thumbnail).await?;
-                    Some(cover)
-                }
+        let key = match thumbnail {
+            ThumbnailState::Url(ref url) => url.clone(),
+            ThumbnailState::Data => provider_item.uri().to_string(),
+            ThumbnailState::None => return Ok(None),
+        };
+        // Local files can only be read by the provider of the item
+        let remote_url = match thumbnail {
+            ThumbnailState::Url(url) if !url.starts_with("file://") => Some(url),
+            _ => None,
+        };
+        if let Some(cover_art) = self.cache.coverart.get(&key, size).await? {
+            if remote_url.is_some() || !self.is_thumbnail_modified(provider_item, &cover_art).await? {
+                return Ok(Some(cover_art));
             }
-            ThumbnailState::Data => {
+            debug!("Cover art of {} changed", provider_item.uri());
+        }
+        match remote_url {
+            Some(url) => self.cache.coverart.download(&key, &url).await?,
+            None => {
                 let provider = self.get_provider_for_item(provider_item)?;
-                provider.get().await.thumbnail(provider_item).await?
+                let thumbnail = provider.thumbnail(provider_item).await?;
+                match thumbnail {
+                    Some(Thumbnail::Data { data, .. }) => {
+                        self.cache.coverart.store(&key, &data).await?
+                    }
+                    Some(Thumbnail::Url(url)) => self.cache.coverart.download(&key, &url).await?,
+                    None => return Ok(None),
+                }
             }
-            _ => None,
-        };
+        }
+
+        self.cache.coverart.get(&key, size).await
+    }
+
+    async fn is_thumbnail_modified(
+        &self,
+        provider_item: &ProviderItemType,
+        cover_art: &cache::CoverArt,
+    ) -> Result<bool, failure::Error> {
+        let provider = self.get_provider_for_item(provider_item)?;
+        let modified = provider.thumbnail_modified(provider_item).await?;
+        // the cache only stores seconds
+        let is_modified = modified
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .zip(cover_art.last_modified.duration_since(UNIX_EPOCH).ok())
+            .map(|(modified, cached)| modified.as_secs() > cached.as_secs())
+            .unwrap_or(false);
 
-        Ok(thumbnail)
+        Ok(is_modified)
     }
 
-    fn get_provider(&self, track: &Track) -> Result<&Provider, failure::Error> {
+    fn get_provider(&self, track: &Track) -> Result<Provider, failure::Error> {
         let provider = self
-            .providers
-            .iter()
-            .find(|p| p.provider_type == track.provider)
+            .find_provider(&track.provider, &track.uri)
             .ok_or_else(|| format_err!("provider for track {:?} not found", track))?;
 
         Ok(provider)
     }
 
-    fn get_provider_for_item(&self, item: &ProviderItemType) -> Result<&Provider, failure::Error> {
-        let provider_type = match item {
-            ProviderItemType::Track(track) => track.provider,
-            ProviderItemType::Artist(artist) => artist.provider,
-            ProviderItemType::Album(album) => album.provider,
-            ProviderItemType::Playlist(playlist) => playlist.provider,
+    fn get_provider_for_item(&self, item: &ProviderItemType) -> Result<Provider, failure::Error> {
+        let provider_id = match item {
+            ProviderItemType::Track(track) => &track.provider,
+            ProviderItemType::Artist(artist) => &artist.provider,
+            ProviderItemType::Album(album) => &album.provider,
+            ProviderItemType::Playlist(playlist) => &playlist.provider,
         };
 
-        self.providers
-            .iter()
-            .find(|p| p.provider_type == provider_type)
+        self.find_provider(provider_id, item.uri())
             .ok_or_else(|| format_err!("provider for item type {:?}", item))
     }
 
+    /// Writes the changed tags through the provider of the item and syncs the result to the library
+    pub async fn update_metadata(
+        &self,
+        uri: InternalUri,
+        update: MetadataUpdate,
+    ) -> Result<(), failure::Error> {
+        let item = match uri {
+            InternalUri::Track(uri) => self
+                .query_track(SingleQuery::uri(uri))
+                .await?
+                .map(ProviderItemType::Track),
+            InternalUri::Album(uri) => {
+                let mut query = SingleQuery::uri(uri);
+                query.join_tracks();
+                self.query_album(query).await?.map(ProviderItemType::Album)
+            }
+            _ => return Err(provider::MetadataError::UnsupportedItem.into()),
+        };
+        let item = item.ok_or_else(|| format_err!("Item not found"))?;
+        let provider = self.get_provider_for_item(&item)?;
+        let mut updated = provider.update_metadata(&item, &update).await?;
+        // Embedded cover art is cached by the uri of the item
+        self.cache.coverart.invalidate(item.uri());
+        for item in updated.iter() {
+            self.cache.coverart.invalidate(item.uri());
+        }
+        // Albums keep their id when renamed, sync them first so their tracks can keep referencing them
+        updated.sort_by_key(|item| !matches!(item, ProviderItemType::Album(_)));
+        let mut album_ids = Vec::new();
+        for item in updated {
+            match item {
+                ProviderItemType::Track(mut track) => {
+                    let query = SingleQuery::uri(track.uri.clone());
+                    if let Some(existing) = self.library.query_track(query)? {
+                        track.id = existing.id;
+                        track.rating = existing.rating;
+                        // Keep the relations as long as the album and artist haven't been renamed
+                        let same_album = track.album.as_ref().map(|album| &album.title)
+                            == existing.album.as_ref().map(|album| &album.title);
+                        if same_album || album_ids.contains(&existing.album_id) {
+                            track.album_id = existing.album_id;
+                        }
+                        if track.artist.as_ref().map(|artist| &artist.name)
+                            == existing.artist.as_ref().map(|artist| &artist.name)
+                        {
+                            track.artist_id = existing.artist_id;
+                        }
+                    }
+                    self.library.sync_track(&mut track)?;
+                }
+                ProviderItemType::Album(mut album) => {
+                    let query = SingleQuery::uri(album.uri.clone());
+                    if let Some(existing) = self.library.query_album(query)? {
+                        album.id = existing.id;
+                        if album.artist.as_ref().map(|artist| &artist.name)
+                            == existing.artist.as_ref().map(|artist| &artist.name)
+                        {
+                            album.artist_id = existing.artist_id;
+                        }
+                    }
+                    self.library.sync_album(&mut album)?;
+                    album_ids.push(album.id);
+                }
+                ProviderItemType::Artist(mut artist) => self.library.sync_artist(&mut artist)?,
+                ProviderItemType::Playlist(mut playlist) => {
+                    self.library.sync_playlist(&mut playlist)?
+                }
+            }
+        }
+        self.library.flush()?;
+
+        Ok(())
+    }
+
+    /// Rates the track in the library and through its provider
+    pub async fn set_rating(&self, uri: String, rating: Rating) -> Result<(), failure::Error> {
+        let track = self
+            .query_track(SingleQuery::uri(uri))
+            .await?
+            .ok_or_else(|| format_err!("Track not found"))?;
+        let provider = self.get_provider(&track)?;
+        provider.set_rating(&track, rating).await?;
+        if track.id.is_some() {
+            self.library.set_rating(&track.uri, rating)?;
+            self.library.flush()?;
+        }
+
+        Ok(())
+    }
+
     pub async fn resolve_share_url(
         &self,
         url: String,
     ) -> Result<Option<InternalUri>, failure::Error> {
         trace!("resolving share url {}", url);
         let url = Url::parse(&url)?;
-        for provider in self.providers.iter() {
+        for provider in self.providers() {
             let url = url.clone();
             let provider = provider.get().await;
             let uri = provider.resolve_share_url(url).await?;
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::library::{Artist, Identifiable, MetaValue};
-use crate::provider::{ProviderType, ThumbnailState};
+use crate::provider::{ProviderId, ThumbnailState};
 use crate::Track;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -13,7 +13,7 @@ pub struct Album {
     pub artist_id: Option<usize>,
     pub artist: Option<Artist>,
     pub tracks: Vec<Track>,
-    pub provider: ProviderType,
+    pub provider: ProviderId,
     pub thumbnail: ThumbnailState,
     pub uri: String,
     pub meta: HashMap<String, MetaValue>,
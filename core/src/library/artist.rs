@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::library::{Identifiable, MetaValue};
-use crate::{Album, Playlist, ProviderType};
+use crate::{Album, Playlist, ProviderId};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Artist {
@@ -12,7 +12,7 @@ pub struct Artist {
     pub uri: String,
     pub image_url: Option<String>,
     pub meta: HashMap<String, MetaValue>,
-    pub provider: ProviderType,
+    pub provider: ProviderId,
     pub albums: Vec<Album>,
     pub playlists: Vec<Playlist>,
     pub description: Option<String>,
@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+
+use crate::library::{Album, Artist, LibraryEvent, Playlist, Track};
+
+/// Field of a library item which was changed by a sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChangedField {
+    Title,
+    Name,
+    Artist,
+    Album,
+    Thumbnail,
+    ImageUrl,
+    Duration,
+    Meta,
+    Explicit,
+    Rating,
+    Position,
+    ShareUrl,
+    Lyrics,
+    Comments,
+    Chapters,
+    Tracks,
+    Description,
+}
+
+impl ChangedField {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangedField::Title => "title",
+            ChangedField::Name => "name",
+            ChangedField::Artist => "artist",
+            ChangedField::Album => "album",
+            ChangedField::Thumbnail => "thumbnail",
+            ChangedField::ImageUrl => "imageUrl",
+            ChangedField::Duration => "duration",
+            ChangedField::Meta => "meta",
+            ChangedField::Explicit => "explicit",
+            ChangedField::Rating => "rating",
+            ChangedField::Position => "position",
+            ChangedField::ShareUrl => "shareUrl",
+            ChangedField::Lyrics => "lyrics",
+            ChangedField::Comments => "comments",
+            ChangedField::Chapters => "chapters",
+            ChangedField::Tracks => "tracks",
+            ChangedField::Description => "description",
+        }
+    }
+}
+
+/// Compares a library item with the previously stored version
+pub trait Changes {
+    fn changes(&self, previous: &Self) -> Vec<ChangedField>;
+}
+
+/// Collects the fields which differ between both items
+struct ChangeSet(Vec<ChangedField>);
+
+impl ChangeSet {
+    fn new() -> Self {
+        ChangeSet(Vec::new())
+    }
+
+    fn compare<T: PartialEq>(mut self, field: ChangedField, current: T, previous: T) -> Self {
+        if current != previous {
+            self.0.push(field);
+        }
+        self
+    }
+}
+
+fn artist_name(artist: &Option<Artist>) -> Option<&String> {
+    artist.as_ref().map(|artist| &artist.name)
+}
+
+fn album_title(album: &Option<Album>) -> Option<&String> {
+    album.as_ref().map(|album| &album.title)
+}
+
+impl Changes for Track {
+    fn changes(&self, previous: &Track) -> Vec<ChangedField> {
+        ChangeSet::new()
+            .compare(ChangedField::Title, &self.title, &previous.title)
+            .compare(
+                ChangedField::Artist,
+                (self.artist_id, artist_name(&self.artist)),
+                (previous.artist_id, artist_name(&previous.artist)),
+            )
+            .compare(
+                ChangedField::Album,
+                (self.album_id, album_title(&self.album)),
+                (previous.album_id, album_title(&previous.album)),
+            )
+            .compare(
+                ChangedField::Thumbnail,
+                &self.thumbnail,
+                &previous.thumbnail,
+            )
+            .compare(ChangedField::Duration, self.duration, previous.duration)
+            .compare(ChangedField::Meta, &self.meta, &previous.meta)
+            .compare(ChangedField::Explicit, self.explicit, previous.explicit)
+            .compare(ChangedField::Rating, self.rating, previous.rating)
+            .compare(ChangedField::Position, &self.position, &previous.position)
+            .compare(ChangedField::ShareUrl, &self.share_url, &previous.share_url)
+            .compare(ChangedField::Lyrics, &self.lyrics, &previous.lyrics)
+            .compare(ChangedField::Comments, &self.comments, &previous.comments)
+            .compare(ChangedField::Chapters, &self.chapters, &previous.chapters)
+            .0
+    }
+}
+
+impl Changes for Album {
+    fn changes(&self, previous: &Album) -> Vec<ChangedField> {
+        ChangeSet::new()
+            .compare(ChangedField::Title, &self.title, &previous.title)
+            .compare(
+                ChangedField::Artist,
+                (self.artist_id, artist_name(&self.artist)),
+                (previous.artist_id, artist_name(&previous.artist)),
+            )
+            .compare(
+                ChangedField::Thumbnail,
+                &self.thumbnail,
+                &previous.thumbnail,
+            )
+            .compare(ChangedField::Meta, &self.meta, &previous.meta)
+            .compare(ChangedField::Explicit, self.explicit, previous.explicit)
+            .compare(
+                ChangedField::Description,
+                &self.description,
+                &previous.description,
+            )
+            .compare(ChangedField::Tracks, &self.tracks, &previous.tracks)
+            .0
+    }
+}
+
+impl Changes for Artist {
+    fn changes(&self, previous: &Artist) -> Vec<ChangedField> {
+        ChangeSet::new()
+            .compare(ChangedField::Name, &self.name, &previous.name)
+            .compare(ChangedField::ImageUrl, &self.image_url, &previous.image_url)
+            .compare(ChangedField::Meta, &self.meta, &previous.meta)
+            .compare(
+                ChangedField::Description,
+                &self.description,
+                &previous.description,
+            )
+            .0
+    }
+}
+
+impl Changes for Playlist {
+    fn changes(&self, previous: &Playlist) -> Vec<ChangedField> {
+        ChangeSet::new()
+            .compare(ChangedField::Title, &self.title, &previous.title)
+            .compare(ChangedField::Tracks, &self.tracks, &previous.tracks)
+            .0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LibraryItemKind {
+    Track,
+    Album,
+    Artist,
+    Playlist,
+}
+
+/// Change tracking of a single library item
+///
+/// Every change increments the revision of the library, the item remembers
+/// in which revision it was added, removed and each of its fields was changed last.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemRevision {
+    pub kind: LibraryItemKind,
+    pub uri: String,
+    /// Revision in which the item was added
+    pub created: u64,
+    /// Revision of the latest change
+    pub revision: u64,
+    /// Latest revision of every changed field
+    pub fields: Vec<(ChangedField, u64)>,
+    pub removed: bool,
+}
+
+impl ItemRevision {
+    pub fn added(kind: LibraryItemKind, uri: String, revision: u64) -> Self {
+        ItemRevision {
+            kind,
+            uri,
+            created: revision,
+            revision,
+            fields: Vec::new(),
+            removed: false,
+        }
+    }
+
+    pub fn updated(mut self, revision: u64, changes: &[ChangedField]) -> Self {
+        if self.removed {
+            return ItemRevision::added(self.kind, self.uri, revision);
+        }
+        self.revision = revision;
+        self.fields.retain(|(field, _)| !changes.contains(field));
+        self.fields
+            .extend(changes.iter().map(|field| (*field, revision)));
+        self
+    }
+
+    pub fn removed(mut self, revision: u64) -> Self {
+        self.revision = revision;
+        self.fields.clear();
+        self.removed = true;
+        self
+    }
+
+    /// Applies the event to the previous revision of the item
+    ///
+    /// Items without a previous revision existed before changes were tracked.
+    pub fn record(previous: Option<ItemRevision>, revision: u64, event: &LibraryEvent) -> Self {
+        use LibraryEvent::*;
+
+        let previous = previous
+            .unwrap_or_else(|| ItemRevision::added(event.item_kind(), event.uri().into(), 0));
+        match event {
+            TrackAdded(_) | AlbumAdded(_) | ArtistAdded(_) | PlaylistAdded(_) => {
+                ItemRevision::added(previous.kind, previous.uri, revision)
+            }
+            TrackUpdated(_, changes)
+            | AlbumUpdated(_, changes)
+            | ArtistUpdated(_, changes)
+            | PlaylistUpdated(_, changes) => previous.updated(revision, changes),
+            TrackRemoved(_) | AlbumRemoved(_) | ArtistRemoved(_) | PlaylistRemoved(_) => {
+                previous.removed(revision)
+            }
+        }
+    }
+
+    /// Fields which were changed after the given revision
+    pub fn changed_since(&self, revision: u64) -> Vec<ChangedField> {
+        self.fields
+            .iter()
+            .filter(|(_, changed)| *changed > revision)
+            .map(|(field, _)| *field)
+            .collect()
+    }
+}
+
+/// A library event together with the revision it created
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryChange {
+    pub revision: u64,
+    pub event: LibraryEvent,
+}
+
+impl LibraryChange {
+    pub fn new(revision: u64, event: LibraryEvent) -> Self {
+        LibraryChange { revision, event }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChangedField, ItemRevision, LibraryItemKind};
+
+    #[test]
+    fn changed_since_should_only_contain_newer_fields() {
+        let revision = ItemRevision::added(LibraryItemKind::Track, "test:track".into(), 1)
+            .updated(2, &[ChangedField::Title])
+            .updated(3, &[ChangedField::Rating]);
+
+        assert_eq!(revision.changed_since(2), vec![ChangedField::Rating]);
+        assert_eq!(
+            revision.changed_since(1),
+            vec![ChangedField::Title, ChangedField::Rating]
+        );
+    }
+
+    #[test]
+    fn updating_a_removed_item_should_add_it_again() {
+        let revision = ItemRevision::added(LibraryItemKind::Track, "test:track".into(), 1)
+            .removed(2)
+            .updated(3, &[ChangedField::Title]);
+
+        assert_eq!(revision.created, 3);
+        assert!(!revision.removed);
+    }
+}
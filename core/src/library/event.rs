@@ -1,21 +1,61 @@
-use crate::library::{Album, Artist, Playlist, Track};
+use serde::Serialize;
 
-#[derive(Debug, Clone)]
+use crate::library::{Album, Artist, ChangedField, LibraryItemKind, Playlist, Track};
+
+#[derive(Debug, Clone, Serialize)]
 pub enum LibraryEvent {
     /// Emitted when a new track was added
     TrackAdded(Track),
     /// Emitted when the track with the given uri was removed
     TrackRemoved(String),
+    /// Emitted when an existing track was synced with new metadata
+    TrackUpdated(Track, Vec<ChangedField>),
     /// Emitted when a new album was added
     AlbumAdded(Album),
     /// Emitted when the album with the given uri was removed
     AlbumRemoved(String),
+    /// Emitted when an existing album was synced with new metadata
+    AlbumUpdated(Album, Vec<ChangedField>),
     /// Emitted when a new artist was added
     ArtistAdded(Artist),
     /// Emitted when the artist with the given uri was removed
     ArtistRemoved(String),
+    /// Emitted when an existing artist was synced with new metadata
+    ArtistUpdated(Artist, Vec<ChangedField>),
     /// Emitted when a new playlist was added
     PlaylistAdded(Playlist),
     /// Emitted when the playlist with the given uri was removed
     PlaylistRemoved(String),
+    /// Emitted when an existing playlist was synced with new tracks or a new title
+    PlaylistUpdated(Playlist, Vec<ChangedField>),
+}
+
+impl LibraryEvent {
+    pub fn item_kind(&self) -> LibraryItemKind {
+        use LibraryEvent::*;
+
+        match self {
+            TrackAdded(_) | TrackRemoved(_) | TrackUpdated(_, _) => LibraryItemKind::Track,
+            AlbumAdded(_) | AlbumRemoved(_) | AlbumUpdated(_, _) => LibraryItemKind::Album,
+            ArtistAdded(_) | ArtistRemoved(_) | ArtistUpdated(_, _) => LibraryItemKind::Artist,
+            PlaylistAdded(_) | PlaylistRemoved(_) | PlaylistUpdated(_, _) => {
+                LibraryItemKind::Playlist
+            }
+        }
+    }
+
+    /// Uri of the affected item
+    pub fn uri(&self) -> &str {
+        use LibraryEvent::*;
+
+        match self {
+            TrackAdded(track) | TrackUpdated(track, _) => &track.uri,
+            AlbumAdded(album) | AlbumUpdated(album, _) => &album.uri,
+            ArtistAdded(artist) | ArtistUpdated(artist, _) => &artist.uri,
+            PlaylistAdded(playlist) | PlaylistUpdated(playlist, _) => &playlist.uri,
+            TrackRemoved(uri) | AlbumRemoved(uri) | ArtistRemoved(uri) | PlaylistRemoved(uri) => {
+                uri
+            }
+        }
+    }
 }
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::library::{Album, Artist, Track};
+
+/// A single play of a track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayRecord {
+    pub track: Track,
+    /// Id of the player the track was played on
+    pub player: String,
+    /// Unix timestamp in seconds
+    pub started_at: u64,
+    /// Seconds the track was actually playing, pauses are not counted
+    pub listened: u64,
+    /// Whether the track played until the end or was skipped
+    pub completed: bool,
+}
+
+/// Filters the listening history, results are ordered newest first
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    /// Unix timestamp in seconds, inclusive
+    pub since: Option<u64>,
+    /// Unix timestamp in seconds, exclusive
+    pub until: Option<u64>,
+    pub track_uri: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    pub fn new() -> Self {
+        HistoryQuery::default()
+    }
+
+    pub fn since(mut self, since: Option<u64>) -> Self {
+        self.since = since;
+        self
+    }
+
+    pub fn until(mut self, until: Option<u64>) -> Self {
+        self.until = until;
+        self
+    }
+
+    pub fn track(mut self, uri: String) -> Self {
+        self.track_uri = Some(uri);
+        self
+    }
+
+    pub fn limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn matches(&self, play: &PlayRecord) -> bool {
+        self.since.map_or(true, |since| play.started_at >= since)
+            && self.until.map_or(true, |until| play.started_at < until)
+            && self
+                .track_uri
+                .as_ref()
+                .map_or(true, |uri| &play.track.uri == uri)
+    }
+
+    /// Filters, sorts and limits the given plays
+    ///
+    /// Used by stores which can't query their plays directly.
+    pub fn apply(&self, plays: impl IntoIterator<Item = PlayRecord>) -> Vec<PlayRecord> {
+        let mut plays: Vec<PlayRecord> = plays
+            .into_iter()
+            .filter(|play| self.matches(play))
+            .collect();
+        plays.sort_by(|lhs, rhs| rhs.started_at.cmp(&lhs.started_at));
+        if let Some(limit) = self.limit {
+            plays.truncate(limit);
+        }
+        plays
+    }
+}
+
+/// Play count and last play of a single track
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrackPlays {
+    /// Number of plays which weren't skipped
+    pub count: u64,
+    /// Unix timestamp in seconds
+    pub last_played: Option<u64>,
+}
+
+impl TrackPlays {
+    /// Play counts by track uri
+    pub fn from_plays(plays: &[PlayRecord]) -> HashMap<String, TrackPlays> {
+        let mut result: HashMap<String, TrackPlays> = HashMap::new();
+        for play in plays {
+            result
+                .entry(play.track.uri.clone())
+                .or_default()
+                .add(play.started_at, play.completed);
+        }
+        result
+    }
+
+    /// Counts a single play which started at the given unix timestamp
+    pub fn add(&mut self, started_at: u64, completed: bool) {
+        if completed {
+            self.count += 1;
+        }
+        self.last_played = self.last_played.max(Some(started_at));
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayCount<T> {
+    pub item: T,
+    pub plays: usize,
+}
+
+/// Most played tracks, artists and albums, skipped plays are not counted
+#[derive(Debug, Clone, Default)]
+pub struct PlayStats {
+    pub tracks: Vec<PlayCount<Track>>,
+    pub artists: Vec<PlayCount<Artist>>,
+    pub albums: Vec<PlayCount<Album>>,
+}
+
+impl PlayStats {
+    /// Expects the plays ordered newest first, ties are ordered by the latest play
+    pub fn from_plays(plays: &[PlayRecord], limit: usize) -> Self {
+        let plays: Vec<&PlayRecord> = plays.iter().filter(|play| play.completed).collect();
+        let tracks = top(plays.iter().map(|play| (&play.track.uri, &play.track)), limit);
+        let artists = top(
+            plays
+                .iter()
+                .filter_map(|play| play.track.artist.as_ref())
+                .map(|artist| (&artist.uri, artist)),
+            limit,
+        );
+        let albums = top(
+            plays
+                .iter()
+                .filter_map(|play| play.track.album.as_ref())
+                .map(|album| (&album.uri, album)),
+            limit,
+        );
+
+        PlayStats {
+            tracks,
+            artists,
+            albums,
+        }
+    }
+}
+
+fn top<'a, T: Clone + 'a>(
+    items: impl Iterator<Item = (&'a String, &'a T)>,
+    limit: usize,
+) -> Vec<PlayCount<T>> {
+    let mut counts: Vec<PlayCount<T>> = Vec::new();
+    let mut indices: HashMap<&String, usize> = HashMap::new();
+    for (uri, item) in items {
+        if let Some(index) = indices.get(uri) {
+            counts[*index].plays += 1;
+        } else {
+            indices.insert(uri, counts.len());
+            counts.push(PlayCount {
+                item: item.clone(),
+                plays: 1,
+            });
+        }
+    }
+    // stable sort keeps the most recently played first
+    counts.sort_by(|lhs, rhs| rhs.plays.cmp(&lhs.plays));
+    counts.truncate(limit);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::library::{Lyrics, Track};
+    use crate::provider::ThumbnailState;
+    use crate::{ProviderId, Rating};
+
+    use super::{PlayRecord, PlayStats, TrackPlays};
+
+    fn play(uri: &str, started_at: u64, completed: bool) -> PlayRecord {
+        PlayRecord {
+            track: Track {
+                id: None,
+                title: uri.into(),
+                artist_id: None,
+                artist: None,
+                album_id: None,
+                album: None,
+                provider: ProviderId::INTERNAL,
+                uri: uri.into(),
+                thumbnail: ThumbnailState::None,
+                duration: None,
+                meta: HashMap::new(),
+                explicit: None,
+                rating: Rating::None,
+                position: None,
+                share_url: None,
+                lyrics: Lyrics::None,
+                comments: None,
+                chapters: Vec::new(),
+            },
+            player: "default".into(),
+            started_at,
+            listened: 0,
+            completed,
+        }
+    }
+
+    #[test]
+    fn stats_should_order_tracks_by_completed_plays() {
+        let plays = vec![
+            play("test:a", 4, false),
+            play("test:b", 3, true),
+            play("test:a", 2, true),
+            play("test:b", 1, true),
+        ];
+
+        let stats = PlayStats::from_plays(&plays, 10);
+
+        let tracks: Vec<_> = stats
+            .tracks
+            .iter()
+            .map(|count| (count.item.uri.as_str(), count.plays))
+            .collect();
+        assert_eq!(tracks, vec![("test:b", 2), ("test:a", 1)]);
+    }
+
+    #[test]
+    fn track_plays_should_not_count_skipped_plays() {
+        let plays = vec![play("test:a", 2, false), play("test:a", 1, true)];
+
+        let plays = TrackPlays::from_plays(&plays);
+
+        assert_eq!(
+            plays["test:a"],
+            TrackPlays {
+                count: 1,
+                last_played: Some(2)
+            }
+        );
+    }
+}
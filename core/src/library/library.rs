@@ -1,9 +1,12 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use failure::Error;
 use futures::stream::BoxStream;
 
-use crate::library::{Album, Artist, LibraryEvent, Playlist, Track};
+use crate::library::{
+    Album, Artist, HistoryQuery, LibraryChange, PlayRecord, Playlist, Rating, Track, TrackPlays,
+};
 use crate::{MultiQuery, SingleQuery};
 
 pub type SharedLibrary = Arc<Box<dyn Library>>;
@@ -90,6 +93,7 @@ pub trait Library: Sync + Send + std::fmt::Debug {
     /**
      * Sync the given track by its uri
      * Will set the id when not persisted yet
+     * Keeps the stored rating when the given track has none
      */
     fn sync_track(&self, track: &mut Track) -> Result<(), Error>;
     /**
@@ -108,6 +112,12 @@ pub trait Library: Sync + Send + std::fmt::Debug {
      */
     fn sync_playlist(&self, playlist: &mut Playlist) -> Result<(), Error>;
 
+    /**
+     * Store the rating of the track with the given uri
+     * Unlike sync_track this also clears an existing rating
+     */
+    fn set_rating(&self, uri: &str, rating: Rating) -> Result<(), Error>;
+
     fn sync_tracks(&self, tracks: &mut Vec<Track>) -> Result<(), Error> {
         tracks.iter_mut().try_for_each(|t| self.sync_track(t))
     }
@@ -134,5 +144,39 @@ pub trait Library: Sync + Send + std::fmt::Debug {
     // TODO: this should happen on an interval on a background thread
     fn flush(&self) -> Result<(), Error>;
 
-    fn observe(&self) -> BoxStream<'static, LibraryEvent>;
+    /**
+     * The revision of the latest change, increases with every added, updated or removed item
+     */
+    fn revision(&self) -> Result<u64, Error>;
+
+    /**
+     * All changes after the given revision, only the latest change of every item is returned
+     */
+    fn changes_since(&self, revision: u64) -> Result<Vec<LibraryChange>, Error>;
+
+    fn observe(&self) -> BoxStream<'static, LibraryChange>;
+
+    /**
+     * Append a play to the listening history
+     */
+    fn add_play(&self, play: PlayRecord) -> Result<(), Error>;
+
+    /**
+     * Plays matching the given query, newest first
+     */
+    fn query_plays(&self, query: HistoryQuery) -> Result<Vec<PlayRecord>, Error>;
+
+    /**
+     * Play count and last play of the tracks with the given uris, unplayed tracks are missing
+     */
+    fn query_track_plays(&self, uris: &[String]) -> Result<HashMap<String, TrackPlays>, Error> {
+        let uris: HashSet<&String> = uris.iter().collect();
+        let plays: Vec<PlayRecord> = self
+            .query_plays(HistoryQuery::new())?
+            .into_iter()
+            .filter(|play| uris.contains(&play.track.uri))
+            .collect();
+
+        Ok(TrackPlays::from_plays(&plays))
+    }
 }
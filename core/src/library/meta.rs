@@ -1,5 +1,5 @@
 use serde_derive::{Deserialize, Serialize};
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum MetaValue {
     Bool(bool),
     String(String),
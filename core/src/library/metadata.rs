@@ -0,0 +1,29 @@
+use crate::provider::Thumbnail;
+
+/// Changes to the tags of a track or album
+///
+/// Fields which are `None` keep their current value.
+#[derive(Debug, Clone, Default)]
+pub struct MetadataUpdate {
+    pub title: Option<String>,
+    pub artists: Option<Vec<String>>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub cover_art: Option<Thumbnail>,
+}
+
+impl MetadataUpdate {
+    /// Only the fields which are shared by all tracks of an album
+    pub fn for_album_tracks(&self) -> MetadataUpdate {
+        MetadataUpdate {
+            title: None,
+            track_number: None,
+            disc_number: None,
+            ..self.clone()
+        }
+    }
+}
@@ -1,8 +1,11 @@
 pub use self::album::Album;
 pub use self::artist::Artist;
+pub use self::changes::*;
 pub use self::event::*;
+pub use self::history::*;
 pub use self::library::{Library, SearchResults, SharedLibrary};
 pub use self::meta::MetaValue;
+pub use self::metadata::MetadataUpdate;
 pub use self::playlist::Playlist;
 pub use self::queries::*;
 pub use self::rating::Rating;
@@ -12,9 +15,12 @@ use serde::{Deserialize, Serialize};
 
 mod album;
 mod artist;
+mod changes;
 mod event;
+mod history;
 mod library;
 mod meta;
+mod metadata;
 mod playlist;
 pub mod queries;
 mod rating;
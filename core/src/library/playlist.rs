@@ -3,14 +3,14 @@ use std::cmp::Ordering;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::library::{Identifiable, Track};
-use crate::provider::ProviderType;
+use crate::provider::ProviderId;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Playlist {
     pub id: Option<usize>,
     pub title: String,
     pub tracks: Vec<Track>,
-    pub provider: ProviderType,
+    pub provider: ProviderId,
     pub uri: String,
 }
 
@@ -1,4 +1,4 @@
-use crate::ProviderType;
+use crate::ProviderId;
 
 use super::{LibraryQueryJoins, QueryJoins};
 
@@ -6,7 +6,7 @@ use super::{LibraryQueryJoins, QueryJoins};
 pub struct MultiQuery {
     pub joins: LibraryQueryJoins,
     pub limit: Option<usize>,
-    pub providers: Vec<ProviderType>,
+    pub providers: Vec<ProviderId>,
 }
 
 impl MultiQuery {
@@ -19,10 +19,15 @@ impl MultiQuery {
         self
     }
 
-    pub fn with_providers(&mut self, providers: Vec<ProviderType>) -> &mut MultiQuery {
+    pub fn with_providers(&mut self, providers: Vec<ProviderId>) -> &mut MultiQuery {
         self.providers = providers;
         self
     }
+
+    /// Whether items of the given provider account pass the provider filter
+    pub fn matches_provider(&self, provider: &ProviderId) -> bool {
+        self.providers.is_empty() || self.providers.iter().any(|p| p.includes(provider))
+    }
 }
 
 impl QueryJoins for MultiQuery {
@@ -49,7 +54,7 @@ impl QueryJoins for MultiQuery {
 
 #[cfg(test)]
 mod tests {
-    use crate::MultiQuery;
+    use crate::{MultiQuery, ProviderId};
 
     use super::{LibraryQueryJoins, QueryJoins};
 
@@ -97,6 +102,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn matches_provider_should_include_accounts_of_filtered_provider() {
+        let mut query = MultiQuery::new();
+        query.with_providers(vec![ProviderId::new("spotify")]);
+
+        assert!(query.matches_provider(&ProviderId::new("spotify").with_instance("work")));
+        assert!(!query.matches_provider(&ProviderId::new("soundcloud")));
+    }
+
     #[test]
     fn joining_all_fields_should_equal_all() {
         let mut query = MultiQuery::new();
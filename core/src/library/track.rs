@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::library::{Album, Artist, Identifiable, MetaValue, Rating};
-use crate::provider::{self, ProviderType};
+use crate::provider::{self, ProviderId};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Track {
@@ -14,7 +14,7 @@ pub struct Track {
     pub artist: Option<Artist>,
     pub album_id: Option<usize>,
     pub album: Option<Album>,
-    pub provider: ProviderType,
+    pub provider: ProviderId,
     pub uri: String,
     pub thumbnail: provider::ThumbnailState,
     pub duration: Option<u64>,
@@ -28,7 +28,7 @@ pub struct Track {
     pub chapters: Vec<Chapter>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum Lyrics {
     None,
     Plain(String),
@@ -62,7 +62,7 @@ impl PartialOrd for Chapter {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TrackPosition {
     pub track: Option<u64>,
     pub disc: Option<u64>,
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use failure::{bail, format_err, Error};
+use pinboard::NonEmptyPinboard;
+use rustic_queue::{multicast, MulticastReceiver, MulticastSender};
+
+use crate::provider::InternalUri;
+use crate::{Album, Playlist, QueryJoins, Rustic, SingleQuery, Track};
+
+#[derive(Debug, Clone)]
+pub enum OfflineEvent {
+    /// Some tracks of the pinned item have been downloaded
+    Progress {
+        uri: String,
+        downloaded: usize,
+        total: usize,
+    },
+    /// All tracks of the pinned item are available offline
+    Completed(String),
+    /// Downloading failed, tracks which are already downloaded are kept
+    Failed(String),
+    Removed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct OfflineState {
+    /// Every subscriber receives every event
+    pub events: MulticastReceiver<OfflineEvent>,
+    tx: MulticastSender<OfflineEvent>,
+    enabled: Arc<NonEmptyPinboard<bool>>,
+}
+
+impl OfflineState {
+    pub(crate) fn new() -> OfflineState {
+        let (tx, rx) = multicast();
+
+        OfflineState {
+            events: rx,
+            tx,
+            enabled: Arc::new(NonEmptyPinboard::new(false)),
+        }
+    }
+
+    /// In offline mode only items which can be played without network access are listed
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.read()
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        log::info!("Offline mode {}", if enabled { "enabled" } else { "disabled" });
+        self.enabled.set(enabled);
+    }
+
+    fn next(&self, event: OfflineEvent) {
+        log::trace!("{:?}", event);
+        self.tx.send(event);
+    }
+}
+
+/// Makes the track, album or playlist available offline
+///
+/// Returns once the tracks of the item are known, the download continues in the background.
+pub async fn pin(app: &Arc<Rustic>, uri: InternalUri) -> Result<(), Error> {
+    let (uri, tracks) = match uri {
+        InternalUri::Track(uri) => {
+            let track = app
+                .query_track(SingleQuery::uri(uri.clone()))
+                .await?
+                .ok_or_else(|| format_err!("Unknown track {}", &uri))?;
+            (uri, vec![track])
+        }
+        InternalUri::Album(uri) => {
+            let mut query = SingleQuery::uri(uri.clone());
+            query.join_tracks();
+            let album = app
+                .query_album(query)
+                .await?
+                .ok_or_else(|| format_err!("Unknown album {}", &uri))?;
+            (uri, album.tracks)
+        }
+        InternalUri::Playlist(uri) => {
+            let mut query = SingleQuery::uri(uri.clone());
+            query.join_tracks();
+            let playlist = app
+                .query_playlist(query)
+                .await?
+                .ok_or_else(|| format_err!("Unknown playlist {}", &uri))?;
+            (uri, playlist.tracks)
+        }
+        InternalUri::Artist(_) => bail!("Artists can't be made available offline"),
+    };
+    app.cache.pinned.pin(&uri, &tracks);
+    let app = Arc::clone(app);
+    tokio::spawn(async move {
+        if let Err(e) = download(&app, &uri, &tracks).await {
+            log::error!("Making {} available offline failed: {:?}", &uri, e);
+            app.offline.next(OfflineEvent::Failed(uri));
+        }
+    });
+
+    Ok(())
+}
+
+pub fn unpin(app: &Arc<Rustic>, uri: InternalUri) -> Result<(), Error> {
+    let uri = match uri {
+        InternalUri::Track(uri) | InternalUri::Album(uri) | InternalUri::Playlist(uri) => uri,
+        InternalUri::Artist(_) => bail!("Artists can't be made available offline"),
+    };
+    app.cache.pinned.unpin(&uri)?;
+    app.offline.next(OfflineEvent::Removed(uri));
+
+    Ok(())
+}
+
+async fn download(app: &Arc<Rustic>, uri: &str, tracks: &[Track]) -> Result<(), Error> {
+    let total = tracks.len();
+    for (downloaded, track) in tracks.iter().enumerate() {
+        if !app.cache.pinned.is_pinned(uri) {
+            log::debug!("{} was unpinned, stopping download", uri);
+            return Ok(());
+        }
+        app.offline.next(OfflineEvent::Progress {
+            uri: uri.to_string(),
+            downloaded,
+            total,
+        });
+        if is_local(track) {
+            continue;
+        }
+        let stream_url = app.stream_url(track).await?;
+        app.cache.pin_track(track, &stream_url).await?;
+    }
+    app.offline.next(OfflineEvent::Completed(uri.to_string()));
+
+    Ok(())
+}
+
+/// Key of the local media provider, all of its instances are available offline
+const LOCAL_MEDIA_KEY: &str = "local";
+
+/// Whether the track can be played without network access
+///
+/// [Rustic::stream_url] serves pinned and cached tracks from their files.
+pub fn is_track_available(app: &Rustic, track: &Track) -> bool {
+    is_local(track)
+        || app.cache.pinned.contains(track)
+        || app.cache.tracks.file_path(track).is_some()
+}
+
+/// Albums in list queries come without tracks, those are available when the album itself is pinned
+pub fn is_album_available(app: &Rustic, album: &Album) -> bool {
+    album.provider.key() == LOCAL_MEDIA_KEY
+        || app.cache.pinned.is_pinned(&album.uri)
+        || album
+            .tracks
+            .iter()
+            .any(|track| is_track_available(app, track))
+}
+
+pub fn is_playlist_available(app: &Rustic, playlist: &Playlist) -> bool {
+    playlist.provider.key() == LOCAL_MEDIA_KEY
+        || app.cache.pinned.is_pinned(&playlist.uri)
+        || playlist
+            .tracks
+            .iter()
+            .any(|track| is_track_available(app, track))
+}
+
+fn is_local(track: &Track) -> bool {
+    track.provider.key() == LOCAL_MEDIA_KEY
+}
@@ -4,9 +4,15 @@ use std::time::Duration;
 
 use failure::Error;
 
+use crate::player::{
+    OutputDevice, PlayerCapabilities, PlayerCapability, PlayerError, PlayerOutput,
+};
 use crate::{PlayerState, Track};
 
 pub trait PlayerBackend: Send + Sync + Debug {
+    /// Features supported by this backend
+    fn capabilities(&self) -> PlayerCapabilities;
+
     fn set_track(&self, track: &Track, stream_url: String) -> Result<(), Error>;
 
     /// Set the player state
@@ -30,6 +36,43 @@ pub trait PlayerBackend: Send + Sync + Debug {
     /// Seek to a point in the current track
     fn seek(&self, duration: Duration) -> Result<(), Error>;
 
+    /// Get the position in the current track if the backend keeps track of it
+    fn position(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Get the outputs this player can play on
+    fn outputs(&self) -> Vec<PlayerOutput> {
+        Vec::new()
+    }
+
+    /// Start or stop playing on the given output
+    fn set_output_enabled(&self, _output_id: &str, _enabled: bool) -> Result<(), Error> {
+        Err(PlayerError::Unsupported(PlayerCapability::OutputDevices).into())
+    }
+
+    /// Set the volume of the given output (from 0 to 1)
+    fn set_output_volume(&self, _output_id: &str, _volume: f32) -> Result<(), Error> {
+        Err(PlayerError::Unsupported(PlayerCapability::OutputDevices).into())
+    }
+
+    /// Mute or unmute the given output
+    fn set_output_muted(&self, _output_id: &str, _muted: bool) -> Result<(), Error> {
+        Err(PlayerError::Unsupported(PlayerCapability::OutputDevices).into())
+    }
+
+    /// Get the audio devices this player can play on
+    fn devices(&self) -> Vec<OutputDevice> {
+        Vec::new()
+    }
+
+    /// Play on the given audio device
+    ///
+    /// `None` selects the default device of the system
+    fn set_device(&self, _device_id: Option<&str>) -> Result<(), Error> {
+        Err(PlayerError::Unsupported(PlayerCapability::OutputDevices).into())
+    }
+
     fn as_any(&self) -> &dyn Any;
 
     fn close(&self) -> Result<(), Error> {
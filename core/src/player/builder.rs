@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use failure::Error;
 
+use crate::player::composite::{CompositeBackend, CompositeOutput};
 use crate::player::{Player, PlayerBackend, PlayerBus, PlayerQueue};
 use crate::Rustic;
 
@@ -9,6 +10,8 @@ pub struct PlayerBuilder {
     core: Arc<Rustic>,
     pub name: Option<String>,
     backend: Option<Box<dyn PlayerBackend>>,
+    outputs: Vec<CompositeOutput>,
+    output_name: Option<String>,
     queue: Option<Box<dyn PlayerQueue>>,
     bus: PlayerBus,
 }
@@ -21,6 +24,8 @@ impl PlayerBuilder {
             core,
             name: None,
             backend: None,
+            outputs: Vec::new(),
+            output_name: None,
             queue: None,
             bus,
         }
@@ -31,12 +36,26 @@ impl PlayerBuilder {
         self
     }
 
+    /// Adds the next player backend as an output with the given name instead of replacing the backend
+    ///
+    /// Players with outputs play on all of them at once.
+    pub fn with_output<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.output_name = Some(name.into());
+        self
+    }
+
     pub fn with_player<P>(&mut self, builder: P) -> Result<&mut Self, Error>
     where
         P: FnOnce(Arc<Rustic>, PlayerBus) -> Result<Box<dyn PlayerBackend>, Error>,
     {
-        let backend = builder(Arc::clone(&self.core), self.bus.clone())?;
-        self.backend = Some(backend);
+        if let Some(name) = self.output_name.take() {
+            let bus = PlayerBus::new();
+            let backend = builder(Arc::clone(&self.core), bus.clone())?;
+            self.outputs.push(CompositeOutput::new(name, backend, bus));
+        } else {
+            let backend = builder(Arc::clone(&self.core), self.bus.clone())?;
+            self.backend = Some(backend);
+        }
 
         Ok(self)
     }
@@ -52,6 +71,13 @@ impl PlayerBuilder {
     }
 
     pub fn build(&mut self) -> Arc<Player> {
+        if !self.outputs.is_empty() {
+            assert!(self.backend.is_none());
+            let outputs = self.outputs.drain(..).collect();
+            let backend = CompositeBackend::new(outputs, self.bus.clone())
+                .expect("outputs are not empty");
+            self.backend = Some(Box::new(backend));
+        }
         assert!(self.backend.is_some());
         assert!(self.queue.is_some());
         assert!(self.name.is_some());
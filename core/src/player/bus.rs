@@ -2,7 +2,10 @@ use crate::player::{PlayerCommand, QueueCommand};
 use crate::PlayerEvent;
 use failure::{format_err, Error};
 use futures::stream::{select, StreamExt};
-use rustic_queue::{broadcast, bus, Receiver, Sender, BusReceiver, BusSender};
+use rustic_queue::{
+    broadcast, bus, multicast, BusReceiver, BusSender, MulticastReceiver, MulticastSender,
+    Receiver, Sender,
+};
 use std::fmt;
 use std::fmt::Debug;
 
@@ -10,6 +13,8 @@ use std::fmt::Debug;
 pub struct PlayerBus {
     event_tx: BusSender<PlayerEvent>,
     event_rx: BusReceiver<PlayerEvent>,
+    events_tx: MulticastSender<PlayerEvent>,
+    events_rx: MulticastReceiver<PlayerEvent>,
     player_tx: Sender<PlayerCommand>,
     player_rx: Receiver<PlayerCommand>,
     queue_tx: Sender<QueueCommand>,
@@ -25,12 +30,15 @@ pub enum PlayerBusCommand {
 impl PlayerBus {
     pub fn new() -> Self {
         let (event_tx, event_rx) = bus();
+        let (events_tx, events_rx) = multicast();
         let (player_tx, player_rx) = broadcast();
         let (queue_tx, queue_rx) = broadcast();
 
         PlayerBus {
             event_rx,
             event_tx,
+            events_tx,
+            events_rx,
             player_tx,
             player_rx,
             queue_tx,
@@ -56,6 +64,7 @@ impl PlayerBus {
 
     pub fn emit_event(&self, event: PlayerEvent) -> Result<(), Error> {
         log::debug!("emit_event {:?}", event);
+        self.events_tx.send(event.clone());
         self.event_tx.send(event)?;
 
         Ok(())
@@ -68,9 +77,15 @@ impl PlayerBus {
         select(player_rx, queue_rx)
     }
 
+    /// The latest event, intermediate events are skipped when the observer falls behind
     pub fn observe(&self) -> BusReceiver<PlayerEvent> {
         self.event_rx.clone()
     }
+
+    /// Every event emitted after subscribing
+    pub fn events(&self) -> MulticastReceiver<PlayerEvent> {
+        self.events_rx.clone()
+    }
 }
 
 impl Debug for PlayerBus {
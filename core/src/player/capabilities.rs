@@ -0,0 +1,59 @@
+use std::fmt;
+
+use failure::Fail;
+
+/// Features a player backend supports
+///
+/// Calls to unsupported features fail with [PlayerError::Unsupported]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlayerCapabilities {
+    pub seek: bool,
+    pub volume: bool,
+    pub crossfade: bool,
+    pub gapless: bool,
+    pub playback_rate: bool,
+    pub output_devices: bool,
+}
+
+impl PlayerCapabilities {
+    /// Capabilities supported by both
+    pub fn intersect(self, other: PlayerCapabilities) -> PlayerCapabilities {
+        PlayerCapabilities {
+            seek: self.seek && other.seek,
+            volume: self.volume && other.volume,
+            crossfade: self.crossfade && other.crossfade,
+            gapless: self.gapless && other.gapless,
+            playback_rate: self.playback_rate && other.playback_rate,
+            output_devices: self.output_devices && other.output_devices,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerCapability {
+    Seek,
+    Volume,
+    Crossfade,
+    Gapless,
+    PlaybackRate,
+    OutputDevices,
+}
+
+impl fmt::Display for PlayerCapability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PlayerCapability::Seek => write!(f, "seeking"),
+            PlayerCapability::Volume => write!(f, "volume control"),
+            PlayerCapability::Crossfade => write!(f, "crossfading"),
+            PlayerCapability::Gapless => write!(f, "gapless playback"),
+            PlayerCapability::PlaybackRate => write!(f, "playback rate control"),
+            PlayerCapability::OutputDevices => write!(f, "output devices"),
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum PlayerError {
+    #[fail(display = "Player backend doesn't support {}", _0)]
+    Unsupported(PlayerCapability),
+}
@@ -0,0 +1,412 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use failure::{format_err, Error};
+use futures::StreamExt;
+use log::error;
+use pinboard::NonEmptyPinboard;
+
+use crate::player::bus::PlayerBusCommand;
+use crate::player::{
+    OutputDevice, PlayerBackend, PlayerBus, PlayerCapabilities, PlayerEvent, PlayerOutput,
+};
+use crate::{PlayerState, Track};
+
+/// Plays one playback session on multiple backends at once
+///
+/// Every backend is exposed as an output which can be enabled and have its own volume.
+/// Disabled outputs are stopped and don't decode anything, once enabled again they load the
+/// current track and catch up with the state and position of the other outputs.
+/// The first enabled output drives the queue and reports the playback state to the player,
+/// disabling the last enabled output is rejected so there always is one.
+pub struct CompositeBackend {
+    state: Arc<CompositeState>,
+}
+
+struct CompositeState {
+    outputs: Vec<CompositeOutput>,
+    bus: PlayerBus,
+    volume: NonEmptyPinboard<f32>,
+    /// The track and stream url outputs load when they get enabled
+    current: NonEmptyPinboard<Option<(Track, String)>>,
+}
+
+pub struct CompositeOutput {
+    name: String,
+    backend: Box<dyn PlayerBackend>,
+    bus: PlayerBus,
+    enabled: NonEmptyPinboard<bool>,
+    volume: NonEmptyPinboard<f32>,
+    muted: NonEmptyPinboard<bool>,
+}
+
+impl CompositeOutput {
+    /// The backend should be constructed with the given bus, not the bus of the player
+    pub fn new(name: String, backend: Box<dyn PlayerBackend>, bus: PlayerBus) -> Self {
+        CompositeOutput {
+            name,
+            backend,
+            bus,
+            enabled: NonEmptyPinboard::new(true),
+            volume: NonEmptyPinboard::new(1.0),
+            muted: NonEmptyPinboard::new(false),
+        }
+    }
+}
+
+impl CompositeBackend {
+    pub fn new(outputs: Vec<CompositeOutput>, bus: PlayerBus) -> Result<Self, Error> {
+        if outputs.is_empty() {
+            failure::bail!("A composite backend requires at least one output");
+        }
+        let state = Arc::new(CompositeState {
+            outputs,
+            bus,
+            volume: NonEmptyPinboard::new(1.0),
+            current: NonEmptyPinboard::new(None),
+        });
+        for index in 0..state.outputs.len() {
+            CompositeState::forward(Arc::clone(&state), index);
+        }
+
+        Ok(CompositeBackend { state })
+    }
+}
+
+impl CompositeState {
+    /// Forwards commands and events of the output at the given index to the player
+    fn forward(state: Arc<CompositeState>, index: usize) {
+        let output_bus = state.outputs[index].bus.clone();
+        let commands_state = Arc::clone(&state);
+        let mut commands = Box::pin(output_bus.commands());
+        tokio::spawn(async move {
+            let state = commands_state;
+            while let Some(cmd) = commands.next().await {
+                if state.driver() != index {
+                    continue;
+                }
+                let result = match cmd {
+                    PlayerBusCommand::Player(cmd) => state.bus.send_player_msg(cmd),
+                    PlayerBusCommand::Queue(cmd) => state.bus.send_queue_msg(cmd),
+                };
+                if let Err(e) = result {
+                    error!("{:?}", e);
+                }
+            }
+        });
+        let mut events = Box::pin(output_bus.events().stream());
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                let result = match event {
+                    PlayerEvent::OutputsChanged(_) => state.emit_outputs(),
+                    PlayerEvent::DevicesChanged(_) => state.emit_devices(),
+                    PlayerEvent::VolumeChanged(_) => Ok(()),
+                    event if state.driver() == index => state.bus.emit_event(event),
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    error!("{:?}", e);
+                }
+            }
+        });
+    }
+
+    /// Index of the output which drives the queue
+    fn driver(&self) -> usize {
+        self.outputs
+            .iter()
+            .position(|output| output.enabled.read())
+            .unwrap_or(0)
+    }
+
+    fn enabled_outputs(&self) -> usize {
+        self.outputs
+            .iter()
+            .filter(|output| output.enabled.read())
+            .count()
+    }
+
+    fn primary(&self) -> &dyn PlayerBackend {
+        self.outputs[self.driver()].backend.as_ref()
+    }
+
+    fn apply_volume(&self, output: &CompositeOutput) -> Result<(), Error> {
+        let volume = if output.enabled.read() && !output.muted.read() {
+            self.volume.read() * output.volume.read()
+        } else {
+            0f32
+        };
+        output.backend.set_volume(volume)
+    }
+
+    fn outputs(&self) -> Vec<PlayerOutput> {
+        let mut outputs = Vec::new();
+        for (index, output) in self.outputs.iter().enumerate() {
+            outputs.push(PlayerOutput {
+                id: index.to_string(),
+                name: output.name.clone(),
+                enabled: output.enabled.read(),
+                volume: output.volume.read(),
+                muted: output.muted.read(),
+            });
+            outputs.extend(output.backend.outputs().into_iter().map(|nested| PlayerOutput {
+                id: format!("{}/{}", index, nested.id),
+                name: format!("{} - {}", output.name, nested.name),
+                ..nested
+            }));
+        }
+        outputs
+    }
+
+    fn emit_outputs(&self) -> Result<(), Error> {
+        self.bus.emit_event(PlayerEvent::OutputsChanged(self.outputs()))
+    }
+
+    fn devices(&self) -> Vec<OutputDevice> {
+        self.outputs
+            .iter()
+            .enumerate()
+            .flat_map(|(index, output)| {
+                output.backend.devices().into_iter().map(move |device| OutputDevice {
+                    id: format!("{}/{}", index, device.id),
+                    name: format!("{} - {}", output.name, device.name),
+                    ..device
+                })
+            })
+            .collect()
+    }
+
+    fn emit_devices(&self) -> Result<(), Error> {
+        self.bus.emit_event(PlayerEvent::DevicesChanged(self.devices()))
+    }
+
+    /// Returns the output and the id of the nested output if the id refers to one
+    fn find_output<'a>(&self, output_id: &'a str) -> Result<(&CompositeOutput, Option<&'a str>), Error> {
+        let (index, nested) = match output_id.find('/') {
+            Some(separator) => (&output_id[..separator], Some(&output_id[separator + 1..])),
+            None => (output_id, None),
+        };
+        let output = index
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| self.outputs.get(index))
+            .ok_or_else(|| format_err!("Unknown output {}", output_id))?;
+
+        Ok((output, nested))
+    }
+
+    /// Brings an output which got enabled up to date with the other enabled outputs
+    fn attach(&self, index: usize) -> Result<(), Error> {
+        let output = &self.outputs[index];
+        let (track, stream_url) = match self.current.read() {
+            Some(current) => current,
+            None => return Ok(()),
+        };
+        let (state, position) = self
+            .outputs
+            .iter()
+            .enumerate()
+            .find(|(i, output)| *i != index && output.enabled.read())
+            .map(|(_, output)| (output.backend.state(), output.backend.position()))
+            .unwrap_or((PlayerState::Stop, None));
+        output.backend.set_track(&track, stream_url)?;
+        output.backend.set_state(state)?;
+        if let Some(position) = position.filter(|_| output.backend.capabilities().seek) {
+            output.backend.seek(position)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the given function to all enabled outputs
+    fn for_each_enabled<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: Fn(&dyn PlayerBackend) -> Result<(), Error>,
+    {
+        self.for_each_output(f, true)
+    }
+
+    fn for_each<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: Fn(&dyn PlayerBackend) -> Result<(), Error>,
+    {
+        self.for_each_output(f, false)
+    }
+
+    fn for_each_output<F>(&self, f: F, enabled_only: bool) -> Result<(), Error>
+    where
+        F: Fn(&dyn PlayerBackend) -> Result<(), Error>,
+    {
+        let driver = self.driver();
+        for (index, output) in self.outputs.iter().enumerate() {
+            if enabled_only && !output.enabled.read() {
+                continue;
+            }
+            let result = f(output.backend.as_ref());
+            match result {
+                Err(e) if index == driver => return Err(e),
+                Err(e) => error!("Output {} failed: {:?}", &output.name, e),
+                Ok(()) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PlayerBackend for CompositeBackend {
+    fn capabilities(&self) -> PlayerCapabilities {
+        self.state
+            .outputs
+            .iter()
+            .map(|output| output.backend.capabilities())
+            .fold(None, |result: Option<PlayerCapabilities>, capabilities| {
+                Some(match result {
+                    Some(result) => result.intersect(capabilities),
+                    None => capabilities,
+                })
+            })
+            .unwrap_or_default()
+    }
+
+    fn set_track(&self, track: &Track, stream_url: String) -> Result<(), Error> {
+        self.state
+            .current
+            .set(Some((track.clone(), stream_url.clone())));
+        self.state
+            .for_each_enabled(|backend| backend.set_track(track, stream_url.clone()))
+    }
+
+    fn set_state(&self, state: PlayerState) -> Result<(), Error> {
+        self.state
+            .for_each_enabled(|backend| backend.set_state(state))
+    }
+
+    fn state(&self) -> PlayerState {
+        self.state.primary().state()
+    }
+
+    fn set_volume(&self, volume: f32) -> Result<(), Error> {
+        self.state.volume.set(volume);
+        for output in self.state.outputs.iter() {
+            self.state.apply_volume(output)?;
+        }
+        self.state.bus.emit_event(PlayerEvent::VolumeChanged(volume))?;
+        Ok(())
+    }
+
+    fn volume(&self) -> f32 {
+        self.state.volume.read()
+    }
+
+    fn set_blend_time(&self, duration: Duration) -> Result<(), Error> {
+        self.state
+            .for_each(|backend| backend.set_blend_time(duration))
+    }
+
+    fn blend_time(&self) -> Duration {
+        self.state.primary().blend_time()
+    }
+
+    fn seek(&self, duration: Duration) -> Result<(), Error> {
+        self.state
+            .for_each_enabled(|backend| backend.seek(duration))
+    }
+
+    fn position(&self) -> Option<Duration> {
+        self.state.primary().position()
+    }
+
+    fn outputs(&self) -> Vec<PlayerOutput> {
+        self.state.outputs()
+    }
+
+    fn set_output_enabled(&self, output_id: &str, enabled: bool) -> Result<(), Error> {
+        match self.state.find_output(output_id)? {
+            (output, Some(nested)) => output.backend.set_output_enabled(nested, enabled),
+            (output, None) => {
+                if output.enabled.read() == enabled {
+                    return Ok(());
+                }
+                if !enabled && self.state.enabled_outputs() == 1 {
+                    failure::bail!("The last enabled output can't be disabled");
+                }
+                output.enabled.set(enabled);
+                self.state.apply_volume(output)?;
+                if enabled {
+                    let index = output_id.parse::<usize>()?;
+                    self.state.attach(index)?;
+                } else {
+                    output.backend.set_state(PlayerState::Stop)?;
+                }
+                self.state.emit_outputs()
+            }
+        }
+    }
+
+    fn set_output_volume(&self, output_id: &str, volume: f32) -> Result<(), Error> {
+        match self.state.find_output(output_id)? {
+            (output, Some(nested)) => output.backend.set_output_volume(nested, volume),
+            (output, None) => {
+                output.volume.set(volume);
+                self.state.apply_volume(output)?;
+                self.state.emit_outputs()
+            }
+        }
+    }
+
+    fn set_output_muted(&self, output_id: &str, muted: bool) -> Result<(), Error> {
+        match self.state.find_output(output_id)? {
+            (output, Some(nested)) => output.backend.set_output_muted(nested, muted),
+            (output, None) => {
+                output.muted.set(muted);
+                self.state.apply_volume(output)?;
+                self.state.emit_outputs()
+            }
+        }
+    }
+
+    fn devices(&self) -> Vec<OutputDevice> {
+        self.state.devices()
+    }
+
+    /// Devices are selected per output, `None` resets all outputs to their default device
+    fn set_device(&self, device_id: Option<&str>) -> Result<(), Error> {
+        match device_id {
+            Some(device_id) => match self.state.find_output(device_id)? {
+                (output, Some(device_id)) => output.backend.set_device(Some(device_id)),
+                (_, None) => Err(format_err!("Unknown device {}", device_id)),
+            },
+            None => self.state.for_each(|backend| {
+                if backend.devices().is_empty() {
+                    Ok(())
+                } else {
+                    backend.set_device(None)
+                }
+            }),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn close(&self) -> Result<(), Error> {
+        self.state.for_each(|backend| backend.close())
+    }
+}
+
+impl fmt::Debug for CompositeBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let outputs: Vec<_> = self
+            .state
+            .outputs
+            .iter()
+            .map(|output| (&output.name, &output.backend))
+            .collect();
+        f.debug_struct("CompositeBackend")
+            .field("outputs", &outputs)
+            .finish()
+    }
+}
@@ -1,6 +1,6 @@
 use super::state::PlayerState;
 use crate::library::Track;
-use crate::player::{QueuedTrack, RepeatMode};
+use crate::player::{OutputDevice, PlayerOutput, QueuedTrack, RepeatMode};
 use std::time::Duration;
 
 #[derive(Debug, Clone)]
@@ -19,4 +19,8 @@ pub enum PlayerEvent {
     VolumeChanged(f32),
     /// The repeat mode has changed
     RepeatChanged(RepeatMode),
+    /// The outputs or their settings have changed
+    OutputsChanged(Vec<PlayerOutput>),
+    /// Audio devices were added or removed or another device was selected
+    DevicesChanged(Vec<OutputDevice>),
 }
@@ -1,16 +1,20 @@
 use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
 
 use failure::Error;
 use log::error;
-use rustic_queue::{BusReceiver, Receiver};
+use rustic_queue::{BusReceiver, MulticastReceiver, Receiver};
 
 pub use self::bus::PlayerBus;
 use crate::library::Track;
 pub use crate::player::backend::PlayerBackend;
 
 pub use self::builder::PlayerBuilder;
+pub use self::capabilities::{PlayerCapabilities, PlayerCapability, PlayerError};
+pub use self::composite::CompositeBackend;
 pub use self::event::PlayerEvent;
+pub use self::output::{OutputDevice, PlayerOutput};
 pub use self::queue::{PlayerQueue, QueuedTrack, RepeatMode};
 pub use self::state::PlayerState;
 use crate::player::bus::PlayerBusCommand;
@@ -20,7 +24,10 @@ use futures::prelude::*;
 pub mod backend;
 pub mod builder;
 pub mod bus;
+pub mod capabilities;
+pub mod composite;
 pub mod event;
+pub mod output;
 pub mod queue;
 pub mod state;
 
@@ -82,27 +89,117 @@ impl Player {
         match msg {
             PlayerCommand::Play(track) => {
                 let stream_url = self.core.stream_url(&track).await?;
-                self.backend.set_track(&track, stream_url)?
+                self.backend.set_track(&track, stream_url)?;
+                self.prefetch().await?;
             }
             PlayerCommand::Stop => self.backend.set_state(PlayerState::Stop)?,
+            PlayerCommand::SetState(state) => self.backend.set_state(state)?,
+            PlayerCommand::Seek(position) => self.backend.seek(position)?,
         };
         Ok(())
     }
 
+    /// Downloads the upcoming tracks in the background so they start without delay
+    async fn prefetch(&self) -> Result<(), Error> {
+        let count = self.core.cache.tracks.prefetch_count();
+        if count == 0 {
+            return Ok(());
+        }
+        let upcoming: Vec<Track> = self
+            .queue
+            .get_queue()
+            .await?
+            .into_iter()
+            .skip_while(|track| !track.playing)
+            .skip(1)
+            .take(count)
+            .map(|track| track.track)
+            .collect();
+        let core = Arc::clone(&self.core);
+        tokio::spawn(async move {
+            for track in upcoming {
+                let result = async {
+                    let stream_url = core.stream_url(&track).await?;
+                    if stream_url.starts_with("http") {
+                        core.cache.prepare_track(&track, &stream_url).await?;
+                    }
+                    Ok::<_, Error>(())
+                };
+                if let Err(e) = result.await {
+                    error!("Prefetching {} failed: {:?}", &track, e);
+                }
+            }
+        });
+
+        Ok(())
+    }
+
     async fn handle_queue_msg(&self, msg: QueueCommand) -> Result<(), Error> {
         match msg {
-            QueueCommand::Next => {
+            QueueCommand::Next(reason) => {
+                if reason == NextReason::Finished {
+                    if let Some(id) = self.core.get_player_id(self) {
+                        self.core.history.track_ended(&id);
+                    }
+                }
                 if self.queue.next().await?.is_none() {
                     self.bus.send_player_msg(PlayerCommand::Stop)?;
                 }
             }
+            QueueCommand::Prev => {
+                self.queue.prev().await?;
+            }
         };
         Ok(())
     }
 
+    /// Moves the queue, repeat mode and playback position to the given player
+    ///
+    /// The queue of this player is cleared unless keep_playing is set.
+    pub async fn transfer_to(&self, target: &Player, keep_playing: bool) -> Result<(), Error> {
+        let queue = self.queue.get_queue().await?;
+        let current_index = queue.iter().position(|track| track.playing);
+        let repeat = self.queue.repeat().await?;
+        let state = self.backend.state();
+        let position = self.backend.position();
+        let tracks: Vec<Track> = queue.into_iter().map(|track| track.track).collect();
+
+        if !keep_playing {
+            self.backend.set_state(PlayerState::Stop)?;
+            self.queue.clear().await?;
+        }
+
+        // Commands sent by the queue are handled in order by the target player.
+        // The target is stopped first so the current track is loaded without playing it,
+        // the state and position are applied once it was loaded
+        target.bus.send_player_msg(PlayerCommand::Stop)?;
+        target.queue.clear().await?;
+        target.queue.set_repeat(repeat).await?;
+        if tracks.is_empty() {
+            return Ok(());
+        }
+        target.queue.replace(&tracks, current_index).await?;
+        if state == PlayerState::Stop || current_index.is_none() {
+            return Ok(());
+        }
+        target
+            .bus
+            .send_player_msg(PlayerCommand::SetState(state))?;
+        if let Some(position) = position.filter(|_| target.backend.capabilities().seek) {
+            target.bus.send_player_msg(PlayerCommand::Seek(position))?;
+        }
+
+        Ok(())
+    }
+
     pub fn observe(&self) -> BusReceiver<PlayerEvent> {
         self.bus.observe()
     }
+
+    /// Every event of this player, use this when no event may be missed
+    pub fn events(&self) -> MulticastReceiver<PlayerEvent> {
+        self.bus.events()
+    }
 }
 
 impl fmt::Debug for Player {
@@ -119,9 +216,23 @@ impl fmt::Debug for Player {
 pub enum PlayerCommand {
     Play(Track),
     Stop,
+    SetState(PlayerState),
+    Seek(Duration),
 }
 
 #[derive(Debug, Clone)]
 pub enum QueueCommand {
-    Next,
+    Next(NextReason),
+    Prev,
+}
+
+/// Why a backend requests the next track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NextReason {
+    /// The current track played until the end
+    Finished,
+    /// The user skipped the current track
+    Skipped,
+    /// The current track could not be played
+    Error,
 }
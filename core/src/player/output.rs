@@ -0,0 +1,20 @@
+/// A device or room a player is playing on, e.g. a snapcast client
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerOutput {
+    pub id: String,
+    pub name: String,
+    /// Whether this output is playing the stream of the player
+    pub enabled: bool,
+    /// Volume of this output (from 0 to 1)
+    pub volume: f32,
+    pub muted: bool,
+}
+
+/// An audio device a backend can play on, e.g. a sound card, usb dac or hdmi output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputDevice {
+    pub id: String,
+    pub name: String,
+    /// Whether the backend is currently playing on this device
+    pub selected: bool,
+}
@@ -125,6 +125,20 @@ impl PlayerQueue for MemoryQueue {
         }
     }
 
+    async fn replace(&self, tracks: &[Track], index: Option<usize>) -> Result<(), Error> {
+        if let Some(index) = index.filter(|index| *index >= tracks.len()) {
+            return Err(format_err!("Index {} out of bounds", index));
+        }
+        let current_index = index.unwrap_or(tracks.len());
+        self.queue.set(tracks.to_vec());
+        self.current_index
+            .store(current_index, atomic::Ordering::Relaxed);
+        self.queue_changed().await?;
+        self.select_track(tracks, current_index)?;
+
+        Ok(())
+    }
+
     async fn remove_item(&self, index: usize) -> Result<(), Error> {
         let current_index = self.current_index.load(atomic::Ordering::Relaxed);
         let mut queue = self.queue.read();
@@ -41,6 +41,11 @@ pub trait PlayerQueue: Send + Sync + Debug {
     /// Jump to item at given index
     async fn select_item(&self, index: usize) -> Result<(), Error>;
 
+    /// Replace the queue with the given tracks and select the item at the given index
+    ///
+    /// Only the selected track is sent to the player, unlike queue_multiple followed by select_item.
+    async fn replace(&self, tracks: &[Track], index: Option<usize>) -> Result<(), Error>;
+
     /// Remove item at the given index
     async fn remove_item(&self, index: usize) -> Result<(), Error>;
 
@@ -0,0 +1,521 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use failure::Error;
+use futures::stream::BoxStream;
+
+use crate::library::{HistoryQuery, LibraryItemIdentifier, PlayRecord, TrackPlays};
+use crate::provider::{ProviderFolder, ProviderItem, ProviderItemType};
+use crate::{
+    Album, Artist, CredentialStore, Credentials, Library, LibraryChange, MultiQuery, Playlist,
+    ProviderId, Rating, SearchResults, SharedLibrary, SingleQuery, Track,
+};
+
+/// Separates the account from the uri the provider knows an item by
+const ACCOUNT_SEPARATOR: &str = "#account=";
+
+/// Appends the account to the uri, items of the default account keep their uri
+///
+/// Otherwise two accounts syncing the same item would share one library entry.
+pub(crate) fn account_uri(uri: &str, id: &ProviderId) -> String {
+    match id.instance() {
+        Some(account) => format!("{}{}{}", provider_uri(uri), ACCOUNT_SEPARATOR, account),
+        None => provider_uri(uri).to_string(),
+    }
+}
+
+/// The uri without the account, as the provider knows the item
+pub(crate) fn provider_uri(uri: &str) -> &str {
+    match uri.find(ACCOUNT_SEPARATOR) {
+        Some(index) => &uri[..index],
+        None => uri,
+    }
+}
+
+/// The account the uri belongs to, `None` for the default account
+pub(crate) fn uri_account(uri: &str) -> Option<&str> {
+    uri.find(ACCOUNT_SEPARATOR)
+        .map(|index| &uri[index + ACCOUNT_SEPARATOR.len()..])
+}
+
+/// Items which are tagged with the provider account they belong to
+pub(crate) trait AccountItem {
+    /// Calls `tag` with the uri and provider of the item and all nested items
+    fn tags(&mut self, tag: &mut dyn FnMut(&mut String, &mut ProviderId));
+
+    fn assign_account(&mut self, id: &ProviderId) {
+        self.tags(&mut |uri, provider| {
+            *uri = account_uri(uri, id);
+            *provider = id.clone();
+        });
+    }
+
+    /// Restores the uris the provider knows, before the item is handed to it
+    fn remove_account(&mut self) {
+        self.tags(&mut |uri, _| {
+            let len = provider_uri(uri).len();
+            uri.truncate(len);
+        });
+    }
+}
+
+impl AccountItem for Track {
+    fn tags(&mut self, tag: &mut dyn FnMut(&mut String, &mut ProviderId)) {
+        tag(&mut self.uri, &mut self.provider);
+        self.artist.tags(tag);
+        self.album.tags(tag);
+    }
+}
+
+impl AccountItem for Album {
+    fn tags(&mut self, tag: &mut dyn FnMut(&mut String, &mut ProviderId)) {
+        tag(&mut self.uri, &mut self.provider);
+        self.artist.tags(tag);
+        self.tracks.tags(tag);
+    }
+}
+
+impl AccountItem for Artist {
+    fn tags(&mut self, tag: &mut dyn FnMut(&mut String, &mut ProviderId)) {
+        tag(&mut self.uri, &mut self.provider);
+        self.albums.tags(tag);
+        self.playlists.tags(tag);
+    }
+}
+
+impl AccountItem for Playlist {
+    fn tags(&mut self, tag: &mut dyn FnMut(&mut String, &mut ProviderId)) {
+        tag(&mut self.uri, &mut self.provider);
+        self.tracks.tags(tag);
+    }
+}
+
+impl AccountItem for ProviderItemType {
+    fn tags(&mut self, tag: &mut dyn FnMut(&mut String, &mut ProviderId)) {
+        match self {
+            ProviderItemType::Track(track) => track.tags(tag),
+            ProviderItemType::Album(album) => album.tags(tag),
+            ProviderItemType::Artist(artist) => artist.tags(tag),
+            ProviderItemType::Playlist(playlist) => playlist.tags(tag),
+        }
+    }
+}
+
+impl AccountItem for ProviderItem {
+    fn tags(&mut self, tag: &mut dyn FnMut(&mut String, &mut ProviderId)) {
+        self.data.tags(tag);
+    }
+}
+
+impl AccountItem for ProviderFolder {
+    fn tags(&mut self, tag: &mut dyn FnMut(&mut String, &mut ProviderId)) {
+        self.items.tags(tag);
+    }
+}
+
+impl<T: AccountItem> AccountItem for Vec<T> {
+    fn tags(&mut self, tag: &mut dyn FnMut(&mut String, &mut ProviderId)) {
+        for item in self.iter_mut() {
+            item.tags(tag);
+        }
+    }
+}
+
+impl<T: AccountItem> AccountItem for Option<T> {
+    fn tags(&mut self, tag: &mut dyn FnMut(&mut String, &mut ProviderId)) {
+        if let Some(item) = self {
+            item.tags(tag);
+        }
+    }
+}
+
+/// Stores the credentials of a provider under the id of its account
+///
+/// Provider implementations always ask for their own key, this way every account keeps its own credentials entry.
+pub(crate) struct AccountCredentialStore<'a> {
+    id: &'a ProviderId,
+    inner: &'a dyn CredentialStore,
+}
+
+impl<'a> AccountCredentialStore<'a> {
+    pub fn new(id: &'a ProviderId, inner: &'a dyn CredentialStore) -> Self {
+        AccountCredentialStore { id, inner }
+    }
+}
+
+#[async_trait]
+impl<'a> CredentialStore for AccountCredentialStore<'a> {
+    async fn get_credentials(&self, provider: ProviderId) -> Result<Option<Credentials>, Error> {
+        self.inner.get_credentials(self.account(provider)).await
+    }
+
+    async fn store_credentials(
+        &self,
+        provider: ProviderId,
+        credentials: Credentials,
+    ) -> Result<(), Error> {
+        self.inner
+            .store_credentials(self.account(provider), credentials)
+            .await
+    }
+
+    async fn delete_credentials(&self, provider: ProviderId) -> Result<(), Error> {
+        self.inner.delete_credentials(self.account(provider)).await
+    }
+
+    fn account(&self, provider: ProviderId) -> ProviderId {
+        if provider.key() == self.id.key() {
+            self.id.clone()
+        } else {
+            provider
+        }
+    }
+}
+
+/// Tags all items a provider syncs with the id of its account
+///
+/// Queries by uri and removals only see the items of the account.
+#[derive(Debug)]
+pub(crate) struct AccountLibrary {
+    id: ProviderId,
+    inner: SharedLibrary,
+}
+
+impl AccountLibrary {
+    pub fn new(id: ProviderId, inner: SharedLibrary) -> Self {
+        AccountLibrary { id, inner }
+    }
+
+    fn single(&self, mut query: SingleQuery) -> SingleQuery {
+        if let LibraryItemIdentifier::Uri(ref mut uri) = query.identifier {
+            *uri = account_uri(uri, &self.id);
+        }
+        query
+    }
+
+    /// Narrows filters for the provider down to the account
+    fn multi(&self, mut query: MultiQuery) -> MultiQuery {
+        for provider in query.providers.iter_mut() {
+            if provider.key() == self.id.key() {
+                *provider = self.id.clone();
+            }
+        }
+        query
+    }
+
+    fn own<T: AccountItem + Clone>(&self, item: &T) -> T {
+        let mut item = item.clone();
+        item.assign_account(&self.id);
+        item
+    }
+}
+
+impl Library for AccountLibrary {
+    fn query_track(&self, query: SingleQuery) -> Result<Option<Track>, Error> {
+        self.inner.query_track(self.single(query))
+    }
+
+    fn query_tracks(&self, query: MultiQuery) -> Result<Vec<Track>, Error> {
+        self.inner.query_tracks(self.multi(query))
+    }
+
+    fn query_album(&self, query: SingleQuery) -> Result<Option<Album>, Error> {
+        self.inner.query_album(self.single(query))
+    }
+
+    fn query_albums(&self, query: MultiQuery) -> Result<Vec<Album>, Error> {
+        self.inner.query_albums(self.multi(query))
+    }
+
+    fn query_artist(&self, query: SingleQuery) -> Result<Option<Artist>, Error> {
+        self.inner.query_artist(self.single(query))
+    }
+
+    fn query_artists(&self, query: MultiQuery) -> Result<Vec<Artist>, Error> {
+        self.inner.query_artists(self.multi(query))
+    }
+
+    fn query_playlist(&self, query: SingleQuery) -> Result<Option<Playlist>, Error> {
+        self.inner.query_playlist(self.single(query))
+    }
+
+    fn query_playlists(&self, query: MultiQuery) -> Result<Vec<Playlist>, Error> {
+        self.inner.query_playlists(self.multi(query))
+    }
+
+    fn add_track(&self, track: &mut Track) -> Result<(), Error> {
+        track.assign_account(&self.id);
+        self.inner.add_track(track)
+    }
+
+    fn add_album(&self, album: &mut Album) -> Result<(), Error> {
+        album.assign_account(&self.id);
+        self.inner.add_album(album)
+    }
+
+    fn add_artist(&self, artist: &mut Artist) -> Result<(), Error> {
+        artist.assign_account(&self.id);
+        self.inner.add_artist(artist)
+    }
+
+    fn add_playlist(&self, playlist: &mut Playlist) -> Result<(), Error> {
+        playlist.assign_account(&self.id);
+        self.inner.add_playlist(playlist)
+    }
+
+    fn add_tracks(&self, tracks: &mut Vec<Track>) -> Result<(), Error> {
+        tracks.assign_account(&self.id);
+        self.inner.add_tracks(tracks)
+    }
+
+    fn add_albums(&self, albums: &mut Vec<Album>) -> Result<(), Error> {
+        albums.assign_account(&self.id);
+        self.inner.add_albums(albums)
+    }
+
+    fn add_artists(&self, artists: &mut Vec<Artist>) -> Result<(), Error> {
+        artists.assign_account(&self.id);
+        self.inner.add_artists(artists)
+    }
+
+    fn add_playlists(&self, playlists: &mut Vec<Playlist>) -> Result<(), Error> {
+        playlists.assign_account(&self.id);
+        self.inner.add_playlists(playlists)
+    }
+
+    fn sync_track(&self, track: &mut Track) -> Result<(), Error> {
+        track.assign_account(&self.id);
+        self.inner.sync_track(track)
+    }
+
+    fn sync_album(&self, album: &mut Album) -> Result<(), Error> {
+        album.assign_account(&self.id);
+        self.inner.sync_album(album)
+    }
+
+    fn sync_artist(&self, artist: &mut Artist) -> Result<(), Error> {
+        artist.assign_account(&self.id);
+        self.inner.sync_artist(artist)
+    }
+
+    fn sync_playlist(&self, playlist: &mut Playlist) -> Result<(), Error> {
+        playlist.assign_account(&self.id);
+        self.inner.sync_playlist(playlist)
+    }
+
+    fn set_rating(&self, uri: &str, rating: Rating) -> Result<(), Error> {
+        self.inner.set_rating(&account_uri(uri, &self.id), rating)
+    }
+
+    fn sync_tracks(&self, tracks: &mut Vec<Track>) -> Result<(), Error> {
+        tracks.assign_account(&self.id);
+        self.inner.sync_tracks(tracks)
+    }
+
+    fn sync_albums(&self, albums: &mut Vec<Album>) -> Result<(), Error> {
+        albums.assign_account(&self.id);
+        self.inner.sync_albums(albums)
+    }
+
+    fn sync_artists(&self, artists: &mut Vec<Artist>) -> Result<(), Error> {
+        artists.assign_account(&self.id);
+        self.inner.sync_artists(artists)
+    }
+
+    fn sync_playlists(&self, playlists: &mut Vec<Playlist>) -> Result<(), Error> {
+        playlists.assign_account(&self.id);
+        self.inner.sync_playlists(playlists)
+    }
+
+    fn remove_track(&self, track: &Track) -> Result<(), Error> {
+        self.inner.remove_track(&self.own(track))
+    }
+
+    fn remove_album(&self, album: &Album) -> Result<(), Error> {
+        self.inner.remove_album(&self.own(album))
+    }
+
+    fn remove_artist(&self, artist: &Artist) -> Result<(), Error> {
+        self.inner.remove_artist(&self.own(artist))
+    }
+
+    fn remove_playlist(&self, playlist: &Playlist) -> Result<(), Error> {
+        self.inner.remove_playlist(&self.own(playlist))
+    }
+
+    fn search(&self, query: String) -> Result<SearchResults, Error> {
+        self.inner.search(query)
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+
+    fn revision(&self) -> Result<u64, Error> {
+        self.inner.revision()
+    }
+
+    fn changes_since(&self, revision: u64) -> Result<Vec<LibraryChange>, Error> {
+        self.inner.changes_since(revision)
+    }
+
+    fn observe(&self) -> BoxStream<'static, LibraryChange> {
+        self.inner.observe()
+    }
+
+    fn add_play(&self, play: PlayRecord) -> Result<(), Error> {
+        self.inner.add_play(play)
+    }
+
+    fn query_plays(&self, query: HistoryQuery) -> Result<Vec<PlayRecord>, Error> {
+        self.inner.query_plays(query)
+    }
+
+    fn query_track_plays(&self, uris: &[String]) -> Result<HashMap<String, TrackPlays>, Error> {
+        let account_uris: Vec<_> = uris.iter().map(|uri| account_uri(uri, &self.id)).collect();
+        let plays = self.inner.query_track_plays(&account_uris)?;
+        let plays = uris
+            .iter()
+            .zip(account_uris)
+            .filter_map(|(uri, account_uri)| {
+                plays.get(&account_uri).map(|plays| (uri.clone(), *plays))
+            })
+            .collect();
+
+        Ok(plays)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use failure::Error;
+
+    use crate::provider::ProviderId;
+    use crate::{Artist, CredentialStore, Credentials, Playlist};
+
+    use super::{account_uri, provider_uri, uri_account, AccountCredentialStore, AccountItem};
+
+    /// Records the ids of deleted credentials
+    #[derive(Default)]
+    struct DeletingStore {
+        deleted: Mutex<Vec<ProviderId>>,
+    }
+
+    #[async_trait]
+    impl CredentialStore for DeletingStore {
+        async fn get_credentials(&self, _: ProviderId) -> Result<Option<Credentials>, Error> {
+            Ok(None)
+        }
+
+        async fn store_credentials(&self, _: ProviderId, _: Credentials) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn delete_credentials(&self, provider: ProviderId) -> Result<(), Error> {
+            self.deleted.lock().unwrap().push(provider);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn assign_account_should_tag_nested_items() {
+        let id = ProviderId::new("spotify").with_instance("work");
+        let playlist = Playlist {
+            id: None,
+            title: String::from("Playlist"),
+            tracks: Vec::new(),
+            provider: ProviderId::new("spotify"),
+            uri: String::from("spotify://playlist/1"),
+        };
+        let mut artist = Artist {
+            id: None,
+            name: String::from("Artist"),
+            uri: String::from("spotify://artist/1"),
+            image_url: None,
+            meta: HashMap::new(),
+            provider: ProviderId::new("spotify"),
+            albums: Vec::new(),
+            playlists: vec![playlist],
+            description: None,
+        };
+
+        artist.assign_account(&id);
+
+        assert_eq!(artist.provider, id);
+        assert_eq!(artist.playlists[0].provider, id);
+        assert_eq!(artist.uri, "spotify://artist/1#account=work");
+        assert_eq!(artist.playlists[0].uri, "spotify://playlist/1#account=work");
+    }
+
+    #[test]
+    fn remove_account_should_restore_the_uris_of_the_provider() {
+        let id = ProviderId::new("spotify").with_instance("work");
+        let mut playlist = Playlist {
+            id: None,
+            title: String::from("Playlist"),
+            tracks: Vec::new(),
+            provider: ProviderId::new("spotify"),
+            uri: String::from("spotify://playlist/1"),
+        };
+        playlist.assign_account(&id);
+
+        playlist.remove_account();
+
+        assert_eq!(playlist.uri, "spotify://playlist/1");
+    }
+
+    #[test]
+    fn account_uri_should_keep_the_uri_of_the_default_account() {
+        let uri = account_uri("spotify://track/1", &ProviderId::new("spotify"));
+
+        assert_eq!(uri, "spotify://track/1");
+        assert_eq!(uri_account(&uri), None);
+    }
+
+    #[test]
+    fn account_uri_should_replace_a_previous_account() {
+        let work = ProviderId::new("spotify").with_instance("work");
+        let home = ProviderId::new("spotify").with_instance("home");
+
+        let uri = account_uri(&account_uri("spotify://track/1", &work), &home);
+
+        assert_eq!(uri, "spotify://track/1#account=home");
+        assert_eq!(uri_account(&uri), Some("home"));
+        assert_eq!(provider_uri(&uri), "spotify://track/1");
+    }
+
+    #[tokio::test]
+    async fn delete_credentials_should_delete_the_credentials_of_the_account() {
+        let id = ProviderId::new("spotify").with_instance("work");
+        let inner = DeletingStore::default();
+        let store = AccountCredentialStore::new(&id, &inner);
+
+        store
+            .delete_credentials(ProviderId::new("spotify"))
+            .await
+            .unwrap();
+        store
+            .delete_credentials(ProviderId::new("soundcloud"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *inner.deleted.lock().unwrap(),
+            vec![id.clone(), ProviderId::new("soundcloud")]
+        );
+    }
+
+    #[test]
+    fn account_should_tell_the_provider_its_account() {
+        let id = ProviderId::new("spotify").with_instance("work");
+        let inner = DeletingStore::default();
+        let store = AccountCredentialStore::new(&id, &inner);
+
+        assert_eq!(store.account(ProviderId::new("spotify")), id);
+        assert_eq!(inner.account(ProviderId::new("spotify")).instance(), None);
+    }
+}
@@ -85,7 +85,7 @@ impl Explorer {
                     .find(|provider| &provider.title() == path);
                 let path = &self.path[1..];
                 match provider {
-                    Some(provider) => provider.get().await.navigate(path.to_vec()).await,
+                    Some(provider) => provider.navigate(path.to_vec()).await,
                     None => Err(Error::from(NavigationError::PathNotFound))
                 }
             }
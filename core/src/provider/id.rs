@@ -0,0 +1,163 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use serde_derive::{Deserialize, Serialize};
+
+/// Keys of the former `ProviderType` enum in declaration order
+///
+/// Stores which persisted the discriminant instead of the key use this to migrate their data.
+pub const LEGACY_PROVIDER_KEYS: [&str; 9] = [
+    "internal",
+    "pocketcasts",
+    "soundcloud",
+    "gmusic",
+    "spotify",
+    "local",
+    "youtube",
+    "ytmusic",
+    "extension",
+];
+
+/// Identifies a provider instance
+///
+/// Consists of the key of the provider implementation, e.g. `spotify`,
+/// and an optional instance id which tells multiple instances of the same implementation apart.
+/// Serialized as `key` or `key:instance`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct ProviderId {
+    key: Cow<'static, str>,
+    instance: Option<String>,
+}
+
+impl ProviderId {
+    /// Items which are managed by rustic itself, e.g. playlists created by the user
+    pub const INTERNAL: ProviderId = ProviderId::new("internal");
+
+    pub const fn new(key: &'static str) -> Self {
+        ProviderId {
+            key: Cow::Borrowed(key),
+            instance: None,
+        }
+    }
+
+    pub fn with_instance<S: Into<String>>(mut self, instance: S) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+
+    /// Whether the given id is matched when filtering by this one
+    ///
+    /// An id without instance matches all instances of the provider.
+    pub fn includes(&self, id: &ProviderId) -> bool {
+        self.key == id.key && (self.instance.is_none() || self.instance == id.instance)
+    }
+
+    /// Maps the discriminant of the former `ProviderType` enum to its key
+    pub fn from_legacy_index(index: usize) -> Option<Self> {
+        LEGACY_PROVIDER_KEYS
+            .get(index)
+            .copied()
+            .map(ProviderId::new)
+    }
+}
+
+impl fmt::Display for ProviderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.instance {
+            Some(ref instance) => write!(f, "{}:{}", self.key, instance),
+            None => write!(f, "{}", self.key),
+        }
+    }
+}
+
+impl From<&str> for ProviderId {
+    fn from(id: &str) -> Self {
+        match id.split_once(':') {
+            Some((key, instance)) if !instance.is_empty() => ProviderId {
+                key: Cow::Owned(key.to_string()),
+                instance: Some(instance.to_string()),
+            },
+            Some((key, _)) => ProviderId {
+                key: Cow::Owned(key.to_string()),
+                instance: None,
+            },
+            None => ProviderId {
+                key: Cow::Owned(id.to_string()),
+                instance: None,
+            },
+        }
+    }
+}
+
+impl From<String> for ProviderId {
+    fn from(id: String) -> Self {
+        ProviderId::from(id.as_str())
+    }
+}
+
+impl From<ProviderId> for String {
+    fn from(id: ProviderId) -> Self {
+        id.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProviderId;
+
+    #[test]
+    fn ids_should_equal_regardless_of_key_ownership() {
+        let id = ProviderId::from(String::from("spotify"));
+
+        assert_eq!(id, ProviderId::new("spotify"));
+    }
+
+    #[test]
+    fn display_should_include_instance() {
+        let id = ProviderId::new("spotify").with_instance("work");
+
+        assert_eq!(id.to_string(), "spotify:work");
+        assert_eq!(ProviderId::from("spotify:work"), id);
+    }
+
+    #[test]
+    fn includes_should_match_all_instances_without_instance() {
+        let provider = ProviderId::new("spotify");
+        let work = ProviderId::new("spotify").with_instance("work");
+        let home = ProviderId::new("spotify").with_instance("home");
+
+        assert!(provider.includes(&work));
+        assert!(provider.includes(&provider));
+        assert!(work.includes(&work));
+        assert!(!work.includes(&home));
+        assert!(!work.includes(&provider));
+        assert!(!provider.includes(&ProviderId::new("soundcloud")));
+    }
+
+    #[test]
+    fn deserialize_should_read_former_enum_representation() {
+        let id: ProviderId = serde_json::from_str("\"ytmusic\"").unwrap();
+
+        assert_eq!(id, ProviderId::new("ytmusic"));
+        assert_eq!(serde_json::to_string(&id).unwrap(), "\"ytmusic\"");
+    }
+
+    #[test]
+    fn from_legacy_index_should_map_discriminants() {
+        assert_eq!(ProviderId::from_legacy_index(0), Some(ProviderId::INTERNAL));
+        assert_eq!(
+            ProviderId::from_legacy_index(5),
+            Some(ProviderId::new("local"))
+        );
+        assert_eq!(ProviderId::from_legacy_index(42), None);
+    }
+}
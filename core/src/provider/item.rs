@@ -35,6 +35,16 @@ impl ProviderItem {
 }
 
 impl ProviderItemType {
+    pub fn uri(&self) -> &str {
+        use ProviderItemType::*;
+        match self {
+            Track(track) => &track.uri,
+            Album(album) => &album.uri,
+            Artist(artist) => &artist.uri,
+            Playlist(playlist) => &playlist.uri,
+        }
+    }
+
     pub fn thumbnail(&self) -> ThumbnailState {
         use ProviderItemType::*;
         match self {
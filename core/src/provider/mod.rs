@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use failure::{Error, Fail};
 use serde_derive::{Deserialize, Serialize};
@@ -8,16 +9,21 @@ use url::Url;
 
 use async_trait::async_trait;
 
-use crate::library::{Album, Artist, SharedLibrary, Track};
+use crate::library::{Album, Artist, MetadataUpdate, Rating, SharedLibrary, Track};
 use crate::{CredentialStore, Playlist};
 
+pub(crate) use self::account::uri_account;
+use self::account::{provider_uri, AccountCredentialStore, AccountItem, AccountLibrary};
 pub use self::explorer::Explorer;
 pub use self::folder::ProviderFolder;
+pub use self::id::{ProviderId, LEGACY_PROVIDER_KEYS};
 pub use self::item::{ProviderItem, ProviderItemType};
 pub use self::sync_error::SyncError;
 
+mod account;
 mod explorer;
 mod folder;
+mod id;
 mod item;
 mod sync_error;
 
@@ -28,15 +34,35 @@ pub type SharedProviders = Vec<Provider>;
 pub struct Provider {
     title: String,
     pub uri_scheme: String,
-    pub provider_type: ProviderType,
+    pub id: ProviderId,
     pub provider: SharedProvider,
 }
 
 impl Provider {
+    /// Creates a provider for one of multiple accounts of the same provider implementation
+    ///
+    /// Credentials, sync state and library items of the account are kept apart by the instance of its id.
+    pub fn for_account(
+        instance: Box<dyn ProviderInstance + Send + Sync>,
+        account: &str,
+        display_name: Option<String>,
+    ) -> Self {
+        let mut provider = Provider::from(instance);
+        provider.title =
+            display_name.unwrap_or_else(|| format!("{} ({})", provider.title, account));
+        provider.id = provider.id.with_instance(account);
+        provider
+    }
+
     pub fn title(&self) -> String {
         self.title.clone()
     }
 
+    /// The account of this provider, `None` for the default account
+    pub fn account(&self) -> Option<&str> {
+        self.id.instance()
+    }
+
     pub async fn get(&self) -> RwLockReadGuard<'_, Box<dyn ProviderInstance + Send + Sync>> {
         self.provider.read().await
     }
@@ -44,18 +70,139 @@ impl Provider {
     pub async fn get_mut(&self) -> RwLockWriteGuard<'_, Box<dyn ProviderInstance + Send + Sync>> {
         self.provider.write().await
     }
+
+    pub async fn setup(&self, cred_store: &dyn CredentialStore) -> Result<(), Error> {
+        let cred_store = AccountCredentialStore::new(&self.id, cred_store);
+        self.get_mut().await.setup(&cred_store).await
+    }
+
+    pub async fn authenticate(
+        &self,
+        auth: Authentication,
+        cred_store: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        let cred_store = AccountCredentialStore::new(&self.id, cred_store);
+        self.get_mut().await.authenticate(auth, &cred_store).await
+    }
+
+    /// Ends the session and removes the stored credentials of this account
+    pub async fn logout(&self, cred_store: &dyn CredentialStore) -> Result<(), Error> {
+        self.get_mut().await.logout().await?;
+        cred_store.delete_credentials(self.id.clone()).await
+    }
+
+    pub async fn refresh_authentication(
+        &self,
+        cred_store: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        let cred_store = AccountCredentialStore::new(&self.id, cred_store);
+        self.get_mut()
+            .await
+            .refresh_authentication(&cred_store)
+            .await
+    }
+
+    pub async fn sync(&self, library: SharedLibrary) -> Result<SyncResult, Error> {
+        let library: SharedLibrary =
+            Arc::new(Box::new(AccountLibrary::new(self.id.clone(), library)));
+        self.get().await.sync(library).await
+    }
+
+    pub async fn navigate(&self, path: Vec<String>) -> Result<ProviderFolder, Error> {
+        let mut folder = self.get().await.navigate(path).await?;
+        folder.assign_account(&self.id);
+        Ok(folder)
+    }
+
+    pub async fn search(&self, query: String) -> Result<Vec<ProviderItem>, Error> {
+        let mut items = self.get().await.search(query).await?;
+        items.assign_account(&self.id);
+        Ok(items)
+    }
+
+    pub async fn resolve_track(&self, uri: &str) -> Result<Option<Track>, Error> {
+        let mut track = self.get().await.resolve_track(provider_uri(uri)).await?;
+        track.assign_account(&self.id);
+        Ok(track)
+    }
+
+    pub async fn resolve_album(&self, uri: &str) -> Result<Option<Album>, Error> {
+        let mut album = self.get().await.resolve_album(provider_uri(uri)).await?;
+        album.assign_account(&self.id);
+        Ok(album)
+    }
+
+    pub async fn resolve_artist(&self, uri: &str) -> Result<Option<Artist>, Error> {
+        let mut artist = self.get().await.resolve_artist(provider_uri(uri)).await?;
+        artist.assign_account(&self.id);
+        Ok(artist)
+    }
+
+    pub async fn resolve_playlist(&self, uri: &str) -> Result<Option<Playlist>, Error> {
+        let mut playlist = self.get().await.resolve_playlist(provider_uri(uri)).await?;
+        playlist.assign_account(&self.id);
+        Ok(playlist)
+    }
+
+    pub async fn stream_url(&self, track: &Track) -> Result<String, Error> {
+        let track = without_account(track);
+        self.get().await.stream_url(&track).await
+    }
+
+    pub async fn thumbnail(
+        &self,
+        provider_item: &ProviderItemType,
+    ) -> Result<Option<Thumbnail>, Error> {
+        let provider_item = without_account(provider_item);
+        self.get().await.thumbnail(&provider_item).await
+    }
+
+    pub async fn thumbnail_modified(
+        &self,
+        provider_item: &ProviderItemType,
+    ) -> Result<Option<SystemTime>, Error> {
+        let provider_item = without_account(provider_item);
+        self.get().await.thumbnail_modified(&provider_item).await
+    }
+
+    pub async fn update_metadata(
+        &self,
+        provider_item: &ProviderItemType,
+        update: &MetadataUpdate,
+    ) -> Result<Vec<ProviderItemType>, Error> {
+        let provider_item = without_account(provider_item);
+        let mut items = self
+            .get()
+            .await
+            .update_metadata(&provider_item, update)
+            .await?;
+        items.assign_account(&self.id);
+        Ok(items)
+    }
+
+    pub async fn set_rating(&self, track: &Track, rating: Rating) -> Result<(), Error> {
+        let track = without_account(track);
+        self.get().await.set_rating(&track, rating).await
+    }
+}
+
+/// Providers only know the uris of their items without the account
+fn without_account<T: AccountItem + Clone>(item: &T) -> T {
+    let mut item = item.clone();
+    item.remove_account();
+    item
 }
 
 impl From<Box<dyn ProviderInstance + Send + Sync>> for Provider {
     fn from(instance: Box<dyn ProviderInstance + Send + Sync>) -> Self {
         let title = instance.title().to_owned();
         let uri_scheme = instance.uri_scheme().to_owned();
-        let provider_type = instance.provider();
+        let id = instance.provider();
 
         Provider {
             title,
             uri_scheme,
-            provider_type,
+            id,
             provider: Arc::new(RwLock::new(instance)),
         }
     }
@@ -79,35 +226,31 @@ impl SyncResult {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
-#[serde(rename_all = "lowercase")]
-pub enum ProviderType {
-    Internal,
-    Pocketcasts,
-    Soundcloud,
-    #[serde(rename = "gmusic")]
-    #[deprecated]
-    GooglePlayMusic,
-    Spotify,
-    #[serde(rename = "local")]
-    LocalMedia,
-    Youtube,
-    #[serde(rename = "ytmusic")]
-    YouTubeMusic,
-}
-
 #[async_trait]
 pub trait ProviderInstance: Debug {
     async fn setup(&mut self, cred_store: &dyn CredentialStore) -> Result<(), Error>;
     fn title(&self) -> &'static str;
     fn uri_scheme(&self) -> &'static str;
-    fn provider(&self) -> ProviderType;
+    fn provider(&self) -> ProviderId;
     fn state(&self) -> ProviderState;
     async fn authenticate(
         &mut self,
         auth: Authentication,
         cred_store: &dyn CredentialStore,
     ) -> Result<(), Error>;
+    /// Forgets the current session, the stored credentials are removed by the caller
+    async fn logout(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+    /// Renews the access token when it is about to expire and stores the new credentials
+    ///
+    /// Called periodically, providers without expiring tokens don't have to do anything.
+    async fn refresh_authentication(
+        &mut self,
+        _cred_store: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
     async fn sync(&self, library: SharedLibrary) -> Result<SyncResult, Error>;
     fn root(&self) -> ProviderFolder;
     async fn navigate(&self, path: Vec<String>) -> Result<ProviderFolder, Error>;
@@ -123,7 +266,30 @@ pub trait ProviderInstance: Debug {
     ) -> Result<Option<Thumbnail>, Error> {
         Ok(None)
     }
+    /// When the source of a thumbnail read from disk changed, cached cover art older than this is replaced
+    async fn thumbnail_modified(
+        &self,
+        _provider_item: &ProviderItemType,
+    ) -> Result<Option<SystemTime>, Error> {
+        Ok(None)
+    }
     async fn resolve_share_url(&self, url: Url) -> Result<Option<InternalUri>, Error>;
+    /// Writes the changed tags of the track or album
+    ///
+    /// Returns the updated items which should be synced to the library.
+    async fn update_metadata(
+        &self,
+        _provider_item: &ProviderItemType,
+        _update: &MetadataUpdate,
+    ) -> Result<Vec<ProviderItemType>, Error> {
+        Err(MetadataError::ReadOnly(self.title()).into())
+    }
+    /// Mirrors the rating to the native concept of the provider, e.g. likes or saved tracks
+    ///
+    /// Providers without such a concept only keep the rating in the library.
+    async fn set_rating(&self, _track: &Track, _rating: Rating) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -151,7 +317,9 @@ pub struct User {
     pub email: Option<String>,
 }
 
-// TODO: what about refresh and auth token?
+/// Authentication provided by the user
+///
+/// Refresh tokens are obtained by the provider while exchanging the token and kept in the credential store.
 #[derive(Debug, Clone)]
 pub enum Authentication {
     Token(String),
@@ -234,6 +402,14 @@ pub enum NavigationError {
     FetchError,
 }
 
+#[derive(Debug, Fail)]
+pub enum MetadataError {
+    #[fail(display = "{} is read-only", _0)]
+    ReadOnly(&'static str),
+    #[fail(display = "Only tracks and albums can be edited")]
+    UnsupportedItem,
+}
+
 // TODO: better name
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum InternalUri {
@@ -2,10 +2,9 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use failure::Error;
-use itertools::Itertools;
 use rustic_queue::{broadcast, Receiver, Sender};
 
-use crate::{ProviderType, Rustic};
+use crate::{ProviderId, Rustic};
 
 #[derive(Debug, Clone)]
 pub enum SyncEvent {
@@ -15,7 +14,7 @@ pub enum SyncEvent {
 
 #[derive(Debug, Clone)]
 pub struct SyncItem {
-    pub provider: ProviderType,
+    pub provider: ProviderId,
     pub state: SyncItemState,
 }
 
@@ -58,25 +57,20 @@ pub async fn start(app: Arc<Rustic>) -> Result<(), Error> {
 }
 
 async fn synchronize(app: &Arc<Rustic>) {
-    let providers = app.providers.clone();
+    let providers = app.providers();
     let mut sync_items: Vec<SyncItem> = providers
         .iter()
         .map(|p| SyncItem {
-            provider: p.provider_type,
+            provider: p.id.clone(),
             state: SyncItemState::Idle,
         })
         .collect();
     app.sync.next(SyncEvent::Synchronizing(sync_items.clone()));
-    for provider in providers {
-        let provider = provider.get().await;
-        if !provider.state().is_authenticated() {
+    for (position, provider) in providers.into_iter().enumerate() {
+        if !provider.get().await.state().is_authenticated() {
             continue;
         }
         log::info!("Syncing {} library", provider.title());
-        let (position, _) = sync_items
-            .iter()
-            .find_position(|i| i.provider == provider.provider())
-            .unwrap();
         sync_items.get_mut(position).unwrap().state = SyncItemState::Syncing;
         app.sync.next(SyncEvent::Synchronizing(sync_items.clone()));
         match provider.sync(Arc::clone(&app.library)).await {
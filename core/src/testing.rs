@@ -0,0 +1,30 @@
+//! Fixtures for the tests of rustic and its stores, enabled by the `testing` feature
+
+use std::collections::HashMap;
+
+use crate::library::{Lyrics, Rating, Track};
+use crate::provider::{ProviderId, ThumbnailState};
+
+/// An internal track without metadata, titled with its uri
+pub fn track(uri: &str) -> Track {
+    Track {
+        id: None,
+        title: uri.into(),
+        artist_id: None,
+        artist: None,
+        album_id: None,
+        album: None,
+        provider: ProviderId::INTERNAL,
+        uri: uri.into(),
+        thumbnail: ThumbnailState::None,
+        duration: None,
+        meta: HashMap::new(),
+        explicit: None,
+        rating: Rating::None,
+        position: None,
+        share_url: None,
+        lyrics: Lyrics::None,
+        comments: None,
+        chapters: Vec::new(),
+    }
+}
@@ -0,0 +1,315 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use failure::Error;
+use futures::StreamExt;
+use url::Url;
+
+use rustic_core::provider::{
+    Authentication, ProviderFolder, ProviderInstance, ProviderItem, ProviderState, SyncResult,
+};
+use rustic_core::{
+    auth, Album, Artist, CredentialStore, Credentials, InternalUri, MultiQuery, Playlist, Provider,
+    ProviderId, Rustic, SharedLibrary, SingleQuery, Track,
+};
+
+use crate::common::{rustic, track};
+
+mod common;
+
+/// Syncs the given tracks, resolved tracks are titled with the account
+#[derive(Debug, Clone)]
+struct TestProvider {
+    account: &'static str,
+    tracks: Vec<&'static str>,
+    authenticated: bool,
+}
+
+#[async_trait]
+impl ProviderInstance for TestProvider {
+    async fn setup(&mut self, _: &dyn CredentialStore) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn title(&self) -> &'static str {
+        "Test"
+    }
+
+    fn uri_scheme(&self) -> &'static str {
+        "test"
+    }
+
+    fn provider(&self) -> ProviderId {
+        ProviderId::new("test")
+    }
+
+    fn state(&self) -> ProviderState {
+        if self.authenticated {
+            ProviderState::Authenticated(None)
+        } else {
+            ProviderState::RequiresPassword
+        }
+    }
+
+    async fn authenticate(
+        &mut self,
+        _: Authentication,
+        _: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        self.authenticated = true;
+        Ok(())
+    }
+
+    async fn logout(&mut self) -> Result<(), Error> {
+        self.authenticated = false;
+        Ok(())
+    }
+
+    async fn sync(&self, library: SharedLibrary) -> Result<SyncResult, Error> {
+        let mut tracks: Vec<Track> = self.tracks.iter().map(|uri| test_track(uri)).collect();
+        library.sync_tracks(&mut tracks)?;
+
+        Ok(SyncResult {
+            tracks: tracks.len(),
+            ..SyncResult::empty()
+        })
+    }
+
+    fn root(&self) -> ProviderFolder {
+        ProviderFolder::empty()
+    }
+
+    async fn navigate(&self, _: Vec<String>) -> Result<ProviderFolder, Error> {
+        Ok(ProviderFolder::empty())
+    }
+
+    async fn search(&self, _: String) -> Result<Vec<ProviderItem>, Error> {
+        Ok(Vec::new())
+    }
+
+    async fn resolve_track(&self, uri: &str) -> Result<Option<Track>, Error> {
+        let mut track = test_track(uri);
+        track.title = self.account.to_string();
+
+        Ok(Some(track))
+    }
+
+    async fn resolve_album(&self, _: &str) -> Result<Option<Album>, Error> {
+        Ok(None)
+    }
+
+    async fn resolve_artist(&self, _: &str) -> Result<Option<Artist>, Error> {
+        Ok(None)
+    }
+
+    async fn resolve_playlist(&self, _: &str) -> Result<Option<Playlist>, Error> {
+        Ok(None)
+    }
+
+    async fn stream_url(&self, track: &Track) -> Result<String, Error> {
+        Ok(track.uri.clone())
+    }
+
+    async fn resolve_share_url(&self, _: Url) -> Result<Option<InternalUri>, Error> {
+        Ok(None)
+    }
+}
+
+struct NoCredentials;
+
+#[async_trait]
+impl CredentialStore for NoCredentials {
+    async fn get_credentials(&self, _: ProviderId) -> Result<Option<Credentials>, Error> {
+        Ok(None)
+    }
+
+    async fn store_credentials(&self, _: ProviderId, _: Credentials) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn delete_credentials(&self, _: ProviderId) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+fn test_track(uri: &str) -> Track {
+    let mut track = track(uri);
+    track.provider = ProviderId::new("test");
+    track
+}
+
+/// Adds the default account and the `work` account, both syncing `test://track/1`
+async fn synced_accounts(rustic: &Arc<Rustic>) -> (Provider, Provider) {
+    let home = TestProvider {
+        account: "home",
+        tracks: vec!["test://track/1", "test://track/2"],
+        authenticated: false,
+    };
+    let work = TestProvider {
+        account: "work",
+        tracks: vec!["test://track/1", "test://track/3"],
+        authenticated: false,
+    };
+    let home = Provider::from(Box::new(home) as Box<dyn ProviderInstance + Send + Sync>);
+    let work = Provider::for_account(Box::new(work), "work", None);
+    for provider in [&home, &work].iter() {
+        rustic.add_provider((*provider).clone());
+        provider.sync(Arc::clone(&rustic.library)).await.unwrap();
+    }
+
+    (home, work)
+}
+
+fn library_tracks(rustic: &Rustic) -> Vec<(String, ProviderId)> {
+    let mut tracks: Vec<_> = rustic
+        .library
+        .query_tracks(MultiQuery::new())
+        .unwrap()
+        .into_iter()
+        .map(|track| (track.uri, track.provider))
+        .collect();
+    tracks.sort();
+    tracks
+}
+
+#[tokio::test]
+async fn accounts_should_keep_their_own_copy_of_shared_items() {
+    let rustic = rustic();
+    let home = ProviderId::new("test");
+    let work = ProviderId::new("test").with_instance("work");
+
+    synced_accounts(&rustic).await;
+
+    assert_eq!(
+        library_tracks(&rustic),
+        vec![
+            ("test://track/1".to_string(), home.clone()),
+            ("test://track/1#account=work".to_string(), work.clone()),
+            ("test://track/2".to_string(), home),
+            ("test://track/3#account=work".to_string(), work),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn purging_an_account_should_keep_the_items_of_other_accounts() {
+    let rustic = rustic();
+    let home = ProviderId::new("test");
+    let (_, work) = synced_accounts(&rustic).await;
+
+    auth::logout(&rustic, &work, &NoCredentials, true)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        library_tracks(&rustic),
+        vec![
+            ("test://track/1".to_string(), home.clone()),
+            ("test://track/2".to_string(), home),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn uris_should_be_resolved_by_their_account() {
+    let rustic = rustic();
+    synced_accounts(&rustic).await;
+
+    let home = rustic
+        .query_track(SingleQuery::uri("test://track/4".into()))
+        .await
+        .unwrap()
+        .unwrap();
+    let work = rustic
+        .query_track(SingleQuery::uri("test://track/4#account=work".into()))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(home.title, "home");
+    assert_eq!(home.uri, "test://track/4");
+    assert_eq!(work.title, "work");
+    assert_eq!(work.uri, "test://track/4#account=work");
+}
+
+#[tokio::test]
+async fn auth_state_should_only_emit_changed_states() {
+    let rustic = rustic();
+    let (home, work) = synced_accounts(&rustic).await;
+    let events = rustic.auth.events.stream();
+    let password = || Authentication::Password("user".into(), "password".into());
+
+    rustic.auth.update(&home).await;
+    rustic.auth.update(&work).await;
+    rustic.auth.update(&work).await;
+    auth::authenticate(&rustic, &work, password(), &NoCredentials)
+        .await
+        .unwrap();
+    auth::authenticate(&rustic, &work, password(), &NoCredentials)
+        .await
+        .unwrap();
+    auth::logout(&rustic, &work, &NoCredentials, false)
+        .await
+        .unwrap();
+
+    let events: Vec<_> = events.take(2).collect().await;
+    assert_eq!(events[0].provider, work.id);
+    assert!(matches!(events[0].state, ProviderState::Authenticated(_)));
+    assert_eq!(events[1].provider, work.id);
+    assert!(matches!(events[1].state, ProviderState::RequiresPassword));
+}
+
+#[tokio::test]
+async fn auth_state_should_deliver_events_to_every_subscriber() {
+    let rustic = rustic();
+    let (_, work) = synced_accounts(&rustic).await;
+    let first = rustic.auth.events.stream();
+    let second = rustic.auth.events.stream();
+    rustic.auth.update(&work).await;
+
+    auth::authenticate(
+        &rustic,
+        &work,
+        Authentication::Token("token".into()),
+        &NoCredentials,
+    )
+    .await
+    .unwrap();
+
+    let first: Vec<_> = first.take(1).collect().await;
+    let second: Vec<_> = second.take(1).collect().await;
+    assert_eq!(first[0].provider, work.id);
+    assert_eq!(second[0].provider, work.id);
+}
+
+#[tokio::test]
+async fn uris_of_unknown_accounts_should_not_use_another_account() {
+    let rustic = rustic();
+    synced_accounts(&rustic).await;
+    let mut track = test_track("test://track/4#account=gone");
+    track.provider = ProviderId::new("test").with_instance("gone");
+
+    let stream_url = rustic.stream_url(&track).await;
+    let resolved = rustic
+        .query_track(SingleQuery::uri(track.uri.clone()))
+        .await
+        .unwrap();
+
+    assert!(stream_url.is_err());
+    assert!(resolved.is_none());
+}
+
+#[tokio::test]
+async fn uris_of_the_default_account_should_fall_back_to_another_account() {
+    let rustic = rustic();
+    let work = TestProvider {
+        account: "work",
+        tracks: Vec::new(),
+        authenticated: true,
+    };
+    rustic.add_provider(Provider::for_account(Box::new(work), "work", None));
+
+    let stream_url = rustic.stream_url(&test_track("test://track/4")).await;
+
+    assert_eq!(stream_url.unwrap(), "test://track/4");
+}
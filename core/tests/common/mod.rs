@@ -0,0 +1,197 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use failure::Error;
+
+use rustic_core::cache::CacheOptions;
+use rustic_core::library::Lyrics;
+use rustic_core::player::{PlayerCapabilities, PlayerCapability, PlayerError};
+use rustic_core::provider::ThumbnailState;
+use rustic_core::{
+    PlayerBackend, PlayerState, ProviderId, Rating, Rustic, StorageBackend, StorageCollection,
+    Track,
+};
+use rustic_memory_store::MemoryLibrary;
+
+#[derive(Debug)]
+struct NoStorage;
+
+#[async_trait]
+impl StorageBackend for NoStorage {
+    async fn open_collection(&self, _: &str) -> Result<Box<dyn StorageCollection>, Error> {
+        unimplemented!()
+    }
+}
+
+/// Creates an instance without providers, which keeps its caches in a temporary directory
+pub fn rustic() -> Arc<Rustic> {
+    let path = temp_dir();
+    let options = CacheOptions {
+        path: path.join("tracks"),
+        max_size: 0,
+        prefetch: 0,
+        coverart_path: path.join("coverart"),
+        coverart_max_size: 0,
+    };
+
+    Rustic::new(
+        Box::new(MemoryLibrary::default()),
+        Arc::new(Box::new(NoStorage)),
+        Vec::new(),
+        options,
+    )
+    .unwrap()
+}
+
+pub fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("rustic-test-{}", uuid::Uuid::new_v4()))
+}
+
+pub fn track(uri: &str) -> Track {
+    Track {
+        id: None,
+        title: uri.into(),
+        artist_id: None,
+        artist: None,
+        album_id: None,
+        album: None,
+        provider: ProviderId::INTERNAL,
+        uri: uri.into(),
+        thumbnail: ThumbnailState::None,
+        duration: None,
+        meta: HashMap::new(),
+        explicit: None,
+        rating: Rating::None,
+        position: None,
+        share_url: None,
+        lyrics: Lyrics::None,
+        comments: None,
+        chapters: Vec::new(),
+    }
+}
+
+/// Pins the tracks so they can be played without a provider
+pub fn make_playable(rustic: &Rustic, tracks: &[Track]) {
+    let file = temp_dir();
+    std::fs::write(&file, b"").unwrap();
+    for track in tracks {
+        rustic.cache.pinned.pin(&track.uri, &[track.clone()]);
+        rustic.cache.pinned.copy(track, &file).unwrap();
+    }
+    std::fs::remove_file(&file).unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackendCall {
+    SetTrack(String),
+    SetState(PlayerState),
+    Seek(Duration),
+}
+
+/// Player backend which records the calls made by the player
+#[derive(Debug, Clone)]
+pub struct FakeBackend {
+    pub calls: Arc<Mutex<Vec<BackendCall>>>,
+    state: Arc<Mutex<PlayerState>>,
+    position: Option<Duration>,
+    seek: bool,
+}
+
+impl FakeBackend {
+    pub fn new(seek: bool) -> Self {
+        FakeBackend {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            state: Arc::new(Mutex::new(PlayerState::Stop)),
+            position: None,
+            seek,
+        }
+    }
+
+    pub fn playing(state: PlayerState, position: Option<Duration>) -> Self {
+        let mut backend = FakeBackend::new(true);
+        backend.state = Arc::new(Mutex::new(state));
+        backend.position = position;
+        backend
+    }
+
+    pub fn calls(&self) -> Vec<BackendCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Waits until the given number of calls was recorded or one second passed
+    pub async fn wait_for_calls(&self, count: usize) -> Vec<BackendCall> {
+        for _ in 0..100 {
+            if self.calls.lock().unwrap().len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        // commands which should not have been sent would arrive in the meantime
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.calls()
+    }
+
+    fn record(&self, call: BackendCall) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+impl PlayerBackend for FakeBackend {
+    fn capabilities(&self) -> PlayerCapabilities {
+        PlayerCapabilities {
+            seek: self.seek,
+            ..PlayerCapabilities::default()
+        }
+    }
+
+    fn set_track(&self, track: &Track, _: String) -> Result<(), Error> {
+        self.record(BackendCall::SetTrack(track.uri.clone()));
+        Ok(())
+    }
+
+    fn set_state(&self, state: PlayerState) -> Result<(), Error> {
+        self.record(BackendCall::SetState(state));
+        *self.state.lock().unwrap() = state;
+        Ok(())
+    }
+
+    fn state(&self) -> PlayerState {
+        *self.state.lock().unwrap()
+    }
+
+    fn set_volume(&self, _: f32) -> Result<(), Error> {
+        Err(PlayerError::Unsupported(PlayerCapability::Volume).into())
+    }
+
+    fn volume(&self) -> f32 {
+        1.0
+    }
+
+    fn set_blend_time(&self, _: Duration) -> Result<(), Error> {
+        Err(PlayerError::Unsupported(PlayerCapability::Crossfade).into())
+    }
+
+    fn blend_time(&self) -> Duration {
+        Duration::default()
+    }
+
+    fn seek(&self, duration: Duration) -> Result<(), Error> {
+        if !self.seek {
+            return Err(PlayerError::Unsupported(PlayerCapability::Seek).into());
+        }
+        self.record(BackendCall::Seek(duration));
+        Ok(())
+    }
+
+    fn position(&self) -> Option<Duration> {
+        self.position
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
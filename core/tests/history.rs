@@ -0,0 +1,78 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rustic_core::library::{HistoryQuery, PlayRecord};
+use rustic_core::player::{NextReason, PlayerBuilder, PlayerBus, QueueCommand};
+use rustic_core::{PlayerEvent, PlayerState, Rustic};
+
+use crate::common::{rustic, track, FakeBackend};
+
+mod common;
+
+fn player(rustic: &Arc<Rustic>) -> PlayerBus {
+    let bus = Arc::new(Mutex::new(None));
+    let backend_bus = Arc::clone(&bus);
+    let player = PlayerBuilder::new(Arc::clone(rustic))
+        .with_name("player")
+        .with_player(move |_, bus| {
+            backend_bus.lock().unwrap().replace(bus);
+            Ok(Box::new(FakeBackend::new(true)))
+        })
+        .unwrap()
+        .with_memory_queue()
+        .build();
+    rustic.add_player("player".into(), player);
+    let bus = bus.lock().unwrap().take();
+    bus.unwrap()
+}
+
+fn play(bus: &PlayerBus, uri: &str) {
+    bus.emit_event(PlayerEvent::TrackChanged(track(uri)))
+        .unwrap();
+    bus.emit_event(PlayerEvent::StateChanged(PlayerState::Play))
+        .unwrap();
+}
+
+async fn end_track(bus: &PlayerBus, reason: NextReason) {
+    bus.send_queue_msg(QueueCommand::Next(reason)).unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+}
+
+fn recorded_play(rustic: &Rustic, uri: &str) -> PlayRecord {
+    rustic
+        .history
+        .query(HistoryQuery::new())
+        .unwrap()
+        .into_iter()
+        .find(|play| play.track.uri == uri)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn finished_tracks_should_be_recorded_as_completed() {
+    let rustic = rustic();
+    let bus = player(&rustic);
+
+    play(&bus, "test:1");
+    end_track(&bus, NextReason::Finished).await;
+    play(&bus, "test:2");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert!(recorded_play(&rustic, "test:1").completed);
+}
+
+#[tokio::test]
+async fn skipped_and_failed_tracks_should_not_be_recorded_as_completed() {
+    let rustic = rustic();
+    let bus = player(&rustic);
+
+    play(&bus, "test:1");
+    end_track(&bus, NextReason::Skipped).await;
+    play(&bus, "test:2");
+    end_track(&bus, NextReason::Error).await;
+    play(&bus, "test:3");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert!(!recorded_play(&rustic, "test:1").completed);
+    assert!(!recorded_play(&rustic, "test:2").completed);
+}
@@ -0,0 +1,35 @@
+use futures::StreamExt;
+
+use rustic_core::offline::{self, OfflineEvent};
+use rustic_core::provider::InternalUri;
+
+use crate::common::{make_playable, rustic, track};
+
+mod common;
+
+#[tokio::test]
+async fn cached_tracks_should_be_available_and_served_from_the_cache() {
+    let rustic = rustic();
+    let track = track("test:1");
+    rustic.cache.store_track(&track, b"data").unwrap();
+
+    let stream_url = rustic.stream_url(&track).await.unwrap();
+
+    assert!(offline::is_track_available(&rustic, &track));
+    assert!(stream_url.starts_with("file://"));
+}
+
+#[tokio::test]
+async fn offline_events_should_be_delivered_to_every_subscriber() {
+    let rustic = rustic();
+    make_playable(&rustic, &[track("test:1")]);
+    let first = rustic.offline.events.stream();
+    let second = rustic.offline.events.stream();
+
+    offline::unpin(&rustic, InternalUri::Track("test:1".into())).unwrap();
+
+    let first: Vec<_> = first.take(1).collect().await;
+    let second: Vec<_> = second.take(1).collect().await;
+    assert!(matches!(&first[0], OfflineEvent::Removed(uri) if uri == "test:1"));
+    assert!(matches!(&second[0], OfflineEvent::Removed(uri) if uri == "test:1"));
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustic_core::player::composite::CompositeOutput;
+use rustic_core::player::{queue::MemoryQueueBuilder, CompositeBackend, PlayerBuilder, PlayerBus};
+use rustic_core::{Player, PlayerBackend, PlayerState, Rustic};
+
+use crate::common::{make_playable, rustic, track, BackendCall, FakeBackend};
+
+mod common;
+
+fn player(rustic: &Arc<Rustic>, name: &str, backend: &FakeBackend) -> Arc<Player> {
+    let backend = backend.clone();
+    PlayerBuilder::new(Arc::clone(rustic))
+        .with_name(name)
+        .with_player(|_, _| Ok(Box::new(backend)))
+        .unwrap()
+        .with_memory_queue()
+        .build()
+}
+
+async fn setup_source(rustic: &Arc<Rustic>, backend: &FakeBackend) -> Arc<Player> {
+    let tracks = vec![track("test:1"), track("test:2"), track("test:3")];
+    make_playable(rustic, &tracks);
+    let source = player(rustic, "source", backend);
+    source.queue.queue_multiple(&tracks).await.unwrap();
+    source.queue.select_item(1).await.unwrap();
+    source
+}
+
+#[tokio::test]
+async fn transfer_to_should_continue_playback_at_the_same_position() {
+    let rustic = rustic();
+    let source_backend = FakeBackend::playing(PlayerState::Play, Some(Duration::from_secs(30)));
+    let target_backend = FakeBackend::new(true);
+    let source = setup_source(&rustic, &source_backend).await;
+    let target = player(&rustic, "target", &target_backend);
+
+    source.transfer_to(&target, false).await.unwrap();
+
+    assert_eq!(
+        target_backend.wait_for_calls(4).await,
+        vec![
+            BackendCall::SetState(PlayerState::Stop),
+            BackendCall::SetTrack("test:2".into()),
+            BackendCall::SetState(PlayerState::Play),
+            BackendCall::Seek(Duration::from_secs(30)),
+        ]
+    );
+    let queue = target.get_queue().await.unwrap();
+    assert_eq!(queue.len(), 3);
+    assert!(queue[1].playing);
+    assert!(source.get_queue().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn transfer_to_should_not_seek_when_the_target_does_not_support_it() {
+    let rustic = rustic();
+    let source_backend = FakeBackend::playing(PlayerState::Pause, Some(Duration::from_secs(30)));
+    let target_backend = FakeBackend::new(false);
+    let source = setup_source(&rustic, &source_backend).await;
+    let target = player(&rustic, "target", &target_backend);
+
+    source.transfer_to(&target, true).await.unwrap();
+
+    assert_eq!(
+        target_backend.wait_for_calls(3).await,
+        vec![
+            BackendCall::SetState(PlayerState::Stop),
+            BackendCall::SetTrack("test:2".into()),
+            BackendCall::SetState(PlayerState::Pause),
+        ]
+    );
+    assert_eq!(source.get_queue().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn transfer_to_should_only_load_the_track_when_stopped() {
+    let rustic = rustic();
+    let source_backend = FakeBackend::playing(PlayerState::Stop, None);
+    let target_backend = FakeBackend::new(true);
+    let source = setup_source(&rustic, &source_backend).await;
+    let target = player(&rustic, "target", &target_backend);
+
+    source.transfer_to(&target, false).await.unwrap();
+
+    assert_eq!(
+        target_backend.wait_for_calls(2).await,
+        vec![
+            BackendCall::SetState(PlayerState::Stop),
+            BackendCall::SetTrack("test:2".into()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn composite_backend_should_keep_the_last_enabled_output() {
+    let backend = FakeBackend::playing(PlayerState::Play, None);
+    let output = CompositeOutput::new("output".into(), Box::new(backend.clone()), PlayerBus::new());
+    let composite = CompositeBackend::new(vec![output], PlayerBus::new()).unwrap();
+
+    assert!(composite.set_output_enabled("0", false).is_err());
+
+    assert!(composite.outputs()[0].enabled);
+    assert!(backend.calls().is_empty());
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use failure::{format_err, Error};
+use serde::Deserialize;
+
+use crate::scrobble::Scrobble;
+use crate::ScrobbleService;
+
+pub const DEFAULT_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+
+/// Last.fm accepts at most 50 scrobbles per request
+const MAX_SCROBBLES: usize = 50;
+
+/// Client for the Audioscrobbler 2.0 api used by Last.fm and compatible services like Libre.fm
+#[derive(Debug, Clone)]
+pub struct LastFmClient {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    api_secret: String,
+    session_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: u32,
+    message: String,
+}
+
+impl LastFmClient {
+    pub fn new(base_url: String, api_key: String, api_secret: String, session_key: String) -> Self {
+        LastFmClient {
+            client: reqwest::Client::new(),
+            base_url,
+            api_key,
+            api_secret,
+            session_key,
+        }
+    }
+
+    async fn call(&self, method: &str, mut params: BTreeMap<String, String>) -> Result<(), Error> {
+        params.insert("method".into(), method.into());
+        params.insert("api_key".into(), self.api_key.clone());
+        params.insert("sk".into(), self.session_key.clone());
+        let signature = sign(&params, &self.api_secret);
+        params.insert("api_sig".into(), signature);
+        params.insert("format".into(), "json".into());
+
+        let res = self
+            .client
+            .post(&self.base_url)
+            .form(&params)
+            .send()
+            .await?;
+        let status = res.status();
+        let body = res.text().await?;
+        if let Ok(error) = serde_json::from_str::<ErrorResponse>(&body) {
+            return Err(format_err!(
+                "Last.fm returned error {}: {}",
+                error.error,
+                error.message
+            ));
+        }
+        if !status.is_success() {
+            return Err(format_err!("Last.fm returned {}: {}", status, body));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ScrobbleService for LastFmClient {
+    async fn now_playing(&self, scrobble: &Scrobble) -> Result<(), Error> {
+        let mut params = BTreeMap::new();
+        params.insert("artist".into(), scrobble.artist.clone());
+        params.insert("track".into(), scrobble.title.clone());
+        if let Some(ref album) = scrobble.album {
+            params.insert("album".into(), album.clone());
+        }
+        if let Some(duration) = scrobble.duration {
+            params.insert("duration".into(), duration.to_string());
+        }
+
+        self.call("track.updateNowPlaying", params).await
+    }
+
+    fn batch_size(&self) -> usize {
+        MAX_SCROBBLES
+    }
+
+    async fn scrobble(&self, scrobbles: &[Scrobble]) -> Result<(), Error> {
+        let mut params = BTreeMap::new();
+        for (i, scrobble) in scrobbles.iter().enumerate() {
+            params.insert(format!("artist[{}]", i), scrobble.artist.clone());
+            params.insert(format!("track[{}]", i), scrobble.title.clone());
+            params.insert(format!("timestamp[{}]", i), scrobble.timestamp.to_string());
+            if let Some(ref album) = scrobble.album {
+                params.insert(format!("album[{}]", i), album.clone());
+            }
+            if let Some(duration) = scrobble.duration {
+                params.insert(format!("duration[{}]", i), duration.to_string());
+            }
+        }
+
+        self.call("track.scrobble", params).await
+    }
+}
+
+/// The signature is the md5 hash of all parameters ordered by name, followed by the shared secret
+fn sign(params: &BTreeMap<String, String>, secret: &str) -> String {
+    let mut signature = String::new();
+    for (key, value) in params {
+        signature.push_str(key);
+        signature.push_str(value);
+    }
+    signature.push_str(secret);
+
+    format!("{:x}", md5::compute(signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use mockito::{mock, Matcher};
+
+    use crate::scrobble::Scrobble;
+    use crate::ScrobbleService;
+
+    use super::{sign, LastFmClient};
+
+    #[test]
+    fn sign_should_hash_the_ordered_params_with_the_secret() {
+        let mut params = BTreeMap::new();
+        params.insert("method".to_string(), "auth.getSession".to_string());
+        params.insert("api_key".to_string(), "key".to_string());
+
+        let signature = sign(&params, "secret");
+
+        // md5("api_keykeymethodauth.getSessionsecret")
+        assert_eq!(signature, "22c8184cc52cd5d7a67f5a8b092ff8b6");
+    }
+
+    #[tokio::test]
+    async fn scrobble_should_post_the_indexed_params() -> Result<(), failure::Error> {
+        let m = mock("POST", "/2.0/")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("method".into(), "track.scrobble".into()),
+                Matcher::UrlEncoded("artist[0]".into(), "Test Artist".into()),
+                Matcher::UrlEncoded("timestamp[0]".into(), "1616925600".into()),
+                Matcher::UrlEncoded("sk".into(), "session".into()),
+            ]))
+            .with_status(200)
+            .with_body(r#"{"scrobbles":{"@attr":{"accepted":1,"ignored":0}}}"#)
+            .create();
+        let client = LastFmClient::new(
+            format!("{}/2.0/", mockito::server_url()),
+            "key".into(),
+            "secret".into(),
+            "session".into(),
+        );
+
+        client
+            .scrobble(&[Scrobble {
+                artist: "Test Artist".into(),
+                title: "Test Track".into(),
+                album: Some("Test Album".into()),
+                duration: Some(200),
+                timestamp: 1616925600,
+            }])
+            .await?;
+
+        m.assert();
+        Ok(())
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use failure::{format_err, Error};
+use log::{debug, error, warn};
+use once_cell::sync::OnceCell;
+use tokio::runtime::Runtime;
+
+use rustic_core::library::{MetaValue, PlayRecord};
+use rustic_core::Track;
+use rustic_extension_api::*;
+
+use crate::lastfm::LastFmClient;
+use crate::listenbrainz::ListenBrainzClient;
+use crate::scrobble::Scrobble;
+
+mod lastfm;
+mod listenbrainz;
+mod scrobble;
+
+/// Key of the extension storage entry which holds scrobbles that failed to submit
+const QUEUE_KEY: &str = "queue";
+/// Oldest scrobbles are dropped when more than this are waiting to be submitted
+const MAX_QUEUED_SCROBBLES: usize = 2000;
+
+/// Extensions are loaded as dynamic libraries which don't share the tokio runtime of the host,
+/// so the http requests have to be executed on a runtime owned by the library.
+///
+/// The runtime lives as long as the library, dropping it inside the async code of the host would panic.
+static EXECUTOR: OnceCell<Runtime> = OnceCell::new();
+
+fn executor() -> Result<&'static Runtime, Error> {
+    let executor = EXECUTOR.get_or_try_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("scrobbler")
+            .enable_all()
+            .build()
+    })?;
+
+    Ok(executor)
+}
+
+#[async_trait]
+pub(crate) trait ScrobbleService: std::fmt::Debug + Send + Sync {
+    async fn now_playing(&self, scrobble: &Scrobble) -> Result<(), Error>;
+
+    /// Maximum number of scrobbles accepted by a single call to scrobble
+    fn batch_size(&self) -> usize;
+
+    async fn scrobble(&self, scrobbles: &[Scrobble]) -> Result<(), Error>;
+}
+
+/// Submits listened tracks to ListenBrainz or a Last.fm compatible api
+///
+/// Configuration:
+/// * `protocol`: `listenbrainz` (default) or `lastfm`
+/// * `base_url`: overrides the api url of the protocol
+/// * `token`: user token for ListenBrainz
+/// * `api_key`, `api_secret`, `session_key`: credentials for Last.fm
+pub struct ScrobblerExtension {
+    service: Option<Arc<dyn ScrobbleService>>,
+    runtime: Option<ExtensionRuntime>,
+}
+
+impl ScrobblerExtension {
+    fn create_service(
+        config: &HashMap<String, ExtensionConfigValue>,
+    ) -> Result<Arc<dyn ScrobbleService>, Error> {
+        let get = |key: &str| {
+            config
+                .get(key)
+                .and_then(ExtensionConfigValue::string)
+                .ok_or_else(|| format_err!("Missing config value {}", key))
+        };
+        let protocol = config.get("protocol");
+        match protocol {
+            None => Self::listenbrainz(config, get("token")?),
+            Some(value) if value.is_string("listenbrainz") => {
+                Self::listenbrainz(config, get("token")?)
+            }
+            Some(value) if value.is_string("lastfm") => {
+                let base_url = get("base_url").unwrap_or_else(|_| lastfm::DEFAULT_URL.into());
+                let client = LastFmClient::new(
+                    base_url,
+                    get("api_key")?,
+                    get("api_secret")?,
+                    get("session_key")?,
+                );
+
+                Ok(Arc::new(client))
+            }
+            Some(value) => Err(format_err!(
+                "invalid protocol {:?}. Allowed values are: listenbrainz, lastfm",
+                value
+            )),
+        }
+    }
+
+    fn listenbrainz(
+        config: &HashMap<String, ExtensionConfigValue>,
+        token: String,
+    ) -> Result<Arc<dyn ScrobbleService>, Error> {
+        let base_url = config
+            .get("base_url")
+            .and_then(ExtensionConfigValue::string)
+            .unwrap_or_else(|| listenbrainz::DEFAULT_URL.into());
+
+        Ok(Arc::new(ListenBrainzClient::new(base_url, token)))
+    }
+
+    fn service(&self) -> Result<Arc<dyn ScrobbleService>, Error> {
+        self.service
+            .clone()
+            .ok_or_else(|| format_err!("Scrobbler is not configured"))
+    }
+
+    fn runtime(&self) -> Result<&ExtensionRuntime, Error> {
+        self.runtime
+            .as_ref()
+            .ok_or_else(|| format_err!("Scrobbler is not setup"))
+    }
+
+    async fn now_playing(&self, scrobble: Scrobble) -> Result<(), Error> {
+        let service = self.service()?;
+        executor()?
+            .spawn(async move { service.now_playing(&scrobble).await })
+            .await?
+    }
+
+    /// Returns the scrobbles which could not be submitted together with the reason
+    async fn submit(&self, scrobbles: Vec<Scrobble>) -> Result<(), (Error, Vec<Scrobble>)> {
+        let service = match self.service() {
+            Ok(service) => service,
+            Err(e) => return Err((e, scrobbles)),
+        };
+        let executor = match executor() {
+            Ok(executor) => executor,
+            Err(e) => return Err((e, scrobbles)),
+        };
+        let pending = scrobbles.clone();
+        executor
+            .spawn(async move { submit_batches(service.as_ref(), scrobbles).await })
+            .await
+            .unwrap_or_else(|e| Err((e.into(), pending)))
+    }
+
+    async fn read_queue(&self) -> Result<Vec<Scrobble>, Error> {
+        let queue = self.runtime()?.read_metadata(QUEUE_KEY).await?;
+        let queue = match queue.and_then(|value| value.string()) {
+            Some(queue) => serde_json::from_str(&queue)?,
+            None => Vec::new(),
+        };
+
+        Ok(queue)
+    }
+
+    async fn write_queue(&self, queue: &[Scrobble]) -> Result<(), Error> {
+        let queue = serde_json::to_string(queue)?;
+        self.runtime()?
+            .write_metadata(QUEUE_KEY, MetaValue::String(queue))
+            .await
+    }
+
+    /// Submits the given scrobbles together with all previously failed ones
+    ///
+    /// Keeps the scrobbles which were not submitted in the queue so they can be retried with the next scrobble.
+    async fn submit_with_queue(&self, scrobbles: Vec<Scrobble>) -> Result<(), Error> {
+        let mut queue = self.read_queue().await?;
+        let had_queue = !queue.is_empty();
+        queue.extend(scrobbles);
+        if queue.is_empty() {
+            return Ok(());
+        }
+        let count = queue.len();
+        match self.submit(queue).await {
+            Ok(()) => {
+                debug!("Submitted {} scrobbles", count);
+                if had_queue {
+                    self.write_queue(&[]).await?;
+                }
+            }
+            Err((e, mut unsent)) => {
+                warn!(
+                    "Submitting scrobbles failed, queueing {} of {} scrobbles: {:?}",
+                    unsent.len(),
+                    count,
+                    e
+                );
+                limit_queue(&mut unsent);
+                self.write_queue(&unsent).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Submits the scrobbles in batches the service accepts
+///
+/// Stops at the first failing batch and returns it together with all following scrobbles.
+async fn submit_batches(
+    service: &dyn ScrobbleService,
+    mut scrobbles: Vec<Scrobble>,
+) -> Result<(), (Error, Vec<Scrobble>)> {
+    while !scrobbles.is_empty() {
+        let batch_size = service.batch_size().min(scrobbles.len());
+        let rest = scrobbles.split_off(batch_size);
+        if let Err(e) = service.scrobble(&scrobbles).await {
+            scrobbles.extend(rest);
+            return Err((e, scrobbles));
+        }
+        scrobbles = rest;
+    }
+
+    Ok(())
+}
+
+/// Drops the oldest scrobbles so the queue doesn't grow without bounds while offline
+fn limit_queue(queue: &mut Vec<Scrobble>) {
+    if queue.len() > MAX_QUEUED_SCROBBLES {
+        let dropped = queue.len() - MAX_QUEUED_SCROBBLES;
+        warn!("Scrobble queue is full, dropping {} scrobbles", dropped);
+        queue.drain(..dropped);
+    }
+}
+
+impl std::fmt::Debug for ScrobblerExtension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScrobblerExtension")
+            .field("service", &self.service)
+            .finish()
+    }
+}
+
+impl ExtensionLibrary for ScrobblerExtension {
+    fn new(config: HashMap<String, ExtensionConfigValue>) -> Self {
+        let service = match ScrobblerExtension::create_service(&config) {
+            Ok(service) => Some(service),
+            Err(e) => {
+                error!("Scrobbler configuration is invalid: {}", e);
+                None
+            }
+        };
+
+        ScrobblerExtension {
+            service,
+            runtime: None,
+        }
+    }
+
+    fn metadata() -> ExtensionMetadata {
+        ExtensionMetadata {
+            id: String::from("scrobbler"),
+            name: String::from("Scrobbler"),
+            version: crate_version!(),
+            config: ExtensionConfigSchema::new()
+                .field(
+                    ExtensionConfigField::string("protocol", "Protocol")
+                        .default_value("listenbrainz")
+                        .allowed_values(vec!["listenbrainz", "lastfm"]),
+                )
+                .field(ExtensionConfigField::string("base_url", "Api Url"))
+                .field(ExtensionConfigField::string("token", "ListenBrainz Token"))
+                .field(ExtensionConfigField::string("api_key", "Last.fm Api Key"))
+                .field(ExtensionConfigField::string(
+                    "api_secret",
+                    "Last.fm Api Secret",
+                ))
+                .field(ExtensionConfigField::string(
+                    "session_key",
+                    "Last.fm Session Key",
+                )),
+        }
+    }
+}
+
+impl Extension for ScrobblerExtension {
+    fn setup(&mut self, runtime: &ExtensionRuntime) -> Result<(), Error> {
+        self.runtime = Some(runtime.clone());
+        Ok(())
+    }
+
+    fn update_config(&mut self, config: ExtensionConfig) -> Result<(), Error> {
+        self.service = Some(ScrobblerExtension::create_service(&config)?);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExtensionApi for ScrobblerExtension {
+    async fn on_enable(&self) -> Result<(), Error> {
+        // retry scrobbles which failed while the extension was disabled or rustic was offline
+        self.submit_with_queue(Vec::new()).await
+    }
+
+    async fn on_play_started(&self, _: String, track: Track) -> Result<(), Error> {
+        if let Some(scrobble) = Scrobble::new(&track, now()) {
+            // now playing notifications are not important enough to be queued
+            if let Err(e) = self.now_playing(scrobble).await {
+                warn!("Sending now playing notification failed: {:?}", e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_play_finished(&self, play: PlayRecord) -> Result<(), Error> {
+        if let Some(scrobble) = Scrobble::from_play(&play) {
+            self.submit_with_queue(vec![scrobble]).await?;
+        }
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+host_extension!(ScrobblerExtension);
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use failure::{format_err, Error};
+
+    use crate::scrobble::Scrobble;
+    use crate::{limit_queue, submit_batches, ScrobbleService, MAX_QUEUED_SCROBBLES};
+
+    /// Accepts batches of two scrobbles and fails while offline
+    #[derive(Debug, Default)]
+    struct FakeService {
+        offline_after: Mutex<Option<usize>>,
+        submitted: Mutex<Vec<Scrobble>>,
+    }
+
+    #[async_trait]
+    impl ScrobbleService for FakeService {
+        async fn now_playing(&self, _: &Scrobble) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn batch_size(&self) -> usize {
+            2
+        }
+
+        async fn scrobble(&self, scrobbles: &[Scrobble]) -> Result<(), Error> {
+            let mut offline_after = self.offline_after.lock().unwrap();
+            match offline_after.as_mut() {
+                Some(0) => return Err(format_err!("offline")),
+                Some(remaining) => *remaining -= 1,
+                None => {}
+            }
+            self.submitted.lock().unwrap().extend_from_slice(scrobbles);
+            Ok(())
+        }
+    }
+
+    fn scrobbles(count: u64) -> Vec<Scrobble> {
+        (0..count)
+            .map(|timestamp| Scrobble {
+                artist: "Test Artist".into(),
+                title: "Test Track".into(),
+                album: None,
+                duration: Some(200),
+                timestamp,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn submit_batches_should_return_only_the_unsent_scrobbles() {
+        let service = FakeService::default();
+        service.offline_after.lock().unwrap().replace(1);
+
+        let result = submit_batches(&service, scrobbles(5)).await;
+
+        let (_, unsent) = result.unwrap_err();
+        assert_eq!(unsent, scrobbles(5).split_off(2));
+        assert_eq!(*service.submitted.lock().unwrap(), scrobbles(2));
+    }
+
+    #[tokio::test]
+    async fn submit_batches_should_submit_the_queue_once_online_again() {
+        let service = FakeService::default();
+        service.offline_after.lock().unwrap().replace(0);
+        let (_, queue) = submit_batches(&service, scrobbles(3)).await.unwrap_err();
+
+        service.offline_after.lock().unwrap().take();
+        let result = submit_batches(&service, queue).await;
+
+        assert!(result.is_ok());
+        assert_eq!(*service.submitted.lock().unwrap(), scrobbles(3));
+    }
+
+    #[test]
+    fn limit_queue_should_drop_the_oldest_scrobbles() {
+        let mut queue = scrobbles(MAX_QUEUED_SCROBBLES as u64 + 2);
+
+        limit_queue(&mut queue);
+
+        assert_eq!(queue.len(), MAX_QUEUED_SCROBBLES);
+        assert_eq!(queue[0].timestamp, 2);
+    }
+}
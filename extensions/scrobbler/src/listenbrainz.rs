@@ -0,0 +1,183 @@
+use async_trait::async_trait;
+use failure::{format_err, Error};
+use serde::Serialize;
+
+use crate::scrobble::Scrobble;
+use crate::ScrobbleService;
+
+pub const DEFAULT_URL: &str = "https://api.listenbrainz.org";
+
+/// ListenBrainz limits the number of listens per request
+const MAX_LISTENS: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct ListenBrainzClient {
+    client: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ListenType {
+    Single,
+    PlayingNow,
+    Import,
+}
+
+#[derive(Debug, Serialize)]
+struct SubmitListens<'a> {
+    listen_type: ListenType,
+    payload: Vec<Listen<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Listen<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    listened_at: Option<u64>,
+    track_metadata: TrackMetadata<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct TrackMetadata<'a> {
+    artist_name: &'a str,
+    track_name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release_name: Option<&'a str>,
+    additional_info: AdditionalInfo,
+}
+
+#[derive(Debug, Serialize)]
+struct AdditionalInfo {
+    media_player: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+}
+
+impl<'a> Listen<'a> {
+    fn new(scrobble: &'a Scrobble, listened_at: Option<u64>) -> Self {
+        Listen {
+            listened_at,
+            track_metadata: TrackMetadata {
+                artist_name: &scrobble.artist,
+                track_name: &scrobble.title,
+                release_name: scrobble.album.as_deref(),
+                additional_info: AdditionalInfo {
+                    media_player: "Rustic",
+                    duration_ms: scrobble.duration.map(|duration| duration * 1000),
+                },
+            },
+        }
+    }
+}
+
+impl ListenBrainzClient {
+    pub fn new(base_url: String, token: String) -> Self {
+        ListenBrainzClient {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    async fn submit(&self, request: SubmitListens<'_>) -> Result<(), Error> {
+        let url = format!("{}/1/submit-listens", self.base_url);
+        let res = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Token {}", self.token))
+            .json(&request)
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(format_err!("ListenBrainz returned {}: {}", status, body));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ScrobbleService for ListenBrainzClient {
+    async fn now_playing(&self, scrobble: &Scrobble) -> Result<(), Error> {
+        self.submit(SubmitListens {
+            listen_type: ListenType::PlayingNow,
+            payload: vec![Listen::new(scrobble, None)],
+        })
+        .await
+    }
+
+    fn batch_size(&self) -> usize {
+        MAX_LISTENS
+    }
+
+    async fn scrobble(&self, scrobbles: &[Scrobble]) -> Result<(), Error> {
+        let listen_type = if scrobbles.len() == 1 {
+            ListenType::Single
+        } else {
+            ListenType::Import
+        };
+        let payload = scrobbles
+            .iter()
+            .map(|scrobble| Listen::new(scrobble, Some(scrobble.timestamp)))
+            .collect();
+        self.submit(SubmitListens {
+            listen_type,
+            payload,
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{mock, Matcher};
+
+    use crate::scrobble::Scrobble;
+    use crate::ScrobbleService;
+
+    use super::ListenBrainzClient;
+
+    fn scrobble() -> Scrobble {
+        Scrobble {
+            artist: "Test Artist".into(),
+            title: "Test Track".into(),
+            album: None,
+            duration: Some(200),
+            timestamp: 1616925600,
+        }
+    }
+
+    #[tokio::test]
+    async fn scrobble_should_submit_a_single_listen() -> Result<(), failure::Error> {
+        let m = mock("POST", "/1/submit-listens")
+            .match_header("authorization", "Token test-token")
+            .match_body(Matcher::PartialJsonString(
+                r#"{"listen_type":"single"}"#.into(),
+            ))
+            .with_status(200)
+            .with_body(r#"{"status":"ok"}"#)
+            .create();
+        let client = ListenBrainzClient::new(mockito::server_url(), "test-token".into());
+
+        client.scrobble(&[scrobble()]).await?;
+
+        m.assert();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn scrobble_should_fail_on_error_responses() {
+        let _m = mock("POST", "/1/submit-listens")
+            .match_header("authorization", "Token invalid-token")
+            .with_status(401)
+            .create();
+        let client = ListenBrainzClient::new(mockito::server_url(), "invalid-token".into());
+
+        let result = client.scrobble(&[scrobble()]).await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use rustic_core::library::PlayRecord;
+use rustic_core::Track;
+
+/// Tracks shorter than this are never scrobbled
+const MIN_DURATION: u64 = 30;
+/// Plays longer than this are always scrobbled, no matter the length of the track
+const MAX_LISTEN_THRESHOLD: u64 = 4 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scrobble {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<u64>,
+    /// Unix timestamp in seconds when the track started playing
+    pub timestamp: u64,
+}
+
+impl Scrobble {
+    /// Returns None for tracks without artist as they can't be scrobbled
+    pub fn new(track: &Track, timestamp: u64) -> Option<Scrobble> {
+        let artist = track.artist.as_ref()?;
+
+        Some(Scrobble {
+            artist: artist.name.clone(),
+            title: track.title.clone(),
+            album: track.album.as_ref().map(|album| album.title.clone()),
+            duration: track.duration,
+            timestamp,
+        })
+    }
+
+    /// Follows the common scrobbling rules
+    ///
+    /// The track has to be longer than 30 seconds and has to be listened to for half its duration or four minutes.
+    /// Tracks without duration have to be listened to for four minutes.
+    pub fn from_play(play: &PlayRecord) -> Option<Scrobble> {
+        let threshold = match play.track.duration {
+            Some(duration) if duration < MIN_DURATION => return None,
+            Some(duration) => (duration / 2).min(MAX_LISTEN_THRESHOLD),
+            None => MAX_LISTEN_THRESHOLD,
+        };
+        if play.listened < threshold {
+            return None;
+        }
+
+        Scrobble::new(&play.track, play.started_at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rustic_core::library::{Lyrics, PlayRecord};
+    use rustic_core::provider::ThumbnailState;
+    use rustic_core::{Artist, ProviderId, Rating, Track};
+
+    use super::Scrobble;
+
+    fn play(duration: Option<u64>, listened: u64) -> PlayRecord {
+        PlayRecord {
+            track: Track {
+                id: None,
+                title: "Test Track".into(),
+                artist_id: None,
+                artist: Some(Artist {
+                    id: None,
+                    name: "Test Artist".into(),
+                    uri: "test:artist".into(),
+                    image_url: None,
+                    meta: HashMap::new(),
+                    provider: ProviderId::INTERNAL,
+                    albums: Vec::new(),
+                    playlists: Vec::new(),
+                    description: None,
+                }),
+                album_id: None,
+                album: None,
+                provider: ProviderId::INTERNAL,
+                uri: "test:track".into(),
+                thumbnail: ThumbnailState::None,
+                duration,
+                meta: HashMap::new(),
+                explicit: None,
+                rating: Rating::None,
+                position: None,
+                share_url: None,
+                lyrics: Lyrics::None,
+                comments: None,
+                chapters: Vec::new(),
+            },
+            player: "default".into(),
+            started_at: 1,
+            listened,
+            completed: false,
+        }
+    }
+
+    #[test]
+    fn should_scrobble_after_half_the_track() {
+        assert!(Scrobble::from_play(&play(Some(200), 99)).is_none());
+        assert!(Scrobble::from_play(&play(Some(200), 100)).is_some());
+    }
+
+    #[test]
+    fn should_scrobble_long_tracks_after_four_minutes() {
+        assert!(Scrobble::from_play(&play(Some(3600), 240)).is_some());
+        assert!(Scrobble::from_play(&play(None, 239)).is_none());
+    }
+
+    #[test]
+    fn should_not_scrobble_short_tracks() {
+        assert!(Scrobble::from_play(&play(Some(20), 20)).is_none());
+    }
+}
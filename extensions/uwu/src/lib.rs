@@ -10,6 +10,23 @@ pub struct UwuExtension {
 }
 
 impl UwuExtension {
+    fn parse_level(config: &HashMap<String, ExtensionConfigValue>) -> OwoifyLevel {
+        match config.get("level") {
+            Some(value) if value.is_string("owo") => OwoifyLevel::Owo,
+            Some(value) if value.is_string("uwu") => OwoifyLevel::Uwu,
+            Some(value) if value.is_string("uvu") => OwoifyLevel::Uvu,
+            None => OwoifyLevel::Uwu,
+            Some(value) => {
+                // TODO: warn
+                println!(
+                    "invalid level value {:?}. Allowed values are: owo, uwu, uvu",
+                    value
+                );
+                OwoifyLevel::Uwu
+            }
+        }
+    }
+
     fn owoify_tracks(&self, tracks: &mut [Track]) {
         tracks.par_iter_mut().for_each(|track| {
             track.title = track.title.owoify(&self.level);
@@ -31,20 +48,7 @@ impl std::fmt::Debug for UwuExtension {
 
 impl ExtensionLibrary for UwuExtension {
     fn new(config: HashMap<String, ExtensionConfigValue>) -> Self {
-        let level = match config.get("level") {
-            Some(value) if value.is_string("owo") => OwoifyLevel::Owo,
-            Some(value) if value.is_string("uwu") => OwoifyLevel::Uwu,
-            Some(value) if value.is_string("uvu") => OwoifyLevel::Uvu,
-            None => OwoifyLevel::Uwu,
-            Some(value) => {
-                // TODO: warn
-                println!(
-                    "invalid level value {:?}. Allowed values are: owo, uwu, uvu",
-                    value
-                );
-                OwoifyLevel::Uwu
-            }
-        };
+        let level = UwuExtension::parse_level(&config);
         UwuExtension { level }
     }
 
@@ -53,11 +57,21 @@ impl ExtensionLibrary for UwuExtension {
             id: String::from("uwu"),
             name: String::from("UwU"),
             version: crate_version!(),
+            config: ExtensionConfigSchema::new().field(
+                ExtensionConfigField::string("level", "Level")
+                    .default_value("uwu")
+                    .allowed_values(vec!["owo", "uwu", "uvu"]),
+            ),
         }
     }
 }
 
-impl Extension for UwuExtension {}
+impl Extension for UwuExtension {
+    fn update_config(&mut self, config: ExtensionConfig) -> Result<(), failure::Error> {
+        self.level = UwuExtension::parse_level(&config);
+        Ok(())
+    }
+}
 
 #[async_trait::async_trait]
 impl ExtensionApi for UwuExtension {
@@ -91,6 +105,11 @@ impl ExtensionApi for UwuExtension {
         self.owoify_tracks(&mut playlist.tracks);
         Ok(playlist)
     }
+
+    async fn resolve_tracks(&self, mut tracks: Vec<Track>) -> Result<Vec<Track>, failure::Error> {
+        self.owoify_tracks(&mut tracks);
+        Ok(tracks)
+    }
 }
 
 host_extension!(UwuExtension);
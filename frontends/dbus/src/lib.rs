@@ -5,7 +5,8 @@ use zbus::{ConnectionBuilder, dbus_interface};
 use zbus::export::futures_util::StreamExt;
 use zbus::zvariant::{Value};
 
-use rustic_api::models::PlayerEventModel;
+use rustic_api::cursor::from_cursor;
+use rustic_api::models::{PlayerEventModel, RatingModel};
 use rustic_api::ApiClient;
 
 const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
@@ -17,6 +18,8 @@ pub async fn start(client: ApiClient) -> Result<(), Error> {
         .name("org.mpris.MediaPlayer2.rustic")?
         .serve_at(MPRIS_PATH, MprisPlayerIdentity)?
         .serve_at(MPRIS_PATH, player)?
+        .serve_at(MPRIS_PATH, RusticPlayers { client: client.clone() })?
+        .serve_at(MPRIS_PATH, RusticLibrary { client: client.clone() })?
         .build()
         .await?;
 
@@ -90,8 +93,10 @@ struct MprisPlayer {
 #[dbus_interface(name = "org.mpris.MediaPlayer2.Player")]
 impl MprisPlayer {
     #[dbus_interface(property)]
-    fn can_seek(&self) -> bool {
-        false
+    async fn can_seek(&self) -> bool {
+        let player = self.client.get_player(None).await.unwrap().unwrap();
+
+        player.capabilities.seek
     }
 
     #[dbus_interface(property)]
@@ -150,6 +155,9 @@ impl MprisPlayer {
             if let Some(album) = track.album {
                 metadata.insert("xesam:album", album.title.into());
             }
+            if let Some(rating) = to_user_rating(track.rating) {
+                metadata.insert("xesam:userRating", rating.into());
+            }
             if let Some(coverart) = track.coverart {
                 metadata.insert("mpris:artUrl", format!("http://127.0.0.1:8080{coverart}").into()); // TODO: get http frontend base url
             }
@@ -210,6 +218,71 @@ impl MprisPlayer {
     }
 }
 
+/// Rustic specific extensions which are not covered by MPRIS
+struct RusticPlayers {
+    client: ApiClient
+}
+
+#[dbus_interface(name = "io.github.rustic_music_player.Players")]
+impl RusticPlayers {
+    /// Returns the cursors and names of all players
+    async fn list_players(&self) -> zbus::fdo::Result<Vec<(String, String)>> {
+        let players = self.client.get_players().await.map_err(to_zbus_fdo_error)?;
+
+        Ok(players.into_iter().map(|player| (player.cursor, player.name)).collect())
+    }
+
+    async fn transfer_playback(&self, from: String, to: String, keep_playing: bool) -> zbus::fdo::Result<()> {
+        let from = from_cursor(&from).map_err(to_zbus_fdo_error)?;
+        let to = from_cursor(&to).map_err(to_zbus_fdo_error)?;
+        self.client.transfer_playback(&from, &to, keep_playing).await.map_err(to_zbus_fdo_error)
+    }
+}
+
+/// MPRIS only exposes the rating, this allows rating tracks over dbus
+struct RusticLibrary {
+    client: ApiClient
+}
+
+#[dbus_interface(name = "io.github.rustic_music_player.Library")]
+impl RusticLibrary {
+    /// Sets the rating of the track with the given mpris:trackid, uses the xesam:userRating range of 0.0 to 1.0
+    async fn set_rating(&self, track_id: String, rating: f64) -> zbus::fdo::Result<()> {
+        let rating = from_user_rating(rating)?;
+        self.client.set_rating(&track_id, rating).await.map_err(to_zbus_fdo_error)
+    }
+
+    /// Removes the rating of the track with the given mpris:trackid, unrated tracks have no xesam:userRating
+    async fn remove_rating(&self, track_id: String) -> zbus::fdo::Result<()> {
+        self.client.set_rating(&track_id, RatingModel::None).await.map_err(to_zbus_fdo_error)
+    }
+}
+
+/// Likes are mapped to the upper and lower bound, stars to fifths
+///
+/// Five stars and a like share the upper bound and are read back as like.
+fn to_user_rating(rating: RatingModel) -> Option<f64> {
+    match rating {
+        RatingModel::None => None,
+        RatingModel::Like => Some(1.0),
+        RatingModel::Dislike => Some(0.0),
+        RatingModel::Stars(stars) => Some((f64::from(stars) / 5.0).min(1.0)),
+    }
+}
+
+fn from_user_rating(rating: f64) -> zbus::fdo::Result<RatingModel> {
+    if !(0.0..=1.0).contains(&rating) {
+        return Err(zbus::fdo::Error::InvalidArgs("rating has to be between 0.0 and 1.0".to_string()));
+    }
+    let stars = (rating * 5.0).round() as u8;
+
+    Ok(match stars {
+        0 => RatingModel::Dislike,
+        5 => RatingModel::Like,
+        stars => RatingModel::Stars(stars),
+    })
+}
+
 fn to_zbus_fdo_error(err: Error) -> zbus::fdo::Error {
     zbus::fdo::Error::Failed(err.to_string())
 }
@@ -217,3 +290,39 @@ fn to_zbus_fdo_error(err: Error) -> zbus::fdo::Error {
 fn to_zbus_error(err: Error) -> zbus::Error {
     zbus::Error::FDO(Box::new(to_zbus_fdo_error(err)))
 }
+
+#[cfg(test)]
+mod tests {
+    use rustic_api::models::RatingModel;
+
+    use super::{from_user_rating, to_user_rating};
+
+    #[test]
+    fn user_ratings_should_round_trip() {
+        let ratings = vec![
+            RatingModel::Like,
+            RatingModel::Dislike,
+            RatingModel::Stars(1),
+            RatingModel::Stars(2),
+            RatingModel::Stars(3),
+            RatingModel::Stars(4),
+        ];
+
+        for rating in ratings {
+            let user_rating = to_user_rating(rating).unwrap();
+
+            assert_eq!(from_user_rating(user_rating).unwrap(), rating);
+        }
+    }
+
+    #[test]
+    fn unrated_tracks_should_have_no_user_rating() {
+        assert_eq!(to_user_rating(RatingModel::None), None);
+    }
+
+    #[test]
+    fn from_user_rating_should_reject_values_out_of_range() {
+        assert!(from_user_rating(1.5).is_err());
+        assert!(from_user_rating(-0.1).is_err());
+    }
+}
@@ -57,10 +57,14 @@ pub mod events {
 
     pub const ALBUM_ADDED: Selector<AlbumModel> = Selector::new("library.album-added");
     pub const ALBUM_REMOVED: Selector<String> = Selector::new("library.album-removed");
+    pub const ALBUM_UPDATED: Selector<AlbumModel> = Selector::new("library.album-updated");
     pub const TRACK_ADDED: Selector<TrackModel> = Selector::new("library.track-added");
     pub const TRACK_REMOVED: Selector<String> = Selector::new("library.track-removed");
+    pub const TRACK_UPDATED: Selector<TrackModel> = Selector::new("library.track-updated");
     pub const PLAYLIST_ADDED: Selector<PlaylistModel> = Selector::new("library.playlist-added");
     pub const PLAYLIST_REMOVED: Selector<String> = Selector::new("library.playlist-removed");
+    pub const PLAYLIST_UPDATED: Selector<PlaylistModel> = Selector::new("library.playlist-updated");
     pub const ARTIST_ADDED: Selector<ArtistModel> = Selector::new("library.artist-added");
     pub const ARTIST_REMOVED: Selector<String> = Selector::new("library.artist-removed");
+    pub const ARTIST_UPDATED: Selector<ArtistModel> = Selector::new("library.artist-updated");
 }
@@ -47,6 +47,16 @@ impl PartialDelegate for AlbumDelegate {
                 _ => {}
             }
             Some(future::ready(()).boxed())
+        } else if let Some(album) = cmd.get(commands::events::ALBUM_UPDATED) {
+            match &mut data.albums {
+                AsyncData::Resolved(ref mut albums) => {
+                    if let Some(index) = albums.iter().position(|a| a.cursor == album.cursor) {
+                        albums.set(index, Arc::new(album.clone()));
+                    }
+                }
+                _ => {}
+            }
+            Some(future::ready(()).boxed())
         } else if let Some(cursor) = cmd.get(commands::events::ALBUM_REMOVED) {
             match &mut data.albums {
                 AsyncData::Resolved(ref mut albums) => {
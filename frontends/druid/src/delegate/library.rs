@@ -20,11 +20,11 @@ impl StreamDelegate for LibraryDelegate {
     fn observe(&self, sink: ExtEventSink) -> BoxFuture<'static, ()> {
         self.client
             .observe_library()
-            .for_each(move |event| {
-                log::trace!("received library message {:?}", event);
+            .for_each(move |change| {
+                log::trace!("received library message {:?}", change);
                 let sink = sink.clone();
                 async move {
-                    match event {
+                    match change.event {
                         LibraryEventModel::AlbumAdded(model) => {
                             sink.submit_command(events::ALBUM_ADDED, model, Target::Auto)
                         }
@@ -49,6 +49,18 @@ impl StreamDelegate for LibraryDelegate {
                         LibraryEventModel::TrackRemoved(cursor) => {
                             sink.submit_command(events::TRACK_REMOVED, cursor, Target::Auto)
                         }
+                        LibraryEventModel::AlbumUpdated { album, .. } => {
+                            sink.submit_command(events::ALBUM_UPDATED, album, Target::Auto)
+                        }
+                        LibraryEventModel::ArtistUpdated { artist, .. } => {
+                            sink.submit_command(events::ARTIST_UPDATED, artist, Target::Auto)
+                        }
+                        LibraryEventModel::PlaylistUpdated { playlist, .. } => {
+                            sink.submit_command(events::PLAYLIST_UPDATED, playlist, Target::Auto)
+                        }
+                        LibraryEventModel::TrackUpdated { track, .. } => {
+                            sink.submit_command(events::TRACK_UPDATED, track, Target::Auto)
+                        }
                     }
                     .unwrap()
                 }
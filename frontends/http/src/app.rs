@@ -30,6 +30,15 @@ fn build_api(client: ApiClient, ws_server: Addr<SocketServer>) -> Scope {
         .service(controller::library::get_tracks)
         .service(controller::library::get_track)
         .service(controller::library::add_track)
+        .service(controller::library::get_changes)
+        .service(controller::library::get_history)
+        .service(controller::library::get_play_stats)
+        .service(controller::library::get_offline_mode)
+        .service(controller::library::set_offline_mode)
+        .service(controller::library::pin_offline)
+        .service(controller::library::unpin_offline)
+        .service(controller::library::update_metadata)
+        .service(controller::library::set_rating)
         .service(controller::library::get_artist_cover_art)
         .service(controller::library::get_album_cover_art)
         .service(controller::library::get_track_cover_art)
@@ -71,14 +80,26 @@ fn build_api(client: ApiClient, ws_server: Addr<SocketServer>) -> Scope {
         .service(controller::player::set_volume)
         .service(controller::player::default_set_repeat)
         .service(controller::player::set_repeat)
+        .service(controller::player::default_set_output_enabled)
+        .service(controller::player::set_output_enabled)
+        .service(controller::player::default_set_output_volume)
+        .service(controller::player::set_output_volume)
+        .service(controller::player::default_set_output_muted)
+        .service(controller::player::set_output_muted)
+        .service(controller::player::transfer_playback)
+        .service(controller::player::default_set_device)
+        .service(controller::player::set_device)
         .service(controller::extensions::get_extensions)
         .service(controller::extensions::enable_extension)
         .service(controller::extensions::disable_extension)
+        .service(controller::extensions::invoke_extension_action)
+        .service(controller::extensions::update_extension_config)
         .service(controller::providers::get_providers)
         .service(controller::providers::navigate)
         .service(controller::providers::get_available_providers)
         .service(controller::providers::provider_token_auth)
         .service(controller::providers::provider_basic_auth)
+        .service(controller::providers::provider_logout)
         .service(socket_service(ws_server))
 }
 
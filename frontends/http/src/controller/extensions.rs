@@ -1,6 +1,10 @@
-use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use std::collections::HashMap;
+
+use actix_web::{get, post, put, web, HttpResponse, Responder, Result};
 use serde::Deserialize;
 
+use rustic_api::models::ExtensionConfigValueModel;
+
 use crate::app::ApiClient;
 use super::failure_to_response;
 
@@ -36,6 +40,33 @@ pub async fn disable_extension(
     Ok(HttpResponse::NoContent())
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtensionActionQuery {
+    id: String,
+    action: String,
+}
+
+#[post("/extensions/{id}/actions/{action}")]
+pub async fn invoke_extension_action(
+    client: web::Data<ApiClient>,
+    params: web::Path<ExtensionActionQuery>,
+) -> Result<impl Responder> {
+    client.invoke_extension_action(&params.id, &params.action).await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent())
+}
+
+#[put("/extensions/{id}/config")]
+pub async fn update_extension_config(
+    client: web::Data<ApiClient>,
+    params: web::Path<ExtensionQuery>,
+    config: web::Json<HashMap<String, Option<ExtensionConfigValueModel>>>,
+) -> Result<impl Responder> {
+    client.update_extension_config(&params.id, config.into_inner()).await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent())
+}
+
 #[cfg(test)]
 mod test {
     use actix_web::dev::*;
@@ -65,7 +96,11 @@ mod test {
             name: String::new(),
             version: String::new(),
             enabled: true,
-            controls: ExtensionControlsModel::default()
+            controls: ExtensionControlsModel::default(),
+            config_schema: Vec::new(),
+            config: Default::default(),
+            disabled_reason: None,
+            metrics: Vec::new(),
         }];
         let mut client = TestApiClient::new();
         client.extensions = extensions.clone();
@@ -1,9 +1,15 @@
-use actix_web::{delete, error, get, post, web, HttpResponse, Responder, Result};
+use std::time::SystemTime;
+
+use actix_web::http::header::{ETag, EntityTag, IfModifiedSince, IfNoneMatch, LastModified};
+use actix_web::{
+    delete, error, get, post, put, web, HttpMessage, HttpRequest, HttpResponse, Responder, Result,
+};
 use futures::stream::StreamExt;
 use serde::Deserialize;
 use serde_qs::actix::QsQuery;
 
-use rustic_api::models::{CoverArtModel, ProviderTypeModel};
+use rustic_api::models::{CoverArtModel, MetadataUpdateModel, ProviderTypeModel, RatingModel};
+use rustic_core::provider::MetadataError;
 
 use crate::app::ApiClient;
 use rustic_api::cursor::Cursor;
@@ -15,11 +21,41 @@ pub struct EntityQuery {
     cursor: String,
 }
 
+#[derive(Deserialize)]
+pub struct ItemQuery {
+    kind: String,
+    cursor: String,
+}
+
+impl ItemQuery {
+    fn into_cursor(self) -> Result<Cursor> {
+        match self.kind.as_str() {
+            "tracks" => Ok(Cursor::Track(self.cursor)),
+            "albums" => Ok(Cursor::Album(self.cursor)),
+            "playlists" => Ok(Cursor::Playlist(self.cursor)),
+            _ => Err(error::ErrorNotFound("Not Found")),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct GetEntitiesQuery {
     providers: Option<Vec<ProviderTypeModel>>,
 }
 
+#[derive(Deserialize)]
+pub struct ChangesQuery {
+    #[serde(default)]
+    since: u64,
+}
+
+#[derive(Deserialize)]
+pub struct HistoryQuery {
+    since: Option<u64>,
+    until: Option<u64>,
+    limit: Option<usize>,
+}
+
 #[get("/library/albums/{cursor}")]
 pub async fn get_album(
     client: web::Data<ApiClient>,
@@ -167,11 +203,60 @@ pub async fn add_track(
     Ok(HttpResponse::NoContent())
 }
 
-fn get_cover_art(cover_art: Option<CoverArtModel>) -> Result<impl Responder> {
+#[derive(Deserialize)]
+pub struct CoverArtQuery {
+    /// Maximum width and height in pixels
+    size: Option<u32>,
+}
+
+/// If-Modified-Since is only evaluated when the request has no If-None-Match header
+fn is_not_modified(
+    req: &HttpRequest,
+    etag: Option<&EntityTag>,
+    last_modified: Option<SystemTime>,
+) -> bool {
+    if let Some(if_none_match) = req.get_header::<IfNoneMatch>() {
+        return match (etag, if_none_match) {
+            (Some(etag), IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+            (Some(_), IfNoneMatch::Any) => true,
+            _ => false,
+        };
+    }
+    match (last_modified, req.get_header::<IfModifiedSince>()) {
+        (Some(last_modified), Some(IfModifiedSince(since))) => {
+            let since: SystemTime = since.into();
+            last_modified <= since
+        }
+        _ => false,
+    }
+}
+
+fn get_cover_art(req: &HttpRequest, cover_art: Option<CoverArtModel>) -> Result<HttpResponse> {
     match cover_art {
-        Some(CoverArtModel::Data { data, mime_type }) => {
+        Some(CoverArtModel::Data {
+            data,
+            mime_type,
+            etag,
+            last_modified,
+        }) => {
+            let etag = etag.map(EntityTag::new_strong);
+            let not_modified = is_not_modified(req, etag.as_ref(), last_modified);
+            let mut response = if not_modified {
+                HttpResponse::NotModified()
+            } else {
+                HttpResponse::Ok()
+            };
+            if let Some(etag) = etag {
+                response.insert_header(ETag(etag));
+            }
+            if let Some(last_modified) = last_modified {
+                response.insert_header(LastModified(last_modified.into()));
+            }
+            if not_modified {
+                return Ok(response.finish());
+            }
             let stream = data.map(|d| Ok(d.into()));
-            let response = HttpResponse::Ok()
+            let response = response
                 .content_type(mime_type)
                 .streaming::<_, actix_web::Error>(stream);
             Ok(response)
@@ -186,33 +271,206 @@ fn get_cover_art(cover_art: Option<CoverArtModel>) -> Result<impl Responder> {
 
 #[get("/albums/{cursor}/coverart")]
 pub async fn get_album_cover_art(
+    req: HttpRequest,
     client: web::Data<ApiClient>,
     params: web::Path<EntityQuery>,
+    query: web::Query<CoverArtQuery>,
 ) -> Result<impl Responder> {
     let cover_art = client
-        .get_thumbnail(Cursor::Album(params.cursor.clone()))
+        .get_thumbnail(Cursor::Album(params.cursor.clone()), query.size)
         .await.map_err(failure_to_response)?;
-    get_cover_art(cover_art)
+    get_cover_art(&req, cover_art)
 }
 
 #[get("/artists/{cursor}/coverart")]
 pub async fn get_artist_cover_art(
+    req: HttpRequest,
     client: web::Data<ApiClient>,
     params: web::Path<EntityQuery>,
+    query: web::Query<CoverArtQuery>,
 ) -> Result<impl Responder> {
     let cover_art = client
-        .get_thumbnail(Cursor::Artist(params.cursor.clone()))
+        .get_thumbnail(Cursor::Artist(params.cursor.clone()), query.size)
         .await.map_err(failure_to_response)?;
-    get_cover_art(cover_art)
+    get_cover_art(&req, cover_art)
 }
 
 #[get("/tracks/{cursor}/coverart")]
 pub async fn get_track_cover_art(
+    req: HttpRequest,
     client: web::Data<ApiClient>,
     params: web::Path<EntityQuery>,
+    query: web::Query<CoverArtQuery>,
 ) -> Result<impl Responder> {
     let cover_art = client
-        .get_thumbnail(Cursor::Track(params.cursor.clone()))
+        .get_thumbnail(Cursor::Track(params.cursor.clone()), query.size)
         .await.map_err(failure_to_response)?;
-    get_cover_art(cover_art)
+    get_cover_art(&req, cover_art)
+}
+
+#[post("/library/{kind}/{cursor}/offline")]
+pub async fn pin_offline(
+    client: web::Data<ApiClient>,
+    params: web::Path<ItemQuery>,
+) -> Result<impl Responder> {
+    let cursor = params.into_inner().into_cursor()?;
+    client.pin_offline(cursor).await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent())
+}
+
+#[delete("/library/{kind}/{cursor}/offline")]
+pub async fn unpin_offline(
+    client: web::Data<ApiClient>,
+    params: web::Path<ItemQuery>,
+) -> Result<impl Responder> {
+    let cursor = params.into_inner().into_cursor()?;
+    client.unpin_offline(cursor).await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent())
+}
+
+#[put("/library/{kind}/{cursor}/metadata")]
+pub async fn update_metadata(
+    client: web::Data<ApiClient>,
+    params: web::Path<ItemQuery>,
+    update: web::Json<MetadataUpdateModel>,
+) -> Result<impl Responder> {
+    let cursor = params.into_inner().into_cursor()?;
+    client
+        .update_metadata(cursor, update.into_inner())
+        .await
+        .map_err(|err| match err.downcast::<MetadataError>() {
+            Ok(err @ MetadataError::UnsupportedItem) => error::ErrorBadRequest(err),
+            Ok(err) => error::ErrorForbidden(err),
+            Err(err) => failure_to_response(err).into(),
+        })?;
+
+    Ok(HttpResponse::NoContent())
+}
+
+#[put("/library/tracks/{cursor}/rating")]
+pub async fn set_rating(
+    client: web::Data<ApiClient>,
+    params: web::Path<EntityQuery>,
+    rating: web::Json<RatingModel>,
+) -> Result<impl Responder> {
+    client
+        .set_rating(&params.cursor, rating.into_inner())
+        .await
+        .map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent())
+}
+
+#[get("/library/offline")]
+pub async fn get_offline_mode(client: web::Data<ApiClient>) -> Result<impl Responder> {
+    let enabled = client.get_offline_mode().await.map_err(failure_to_response)?;
+
+    Ok(web::Json(enabled))
+}
+
+#[get("/library/changes")]
+pub async fn get_changes(
+    client: web::Data<ApiClient>,
+    params: web::Query<ChangesQuery>,
+) -> Result<impl Responder> {
+    let changes = client
+        .get_library_changes(params.since)
+        .await
+        .map_err(failure_to_response)?;
+
+    Ok(web::Json(changes))
+}
+
+#[get("/library/history")]
+pub async fn get_history(
+    client: web::Data<ApiClient>,
+    params: web::Query<HistoryQuery>,
+) -> Result<impl Responder> {
+    let plays = client
+        .get_history(params.since, params.limit)
+        .await
+        .map_err(failure_to_response)?;
+
+    Ok(web::Json(plays))
+}
+
+#[get("/library/stats")]
+pub async fn get_play_stats(
+    client: web::Data<ApiClient>,
+    params: web::Query<HistoryQuery>,
+) -> Result<impl Responder> {
+    let stats = client
+        .get_play_stats(params.since, params.until, params.limit)
+        .await
+        .map_err(failure_to_response)?;
+
+    Ok(web::Json(stats))
+}
+
+#[put("/library/offline")]
+pub async fn set_offline_mode(
+    client: web::Data<ApiClient>,
+    enabled: web::Json<bool>,
+) -> Result<impl Responder> {
+    client
+        .set_offline_mode(enabled.into_inner())
+        .await
+        .map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use actix_web::http::header::{EntityTag, IfModifiedSince, IfNoneMatch};
+    use actix_web::test;
+
+    use super::is_not_modified;
+
+    fn modified_at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn is_not_modified_should_match_etag() {
+        let etag = EntityTag::new_strong("abc".into());
+        let req = test::TestRequest::default()
+            .insert_header(IfNoneMatch::Items(vec![etag.clone()]))
+            .to_http_request();
+
+        assert!(is_not_modified(&req, Some(&etag), None));
+    }
+
+    #[test]
+    fn is_not_modified_should_compare_if_modified_since() {
+        let req = test::TestRequest::default()
+            .insert_header(IfModifiedSince(modified_at(2_000).into()))
+            .to_http_request();
+
+        assert!(is_not_modified(&req, None, Some(modified_at(1_000))));
+        assert!(is_not_modified(&req, None, Some(modified_at(2_000))));
+        assert!(!is_not_modified(&req, None, Some(modified_at(3_000))));
+    }
+
+    #[test]
+    fn is_not_modified_should_prefer_etag_over_if_modified_since() {
+        let etag = EntityTag::new_strong("abc".into());
+        let req = test::TestRequest::default()
+            .insert_header(IfNoneMatch::Items(vec![EntityTag::new_strong("def".into())]))
+            .insert_header(IfModifiedSince(modified_at(2_000).into()))
+            .to_http_request();
+
+        assert!(!is_not_modified(&req, Some(&etag), Some(modified_at(1_000))));
+    }
+
+    #[test]
+    fn is_not_modified_should_be_false_without_conditional_headers() {
+        let req = test::TestRequest::default().to_http_request();
+
+        assert!(!is_not_modified(&req, None, Some(modified_at(1_000))));
+    }
 }
@@ -148,3 +148,164 @@ pub async fn set_repeat(
 
     Ok(HttpResponse::NoContent().finish())
 }
+
+#[derive(Deserialize)]
+pub struct DefaultPlayerOutputQuery {
+    output: String,
+}
+
+#[derive(Deserialize)]
+pub struct PlayerOutputQuery {
+    player: String,
+    output: String,
+}
+
+#[post("/player/outputs/{output}/enabled")]
+pub async fn default_set_output_enabled(
+    client: web::Data<ApiClient>,
+    params: web::Path<DefaultPlayerOutputQuery>,
+    enabled: web::Json<bool>,
+) -> Result<impl Responder> {
+    let output_id = from_cursor(&params.output).map_err(failure_to_response)?;
+    client
+        .player_set_output_enabled(None, &output_id, enabled.into_inner())
+        .await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/players/{player}/outputs/{output}/enabled")]
+pub async fn set_output_enabled(
+    client: web::Data<ApiClient>,
+    params: web::Path<PlayerOutputQuery>,
+    enabled: web::Json<bool>,
+) -> Result<impl Responder> {
+    let player_id = from_cursor(&params.player).map_err(failure_to_response)?;
+    let output_id = from_cursor(&params.output).map_err(failure_to_response)?;
+    client
+        .player_set_output_enabled(Some(&player_id), &output_id, enabled.into_inner())
+        .await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/player/outputs/{output}/volume")]
+pub async fn default_set_output_volume(
+    client: web::Data<ApiClient>,
+    params: web::Path<DefaultPlayerOutputQuery>,
+    volume: web::Json<f32>,
+) -> Result<impl Responder> {
+    let output_id = from_cursor(&params.output).map_err(failure_to_response)?;
+    client
+        .player_set_output_volume(None, &output_id, volume.into_inner())
+        .await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/players/{player}/outputs/{output}/volume")]
+pub async fn set_output_volume(
+    client: web::Data<ApiClient>,
+    params: web::Path<PlayerOutputQuery>,
+    volume: web::Json<f32>,
+) -> Result<impl Responder> {
+    let player_id = from_cursor(&params.player).map_err(failure_to_response)?;
+    let output_id = from_cursor(&params.output).map_err(failure_to_response)?;
+    client
+        .player_set_output_volume(Some(&player_id), &output_id, volume.into_inner())
+        .await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/player/outputs/{output}/muted")]
+pub async fn default_set_output_muted(
+    client: web::Data<ApiClient>,
+    params: web::Path<DefaultPlayerOutputQuery>,
+    muted: web::Json<bool>,
+) -> Result<impl Responder> {
+    let output_id = from_cursor(&params.output).map_err(failure_to_response)?;
+    client
+        .player_set_output_muted(None, &output_id, muted.into_inner())
+        .await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/players/{player}/outputs/{output}/muted")]
+pub async fn set_output_muted(
+    client: web::Data<ApiClient>,
+    params: web::Path<PlayerOutputQuery>,
+    muted: web::Json<bool>,
+) -> Result<impl Responder> {
+    let player_id = from_cursor(&params.player).map_err(failure_to_response)?;
+    let output_id = from_cursor(&params.output).map_err(failure_to_response)?;
+    client
+        .player_set_output_muted(Some(&player_id), &output_id, muted.into_inner())
+        .await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Deserialize)]
+pub struct TransferQuery {
+    player: String,
+    target: String,
+}
+
+#[derive(Deserialize)]
+pub struct TransferOptions {
+    #[serde(default)]
+    keep_playing: bool,
+}
+
+#[post("/players/{player}/transfer/{target}")]
+pub async fn transfer_playback(
+    client: web::Data<ApiClient>,
+    params: web::Path<TransferQuery>,
+    options: web::Query<TransferOptions>,
+) -> Result<impl Responder> {
+    let player_id = from_cursor(&params.player).map_err(failure_to_response)?;
+    let target_id = from_cursor(&params.target).map_err(failure_to_response)?;
+    client
+        .transfer_playback(&player_id, &target_id, options.keep_playing)
+        .await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/player/device")]
+pub async fn default_set_device(
+    client: web::Data<ApiClient>,
+    device: web::Json<Option<String>>,
+) -> Result<impl Responder> {
+    let device_id = device
+        .into_inner()
+        .map(|cursor| from_cursor(&cursor))
+        .transpose()
+        .map_err(failure_to_response)?;
+    client
+        .player_set_device(None, device_id.as_deref())
+        .await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/players/{player}/device")]
+pub async fn set_device(
+    client: web::Data<ApiClient>,
+    params: web::Path<PlayerQuery>,
+    device: web::Json<Option<String>>,
+) -> Result<impl Responder> {
+    let player_id = from_cursor(&params.player).map_err(failure_to_response)?;
+    let device_id = device
+        .into_inner()
+        .map(|cursor| from_cursor(&cursor))
+        .transpose()
+        .map_err(failure_to_response)?;
+    client
+        .player_set_device(Some(&player_id), device_id.as_deref())
+        .await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,4 +1,4 @@
-use actix_web::{get, post, web, Responder, Result, HttpResponse};
+use actix_web::{delete, get, post, web, Responder, Result, HttpResponse};
 use serde::Deserialize;
 
 use rustic_api::models::{ProviderAuthModel, ProviderTypeModel};
@@ -16,6 +16,12 @@ pub struct ProviderParams {
     provider: ProviderTypeModel,
 }
 
+#[derive(Deserialize)]
+pub struct LogoutQuery {
+    #[serde(default)]
+    purge_library: bool,
+}
+
 #[get("/providers")]
 pub async fn get_providers(client: web::Data<ApiClient>) -> Result<impl Responder> {
     let providers = client.get_providers().await.map_err(failure_to_response)?;
@@ -30,7 +36,7 @@ pub async fn navigate(
     query: web::Query<NavigateQuery>,
 ) -> Result<impl Responder> {
     let folder = client
-        .navigate_provider(params.provider, &query.path)
+        .navigate_provider(params.into_inner().provider, &query.path)
         .await.map_err(failure_to_response)?;
 
     Ok(web::Json(folder))
@@ -50,7 +56,7 @@ pub async fn provider_basic_auth(
     client: web::Data<ApiClient>,
 ) -> Result<impl Responder> {
     client
-        .authenticate_provider(params.provider, body.into_inner())
+        .authenticate_provider(params.into_inner().provider, body.into_inner())
         .await.map_err(failure_to_response)?;
 
     Ok(HttpResponse::NoContent().finish())
@@ -63,10 +69,23 @@ pub async fn provider_token_auth(
     client: web::Data<ApiClient>,
 ) -> Result<impl Responder> {
     client
-        .authenticate_provider(params.provider, query.into_inner())
+        .authenticate_provider(params.into_inner().provider, query.into_inner())
         .await.map_err(failure_to_response)?;
 
     Ok(HttpResponse::Ok().body(
         "<html><body>You can close this window now<script>window.close()</script></body></html>",
     ))
 }
+
+#[delete("/providers/{provider}/auth")]
+pub async fn provider_logout(
+    params: web::Path<ProviderParams>,
+    query: web::Query<LogoutQuery>,
+    client: web::Data<ApiClient>,
+) -> Result<impl Responder> {
+    client
+        .logout_provider(params.into_inner().provider, query.purge_library)
+        .await.map_err(failure_to_response)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
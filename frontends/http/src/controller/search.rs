@@ -118,7 +118,10 @@ mod test {
     #[tokio::test]
     async fn search_should_perform_search_with_providers() {
         let mut client = TestApiClient::new();
-        let providers = vec![ProviderTypeModel::Soundcloud, ProviderTypeModel::Spotify];
+        let providers = vec![
+            ProviderTypeModel::from("soundcloud"),
+            ProviderTypeModel::from("spotify"),
+        ];
         client
             .expect_search()
             .called_once()
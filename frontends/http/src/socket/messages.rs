@@ -2,7 +2,9 @@ use actix::Recipient;
 use serde::Serialize;
 
 use rustic_api::models::{
-    AlbumModel, ArtistModel, LibraryEventModel, PlaylistModel, QueuedTrackModel, TrackModel,
+    AlbumModel, ArtistModel, LibraryChangeModel, LibraryEventModel, OfflineEventModel,
+    OfflineProgressModel, OutputDeviceModel, PlayerOutputModel, PlaylistModel,
+    ProviderStateEventModel, QueuedTrackModel, TrackModel,
 };
 
 #[derive(Message, Clone, Debug, Serialize)]
@@ -11,6 +13,8 @@ use rustic_api::models::{
 pub enum Message {
     PlayerMessage(PlayerMessage),
     LibraryMessage(LibraryMessage),
+    OfflineMessage(OfflineMessage),
+    ProviderMessage(ProviderMessage),
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -27,44 +31,136 @@ pub enum PlayerMessageData {
     CurrentlyPlayingChanged(Option<TrackModel>),
     QueueUpdated(Vec<QueuedTrackModel>),
     VolumeChanged(f32),
+    OutputsChanged(Vec<PlayerOutputModel>),
+    DevicesChanged(Vec<OutputDeviceModel>),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LibraryMessage {
+    /// Library revision created by this change
+    pub revision: u64,
+    #[serde(flatten)]
+    pub message: LibraryMessageData,
 }
 
 #[derive(Clone, Debug, Serialize)]
 #[serde(tag = "type", content = "payload", rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum LibraryMessage {
+pub enum LibraryMessageData {
     /// Emitted when a new track was added
     TrackAdded(TrackModel),
     /// Emitted when the track with the given cursor was removed
     TrackRemoved(String),
+    /// Emitted when an existing track was synced with new metadata
+    TrackUpdated {
+        track: TrackModel,
+        changed: Vec<String>,
+    },
     /// Emitted when a new album was added
     AlbumAdded(AlbumModel),
     /// Emitted when the album with the given cursor was removed
     AlbumRemoved(String),
+    /// Emitted when an existing album was synced with new metadata
+    AlbumUpdated {
+        album: AlbumModel,
+        changed: Vec<String>,
+    },
     /// Emitted when a new artist was added
     ArtistAdded(ArtistModel),
     /// Emitted when the artist with the given cursor was removed
     ArtistRemoved(String),
+    /// Emitted when an existing artist was synced with new metadata
+    ArtistUpdated {
+        artist: ArtistModel,
+        changed: Vec<String>,
+    },
     /// Emitted when a new playlist was added
     PlaylistAdded(PlaylistModel),
     /// Emitted when the playlist with the given cursor was removed
     PlaylistRemoved(String),
+    /// Emitted when an existing playlist was synced with new tracks or a new title
+    PlaylistUpdated {
+        playlist: PlaylistModel,
+        changed: Vec<String>,
+    },
+}
+
+impl From<LibraryChangeModel> for LibraryMessage {
+    fn from(change: LibraryChangeModel) -> Self {
+        LibraryMessage {
+            revision: change.revision,
+            message: change.event.into(),
+        }
+    }
 }
 
-impl From<LibraryEventModel> for LibraryMessage {
+impl From<LibraryEventModel> for LibraryMessageData {
     fn from(event: LibraryEventModel) -> Self {
         match event {
-            LibraryEventModel::TrackAdded(track) => LibraryMessage::TrackAdded(track),
-            LibraryEventModel::TrackRemoved(cursor) => LibraryMessage::TrackRemoved(cursor),
-            LibraryEventModel::AlbumAdded(album) => LibraryMessage::AlbumAdded(album),
-            LibraryEventModel::AlbumRemoved(cursor) => LibraryMessage::AlbumRemoved(cursor),
-            LibraryEventModel::ArtistAdded(artist) => LibraryMessage::ArtistAdded(artist),
-            LibraryEventModel::ArtistRemoved(cursor) => LibraryMessage::ArtistRemoved(cursor),
-            LibraryEventModel::PlaylistAdded(playlist) => LibraryMessage::PlaylistAdded(playlist),
-            LibraryEventModel::PlaylistRemoved(cursor) => LibraryMessage::PlaylistRemoved(cursor),
+            LibraryEventModel::TrackAdded(track) => LibraryMessageData::TrackAdded(track),
+            LibraryEventModel::TrackRemoved(cursor) => LibraryMessageData::TrackRemoved(cursor),
+            LibraryEventModel::TrackUpdated { track, changed } => {
+                LibraryMessageData::TrackUpdated { track, changed }
+            }
+            LibraryEventModel::AlbumAdded(album) => LibraryMessageData::AlbumAdded(album),
+            LibraryEventModel::AlbumRemoved(cursor) => LibraryMessageData::AlbumRemoved(cursor),
+            LibraryEventModel::AlbumUpdated { album, changed } => {
+                LibraryMessageData::AlbumUpdated { album, changed }
+            }
+            LibraryEventModel::ArtistAdded(artist) => LibraryMessageData::ArtistAdded(artist),
+            LibraryEventModel::ArtistRemoved(cursor) => LibraryMessageData::ArtistRemoved(cursor),
+            LibraryEventModel::ArtistUpdated { artist, changed } => {
+                LibraryMessageData::ArtistUpdated { artist, changed }
+            }
+            LibraryEventModel::PlaylistAdded(playlist) => {
+                LibraryMessageData::PlaylistAdded(playlist)
+            }
+            LibraryEventModel::PlaylistRemoved(cursor) => {
+                LibraryMessageData::PlaylistRemoved(cursor)
+            }
+            LibraryEventModel::PlaylistUpdated { playlist, changed } => {
+                LibraryMessageData::PlaylistUpdated { playlist, changed }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", content = "payload", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OfflineMessage {
+    /// Emitted while the tracks of a pinned item are downloading
+    OfflineProgress(OfflineProgressModel),
+    /// Emitted when the item with the given cursor is available offline
+    OfflineCompleted(String),
+    /// Emitted when downloading the item with the given cursor failed
+    OfflineFailed(String),
+    /// Emitted when the item with the given cursor is no longer available offline
+    OfflineRemoved(String),
+}
+
+impl From<OfflineEventModel> for OfflineMessage {
+    fn from(event: OfflineEventModel) -> Self {
+        match event {
+            OfflineEventModel::Progress(progress) => OfflineMessage::OfflineProgress(progress),
+            OfflineEventModel::Completed(cursor) => OfflineMessage::OfflineCompleted(cursor),
+            OfflineEventModel::Failed(cursor) => OfflineMessage::OfflineFailed(cursor),
+            OfflineEventModel::Removed(cursor) => OfflineMessage::OfflineRemoved(cursor),
         }
     }
 }
 
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", content = "payload", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProviderMessage {
+    /// Emitted when a provider was logged in or out, e.g. because its token expired
+    ProviderStateChanged(ProviderStateEventModel),
+}
+
+impl From<ProviderStateEventModel> for ProviderMessage {
+    fn from(event: ProviderStateEventModel) -> Self {
+        ProviderMessage::ProviderStateChanged(event)
+    }
+}
+
 #[derive(Message)]
 #[rtype(String)]
 pub struct Connect {
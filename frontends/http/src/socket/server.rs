@@ -50,6 +50,20 @@ impl Actor for SocketServer {
 
         ctx.add_message_stream(stream);
 
+        let stream = self
+            .client
+            .observe_offline()
+            .map(|event| messages::Message::OfflineMessage(event.into()));
+
+        ctx.add_message_stream(stream);
+
+        let stream = self
+            .client
+            .observe_providers()
+            .map(|event| messages::Message::ProviderMessage(event.into()));
+
+        ctx.add_message_stream(stream);
+
         let players = self.app.get_players();
         for (id, _) in players {
             let id2 = id.clone();
@@ -132,6 +146,20 @@ impl SocketServer {
                     player_cursor: to_cursor(&id),
                 }))
             }
+            PlayerEventModel::OutputsChanged(outputs) => {
+                let msg = messages::PlayerMessageData::OutputsChanged(outputs);
+                Some(messages::Message::PlayerMessage(messages::PlayerMessage {
+                    message: msg,
+                    player_cursor: to_cursor(&id),
+                }))
+            }
+            PlayerEventModel::DevicesChanged(devices) => {
+                let msg = messages::PlayerMessageData::DevicesChanged(devices);
+                Some(messages::Message::PlayerMessage(messages::PlayerMessage {
+                    message: msg,
+                    player_cursor: to_cursor(&id),
+                }))
+            }
             msg => {
                 log::warn!("unexpected msg {:?}", msg);
                 None
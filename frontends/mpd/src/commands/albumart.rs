@@ -9,16 +9,21 @@ use rustic_api::cursor::Cursor;
 use rustic_api::models::CoverArtModel;
 use crate::FutureExt;
 
+/// Sends the cover art of the given track in chunks
+///
+/// Extends the mpd protocol with an optional size in pixels: albumart "<uri>" <offset> [size]
 pub struct AlbumArtCommand {
     uri: String,
     offset: u32,
+    size: Option<u32>,
 }
 
 impl AlbumArtCommand {
-    pub fn new(uri: String, offset: u32) -> Self {
+    pub fn new(uri: String, offset: u32, size: Option<u32>) -> Self {
         Self {
             uri,
             offset,
+            size,
         }
     }
 }
@@ -32,9 +37,9 @@ pub struct AlbumArt {
 impl MpdCommand<AlbumArt> for AlbumArtCommand {
     fn handle(&self, _: Arc<Rustic>, client: ApiClient) -> BoxFuture<Result<AlbumArt, Error>> {
         async move {
-            let thumbnail = client.get_thumbnail(Cursor::Track(self.uri.clone())).await?;
+            let thumbnail = client.get_thumbnail(Cursor::Track(self.uri.clone()), self.size).await?;
             match thumbnail {
-                Some(CoverArtModel::Data { data, mime_type }) => {
+                Some(CoverArtModel::Data { data, mime_type, .. }) => {
                     let bytes: Vec<Vec<u8>> = data.collect().await;
                     let bytes: Vec<_> = bytes.into_iter().flatten().collect();
                     let total_bytes = bytes.len();
@@ -46,9 +51,7 @@ impl MpdCommand<AlbumArt> for AlbumArtCommand {
                         mime_type,
                     })
                 },
-                Some(CoverArtModel::Url(url)) => {
-                    todo!()
-                },
+                Some(CoverArtModel::Url(_)) => Err(failure::format_err!("Remote thumbnails are not supported")),
                 None => Err(failure::format_err!("Missing thumbnail"))
             }
         }.boxed()
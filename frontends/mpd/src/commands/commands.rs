@@ -31,14 +31,22 @@ impl CommandsCommand {
 }
 
 impl MpdCommand<Vec<Command>> for CommandsCommand {
-    fn handle(&self, _app: Arc<Rustic>, _client: ApiClient) -> BoxFuture<Result<Vec<Command>, Error>> {
+    fn handle(&self, _app: Arc<Rustic>, client: ApiClient) -> BoxFuture<Result<Vec<Command>, Error>> {
         async move {
-            Ok(vec![
+            let capabilities = client.get_player(None).await?
+                .map(|player| player.capabilities)
+                .unwrap_or_default();
+            let mut commands = vec![
                 Command::new("status"),
                 Command::new("currentsong"),
                 Command::new("commandlist"),
                 Command::new("plchanges"),
                 Command::new("outputs"),
+                Command::new("enableoutput"),
+                Command::new("disableoutput"),
+                Command::new("toggleoutput"),
+                Command::new("transferplayback"),
+                Command::new("sticker"),
                 Command::new("decoders"),
                 Command::new("idle"),
                 Command::new("noidle"),
@@ -57,12 +65,15 @@ impl MpdCommand<Vec<Command>> for CommandsCommand {
                 Command::new("find"),
                 Command::new("add"),
                 Command::new("addid"),
-                Command::new("volume"),
-                Command::new("setvol"),
                 Command::new("commands"),
                 Command::new("tagtypes"),
                 Command::new("albumart"),
-            ])
+            ];
+            if capabilities.volume {
+                commands.push(Command::new("volume"));
+                commands.push(Command::new("setvol"));
+            }
+            Ok(commands)
         }.boxed()
     }
 }
@@ -41,7 +41,7 @@ impl MpdCommand<ListInfoResponse> for ListInfoCommand {
         async move {
             match self.path {
                 None => {
-                    let explorer = Explorer::new(app.providers.to_vec());
+                    let explorer = Explorer::new(app.providers());
                     let folders = explorer
                         .items()
                         .await
@@ -56,7 +56,7 @@ impl MpdCommand<ListInfoResponse> for ListInfoCommand {
                     Ok((folders, playlists, vec![]))
                 }
                 Some(ref path) => {
-                    let mut explorer = Explorer::new(app.providers.to_vec());
+                    let mut explorer = Explorer::new(app.providers());
                     explorer.navigate_absolute(path);
                     let path = explorer.path();
                     let folder = explorer.items().await.unwrap();
@@ -21,10 +21,13 @@ mod play;
 mod previous;
 mod set_volume;
 mod status;
+mod sticker;
 mod stop;
 mod tagtypes;
 mod playlist_info;
 mod add_track;
+mod toggle_output;
+mod transfer_playback;
 mod toggle_pause;
 mod clear_queue;
 
@@ -45,11 +48,14 @@ pub use self::play::PlayCommand;
 pub use self::previous::PreviousCommand;
 pub use self::set_volume::SetVolumeCommand;
 pub use self::status::StatusCommand;
+pub use self::sticker::{NoSuchSticker, StickerAction, StickerCommand};
 pub use self::stop::StopCommand;
 pub use self::tagtypes::TagTypesCommand;
 pub use self::playlist_info::PlaylistInfoCommand;
 pub use self::add_track::AddTrackCommand;
+pub use self::toggle_output::ToggleOutputCommand;
 pub use self::toggle_pause::TogglePauseCommand;
+pub use self::transfer_playback::TransferPlaybackCommand;
 pub use self::clear_queue::ClearQueueCommand;
 
 pub trait MpdCommand<T> {
@@ -26,13 +26,28 @@ impl OutputsCommand {
 }
 
 impl MpdCommand<Vec<OutputEntry>> for OutputsCommand {
-    fn handle(&self, _: Arc<Rustic>, _client: ApiClient) -> BoxFuture<Result<Vec<OutputEntry>, Error>> {
+    fn handle(&self, _: Arc<Rustic>, client: ApiClient) -> BoxFuture<Result<Vec<OutputEntry>, Error>> {
         async move {
-            Ok(vec![OutputEntry {
-                id: 0,
-                name: String::from("Default"),
-                enabled: true,
-            }])
+            let player = client.get_player(None).await?
+                .ok_or(failure::format_err!("Missing default player"))?;
+            if player.outputs.is_empty() {
+                return Ok(vec![OutputEntry {
+                    id: 0,
+                    name: String::from("Default"),
+                    enabled: true,
+                }]);
+            }
+            let outputs = player.outputs
+                .into_iter()
+                .enumerate()
+                .map(|(id, output)| OutputEntry {
+                    id: id as i64,
+                    name: output.name,
+                    enabled: output.enabled,
+                })
+                .collect();
+
+            Ok(outputs)
         }.boxed()
     }
 }
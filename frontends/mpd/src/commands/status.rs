@@ -17,7 +17,8 @@ pub struct AudioFormat {
 
 #[derive(Debug, Serialize)]
 pub struct StatusResponse {
-    volume: u32,
+    /// -1 when the player has no volume control
+    volume: i32,
     repeat: bool,
     random: bool,
     single: bool,
@@ -57,7 +58,11 @@ impl MpdCommand<StatusResponse> for StatusCommand {
             let queue = client.get_queue(None).await?;
 
             Ok(StatusResponse {
-                volume: (status.volume * 100f32) as u32,
+                volume: if status.capabilities.volume {
+                    (status.volume * 100f32) as i32
+                } else {
+                    -1
+                },
                 repeat: status.repeat == RepeatModeModel::All,
                 single: status.repeat == RepeatModeModel::Single,
                 random: false,
@@ -0,0 +1,106 @@
+use crate::commands::MpdCommand;
+use failure::Error;
+use rustic_core::Rustic;
+use std::sync::Arc;
+use futures::future::{BoxFuture, FutureExt};
+use rustic_api::ApiClient;
+use rustic_api::models::{AggregatedTrack, RatingModel};
+
+const RATING_STICKER: &str = "rating";
+const LIKE_STICKER: &str = "like";
+
+/// The requested sticker is not set, clients get an ACK instead of a closed connection
+#[derive(Debug, Fail)]
+#[fail(display = "no such sticker")]
+pub struct NoSuchSticker;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StickerAction {
+    Get(String),
+    Set(String, u8),
+    /// Deletes the named sticker or all stickers
+    Delete(Option<String>),
+    List,
+}
+
+/// Maps the song stickers used by mpd clients to track ratings
+///
+/// `rating` is a value between 0 and 10 and maps to stars, `like` is 0 (dislike), 1 (neutral) or 2 (like).
+/// Only song stickers are supported: sticker get|set|delete|list song "<uri>" [name] [value]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StickerCommand {
+    uri: String,
+    action: StickerAction,
+}
+
+impl StickerCommand {
+    pub fn new(uri: String, action: StickerAction) -> StickerCommand {
+        StickerCommand { uri, action }
+    }
+}
+
+impl MpdCommand<Option<String>> for StickerCommand {
+    /// Returns the response lines or None when the requested sticker doesn't exist
+    fn handle(&self, _: Arc<Rustic>, client: ApiClient) -> BoxFuture<Result<Option<String>, Error>> {
+        async move {
+            let rating = match client.get_track(&self.uri).await? {
+                Some(AggregatedTrack::Single(track)) => track.rating,
+                Some(AggregatedTrack::Multi(mut collection)) if !collection.entries.is_empty() => {
+                    collection.entries.remove(0).rating
+                }
+                _ => return Err(format_err!("No such song")),
+            };
+            let stickers = to_stickers(rating);
+            match &self.action {
+                StickerAction::Get(name) => Ok(stickers
+                    .into_iter()
+                    .find(|(sticker, _)| *sticker == name.as_str())
+                    .map(|(name, value)| format!("sticker: {}={}\n", name, value))),
+                StickerAction::List => Ok(Some(
+                    stickers
+                        .into_iter()
+                        .map(|(name, value)| format!("sticker: {}={}\n", name, value))
+                        .collect(),
+                )),
+                StickerAction::Set(name, value) => {
+                    let rating = from_sticker(name, *value)
+                        .ok_or(format_err!("Unsupported sticker {}", name))?;
+                    client.set_rating(&self.uri, rating).await?;
+
+                    Ok(Some(String::new()))
+                }
+                StickerAction::Delete(Some(name))
+                    if !stickers.iter().any(|(sticker, _)| *sticker == name.as_str()) =>
+                {
+                    Ok(None)
+                }
+                // Both stickers are backed by the same rating
+                StickerAction::Delete(_) => {
+                    client.set_rating(&self.uri, RatingModel::None).await?;
+
+                    Ok(Some(String::new()))
+                }
+            }
+        }.boxed()
+    }
+}
+
+fn to_stickers(rating: RatingModel) -> Vec<(&'static str, u8)> {
+    match rating {
+        RatingModel::None => vec![],
+        RatingModel::Like => vec![(LIKE_STICKER, 2)],
+        RatingModel::Dislike => vec![(LIKE_STICKER, 0)],
+        RatingModel::Stars(stars) => vec![(RATING_STICKER, stars.saturating_mul(2).min(10))],
+    }
+}
+
+fn from_sticker(name: &str, value: u8) -> Option<RatingModel> {
+    match (name, value) {
+        (RATING_STICKER, 0) => Some(RatingModel::None),
+        (RATING_STICKER, value) => Some(RatingModel::Stars((value.min(10) + 1) / 2)),
+        (LIKE_STICKER, 0) => Some(RatingModel::Dislike),
+        (LIKE_STICKER, 1) => Some(RatingModel::None),
+        (LIKE_STICKER, _) => Some(RatingModel::Like),
+        _ => None,
+    }
+}
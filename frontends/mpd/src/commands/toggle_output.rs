@@ -0,0 +1,38 @@
+use crate::commands::MpdCommand;
+use failure::Error;
+use rustic_core::Rustic;
+use std::sync::Arc;
+use futures::future::{BoxFuture, FutureExt};
+use rustic_api::ApiClient;
+use rustic_api::cursor::from_cursor;
+
+/// Handles enableoutput, disableoutput and toggleoutput
+///
+/// The output id is the index in the output list of the default player
+pub struct ToggleOutputCommand {
+    id: usize,
+    enabled: Option<bool>,
+}
+
+impl ToggleOutputCommand {
+    pub fn new(id: usize, enabled: Option<bool>) -> ToggleOutputCommand {
+        ToggleOutputCommand { id, enabled }
+    }
+}
+
+impl MpdCommand<()> for ToggleOutputCommand {
+    fn handle(&self, _: Arc<Rustic>, client: ApiClient) -> BoxFuture<Result<(), Error>> {
+        async move {
+            let player = client.get_player(None).await?
+                .ok_or(failure::format_err!("Missing default player"))?;
+            let output = player.outputs.get(self.id)
+                .ok_or(failure::format_err!("No such audio output"))?;
+            let output_id = from_cursor(&output.cursor)?;
+            let enabled = self.enabled.unwrap_or(!output.enabled);
+
+            client.player_set_output_enabled(None, &output_id, enabled).await?;
+
+            Ok(())
+        }.boxed()
+    }
+}
@@ -0,0 +1,40 @@
+use crate::commands::MpdCommand;
+use failure::Error;
+use rustic_core::Rustic;
+use std::sync::Arc;
+use futures::future::{BoxFuture, FutureExt};
+use rustic_api::ApiClient;
+use rustic_api::cursor::from_cursor;
+
+/// Moves playback of the default player to the player with the given name
+///
+/// Not part of the mpd protocol: transferplayback "<player>" ["keep"]
+pub struct TransferPlaybackCommand {
+    target: String,
+    keep_playing: bool,
+}
+
+impl TransferPlaybackCommand {
+    pub fn new(target: String, keep_playing: bool) -> TransferPlaybackCommand {
+        TransferPlaybackCommand { target, keep_playing }
+    }
+}
+
+impl MpdCommand<()> for TransferPlaybackCommand {
+    fn handle(&self, _: Arc<Rustic>, client: ApiClient) -> BoxFuture<Result<(), Error>> {
+        async move {
+            let source = client.get_player(None).await?
+                .ok_or(failure::format_err!("Missing default player"))?;
+            let target = client.get_players().await?
+                .into_iter()
+                .find(|player| player.name == self.target)
+                .ok_or(failure::format_err!("Unknown player {}", &self.target))?;
+            let source = from_cursor(&source.cursor)?;
+            let target = from_cursor(&target.cursor)?;
+
+            client.transfer_playback(&source, &target, self.keep_playing).await?;
+
+            Ok(())
+        }.boxed()
+    }
+}
@@ -2,6 +2,7 @@
 extern crate failure;
 
 mod commands;
+mod protocol;
 mod song;
 pub(crate) mod client_ext;
 
@@ -17,6 +18,7 @@ use tokio::net::{TcpListener, TcpStream};
 use tokio::io::{BufReader, AsyncWriteExt, AsyncBufReadExt};
 
 use crate::commands::MpdCommand;
+use crate::protocol::{Command, Request};
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct MpdConfig {
@@ -80,77 +82,105 @@ async fn handle_line(reader: &mut BufReader<TcpStream>, app: &Arc<Rustic>, clien
         return Ok(None);
     }
     log::trace!("> {:?}", line);
-    let cmd: mpd_protocol::Request = if line
+    let cmd: Request = if line
         == "command_list_ok_begin" || line == "command_list_begin"
     {
         let mut current = String::new();
         reader.read_line(&mut current).await?;
         log::trace!("> {:?}", &current);
-        let mut cmds: Vec<mpd_protocol::Command> = vec![];
+        let mut cmds: Vec<Command> = vec![];
         while current.trim() != "command_list_end" {
-            if let Ok((_, cmd)) = mpd_protocol::parse_command(&current.trim()) {
+            if let Ok(cmd) = protocol::parse_command(&current.trim()) {
                 cmds.push(cmd)
             }
             current.clear();
             reader.read_line(&mut current).await?;
             log::trace!("> {:?}", &current);
         }
-        mpd_protocol::Request::CommandList(cmds, line == "command_list_ok_begin")
+        Request::CommandList(cmds, line == "command_list_ok_begin")
     } else {
-        parse_single(&line)?
+        Request::Command(protocol::parse_command(&line)?)
     };
 
     match cmd {
-        mpd_protocol::Request::Command(mpd_protocol::Command::Idle(_)) => Ok(Some(())),
-        mpd_protocol::Request::Command(mpd_protocol::Command::Close) => Ok(None),
-        mpd_protocol::Request::Command(mpd_protocol::Command::AlbumArt(uri, offset)) => {
-            let album_art = commands::AlbumArtCommand::new(uri, offset)
-                .handle(app.clone(), client.clone())
-                .await?;
-
-            let byte_count = album_art.bytes.len();
-            let bytes = album_art.bytes.as_slice();
-            let writer = reader.get_mut();
-            let result = format!("size: {}\ntype: {}\nbinary: {}\n", album_art.total_size, album_art.mime_type, byte_count);
-            writer.write_all(result.as_bytes()).await?;
-            writer.write_all(bytes).await?;
-            writer.write_all("\nOK\n".as_bytes()).await?;
-
-            Ok(Some(()))
-        },
-        mpd_protocol::Request::Command(cmd) => {
-            let mut result = handle_mpd_command(cmd, app.clone(), client.clone()).await?;
-            result += "OK\n";
-            log::trace!("< {:?}", &result);
-            reader.get_mut().write_all(result.as_bytes()).await?;
+        Request::Command(Command::Mpd(mpd_protocol::Command::Idle(_))) => Ok(Some(())),
+        Request::Command(Command::Mpd(mpd_protocol::Command::Close)) => Ok(None),
+        Request::Command(cmd) => {
+            let result = match handle_command(cmd, app.clone(), client.clone()).await {
+                Ok(mut result) => {
+                    result.extend_from_slice(b"OK\n");
+                    result
+                }
+                Err(err) => ack(err, 0)?,
+            };
+            log::trace!("< {:?}", String::from_utf8_lossy(&result));
+            reader.get_mut().write_all(&result).await?;
 
             Ok(Some(()))
         }
-        mpd_protocol::Request::CommandList(commands, list_ok) => {
-            let mut result = String::new();
-            for command in commands {
-                result += handle_mpd_command(command, app.clone(), client.clone()).await?.as_str();
+        Request::CommandList(commands, list_ok) => {
+            let mut result = Vec::new();
+            let mut failed = false;
+            for (index, command) in commands.into_iter().enumerate() {
+                match handle_command(command, app.clone(), client.clone()).await {
+                    Ok(response) => result.extend(response),
+                    // the remaining commands are skipped like in mpd
+                    Err(err) => {
+                        result.extend(ack(err, index)?);
+                        failed = true;
+                        break;
+                    }
+                }
                 if list_ok {
-                    result += "list_OK\n";
+                    result.extend_from_slice(b"list_OK\n");
                 }
             }
-            result += "OK\n";
-            log::trace!("< {:?}", &result);
-            reader.get_mut().write_all(result.as_bytes()).await?;
+            if !failed {
+                result.extend_from_slice(b"OK\n");
+            }
+            log::trace!("< {:?}", String::from_utf8_lossy(&result));
+            reader.get_mut().write_all(&result).await?;
 
             Ok(Some(()))
         }
     }
 }
 
-fn parse_single(line: &str) -> Result<mpd_protocol::Request, failure::Error> {
-    match mpd_protocol::parse_command(line) {
-        Ok((_, command)) => {
-            Ok(mpd_protocol::Request::Command(command))
-        }
-        Err(err) => {
-            failure::bail!("MPD Parse error: {}", err)
+/// The response of the command without the trailing OK, album art responses contain binary data
+async fn handle_command(cmd: Command, app: Arc<Rustic>, client: ApiClient) -> Result<Vec<u8>, failure::Error> {
+    match cmd {
+        Command::Mpd(cmd) => handle_mpd_command(cmd, app, client)
+            .await
+            .map(String::into_bytes),
+        Command::AlbumArt(cmd) => {
+            let album_art = cmd.handle(app, client).await?;
+            let mut result = format!("size: {}\ntype: {}\nbinary: {}\n", album_art.total_size, album_art.mime_type, album_art.bytes.len())
+                .into_bytes();
+            result.extend(album_art.bytes);
+            result.push(b'\n');
+
+            Ok(result)
         }
+        Command::Sticker(cmd) => match cmd.handle(app, client).await? {
+            Some(stickers) => Ok(stickers.into_bytes()),
+            None => Err(commands::NoSuchSticker.into()),
+        },
+        Command::ToggleOutput(cmd) => cmd
+            .handle(app, client)
+            .await
+            .map(|_| Vec::new()),
+        Command::TransferPlayback(cmd) => cmd
+            .handle(app, client)
+            .await
+            .map(|_| Vec::new()),
+    }
+}
+
+/// Answers errors clients can handle with an ACK, all other errors close the connection
+fn ack(err: failure::Error, index: usize) -> Result<Vec<u8>, failure::Error> {
+    match err.downcast::<commands::NoSuchSticker>() {
+        Ok(_) => Ok(format!("ACK [50@{}] {{sticker}} no such sticker\n", index).into_bytes()),
+        Err(err) => Err(err),
     }
 }
 
@@ -0,0 +1,211 @@
+use crate::commands::{
+    AlbumArtCommand, StickerAction, StickerCommand, ToggleOutputCommand, TransferPlaybackCommand,
+};
+
+/// A request line, either one of the commands mpd_protocol knows about or one of our extensions
+///
+/// Every line is parsed through [parse_command], this way the extensions also work inside command lists.
+pub enum Command {
+    Mpd(mpd_protocol::Command),
+    AlbumArt(AlbumArtCommand),
+    Sticker(StickerCommand),
+    ToggleOutput(ToggleOutputCommand),
+    TransferPlayback(TransferPlaybackCommand),
+}
+
+pub enum Request {
+    Command(Command),
+    CommandList(Vec<Command>, bool),
+}
+
+pub fn parse_command(line: &str) -> Result<Command, failure::Error> {
+    let args = split_args(line);
+    let name = args.first().map(String::as_str).unwrap_or_default();
+    let command = match name {
+        "albumart" => parse_album_art(&args),
+        "enableoutput" => parse_output_command(&args, Some(true)),
+        "disableoutput" => parse_output_command(&args, Some(false)),
+        "toggleoutput" => parse_output_command(&args, None),
+        "transferplayback" => parse_transfer_playback(&args),
+        "sticker" => parse_sticker(&args),
+        _ => None,
+    };
+    if let Some(command) = command {
+        return Ok(command);
+    }
+    match mpd_protocol::parse_command(line) {
+        Ok((_, command)) => Ok(Command::Mpd(command)),
+        Err(err) => failure::bail!("MPD Parse error: {}", err),
+    }
+}
+
+/// albumart "<uri>" <offset> [size]
+fn parse_album_art(args: &[String]) -> Option<Command> {
+    let (uri, offset, size) = match args {
+        [_, uri, offset] => (uri, offset, None),
+        [_, uri, offset, size] => (uri, offset, Some(size.parse().ok()?)),
+        _ => return None,
+    };
+    let offset = offset.parse().ok()?;
+
+    Some(Command::AlbumArt(AlbumArtCommand::new(
+        uri.clone(),
+        offset,
+        size,
+    )))
+}
+
+fn parse_output_command(args: &[String], enabled: Option<bool>) -> Option<Command> {
+    match args {
+        [_, id] => {
+            let id = id.parse().ok()?;
+            Some(Command::ToggleOutput(ToggleOutputCommand::new(id, enabled)))
+        }
+        _ => None,
+    }
+}
+
+/// transferplayback "<player>" ["keep"]
+fn parse_transfer_playback(args: &[String]) -> Option<Command> {
+    let (target, keep_playing) = match args {
+        [_, target] => (target, false),
+        [_, target, keep] if keep == "keep" => (target, true),
+        _ => return None,
+    };
+    Some(Command::TransferPlayback(TransferPlaybackCommand::new(
+        target.clone(),
+        keep_playing,
+    )))
+}
+
+/// sticker get|set|delete|list song "<uri>" [name] [value]
+fn parse_sticker(args: &[String]) -> Option<Command> {
+    let (action, uri, args) = match args {
+        [_, action, kind, uri, args @ ..] if kind == "song" => (action, uri, args),
+        _ => return None,
+    };
+    let action = match (action.as_str(), args) {
+        ("get", [name]) => StickerAction::Get(name.clone()),
+        ("set", [name, value]) => StickerAction::Set(name.clone(), value.parse().ok()?),
+        ("delete", []) => StickerAction::Delete(None),
+        ("delete", [name]) => StickerAction::Delete(Some(name.clone())),
+        ("list", []) => StickerAction::List,
+        _ => return None,
+    };
+
+    Some(Command::Sticker(StickerCommand::new(uri.clone(), action)))
+}
+
+/// Splits the arguments by whitespace, double quoted arguments may contain whitespace
+pub fn split_args(args: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = args.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => current.extend(chars.next()),
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    result.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        result.push(current);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command_should_parse_quoted_output_ids() {
+        let command = parse_command("disableoutput \"1\"").unwrap();
+
+        assert!(matches!(command, Command::ToggleOutput(_)));
+    }
+
+    #[test]
+    fn parse_command_should_fall_back_to_mpd_protocol() {
+        let command = parse_command("status").unwrap();
+
+        assert!(matches!(
+            command,
+            Command::Mpd(mpd_protocol::Command::Status)
+        ));
+    }
+
+    #[test]
+    fn parse_command_should_parse_transfer_playback() {
+        let command = parse_command("transferplayback \"Living Room\" keep").unwrap();
+
+        assert!(matches!(command, Command::TransferPlayback(_)));
+    }
+
+    #[test]
+    fn parse_command_should_parse_album_art_with_size() {
+        let command = parse_command("albumart \"local:///Some Song.mp3\" 0 256").unwrap();
+
+        assert!(matches!(command, Command::AlbumArt(_)));
+    }
+
+    #[test]
+    fn parse_command_should_parse_album_art_without_size() {
+        let command = parse_command("albumart song.mp3 1024").unwrap();
+
+        assert!(matches!(command, Command::AlbumArt(_)));
+    }
+
+    #[test]
+    fn parse_command_should_parse_sticker_delete_with_name() {
+        let command = parse_command(r#"sticker delete song "Some Song.mp3" rating"#).unwrap();
+
+        match command {
+            Command::Sticker(command) => assert_eq!(
+                command,
+                StickerCommand::new(
+                    "Some Song.mp3".into(),
+                    StickerAction::Delete(Some("rating".into()))
+                )
+            ),
+            _ => panic!("expected sticker command"),
+        }
+    }
+
+    #[test]
+    fn parse_command_should_parse_sticker_set() {
+        let command = parse_command("sticker set song song.mp3 like 2").unwrap();
+
+        match command {
+            Command::Sticker(command) => assert_eq!(
+                command,
+                StickerCommand::new("song.mp3".into(), StickerAction::Set("like".into(), 2))
+            ),
+            _ => panic!("expected sticker command"),
+        }
+    }
+
+    #[test]
+    fn parse_command_should_reject_stickers_of_other_objects() {
+        assert!(!matches!(
+            parse_command("sticker list playlist foo"),
+            Ok(Command::Sticker(_))
+        ));
+    }
+
+    #[test]
+    fn split_args_should_keep_whitespace_in_quotes() {
+        let args = split_args(r#"sticker get song "Some \"Song\".mp3" rating"#);
+
+        assert_eq!(
+            args,
+            vec!["sticker", "get", "song", "Some \"Song\".mp3", "rating"]
+        );
+    }
+}
@@ -1,29 +1,60 @@
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
 
-use failure::{format_err, Error};
+use failure::{bail, format_err, Error};
 use maplit::hashmap;
 use serde_derive::Deserialize;
 
 use async_trait::async_trait;
-use rustic_core::library::{self, Lyrics, SharedLibrary};
+use rustic_core::library::{self, Lyrics, MetadataUpdate, SharedLibrary};
 use rustic_core::provider::*;
 use rustic_core::{CredentialStore, Rating, TrackPosition};
 
-use crate::scanner::Track;
+use crate::scanner::{artist_dir, find_artwork, ArtworkNames, Track};
 
 pub mod scanner;
+mod tags;
 
 const META_LOCAL_FILE_URL: &str = "LOCAL_FILE_URL";
+const META_LOCAL_GENRE: &str = "LOCAL_GENRE";
+const META_LOCAL_YEAR: &str = "LOCAL_YEAR";
+
+pub(crate) const PROVIDER_ID: ProviderId = ProviderId::new("local");
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct LocalProvider {
     path: PathBuf,
+    /// Filenames of album artwork next to the tracks, ordered by priority
+    #[serde(default = "default_album_artwork")]
+    album_artwork: Vec<String>,
+    /// Filenames of artist images in the parent directory of albums, ordered by priority
+    #[serde(default = "default_artist_artwork")]
+    artist_artwork: Vec<String>,
 }
 
 impl LocalProvider {
     pub fn new() -> Option<Self> {
-        dirs::audio_dir().map(|path| LocalProvider { path })
+        dirs::audio_dir().map(|path| LocalProvider {
+            path,
+            album_artwork: default_album_artwork(),
+            artist_artwork: default_artist_artwork(),
+        })
     }
+
+    fn artwork_names(&self) -> ArtworkNames {
+        ArtworkNames {
+            album: self.album_artwork.clone(),
+            artist: self.artist_artwork.clone(),
+        }
+    }
+}
+
+fn default_album_artwork() -> Vec<String> {
+    ArtworkNames::default().album
+}
+
+fn default_artist_artwork() -> Vec<String> {
+    ArtworkNames::default().artist
 }
 
 #[async_trait]
@@ -36,8 +67,8 @@ impl ProviderInstance for LocalProvider {
         "file"
     }
 
-    fn provider(&self) -> ProviderType {
-        ProviderType::LocalMedia
+    fn provider(&self) -> ProviderId {
+        PROVIDER_ID
     }
 
     async fn setup(&mut self, _cred_store: &dyn CredentialStore) -> Result<(), Error> {
@@ -57,7 +88,7 @@ impl ProviderInstance for LocalProvider {
     }
 
     async fn sync(&self, library: SharedLibrary) -> Result<SyncResult, Error> {
-        let scanner = scanner::Scanner::new(&self.path);
+        let scanner = scanner::Scanner::new(&self.path).with_artwork(self.artwork_names());
         let tracks = scanner.scan()?;
         let artists = LocalProvider::sync_artists(&library, &tracks);
         let albums = LocalProvider::sync_albums(&library, &tracks, &artists);
@@ -115,7 +146,7 @@ impl ProviderInstance for LocalProvider {
     }
 
     async fn stream_url(&self, track: &library::Track) -> Result<String, Error> {
-        if track.provider == ProviderType::LocalMedia {
+        if track.provider == PROVIDER_ID {
             return Ok(track.uri.clone());
         }
 
@@ -126,48 +157,112 @@ impl ProviderInstance for LocalProvider {
         &self,
         provider_item: &ProviderItemType,
     ) -> Result<Option<Thumbnail>, Error> {
-        let uri = match provider_item {
-            ProviderItemType::Track(track) => {
-                if track.thumbnail == ThumbnailState::Data {
-                    Some(track.uri.clone())
-                } else {
-                    None
+        match provider_item {
+            ProviderItemType::Track(track) if track.thumbnail == ThumbnailState::Data => {
+                let path = self.library_path(&track.uri)?;
+                match tags::picture(path)? {
+                    Some(picture) => Ok(Some(picture)),
+                    None => self.sidecar_artwork(path),
                 }
             }
-            ProviderItemType::Album(album) => {
-                if album.thumbnail == ThumbnailState::Data {
-                    Some(album.uri.clone())
-                } else {
-                    None
+            ProviderItemType::Album(album) if album.thumbnail == ThumbnailState::Data => {
+                let path = self.library_path(&album.uri)?;
+                match self.sidecar_artwork(path)? {
+                    Some(artwork) => Ok(Some(artwork)),
+                    None => tags::picture(path),
                 }
             }
+            ProviderItemType::Artist(artist) => match artist.image_url {
+                Some(ref url) => read_image(self.library_path(url)?).map(Some),
+                None => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    async fn thumbnail_modified(
+        &self,
+        provider_item: &ProviderItemType,
+    ) -> Result<Option<SystemTime>, Error> {
+        let modified = match provider_item {
+            ProviderItemType::Track(library::Track { uri, .. })
+            | ProviderItemType::Album(library::Album { uri, .. }) => {
+                let path = self.library_path(uri)?;
+                let artwork = path
+                    .parent()
+                    .and_then(|dir| find_artwork(dir, &self.album_artwork));
+                modified(path).max(artwork.and_then(|artwork| modified(&artwork)))
+            }
+            ProviderItemType::Artist(library::Artist {
+                image_url: Some(url),
+                ..
+            }) => modified(self.library_path(url)?),
             _ => None,
         };
-        if let Some(uri) = uri {
-            let path = &uri["file://".len()..];
-            let tag = id3::Tag::read_from_path(path)?;
-            let picture = tag
-                .pictures()
-                .find(|_| true)
-                .map(|picture| Thumbnail::Data {
-                    data: picture.data.clone(),
-                    mime_type: picture.mime_type.clone(),
-                });
-
-            Ok(picture)
-        } else {
-            Ok(None)
-        }
+
+        Ok(modified)
     }
 
     async fn resolve_share_url(&self, _url: url::Url) -> Result<Option<InternalUri>, Error> {
         Ok(None)
     }
+
+    async fn update_metadata(
+        &self,
+        provider_item: &ProviderItemType,
+        update: &MetadataUpdate,
+    ) -> Result<Vec<ProviderItemType>, Error> {
+        match provider_item {
+            ProviderItemType::Track(track) => {
+                let track = self.write_tags(&track.uri, update)?;
+
+                Ok(vec![ProviderItemType::Track(track.into())])
+            }
+            ProviderItemType::Album(album) => {
+                let update = update.for_album_tracks();
+                let tracks = album
+                    .tracks
+                    .iter()
+                    .map(|track| self.write_tags(&track.uri, &update))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                // The album is identified by the path of its first track
+                let updated_album = tracks
+                    .iter()
+                    .find(|track| format!("file://{}", track.path) == album.uri)
+                    .or_else(|| tracks.first())
+                    .cloned()
+                    .and_then(Option::<library::Album>::from)
+                    .map(|mut updated| {
+                        updated.uri = album.uri.clone();
+                        updated.meta = album.meta.clone();
+                        updated
+                    });
+                let mut items: Vec<ProviderItemType> = tracks
+                    .into_iter()
+                    .map(library::Track::from)
+                    .map(ProviderItemType::Track)
+                    .collect();
+                items.extend(updated_album.map(ProviderItemType::Album));
+
+                Ok(items)
+            }
+            _ => Err(MetadataError::UnsupportedItem.into()),
+        }
+    }
 }
 
 impl From<scanner::Track> for library::Track {
     fn from(track: scanner::Track) -> Self {
         let path = track.path.clone();
+        let mut meta = hashmap!(
+            META_LOCAL_FILE_URL.into() => path.into()
+        );
+        if let Some(genre) = track.genre.clone() {
+            meta.insert(META_LOCAL_GENRE.into(), genre.into());
+        }
+        if let Some(year) = track.year {
+            meta.insert(META_LOCAL_YEAR.into(), u64::from(year).into());
+        }
         library::Track {
             id: None,
             title: track.title.clone(),
@@ -175,17 +270,15 @@ impl From<scanner::Track> for library::Track {
             album: track.clone().into(),
             artist_id: None,
             artist: track.clone().into(),
-            thumbnail: if track.has_coverart {
+            thumbnail: if track.has_coverart || track.artwork.is_some() {
                 ThumbnailState::Data
             } else {
                 ThumbnailState::None
             },
-            provider: ProviderType::LocalMedia,
+            provider: PROVIDER_ID,
             uri: format!("file://{}", track.path),
             duration: track.duration.map(u64::from),
-            meta: hashmap!(
-                META_LOCAL_FILE_URL.into() => path.into()
-            ),
+            meta,
             explicit: None,
             rating: Rating::None,
             position: TrackPosition::new(track.track.map(u64::from), track.disc.map(u64::from)),
@@ -200,14 +293,14 @@ impl From<scanner::Track> for library::Track {
 impl From<scanner::Track> for Option<library::Album> {
     fn from(track: scanner::Track) -> Self {
         let path = track.path.clone();
-        let artist = track.clone().into();
-        let has_coverart = track.has_coverart;
+        let artist = album_artist(&track);
+        let has_coverart = track.has_coverart || track.artwork.is_some();
         track.album.map(|name| library::Album {
             id: None,
             title: name,
             artist_id: None,
             artist,
-            provider: ProviderType::LocalMedia,
+            provider: PROVIDER_ID,
             thumbnail: if has_coverart {
                 ThumbnailState::Data
             } else {
@@ -231,11 +324,14 @@ impl From<scanner::Track> for Option<library::Artist> {
             id: None,
             name,
             uri: format!("file://{}", &path),
-            image_url: None,
+            image_url: track
+                .artist_artwork
+                .as_ref()
+                .map(|artwork| format!("file://{}", artwork)),
             meta: hashmap!(
                 META_LOCAL_FILE_URL.into() => path.into()
             ),
-            provider: ProviderType::LocalMedia,
+            provider: PROVIDER_ID,
             albums: vec![],
             playlists: vec![],
             description: None,
@@ -243,6 +339,16 @@ impl From<scanner::Track> for Option<library::Artist> {
     }
 }
 
+/// The album artist tag, falling back to the artist of the track
+fn album_artist(track: &Track) -> Option<library::Artist> {
+    let track = Track {
+        artist: track.album_artist.clone().or_else(|| track.artist.clone()),
+        ..track.clone()
+    };
+
+    track.into()
+}
+
 impl LocalProvider {
     fn sync_albums(
         library: &SharedLibrary,
@@ -278,8 +384,8 @@ impl LocalProvider {
     fn sync_artists(library: &SharedLibrary, tracks: &[Track]) -> Vec<library::Artist> {
         let artists: Vec<library::Artist> = tracks
             .iter()
-            .cloned()
-            .filter_map(Option::<library::Artist>::from)
+            .flat_map(|track| vec![track.clone().into(), album_artist(track)])
+            .flatten()
             .fold(Vec::new(), |mut artists, artist| {
                 if artists.iter().find(|a| a.name == artist.name).is_none() {
                     artists.push(artist);
@@ -315,20 +421,76 @@ impl LocalProvider {
         }
     }
 
+    /// Path of the file with the given uri, only files inside of the library can be accessed
+    fn library_path<'a>(&self, uri: &'a str) -> Result<&'a Path, Error> {
+        let path = uri
+            .strip_prefix("file://")
+            .map(Path::new)
+            .ok_or_else(|| format_err!("Invalid uri {}", uri))?;
+        let escapes = path
+            .components()
+            .any(|component| component == Component::ParentDir);
+        if escapes || !path.starts_with(&self.path) {
+            bail!("{} is outside of the library", uri);
+        }
+
+        Ok(path)
+    }
+
     fn get_track(&self, uri: &str) -> Result<Track, Error> {
-        let path = &uri["file://".len()..];
-        let tag = id3::Tag::read_from_path(path)?;
-        let track = Track {
-            path: path.to_string(),
-            title: tag.title().map(String::from).unwrap_or_default(),
-            artist: tag.artist().map(String::from),
-            album: tag.album().map(String::from),
-            has_coverart: tag.pictures().any(|_| true),
-            duration: tag.duration(),
-            track: tag.track(),
-            disc: tag.disc(),
-        };
+        let path = self.library_path(uri)?;
+        let album_dir = path.parent();
+        let artwork = album_dir
+            .and_then(|dir| find_artwork(dir, &self.album_artwork))
+            .and_then(|artwork| artwork.to_str().map(String::from));
+        let artist_artwork = album_dir
+            .and_then(|dir| artist_dir(&self.path, dir))
+            .and_then(|dir| find_artwork(dir, &self.artist_artwork))
+            .and_then(|artwork| artwork.to_str().map(String::from));
+
+        Track::read(path, artwork, artist_artwork)
+    }
+
+    fn write_tags(&self, uri: &str, update: &MetadataUpdate) -> Result<Track, Error> {
+        let path = self.library_path(uri)?;
+        tags::write(path, update)?;
 
-        Ok(track)
+        self.get_track(uri)
     }
+
+    /// Artwork next to the track at the given path
+    fn sidecar_artwork(&self, path: &Path) -> Result<Option<Thumbnail>, Error> {
+        let artwork = path
+            .parent()
+            .and_then(|dir| find_artwork(dir, &self.album_artwork));
+        match artwork {
+            Some(artwork) => read_image(&artwork).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn read_image(path: &Path) -> Result<Thumbnail, Error> {
+    let data = std::fs::read(path)?;
+    let mime_type = match path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        _ => "image/jpeg",
+    };
+
+    Ok(Thumbnail::Data {
+        data,
+        mime_type: mime_type.to_string(),
+    })
 }
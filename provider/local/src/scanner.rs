@@ -1,6 +1,11 @@
-use failure::Error;
+use failure::{format_err, Error};
 use log::error;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::tags;
+
+const ARTWORK_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Track {
@@ -8,91 +13,199 @@ pub struct Track {
     pub title: String,
     pub artist: Option<String>,
     pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
     pub has_coverart: bool,
+    /// Path of the album artwork next to the track
+    pub artwork: Option<String>,
+    /// Path of the artist image in the parent directory of the album
+    pub artist_artwork: Option<String>,
     pub duration: Option<u32>,
     pub disc: Option<u32>,
     pub track: Option<u32>,
 }
 
+/// Filenames of sidecar artwork without extension, ordered by priority
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtworkNames {
+    pub album: Vec<String>,
+    pub artist: Vec<String>,
+}
+
+impl Default for ArtworkNames {
+    fn default() -> Self {
+        ArtworkNames {
+            album: vec![
+                "cover".into(),
+                "folder".into(),
+                "front".into(),
+                "album".into(),
+            ],
+            artist: vec!["artist".into()],
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Scanner {
     path: PathBuf,
+    artwork: ArtworkNames,
 }
 
-fn is_mp3(entry: &walkdir::DirEntry) -> bool {
-    if entry.file_type().is_file() {
-        entry
+fn is_audio_file(entry: &walkdir::DirEntry) -> bool {
+    !entry.file_type().is_file() || tags::is_supported(entry.path())
+}
+
+impl Track {
+    /// Reads the tags of the file at the given path
+    ///
+    /// Files without tags are named after the file.
+    pub fn read(
+        path: &Path,
+        artwork: Option<String>,
+        artist_artwork: Option<String>,
+    ) -> Result<Track, Error> {
+        let filename = path
             .file_name()
-            .to_str()
-            .map(|s| s.ends_with(".mp3"))
-            .unwrap_or(false)
-    } else {
-        true
+            .and_then(|filename| filename.to_str())
+            .ok_or_else(|| format_err!("Invalid Filename"))?;
+        let tags = tags::read(path)?;
+
+        Ok(Track {
+            path: path
+                .to_str()
+                .ok_or_else(|| format_err!("Invalid Path"))?
+                .to_string(),
+            title: tags.title.unwrap_or_else(|| filename.to_string()),
+            artist: tags.artist,
+            album: tags.album,
+            album_artist: tags.album_artist,
+            genre: tags.genre,
+            year: tags.year,
+            has_coverart: tags.has_picture,
+            artwork,
+            artist_artwork,
+            duration: tags.duration,
+            disc: tags.disc,
+            track: tags.track,
+        })
     }
 }
 
 impl Scanner {
     pub fn new<P: Into<PathBuf>>(path: P) -> Scanner {
-        Scanner { path: path.into() }
+        Scanner {
+            path: path.into(),
+            artwork: ArtworkNames::default(),
+        }
+    }
+
+    pub fn with_artwork(mut self, artwork: ArtworkNames) -> Scanner {
+        self.artwork = artwork;
+        self
     }
 
     pub fn scan(&self) -> Result<Vec<Track>, Error> {
-        walkdir::WalkDir::new(&self.path)
+        let mut artwork = ArtworkLookup::new(&self.artwork, &self.path);
+        let tracks = walkdir::WalkDir::new(&self.path)
             .into_iter()
-            .filter_entry(|e| is_mp3(e))
-            .map(|entry| {
-                entry.map_err(failure::Error::from).and_then(|entry| {
-                    let path = entry
-                        .path()
-                        .to_str()
-                        .ok_or_else(|| failure::err_msg("Invalid Path"))?
-                        .to_string();
-                    let filename = entry
-                        .file_name()
-                        .to_str()
-                        .ok_or_else(|| failure::err_msg("Invalid Filename"))?;
-                    match id3::Tag::read_from_path(entry.path()) {
-                        Ok(tag) => {
-                            let title = tag.title().unwrap_or(filename).to_string();
-                            let artist = tag.artist().map(|s| s.to_string());
-                            let album = tag.album().map(|s| s.to_string());
-                            let has_coverart = tag.pictures().any(|_| true);
-                            let duration = tag.duration();
-                            let disc = tag.disc();
-                            let track = tag.track();
-
-                            Ok(Track {
-                                path,
-                                title,
-                                artist,
-                                album,
-                                has_coverart,
-                                duration,
-                                disc,
-                                track,
-                            })
-                        }
-                        Err(id3::Error {
-                            kind: id3::ErrorKind::NoTag,
-                            ..
-                        }) => Ok(Track {
-                            path,
-                            title: filename.to_string(),
-                            artist: None,
-                            album: None,
-                            has_coverart: false,
-                            duration: None,
-                            track: None,
-                            disc: None,
-                        }),
-                        Err(e) => {
-                            error!("{:?} {:?}", entry.path(), e);
-                            Err(failure::Error::from(e))
-                        }
-                    }
-                })
+            .filter_entry(|e| is_audio_file(e))
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                let (album_artwork, artist_artwork) = artwork.lookup(entry.path());
+                Track::read(entry.path(), album_artwork, artist_artwork)
+                    .map_err(|e| error!("{:?} {:?}", entry.path(), e))
+                    .ok()
             })
-            .filter(|track| track.is_ok())
-            .collect()
+            .collect();
+
+        Ok(tracks)
+    }
+}
+
+/// Caches sidecar artwork per directory so every directory is only listed once per scan
+struct ArtworkLookup<'a> {
+    names: &'a ArtworkNames,
+    root: &'a Path,
+    albums: HashMap<PathBuf, Option<String>>,
+    artists: HashMap<PathBuf, Option<String>>,
+}
+
+impl<'a> ArtworkLookup<'a> {
+    fn new(names: &'a ArtworkNames, root: &'a Path) -> Self {
+        ArtworkLookup {
+            names,
+            root,
+            albums: HashMap::new(),
+            artists: HashMap::new(),
+        }
+    }
+
+    /// Returns the album and artist artwork for the track at the given path
+    fn lookup(&mut self, track: &Path) -> (Option<String>, Option<String>) {
+        let album_dir = match track.parent() {
+            Some(dir) => dir,
+            None => return (None, None),
+        };
+        let names = self.names;
+        let album = self
+            .albums
+            .entry(album_dir.to_path_buf())
+            .or_insert_with(|| find_artwork(album_dir, &names.album).and_then(path_to_string))
+            .clone();
+        let artist = artist_dir(self.root, album_dir).and_then(|artist_dir| {
+            self.artists
+                .entry(artist_dir.to_path_buf())
+                .or_insert_with(|| find_artwork(artist_dir, &names.artist).and_then(path_to_string))
+                .clone()
+        });
+
+        (album, artist)
     }
 }
+
+/// The parent directory of the album if it's a subdirectory of the library
+///
+/// In flat libraries the parent of an album is the library itself or outside of it,
+/// an image there doesn't belong to a single artist.
+pub fn artist_dir<'a>(root: &Path, album_dir: &'a Path) -> Option<&'a Path> {
+    album_dir
+        .parent()
+        .filter(|dir| dir.starts_with(root) && *dir != root)
+}
+
+/// Finds the image in the directory whose name comes first in `names`
+///
+/// Names are matched case insensitive and without extension.
+pub fn find_artwork(dir: &Path, names: &[String]) -> Option<PathBuf> {
+    let images: Vec<(String, PathBuf)> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| ARTWORK_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?.to_lowercase();
+            Some((stem, path))
+        })
+        .collect();
+
+    names.iter().find_map(|name| {
+        let name = name.to_lowercase();
+        images
+            .iter()
+            .find(|(stem, _)| stem == &name)
+            .map(|(_, path)| path.clone())
+    })
+}
+
+fn path_to_string(path: PathBuf) -> Option<String> {
+    path.to_str().map(String::from)
+}
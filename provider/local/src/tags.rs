@@ -0,0 +1,305 @@
+use std::path::Path;
+
+use failure::{bail, Error};
+use lofty::{
+    Accessor, AudioFile, FileType, ItemKey, ItemValue, MimeType, Picture, PictureType, Tag, TagExt,
+    TagItem, TaggedFile,
+};
+use log::debug;
+
+use rustic_core::library::MetadataUpdate;
+use rustic_core::provider::Thumbnail;
+
+/// Separator used to list multiple artists of a track as one artist
+const ARTIST_SEPARATOR: &str = "; ";
+
+/// Tags and properties of an audio file
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub track: Option<u32>,
+    pub disc: Option<u32>,
+    /// Duration in seconds
+    pub duration: Option<u32>,
+    pub has_picture: bool,
+}
+
+/// Whether the file has a format we can read tags from
+pub fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| FileType::from_ext(extension).is_some())
+        .unwrap_or(false)
+}
+
+/// Reads the primary tag of the file, falling back to the first tag when the primary one is missing
+pub fn read(path: &Path) -> Result<FileTags, Error> {
+    let file = lofty::read_from_path(path)?;
+    let duration = file.properties().duration().as_secs() as u32;
+    let mut tags = FileTags {
+        duration: Some(duration).filter(|duration| *duration > 0),
+        ..FileTags::default()
+    };
+    if let Some(tag) = main_tag(&file) {
+        let text = |key: ItemKey| tag.get_string(&key).map(String::from);
+        tags.title = text(ItemKey::TrackTitle);
+        let artists: Vec<&str> = tag.get_strings(&ItemKey::TrackArtist).collect();
+        tags.artist = Some(artists.join(ARTIST_SEPARATOR)).filter(|artist| !artist.is_empty());
+        tags.album = text(ItemKey::AlbumTitle);
+        tags.album_artist = text(ItemKey::AlbumArtist);
+        tags.genre = text(ItemKey::Genre);
+        tags.year = text(ItemKey::Year)
+            .or_else(|| text(ItemKey::RecordingDate))
+            .and_then(|date| parse_year(&date));
+        tags.track = tag.track();
+        tags.disc = tag.disk();
+        tags.has_picture = !tag.pictures().is_empty();
+    }
+
+    Ok(tags)
+}
+
+/// The embedded front cover, or any other picture when there is no front cover
+pub fn picture(path: &Path) -> Result<Option<Thumbnail>, Error> {
+    let file = lofty::read_from_path(path)?;
+    let pictures = match main_tag(&file) {
+        Some(tag) => tag.pictures(),
+        None => return Ok(None),
+    };
+    let picture = pictures
+        .iter()
+        .find(|picture| picture.pic_type() == PictureType::CoverFront)
+        .or_else(|| pictures.first())
+        .map(|picture| Thumbnail::Data {
+            data: picture.data().to_vec(),
+            mime_type: picture.mime_type().as_str().to_string(),
+        });
+
+    Ok(picture)
+}
+
+fn main_tag(file: &TaggedFile) -> Option<&Tag> {
+    file.primary_tag().or_else(|| file.first_tag())
+}
+
+/// Dates are stored as year or as full date, e.g. 2021-04-01
+fn parse_year(date: &str) -> Option<u32> {
+    date.get(..4)?.parse().ok()
+}
+
+/// Writes the changed fields to the primary tag of the file
+///
+/// Supports id3v2 (mp3), vorbis comments (flac, ogg) and mp4 atoms.
+/// Files without a tag get a new one of the primary tag type of their format.
+pub fn write(path: &Path, update: &MetadataUpdate) -> Result<(), Error> {
+    debug!("Writing tags to {}", path.display());
+    let mut file = lofty::read_from_path(path)?;
+    let tag_type = file.primary_tag_type();
+    if file.primary_tag_mut().is_none() {
+        file.insert_tag(Tag::new(tag_type));
+    }
+    let tag = file.primary_tag_mut().unwrap();
+    apply(tag, update)?;
+    tag.save_to_path(path)?;
+
+    Ok(())
+}
+
+fn apply(tag: &mut Tag, update: &MetadataUpdate) -> Result<(), Error> {
+    if let Some(ref title) = update.title {
+        tag.set_title(title.clone());
+    }
+    if let Some(ref artists) = update.artists {
+        tag.remove_key(&ItemKey::TrackArtist);
+        for artist in artists {
+            let item = TagItem::new(ItemKey::TrackArtist, ItemValue::Text(artist.clone()));
+            tag.push_item(item);
+        }
+    }
+    if let Some(ref album) = update.album {
+        tag.set_album(album.clone());
+    }
+    if let Some(ref album_artist) = update.album_artist {
+        tag.insert_text(ItemKey::AlbumArtist, album_artist.clone());
+    }
+    if let Some(track_number) = update.track_number {
+        tag.set_track(track_number);
+    }
+    if let Some(disc_number) = update.disc_number {
+        tag.set_disk(disc_number);
+    }
+    if let Some(ref genre) = update.genre {
+        tag.set_genre(genre.clone());
+    }
+    if let Some(year) = update.year {
+        tag.insert_text(ItemKey::Year, year.to_string());
+    }
+    match update.cover_art {
+        Some(Thumbnail::Data {
+            ref data,
+            ref mime_type,
+        }) => {
+            let picture = Picture::new_unchecked(
+                PictureType::CoverFront,
+                MimeType::from_str(mime_type),
+                None,
+                data.clone(),
+            );
+            tag.remove_picture_type(PictureType::CoverFront);
+            tag.push_picture(picture);
+        }
+        Some(Thumbnail::Url(_)) => bail!("Cover art has to be embedded as data"),
+        None => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rustic_core::library::MetadataUpdate;
+    use rustic_core::provider::Thumbnail;
+
+    use crate::scanner::Track;
+
+    use super::{parse_year, picture, read, write};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0";
+
+    /// Copy of the test asset which can be modified by the given test
+    fn asset(test: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rustic-{}-{}.mp3", test, std::process::id()));
+        std::fs::copy(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/assets/bensound-ukulele.mp3"),
+            &path,
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn read_should_read_the_asset() {
+        let path = asset("read_should_read_the_asset");
+
+        let tags = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(tags.title, Some("Ukulele".into()));
+        assert_eq!(tags.artist, Some("Bensound".into()));
+        assert!(tags.duration.is_some());
+    }
+
+    #[test]
+    fn write_should_round_trip_all_fields() {
+        let path = asset("write_should_round_trip_all_fields");
+        let update = MetadataUpdate {
+            title: Some("Title".into()),
+            artists: Some(vec!["Artist 1".into(), "Artist 2".into()]),
+            album: Some("Album".into()),
+            album_artist: Some("Album Artist".into()),
+            track_number: Some(3),
+            disc_number: Some(2),
+            genre: Some("Folk".into()),
+            year: Some(2021),
+            cover_art: None,
+        };
+
+        write(&path, &update).unwrap();
+        let tags = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(tags.title, Some("Title".into()));
+        assert_eq!(tags.artist, Some("Artist 1; Artist 2".into()));
+        assert_eq!(tags.album, Some("Album".into()));
+        assert_eq!(tags.album_artist, Some("Album Artist".into()));
+        assert_eq!(tags.track, Some(3));
+        assert_eq!(tags.disc, Some(2));
+        assert_eq!(tags.genre, Some("Folk".into()));
+        assert_eq!(tags.year, Some(2021));
+    }
+
+    #[test]
+    fn write_should_store_every_artist_for_the_rescan() {
+        let path = asset("write_should_store_every_artist_for_the_rescan");
+        let update = MetadataUpdate {
+            artists: Some(vec!["Artist 1".into(), "Artist 2".into()]),
+            ..MetadataUpdate::default()
+        };
+
+        write(&path, &update).unwrap();
+        let track = Track::read(&path, None, None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(track.title, "Ukulele");
+        assert_eq!(track.artist, Some("Artist 1; Artist 2".into()));
+    }
+
+    #[test]
+    fn write_should_keep_unchanged_fields() {
+        let path = asset("write_should_keep_unchanged_fields");
+        let update = MetadataUpdate {
+            album: Some("Album".into()),
+            ..MetadataUpdate::default()
+        };
+
+        write(&path, &update).unwrap();
+        let tags = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(tags.title, Some("Ukulele".into()));
+        assert_eq!(tags.album, Some("Album".into()));
+    }
+
+    #[test]
+    fn write_should_embed_cover_art() {
+        let path = asset("write_should_embed_cover_art");
+        let update = MetadataUpdate {
+            cover_art: Some(Thumbnail::Data {
+                data: PNG.to_vec(),
+                mime_type: "image/png".into(),
+            }),
+            ..MetadataUpdate::default()
+        };
+
+        write(&path, &update).unwrap();
+        let cover_art = picture(&path).unwrap();
+        let tags = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(tags.has_picture);
+        match cover_art {
+            Some(Thumbnail::Data { data, mime_type }) => {
+                assert_eq!(data, PNG);
+                assert_eq!(mime_type, "image/png");
+            }
+            _ => panic!("cover art missing"),
+        }
+    }
+
+    #[test]
+    fn write_should_reject_cover_art_urls() {
+        let path = asset("write_should_reject_cover_art_urls");
+        let update = MetadataUpdate {
+            cover_art: Some(Thumbnail::Url("https://example.com/cover.jpg".into())),
+            ..MetadataUpdate::default()
+        };
+
+        let result = write(&path, &update);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_year_should_accept_dates() {
+        assert_eq!(parse_year("2021"), Some(2021));
+        assert_eq!(parse_year("2021-04-01"), Some(2021));
+        assert_eq!(parse_year("21"), None);
+    }
+}
@@ -1,8 +1,11 @@
 #[test]
 fn test_scan() {
     let scanner = rustic_local_provider::scanner::Scanner::new("assets");
-    let res = scanner.scan().unwrap();
+    let mut res = scanner.scan().unwrap();
 
+    assert_eq!(res.len(), 1);
+    assert!(res[0].duration.is_some());
+    res[0].duration = None;
     assert_eq!(
         res,
         vec![rustic_local_provider::scanner::Track {
@@ -10,10 +13,70 @@ fn test_scan() {
             title: "Ukulele".into(),
             artist: Some("Bensound".into()),
             album: None,
+            album_artist: None,
+            genre: None,
+            year: None,
             has_coverart: false,
+            artwork: None,
+            artist_artwork: None,
             duration: None,
             track: None,
             disc: None,
         }]
     );
 }
+
+#[test]
+fn test_scan_skips_unsupported_files() {
+    let dir = std::env::temp_dir().join(format!("rustic-scan-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("assets/bensound-ukulele.mp3", dir.join("ukulele.mp3")).unwrap();
+    std::fs::write(dir.join("notes.txt"), b"").unwrap();
+
+    let res = rustic_local_provider::scanner::Scanner::new(&dir).scan();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let paths: Vec<_> = res.unwrap().into_iter().map(|track| track.path).collect();
+    assert_eq!(
+        paths,
+        vec![dir.join("ukulele.mp3").to_str().unwrap().to_string()]
+    );
+}
+
+#[test]
+fn test_find_artwork_by_priority() {
+    let dir = std::env::temp_dir().join(format!("rustic-artwork-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("Folder.PNG"), b"").unwrap();
+    std::fs::write(dir.join("front.jpg"), b"").unwrap();
+    std::fs::write(dir.join("cover.txt"), b"").unwrap();
+    let names = vec!["cover".to_string(), "folder".to_string(), "front".to_string()];
+
+    let artwork = rustic_local_provider::scanner::find_artwork(&dir, &names);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(artwork, Some(dir.join("Folder.PNG")));
+}
+
+#[test]
+fn test_artist_dir_in_nested_library() {
+    let root = std::path::Path::new("/music");
+
+    let artist_dir = rustic_local_provider::scanner::artist_dir(
+        root,
+        std::path::Path::new("/music/Artist/Album"),
+    );
+
+    assert_eq!(artist_dir, Some(std::path::Path::new("/music/Artist")));
+}
+
+#[test]
+fn test_artist_dir_in_flat_library() {
+    let root = std::path::Path::new("/music");
+
+    assert_eq!(
+        rustic_local_provider::scanner::artist_dir(root, std::path::Path::new("/music/Album")),
+        None
+    );
+    assert_eq!(rustic_local_provider::scanner::artist_dir(root, root), None);
+}
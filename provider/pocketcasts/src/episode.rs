@@ -3,8 +3,9 @@ use pocketcasts::Episode;
 use serde::{Deserialize, Serialize};
 
 use rustic_core::library::{Lyrics, Rating, Track};
-use rustic_core::provider::{ProviderType, ThumbnailState};
+use rustic_core::provider::ThumbnailState;
 
+use crate::PROVIDER_ID;
 use crate::meta::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,7 +21,7 @@ impl From<PocketcastTrack> for Track {
             artist: None,
             album_id: None,
             album: None,
-            provider: ProviderType::Pocketcasts,
+            provider: PROVIDER_ID,
             uri: format!("pocketcasts://episode/{}", episode.uuid),
             thumbnail: ThumbnailState::None,
             duration: Some(episode.duration),
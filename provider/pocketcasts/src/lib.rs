@@ -14,6 +14,8 @@ mod episode;
 mod meta;
 mod podcast;
 
+pub(crate) const PROVIDER_ID: provider::ProviderId = provider::ProviderId::new("pocketcasts");
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct PocketcastsCredentials {
     email: String,
@@ -48,7 +50,7 @@ impl provider::ProviderInstance for PocketcastsProvider {
                     .await?,
             )
         } else if let Some(Credentials::UserPass { username, password }) = cred_store
-            .get_credentials(provider::ProviderType::Pocketcasts)
+            .get_credentials(PROVIDER_ID)
             .await?
         {
             Some(PocketcastClient::login(username, password).await?)
@@ -67,8 +69,8 @@ impl provider::ProviderInstance for PocketcastsProvider {
         "pocketcasts"
     }
 
-    fn provider(&self) -> provider::ProviderType {
-        provider::ProviderType::Pocketcasts
+    fn provider(&self) -> provider::ProviderId {
+        PROVIDER_ID
     }
 
     fn state(&self) -> provider::ProviderState {
@@ -91,7 +93,7 @@ impl provider::ProviderInstance for PocketcastsProvider {
                 self.login(email.clone(), password.clone()).await?;
                 cred_store
                     .store_credentials(
-                        provider::ProviderType::Pocketcasts,
+                        PROVIDER_ID,
                         Credentials::password(email, password),
                     )
                     .await?;
@@ -101,6 +103,11 @@ impl provider::ProviderInstance for PocketcastsProvider {
         }
     }
 
+    async fn logout(&mut self) -> Result<(), Error> {
+        self.client = None;
+        Ok(())
+    }
+
     async fn sync(&self, library: SharedLibrary) -> Result<provider::SyncResult, Error> {
         let client = self
             .client
@@ -230,7 +237,7 @@ impl provider::ProviderInstance for PocketcastsProvider {
     }
 
     async fn stream_url(&self, track: &Track) -> Result<String, Error> {
-        if track.provider == provider::ProviderType::Pocketcasts {
+        if track.provider == PROVIDER_ID {
             if let rustic_core::library::MetaValue::String(stream_url) =
                 track.meta.get(meta::META_POCKETCASTS_STREAM_URL).unwrap()
             {
@@ -5,8 +5,9 @@ use pocketcasts::{Podcast, SearchPodcast};
 use serde::{Deserialize, Serialize};
 
 use rustic_core::library::{Album, Artist};
-use rustic_core::provider::{ProviderFolder, ProviderType, ThumbnailState};
+use rustic_core::provider::{ProviderFolder, ThumbnailState};
 
+use crate::PROVIDER_ID;
 use crate::meta::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,13 +35,13 @@ impl From<PocketcastAlbum> for Album {
                 meta: hashmap!(
                     META_POCKETCASTS_PODCAST_UUID.into() => podcast.uuid.into()
                 ),
-                provider: ProviderType::Pocketcasts,
+                provider: PROVIDER_ID,
                 albums: Vec::new(),
                 playlists: Vec::new(),
                 description: Some(podcast.description.clone()),
             }),
             tracks: vec![],
-            provider: ProviderType::Pocketcasts,
+            provider: PROVIDER_ID,
             thumbnail: ThumbnailState::Url(thumbnail_url.clone()),
             uri: format!("pocketcasts://podcast/{}", podcast.uuid),
             meta: hashmap!(
@@ -63,7 +64,7 @@ impl From<PocketcastAlbum> for Artist {
             name: podcast.author,
             image_url: None,
             meta: HashMap::new(),
-            provider: ProviderType::Pocketcasts,
+            provider: PROVIDER_ID,
             albums: vec![album.into()],
             playlists: Vec::new(),
             description: Some(podcast.description),
@@ -99,13 +100,13 @@ impl From<PocketcastSearchResult> for Album {
                 name: podcast.author,
                 image_url: None,
                 meta: HashMap::new(),
-                provider: ProviderType::Pocketcasts,
+                provider: PROVIDER_ID,
                 albums: vec![],
                 playlists: vec![],
                 description: Some(podcast.description.clone()),
             }),
             tracks: vec![],
-            provider: ProviderType::Pocketcasts,
+            provider: PROVIDER_ID,
             thumbnail: ThumbnailState::Url(thumbnail_url.clone()),
             uri: format!("pocketcasts://podcast/{}", podcast.uuid),
             meta: hashmap!(
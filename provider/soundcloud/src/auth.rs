@@ -0,0 +1,251 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use failure::Error;
+use serde::{Deserialize, Serialize};
+
+use rustic_core::Credentials;
+
+use crate::SOUNDCLOUD_REDIRECT_URI;
+
+/// Tokens are refreshed when they expire within this many seconds
+const EXPIRY_MARGIN: u64 = 10 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoundcloudToken {
+    pub access_token: String,
+    /// Only the authorization code flow provides a refresh token
+    pub refresh_token: Option<String>,
+    /// Unix timestamp in seconds
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<u64>,
+}
+
+impl SoundcloudToken {
+    pub fn new(access_token: String) -> Self {
+        SoundcloudToken {
+            access_token,
+            refresh_token: None,
+            expires_at: None,
+        }
+    }
+
+    /// Earlier versions stored only the access token
+    pub fn from_credentials(credentials: Credentials) -> Option<Self> {
+        match credentials {
+            Credentials::Token(token) => {
+                let parsed = serde_json::from_str(&token).ok();
+                Some(parsed.unwrap_or_else(|| SoundcloudToken::new(token)))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn expires_soon(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| expires_at <= now() + EXPIRY_MARGIN)
+            .unwrap_or(false)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| expires_at <= now())
+            .unwrap_or(false)
+    }
+
+    /// Exchanges the code of the authorization code flow
+    pub async fn request(
+        api_url: &str,
+        client_id: &str,
+        client_secret: &str,
+        code: &str,
+    ) -> Result<Self, Error> {
+        let params = [
+            ("grant_type", "authorization_code"),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("redirect_uri", SOUNDCLOUD_REDIRECT_URI),
+            ("code", code),
+        ];
+        let response = SoundcloudToken::fetch(api_url, &params).await?;
+
+        Ok(SoundcloudToken::from_response(response, None))
+    }
+
+    pub async fn refresh(
+        &self,
+        api_url: &str,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<Option<Self>, Error> {
+        let refresh_token = match self.refresh_token {
+            Some(ref refresh_token) => refresh_token,
+            None => return Ok(None),
+        };
+        let params = [
+            ("grant_type", "refresh_token"),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("refresh_token", refresh_token.as_str()),
+        ];
+        let response = SoundcloudToken::fetch(api_url, &params).await?;
+        let token = SoundcloudToken::from_response(response, Some(refresh_token.clone()));
+
+        Ok(Some(token))
+    }
+
+    async fn fetch(api_url: &str, params: &[(&str, &str)]) -> Result<TokenResponse, Error> {
+        let url = format!("{}/oauth2/token", api_url);
+        let response = reqwest::Client::new()
+            .post(&url)
+            .form(params)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Keeps the previous refresh token when the response doesn't rotate it
+    fn from_response(response: TokenResponse, refresh_token: Option<String>) -> Self {
+        SoundcloudToken {
+            access_token: response.access_token,
+            refresh_token: response.refresh_token.or(refresh_token),
+            expires_at: response.expires_in.map(|expires_in| now() + expires_in),
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{mock, Matcher};
+
+    use rustic_core::Credentials;
+
+    use super::{now, SoundcloudToken};
+
+    fn token(refresh_token: &str) -> SoundcloudToken {
+        SoundcloudToken {
+            access_token: "expired".into(),
+            refresh_token: Some(refresh_token.into()),
+            expires_at: Some(now()),
+        }
+    }
+
+    #[test]
+    fn from_credentials_should_read_stored_tokens() {
+        let credentials = Credentials::token(token("refresh")).unwrap();
+
+        let token = SoundcloudToken::from_credentials(credentials).unwrap();
+
+        assert_eq!(token.access_token, "expired");
+        assert_eq!(token.refresh_token, Some("refresh".into()));
+        assert!(token.is_expired());
+    }
+
+    #[test]
+    fn from_credentials_should_read_access_tokens_of_earlier_versions() {
+        let credentials = Credentials::Token("access".into());
+
+        let token = SoundcloudToken::from_credentials(credentials).unwrap();
+
+        assert_eq!(token.access_token, "access");
+        assert_eq!(token.refresh_token, None);
+        assert!(!token.expires_soon());
+    }
+
+    #[test]
+    fn from_credentials_should_ignore_passwords() {
+        let credentials = Credentials::password("user".into(), "password".into());
+
+        assert!(SoundcloudToken::from_credentials(credentials).is_none());
+    }
+
+    #[tokio::test]
+    async fn refresh_should_keep_the_refresh_token_when_it_is_not_rotated() {
+        let m = mock("POST", "/oauth2/token")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("grant_type".into(), "refresh_token".into()),
+                Matcher::UrlEncoded("client_id".into(), "client".into()),
+                Matcher::UrlEncoded("client_secret".into(), "secret".into()),
+                Matcher::UrlEncoded("refresh_token".into(), "kept".into()),
+            ]))
+            .with_status(200)
+            .with_body(r#"{"access_token":"access","expires_in":3600}"#)
+            .create();
+
+        let token = token("kept")
+            .refresh(&mockito::server_url(), "client", "secret")
+            .await
+            .unwrap()
+            .unwrap();
+
+        m.assert();
+        assert_eq!(token.access_token, "access");
+        assert_eq!(token.refresh_token, Some("kept".into()));
+        assert!(!token.expires_soon());
+    }
+
+    #[tokio::test]
+    async fn refresh_should_use_a_rotated_refresh_token() {
+        let _m = mock("POST", "/oauth2/token")
+            .match_body(Matcher::UrlEncoded(
+                "refresh_token".into(),
+                "rotated".into(),
+            ))
+            .with_status(200)
+            .with_body(r#"{"access_token":"access","refresh_token":"next","expires_in":3600}"#)
+            .create();
+
+        let token = token("rotated")
+            .refresh(&mockito::server_url(), "client", "secret")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(token.refresh_token, Some("next".into()));
+    }
+
+    #[tokio::test]
+    async fn refresh_should_fail_on_error_responses() {
+        let _m = mock("POST", "/oauth2/token")
+            .match_body(Matcher::UrlEncoded(
+                "refresh_token".into(),
+                "revoked".into(),
+            ))
+            .with_status(401)
+            .create();
+
+        let result = token("revoked")
+            .refresh(&mockito::server_url(), "client", "secret")
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn refresh_should_skip_tokens_without_refresh_token() {
+        let token = SoundcloudToken::new("access".into());
+
+        let token = token
+            .refresh(&mockito::server_url(), "client", "secret")
+            .await
+            .unwrap();
+
+        assert!(token.is_none());
+    }
+}
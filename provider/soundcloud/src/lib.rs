@@ -8,14 +8,16 @@ use async_trait::async_trait;
 use futures::prelude::*;
 use lazy_static::lazy_static;
 use rustic_core::library::{Album, Artist, Playlist, SharedLibrary, Track};
-use rustic_core::{provider, CredentialStore, Credentials, ProviderType};
+use rustic_core::{provider, CredentialStore, Credentials, ProviderId, Rating};
 
+use crate::auth::SoundcloudToken;
 use crate::playlist::SoundcloudPlaylist;
 use crate::track::SoundcloudTrack;
 use crate::user::SoundcloudUser;
 use soundcloud::StreamingApiExt;
 use std::collections::HashMap;
 
+mod auth;
 mod error;
 mod meta;
 mod playlist;
@@ -34,22 +36,35 @@ lazy_static! {
             .unwrap();
 }
 
+const SOUNDCLOUD_API_URL: &str = "https://api.soundcloud.com";
+
 const TRACK_URI_PREFIX: &str = "soundcloud://track/";
 const USER_URI_PREFIX: &str = "soundcloud://user/";
 
+pub(crate) const PROVIDER_ID: ProviderId = ProviderId::new("soundcloud");
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct SoundcloudProvider {
     client_id: String,
+    /// Enables the authorization code flow which allows refreshing of expired tokens
+    #[serde(default)]
+    client_secret: Option<String>,
+    /// Token of earlier versions, seeds the stored credentials of the default account
     auth_token: Option<String>,
+    #[serde(skip)]
+    token: Option<SoundcloudToken>,
 }
 
 impl SoundcloudProvider {
     pub fn new() -> Option<Self> {
         let client_id = option_env!("SOUNDCLOUD_CLIENT_ID");
+        let client_secret = option_env!("SOUNDCLOUD_CLIENT_SECRET");
 
         client_id.map(|client_id| SoundcloudProvider {
             client_id: client_id.into(),
+            client_secret: client_secret.map(String::from),
             auth_token: None,
+            token: None,
         })
     }
 }
@@ -57,12 +72,32 @@ impl SoundcloudProvider {
 impl SoundcloudProvider {
     fn client(&self) -> soundcloud::Client {
         let mut client = soundcloud::Client::new(self.client_id.as_str());
-        if let Some(token) = self.auth_token.as_ref() {
-            client.authenticate_with_token(token.clone());
+        if let Some(token) = self.access_token() {
+            client.authenticate_with_token(token.to_string());
         }
         client
     }
 
+    fn access_token(&self) -> Option<&str> {
+        self.token
+            .as_ref()
+            .filter(|token| !token.is_expired())
+            .map(|token| token.access_token.as_str())
+    }
+
+    async fn store_token(
+        &mut self,
+        token: SoundcloudToken,
+        cred_store: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        cred_store
+            .store_credentials(PROVIDER_ID, Credentials::token(&token)?)
+            .await?;
+        self.token = Some(token);
+
+        Ok(())
+    }
+
     async fn get_playlist(&self, uri: &str) -> Result<Option<SoundcloudPlaylist>, Error> {
         ensure!(
             uri.starts_with("soundcloud://playlist/"),
@@ -143,11 +178,18 @@ impl SoundcloudProvider {
 #[async_trait]
 impl provider::ProviderInstance for SoundcloudProvider {
     async fn setup(&mut self, cred_store: &dyn CredentialStore) -> Result<(), Error> {
-        if let Some(Credentials::Token(token)) = cred_store
-            .get_credentials(provider::ProviderType::Soundcloud)
-            .await?
-        {
-            self.auth_token = Some(token);
+        let credentials = cred_store.get_credentials(PROVIDER_ID).await?;
+        let is_default_account = cred_store.account(PROVIDER_ID).instance().is_none();
+        match (credentials, self.auth_token.clone()) {
+            (Some(credentials), _) => {
+                self.token = SoundcloudToken::from_credentials(credentials);
+            }
+            (None, Some(token)) if is_default_account => {
+                warn!("Moved the auth_token into the credential store, remove it from the config");
+                self.store_token(SoundcloudToken::new(token), cred_store)
+                    .await?;
+            }
+            _ => self.token = None,
         }
         Ok(())
     }
@@ -160,13 +202,18 @@ impl provider::ProviderInstance for SoundcloudProvider {
         "soundcloud"
     }
 
-    fn provider(&self) -> provider::ProviderType {
-        provider::ProviderType::Soundcloud
+    fn provider(&self) -> provider::ProviderId {
+        PROVIDER_ID
     }
 
     fn state(&self) -> provider::ProviderState {
-        if self.auth_token.is_some() {
+        if self.access_token().is_some() {
             provider::ProviderState::Authenticated(None)
+        } else if self.client_secret.is_some() {
+            provider::ProviderState::RequiresOAuth(format!(
+                "https://soundcloud.com/connect?client_id={}&response_type=code&redirect_uri={}",
+                &self.client_id, SOUNDCLOUD_REDIRECT_URI
+            ))
         } else {
             provider::ProviderState::RequiresOAuth(
                 format!("https://soundcloud.com/connect?client_id={}&response_type=token&redirect_uri={}/api/auth/soundcloud", &self.client_id, SOUNDCLOUD_REDIRECT_URI)
@@ -180,19 +227,43 @@ impl provider::ProviderInstance for SoundcloudProvider {
         cred_store: &dyn CredentialStore,
     ) -> Result<(), Error> {
         use provider::Authentication::*;
-        match auth {
-            Token(token) => {
-                self.auth_token = Some(token.clone());
-                cred_store
-                    .store_credentials(
-                        provider::ProviderType::Soundcloud,
-                        Credentials::Token(token),
-                    )
-                    .await?;
-                Ok(())
+        let token = match (auth, self.client_secret.as_ref()) {
+            (Token(code), Some(client_secret))
+            | (TokenWithState(code, _), Some(client_secret)) => {
+                SoundcloudToken::request(SOUNDCLOUD_API_URL, &self.client_id, client_secret, &code)
+                    .await?
             }
-            _ => Err(format_err!("Invalid authentication method")),
+            (Token(token), None) => SoundcloudToken::new(token),
+            _ => return Err(format_err!("Invalid authentication method")),
+        };
+        self.store_token(token, cred_store).await
+    }
+
+    async fn logout(&mut self) -> Result<(), Error> {
+        self.token = None;
+        Ok(())
+    }
+
+    async fn refresh_authentication(
+        &mut self,
+        cred_store: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        let client_secret = match self.client_secret {
+            Some(ref client_secret) => client_secret,
+            None => return Ok(()),
+        };
+        let token = match self.token {
+            Some(ref token) if token.expires_soon() => token,
+            _ => return Ok(()),
+        };
+        trace!("Refreshing soundcloud token");
+        let token = token
+            .refresh(SOUNDCLOUD_API_URL, &self.client_id, client_secret)
+            .await?;
+        if let Some(token) = token {
+            self.store_token(token, cred_store).await?;
         }
+        Ok(())
     }
 
     async fn sync(&self, library: SharedLibrary) -> Result<provider::SyncResult, Error> {
@@ -298,7 +369,7 @@ impl provider::ProviderInstance for SoundcloudProvider {
             name: user.username,
             playlists,
             albums,
-            provider: ProviderType::Soundcloud,
+            provider: PROVIDER_ID,
             meta: HashMap::new(),
             image_url: Some(user.avatar_url.replace("large", "t500x500")),
             uri: format!("soundcloud://user/{}", user.id),
@@ -313,7 +384,7 @@ impl provider::ProviderInstance for SoundcloudProvider {
     }
 
     async fn stream_url(&self, track: &Track) -> Result<String, Error> {
-        if track.provider == provider::ProviderType::Soundcloud {
+        if track.provider == PROVIDER_ID {
             if let rustic_core::library::MetaValue::String(stream_url) =
                 track.meta.get(meta::META_SOUNDCLOUD_STREAM_URL).unwrap()
             {
@@ -332,6 +403,33 @@ impl provider::ProviderInstance for SoundcloudProvider {
         Err(format_err!("Invalid provider: {:?}", track.provider))
     }
 
+    /// Liked tracks are added to the soundcloud likes, soundcloud has no dislikes or stars
+    async fn set_rating(&self, track: &Track, rating: Rating) -> Result<(), Error> {
+        let token = self
+            .access_token()
+            .ok_or_else(|| format_err!("Soundcloud is not authenticated"))?;
+        ensure!(
+            track.uri.starts_with(TRACK_URI_PREFIX),
+            "Invalid Uri: {}",
+            track.uri
+        );
+        let id = &track.uri[TRACK_URI_PREFIX.len()..];
+        let url = format!("{}/likes/tracks/{}", SOUNDCLOUD_API_URL, id);
+        let client = reqwest::Client::new();
+        let request = match rating {
+            Rating::Like => client.post(&url),
+            Rating::Dislike | Rating::None => client.delete(&url),
+            Rating::Stars(_) => return Ok(()),
+        };
+        request
+            .header("Authorization", format!("OAuth {}", token))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
     async fn resolve_share_url(
         &self,
         url: url::Url,
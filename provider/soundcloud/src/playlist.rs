@@ -1,6 +1,7 @@
 use rustic_core::library::{Playlist, Track};
 use rustic_core::{provider, Album};
 
+use crate::PROVIDER_ID;
 use crate::track::SoundcloudTrack;
 use crate::user::SoundcloudUser;
 use rustic_core::provider::ThumbnailState;
@@ -28,7 +29,7 @@ impl From<SoundcloudPlaylist> for Playlist {
             id: None,
             title: playlist.title,
             tracks: playlist.tracks,
-            provider: provider::ProviderType::Soundcloud,
+            provider: PROVIDER_ID,
             uri: format!("soundcloud://playlist/{}", playlist.id),
         }
     }
@@ -40,7 +41,7 @@ impl From<SoundcloudPlaylist> for Album {
             id: None,
             title: playlist.title,
             tracks: playlist.tracks,
-            provider: provider::ProviderType::Soundcloud,
+            provider: PROVIDER_ID,
             uri: format!("soundcloud://playlist/{}", playlist.id),
             thumbnail: playlist
                 .artwork_url
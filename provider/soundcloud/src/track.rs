@@ -3,6 +3,7 @@ use maplit::hashmap;
 use rustic_core::library::{Lyrics, Track};
 use rustic_core::{provider, Rating};
 
+use crate::PROVIDER_ID;
 use crate::meta::*;
 use crate::user::SoundcloudUser;
 use rustic_core::provider::ThumbnailState;
@@ -27,7 +28,7 @@ impl From<SoundcloudTrack> for Track {
             artist_id: None,
             album: None,
             album_id: None,
-            provider: provider::ProviderType::Soundcloud,
+            provider: PROVIDER_ID,
             uri: format!("soundcloud://track/{}", track.id),
             thumbnail: track
                 .artwork_url
@@ -1,7 +1,8 @@
 use maplit::hashmap;
 
-use rustic_core::{provider::ProviderItem, Artist, ProviderType};
+use rustic_core::{provider::ProviderItem, Artist};
 
+use crate::PROVIDER_ID;
 use crate::meta::META_SOUNDCLOUD_USER_ID;
 
 #[derive(Debug, Clone)]
@@ -24,7 +25,7 @@ impl From<SoundcloudUser> for Artist {
             meta: hashmap!(
                 META_SOUNDCLOUD_USER_ID.into() => user.id.into()
             ),
-            provider: ProviderType::Soundcloud,
+            provider: PROVIDER_ID,
             albums: Vec::new(),
             playlists: Vec::new(),
             description: user.description,
@@ -6,6 +6,7 @@ use serde_derive::{Deserialize, Serialize};
 use rustic_core::library::Album;
 use rustic_core::provider;
 
+use crate::PROVIDER_ID;
 use crate::util::*;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -24,7 +25,7 @@ impl From<SpotifyFullAlbum> for Album {
             artist_id: None,
             artist,
             tracks: vec![],
-            provider: provider::ProviderType::Spotify,
+            provider: PROVIDER_ID,
             thumbnail: convert_images(&album.images),
             uri: format!("spotify://album/{}", album.id),
             meta: HashMap::new(),
@@ -43,7 +44,7 @@ impl From<SpotifySimplifiedAlbum> for Album {
             title: album.name,
             artist_id: None,
             artist,
-            provider: provider::ProviderType::Spotify,
+            provider: PROVIDER_ID,
             thumbnail: convert_images(&album.images),
             tracks: vec![],
             uri: album
@@ -5,7 +5,7 @@ use serde_derive::{Deserialize, Serialize};
 
 use rustic_core::library::Artist;
 
-use rustic_core::ProviderType;
+use crate::PROVIDER_ID;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SpotifyFullArtist(FullArtist);
@@ -22,7 +22,7 @@ impl From<SpotifyFullArtist> for Artist {
             image_url: artist.images.first().map(|image| image.url.clone()),
             uri: format!("spotify://artist/{}", artist.id),
             meta: HashMap::new(),
-            provider: ProviderType::Spotify,
+            provider: PROVIDER_ID,
             albums: Vec::new(),
             playlists: Vec::new(),
             description: None,
@@ -42,7 +42,7 @@ impl From<SpotifySimplifiedArtist> for Artist {
                 .map(|id| format!("spotify://artist/{}", id))
                 .unwrap(),
             meta: HashMap::new(),
-            provider: ProviderType::Spotify,
+            provider: PROVIDER_ID,
             albums: Vec::new(),
             playlists: Vec::new(),
             description: None,
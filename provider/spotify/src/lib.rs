@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use chrono::Utc;
 use failure::{Error, format_err};
 use log::trace;
 use rspotify::{AuthCodeSpotify, OAuth, Token};
@@ -6,7 +7,7 @@ use rspotify::model::{AlbumId, ArtistId, PlaylistId, SearchResult, SearchType, T
 use rspotify::prelude::*;
 use serde_derive::Deserialize;
 
-use rustic_core::{CredentialStore, provider};
+use rustic_core::{auth, CredentialStore, Credentials, provider, Rating};
 use rustic_core::library::{Album, Artist, MetaValue, Playlist, SharedLibrary, Track};
 
 use crate::album::*;
@@ -26,6 +27,8 @@ mod util;
 // TODO: configurable host
 const SPOTIFY_REDIRECT_URI: &str = "http://localhost:8080/api/providers/spotify/auth/redirect";
 
+pub(crate) const PROVIDER_ID: provider::ProviderId = provider::ProviderId::new("spotify");
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct SpotifyProvider {
     client_id: String,
@@ -115,6 +118,7 @@ impl SpotifyProvider {
             redirect_uri: SPOTIFY_REDIRECT_URI.to_string(),
             scopes: rspotify::scopes!(
                 "user-library-read",
+                "user-library-modify",
                 "playlist-read-private",
                 "user-top-read",
                 "user-read-recently-played",
@@ -124,32 +128,76 @@ impl SpotifyProvider {
         };
         let credentials = rspotify::Credentials::new(&self.client_id, &self.client_secret);
 
-        let mut spotify = AuthCodeSpotify::new(credentials, oauth);
-        spotify.config.token_cached = true;
-        spotify.token = match Token::from_cache(&spotify.config.cache_path) {
+        let spotify = AuthCodeSpotify::new(credentials, oauth);
+        Ok(spotify)
+    }
+
+    /// Loads the token from the credential store
+    ///
+    /// The token cached by earlier versions is moved into the store of the default account,
+    /// the cache file is removed so a logout can't be undone by migrating it again.
+    async fn load_token(
+        &self,
+        client: &AuthCodeSpotify,
+        cred_store: &dyn CredentialStore,
+    ) -> Result<Option<Token>, Error> {
+        if let Some(credentials) = cred_store.get_credentials(PROVIDER_ID).await? {
+            let token = credentials.get_token()?;
+            return Ok(Some(token));
+        }
+        if cred_store.account(PROVIDER_ID).instance().is_some() {
+            return Ok(None);
+        }
+        let cache_path = &client.config.cache_path;
+        match Token::from_cache(cache_path) {
             Ok(token) => {
-                Some(token)
+                SpotifyProvider::store_token(&token, cred_store).await?;
+                if let Err(err) = std::fs::remove_file(cache_path) {
+                    log::warn!("Could not remove cached spotify token {:?}", err);
+                }
+                Ok(Some(token))
             }
             Err(err) => {
-                log::warn!("Loading spotify token failed {:?}", err);
-                None
+                log::debug!("No cached spotify token {:?}", err);
+                Ok(None)
             }
-        };
-        Ok(spotify)
+        }
     }
+
+    async fn store_token(token: &Token, cred_store: &dyn CredentialStore) -> Result<(), Error> {
+        cred_store
+            .store_credentials(PROVIDER_ID, Credentials::token(token)?)
+            .await
+    }
+}
+
+/// Tokens are refreshed ahead of time so they don't expire between two refreshes
+fn expires_before_next_refresh(token: &Token) -> bool {
+    let refresh_interval = chrono::Duration::from_std(auth::REFRESH_INTERVAL).unwrap();
+    token
+        .expires_at
+        .map_or(true, |expires_at| expires_at <= Utc::now() + refresh_interval)
 }
 
 #[async_trait]
 impl rustic_core::provider::ProviderInstance for SpotifyProvider {
     async fn setup(&mut self, cred_store: &dyn CredentialStore) -> Result<(), Error> {
-        let client = self.get_oauth_client()?;
+        let mut client = self.get_oauth_client()?;
+        client.token = self.load_token(&client, cred_store).await?;
         self.client = Some(client);
 
         Ok(())
     }
 
     fn state(&self) -> provider::ProviderState {
-        if let Some(_) = self.client.as_ref().and_then(|client| client.get_token()) {
+        let authenticated = self
+            .client
+            .as_ref()
+            .and_then(|client| client.get_token())
+            .map_or(false, |token| {
+                !token.is_expired() || token.refresh_token.is_some()
+            });
+        if authenticated {
             provider::ProviderState::Authenticated(None)
         } else if let Some(ref client) = self.client {
             let auth_url = client.get_authorize_url(false).unwrap();
@@ -169,7 +217,6 @@ impl rustic_core::provider::ProviderInstance for SpotifyProvider {
             Token(token) => {
                 if let Some(ref mut client) = self.client {
                     client.request_token(&token).await?;
-                    Ok(())
                 } else {
                     unreachable!()
                 }
@@ -180,13 +227,45 @@ impl rustic_core::provider::ProviderInstance for SpotifyProvider {
                         return Err(format_err!("Invalid token state"));
                     }
                     client.request_token(&token).await?;
-                    Ok(())
                 } else {
                     unreachable!()
                 }
             }
-            _ => Err(format_err!("Invalid authentication method")),
+            _ => return Err(format_err!("Invalid authentication method")),
+        }
+        if let Some(token) = self.client.as_ref().and_then(|client| client.get_token()) {
+            SpotifyProvider::store_token(token, cred_store).await?;
         }
+        Ok(())
+    }
+
+    async fn logout(&mut self) -> Result<(), Error> {
+        if let Some(ref mut client) = self.client {
+            client.token = None;
+        }
+        Ok(())
+    }
+
+    async fn refresh_authentication(
+        &mut self,
+        cred_store: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        let client = match self.client {
+            Some(ref mut client) => client,
+            None => return Ok(()),
+        };
+        let refresh_token = match client.get_token() {
+            Some(token) if expires_before_next_refresh(token) => token.refresh_token.clone(),
+            _ => return Ok(()),
+        };
+        if let Some(refresh_token) = refresh_token {
+            log::debug!("Refreshing spotify token");
+            client.refresh_token(&refresh_token).await?;
+            if let Some(token) = client.get_token() {
+                SpotifyProvider::store_token(token, cred_store).await?;
+            }
+        }
+        Ok(())
     }
 
     fn title(&self) -> &'static str {
@@ -197,8 +276,8 @@ impl rustic_core::provider::ProviderInstance for SpotifyProvider {
         "spotify"
     }
 
-    fn provider(&self) -> provider::ProviderType {
-        provider::ProviderType::Spotify
+    fn provider(&self) -> provider::ProviderId {
+        PROVIDER_ID
     }
 
     async fn sync(&self, library: SharedLibrary) -> Result<provider::SyncResult, Error> {
@@ -327,4 +406,26 @@ impl rustic_core::provider::ProviderInstance for SpotifyProvider {
     ) -> Result<Option<provider::InternalUri>, Error> {
         Ok(None)
     }
+
+    /// Liked tracks are saved to the library of the user
+    async fn set_rating(&self, track: &Track, rating: Rating) -> Result<(), Error> {
+        let spotify = self
+            .client
+            .as_ref()
+            .ok_or_else(|| format_err!("Spotify is not authenticated"))?;
+        let id = track
+            .uri
+            .strip_prefix("spotify://track/")
+            .ok_or_else(|| format_err!("Invalid spotify track uri {}", track.uri))?;
+        let id = TrackId::from_id(id)?;
+        match rating {
+            Rating::Like => spotify.current_user_saved_tracks_add(vec![&id]).await?,
+            Rating::Dislike | Rating::None => {
+                spotify.current_user_saved_tracks_delete(vec![&id]).await?
+            }
+            Rating::Stars(_) => {}
+        }
+
+        Ok(())
+    }
 }
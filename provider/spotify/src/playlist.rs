@@ -2,8 +2,9 @@ use rspotify::model::PlayableItem;
 use rspotify::model::playlist::*;
 use serde_derive::{Deserialize, Serialize};
 
-use rustic_core::{Playlist, ProviderType, Track};
+use rustic_core::{Playlist, Track};
 
+use crate::PROVIDER_ID;
 use crate::track::SpotifyFullTrack;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -22,7 +23,7 @@ impl From<SpotifyPlaylist> for Playlist {
         Playlist {
             id: None,
             title: playlist.name,
-            provider: ProviderType::Spotify,
+            provider: PROVIDER_ID,
             uri: format!("spotify://playlists/{}", playlist.id),
             tracks: playlist
                 .tracks
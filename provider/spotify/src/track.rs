@@ -10,6 +10,7 @@ use rustic_core::library::{Album, Lyrics, MetaValue, Track, TrackPosition};
 use rustic_core::provider::ThumbnailState;
 use rustic_core::{provider, Rating};
 
+use crate::PROVIDER_ID;
 use crate::meta::*;
 use crate::util::*;
 
@@ -47,7 +48,7 @@ impl From<SpotifyFullTrack> for Track {
                 artist_id: None,
                 artist,
                 tracks: vec![],
-                provider: provider::ProviderType::Spotify,
+                provider: PROVIDER_ID,
                 thumbnail: convert_images(&track.album.images),
                 uri: track
                     .album
@@ -61,7 +62,7 @@ impl From<SpotifyFullTrack> for Track {
                 explicit: None,
                 description: None,
             }),
-            provider: provider::ProviderType::Spotify,
+            provider: PROVIDER_ID,
             thumbnail: convert_images(&track.album.images),
             uri: track
                 .id
@@ -107,7 +108,7 @@ impl From<SpotifySimplifiedTrack> for Track {
             artist,
             album_id: None,
             album: None,
-            provider: provider::ProviderType::Spotify,
+            provider: PROVIDER_ID,
             thumbnail: ThumbnailState::None,
             uri: track
                 .id
@@ -5,7 +5,8 @@ use rspotify::model::image::Image;
 
 use rustic_core::library::Artist;
 use rustic_core::provider::ThumbnailState;
-use rustic_core::ProviderType;
+
+use crate::PROVIDER_ID;
 
 pub fn convert_images(images: &[Image]) -> ThumbnailState {
     images
@@ -39,7 +40,7 @@ pub fn artists_to_artist(artists: Vec<SimplifiedArtist>) -> Option<Artist> {
         uri,
         image_url: None,
         meta: HashMap::new(),
-        provider: ProviderType::Spotify,
+        provider: PROVIDER_ID,
         albums: Vec::new(),
         playlists: Vec::new(),
         description: None,
@@ -4,7 +4,7 @@ use serde::Deserialize;
 use tokio::sync::Mutex;
 use url::Url;
 use youtube_api::models::{
-    ListPlaylistItemsRequestBuilder, ListPlaylistsRequestBuilder, SearchRequestBuilder,
+    ListPlaylistItemsRequestBuilder, ListPlaylistsRequestBuilder, SearchRequestBuilder, VideoRating,
 };
 use youtube_api::{YoutubeApi, YoutubeDl};
 
@@ -14,7 +14,9 @@ use rustic_core::provider::{
     Authentication, InternalUri, ProviderFolder, ProviderInstance, ProviderItem, ProviderState,
     SyncResult,
 };
-use rustic_core::{Album, Artist, CredentialStore, Playlist, ProviderType, SharedLibrary, Track};
+use rustic_core::{
+    Album, Artist, CredentialStore, Credentials, Playlist, ProviderId, Rating, SharedLibrary, Track,
+};
 
 use crate::playlist::{PlaylistWithItems, YoutubePlaylist};
 use crate::playlist_item::YoutubePlaylistItem;
@@ -45,6 +47,11 @@ lazy_static! {
 // TODO: configurable host
 const YOUTUBE_REDIRECT_URI: &str = "http://localhost:8080/api/providers/youtube/auth/redirect";
 
+pub(crate) const PROVIDER_ID: ProviderId = ProviderId::new("youtube");
+
+/// Where youtube-api stored the token before tokens moved into the credential store
+const LEGACY_TOKEN_FILE: &str = ".youtube-token.json";
+
 const VIDEO_URI_PREFIX: &str = "youtube://video/";
 const PLAYLIST_URI_PREFIX: &str = "youtube://playlist/";
 const CHANNEL_URI_PREFIX: &str = "youtube://channel/";
@@ -81,11 +88,38 @@ impl YoutubeProvider {
 
         Ok(id)
     }
+
+    /// Reads the token from the credential store
+    ///
+    /// The token file of earlier versions is moved into the store of the default account,
+    /// afterwards it is removed so a logout can't be undone by migrating it again.
+    async fn load_token(api: &YoutubeApi, cred_store: &dyn CredentialStore) -> Result<(), Error> {
+        if let Some(credentials) = cred_store.get_credentials(PROVIDER_ID).await? {
+            api.set_token(credentials.get_token()?).await;
+        } else if cred_store.account(PROVIDER_ID).instance().is_none()
+            && api.load_token().await.is_ok()
+        {
+            YoutubeProvider::store_token(api, cred_store).await?;
+            if let Err(err) = std::fs::remove_file(LEGACY_TOKEN_FILE) {
+                warn!("Could not remove token file {:?}", err);
+            }
+        }
+        Ok(())
+    }
+
+    async fn store_token(api: &YoutubeApi, cred_store: &dyn CredentialStore) -> Result<(), Error> {
+        if let Some(token) = api.get_token().await {
+            cred_store
+                .store_credentials(PROVIDER_ID, Credentials::token(token)?)
+                .await?;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl ProviderInstance for YoutubeProvider {
-    async fn setup(&mut self, _cred_store: &dyn CredentialStore) -> Result<(), Error> {
+    async fn setup(&mut self, cred_store: &dyn CredentialStore) -> Result<(), Error> {
         self.client = match (
             self.api_key.as_ref(),
             self.client_id.as_ref(),
@@ -99,7 +133,7 @@ impl ProviderInstance for YoutubeProvider {
                     client_secret.clone(),
                     Some(YOUTUBE_REDIRECT_URI),
                 )?;
-                if let Err(err) = api.load_token().await {
+                if let Err(err) = YoutubeProvider::load_token(&api, cred_store).await {
                     warn!("Could not load previous token {}", err);
                 }
                 Some(api)
@@ -118,8 +152,8 @@ impl ProviderInstance for YoutubeProvider {
         "youtube"
     }
 
-    fn provider(&self) -> ProviderType {
-        ProviderType::Youtube
+    fn provider(&self) -> ProviderId {
+        PROVIDER_ID
     }
 
     fn state(&self) -> ProviderState {
@@ -140,7 +174,7 @@ impl ProviderInstance for YoutubeProvider {
     async fn authenticate(
         &mut self,
         auth: Authentication,
-        _cred_store: &dyn CredentialStore,
+        cred_store: &dyn CredentialStore,
     ) -> Result<(), Error> {
         let client = self.client.as_mut().expect("client isn't setup yet");
         use rustic_core::provider::Authentication::*;
@@ -153,13 +187,31 @@ impl ProviderInstance for YoutubeProvider {
                     .ok_or_else(|| format_err!("Missing state"))?;
                 debug!("State: {}", state);
                 client.request_token(token, state).await?;
-                client.store_token().await?;
+                YoutubeProvider::store_token(client, cred_store).await?;
                 Ok(())
             }
             _ => Err(format_err!("Invalid authentication method")),
         }
     }
 
+    async fn logout(&mut self) -> Result<(), Error> {
+        if let Some(client) = self.client.as_ref() {
+            client.clear_token().await;
+        }
+        Ok(())
+    }
+
+    async fn refresh_authentication(
+        &mut self,
+        cred_store: &dyn CredentialStore,
+    ) -> Result<(), Error> {
+        if let Some(client) = self.client.as_ref().filter(|client| client.has_token()) {
+            client.refresh_token().await?;
+            YoutubeProvider::store_token(client, cred_store).await?;
+        }
+        Ok(())
+    }
+
     async fn sync(&self, library: SharedLibrary) -> Result<SyncResult, Error> {
         if let Some(client) = self.client.as_ref() {
             let request = ListPlaylistsRequestBuilder {
@@ -251,6 +303,24 @@ impl ProviderInstance for YoutubeProvider {
         Ok(url)
     }
 
+    async fn set_rating(&self, track: &Track, rating: Rating) -> Result<(), Error> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| format_err!("client is not configured"))?;
+        let id = self.get_youtube_id(&track.uri)?;
+        let rating = match rating {
+            Rating::Like => VideoRating::Like,
+            Rating::Dislike => VideoRating::Dislike,
+            Rating::None => VideoRating::None,
+            // Stars have no equivalent on youtube and are only stored in the library
+            Rating::Stars(_) => return Ok(()),
+        };
+        client.rate_video(id, rating).await?;
+
+        Ok(())
+    }
+
     async fn resolve_share_url(&self, url: Url) -> Result<Option<InternalUri>, Error> {
         if url.host_str() == Some("music.youtube.com") {
             return Ok(None);
@@ -1,7 +1,8 @@
 use youtube_api::models::PlaylistResource;
 
+use crate::PROVIDER_ID;
 use crate::playlist_item::YoutubePlaylistItem;
-use rustic_core::{Playlist, ProviderType, Track};
+use rustic_core::{Playlist, Track};
 
 #[derive(Clone)]
 pub struct YoutubePlaylist(PlaylistResource);
@@ -28,7 +29,7 @@ impl From<PlaylistWithItems> for Playlist {
         Playlist {
             id: None,
             tracks,
-            provider: ProviderType::Youtube,
+            provider: PROVIDER_ID,
             title: resource.snippet.title,
             uri: format!("youtube://playlist/{}", &resource.id),
         }
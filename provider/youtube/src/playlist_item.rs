@@ -2,9 +2,11 @@ use youtube_api::models::PlaylistItemResource;
 
 use rustic_core::library::Lyrics;
 use rustic_core::provider::ThumbnailState;
-use rustic_core::{Artist, ProviderType, Rating, Track};
+use rustic_core::{Artist, Rating, Track};
 use std::collections::HashMap;
 
+use crate::PROVIDER_ID;
+
 #[derive(Clone)]
 pub struct YoutubePlaylistItem(PlaylistItemResource);
 
@@ -33,7 +35,7 @@ impl From<YoutubePlaylistItem> for Track {
         let id = resource.snippet.resource_id.into_inner();
         Track {
             id: None,
-            provider: ProviderType::Youtube,
+            provider: PROVIDER_ID,
             title: resource.snippet.inner.title,
             uri: format!("youtube://video/{}", &id),
             album_id: None,
@@ -46,7 +48,7 @@ impl From<YoutubePlaylistItem> for Track {
                 name: resource.snippet.inner.channel_title,
                 image_url: None,
                 meta: HashMap::new(),
-                provider: ProviderType::Youtube,
+                provider: PROVIDER_ID,
                 albums: vec![],
                 playlists: vec![],
                 description: None,
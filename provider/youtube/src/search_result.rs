@@ -1,9 +1,11 @@
 use rustic_core::library::Lyrics;
 use rustic_core::provider::{ProviderItem, ProviderItemType, ThumbnailState};
-use rustic_core::{Artist, Playlist, ProviderType, Rating, Track};
+use rustic_core::{Artist, Playlist, Rating, Track};
 use std::collections::HashMap;
 use youtube_api::models::{Id, SearchResult};
 
+use crate::PROVIDER_ID;
+
 #[derive(Clone)]
 pub struct YoutubeSearchResult(SearchResult);
 
@@ -54,7 +56,7 @@ impl From<YoutubeSearchResult> for Track {
             uri: format!("youtube://video/{}", &id),
             duration: None,
             thumbnail,
-            provider: ProviderType::Youtube,
+            provider: PROVIDER_ID,
             artist_id: None,
             artist: Some(Artist {
                 meta: HashMap::new(),
@@ -62,7 +64,7 @@ impl From<YoutubeSearchResult> for Track {
                 uri: format!("youtube://author/{}", &result.snippet.channel_id),
                 name: result.snippet.channel_title,
                 image_url: None,
-                provider: ProviderType::Youtube,
+                provider: PROVIDER_ID,
                 albums: vec![],
                 playlists: vec![],
                 description: None,
@@ -90,7 +92,7 @@ impl From<YoutubeSearchResult> for Artist {
             id: None,
             name: result.snippet.title,
             uri: format!("youtube://author/{}", result.id.into_inner()),
-            provider: ProviderType::Youtube,
+            provider: PROVIDER_ID,
             playlists: Vec::new(),
             albums: Vec::new(),
             image_url: thumbnail.map(|thumbnail| thumbnail.url.clone()),
@@ -108,7 +110,7 @@ impl From<YoutubeSearchResult> for Playlist {
             id: None,
             title: result.snippet.title,
             uri: format!("youtube://playlist/{}", result.id.into_inner()),
-            provider: ProviderType::Youtube,
+            provider: PROVIDER_ID,
             tracks: Vec::new(),
         }
     }
@@ -1,10 +1,12 @@
 use rustic_core::library::Lyrics;
 use rustic_core::provider::ThumbnailState;
-use rustic_core::{Artist, ProviderType, Rating, Track};
+use rustic_core::{Artist, Rating, Track};
 use std::collections::HashMap;
 use std::str::FromStr;
 use youtube_api::models;
 
+use crate::PROVIDER_ID;
+
 pub(crate) struct YoutubeVideoMetadata(models::VideoMetadata);
 
 impl YoutubeVideoMetadata {
@@ -36,7 +38,7 @@ impl From<YoutubeVideoMetadata> for Track {
                 id: None,
                 image_url: None,
                 meta: HashMap::new(),
-                provider: ProviderType::Youtube,
+                provider: PROVIDER_ID,
                 albums: vec![],
                 playlists: vec![],
                 description: None,
@@ -44,7 +46,7 @@ impl From<YoutubeVideoMetadata> for Track {
             album: None,
             album_id: None,
             artist_id: None,
-            provider: ProviderType::Youtube,
+            provider: PROVIDER_ID,
             uri: format!("youtube://video/{}", video.video_details.video_id),
             thumbnail,
             id: None,
@@ -4,7 +4,7 @@ use serde::Deserialize;
 use url::Url;
 use youtube_api::YoutubeDl;
 
-use rustic_core::{Credentials, CredentialStore, InternalUri, Playlist, provider, ProviderType, Rating, TrackPosition};
+use rustic_core::{Credentials, CredentialStore, InternalUri, Playlist, provider, ProviderId, Rating, TrackPosition};
 use rustic_core::library::{Album, Artist, Lyrics, SharedLibrary, Track};
 use rustic_core::provider::{Authentication, ProviderFolder, ProviderItem, ProviderState, SyncResult, ThumbnailState};
 use ytmusic::YoutubeMusicClient;
@@ -15,6 +15,8 @@ const ALBUM_URI_PREFIX: &str = "ytmusic://album/";
 const TRACK_URI_PREFIX: &str = "ytmusic://track/";
 const PLAYLIST_URI_PREFIX: &str = "ytmusic://playlist/";
 
+pub(crate) const PROVIDER_ID: ProviderId = ProviderId::new("ytmusic");
+
 mod mappings;
 
 #[derive(Debug, Default, Clone, Deserialize)]
@@ -123,8 +125,8 @@ impl provider::ProviderInstance for YouTubeMusicProvider {
         "ytmusic"
     }
 
-    fn provider(&self) -> ProviderType {
-        ProviderType::YouTubeMusic
+    fn provider(&self) -> ProviderId {
+        PROVIDER_ID
     }
 
     fn state(&self) -> ProviderState {
@@ -1,13 +1,15 @@
-use rustic_core::{Album, Artist, ProviderType};
+use rustic_core::{Album, Artist};
 use super::{map_playlist_item, map_thumbnail};
 
+use crate::PROVIDER_ID;
+
 pub fn map_album(album: ytmusic::BrowseAlbum) -> Album {
     let thumbnail = map_thumbnail(album.thumbnails);
 
     Album {
         id: None,
         title: album.title,
-        provider: ProviderType::YouTubeMusic,
+        provider: PROVIDER_ID,
         uri: format!("ytmusic://album/{}", album.id),
         tracks: album.tracks.into_iter()
             .map(map_playlist_item)
@@ -21,7 +23,7 @@ pub fn map_album(album: ytmusic::BrowseAlbum) -> Album {
             id: None,
             name: artist.name.clone(),
             uri: format!("ytmusic://artist/{}", artist.id),
-            provider: ProviderType::YouTubeMusic,
+            provider: PROVIDER_ID,
             playlists: vec![],
             albums: vec![],
             image_url: None,
@@ -1,11 +1,13 @@
-use rustic_core::{Album, Artist, ProviderType};
+use rustic_core::{Album, Artist};
 use super::{map_playlist_item, map_thumbnail};
 
+use crate::PROVIDER_ID;
+
 pub fn map_artist(artist: ytmusic::BrowseArtist) -> Artist {
     Artist {
         id: None,
         name: artist.name,
-        provider: ProviderType::YouTubeMusic,
+        provider: PROVIDER_ID,
         uri: format!("ytmusic://artist/{}", artist.id),
         playlists: Default::default(),
         albums: Default::default(),
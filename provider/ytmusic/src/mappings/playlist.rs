@@ -1,13 +1,15 @@
 use rustic_core::library::Lyrics;
-use rustic_core::{Album, Artist, Playlist, ProviderType, Rating, Track};
+use rustic_core::{Album, Artist, Playlist, Rating, Track};
 use rustic_core::provider::ThumbnailState;
 use super::map_thumbnail;
 
+use crate::PROVIDER_ID;
+
 pub fn map_playlist(playlist: ytmusic::Playlist) -> Playlist {
     Playlist {
         id: None,
         title: playlist.title,
-        provider: ProviderType::YouTubeMusic,
+        provider: PROVIDER_ID,
         uri: format!("ytmusic://playlist/{}", playlist.id),
         tracks: playlist.tracks.into_iter()
             .map(map_playlist_item)
@@ -20,13 +22,13 @@ pub fn map_playlist_item(track: ytmusic::PlaylistItem) -> Track {
 
     Track {
         id: None,
-        provider: ProviderType::YouTubeMusic,
+        provider: PROVIDER_ID,
         uri: format!("ytmusic://track/{}", track.video_id),
         title: track.title,
         album: track.album.map(|album| Album {
             id: None,
             title: album.name,
-            provider: ProviderType::YouTubeMusic,
+            provider: PROVIDER_ID,
             uri: format!("ytmusic://album/{}", album.id),
             tracks: Default::default(),
             artist: None,
@@ -43,7 +45,7 @@ pub fn map_playlist_item(track: ytmusic::PlaylistItem) -> Track {
                 id: None,
                 name: artist.name.clone(),
                 description: None,
-                provider: ProviderType::YouTubeMusic,
+                provider: PROVIDER_ID,
                 meta: maplit::hashmap! {},
                 uri: format!("ytmusic://artist/{}", artist.id),
                 albums: vec![],
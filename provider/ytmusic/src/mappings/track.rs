@@ -1,13 +1,15 @@
 use rustic_core::library::Lyrics;
-use rustic_core::{Artist, ProviderType, Rating, Track};
+use rustic_core::{Artist, Rating, Track};
 use super::map_thumbnail;
 
+use crate::PROVIDER_ID;
+
 pub fn map_track(track: ytmusic::BrowseSong) -> Track {
     let thumbnail = map_thumbnail(track.video_details.thumbnail.thumbnails);
 
     Track {
         id: None,
-        provider: ProviderType::YouTubeMusic,
+        provider: PROVIDER_ID,
         uri: format!("ytmusic://track/{}", track.video_details.video_id),
         title: track.video_details.title,
         album: None,
@@ -17,7 +19,7 @@ pub fn map_track(track: ytmusic::BrowseSong) -> Track {
             id: None,
             name: track.video_details.author,
             description: None,
-            provider: ProviderType::YouTubeMusic,
+            provider: PROVIDER_ID,
             meta: maplit::hashmap! {},
             uri: Default::default(),
             albums: vec![],
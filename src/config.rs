@@ -2,7 +2,7 @@ use std::fs::File;
 use std::io::prelude::*;
 #[cfg(feature = "google-cast-backend")]
 use std::net::IpAddr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use rustic_extension_api::ExtensionConfigValue;
 
@@ -30,6 +30,8 @@ pub struct Config {
     pub discovery: DiscoveryConfig,
     #[serde(default)]
     pub client: ClientConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
 }
 
 impl Default for Config {
@@ -43,6 +45,7 @@ impl Default for Config {
             extensions: ExtensionConfig::default(),
             discovery: DiscoveryConfig::default(),
             client: ClientConfig::default(),
+            cache: CacheConfig::default(),
         }
     }
 }
@@ -125,6 +128,18 @@ pub struct ProviderConfig {
     #[cfg(feature = "ytmusic-provider")]
     #[serde(default = "rustic_ytmusic_provider::YouTubeMusicProvider::new")]
     pub ytmusic: Option<rustic_ytmusic_provider::YouTubeMusicProvider>,
+    /// Additional accounts of the configured providers
+    #[serde(default)]
+    pub accounts: Vec<ProviderAccountConfig>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ProviderAccountConfig {
+    /// Key of the provider, e.g. `spotify`
+    pub provider: String,
+    /// Has to be unique for the provider, credentials are stored by it
+    pub id: String,
+    pub name: Option<String>,
 }
 
 impl Default for ProviderConfig {
@@ -142,10 +157,70 @@ impl Default for ProviderConfig {
             youtube: rustic_youtube_provider::YoutubeProvider::new(),
             #[cfg(feature = "ytmusic-provider")]
             ytmusic: rustic_ytmusic_provider::YouTubeMusicProvider::new(),
+            accounts: Vec::new(),
         }
     }
 }
 
+#[derive(Deserialize, Clone, Debug)]
+pub struct CacheConfig {
+    /// Directory for cached tracks, defaults to the XDG cache dir
+    pub path: Option<PathBuf>,
+    /// Maximum size of the cached tracks in megabytes
+    #[serde(default = "default_cache_size")]
+    pub max_size: u64,
+    /// Number of upcoming queue entries to download ahead of time
+    #[serde(default = "default_prefetch")]
+    pub prefetch: usize,
+    /// Directory for cached cover art, defaults to the XDG cache dir
+    pub coverart_path: Option<PathBuf>,
+    /// Maximum size of the cached cover art in megabytes
+    #[serde(default = "default_coverart_cache_size")]
+    pub coverart_max_size: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            path: None,
+            max_size: default_cache_size(),
+            prefetch: default_prefetch(),
+            coverart_path: None,
+            coverart_max_size: default_coverart_cache_size(),
+        }
+    }
+}
+
+impl From<&CacheConfig> for rustic_core::cache::CacheOptions {
+    fn from(config: &CacheConfig) -> Self {
+        rustic_core::cache::CacheOptions {
+            path: config
+                .path
+                .clone()
+                .unwrap_or_else(rustic_core::cache::default_cache_dir),
+            max_size: config.max_size * 1024 * 1024,
+            prefetch: config.prefetch,
+            coverart_path: config
+                .coverart_path
+                .clone()
+                .unwrap_or_else(rustic_core::cache::default_coverart_dir),
+            coverart_max_size: config.coverart_max_size * 1024 * 1024,
+        }
+    }
+}
+
+fn default_cache_size() -> u64 {
+    2048
+}
+
+fn default_prefetch() -> usize {
+    2
+}
+
+fn default_coverart_cache_size() -> u64 {
+    256
+}
+
 #[derive(Deserialize, Clone, Debug)]
 #[serde(tag = "store", rename_all = "lowercase")]
 pub enum LibraryConfig {
@@ -175,15 +250,31 @@ pub struct PlayerBackendConfig {
     pub default: bool,
     #[serde(flatten)]
     pub backend_type: PlayerBackend,
+    /// Additional backends playing the same queue
+    #[serde(default, rename = "output")]
+    pub outputs: Vec<PlayerOutputConfig>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PlayerOutputConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub backend_type: PlayerBackend,
 }
 
 #[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(rename_all = "lowercase", tag = "type")]
 pub enum PlayerBackend {
     #[cfg(feature = "gstreamer-backend")]
-    GStreamer,
+    GStreamer {
+        /// Name of the audio device, the default sink is used when missing
+        device: Option<String>,
+    },
     #[cfg(feature = "rodio-backend")]
-    Rodio,
+    Rodio {
+        /// Name of the audio device, the default device is used when missing
+        device: Option<String>,
+    },
     #[cfg(feature = "google-cast-backend")]
     GoogleCast { ip: IpAddr },
     #[cfg(feature = "snapcast-backend")]
@@ -192,6 +283,12 @@ pub enum PlayerBackend {
         pipe: Option<String>,
         port: Option<u16>,
         host: Option<String>,
+        /// Path of `backends/snapcast/controlscript/rustic-bridge.py` on the snapserver host
+        ///
+        /// Required for stream metadata and control commands of snapcast clients
+        controlscript: Option<String>,
+        /// Address the controlscript connects to, defaults to `127.0.0.1` and a random port
+        control_address: Option<String>,
     },
 }
 
@@ -239,13 +336,14 @@ impl Default for ClientConfig {
 fn default_backend() -> Vec<PlayerBackendConfig> {
     #[cfg(feature = "rodio-backend")]
     #[allow(unused_variables)]
-    let backend_type = PlayerBackend::Rodio;
+    let backend_type = PlayerBackend::Rodio { device: None };
     #[cfg(feature = "gstreamer-backend")]
-    let backend_type = PlayerBackend::GStreamer;
+    let backend_type = PlayerBackend::GStreamer { device: None };
     let config = PlayerBackendConfig {
         name: "default".to_string(),
         default: true,
         backend_type,
+        outputs: Vec::new(),
     };
 
     vec![config]
@@ -7,10 +7,10 @@ use pinboard::NonEmptyPinboard;
 use tokio::fs;
 
 use async_trait::async_trait;
-use rustic_core::{CredentialStore, Credentials, ProviderType};
+use rustic_core::{CredentialStore, Credentials, ProviderId};
 
 pub struct FileCredentialStore {
-    credentials: NonEmptyPinboard<HashMap<ProviderType, Credentials>>,
+    credentials: NonEmptyPinboard<HashMap<ProviderId, Credentials>>,
     path: PathBuf,
 }
 
@@ -40,14 +40,14 @@ impl FileCredentialStore {
 
 #[async_trait]
 impl CredentialStore for FileCredentialStore {
-    async fn get_credentials(&self, provider: ProviderType) -> Result<Option<Credentials>, Error> {
+    async fn get_credentials(&self, provider: ProviderId) -> Result<Option<Credentials>, Error> {
         let credentials = self.credentials.read();
         Ok(credentials.get(&provider).cloned())
     }
 
     async fn store_credentials(
         &self,
-        provider: ProviderType,
+        provider: ProviderId,
         credentials: Credentials,
     ) -> Result<(), Error> {
         let mut credentials_map = self.credentials.read();
@@ -58,4 +58,65 @@ impl CredentialStore for FileCredentialStore {
 
         Ok(())
     }
+
+    async fn delete_credentials(&self, provider: ProviderId) -> Result<(), Error> {
+        let mut credentials_map = self.credentials.read();
+        if credentials_map.remove(&provider).is_some() {
+            self.credentials.set(credentials_map);
+            self.save().await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rustic_core::{CredentialStore, Credentials, ProviderId};
+
+    use super::FileCredentialStore;
+
+    fn store_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rustic-credentials-{}.json", name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn delete_credentials_should_only_remove_the_account() {
+        let path = store_path("delete");
+        let work = ProviderId::new("spotify").with_instance("work");
+        let store = FileCredentialStore::load(&path).await.unwrap();
+        let token = || Credentials::Token("token".into());
+        store
+            .store_credentials(ProviderId::new("spotify"), token())
+            .await
+            .unwrap();
+        store
+            .store_credentials(work.clone(), token())
+            .await
+            .unwrap();
+
+        store.delete_credentials(work.clone()).await.unwrap();
+
+        let store = FileCredentialStore::load(&path).await.unwrap();
+        let default = store.get_credentials(ProviderId::new("spotify")).await;
+        assert!(default.unwrap().is_some());
+        assert!(store.get_credentials(work).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn delete_credentials_should_succeed_without_credentials() {
+        let path = store_path("missing");
+        let store = FileCredentialStore::load(&path).await.unwrap();
+
+        store
+            .delete_credentials(ProviderId::new("spotify"))
+            .await
+            .unwrap();
+
+        assert!(!path.exists());
+    }
 }
@@ -2,7 +2,7 @@ use failure::{format_err, Error};
 use keyring::{Keyring, KeyringError};
 
 use async_trait::async_trait;
-use rustic_core::{CredentialStore, Credentials, ProviderType};
+use rustic_core::{CredentialStore, Credentials, ProviderId};
 
 pub struct KeychainCredentialStore;
 
@@ -20,13 +20,9 @@ impl KeychainCredentialStore {
             KeyringError::Parse(e) => e.into(),
         }
     }
-}
 
-#[async_trait]
-impl CredentialStore for KeychainCredentialStore {
-    async fn get_credentials(&self, provider: ProviderType) -> Result<Option<Credentials>, Error> {
-        let provider = format!("{:?}", provider);
-        let keyring = Keyring::new("rustic", &provider);
+    fn read_credentials(account: &str) -> Result<Option<Credentials>, Error> {
+        let keyring = Keyring::new("rustic", account);
         match keyring.get_password() {
             Ok(password) => {
                 let credentials = serde_json::from_str(&password)?;
@@ -38,12 +34,46 @@ impl CredentialStore for KeychainCredentialStore {
         }
     }
 
+    fn delete_credentials(account: &str) -> Result<(), Error> {
+        let keyring = Keyring::new("rustic", account);
+        match keyring.delete_password() {
+            Ok(_) | Err(KeyringError::NoPasswordFound) => Ok(()),
+            Err(e) => Err(KeychainCredentialStore::handle_error(e)),
+        }
+    }
+}
+
+/// Credentials used to be stored by the debug name of the former `ProviderType` enum
+fn legacy_account(provider: &ProviderId) -> Option<&'static str> {
+    if provider.instance().is_some() {
+        return None;
+    }
+    match provider.key() {
+        "pocketcasts" => Some("Pocketcasts"),
+        "soundcloud" => Some("Soundcloud"),
+        "spotify" => Some("Spotify"),
+        "youtube" => Some("Youtube"),
+        "ytmusic" => Some("YouTubeMusic"),
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl CredentialStore for KeychainCredentialStore {
+    async fn get_credentials(&self, provider: ProviderId) -> Result<Option<Credentials>, Error> {
+        let credentials = KeychainCredentialStore::read_credentials(&provider.to_string())?;
+        match (credentials, legacy_account(&provider)) {
+            (None, Some(account)) => KeychainCredentialStore::read_credentials(account),
+            (credentials, _) => Ok(credentials),
+        }
+    }
+
     async fn store_credentials(
         &self,
-        provider: ProviderType,
+        provider: ProviderId,
         credentials: Credentials,
     ) -> Result<(), Error> {
-        let provider = format!("{:?}", provider);
+        let provider = provider.to_string();
         let keyring = Keyring::new("rustic", &provider);
         let password = serde_json::to_string(&credentials)?;
         match keyring.set_password(&password) {
@@ -51,4 +81,28 @@ impl CredentialStore for KeychainCredentialStore {
             Err(e) => Err(KeychainCredentialStore::handle_error(e)),
         }
     }
+
+    async fn delete_credentials(&self, provider: ProviderId) -> Result<(), Error> {
+        KeychainCredentialStore::delete_credentials(&provider.to_string())?;
+        if let Some(account) = legacy_account(&provider) {
+            KeychainCredentialStore::delete_credentials(account)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustic_core::ProviderId;
+
+    use super::legacy_account;
+
+    #[test]
+    fn legacy_account_should_only_exist_for_the_default_account() {
+        let spotify = ProviderId::new("spotify");
+
+        assert_eq!(legacy_account(&spotify), Some("Spotify"));
+        assert_eq!(legacy_account(&spotify.with_instance("work")), None);
+    }
 }
@@ -91,7 +91,10 @@ impl StorageCollection for JsonFile {
         let mut collection = self.read_file().await?.unwrap_or_default();
         collection.insert(name.to_string(), value);
 
-        fs::write(&self.path, serde_json::to_string(&collection)?).await?;
+        // write to a temporary file first so a crash can't leave a truncated collection behind
+        let tmp_path = format!("{}.tmp", &self.path);
+        fs::write(&tmp_path, serde_json::to_string(&collection)?).await?;
+        fs::rename(&tmp_path, &self.path).await?;
         Ok(())
     }
 }
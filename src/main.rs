@@ -78,7 +78,8 @@ async fn setup_instance(
             Box::new(FileCredentialStore::load(path).await?)
         }
     };
-    let providers = setup_providers(&config, credential_store.as_ref()).await?;
+    let credential_store = Arc::new(credential_store);
+    let providers = setup_providers(&config, credential_store.as_ref().as_ref()).await?;
     let storage = json_storage::JsonStorage::new(".storage".into())?;
     let storage: Arc<Box<dyn StorageBackend>> = Arc::new(Box::new(storage));
 
@@ -96,10 +97,17 @@ async fn setup_instance(
         }
     };
 
-    let app = Rustic::new(library, storage, providers)?;
-    let extension_runtime =
-        ExtensionRuntime::new(Arc::clone(&app), Arc::clone(&app.storage));
+    let app = Rustic::new(library, storage, providers, (&config.cache).into())?;
+    let extension_runtime = ExtensionRuntime::new(
+        Arc::clone(&app),
+        Arc::clone(&app.storage),
+        Arc::clone(&credential_store),
+    );
     extensions.setup(extension_runtime).await?;
+    tokio::spawn(rustic_core::auth::start(
+        Arc::clone(&app),
+        Arc::clone(&credential_store),
+    ));
     let client = setup_client(&app, extensions, credential_store);
 
     for player_config in config.players.iter() {
@@ -115,8 +123,6 @@ async fn setup_instance(
         }
     }
 
-    rustic_core::cache::setup()?;
-
     Ok((app, client))
 }
 
@@ -10,7 +10,7 @@ use crate::options;
 pub(crate) fn setup_client(
     app: &Arc<Rustic>,
     extensions: ExtensionManager,
-    cred_store: Box<dyn CredentialStore>,
+    cred_store: Arc<Box<dyn CredentialStore>>,
 ) -> ApiClient {
     let client: Box<dyn RusticApiClient> = {
         let client =
@@ -18,31 +18,51 @@ pub(crate) fn setup_player(
     player_config: &PlayerBackendConfig,
 ) -> Result<(), failure::Error> {
     let name = player_config.name.clone();
-    let player = match player_config.backend_type {
+    let mut builder = PlayerBuilder::new(Arc::clone(&app));
+    builder.with_name(&name).with_memory_queue();
+    if player_config.outputs.is_empty() {
+        setup_backend(&mut builder, &player_config.backend_type)?;
+    } else {
+        builder.with_output(&name);
+        setup_backend(&mut builder, &player_config.backend_type)?;
+        for output in player_config.outputs.iter() {
+            builder.with_output(&output.name);
+            setup_backend(&mut builder, &output.backend_type)?;
+        }
+    }
+    let player = builder.build();
+    app.add_player(name.clone(), player);
+    if player_config.default {
+        app.set_default_player(name);
+    }
+    Ok(())
+}
+
+fn setup_backend(
+    builder: &mut PlayerBuilder,
+    backend: &PlayerBackend,
+) -> Result<(), failure::Error> {
+    match *backend {
         #[cfg(feature = "gstreamer-backend")]
-        PlayerBackend::GStreamer => PlayerBuilder::new(Arc::clone(&app))
-            .with_name(&name)
-            .with_memory_queue()
-            .with_gstreamer()?
-            .build(),
+        PlayerBackend::GStreamer { ref device } => {
+            builder.with_gstreamer(device.clone())?;
+        }
         #[cfg(feature = "google-cast-backend")]
-        PlayerBackend::GoogleCast { ip } => PlayerBuilder::new(Arc::clone(&app))
-            .with_name(&name)
-            .with_memory_queue()
-            .with_google_cast(ip)?
-            .build(),
+        PlayerBackend::GoogleCast { ip } => {
+            builder.with_google_cast(ip)?;
+        }
         #[cfg(feature = "rodio-backend")]
-        PlayerBackend::Rodio => PlayerBuilder::new(Arc::clone(&app))
-            .with_name(&name)
-            .with_memory_queue()
-            .with_rodio()?
-            .build(),
+        PlayerBackend::Rodio { ref device } => {
+            builder.with_rodio(device.clone())?;
+        }
         #[cfg(feature = "snapcast-backend")]
         PlayerBackend::Snapcast {
             ref api_url,
             ref host,
             ref port,
             ref pipe,
+            ref controlscript,
+            ref control_address,
         } => {
             let transport = if let Some(pipe) = pipe {
                 rustic_snapcast_backend::SnapcastAudioTransport::Pipe(pipe.clone())
@@ -53,17 +73,15 @@ pub(crate) fn setup_player(
             };
             let api_url = api_url
                 .clone()
-                .unwrap_or_else(|| "http://localhost:1780".to_string());
-            PlayerBuilder::new(Arc::clone(&app))
-                .with_name(&name)
-                .with_memory_queue()
-                .with_snapcast(api_url, transport)?
-                .build()
+                .unwrap_or_else(|| "tcp://localhost:1705".to_string());
+            let control = controlscript.clone().map(|path| {
+                let address = control_address
+                    .clone()
+                    .unwrap_or_else(|| "127.0.0.1:0".to_string());
+                rustic_snapcast_backend::SnapcastControlScript { path, address }
+            });
+            builder.with_snapcast(api_url, transport, control)?;
         }
-    };
-    app.add_player(name.clone(), player);
-    if player_config.default {
-        app.set_default_player(name);
     }
     Ok(())
 }
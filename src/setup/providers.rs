@@ -2,65 +2,88 @@ use log::{error, info};
 
 use rustic_core::{CredentialStore, Provider};
 
-use crate::config::Config;
+use crate::config::{Config, ProviderConfig};
 use rustic_core::provider::ProviderInstance;
 
 pub(crate) async fn setup_providers(
     config: &Config,
     cred_store: &dyn CredentialStore,
 ) -> Result<Vec<Provider>, failure::Error> {
-    let mut providers: Vec<Box<dyn ProviderInstance + Send + Sync>> = vec![];
+    let mut providers: Vec<Provider> = vec![];
 
     #[cfg(feature = "pocketcasts-provider")]
     {
         if let Some(pocketcasts) = config.provider.pocketcasts.clone() {
             info!("Loading Pocketcasts Provider");
-            providers.push(Box::new(pocketcasts));
+            providers.append(&mut with_accounts(&config.provider, pocketcasts));
         }
     }
     #[cfg(feature = "soundcloud-provider")]
     {
         if let Some(soundcloud) = config.provider.soundcloud.clone() {
             info!("Loading Soundcloud Provider");
-            providers.push(Box::new(soundcloud));
+            providers.append(&mut with_accounts(&config.provider, soundcloud));
         }
     }
     #[cfg(feature = "spotify-provider")]
     {
         if let Some(spotify) = config.provider.spotify.clone() {
             info!("Loading Spotify Provider");
-            providers.push(Box::new(spotify));
+            providers.append(&mut with_accounts(&config.provider, spotify));
         }
     }
     #[cfg(feature = "local-files-provider")]
     {
         if let Some(local) = config.provider.local.clone() {
             info!("Loading Local Provider");
-            providers.push(Box::new(local));
+            providers.append(&mut with_accounts(&config.provider, local));
         }
     }
     #[cfg(feature = "youtube-provider")]
     {
         if let Some(youtube) = config.provider.youtube.clone() {
             info!("Loading Youtube Provider");
-            providers.push(Box::new(youtube));
+            providers.append(&mut with_accounts(&config.provider, youtube));
         }
     }
     #[cfg(feature = "ytmusic-provider")]
     {
         if let Some(ytmusic) = config.provider.ytmusic.clone() {
             info!("Loading YouTube Music Provider");
-            providers.push(Box::new(ytmusic));
+            providers.append(&mut with_accounts(&config.provider, ytmusic));
         }
     }
-    for provider in &mut providers {
+    for provider in &providers {
         provider
             .setup(cred_store)
             .await
             .unwrap_or_else(|err| error!("Can't setup {} provider: {:?}", provider.title(), err));
     }
 
-    let providers = providers.into_iter().map(Provider::from).collect();
-
     Ok(providers)
 }
+
+/// Creates the default instance of the provider and one instance per configured account
+fn with_accounts<P>(config: &ProviderConfig, provider: P) -> Vec<Provider>
+where
+    P: ProviderInstance + Clone + Send + Sync + 'static,
+{
+    let id = provider.provider();
+    let accounts = config
+        .accounts
+        .iter()
+        .filter(|account| account.provider == id.key())
+        .map(|account| {
+            info!("Loading {} account {}", provider.title(), account.id);
+            Provider::for_account(
+                Box::new(provider.clone()),
+                &account.id,
+                account.name.clone(),
+            )
+        });
+
+    let instance: Box<dyn ProviderInstance + Send + Sync> = Box::new(provider.clone());
+    std::iter::once(Provider::from(instance))
+        .chain(accounts)
+        .collect()
+}
@@ -1,6 +1,10 @@
 use failure::Error;
 
-use rustic_core::{Album, Artist, Library, LibraryQueryJoins, MultiQuery, SingleQuery, Track};
+use rustic_core::library::{ItemRevision, LibraryItemKind};
+use rustic_core::{
+    Album, Artist, Library, LibraryChange, LibraryEvent, LibraryQueryJoins, MultiQuery,
+    SingleQuery, Track,
+};
 
 pub fn join_track(
     store: &dyn Library,
@@ -115,3 +119,74 @@ pub fn join_artist(
     };
     Ok(Artist { albums, ..artist })
 }
+
+/// Builds the changes after the given revision from the revisions of all items
+///
+/// Items which were added and removed again after the revision are skipped,
+/// the client never knew about them.
+pub fn collect_changes(
+    store: &dyn Library,
+    mut revisions: Vec<ItemRevision>,
+    since: u64,
+) -> Result<Vec<LibraryChange>, Error> {
+    revisions.retain(|revision| revision.revision > since);
+    revisions.sort_by_key(|revision| revision.revision);
+    let mut changes = Vec::with_capacity(revisions.len());
+    for revision in revisions {
+        let added = revision.created > since;
+        if revision.removed {
+            if !added {
+                let event = removed_event(&revision);
+                changes.push(LibraryChange::new(revision.revision, event));
+            }
+            continue;
+        }
+        let changed = revision.changed_since(since);
+        let query = SingleQuery::uri(revision.uri.clone());
+        let event = match revision.kind {
+            LibraryItemKind::Track => store.query_track(query)?.map(|track| {
+                if added {
+                    LibraryEvent::TrackAdded(track)
+                } else {
+                    LibraryEvent::TrackUpdated(track, changed)
+                }
+            }),
+            LibraryItemKind::Album => store.query_album(query)?.map(|album| {
+                if added {
+                    LibraryEvent::AlbumAdded(album)
+                } else {
+                    LibraryEvent::AlbumUpdated(album, changed)
+                }
+            }),
+            LibraryItemKind::Artist => store.query_artist(query)?.map(|artist| {
+                if added {
+                    LibraryEvent::ArtistAdded(artist)
+                } else {
+                    LibraryEvent::ArtistUpdated(artist, changed)
+                }
+            }),
+            LibraryItemKind::Playlist => store.query_playlist(query)?.map(|playlist| {
+                if added {
+                    LibraryEvent::PlaylistAdded(playlist)
+                } else {
+                    LibraryEvent::PlaylistUpdated(playlist, changed)
+                }
+            }),
+        };
+        if let Some(event) = event {
+            changes.push(LibraryChange::new(revision.revision, event));
+        }
+    }
+
+    Ok(changes)
+}
+
+fn removed_event(revision: &ItemRevision) -> LibraryEvent {
+    let uri = revision.uri.clone();
+    match revision.kind {
+        LibraryItemKind::Track => LibraryEvent::TrackRemoved(uri),
+        LibraryItemKind::Album => LibraryEvent::AlbumRemoved(uri),
+        LibraryItemKind::Artist => LibraryEvent::ArtistRemoved(uri),
+        LibraryItemKind::Playlist => LibraryEvent::PlaylistRemoved(uri),
+    }
+}
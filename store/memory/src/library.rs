@@ -1,21 +1,28 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::BufReader;
 use std::path::Path;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
 use failure::Error;
 use futures::stream::{BoxStream, StreamExt};
 use log::trace;
 use pinboard::NonEmptyPinboard;
-use rustic_queue::{broadcast, Receiver, Sender};
+use rustic_queue::{multicast, MulticastReceiver, MulticastSender};
 use serde::{Deserialize, Serialize};
 use serde_json::from_reader;
 
-use rustic_core::library::{Identifiable, LibraryItemIdentifier};
+use rustic_core::library::{
+    ChangedField, Changes, HistoryQuery, Identifiable, ItemRevision, LibraryItemIdentifier,
+    LibraryItemKind, PlayRecord, TrackPlays,
+};
 use rustic_core::{
-    Album, Artist, Library, LibraryEvent, MultiQuery, Playlist, SearchResults, SingleQuery, Track,
+    Album, Artist, Library, LibraryChange, LibraryEvent, MultiQuery, Playlist, Rating,
+    SearchResults, SingleQuery, Track,
 };
-use rustic_store_helpers::{join_album, join_albums, join_artist, join_track};
+use rustic_store_helpers::{collect_changes, join_album, join_albums, join_artist, join_track};
+
+type Revisions = HashMap<(LibraryItemKind, String), ItemRevision>;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct LibrarySnapshot {
@@ -27,11 +34,17 @@ struct LibrarySnapshot {
     artists: Vec<Artist>,
     tracks: Vec<Track>,
     playlists: Vec<Playlist>,
+    #[serde(default)]
+    revision: u64,
+    #[serde(default)]
+    revisions: Vec<ItemRevision>,
+    #[serde(default)]
+    plays: Vec<PlayRecord>,
 }
 
 impl From<LibrarySnapshot> for MemoryLibrary {
     fn from(snapshot: LibrarySnapshot) -> Self {
-        let (tx, rx) = broadcast();
+        let (tx, rx) = multicast();
         MemoryLibrary {
             persist: true,
             album_id: AtomicUsize::new(snapshot.album_id),
@@ -42,6 +55,15 @@ impl From<LibrarySnapshot> for MemoryLibrary {
             artists: NonEmptyPinboard::new(snapshot.artists),
             tracks: NonEmptyPinboard::new(snapshot.tracks),
             playlists: NonEmptyPinboard::new(snapshot.playlists),
+            revision: AtomicU64::new(snapshot.revision),
+            revisions: NonEmptyPinboard::new(
+                snapshot
+                    .revisions
+                    .into_iter()
+                    .map(|revision| ((revision.kind, revision.uri.clone()), revision))
+                    .collect(),
+            ),
+            plays: NonEmptyPinboard::new(snapshot.plays),
             event_sender: tx,
             event_receiver: rx,
         }
@@ -59,13 +81,16 @@ pub struct MemoryLibrary {
     artists: NonEmptyPinboard<Vec<Artist>>,
     tracks: NonEmptyPinboard<Vec<Track>>,
     playlists: NonEmptyPinboard<Vec<Playlist>>,
-    event_sender: Sender<LibraryEvent>,
-    event_receiver: Receiver<LibraryEvent>,
+    revision: AtomicU64,
+    revisions: NonEmptyPinboard<Revisions>,
+    plays: NonEmptyPinboard<Vec<PlayRecord>>,
+    event_sender: MulticastSender<LibraryChange>,
+    event_receiver: MulticastReceiver<LibraryChange>,
 }
 
 impl Default for MemoryLibrary {
     fn default() -> Self {
-        let (tx, rx) = broadcast();
+        let (tx, rx) = multicast();
         MemoryLibrary {
             persist: false,
             album_id: AtomicUsize::new(1),
@@ -76,6 +101,9 @@ impl Default for MemoryLibrary {
             artists: NonEmptyPinboard::new(Vec::new()),
             tracks: NonEmptyPinboard::new(Vec::new()),
             playlists: NonEmptyPinboard::new(Vec::new()),
+            revision: AtomicU64::new(0),
+            revisions: NonEmptyPinboard::new(HashMap::new()),
+            plays: NonEmptyPinboard::new(Vec::new()),
             event_sender: tx,
             event_receiver: rx,
         }
@@ -118,9 +146,28 @@ impl MemoryLibrary {
             artists: self.artists.read(),
             tracks: self.tracks.read(),
             playlists: self.playlists.read(),
+            revision: self.revision.load(Ordering::Relaxed),
+            revisions: self
+                .revisions
+                .read()
+                .into_iter()
+                .map(|(_, revision)| revision)
+                .collect(),
+            plays: self.plays.read(),
         }
     }
 
+    /// Records the change with a new revision and notifies subscribers
+    fn emit(&self, event: LibraryEvent) {
+        let revision = self.revision.fetch_add(1, Ordering::Relaxed) + 1;
+        let key = (event.item_kind(), event.uri().to_string());
+        let mut revisions = self.revisions.read();
+        let previous = revisions.remove(&key);
+        revisions.insert(key, ItemRevision::record(previous, revision, &event));
+        self.revisions.set(revisions);
+        self.event_sender.send(LibraryChange::new(revision, event));
+    }
+
     fn store(snapshot: LibrarySnapshot) -> Result<(), failure::Error> {
         let file = fs::OpenOptions::new()
             .create(true)
@@ -196,13 +243,7 @@ impl Library for MemoryLibrary {
         self.tracks
             .read()
             .into_iter()
-            .filter(|track| {
-                if query.providers.is_empty() {
-                    true
-                } else {
-                    query.providers.contains(&track.provider)
-                }
-            })
+            .filter(|track| query.matches_provider(&track.provider))
             .map(|track| join_track(self, track, query.joins))
             .collect()
     }
@@ -224,13 +265,7 @@ impl Library for MemoryLibrary {
             .albums
             .read()
             .into_iter()
-            .filter(|album| {
-                if query.providers.is_empty() {
-                    true
-                } else {
-                    query.providers.contains(&album.provider)
-                }
-            })
+            .filter(|album| query.matches_provider(&album.provider))
             .collect();
         join_albums(self, &albums, query.joins)
     }
@@ -264,13 +299,7 @@ impl Library for MemoryLibrary {
             .playlists
             .read()
             .into_iter()
-            .filter(|playlist| {
-                if query.providers.is_empty() {
-                    true
-                } else {
-                    query.providers.contains(&playlist.provider)
-                }
-            })
+            .filter(|playlist| query.matches_provider(&playlist.provider))
             .collect();
         Ok(playlists)
     }
@@ -281,8 +310,7 @@ impl Library for MemoryLibrary {
         let mut tracks = self.tracks.read();
         tracks.push(track.clone());
         self.tracks.set(tracks);
-        self.event_sender
-            .send(LibraryEvent::TrackAdded(track.clone()));
+        self.emit(LibraryEvent::TrackAdded(track.clone()));
 
         Ok(())
     }
@@ -307,8 +335,7 @@ impl Library for MemoryLibrary {
         let mut albums = self.albums.read();
         albums.push(album.clone());
         self.albums.set(albums);
-        self.event_sender
-            .send(LibraryEvent::AlbumAdded(album.clone()));
+        self.emit(LibraryEvent::AlbumAdded(album.clone()));
 
         Ok(())
     }
@@ -322,8 +349,7 @@ impl Library for MemoryLibrary {
             let mut artists = self.artists.read();
             artists.push(artist.clone());
             self.artists.set(artists);
-            self.event_sender
-                .send(LibraryEvent::ArtistAdded(artist.clone()));
+            self.emit(LibraryEvent::ArtistAdded(artist.clone()));
         }
         Ok(())
     }
@@ -333,8 +359,7 @@ impl Library for MemoryLibrary {
         let mut playlists = self.playlists.read();
         playlists.push(playlist.clone());
         self.playlists.set(playlists);
-        self.event_sender
-            .send(LibraryEvent::PlaylistAdded(playlist.clone()));
+        self.emit(LibraryEvent::PlaylistAdded(playlist.clone()));
         Ok(())
     }
 
@@ -377,13 +402,44 @@ impl Library for MemoryLibrary {
             .unwrap_or_else(|| self.track_id.fetch_add(1, Ordering::Relaxed));
         track.id = Some(id);
 
+        let mut tracks = self.tracks.read();
         if has_track.is_none() {
-            let mut tracks = self.tracks.read();
             tracks.push(track.clone());
             self.tracks.set(tracks);
-            self.event_sender
-                .send(LibraryEvent::TrackAdded(track.clone()));
+            self.emit(LibraryEvent::TrackAdded(track.clone()));
+        } else {
+            let target_track = tracks.iter_mut().find(|t| t.uri == track.uri).unwrap();
+            if track.rating == Rating::None {
+                track.rating = target_track.rating;
+            }
+            let changes = track.changes(target_track);
+            if changes.is_empty() {
+                return Ok(());
+            }
+            *target_track = track.clone();
+            self.tracks.set(tracks);
+            self.emit(LibraryEvent::TrackUpdated(track.clone(), changes));
+        }
+        Ok(())
+    }
+
+    fn set_rating(&self, uri: &str, rating: Rating) -> Result<(), Error> {
+        let mut tracks = self.tracks.read();
+        let track = tracks
+            .iter_mut()
+            .find(|t| t.uri == uri)
+            .ok_or_else(|| failure::format_err!("Track not found"))?;
+        if track.rating == rating {
+            return Ok(());
         }
+        track.rating = rating;
+        let track = track.clone();
+        self.tracks.set(tracks);
+        self.emit(LibraryEvent::TrackUpdated(
+            track,
+            vec![ChangedField::Rating],
+        ));
+
         Ok(())
     }
 
@@ -398,12 +454,20 @@ impl Library for MemoryLibrary {
             .unwrap_or_else(|| self.album_id.fetch_add(1, Ordering::Relaxed));
         album.id = Some(id);
 
+        let mut albums = self.albums.read();
         if has_album.is_none() {
-            let mut albums = self.albums.read();
             albums.push(album.clone());
             self.albums.set(albums);
-            self.event_sender
-                .send(LibraryEvent::AlbumAdded(album.clone()));
+            self.emit(LibraryEvent::AlbumAdded(album.clone()));
+        } else {
+            let target_album = albums.iter_mut().find(|a| a.uri == album.uri).unwrap();
+            let changes = album.changes(target_album);
+            if changes.is_empty() {
+                return Ok(());
+            }
+            *target_album = album.clone();
+            self.albums.set(albums);
+            self.emit(LibraryEvent::AlbumUpdated(album.clone(), changes));
         }
         Ok(())
     }
@@ -422,17 +486,22 @@ impl Library for MemoryLibrary {
         let mut artists = self.artists.read();
         if has_artist.is_none() {
             artists.push(artist.clone());
-            self.event_sender
-                .send(LibraryEvent::ArtistAdded(artist.clone()));
+            self.artists.set(artists);
+            self.emit(LibraryEvent::ArtistAdded(artist.clone()));
         } else {
             let index = artists
                 .iter()
                 .position(|artist| artist.id == Some(id))
                 .unwrap();
             let target_artist = artists.get_mut(index).unwrap();
+            let changes = artist.changes(target_artist);
+            if changes.is_empty() {
+                return Ok(());
+            }
             *target_artist = artist.clone();
+            self.artists.set(artists);
+            self.emit(LibraryEvent::ArtistUpdated(artist.clone(), changes));
         }
-        self.artists.set(artists);
         Ok(())
     }
 
@@ -451,59 +520,55 @@ impl Library for MemoryLibrary {
         let mut playlists = self.playlists.read();
         if let Some(index) = index {
             let target_playlist = playlists.get_mut(index).unwrap();
+            let changes = playlist.changes(target_playlist);
+            if changes.is_empty() {
+                return Ok(());
+            }
             *target_playlist = playlist.clone();
+            self.playlists.set(playlists);
+            self.emit(LibraryEvent::PlaylistUpdated(playlist.clone(), changes));
         } else {
             playlists.push(playlist.clone());
-            self.event_sender
-                .send(LibraryEvent::PlaylistAdded(playlist.clone()));
+            self.playlists.set(playlists);
+            self.emit(LibraryEvent::PlaylistAdded(playlist.clone()));
         }
-        self.playlists.set(playlists);
         Ok(())
     }
 
     fn sync_tracks(&self, tracks: &mut Vec<Track>) -> Result<(), Error> {
-        tracks
-            .iter_mut()
-            .filter(|track| {
-                self.tracks
-                    .read()
-                    .iter()
-                    .find(|t| t.uri == track.uri)
-                    .map(|_t| false)
-                    .unwrap_or(true)
-            })
-            .map(|mut track| self.add_track(&mut track))
-            .collect()
+        for track in tracks {
+            let exists = self.tracks.read().iter().any(|t| t.uri == track.uri);
+            if exists {
+                self.sync_track(track)?;
+            } else {
+                self.add_track(track)?;
+            }
+        }
+        Ok(())
     }
 
     fn sync_albums(&self, albums: &mut Vec<Album>) -> Result<(), Error> {
-        albums
-            .iter_mut()
-            .filter(|album| {
-                self.albums
-                    .read()
-                    .iter()
-                    .find(|t| t.uri == album.uri)
-                    .map(|_t| false)
-                    .unwrap_or(true)
-            })
-            .map(|mut album| self.add_album(&mut album))
-            .collect()
+        for album in albums {
+            let exists = self.albums.read().iter().any(|a| a.uri == album.uri);
+            if exists {
+                self.sync_album(album)?;
+            } else {
+                self.add_album(album)?;
+            }
+        }
+        Ok(())
     }
 
     fn sync_artists(&self, artists: &mut Vec<Artist>) -> Result<(), Error> {
-        artists
-            .iter_mut()
-            .filter(|artist| {
-                self.artists
-                    .read()
-                    .iter()
-                    .find(|t| t.uri == artist.uri)
-                    .map(|_t| false)
-                    .unwrap_or(true)
-            })
-            .map(|mut artist| self.add_artist(&mut artist))
-            .collect()
+        for artist in artists {
+            let exists = self.artists.read().iter().any(|a| a.uri == artist.uri);
+            if exists {
+                self.sync_artist(artist)?;
+            } else {
+                self.add_artist(artist)?;
+            }
+        }
+        Ok(())
     }
 
     fn sync_playlists(&self, playlists: &mut Vec<Playlist>) -> Result<(), Error> {
@@ -523,8 +588,7 @@ impl Library for MemoryLibrary {
         if let Some(position) = tracks.iter().position(|t| t.id == track.id) {
             tracks.remove(position);
             self.tracks.set(tracks);
-            self.event_sender
-                .send(LibraryEvent::TrackRemoved(track.uri.clone()));
+            self.emit(LibraryEvent::TrackRemoved(track.uri.clone()));
             Ok(())
         } else {
             Ok(())
@@ -536,8 +600,7 @@ impl Library for MemoryLibrary {
         if let Some(position) = albums.iter().position(|t| t.id == album.id) {
             albums.remove(position);
             self.albums.set(albums);
-            self.event_sender
-                .send(LibraryEvent::AlbumRemoved(album.uri.clone()));
+            self.emit(LibraryEvent::AlbumRemoved(album.uri.clone()));
             Ok(())
         } else {
             Ok(())
@@ -549,8 +612,7 @@ impl Library for MemoryLibrary {
         if let Some(position) = artists.iter().position(|t| t.id == artist.id) {
             artists.remove(position);
             self.artists.set(artists);
-            self.event_sender
-                .send(LibraryEvent::ArtistRemoved(artist.uri.clone()));
+            self.emit(LibraryEvent::ArtistRemoved(artist.uri.clone()));
             Ok(())
         } else {
             Ok(())
@@ -562,8 +624,7 @@ impl Library for MemoryLibrary {
         if let Some(position) = playlists.iter().position(|t| t.id == playlist.id) {
             playlists.remove(position);
             self.playlists.set(playlists);
-            self.event_sender
-                .send(LibraryEvent::PlaylistRemoved(playlist.uri.clone()));
+            self.emit(LibraryEvent::PlaylistRemoved(playlist.uri.clone()));
             Ok(())
         } else {
             Ok(())
@@ -596,7 +657,49 @@ impl Library for MemoryLibrary {
         Ok(())
     }
 
-    fn observe(&self) -> BoxStream<'static, LibraryEvent> {
+    fn revision(&self) -> Result<u64, Error> {
+        Ok(self.revision.load(Ordering::Relaxed))
+    }
+
+    fn changes_since(&self, revision: u64) -> Result<Vec<LibraryChange>, Error> {
+        let revisions = self
+            .revisions
+            .read()
+            .into_iter()
+            .map(|(_, revision)| revision)
+            .collect();
+
+        collect_changes(self, revisions, revision)
+    }
+
+    fn add_play(&self, play: PlayRecord) -> Result<(), Error> {
+        let mut plays = self.plays.read();
+        plays.push(play);
+        self.plays.set(plays);
+
+        Ok(())
+    }
+
+    fn query_plays(&self, query: HistoryQuery) -> Result<Vec<PlayRecord>, Error> {
+        Ok(query.apply(self.plays.read()))
+    }
+
+    fn query_track_plays(&self, uris: &[String]) -> Result<HashMap<String, TrackPlays>, Error> {
+        let uris: HashSet<&String> = uris.iter().collect();
+        let mut result: HashMap<String, TrackPlays> = HashMap::new();
+        for play in self.plays.read() {
+            if uris.contains(&play.track.uri) {
+                result
+                    .entry(play.track.uri)
+                    .or_default()
+                    .add(play.started_at, play.completed);
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn observe(&self) -> BoxStream<'static, LibraryChange> {
         self.event_receiver.stream().boxed()
     }
 }
@@ -605,7 +708,9 @@ impl Library for MemoryLibrary {
 mod tests {
     use std::collections::HashMap;
 
-    use rustic_core::{Artist, Library, ProviderType};
+    use rustic_core::library::{ChangedField, HistoryQuery, PlayRecord};
+    use rustic_core::testing::track;
+    use rustic_core::{Artist, Library, LibraryEvent, ProviderId, Rating, SingleQuery};
 
     use crate::MemoryLibrary;
 
@@ -617,7 +722,7 @@ mod tests {
             uri: "test:artist".into(),
             image_url: None,
             meta: HashMap::new(),
-            provider: ProviderType::Internal,
+            provider: ProviderId::INTERNAL,
             albums: Vec::new(),
             playlists: Vec::new(),
             description: None,
@@ -630,4 +735,92 @@ mod tests {
 
         assert_eq!(second.id, artist.id);
     }
+
+    #[test]
+    fn changes_since_should_contain_the_changed_fields() {
+        let store = MemoryLibrary::default();
+        let mut track = track("test:track");
+        store.sync_track(&mut track).unwrap();
+        let revision = store.revision().unwrap();
+
+        track.title = "Updated Track".into();
+        store.sync_track(&mut track).unwrap();
+
+        let changes = store.changes_since(revision).unwrap();
+        assert_eq!(changes.len(), 1);
+        match changes[0].event {
+            LibraryEvent::TrackUpdated(ref track, ref fields) => {
+                assert_eq!(track.title, "Updated Track");
+                assert_eq!(fields, &vec![ChangedField::Title]);
+            }
+            ref event => panic!("unexpected event {:?}", event),
+        }
+    }
+
+    #[test]
+    fn syncing_an_unchanged_track_should_not_create_a_revision() {
+        let store = MemoryLibrary::default();
+        let mut track = track("test:track");
+        store.sync_track(&mut track).unwrap();
+        let revision = store.revision().unwrap();
+
+        store.sync_track(&mut track).unwrap();
+
+        assert_eq!(store.revision().unwrap(), revision);
+    }
+
+    #[test]
+    fn syncing_a_track_should_keep_its_rating() {
+        let store = MemoryLibrary::default();
+        let mut track = track("test:track");
+        store.sync_track(&mut track).unwrap();
+        store.set_rating(&track.uri, Rating::Like).unwrap();
+
+        store.sync_track(&mut track).unwrap();
+
+        let stored = store
+            .query_track(SingleQuery::uri(track.uri.clone()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.rating, Rating::Like);
+    }
+
+    #[test]
+    fn set_rating_should_clear_the_rating() {
+        let store = MemoryLibrary::default();
+        let mut track = track("test:track");
+        track.rating = Rating::Stars(4);
+        store.sync_track(&mut track).unwrap();
+
+        store.set_rating(&track.uri, Rating::None).unwrap();
+
+        let stored = store
+            .query_track(SingleQuery::uri(track.uri.clone()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.rating, Rating::None);
+    }
+
+    #[test]
+    fn query_plays_should_return_the_newest_plays_first() {
+        let store = MemoryLibrary::default();
+        for started_at in 1..=3 {
+            store
+                .add_play(PlayRecord {
+                    track: track("test:track"),
+                    player: "default".into(),
+                    started_at,
+                    listened: 0,
+                    completed: true,
+                })
+                .unwrap();
+        }
+
+        let plays = store
+            .query_plays(HistoryQuery::new().since(Some(2)))
+            .unwrap();
+
+        let started_at: Vec<u64> = plays.iter().map(|play| play.started_at).collect();
+        assert_eq!(started_at, vec![3, 2]);
+    }
 }
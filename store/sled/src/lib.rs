@@ -1,4 +1,5 @@
 mod library;
+mod migrations;
 mod util;
 
 pub use crate::library::SledLibrary;
@@ -4,18 +4,25 @@ use bimap::BiMap;
 use bincode::{deserialize, serialize};
 use failure::{err_msg, Error};
 use futures::stream::BoxStream;
-use futures::{stream, FutureExt, StreamExt};
+use futures::StreamExt;
 use serde::de::DeserializeOwned;
 use sled::Tree;
 
-use rustic_core::library::LibraryItemIdentifier;
+use rustic_core::library::{
+    ChangedField, Changes, HistoryQuery, ItemRevision, LibraryItemIdentifier, PlayRecord,
+};
 use rustic_core::{
-    Album, Artist, LibraryEvent, MultiQuery, Playlist, SearchResults, SingleQuery, Track,
+    Album, Artist, LibraryChange, LibraryEvent, MultiQuery, Playlist, Rating, SearchResults,
+    SingleQuery, Track,
 };
-use rustic_store_helpers::{join_album, join_albums, join_track};
+use rustic_queue::{multicast, MulticastReceiver, MulticastSender};
+use rustic_store_helpers::{collect_changes, join_album, join_albums, join_track};
 
+use crate::migrations;
 use crate::util::*;
 
+const REVISION_KEY: &[u8] = b"revision";
+
 /// **Experimental**
 ///
 /// # TODO: optimize joins with associations tree and maybe the following instead of plain entities
@@ -42,15 +49,25 @@ pub struct SledLibrary {
     albums_tree: sled::Tree,
     tracks_tree: sled::Tree,
     playlists_tree: sled::Tree,
+    /// Change tracking of all items, keyed by kind and uri
+    revisions_tree: sled::Tree,
+    /// Listening history
+    plays_tree: sled::Tree,
+    event_sender: MulticastSender<LibraryChange>,
+    event_receiver: MulticastReceiver<LibraryChange>,
 }
 
 impl SledLibrary {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<SledLibrary, Error> {
         let db = sled::open(path)?;
+        migrations::migrate(&db)?;
         let artists_tree = db.open_tree("artists")?;
         let albums_tree = db.open_tree("albums")?;
         let tracks_tree = db.open_tree("tracks")?;
         let playlists_tree = db.open_tree("playlists")?;
+        let revisions_tree = db.open_tree("revisions")?;
+        let plays_tree = db.open_tree("plays")?;
+        let (event_sender, event_receiver) = multicast();
 
         Ok(SledLibrary {
             db,
@@ -58,9 +75,33 @@ impl SledLibrary {
             albums_tree,
             tracks_tree,
             playlists_tree,
+            revisions_tree,
+            plays_tree,
+            event_sender,
+            event_receiver,
         })
     }
 
+    /// Records the change with a new revision and notifies subscribers
+    fn emit(&self, event: LibraryEvent) -> Result<(), Error> {
+        let revision = self
+            .db
+            .update_and_fetch(REVISION_KEY, increment)?
+            .ok_or_else(|| err_msg("missing revision"))?;
+        let revision = deserialize_id(&revision)? as u64;
+        let key = serialize(&(event.item_kind(), event.uri()))?;
+        let previous = match self.revisions_tree.get(&key)? {
+            Some(bytes) => Some(deserialize::<ItemRevision>(&bytes)?),
+            None => None,
+        };
+        let item_revision = ItemRevision::record(previous, revision, &event);
+        self.revisions_tree
+            .insert(key, serialize(&item_revision)?)?;
+        self.event_sender.send(LibraryChange::new(revision, event));
+
+        Ok(())
+    }
+
     fn get_ids<T: DeserializeOwned, U: Fn(T) -> String>(
         tree: &Tree,
         uri_accessor: U,
@@ -135,7 +176,9 @@ impl rustic_core::Library for SledLibrary {
     fn query_track(&self, query: SingleQuery) -> Result<Option<Track>, Error> {
         let entity = match query.identifier {
             LibraryItemIdentifier::Id(id) => fetch_entity(&self.tracks_tree, id),
-            _ => Ok(None),
+            LibraryItemIdentifier::Uri(uri) => {
+                find_entity::<Track, _>(&self.tracks_tree, |t| t.uri == uri)
+            }
         }?;
         match entity {
             Some(track) => Ok(Some(join_track(self, track, query.joins)?)),
@@ -150,7 +193,9 @@ impl rustic_core::Library for SledLibrary {
     fn query_album(&self, query: SingleQuery) -> Result<Option<Album>, Error> {
         let entity = match query.identifier {
             LibraryItemIdentifier::Id(id) => fetch_entity(&self.albums_tree, id),
-            _ => Ok(None),
+            LibraryItemIdentifier::Uri(uri) => {
+                find_entity::<Album, _>(&self.albums_tree, |a| a.uri == uri)
+            }
         }?;
         match entity {
             Some(album) => Ok(Some(join_album(self, album, query.joins)?)),
@@ -166,7 +211,9 @@ impl rustic_core::Library for SledLibrary {
     fn query_artist(&self, query: SingleQuery) -> Result<Option<Artist>, Error> {
         match query.identifier {
             LibraryItemIdentifier::Id(id) => fetch_entity(&self.artists_tree, id),
-            _ => Ok(None),
+            LibraryItemIdentifier::Uri(uri) => {
+                find_entity::<Artist, _>(&self.artists_tree, |a| a.uri == uri)
+            }
         }
     }
 
@@ -177,7 +224,9 @@ impl rustic_core::Library for SledLibrary {
     fn query_playlist(&self, query: SingleQuery) -> Result<Option<Playlist>, Error> {
         match query.identifier {
             LibraryItemIdentifier::Id(id) => fetch_entity(&self.playlists_tree, id),
-            _ => Ok(None),
+            LibraryItemIdentifier::Uri(uri) => {
+                find_entity::<Playlist, _>(&self.playlists_tree, |p| p.uri == uri)
+            }
         }
     }
 
@@ -189,28 +238,28 @@ impl rustic_core::Library for SledLibrary {
         track.id = Some(self.id(track.id)?);
         let (id, bytes) = self.serialize_track(track)?;
         self.tracks_tree.insert(id, bytes)?;
-        Ok(())
+        self.emit(LibraryEvent::TrackAdded(track.clone()))
     }
 
     fn add_album(&self, album: &mut Album) -> Result<(), Error> {
         album.id = Some(self.id(album.id)?);
         let (id, bytes) = self.serialize_album(&album)?;
         self.albums_tree.insert(id, bytes)?;
-        Ok(())
+        self.emit(LibraryEvent::AlbumAdded(album.clone()))
     }
 
     fn add_artist(&self, artist: &mut Artist) -> Result<(), Error> {
         artist.id = Some(self.id(artist.id)?);
         let (id, bytes) = self.serialize_artist(&artist)?;
         self.artists_tree.insert(id, bytes)?;
-        Ok(())
+        self.emit(LibraryEvent::ArtistAdded(artist.clone()))
     }
 
     fn add_playlist(&self, playlist: &mut Playlist) -> Result<(), Error> {
         playlist.id = Some(self.id(playlist.id)?);
         let (id, bytes) = self.serialize_playlist(&playlist)?;
         self.playlists_tree.insert(id, bytes)?;
-        Ok(())
+        self.emit(LibraryEvent::PlaylistAdded(playlist.clone()))
     }
 
     fn add_tracks(&self, tracks: &mut Vec<Track>) -> Result<(), Error> {
@@ -246,23 +295,52 @@ impl rustic_core::Library for SledLibrary {
         if let Some(found_track) = find_result {
             let id = self.id(found_track.id)?;
             track.id = Some(id);
+            if track.rating == Rating::None {
+                track.rating = found_track.rating;
+            }
+            let changes = track.changes(&found_track);
+            if changes.is_empty() {
+                return Ok(());
+            }
             let id = serialize_id(id)?;
-            let track = serialize(track)?;
-            self.tracks_tree.insert(id, track)?;
+            let bytes = serialize(track)?;
+            self.tracks_tree.insert(id, bytes)?;
+            self.emit(LibraryEvent::TrackUpdated(track.clone(), changes))?;
         } else {
             self.add_track(track)?;
         }
         Ok(())
     }
 
+    fn set_rating(&self, uri: &str, rating: Rating) -> Result<(), Error> {
+        let mut track = find_entity::<Track, _>(&self.tracks_tree, |t| t.uri == uri)?
+            .ok_or_else(|| err_msg("Track not found"))?;
+        if track.rating == rating {
+            return Ok(());
+        }
+        track.rating = rating;
+        let id = serialize_id(self.id(track.id)?)?;
+        let bytes = serialize(&track)?;
+        self.tracks_tree.insert(id, bytes)?;
+        self.emit(LibraryEvent::TrackUpdated(
+            track,
+            vec![ChangedField::Rating],
+        ))
+    }
+
     fn sync_album(&self, album: &mut Album) -> Result<(), Error> {
         let find_result = find_entity::<Album, _>(&self.albums_tree, |a| a.uri == album.uri)?;
         if let Some(found_album) = find_result {
             let id = self.id(found_album.id)?;
             album.id = Some(id);
+            let changes = album.changes(&found_album);
+            if changes.is_empty() {
+                return Ok(());
+            }
             let id = serialize_id(id)?;
-            let album = serialize(album)?;
-            self.albums_tree.insert(id, album)?;
+            let bytes = serialize(album)?;
+            self.albums_tree.insert(id, bytes)?;
+            self.emit(LibraryEvent::AlbumUpdated(album.clone(), changes))?;
         } else {
             self.add_album(album)?;
         }
@@ -274,9 +352,14 @@ impl rustic_core::Library for SledLibrary {
         if let Some(found_artist) = find_result {
             let id = self.id(found_artist.id)?;
             artist.id = Some(id);
+            let changes = artist.changes(&found_artist);
+            if changes.is_empty() {
+                return Ok(());
+            }
             let id = serialize_id(id)?;
-            let artist = serialize(artist)?;
-            self.artists_tree.insert(id, artist)?;
+            let bytes = serialize(artist)?;
+            self.artists_tree.insert(id, bytes)?;
+            self.emit(LibraryEvent::ArtistUpdated(artist.clone(), changes))?;
         } else {
             self.add_artist(artist)?;
         }
@@ -289,9 +372,14 @@ impl rustic_core::Library for SledLibrary {
         if let Some(found_playlist) = find_result {
             let id = self.id(found_playlist.id)?;
             playlist.id = Some(id);
+            let changes = playlist.changes(&found_playlist);
+            if changes.is_empty() {
+                return Ok(());
+            }
             let id = serialize_id(id)?;
-            let playlist = serialize(playlist)?;
-            self.playlists_tree.insert(id, playlist)?;
+            let bytes = serialize(playlist)?;
+            self.playlists_tree.insert(id, bytes)?;
+            self.emit(LibraryEvent::PlaylistUpdated(playlist.clone(), changes))?;
         } else {
             self.add_playlist(playlist)?;
         }
@@ -340,19 +428,35 @@ impl rustic_core::Library for SledLibrary {
         Ok(())
     }
 
-    fn observe(&self) -> BoxStream<'static, LibraryEvent> {
-        let mut album_subscription = self.albums_tree.watch_prefix(vec![]);
-        let album_stream = stream::poll_fn(move |cx| album_subscription.poll_unpin(cx));
-
-        album_stream
-            .map(|event| match event {
-                sled::Event::Insert { value, .. } => {
-                    LibraryEvent::AlbumAdded(bincode::deserialize(&value).unwrap())
-                }
-                sled::Event::Remove { key } => {
-                    unimplemented!("removing of items is not implemented yet")
-                }
-            })
-            .boxed()
+    fn revision(&self) -> Result<u64, Error> {
+        let revision = match self.db.get(REVISION_KEY)? {
+            Some(bytes) => deserialize_id(&bytes)? as u64,
+            None => 0,
+        };
+
+        Ok(revision)
+    }
+
+    fn changes_since(&self, revision: u64) -> Result<Vec<LibraryChange>, Error> {
+        let revisions = fetch_entities::<ItemRevision>(&self.revisions_tree)?;
+
+        collect_changes(self, revisions, revision)
+    }
+
+    fn add_play(&self, play: PlayRecord) -> Result<(), Error> {
+        let id = serialize_id(self.next_id()?)?;
+        self.plays_tree.insert(id, serialize(&play)?)?;
+
+        Ok(())
+    }
+
+    fn query_plays(&self, query: HistoryQuery) -> Result<Vec<PlayRecord>, Error> {
+        let plays = search_entities(&self.plays_tree, |play| query.matches(play))?;
+
+        Ok(query.apply(plays))
+    }
+
+    fn observe(&self) -> BoxStream<'static, LibraryChange> {
+        self.event_receiver.stream().boxed()
     }
 }
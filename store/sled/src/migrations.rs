@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+
+use bincode::{deserialize, serialize};
+use failure::{format_err, Error};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_derive::Deserialize;
+
+use rustic_core::library::{Chapter, Lyrics, MetaValue, PlayRecord};
+use rustic_core::provider::{ProviderId, ThumbnailState};
+use rustic_core::{Album, Artist, Playlist, Rating, Track, TrackPosition};
+
+use crate::util::{deserialize_id, serialize_id};
+
+const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+
+/// Version 1 stores providers by their id instead of the discriminant of the former `ProviderType` enum
+const SCHEMA_VERSION: usize = 1;
+
+/// Rewrites all entities which were stored with an older layout
+pub fn migrate(db: &sled::Db) -> Result<(), Error> {
+    let version = match db.get(SCHEMA_VERSION_KEY)? {
+        Some(version) => deserialize_id(&version)?,
+        None => 0,
+    };
+    if version < 1 {
+        migrate_tree::<LegacyArtist, Artist>(&db.open_tree("artists")?)?;
+        migrate_tree::<LegacyAlbum, Album>(&db.open_tree("albums")?)?;
+        migrate_tree::<LegacyTrack, Track>(&db.open_tree("tracks")?)?;
+        migrate_tree::<LegacyPlaylist, Playlist>(&db.open_tree("playlists")?)?;
+        migrate_tree::<LegacyPlayRecord, PlayRecord>(&db.open_tree("plays")?)?;
+    }
+    db.insert(SCHEMA_VERSION_KEY, serialize_id(SCHEMA_VERSION)?)?;
+    db.flush()?;
+
+    Ok(())
+}
+
+fn migrate_tree<L, E>(tree: &sled::Tree) -> Result<(), Error>
+where
+    L: DeserializeOwned + TryInto<E, Error = Error>,
+    E: Serialize,
+{
+    let entries = tree.iter().collect::<Result<Vec<_>, _>>()?;
+    for (key, bytes) in entries {
+        let legacy: L = deserialize(&bytes)?;
+        let entity: E = legacy.try_into()?;
+        tree.insert(key, serialize(&entity)?)?;
+    }
+
+    Ok(())
+}
+
+fn provider(index: u32) -> Result<ProviderId, Error> {
+    ProviderId::from_legacy_index(index as usize)
+        .ok_or_else(|| format_err!("Unknown provider {}", index))
+}
+
+fn convert_all<L, E>(items: Vec<L>) -> Result<Vec<E>, Error>
+where
+    L: TryInto<E, Error = Error>,
+{
+    items.into_iter().map(L::try_into).collect()
+}
+
+// bincode stores enum variants by their discriminant, which is why providers are read as u32
+
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+struct LegacyTrack {
+    id: Option<usize>,
+    title: String,
+    artist_id: Option<usize>,
+    artist: Option<LegacyArtist>,
+    album_id: Option<usize>,
+    album: Option<LegacyAlbum>,
+    provider: u32,
+    uri: String,
+    thumbnail: ThumbnailState,
+    duration: Option<u64>,
+    meta: HashMap<String, MetaValue>,
+    explicit: Option<bool>,
+    rating: Rating,
+    position: Option<TrackPosition>,
+    share_url: Option<String>,
+    lyrics: Lyrics,
+    comments: Option<String>,
+    chapters: Vec<Chapter>,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+struct LegacyAlbum {
+    id: Option<usize>,
+    title: String,
+    artist_id: Option<usize>,
+    artist: Option<LegacyArtist>,
+    tracks: Vec<LegacyTrack>,
+    provider: u32,
+    thumbnail: ThumbnailState,
+    uri: String,
+    meta: HashMap<String, MetaValue>,
+    explicit: Option<bool>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+struct LegacyArtist {
+    id: Option<usize>,
+    name: String,
+    uri: String,
+    image_url: Option<String>,
+    meta: HashMap<String, MetaValue>,
+    provider: u32,
+    albums: Vec<LegacyAlbum>,
+    playlists: Vec<LegacyPlaylist>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+struct LegacyPlaylist {
+    id: Option<usize>,
+    title: String,
+    tracks: Vec<LegacyTrack>,
+    provider: u32,
+    uri: String,
+}
+
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(serde_derive::Serialize))]
+struct LegacyPlayRecord {
+    track: LegacyTrack,
+    player: String,
+    started_at: u64,
+    listened: u64,
+    completed: bool,
+}
+
+impl TryFrom<LegacyTrack> for Track {
+    type Error = Error;
+
+    fn try_from(track: LegacyTrack) -> Result<Self, Self::Error> {
+        Ok(Track {
+            id: track.id,
+            title: track.title,
+            artist_id: track.artist_id,
+            artist: track.artist.map(Artist::try_from).transpose()?,
+            album_id: track.album_id,
+            album: track.album.map(Album::try_from).transpose()?,
+            provider: provider(track.provider)?,
+            uri: track.uri,
+            thumbnail: track.thumbnail,
+            duration: track.duration,
+            meta: track.meta,
+            explicit: track.explicit,
+            rating: track.rating,
+            position: track.position,
+            share_url: track.share_url,
+            lyrics: track.lyrics,
+            comments: track.comments,
+            chapters: track.chapters,
+        })
+    }
+}
+
+impl TryFrom<LegacyAlbum> for Album {
+    type Error = Error;
+
+    fn try_from(album: LegacyAlbum) -> Result<Self, Self::Error> {
+        Ok(Album {
+            id: album.id,
+            title: album.title,
+            artist_id: album.artist_id,
+            artist: album.artist.map(Artist::try_from).transpose()?,
+            tracks: convert_all(album.tracks)?,
+            provider: provider(album.provider)?,
+            thumbnail: album.thumbnail,
+            uri: album.uri,
+            meta: album.meta,
+            explicit: album.explicit,
+            description: album.description,
+        })
+    }
+}
+
+impl TryFrom<LegacyArtist> for Artist {
+    type Error = Error;
+
+    fn try_from(artist: LegacyArtist) -> Result<Self, Self::Error> {
+        Ok(Artist {
+            id: artist.id,
+            name: artist.name,
+            uri: artist.uri,
+            image_url: artist.image_url,
+            meta: artist.meta,
+            provider: provider(artist.provider)?,
+            albums: convert_all(artist.albums)?,
+            playlists: convert_all(artist.playlists)?,
+            description: artist.description,
+        })
+    }
+}
+
+impl TryFrom<LegacyPlaylist> for Playlist {
+    type Error = Error;
+
+    fn try_from(playlist: LegacyPlaylist) -> Result<Self, Self::Error> {
+        Ok(Playlist {
+            id: playlist.id,
+            title: playlist.title,
+            tracks: convert_all(playlist.tracks)?,
+            provider: provider(playlist.provider)?,
+            uri: playlist.uri,
+        })
+    }
+}
+
+impl TryFrom<LegacyPlayRecord> for PlayRecord {
+    type Error = Error;
+
+    fn try_from(play: LegacyPlayRecord) -> Result<Self, Self::Error> {
+        Ok(PlayRecord {
+            track: play.track.try_into()?,
+            player: play.player,
+            started_at: play.started_at,
+            listened: play.listened,
+            completed: play.completed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bincode::{deserialize, serialize};
+
+    use rustic_core::library::Lyrics;
+    use rustic_core::provider::{ProviderId, ThumbnailState};
+    use rustic_core::{Rating, Track};
+
+    use super::{migrate, LegacyTrack};
+    use crate::util::serialize_id;
+
+    #[test]
+    fn migrate_should_map_legacy_providers_to_ids() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let tracks = db.open_tree("tracks").unwrap();
+        let track = LegacyTrack {
+            id: Some(1),
+            title: String::from("Track"),
+            artist_id: None,
+            artist: None,
+            album_id: None,
+            album: None,
+            provider: 4,
+            uri: String::from("spotify:track:1"),
+            thumbnail: ThumbnailState::None,
+            duration: None,
+            meta: HashMap::new(),
+            explicit: None,
+            rating: Rating::None,
+            position: None,
+            share_url: None,
+            lyrics: Lyrics::None,
+            comments: None,
+            chapters: Vec::new(),
+        };
+        let key = serialize_id(1).unwrap();
+        tracks.insert(&key, serialize(&track).unwrap()).unwrap();
+
+        migrate(&db).unwrap();
+
+        let bytes = tracks.get(&key).unwrap().unwrap();
+        let track: Track = deserialize(&bytes).unwrap();
+        assert_eq!(track.provider, ProviderId::new("spotify"));
+    }
+}
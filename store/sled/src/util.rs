@@ -10,7 +10,6 @@ pub fn serialize_id(id: usize) -> Result<Vec<u8>, Error> {
     Ok(id_bytes)
 }
 
-#[allow(unused)]
 pub fn deserialize_id(id: &[u8]) -> Result<usize, Error> {
     let mut bytes = id;
     let id = bytes.read_u64::<LittleEndian>()?;
@@ -18,6 +17,15 @@ pub fn deserialize_id(id: &[u8]) -> Result<usize, Error> {
     Ok(id as usize)
 }
 
+/// Increments the counter stored in the given value, used with `update_and_fetch`
+pub fn increment(value: Option<&[u8]>) -> Option<Vec<u8>> {
+    let current = value
+        .and_then(|value| deserialize_id(value).ok())
+        .unwrap_or_default();
+
+    serialize_id(current + 1).ok()
+}
+
 pub fn fetch_entity<E>(tree: &sled::Tree, id: usize) -> Result<Option<E>, Error>
 where
     E: DeserializeOwned,
@@ -0,0 +1,2 @@
+DROP INDEX revisions_revision_index;
+DROP TABLE revisions;
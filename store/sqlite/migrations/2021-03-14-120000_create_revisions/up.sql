@@ -0,0 +1,11 @@
+CREATE TABLE revisions
+(
+    kind INTEGER NOT NULL,
+    uri TEXT NOT NULL,
+    created BIGINT NOT NULL,
+    revision BIGINT NOT NULL,
+    removed BOOLEAN NOT NULL,
+    fields TEXT NOT NULL,
+    CONSTRAINT revisions_pk PRIMARY KEY (kind, uri)
+);
+CREATE INDEX revisions_revision_index ON revisions (revision);
@@ -0,0 +1,21 @@
+-- SQLite before 3.35 can't drop columns, so the table is rebuilt without the rating
+CREATE TABLE tracks_new
+(
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  title VARCHAR(255) NOT NULL,
+  artist_id INTEGER,
+  album_id INTEGER,
+  uri TEXT NOT NULL,
+  image_url TEXT,
+  duration INTEGER,
+  provider INTEGER NOT NULL,
+  CONSTRAINT tracks_artists_id_fk FOREIGN KEY (artist_id) REFERENCES artists (id) ON DELETE SET NULL,
+  CONSTRAINT tracks_albums_id_fk FOREIGN KEY (album_id) REFERENCES albums (id) ON DELETE SET NULL
+);
+INSERT INTO tracks_new (id, title, artist_id, album_id, uri, image_url, duration, provider)
+SELECT id, title, artist_id, album_id, uri, image_url, duration, provider
+FROM tracks;
+DROP TABLE tracks;
+ALTER TABLE tracks_new RENAME TO tracks;
+CREATE UNIQUE INDEX tracks_id_uindex ON tracks (id);
+CREATE UNIQUE INDEX tracks_uri_uindex ON tracks (uri);
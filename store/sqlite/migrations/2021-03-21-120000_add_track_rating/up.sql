@@ -0,0 +1 @@
+ALTER TABLE tracks ADD COLUMN rating INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+DROP INDEX plays_track_uri_index;
+DROP INDEX plays_started_at_index;
+DROP TABLE plays;
@@ -0,0 +1,12 @@
+CREATE TABLE plays
+(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    track_uri TEXT NOT NULL,
+    player TEXT NOT NULL,
+    started_at BIGINT NOT NULL,
+    listened BIGINT NOT NULL,
+    completed BOOLEAN NOT NULL,
+    track TEXT NOT NULL
+);
+CREATE INDEX plays_started_at_index ON plays (started_at);
+CREATE INDEX plays_track_uri_index ON plays (track_uri);
@@ -0,0 +1,117 @@
+-- Provider keys which have no discriminant, e.g. those of additional accounts, fail the migration.
+
+CREATE TABLE artists_new
+(
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  name VARCHAR(255) NOT NULL,
+  image_url TEXT,
+  uri TEXT NOT NULL,
+  provider INTEGER NOT NULL
+);
+INSERT INTO artists_new (id, name, image_url, uri, provider)
+SELECT id, name, image_url, uri,
+    CASE provider
+        WHEN 'internal' THEN 0
+        WHEN 'pocketcasts' THEN 1
+        WHEN 'soundcloud' THEN 2
+        WHEN 'gmusic' THEN 3
+        WHEN 'spotify' THEN 4
+        WHEN 'local' THEN 5
+        WHEN 'youtube' THEN 6
+        WHEN 'ytmusic' THEN 7
+        WHEN 'extension' THEN 8
+        ELSE NULL
+    END
+FROM artists;
+DROP TABLE artists;
+ALTER TABLE artists_new RENAME TO artists;
+CREATE UNIQUE INDEX artists_id_uindex ON artists (id);
+
+CREATE TABLE albums_new
+(
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  title VARCHAR(255) NOT NULL,
+  artist_id INTEGER,
+  image_url TEXT,
+  uri TEXT NOT NULL,
+  provider INTEGER NOT NULL,
+  CONSTRAINT albums_artists_id_fk FOREIGN KEY (artist_id) REFERENCES artists (id) ON DELETE SET NULL
+);
+INSERT INTO albums_new (id, title, artist_id, image_url, uri, provider)
+SELECT id, title, artist_id, image_url, uri,
+    CASE provider
+        WHEN 'internal' THEN 0
+        WHEN 'pocketcasts' THEN 1
+        WHEN 'soundcloud' THEN 2
+        WHEN 'gmusic' THEN 3
+        WHEN 'spotify' THEN 4
+        WHEN 'local' THEN 5
+        WHEN 'youtube' THEN 6
+        WHEN 'ytmusic' THEN 7
+        WHEN 'extension' THEN 8
+        ELSE NULL
+    END
+FROM albums;
+DROP TABLE albums;
+ALTER TABLE albums_new RENAME TO albums;
+CREATE UNIQUE INDEX albums_id_uindex ON albums (id);
+
+CREATE TABLE tracks_new
+(
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  title VARCHAR(255) NOT NULL,
+  artist_id INTEGER,
+  album_id INTEGER,
+  uri TEXT NOT NULL,
+  image_url TEXT,
+  duration INTEGER,
+  provider INTEGER NOT NULL,
+  rating INTEGER NOT NULL DEFAULT 0,
+  CONSTRAINT tracks_artists_id_fk FOREIGN KEY (artist_id) REFERENCES artists (id) ON DELETE SET NULL,
+  CONSTRAINT tracks_albums_id_fk FOREIGN KEY (album_id) REFERENCES albums (id) ON DELETE SET NULL
+);
+INSERT INTO tracks_new (id, title, artist_id, album_id, uri, image_url, duration, rating, provider)
+SELECT id, title, artist_id, album_id, uri, image_url, duration, rating,
+    CASE provider
+        WHEN 'internal' THEN 0
+        WHEN 'pocketcasts' THEN 1
+        WHEN 'soundcloud' THEN 2
+        WHEN 'gmusic' THEN 3
+        WHEN 'spotify' THEN 4
+        WHEN 'local' THEN 5
+        WHEN 'youtube' THEN 6
+        WHEN 'ytmusic' THEN 7
+        WHEN 'extension' THEN 8
+        ELSE NULL
+    END
+FROM tracks;
+DROP TABLE tracks;
+ALTER TABLE tracks_new RENAME TO tracks;
+CREATE UNIQUE INDEX tracks_id_uindex ON tracks (id);
+CREATE UNIQUE INDEX tracks_uri_uindex ON tracks (uri);
+
+CREATE TABLE playlists_new
+(
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  title VARCHAR(255) NOT NULL,
+  uri TEXT NOT NULL,
+  provider INTEGER NOT NULL
+);
+INSERT INTO playlists_new (id, title, uri, provider)
+SELECT id, title, uri,
+    CASE provider
+        WHEN 'internal' THEN 0
+        WHEN 'pocketcasts' THEN 1
+        WHEN 'soundcloud' THEN 2
+        WHEN 'gmusic' THEN 3
+        WHEN 'spotify' THEN 4
+        WHEN 'local' THEN 5
+        WHEN 'youtube' THEN 6
+        WHEN 'ytmusic' THEN 7
+        WHEN 'extension' THEN 8
+        ELSE NULL
+    END
+FROM playlists;
+DROP TABLE playlists;
+ALTER TABLE playlists_new RENAME TO playlists;
+CREATE UNIQUE INDEX playlists_id_uindex ON playlists (id);
@@ -1,7 +1,6 @@
 use std::collections::HashMap;
 use std::convert::TryInto;
 
-use entities::provider::{int_to_provider, provider_to_int};
 use rustic_core::library::MetaValue;
 use rustic_core::provider::ThumbnailState;
 use rustic_core::Album;
@@ -15,7 +14,7 @@ pub struct AlbumEntity {
     pub artist_id: Option<i32>,
     pub image_url: Option<String>,
     pub uri: String,
-    pub provider: i32,
+    pub provider: String,
 }
 
 #[derive(Identifiable, Queryable, Associations, PartialEq, Debug)]
@@ -66,7 +65,7 @@ impl AlbumEntity {
             artist_id: self.artist_id.map(|id| id as usize),
             artist: None,
             tracks: vec![],
-            provider: int_to_provider(self.provider),
+            provider: self.provider.into(),
             thumbnail: self.image_url.map(ThumbnailState::Url).unwrap_or_default(),
             uri: self.uri,
             meta: AlbumMeta::to_meta_map(meta),
@@ -76,14 +75,15 @@ impl AlbumEntity {
     }
 }
 
-#[derive(Insertable)]
+#[derive(Insertable, AsChangeset)]
 #[table_name = "albums"]
+#[changeset_options(treat_none_as_null = "true")]
 pub struct AlbumInsert {
     pub title: String,
     pub artist_id: Option<i32>,
     pub image_url: Option<String>,
     pub uri: String,
-    pub provider: i32,
+    pub provider: String,
 }
 
 impl From<Album> for AlbumInsert {
@@ -93,7 +93,7 @@ impl From<Album> for AlbumInsert {
             artist_id: album.artist_id.map(|id| id as i32),
             uri: album.uri,
             image_url: album.thumbnail.to_url(),
-            provider: provider_to_int(album.provider),
+            provider: album.provider.into(),
         }
     }
 }
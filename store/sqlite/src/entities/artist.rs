@@ -1,7 +1,6 @@
 use std::collections::HashMap;
 use std::convert::TryInto;
 
-use entities::provider::{int_to_provider, provider_to_int};
 use rustic_core::library::MetaValue;
 use rustic_core::Artist;
 use schema::{artists, artists_meta};
@@ -13,7 +12,7 @@ pub struct ArtistEntity {
     pub name: String,
     pub image_url: Option<String>,
     pub uri: String,
-    pub provider: i32,
+    pub provider: String,
 }
 
 #[derive(Identifiable, Queryable, Associations, PartialEq, Debug)]
@@ -56,13 +55,14 @@ impl From<&ArtistMeta> for MetaValue {
     }
 }
 
-#[derive(Insertable)]
+#[derive(Insertable, AsChangeset)]
 #[table_name = "artists"]
+#[changeset_options(treat_none_as_null = "true")]
 pub struct ArtistInsert {
     pub name: String,
     pub image_url: Option<String>,
     pub uri: String,
-    pub provider: i32,
+    pub provider: String,
 }
 
 impl ArtistEntity {
@@ -73,7 +73,7 @@ impl ArtistEntity {
             uri: self.uri,
             image_url: self.image_url,
             meta: ArtistMeta::to_meta_map(meta),
-            provider: int_to_provider(self.provider),
+            provider: self.provider.into(),
             // TODO: get via mappings
             albums: Vec::new(),
             // TODO: add mappings
@@ -89,7 +89,7 @@ impl From<Artist> for ArtistInsert {
             name: artist.name,
             image_url: artist.image_url,
             uri: artist.uri,
-            provider: provider_to_int(artist.provider),
+            provider: artist.provider.into(),
         }
     }
 }
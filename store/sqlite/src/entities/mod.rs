@@ -1,7 +1,8 @@
 pub mod album;
 pub mod artist;
+pub mod play;
 pub mod playlist;
-pub mod provider;
+pub mod revision;
 pub mod track;
 
 pub use self::album::*;
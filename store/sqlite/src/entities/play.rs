@@ -0,0 +1,52 @@
+use failure::Error;
+
+use rustic_core::library::PlayRecord;
+use schema::plays;
+
+#[derive(Queryable, PartialEq, Debug)]
+pub struct PlayEntity {
+    pub id: i32,
+    pub track_uri: String,
+    pub player: String,
+    pub started_at: i64,
+    pub listened: i64,
+    pub completed: bool,
+    /// Json encoded track as it was played
+    pub track: String,
+}
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "plays"]
+pub struct PlayInsert {
+    pub track_uri: String,
+    pub player: String,
+    pub started_at: i64,
+    pub listened: i64,
+    pub completed: bool,
+    pub track: String,
+}
+
+impl PlayEntity {
+    pub fn into_play(self) -> Result<PlayRecord, Error> {
+        Ok(PlayRecord {
+            track: serde_json::from_str(&self.track)?,
+            player: self.player,
+            started_at: self.started_at as u64,
+            listened: self.listened as u64,
+            completed: self.completed,
+        })
+    }
+}
+
+impl PlayInsert {
+    pub fn from_play(play: &PlayRecord) -> Result<Self, Error> {
+        Ok(PlayInsert {
+            track_uri: play.track.uri.clone(),
+            player: play.player.clone(),
+            started_at: play.started_at as i64,
+            listened: play.listened as i64,
+            completed: play.completed,
+            track: serde_json::to_string(&play.track)?,
+        })
+    }
+}
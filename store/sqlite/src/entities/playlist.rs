@@ -1,4 +1,3 @@
-use entities::provider::{int_to_provider, provider_to_int};
 use rustic_core::Playlist;
 use schema::{playlist_tracks, playlists};
 
@@ -11,7 +10,7 @@ pub struct PlaylistEntity {
     pub id: i32,
     pub title: String,
     pub uri: String,
-    pub provider: i32,
+    pub provider: String,
 }
 
 impl PlaylistEntity {
@@ -20,7 +19,7 @@ impl PlaylistEntity {
             id: Some(self.id as usize),
             title: self.title,
             uri: self.uri,
-            provider: int_to_provider(self.provider),
+            provider: self.provider.into(),
             tracks: tracks
                 .into_iter()
                 .map(|(track, meta)| track.into_track(&meta))
@@ -44,15 +43,16 @@ impl From<Playlist> for PlaylistInsert {
         PlaylistInsert {
             title: playlist.title,
             uri: playlist.uri,
-            provider: provider_to_int(playlist.provider),
+            provider: playlist.provider.into(),
         }
     }
 }
 
-#[derive(Insertable)]
+#[derive(Insertable, AsChangeset)]
 #[table_name = "playlists"]
+#[changeset_options(treat_none_as_null = "true")]
 pub struct PlaylistInsert {
     pub title: String,
     pub uri: String,
-    pub provider: i32,
+    pub provider: String,
 }
@@ -0,0 +1,59 @@
+use failure::Error;
+
+use rustic_core::library::{ItemRevision, LibraryItemKind};
+use schema::revisions;
+
+#[derive(Queryable, Insertable, PartialEq, Debug)]
+#[table_name = "revisions"]
+pub struct RevisionEntity {
+    pub kind: i32,
+    pub uri: String,
+    pub created: i64,
+    pub revision: i64,
+    pub removed: bool,
+    /// Json encoded list of changed fields with their revision
+    pub fields: String,
+}
+
+impl RevisionEntity {
+    pub fn into_revision(self) -> Result<ItemRevision, Error> {
+        Ok(ItemRevision {
+            kind: int_to_kind(self.kind),
+            uri: self.uri,
+            created: self.created as u64,
+            revision: self.revision as u64,
+            removed: self.removed,
+            fields: serde_json::from_str(&self.fields)?,
+        })
+    }
+
+    pub fn from_revision(revision: &ItemRevision) -> Result<Self, Error> {
+        Ok(RevisionEntity {
+            kind: kind_to_int(revision.kind),
+            uri: revision.uri.clone(),
+            created: revision.created as i64,
+            revision: revision.revision as i64,
+            removed: revision.removed,
+            fields: serde_json::to_string(&revision.fields)?,
+        })
+    }
+}
+
+pub fn kind_to_int(kind: LibraryItemKind) -> i32 {
+    match kind {
+        LibraryItemKind::Track => 0,
+        LibraryItemKind::Album => 1,
+        LibraryItemKind::Artist => 2,
+        LibraryItemKind::Playlist => 3,
+    }
+}
+
+pub fn int_to_kind(kind: i32) -> LibraryItemKind {
+    match kind {
+        0 => LibraryItemKind::Track,
+        1 => LibraryItemKind::Album,
+        2 => LibraryItemKind::Artist,
+        3 => LibraryItemKind::Playlist,
+        _ => unreachable!("someone tampered with the data"),
+    }
+}
@@ -1,6 +1,5 @@
 use std::collections::HashMap;
 
-use entities::provider::{int_to_provider, provider_to_int};
 use rustic_core::library::{Lyrics, MetaValue};
 use rustic_core::provider::ThumbnailState;
 use rustic_core::{Rating, Track};
@@ -17,7 +16,8 @@ pub struct TrackEntity {
     pub uri: String,
     pub image_url: Option<String>,
     pub duration: Option<i32>,
-    pub provider: i32,
+    pub provider: String,
+    pub rating: i32,
 }
 
 #[derive(Identifiable, Queryable, Associations, PartialEq, Debug)]
@@ -69,13 +69,13 @@ impl TrackEntity {
             artist: None,
             album_id: self.album_id.map(|id| id as usize),
             album: None,
-            provider: int_to_provider(self.provider),
+            provider: self.provider.into(),
             uri: self.uri,
             thumbnail: self.image_url.map(ThumbnailState::Url).unwrap_or_default(),
             duration: self.duration.map(|duration| duration as u64),
             meta: TrackMeta::to_meta_map(meta),
             explicit: None,
-            rating: Rating::None,
+            rating: int_to_rating(self.rating),
             position: None,
             share_url: None,
             comments: None,
@@ -85,8 +85,9 @@ impl TrackEntity {
     }
 }
 
-#[derive(Insertable)]
+#[derive(Insertable, AsChangeset)]
 #[table_name = "tracks"]
+#[changeset_options(treat_none_as_null = "true")]
 pub struct TrackInsert {
     pub title: String,
     pub artist_id: Option<i32>,
@@ -94,7 +95,8 @@ pub struct TrackInsert {
     pub uri: String,
     pub image_url: Option<String>,
     pub duration: Option<i32>,
-    pub provider: i32,
+    pub provider: String,
+    pub rating: i32,
 }
 
 impl From<Track> for TrackInsert {
@@ -106,7 +108,30 @@ impl From<Track> for TrackInsert {
             uri: track.uri,
             image_url: track.thumbnail.to_url(),
             duration: track.duration.map(|id| id as i32),
-            provider: provider_to_int(track.provider),
+            provider: track.provider.into(),
+            rating: rating_to_int(track.rating),
         }
     }
 }
+
+/// Stars are stored with an offset so they don't collide with likes
+const STARS_OFFSET: i32 = 10;
+
+pub fn rating_to_int(rating: Rating) -> i32 {
+    match rating {
+        Rating::None => 0,
+        Rating::Like => 1,
+        Rating::Dislike => 2,
+        Rating::Stars(stars) => STARS_OFFSET + i32::from(stars),
+    }
+}
+
+pub fn int_to_rating(rating: i32) -> Rating {
+    match rating {
+        0 => Rating::None,
+        1 => Rating::Like,
+        2 => Rating::Dislike,
+        stars if stars >= STARS_OFFSET => Rating::Stars((stars - STARS_OFFSET) as u8),
+        _ => unreachable!("someone tampered with the data"),
+    }
+}
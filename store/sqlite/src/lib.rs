@@ -6,19 +6,32 @@ extern crate diesel_migrations;
 extern crate failure;
 #[macro_use]
 extern crate log;
-extern crate rustic_core;
 extern crate futures;
+extern crate parking_lot;
+extern crate rustic_core;
+extern crate rustic_queue;
+extern crate rustic_store_helpers;
+extern crate serde_json;
 
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use diesel::prelude::*;
 use diesel::sqlite::SqliteConnection;
 use failure::Error;
-use futures::stream::BoxStream;
+use futures::stream::{BoxStream, StreamExt};
+use parking_lot::ReentrantMutex;
 
+use rustic_core::library::{
+    ChangedField, Changes, HistoryQuery, Identifiable, ItemRevision, PlayRecord, TrackPlays,
+};
 use rustic_core::{
-    Album, Artist, LibraryEvent, MultiQuery, Playlist, SearchResults, SingleQuery, Track,
+    Album, Artist, LibraryChange, LibraryEvent, MultiQuery, Playlist, Rating, SearchResults,
+    SingleQuery, Track,
 };
+use rustic_queue::{multicast, MulticastReceiver, MulticastSender};
+use rustic_store_helpers::collect_changes;
 
 use crate::repositories::*;
 
@@ -30,11 +43,16 @@ mod schema;
 
 #[derive(Clone)]
 pub struct SqliteLibrary {
-    connection: Arc<Mutex<SqliteConnection>>,
+    connection: Connection,
     albums: AlbumRepository,
     artists: ArtistRepository,
     tracks: TrackRepository,
     playlists: PlaylistRepository,
+    revisions: RevisionRepository,
+    revision: Arc<AtomicU64>,
+    plays: PlayRepository,
+    event_sender: MulticastSender<LibraryChange>,
+    event_receiver: MulticastReceiver<LibraryChange>,
 }
 
 impl std::fmt::Debug for SqliteLibrary {
@@ -51,11 +69,15 @@ impl SqliteLibrary {
         debug!("Migrating Database");
         embedded_migrations::run(&connection)?;
 
-        let connection = Arc::new(Mutex::new(connection));
+        let connection = Arc::new(ReentrantMutex::new(connection));
         let album_repository = AlbumRepository::new(Arc::clone(&connection));
         let artist_repository = ArtistRepository::new(Arc::clone(&connection));
         let track_repository = TrackRepository::new(Arc::clone(&connection));
         let playlist_repository = PlaylistRepository::new(Arc::clone(&connection));
+        let revision_repository = RevisionRepository::new(Arc::clone(&connection));
+        let revision = revision_repository.latest()?;
+        let play_repository = PlayRepository::new(Arc::clone(&connection));
+        let (event_sender, event_receiver) = multicast();
 
         Ok(SqliteLibrary {
             connection,
@@ -63,8 +85,219 @@ impl SqliteLibrary {
             artists: artist_repository,
             tracks: track_repository,
             playlists: playlist_repository,
+            revisions: revision_repository,
+            revision: Arc::new(AtomicU64::new(revision)),
+            plays: play_repository,
+            event_sender,
+            event_receiver,
+        })
+    }
+
+    /// Runs the changes in a transaction and notifies subscribers once it is committed
+    ///
+    /// The closure collects the changes it recorded with [SqliteLibrary::emit].
+    fn transaction<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Vec<LibraryChange>) -> Result<T, Error>,
+    {
+        let connection = self.connection.lock();
+        let mut changes = Vec::new();
+        let result = connection.transaction(|| f(&mut changes))?;
+        if let Some(change) = changes.last() {
+            self.revision.store(change.revision, Ordering::SeqCst);
+        }
+        drop(connection);
+        for change in changes {
+            self.event_sender.send(change);
+        }
+
+        Ok(result)
+    }
+
+    /// Records the change with the next revision
+    ///
+    /// Only valid inside of [SqliteLibrary::transaction], the revision is published on commit.
+    fn emit(&self, event: LibraryEvent, changes: &mut Vec<LibraryChange>) -> Result<(), Error> {
+        let revision = self.revision.load(Ordering::SeqCst) + changes.len() as u64 + 1;
+        let previous = self.revisions.query(event.item_kind(), event.uri())?;
+        let item_revision = ItemRevision::record(previous, revision, &event);
+        self.revisions.save(&item_revision)?;
+        changes.push(LibraryChange::new(revision, event));
+
+        Ok(())
+    }
+
+    /// Compares the stored item before and after a sync
+    ///
+    /// Only persisted fields are compared, everything else is lost in the database anyway.
+    fn emit_sync<T: SyncItem>(
+        &self,
+        previous: Option<T>,
+        current: Option<T>,
+        changes: &mut Vec<LibraryChange>,
+    ) -> Result<(), Error> {
+        match (previous, current) {
+            (None, Some(current)) => self.emit(current.added(), changes),
+            (Some(previous), Some(current)) => {
+                let fields = current.changes(&previous);
+                if fields.is_empty() {
+                    Ok(())
+                } else {
+                    self.emit(current.updated(fields), changes)
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Inserts the items and records them as added
+    fn add_all<T: SyncItem>(
+        &self,
+        repository: &dyn Repository<T>,
+        items: &mut Vec<T>,
+    ) -> Result<(), Error> {
+        self.transaction(|changes| {
+            repository.insert_all(items)?;
+            let uris: Vec<String> = items.iter().map(Identifiable::get_uri).collect();
+            let mut stored = by_uri(repository.query_uris(&uris)?);
+            for item in items.iter_mut() {
+                let stored = stored.remove(&item.get_uri());
+                item.set_id(stored.as_ref().and_then(Identifiable::get_id));
+                self.emit_sync(None, stored, changes)?;
+            }
+            Ok(())
         })
     }
+
+    /// Inserts or updates the items in one transaction
+    ///
+    /// The stored items are loaded with one query before and one after writing the items.
+    fn sync_all<T: SyncItem>(
+        &self,
+        repository: &dyn Repository<T>,
+        items: &mut Vec<T>,
+    ) -> Result<(), Error> {
+        self.transaction(|changes| {
+            let uris: Vec<String> = items.iter().map(Identifiable::get_uri).collect();
+            let mut previous = by_uri(repository.query_uris(&uris)?);
+            let mut written = HashSet::new();
+            for item in items.iter_mut() {
+                let uri = item.get_uri();
+                if let Some(stored) = previous.get(&uri) {
+                    item.keep_stored_fields(stored);
+                }
+                if previous.contains_key(&uri) || !written.insert(uri) {
+                    repository.update(item)?;
+                } else {
+                    repository.insert(item)?;
+                }
+            }
+            let mut current = by_uri(repository.query_uris(&uris)?);
+            for item in items.iter_mut() {
+                let uri = item.get_uri();
+                item.set_id(current.get(&uri).and_then(Identifiable::get_id));
+                // duplicates are only reported once
+                if let Some(stored) = current.remove(&uri) {
+                    self.emit_sync(previous.remove(&uri), Some(stored), changes)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn sync_one<T: SyncItem>(
+        &self,
+        repository: &dyn Repository<T>,
+        item: &mut T,
+    ) -> Result<(), Error> {
+        let mut items = vec![item.clone()];
+        self.sync_all(repository, &mut items)?;
+        *item = items.remove(0);
+
+        Ok(())
+    }
+}
+
+fn by_uri<T: Identifiable>(items: Vec<T>) -> HashMap<String, T> {
+    items
+        .into_iter()
+        .map(|item| (item.get_uri(), item))
+        .collect()
+}
+
+/// Library items which are synced with the database
+trait SyncItem: Identifiable + Changes + Clone {
+    fn set_id(&mut self, id: Option<usize>);
+
+    fn added(self) -> LibraryEvent;
+
+    fn updated(self, changes: Vec<ChangedField>) -> LibraryEvent;
+
+    /// Copies fields from the stored item which a sync must not reset
+    fn keep_stored_fields(&mut self, _stored: &Self) {}
+}
+
+impl SyncItem for Track {
+    fn set_id(&mut self, id: Option<usize>) {
+        self.id = id;
+    }
+
+    fn added(self) -> LibraryEvent {
+        LibraryEvent::TrackAdded(self)
+    }
+
+    fn updated(self, changes: Vec<ChangedField>) -> LibraryEvent {
+        LibraryEvent::TrackUpdated(self, changes)
+    }
+
+    /// Ratings are not known by every provider
+    fn keep_stored_fields(&mut self, stored: &Self) {
+        if self.rating == Rating::None {
+            self.rating = stored.rating;
+        }
+    }
+}
+
+impl SyncItem for Album {
+    fn set_id(&mut self, id: Option<usize>) {
+        self.id = id;
+    }
+
+    fn added(self) -> LibraryEvent {
+        LibraryEvent::AlbumAdded(self)
+    }
+
+    fn updated(self, changes: Vec<ChangedField>) -> LibraryEvent {
+        LibraryEvent::AlbumUpdated(self, changes)
+    }
+}
+
+impl SyncItem for Artist {
+    fn set_id(&mut self, id: Option<usize>) {
+        self.id = id;
+    }
+
+    fn added(self) -> LibraryEvent {
+        LibraryEvent::ArtistAdded(self)
+    }
+
+    fn updated(self, changes: Vec<ChangedField>) -> LibraryEvent {
+        LibraryEvent::ArtistUpdated(self, changes)
+    }
+}
+
+impl SyncItem for Playlist {
+    fn set_id(&mut self, id: Option<usize>) {
+        self.id = id;
+    }
+
+    fn added(self) -> LibraryEvent {
+        LibraryEvent::PlaylistAdded(self)
+    }
+
+    fn updated(self, changes: Vec<ChangedField>) -> LibraryEvent {
+        LibraryEvent::PlaylistUpdated(self, changes)
+    }
 }
 
 impl rustic_core::Library for SqliteLibrary {
@@ -101,67 +334,94 @@ impl rustic_core::Library for SqliteLibrary {
     }
 
     fn add_track(&self, track: &mut Track) -> Result<(), Error> {
-        self.tracks.insert(track)
+        let mut tracks = vec![track.clone()];
+        self.add_all(&self.tracks, &mut tracks)?;
+        *track = tracks.remove(0);
+        Ok(())
     }
 
     fn add_album(&self, album: &mut Album) -> Result<(), Error> {
-        self.albums.insert(album)
+        let mut albums = vec![album.clone()];
+        self.add_all(&self.albums, &mut albums)?;
+        *album = albums.remove(0);
+        Ok(())
     }
 
     fn add_artist(&self, artist: &mut Artist) -> Result<(), Error> {
-        self.artists.insert(artist)
+        let mut artists = vec![artist.clone()];
+        self.add_all(&self.artists, &mut artists)?;
+        *artist = artists.remove(0);
+        Ok(())
     }
 
     fn add_playlist(&self, playlist: &mut Playlist) -> Result<(), Error> {
-        self.playlists.insert(playlist)
+        let mut playlists = vec![playlist.clone()];
+        self.add_all(&self.playlists, &mut playlists)?;
+        *playlist = playlists.remove(0);
+        Ok(())
     }
 
     fn add_tracks(&self, tracks: &mut Vec<Track>) -> Result<(), Error> {
-        self.tracks.insert_all(tracks)
+        self.add_all(&self.tracks, tracks)
     }
 
     fn add_albums(&self, albums: &mut Vec<Album>) -> Result<(), Error> {
-        self.albums.insert_all(albums)
+        self.add_all(&self.albums, albums)
     }
 
     fn add_artists(&self, artists: &mut Vec<Artist>) -> Result<(), Error> {
-        self.artists.insert_all(artists)
+        self.add_all(&self.artists, artists)
     }
 
     fn add_playlists(&self, playlists: &mut Vec<Playlist>) -> Result<(), Error> {
-        self.playlists.insert_all(playlists)
+        self.add_all(&self.playlists, playlists)
     }
 
     fn sync_track(&self, track: &mut Track) -> Result<(), Error> {
-        self.tracks.sync(track)
+        self.sync_one(&self.tracks, track)
     }
 
-    fn sync_album(&self, album: &mut Album) -> Result<(), Error> {
-        self.albums.sync(album)
+    fn sync_tracks(&self, tracks: &mut Vec<Track>) -> Result<(), Error> {
+        self.sync_all(&self.tracks, tracks)
+    }
+
+    fn set_rating(&self, uri: &str, rating: Rating) -> Result<(), Error> {
+        self.transaction(|changes| {
+            let query = SingleQuery::uri(uri.to_string());
+            let previous = self
+                .tracks
+                .query(query.clone())?
+                .ok_or_else(|| format_err!("Track not found"))?;
+            let mut track = previous.clone();
+            track.rating = rating;
+            self.tracks.update(&mut track)?;
+            let current = self.tracks.query(query)?;
+            self.emit_sync(Some(previous), current, changes)
+        })
     }
 
-    fn sync_artist(&self, artist: &mut Artist) -> Result<(), Error> {
-        self.artists.sync(artist)
+    fn sync_album(&self, album: &mut Album) -> Result<(), Error> {
+        self.sync_one(&self.albums, album)
     }
 
-    fn sync_playlist(&self, playlist: &mut Playlist) -> Result<(), Error> {
-        self.playlists.sync(playlist)
+    fn sync_albums(&self, albums: &mut Vec<Album>) -> Result<(), Error> {
+        self.sync_all(&self.albums, albums)
     }
 
-    fn sync_tracks(&self, tracks: &mut Vec<Track>) -> Result<(), Error> {
-        self.tracks.sync_all(tracks)
+    fn sync_artist(&self, artist: &mut Artist) -> Result<(), Error> {
+        self.sync_one(&self.artists, artist)
     }
 
-    fn sync_albums(&self, albums: &mut Vec<Album>) -> Result<(), Error> {
-        self.albums.sync_all(albums)
+    fn sync_artists(&self, artists: &mut Vec<Artist>) -> Result<(), Error> {
+        self.sync_all(&self.artists, artists)
     }
 
-    fn sync_artists(&self, artists: &mut Vec<Artist>) -> Result<(), Error> {
-        self.artists.sync_all(artists)
+    fn sync_playlist(&self, playlist: &mut Playlist) -> Result<(), Error> {
+        self.sync_one(&self.playlists, playlist)
     }
 
     fn sync_playlists(&self, playlists: &mut Vec<Playlist>) -> Result<(), Error> {
-        self.playlists.sync_all(playlists)
+        self.sync_all(&self.playlists, playlists)
     }
 
     fn remove_track(&self, _track: &Track) -> Result<(), Error> {
@@ -188,7 +448,124 @@ impl rustic_core::Library for SqliteLibrary {
         Ok(())
     }
 
-    fn observe(&self) -> BoxStream<'static, LibraryEvent> {
-        unimplemented!()
+    fn revision(&self) -> Result<u64, Error> {
+        Ok(self.revision.load(Ordering::Relaxed))
+    }
+
+    fn changes_since(&self, revision: u64) -> Result<Vec<LibraryChange>, Error> {
+        let revisions = self.revisions.query_since(revision)?;
+
+        collect_changes(self, revisions, revision)
+    }
+
+    fn add_play(&self, play: PlayRecord) -> Result<(), Error> {
+        self.plays.insert(&play)
+    }
+
+    fn query_plays(&self, query: HistoryQuery) -> Result<Vec<PlayRecord>, Error> {
+        self.plays.query(query)
+    }
+
+    fn query_track_plays(&self, uris: &[String]) -> Result<HashMap<String, TrackPlays>, Error> {
+        self.plays.query_track_plays(uris)
+    }
+
+    fn observe(&self) -> BoxStream<'static, LibraryChange> {
+        self.event_receiver.stream().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use futures::{FutureExt, StreamExt};
+
+    use rustic_core::library::Lyrics;
+    use rustic_core::provider::ThumbnailState;
+    use rustic_core::{Library, LibraryEvent, ProviderId, Rating, Track};
+
+    use crate::SqliteLibrary;
+
+    fn library() -> SqliteLibrary {
+        SqliteLibrary::new(":memory:".into()).unwrap()
+    }
+
+    fn track(uri: &str) -> Track {
+        Track {
+            id: None,
+            title: "Test Track".into(),
+            artist_id: None,
+            artist: None,
+            album_id: None,
+            album: None,
+            provider: ProviderId::INTERNAL,
+            uri: uri.into(),
+            thumbnail: ThumbnailState::None,
+            duration: None,
+            meta: HashMap::new(),
+            explicit: None,
+            rating: Rating::None,
+            position: None,
+            share_url: None,
+            lyrics: Lyrics::None,
+            comments: None,
+            chapters: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn sync_tracks_should_store_the_tracks_with_one_revision_each() {
+        let library = library();
+        let mut tracks = vec![track("test:1"), track("test:2")];
+
+        library.sync_tracks(&mut tracks).unwrap();
+
+        assert!(tracks.iter().all(|track| track.id.is_some()));
+        assert_eq!(library.revision().unwrap(), 2);
+        assert_eq!(library.changes_since(0).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn sync_tracks_should_not_record_unchanged_tracks() {
+        let library = library();
+        let mut tracks = vec![track("test:1"), track("test:2")];
+        library.sync_tracks(&mut tracks).unwrap();
+
+        let mut tracks = vec![track("test:1"), track("test:2")];
+        tracks[1].title = "Renamed".into();
+        library.sync_tracks(&mut tracks).unwrap();
+
+        assert_eq!(library.revision().unwrap(), 3);
+        let changes = library.changes_since(2).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].event.uri(), "test:2");
+    }
+
+    #[test]
+    fn sync_track_should_keep_the_stored_rating() {
+        let library = library();
+        library.sync_track(&mut track("test:1")).unwrap();
+        library.set_rating("test:1", Rating::Like).unwrap();
+
+        let mut track = track("test:1");
+        library.sync_track(&mut track).unwrap();
+
+        assert_eq!(track.rating, Rating::Like);
+    }
+
+    #[test]
+    fn observe_should_notify_every_subscriber() {
+        let library = library();
+        let first = library.observe();
+        let second = library.observe();
+
+        library.sync_track(&mut track("test:1")).unwrap();
+
+        for mut events in vec![first, second] {
+            let change = events.next().now_or_never().flatten().unwrap();
+            assert_eq!(change.revision, 1);
+            assert!(matches!(change.event, LibraryEvent::TrackAdded(_)));
+        }
     }
 }
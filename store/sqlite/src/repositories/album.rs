@@ -1,22 +1,20 @@
-use std::sync::{Arc, Mutex};
-
+use diesel::insert_into;
 use diesel::prelude::*;
-use diesel::{insert_into, SqliteConnection};
 use failure::Error;
 
 use rustic_core::library::LibraryItemIdentifier;
 use rustic_core::{Album, MultiQuery, SingleQuery};
 
 use crate::entities::album::*;
-use crate::repositories::Repository;
+use crate::repositories::{Connection, Repository};
 
 #[derive(Clone)]
 pub struct AlbumRepository {
-    connection: Arc<Mutex<SqliteConnection>>,
+    connection: Connection,
 }
 
 impl AlbumRepository {
-    pub fn new(connection: Arc<Mutex<SqliteConnection>>) -> Self {
+    pub fn new(connection: Connection) -> Self {
         AlbumRepository { connection }
     }
 }
@@ -25,7 +23,7 @@ impl Repository<Album> for AlbumRepository {
     fn query(&self, query: SingleQuery) -> Result<Option<Album>, Error> {
         use schema::albums::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let album = match query.identifier {
             LibraryItemIdentifier::Id(album_id) => albums
@@ -52,7 +50,7 @@ impl Repository<Album> for AlbumRepository {
     fn query_all(&self, query: MultiQuery) -> Result<Vec<Album>, Error> {
         use schema::albums::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let album_list = albums.load::<AlbumEntity>(&*connection)?;
         let meta = AlbumMeta::belonging_to(&album_list)
@@ -68,10 +66,31 @@ impl Repository<Album> for AlbumRepository {
         Ok(album_list)
     }
 
+    fn query_uris(&self, uris: &[String]) -> Result<Vec<Album>, Error> {
+        use crate::schema::albums::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let album_list = albums
+            .filter(uri.eq_any(uris))
+            .load::<AlbumEntity>(&*connection)?;
+        let meta = AlbumMeta::belonging_to(&album_list)
+            .load::<AlbumMeta>(&*connection)?
+            .grouped_by(&album_list);
+
+        let album_list = album_list
+            .into_iter()
+            .zip(meta)
+            .map(|(entity, meta)| entity.into_album(&meta))
+            .collect();
+
+        Ok(album_list)
+    }
+
     fn insert(&self, album: &mut Album) -> Result<(), Error> {
         use crate::schema::albums::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let entity: AlbumInsert = album.clone().into();
 
@@ -85,7 +104,7 @@ impl Repository<Album> for AlbumRepository {
     fn insert_all(&self, models: &mut Vec<Album>) -> Result<(), Error> {
         use crate::schema::albums::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let entities = models
             .iter()
@@ -103,10 +122,23 @@ impl Repository<Album> for AlbumRepository {
     }
 
     fn update(&self, model: &mut Album) -> Result<(), Error> {
-        unimplemented!()
+        use crate::schema::albums::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let entity: AlbumInsert = model.clone().into();
+
+        diesel::update(albums.filter(uri.eq(&model.uri)))
+            .set(&entity)
+            .execute(&*connection)?;
+
+        Ok(())
     }
 
     fn update_all(&self, models: &mut Vec<Album>) -> Result<(), Error> {
-        unimplemented!()
+        for model in models.iter_mut() {
+            self.update(model)?;
+        }
+        Ok(())
     }
 }
@@ -1,22 +1,20 @@
-use std::sync::{Arc, Mutex};
-
+use diesel::insert_into;
 use diesel::prelude::*;
-use diesel::{insert_into, SqliteConnection};
 use failure::Error;
 
 use rustic_core::library::LibraryItemIdentifier;
 use rustic_core::{Artist, MultiQuery, SingleQuery};
 
 use crate::entities::artist::*;
-use crate::repositories::Repository;
+use crate::repositories::{Connection, Repository};
 
 #[derive(Clone)]
 pub struct ArtistRepository {
-    connection: Arc<Mutex<SqliteConnection>>,
+    connection: Connection,
 }
 
 impl ArtistRepository {
-    pub fn new(connection: Arc<Mutex<SqliteConnection>>) -> Self {
+    pub fn new(connection: Connection) -> Self {
         ArtistRepository { connection }
     }
 }
@@ -25,7 +23,7 @@ impl Repository<Artist> for ArtistRepository {
     fn query(&self, query: SingleQuery) -> Result<Option<Artist>, Error> {
         use crate::schema::artists::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let artist = match query.identifier {
             LibraryItemIdentifier::Id(artist_id) => artists
@@ -52,7 +50,7 @@ impl Repository<Artist> for ArtistRepository {
     fn query_all(&self, query: MultiQuery) -> Result<Vec<Artist>, Error> {
         use crate::schema::artists::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let artist_list = artists.load::<ArtistEntity>(&*connection)?;
         let meta = ArtistMeta::belonging_to(&artist_list)
@@ -68,10 +66,31 @@ impl Repository<Artist> for ArtistRepository {
         Ok(artist_list)
     }
 
+    fn query_uris(&self, uris: &[String]) -> Result<Vec<Artist>, Error> {
+        use crate::schema::artists::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let artist_list = artists
+            .filter(uri.eq_any(uris))
+            .load::<ArtistEntity>(&*connection)?;
+        let meta = ArtistMeta::belonging_to(&artist_list)
+            .load::<ArtistMeta>(&*connection)?
+            .grouped_by(&artist_list);
+
+        let artist_list = artist_list
+            .into_iter()
+            .zip(meta)
+            .map(|(entity, meta)| entity.into_artist(&meta))
+            .collect();
+
+        Ok(artist_list)
+    }
+
     fn insert(&self, artist: &mut Artist) -> Result<(), Error> {
         use crate::schema::artists::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let entity: ArtistInsert = artist.clone().into();
 
@@ -85,7 +104,7 @@ impl Repository<Artist> for ArtistRepository {
     fn insert_all(&self, models: &mut Vec<Artist>) -> Result<(), Error> {
         use crate::schema::artists::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let entities = models
             .iter()
@@ -103,10 +122,23 @@ impl Repository<Artist> for ArtistRepository {
     }
 
     fn update(&self, model: &mut Artist) -> Result<(), Error> {
-        unimplemented!()
+        use crate::schema::artists::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let entity: ArtistInsert = model.clone().into();
+
+        diesel::update(artists.filter(uri.eq(&model.uri)))
+            .set(&entity)
+            .execute(&*connection)?;
+
+        Ok(())
     }
 
     fn update_all(&self, models: &mut Vec<Artist>) -> Result<(), Error> {
-        unimplemented!()
+        for model in models.iter_mut() {
+            self.update(model)?;
+        }
+        Ok(())
     }
 }
@@ -1,25 +1,40 @@
+use std::sync::Arc;
+
+use diesel::SqliteConnection;
 use failure::Error;
+use parking_lot::ReentrantMutex;
 
 use rustic_core::{MultiQuery, SingleQuery};
 
 pub use self::album::AlbumRepository;
 pub use self::artist::ArtistRepository;
+pub use self::play::PlayRepository;
 pub use self::playlist::PlaylistRepository;
+pub use self::revision::RevisionRepository;
 pub use self::track::TrackRepository;
 
 use rustic_core::library::Identifiable;
 
 mod album;
 mod artist;
+mod play;
 mod playlist;
+mod revision;
 mod track;
 
+/// Connection shared by all repositories
+///
+/// The mutex is reentrant so repositories can be used inside of a transaction of the library.
+pub type Connection = Arc<ReentrantMutex<SqliteConnection>>;
+
 pub trait Repository<TModel>
 where
     TModel: Identifiable,
 {
     fn query(&self, query: SingleQuery) -> Result<Option<TModel>, Error>;
     fn query_all(&self, query: MultiQuery) -> Result<Vec<TModel>, Error>;
+    /// All items with one of the given uris, in no particular order
+    fn query_uris(&self, uris: &[String]) -> Result<Vec<TModel>, Error>;
 
     fn insert(&self, model: &mut TModel) -> Result<(), Error>;
     fn insert_all(&self, models: &mut Vec<TModel>) -> Result<(), Error>;
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use diesel::insert_into;
+use diesel::prelude::*;
+use failure::Error;
+
+use rustic_core::library::{HistoryQuery, PlayRecord, TrackPlays};
+
+use crate::entities::play::*;
+use crate::repositories::Connection;
+
+/// Sqlite limits the number of variables in a single statement
+const MAX_URIS_PER_QUERY: usize = 500;
+
+/// Listening history
+#[derive(Clone)]
+pub struct PlayRepository {
+    connection: Connection,
+}
+
+impl PlayRepository {
+    pub fn new(connection: Connection) -> Self {
+        PlayRepository { connection }
+    }
+
+    pub fn query(&self, query: HistoryQuery) -> Result<Vec<PlayRecord>, Error> {
+        use crate::schema::plays::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let mut statement = plays.order(started_at.desc()).into_boxed();
+        if let Some(since) = query.since {
+            statement = statement.filter(started_at.ge(since as i64));
+        }
+        if let Some(until) = query.until {
+            statement = statement.filter(started_at.lt(until as i64));
+        }
+        if let Some(uri) = query.track_uri {
+            statement = statement.filter(track_uri.eq(uri));
+        }
+        if let Some(limit) = query.limit {
+            statement = statement.limit(limit as i64);
+        }
+
+        statement
+            .load::<PlayEntity>(&*connection)?
+            .into_iter()
+            .map(PlayEntity::into_play)
+            .collect()
+    }
+
+    /// Play counts of the given tracks, only the columns required for counting are loaded
+    pub fn query_track_plays(&self, uris: &[String]) -> Result<HashMap<String, TrackPlays>, Error> {
+        use crate::schema::plays::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let mut result: HashMap<String, TrackPlays> = HashMap::new();
+        for uris in uris.chunks(MAX_URIS_PER_QUERY) {
+            let rows = plays
+                .filter(track_uri.eq_any(uris))
+                .select((track_uri, started_at, completed))
+                .load::<(String, i64, bool)>(&*connection)?;
+            for (uri, started, played_completely) in rows {
+                result
+                    .entry(uri)
+                    .or_default()
+                    .add(started as u64, played_completely);
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn insert(&self, play: &PlayRecord) -> Result<(), Error> {
+        use crate::schema::plays::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let entity = PlayInsert::from_play(play)?;
+
+        insert_into(plays).values(&entity).execute(&*connection)?;
+
+        Ok(())
+    }
+}
@@ -1,22 +1,20 @@
-use std::sync::{Arc, Mutex};
-
+use diesel::insert_into;
 use diesel::prelude::*;
-use diesel::{insert_into, SqliteConnection};
 use failure::Error;
 
 use rustic_core::library::LibraryItemIdentifier;
 use rustic_core::{MultiQuery, Playlist, SingleQuery};
 
 use crate::entities::playlist::*;
-use crate::repositories::Repository;
+use crate::repositories::{Connection, Repository};
 
 #[derive(Clone)]
 pub struct PlaylistRepository {
-    connection: Arc<Mutex<SqliteConnection>>,
+    connection: Connection,
 }
 
 impl PlaylistRepository {
-    pub fn new(connection: Arc<Mutex<SqliteConnection>>) -> Self {
+    pub fn new(connection: Connection) -> Self {
         PlaylistRepository { connection }
     }
 }
@@ -25,7 +23,7 @@ impl Repository<Playlist> for PlaylistRepository {
     fn query(&self, query: SingleQuery) -> Result<Option<Playlist>, Error> {
         use schema::playlists::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let playlist = match query.identifier {
             LibraryItemIdentifier::Id(playlist_id) => playlists
@@ -52,7 +50,7 @@ impl Repository<Playlist> for PlaylistRepository {
     fn query_all(&self, query: MultiQuery) -> Result<Vec<Playlist>, Error> {
         use schema::playlists::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let playlist_list = playlists.load::<PlaylistEntity>(&*connection)?;
 
@@ -64,10 +62,25 @@ impl Repository<Playlist> for PlaylistRepository {
         Ok(playlist_list)
     }
 
+    fn query_uris(&self, uris: &[String]) -> Result<Vec<Playlist>, Error> {
+        use crate::schema::playlists::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let playlist_list = playlists
+            .filter(uri.eq_any(uris))
+            .load::<PlaylistEntity>(&*connection)?
+            .into_iter()
+            .map(|entity| entity.into_playlist(vec![]))
+            .collect();
+
+        Ok(playlist_list)
+    }
+
     fn insert(&self, playlist: &mut Playlist) -> Result<(), Error> {
         use crate::schema::playlists::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let entity: PlaylistInsert = playlist.clone().into();
 
@@ -83,7 +96,7 @@ impl Repository<Playlist> for PlaylistRepository {
     fn insert_all(&self, models: &mut Vec<Playlist>) -> Result<(), Error> {
         use crate::schema::playlists::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let entities = models
             .iter()
@@ -101,10 +114,23 @@ impl Repository<Playlist> for PlaylistRepository {
     }
 
     fn update(&self, model: &mut Playlist) -> Result<(), Error> {
-        unimplemented!()
+        use crate::schema::playlists::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let entity: PlaylistInsert = model.clone().into();
+
+        diesel::update(playlists.filter(uri.eq(&model.uri)))
+            .set(&entity)
+            .execute(&*connection)?;
+
+        Ok(())
     }
 
     fn update_all(&self, models: &mut Vec<Playlist>) -> Result<(), Error> {
-        unimplemented!()
+        for model in models.iter_mut() {
+            self.update(model)?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,79 @@
+use diesel::prelude::*;
+use diesel::replace_into;
+use failure::Error;
+
+use rustic_core::library::{ItemRevision, LibraryItemKind};
+
+use crate::entities::revision::*;
+use crate::repositories::Connection;
+
+/// Change tracking of library items
+#[derive(Clone)]
+pub struct RevisionRepository {
+    connection: Connection,
+}
+
+impl RevisionRepository {
+    pub fn new(connection: Connection) -> Self {
+        RevisionRepository { connection }
+    }
+
+    pub fn query(
+        &self,
+        item_kind: LibraryItemKind,
+        item_uri: &str,
+    ) -> Result<Option<ItemRevision>, Error> {
+        use crate::schema::revisions::dsl::*;
+
+        let connection = self.connection.lock();
+
+        revisions
+            .find((kind_to_int(item_kind), item_uri))
+            .first::<RevisionEntity>(&*connection)
+            .optional()?
+            .map(RevisionEntity::into_revision)
+            .transpose()
+    }
+
+    /// All revisions which changed after the given revision
+    pub fn query_since(&self, since: u64) -> Result<Vec<ItemRevision>, Error> {
+        use crate::schema::revisions::dsl::*;
+
+        let connection = self.connection.lock();
+
+        revisions
+            .filter(revision.gt(since as i64))
+            .load::<RevisionEntity>(&*connection)?
+            .into_iter()
+            .map(RevisionEntity::into_revision)
+            .collect()
+    }
+
+    /// The highest revision which has been stored
+    pub fn latest(&self) -> Result<u64, Error> {
+        use crate::schema::revisions::dsl::*;
+        use diesel::dsl::max;
+
+        let connection = self.connection.lock();
+
+        let latest = revisions
+            .select(max(revision))
+            .first::<Option<i64>>(&*connection)?;
+
+        Ok(latest.unwrap_or_default() as u64)
+    }
+
+    pub fn save(&self, item_revision: &ItemRevision) -> Result<(), Error> {
+        use crate::schema::revisions::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let entity = RevisionEntity::from_revision(item_revision)?;
+
+        replace_into(revisions)
+            .values(&entity)
+            .execute(&*connection)?;
+
+        Ok(())
+    }
+}
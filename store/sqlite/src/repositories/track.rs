@@ -1,22 +1,20 @@
-use std::sync::{Arc, Mutex};
-
+use diesel::insert_into;
 use diesel::prelude::*;
-use diesel::{insert_into, SqliteConnection};
 use failure::Error;
 
 use rustic_core::library::LibraryItemIdentifier;
 use rustic_core::{MultiQuery, SingleQuery, Track};
 
 use crate::entities::track::*;
-use crate::repositories::Repository;
+use crate::repositories::{Connection, Repository};
 
 #[derive(Clone)]
 pub struct TrackRepository {
-    connection: Arc<Mutex<SqliteConnection>>,
+    connection: Connection,
 }
 
 impl TrackRepository {
-    pub fn new(connection: Arc<Mutex<SqliteConnection>>) -> Self {
+    pub fn new(connection: Connection) -> Self {
         TrackRepository { connection }
     }
 }
@@ -25,7 +23,7 @@ impl Repository<Track> for TrackRepository {
     fn query(&self, query: SingleQuery) -> Result<Option<Track>, Error> {
         use schema::tracks::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let track = match query.identifier {
             LibraryItemIdentifier::Id(track_id) => tracks
@@ -52,7 +50,7 @@ impl Repository<Track> for TrackRepository {
     fn query_all(&self, query: MultiQuery) -> Result<Vec<Track>, Error> {
         use schema::tracks::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let track_list = tracks.load::<TrackEntity>(&*connection)?;
         let meta = TrackMeta::belonging_to(&track_list)
@@ -68,10 +66,31 @@ impl Repository<Track> for TrackRepository {
         Ok(track_list)
     }
 
+    fn query_uris(&self, uris: &[String]) -> Result<Vec<Track>, Error> {
+        use crate::schema::tracks::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let track_list = tracks
+            .filter(uri.eq_any(uris))
+            .load::<TrackEntity>(&*connection)?;
+        let meta = TrackMeta::belonging_to(&track_list)
+            .load::<TrackMeta>(&*connection)?
+            .grouped_by(&track_list);
+
+        let track_list = track_list
+            .into_iter()
+            .zip(meta)
+            .map(|(entity, meta)| entity.into_track(&meta))
+            .collect();
+
+        Ok(track_list)
+    }
+
     fn insert(&self, track: &mut Track) -> Result<(), Error> {
         use crate::schema::tracks::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let entity: TrackInsert = track.clone().into();
 
@@ -85,7 +104,7 @@ impl Repository<Track> for TrackRepository {
     fn insert_all(&self, models: &mut Vec<Track>) -> Result<(), Error> {
         use crate::schema::tracks::dsl::*;
 
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock();
 
         let entities = models
             .iter()
@@ -103,10 +122,23 @@ impl Repository<Track> for TrackRepository {
     }
 
     fn update(&self, model: &mut Track) -> Result<(), Error> {
-        unimplemented!()
+        use crate::schema::tracks::dsl::*;
+
+        let connection = self.connection.lock();
+
+        let entity: TrackInsert = model.clone().into();
+
+        diesel::update(tracks.filter(uri.eq(&model.uri)))
+            .set(&entity)
+            .execute(&*connection)?;
+
+        Ok(())
     }
 
     fn update_all(&self, models: &mut Vec<Track>) -> Result<(), Error> {
-        unimplemented!()
+        for model in models.iter_mut() {
+            self.update(model)?;
+        }
+        Ok(())
     }
 }
@@ -5,7 +5,7 @@ table! {
         artist_id -> Nullable<Integer>,
         image_url -> Nullable<Text>,
         uri -> Text,
-        provider -> Integer,
+        provider -> Text,
     }
 }
 
@@ -26,7 +26,7 @@ table! {
         name -> Text,
         image_url -> Nullable<Text>,
         uri -> Text,
-        provider -> Integer,
+        provider -> Text,
     }
 }
 
@@ -53,7 +53,30 @@ table! {
         id -> Integer,
         title -> Text,
         uri -> Text,
-        provider -> Integer,
+        provider -> Text,
+    }
+}
+
+table! {
+    plays (id) {
+        id -> Integer,
+        track_uri -> Text,
+        player -> Text,
+        started_at -> BigInt,
+        listened -> BigInt,
+        completed -> Bool,
+        track -> Text,
+    }
+}
+
+table! {
+    revisions (kind, uri) {
+        kind -> Integer,
+        uri -> Text,
+        created -> BigInt,
+        revision -> BigInt,
+        removed -> Bool,
+        fields -> Text,
     }
 }
 
@@ -66,7 +89,8 @@ table! {
         uri -> Text,
         image_url -> Nullable<Text>,
         duration -> Nullable<Integer>,
-        provider -> Integer,
+        provider -> Text,
+        rating -> Integer,
     }
 }
 
@@ -97,6 +121,8 @@ allow_tables_to_appear_in_same_query!(
     artists_meta,
     playlist_tracks,
     playlists,
+    plays,
+    revisions,
     tracks,
     tracks_meta,
 );